@@ -0,0 +1,183 @@
+use std::{fmt, time::Duration};
+
+/// Provider 层的类型化错误，调用方据此决定重试、重新输入 Key 或直接报错。
+/// 实现了 `std::error::Error`，可通过 `?` 直接转换为 `anyhow::Error`。
+#[derive(Debug)]
+pub(crate) enum ProviderError {
+    /// 401/403，或本地未配置 API Key
+    Unauthorized(String),
+    /// 429，`retry_after` 取自 `Retry-After` 响应头
+    RateLimited {
+        retry_after: Option<Duration>,
+        message: String,
+    },
+    /// 连接失败、读流中断、5xx 等可重试的传输/服务端错误
+    Network(String),
+    /// 请求超时（含 408/504）
+    Timeout(String),
+    /// 响应无法解析、内容为空或其它非预期状态码
+    BadResponse(String),
+    /// 请求在完成前被放弃
+    Cancelled,
+    /// 200 但内容被 provider 过滤/拒答；重试大概率得到同样结果
+    Refused { reason: String },
+}
+
+impl ProviderError {
+    /// 按 HTTP 状态码归类错误，消息沿用 `API error <status>: <body>` 格式。
+    pub(crate) fn from_status(
+        status: reqwest::StatusCode,
+        retry_after: Option<Duration>,
+        body: &str,
+    ) -> Self {
+        let message = format!("API error {status}: {body}");
+        match status.as_u16() {
+            401 | 403 => Self::Unauthorized(message),
+            429 => Self::RateLimited {
+                retry_after,
+                message,
+            },
+            408 | 504 => Self::Timeout(message),
+            500..=599 => Self::Network(message),
+            _ => Self::BadResponse(message),
+        }
+    }
+
+    /// 读取非 2xx 响应的状态码、`Retry-After` 与 body 并归类。
+    pub(crate) async fn from_response(resp: reqwest::Response) -> Self {
+        let status = resp.status();
+        let retry_after = resp
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = resp.text().await.unwrap_or_default();
+        Self::from_status(status, retry_after, &body)
+    }
+
+    /// 归类 reqwest 传输错误，`context` 描述失败的阶段。
+    pub(crate) fn transport(context: &str, err: reqwest::Error) -> Self {
+        let message = format!("{context}: {err}");
+        if err.is_timeout() {
+            Self::Timeout(message)
+        } else if err.is_decode() {
+            Self::BadResponse(message)
+        } else {
+            Self::Network(message)
+        }
+    }
+
+    /// 对应 provider 的 API Key 环境变量未设置。
+    pub(crate) fn missing_key(key_name: &str) -> Self {
+        Self::Unauthorized(format!("{key_name} env var not set"))
+    }
+
+    /// RateLimited / Network / Timeout 值得自动重试。
+    pub(crate) fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited { .. } | Self::Network(_) | Self::Timeout(_)
+        )
+    }
+
+    pub(crate) fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unauthorized(msg) => write!(f, "unauthorized: {msg}"),
+            Self::RateLimited {
+                retry_after: Some(after),
+                message,
+            } => write!(
+                f,
+                "rate limited (retry after {}s): {message}",
+                after.as_secs()
+            ),
+            Self::RateLimited { message, .. } => write!(f, "rate limited: {message}"),
+            Self::Network(msg) | Self::Timeout(msg) | Self::BadResponse(msg) => f.write_str(msg),
+            Self::Cancelled => f.write_str("request cancelled"),
+            Self::Refused { reason } => write!(f, "the model declined: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+#[cfg(test)]
+mod tests {
+    use super::ProviderError;
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    fn status(code: u16) -> ProviderError {
+        ProviderError::from_status(StatusCode::from_u16(code).unwrap(), None, "boom")
+    }
+
+    #[test]
+    fn maps_http_statuses_to_variants() {
+        assert!(matches!(status(401), ProviderError::Unauthorized(_)));
+        assert!(matches!(status(403), ProviderError::Unauthorized(_)));
+        assert!(matches!(status(408), ProviderError::Timeout(_)));
+        assert!(matches!(status(504), ProviderError::Timeout(_)));
+        assert!(matches!(status(502), ProviderError::Network(_)));
+        assert!(matches!(status(400), ProviderError::BadResponse(_)));
+        assert_eq!(
+            status(500).to_string(),
+            "API error 500 Internal Server Error: boom"
+        );
+
+        let limited = ProviderError::from_status(
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(7)),
+            "slow down",
+        );
+        assert!(limited.is_retryable());
+        assert_eq!(limited.retry_after(), Some(Duration::from_secs(7)));
+        assert!(!status(401).is_retryable());
+        assert!(!status(400).is_retryable());
+    }
+
+    #[tokio::test]
+    async fn maps_transport_errors_to_variants() {
+        // 只接受连接、从不响应的本地服务 → 客户端超时
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let err = client
+            .get(format!("http://{addr}"))
+            .send()
+            .await
+            .unwrap_err();
+        let err = ProviderError::transport("HTTP request failed", err);
+        assert!(matches!(err, ProviderError::Timeout(_)), "{err}");
+
+        // 端口已关闭 → 连接失败
+        drop(listener);
+        let err = client
+            .get(format!("http://{addr}"))
+            .send()
+            .await
+            .unwrap_err();
+        let err = ProviderError::transport("HTTP request failed", err);
+        assert!(matches!(err, ProviderError::Network(_)), "{err}");
+        assert!(err.to_string().starts_with("HTTP request failed: "));
+
+        let err: anyhow::Error = ProviderError::missing_key("KIMI_API_KEY").into();
+        assert!(matches!(
+            err.downcast_ref::<ProviderError>(),
+            Some(ProviderError::Unauthorized(_))
+        ));
+    }
+}
@@ -1,7 +1,9 @@
-use anyhow::{Context, Result, anyhow};
 use serde::{Deserialize, Serialize};
 
-use crate::agent::provider::{LlmProvider, Message, Role, Usage};
+use crate::agent::provider::{
+    ApiContent, LlmProvider, Message, ProviderError, RateLimits, Role, Usage, check_refusal,
+    model_supports_images,
+};
 
 #[derive(Clone, Copy)]
 pub(crate) struct GlmProvider;
@@ -13,7 +15,7 @@ const DEFAULT_BASE_URL: &str = "https://open.bigmodel.cn/api/coding/paas/v4";
 #[derive(Serialize)]
 struct ApiMessage {
     role: &'static str,
-    content: String,
+    content: ApiContent,
 }
 
 #[derive(Serialize)]
@@ -43,6 +45,8 @@ struct ApiResponse {
 #[derive(Deserialize)]
 struct ApiChoice {
     message: ApiChoiceMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -70,6 +74,8 @@ impl UsageParam {
             prompt_tokens: self.prompt_tokens.unwrap_or(0),
             completion_tokens: self.completion_tokens.unwrap_or(0),
             total_tokens: self.total_tokens.unwrap_or(0),
+            rate_limit: None,
+            finish_reason: None,
         }
     }
 }
@@ -77,6 +83,8 @@ impl UsageParam {
 #[derive(Deserialize)]
 struct StreamChoice {
     delta: StreamDelta,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -97,7 +105,7 @@ impl LlmProvider for GlmProvider {
         show_thinking: bool,
         on_delta: F,
         on_thinking_delta: G,
-    ) -> Result<(String, Usage)>
+    ) -> Result<(String, Usage), ProviderError>
     where
         F: FnMut(&str),
         G: FnMut(&str),
@@ -130,7 +138,7 @@ async fn chat_stream_with_impl<F, G>(
     show_thinking: bool,
     mut on_delta: F,
     mut on_thinking_delta: G,
-) -> Result<(String, Usage)>
+) -> Result<(String, Usage), ProviderError>
 where
     F: FnMut(&str),
     G: FnMut(&str),
@@ -143,12 +151,10 @@ where
         .json(&body)
         .send()
         .await
-        .context("HTTP request failed")?;
+        .map_err(|e| ProviderError::transport("HTTP request failed", e))?;
 
     if !resp.status().is_success() {
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-        return Err(anyhow!("API error {status}: {text}"));
+        return Err(ProviderError::from_response(resp).await);
     }
 
     let content_type = resp
@@ -162,11 +168,16 @@ where
         return parse_non_stream_response(resp).await;
     }
 
+    let rate_limit = RateLimits::from_headers(resp.headers());
     let mut merged = String::new();
     let mut pending = String::new();
     let mut final_usage = Usage::default();
 
-    while let Some(chunk) = resp.chunk().await.context("failed reading stream chunk")? {
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| ProviderError::transport("failed reading stream chunk", e))?
+    {
         pending.push_str(&String::from_utf8_lossy(&chunk));
         drain_sse_frames(
             &mut pending,
@@ -184,9 +195,13 @@ where
         &mut on_thinking_delta,
     );
 
+    check_refusal(final_usage.finish_reason.as_deref(), &merged)?;
     if merged.is_empty() {
-        return Err(anyhow!("API returned empty content"));
+        return Err(ProviderError::BadResponse(
+            "API returned empty content".to_string(),
+        ));
     }
+    final_usage.rate_limit = rate_limit;
     Ok((merged, final_usage))
 }
 
@@ -195,8 +210,9 @@ fn build_request(
     model: &str,
     stream: bool,
     show_thinking: bool,
-) -> Result<(String, String, ApiRequest)> {
-    let api_key = std::env::var("BIGMODEL_API_KEY").context("BIGMODEL_API_KEY env var not set")?;
+) -> Result<(String, String, ApiRequest), ProviderError> {
+    let api_key = std::env::var("BIGMODEL_API_KEY")
+        .map_err(|_| ProviderError::missing_key("BIGMODEL_API_KEY"))?;
     let model = normalize_glm_model(model);
 
     let api_messages: Vec<ApiMessage> = messages
@@ -207,7 +223,7 @@ fn build_request(
                 Role::User => "user",
                 Role::Assistant => "assistant",
             },
-            content: m.content.clone(),
+            content: m.api_content(model_supports_images(&model)),
         })
         .collect();
 
@@ -234,23 +250,31 @@ fn normalize_glm_model(model: &str) -> String {
     }
 }
 
-async fn parse_non_stream_response(resp: reqwest::Response) -> Result<(String, Usage)> {
+async fn parse_non_stream_response(
+    resp: reqwest::Response,
+) -> Result<(String, Usage), ProviderError> {
     if !resp.status().is_success() {
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-        return Err(anyhow!("API error {status}: {text}"));
+        return Err(ProviderError::from_response(resp).await);
     }
-    let parsed: ApiResponse = resp.json().await.context("failed to parse API response")?;
-    let text = parsed
+    let rate_limit = RateLimits::from_headers(resp.headers());
+    let parsed: ApiResponse = resp
+        .json()
+        .await
+        .map_err(|e| ProviderError::transport("failed to parse API response", e))?;
+    let (text, finish_reason) = parsed
         .choices
         .into_iter()
         .next()
-        .and_then(|c| c.message.content)
+        .map(|c| (c.message.content.unwrap_or_default(), c.finish_reason))
         .unwrap_or_default();
+    check_refusal(finish_reason.as_deref(), &text)?;
     if text.is_empty() {
-        return Err(anyhow!("API returned empty content"));
+        return Err(ProviderError::BadResponse(
+            "API returned empty content".to_string(),
+        ));
     }
-    let usage = parsed.usage.map(|u| u.to_usage()).unwrap_or_default();
+    let mut usage = parsed.usage.map(|u| u.to_usage()).unwrap_or_default();
+    usage.rate_limit = rate_limit;
     Ok((text, usage))
 }
 
@@ -303,11 +327,19 @@ fn handle_sse_frame<F, G>(
             continue;
         };
         if let Some(usage) = event.usage {
-            *final_usage = usage.to_usage();
+            // finish_reason 可能先于 usage 到达，覆盖时保留
+            let finish_reason = final_usage.finish_reason.take();
+            *final_usage = Usage {
+                finish_reason,
+                ..usage.to_usage()
+            };
         }
         let Some(choice) = event.choices.into_iter().next() else {
             continue;
         };
+        if let Some(reason) = choice.finish_reason {
+            final_usage.finish_reason = Some(reason);
+        }
         if let Some(text) = choice.delta.content.filter(|t| !t.is_empty()) {
             merged.push_str(&text);
             on_delta(&text);
@@ -372,6 +404,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sensitive_stream_maps_to_refused() {
+        // 200 流式响应：usage 晚于 finish_reason 到达，正文是固定拒答文案
+        let frame = concat!(
+            r#"data: {"choices":[{"delta":{"content":"系统检测到输入或生成内容可能包含不安全或敏感内容"},"finish_reason":"sensitive"}]}"#,
+            "\n",
+            r#"data: {"choices":[],"usage":{"prompt_tokens":5,"completion_tokens":1,"total_tokens":6}}"#,
+        );
+        let mut merged = String::new();
+        let mut usage = Usage::default();
+        handle_sse_frame(frame, &mut merged, &mut usage, &mut |_| {}, &mut |_| {});
+        assert_eq!(usage.total_tokens, 6);
+        assert_eq!(usage.finish_reason.as_deref(), Some("sensitive"));
+
+        let err = check_refusal(usage.finish_reason.as_deref(), &merged).unwrap_err();
+        assert!(matches!(err, ProviderError::Refused { .. }), "{err}");
+        assert!(!err.is_retryable());
+        assert!(err.to_string().starts_with("the model declined: "), "{err}");
+    }
+
     #[test]
     fn glm_model_aliases_normalize_to_official_names() {
         assert_eq!(normalize_glm_model("glm-5.1"), "GLM-5.1");
@@ -1,7 +1,9 @@
-use anyhow::{Context, Result, anyhow};
 use serde::{Deserialize, Serialize};
 
-use crate::agent::provider::{LlmProvider, Message, Role, Usage};
+use crate::agent::provider::{
+    ApiContent, LlmProvider, Message, ProviderError, RateLimits, Role, Usage, check_refusal,
+    model_supports_images,
+};
 
 #[derive(Clone, Copy)]
 pub(crate) struct KimiProvider;
@@ -11,7 +13,7 @@ pub(crate) struct KimiProvider;
 #[derive(Serialize)]
 struct ApiMessage {
     role: &'static str,
-    content: String,
+    content: ApiContent,
 }
 
 #[derive(Serialize)]
@@ -48,6 +50,8 @@ struct ApiResponse {
 #[derive(Deserialize)]
 struct ApiChoice {
     message: ApiChoiceMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -70,6 +74,8 @@ struct StreamEvent {
 #[derive(Deserialize)]
 struct StreamChoice {
     delta: StreamDelta,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -92,6 +98,8 @@ impl UsageParam {
             prompt_tokens: self.prompt_tokens.unwrap_or(0),
             completion_tokens: self.completion_tokens.unwrap_or(0),
             total_tokens: self.total_tokens.unwrap_or(0),
+            rate_limit: None,
+            finish_reason: None,
         }
     }
 }
@@ -107,7 +115,7 @@ impl LlmProvider for KimiProvider {
         show_thinking: bool,
         mut on_delta: F,
         mut on_thinking_delta: G,
-    ) -> Result<(String, Usage)>
+    ) -> Result<(String, Usage), ProviderError>
     where
         F: FnMut(&str),
         G: FnMut(&str),
@@ -127,12 +135,10 @@ impl LlmProvider for KimiProvider {
             .json(&body)
             .send()
             .await
-            .context("HTTP request failed")?;
+            .map_err(|e| ProviderError::transport("HTTP request failed", e))?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("API error {status}: {text}"));
+            return Err(ProviderError::from_response(resp).await);
         }
 
         let content_type = resp
@@ -146,11 +152,16 @@ impl LlmProvider for KimiProvider {
             return parse_non_stream_response(resp).await;
         }
 
+        let rate_limit = RateLimits::from_headers(resp.headers());
         let mut merged = String::new();
         let mut pending = String::new();
         let mut final_usage = Usage::default();
 
-        while let Some(chunk) = resp.chunk().await.context("failed reading stream chunk")? {
+        while let Some(chunk) = resp
+            .chunk()
+            .await
+            .map_err(|e| ProviderError::transport("failed reading stream chunk", e))?
+        {
             pending.push_str(&String::from_utf8_lossy(&chunk));
             drain_sse_frames(
                 &mut pending,
@@ -168,9 +179,13 @@ impl LlmProvider for KimiProvider {
             &mut on_thinking_delta,
         );
 
+        check_refusal(final_usage.finish_reason.as_deref(), &merged)?;
         if merged.is_empty() {
-            return Err(anyhow!("API returned empty content"));
+            return Err(ProviderError::BadResponse(
+                "API returned empty content".to_string(),
+            ));
         }
+        final_usage.rate_limit = rate_limit;
         Ok((merged, final_usage))
     }
 }
@@ -180,8 +195,9 @@ fn build_request(
     model: &str,
     stream: bool,
     show_thinking: bool,
-) -> Result<(String, String, ApiRequest)> {
-    let api_key = std::env::var("KIMI_API_KEY").context("KIMI_API_KEY env var not set")?;
+) -> Result<(String, String, ApiRequest), ProviderError> {
+    let api_key =
+        std::env::var("KIMI_API_KEY").map_err(|_| ProviderError::missing_key("KIMI_API_KEY"))?;
 
     // sk-kimi- 前缀 → Kimi for Coding 端点；其他 → Moonshot 通用端点
     let default_base = if api_key.starts_with("sk-kimi-") {
@@ -200,7 +216,7 @@ fn build_request(
                 Role::User => "user",
                 Role::Assistant => "assistant",
             },
-            content: m.content.clone(),
+            content: m.api_content(model_supports_images(model)),
         })
         .collect();
 
@@ -246,23 +262,31 @@ fn normalize_model_for_endpoint(model: &str, coding_endpoint: bool) -> String {
     }
 }
 
-async fn parse_non_stream_response(resp: reqwest::Response) -> Result<(String, Usage)> {
+async fn parse_non_stream_response(
+    resp: reqwest::Response,
+) -> Result<(String, Usage), ProviderError> {
     if !resp.status().is_success() {
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-        return Err(anyhow!("API error {status}: {text}"));
+        return Err(ProviderError::from_response(resp).await);
     }
-    let parsed: ApiResponse = resp.json().await.context("failed to parse API response")?;
-    let text = parsed
+    let rate_limit = RateLimits::from_headers(resp.headers());
+    let parsed: ApiResponse = resp
+        .json()
+        .await
+        .map_err(|e| ProviderError::transport("failed to parse API response", e))?;
+    let (text, finish_reason) = parsed
         .choices
         .into_iter()
         .next()
-        .and_then(|c| c.message.content)
+        .map(|c| (c.message.content.unwrap_or_default(), c.finish_reason))
         .unwrap_or_default();
+    check_refusal(finish_reason.as_deref(), &text)?;
     if text.is_empty() {
-        return Err(anyhow!("API returned empty content"));
+        return Err(ProviderError::BadResponse(
+            "API returned empty content".to_string(),
+        ));
     }
-    let usage = parsed.usage.map(|u| u.to_usage()).unwrap_or_default();
+    let mut usage = parsed.usage.map(|u| u.to_usage()).unwrap_or_default();
+    usage.rate_limit = rate_limit;
     Ok((text, usage))
 }
 
@@ -315,11 +339,19 @@ fn handle_sse_frame<F, G>(
             continue;
         };
         if let Some(usage) = event.usage {
-            *final_usage = usage.to_usage();
+            // finish_reason 可能先于 usage 到达，覆盖时保留
+            let finish_reason = final_usage.finish_reason.take();
+            *final_usage = Usage {
+                finish_reason,
+                ..usage.to_usage()
+            };
         }
         let Some(choice) = event.choices.into_iter().next() else {
             continue;
         };
+        if let Some(reason) = choice.finish_reason {
+            final_usage.finish_reason = Some(reason);
+        }
         if let Some(text) = choice.delta.content.filter(|t| !t.is_empty()) {
             merged.push_str(&text);
             on_delta(&text);
@@ -341,7 +373,7 @@ mod tests {
             model: "kimi-k2.5".to_string(),
             messages: vec![ApiMessage {
                 role: "user",
-                content: "Hi".to_string(),
+                content: ApiContent::Text("Hi".to_string()),
             }],
             max_completion_tokens: Some(32768),
             stream: Some(true),
@@ -1,7 +1,9 @@
-use anyhow::{Context, Result, anyhow};
 use serde::{Deserialize, Serialize};
 
-use crate::agent::provider::{LlmProvider, Message, Role, Usage};
+use crate::agent::provider::{
+    ApiContent, LlmProvider, Message, ProviderError, RateLimits, Role, Usage, check_refusal,
+    model_supports_images,
+};
 
 #[derive(Clone, Copy)]
 pub(crate) struct MimoProvider;
@@ -11,7 +13,7 @@ pub(crate) struct MimoProvider;
 #[derive(Serialize)]
 struct ApiMessage {
     role: &'static str,
-    content: String,
+    content: ApiContent,
 }
 
 #[derive(Serialize)]
@@ -49,6 +51,8 @@ struct ApiResponse {
 #[derive(Deserialize)]
 struct ApiChoice {
     message: ApiChoiceMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -68,6 +72,8 @@ struct StreamEvent {
 #[derive(Deserialize)]
 struct StreamChoice {
     delta: StreamDelta,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -90,6 +96,8 @@ impl UsageParam {
             prompt_tokens: self.prompt_tokens.unwrap_or(0),
             completion_tokens: self.completion_tokens.unwrap_or(0),
             total_tokens: self.total_tokens.unwrap_or(0),
+            rate_limit: None,
+            finish_reason: None,
         }
     }
 }
@@ -105,7 +113,7 @@ impl LlmProvider for MimoProvider {
         show_thinking: bool,
         mut on_delta: F,
         mut on_thinking_delta: G,
-    ) -> Result<(String, Usage)>
+    ) -> Result<(String, Usage), ProviderError>
     where
         F: FnMut(&str),
         G: FnMut(&str),
@@ -118,12 +126,10 @@ impl LlmProvider for MimoProvider {
             .json(&body)
             .send()
             .await
-            .context("HTTP request failed")?;
+            .map_err(|e| ProviderError::transport("HTTP request failed", e))?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("API error {status}: {text}"));
+            return Err(ProviderError::from_response(resp).await);
         }
 
         let content_type = resp
@@ -137,11 +143,16 @@ impl LlmProvider for MimoProvider {
             return parse_non_stream_response(resp).await;
         }
 
+        let rate_limit = RateLimits::from_headers(resp.headers());
         let mut merged = String::new();
         let mut pending = String::new();
         let mut final_usage = Usage::default();
 
-        while let Some(chunk) = resp.chunk().await.context("failed reading stream chunk")? {
+        while let Some(chunk) = resp
+            .chunk()
+            .await
+            .map_err(|e| ProviderError::transport("failed reading stream chunk", e))?
+        {
             pending.push_str(&String::from_utf8_lossy(&chunk));
             drain_sse_frames(
                 &mut pending,
@@ -159,9 +170,13 @@ impl LlmProvider for MimoProvider {
             &mut on_thinking_delta,
         );
 
+        check_refusal(final_usage.finish_reason.as_deref(), &merged)?;
         if merged.is_empty() {
-            return Err(anyhow!("API returned empty content"));
+            return Err(ProviderError::BadResponse(
+                "API returned empty content".to_string(),
+            ));
         }
+        final_usage.rate_limit = rate_limit;
         Ok((merged, final_usage))
     }
 }
@@ -171,11 +186,12 @@ fn build_request(
     model: &str,
     stream: bool,
     show_thinking: bool,
-) -> Result<(String, String, ApiRequest)> {
+) -> Result<(String, String, ApiRequest), ProviderError> {
     const BASE_URL: &str = "https://api.xiaomimimo.com/v1";
 
     let base_url = std::env::var("MIMO_BASE_URL").unwrap_or_else(|_| BASE_URL.to_string());
-    let api_key = std::env::var("MIMO_API_KEY").context("MIMO_API_KEY env var not set")?;
+    let api_key =
+        std::env::var("MIMO_API_KEY").map_err(|_| ProviderError::missing_key("MIMO_API_KEY"))?;
     let model = model.to_string();
 
     let api_messages: Vec<ApiMessage> = messages
@@ -186,7 +202,7 @@ fn build_request(
                 Role::User => "user",
                 Role::Assistant => "assistant",
             },
-            content: m.content.clone(),
+            content: m.api_content(model_supports_images(&model)),
         })
         .collect();
 
@@ -208,23 +224,31 @@ fn build_request(
     Ok((base_url, api_key, body))
 }
 
-async fn parse_non_stream_response(resp: reqwest::Response) -> Result<(String, Usage)> {
+async fn parse_non_stream_response(
+    resp: reqwest::Response,
+) -> Result<(String, Usage), ProviderError> {
     if !resp.status().is_success() {
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-        return Err(anyhow!("API error {status}: {text}"));
+        return Err(ProviderError::from_response(resp).await);
     }
-    let parsed: ApiResponse = resp.json().await.context("failed to parse API response")?;
-    let text = parsed
+    let rate_limit = RateLimits::from_headers(resp.headers());
+    let parsed: ApiResponse = resp
+        .json()
+        .await
+        .map_err(|e| ProviderError::transport("failed to parse API response", e))?;
+    let (text, finish_reason) = parsed
         .choices
         .into_iter()
         .next()
-        .and_then(|c| c.message.content)
+        .map(|c| (c.message.content.unwrap_or_default(), c.finish_reason))
         .unwrap_or_default();
+    check_refusal(finish_reason.as_deref(), &text)?;
     if text.is_empty() {
-        return Err(anyhow!("API returned empty content"));
+        return Err(ProviderError::BadResponse(
+            "API returned empty content".to_string(),
+        ));
     }
-    let usage = parsed.usage.map(|u| u.to_usage()).unwrap_or_default();
+    let mut usage = parsed.usage.map(|u| u.to_usage()).unwrap_or_default();
+    usage.rate_limit = rate_limit;
     Ok((text, usage))
 }
 
@@ -277,11 +301,19 @@ fn handle_sse_frame<F, G>(
             continue;
         };
         if let Some(usage) = event.usage {
-            *final_usage = usage.to_usage();
+            // finish_reason 可能先于 usage 到达，覆盖时保留
+            let finish_reason = final_usage.finish_reason.take();
+            *final_usage = Usage {
+                finish_reason,
+                ..usage.to_usage()
+            };
         }
         let Some(choice) = event.choices.into_iter().next() else {
             continue;
         };
+        if let Some(reason) = choice.finish_reason {
+            final_usage.finish_reason = Some(reason);
+        }
         if let Some(text) = choice.delta.content.filter(|t| !t.is_empty()) {
             merged.push_str(&text);
             on_delta(&text);
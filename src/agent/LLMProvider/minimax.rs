@@ -1,7 +1,9 @@
-use anyhow::{Context, Result, anyhow};
 use serde::{Deserialize, Serialize};
 
-use crate::agent::provider::{LlmProvider, Message, Role, Usage};
+use crate::agent::provider::{
+    ApiContent, LlmProvider, Message, ProviderError, RateLimits, Role, Usage, check_refusal,
+    model_supports_images,
+};
 
 #[derive(Clone, Copy)]
 pub(crate) struct MiniMaxProvider;
@@ -11,7 +13,7 @@ pub(crate) struct MiniMaxProvider;
 #[derive(Serialize)]
 struct ApiMessage {
     role: &'static str,
-    content: String,
+    content: ApiContent,
 }
 
 #[derive(Serialize)]
@@ -38,6 +40,8 @@ struct ApiResponse {
 #[derive(Deserialize)]
 struct ApiChoice {
     message: ApiChoiceMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -62,6 +66,8 @@ struct StreamEvent {
 #[derive(Deserialize)]
 struct StreamChoice {
     delta: StreamDelta,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -85,6 +91,8 @@ impl UsageParam {
             prompt_tokens: self.prompt_tokens.unwrap_or(0),
             completion_tokens: self.completion_tokens.unwrap_or(0),
             total_tokens: self.total_tokens.unwrap_or(0),
+            rate_limit: None,
+            finish_reason: None,
         }
     }
 }
@@ -100,7 +108,7 @@ impl LlmProvider for MiniMaxProvider {
         show_thinking: bool,
         mut on_delta: F,
         mut on_thinking_delta: G,
-    ) -> Result<(String, Usage)>
+    ) -> Result<(String, Usage), ProviderError>
     where
         F: FnMut(&str),
         G: FnMut(&str),
@@ -113,12 +121,10 @@ impl LlmProvider for MiniMaxProvider {
             .json(&body)
             .send()
             .await
-            .context("HTTP request failed")?;
+            .map_err(|e| ProviderError::transport("HTTP request failed", e))?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("API error {status}: {text}"));
+            return Err(ProviderError::from_response(resp).await);
         }
 
         let content_type = resp
@@ -132,13 +138,18 @@ impl LlmProvider for MiniMaxProvider {
             return parse_non_stream_response(resp).await;
         }
 
+        let rate_limit = RateLimits::from_headers(resp.headers());
         let mut merged = String::new();
         let mut pending = String::new();
         // reasoning_details 是累积字符串，记录已向上层发送的字节数，避免重复推送
         let mut reasoning_seen = 0usize;
         let mut final_usage = Usage::default();
 
-        while let Some(chunk) = resp.chunk().await.context("failed reading stream chunk")? {
+        while let Some(chunk) = resp
+            .chunk()
+            .await
+            .map_err(|e| ProviderError::transport("failed reading stream chunk", e))?
+        {
             pending.push_str(&String::from_utf8_lossy(&chunk));
             drain_sse_frames(
                 &mut pending,
@@ -158,9 +169,13 @@ impl LlmProvider for MiniMaxProvider {
             &mut on_thinking_delta,
         );
 
+        check_refusal(final_usage.finish_reason.as_deref(), &merged)?;
         if merged.is_empty() {
-            return Err(anyhow!("API returned empty content"));
+            return Err(ProviderError::BadResponse(
+                "API returned empty content".to_string(),
+            ));
         }
+        final_usage.rate_limit = rate_limit;
         Ok((merged, final_usage))
     }
 }
@@ -170,11 +185,12 @@ fn build_request(
     model: &str,
     stream: bool,
     show_thinking: bool,
-) -> Result<(String, String, ApiRequest)> {
+) -> Result<(String, String, ApiRequest), ProviderError> {
     const BASE_URL: &str = "https://api.minimaxi.com/v1";
 
     let base_url = std::env::var("MINIMAX_BASE_URL").unwrap_or_else(|_| BASE_URL.to_string());
-    let api_key = std::env::var("MINIMAX_API_KEY").context("MINIMAX_API_KEY env var not set")?;
+    let api_key = std::env::var("MINIMAX_API_KEY")
+        .map_err(|_| ProviderError::missing_key("MINIMAX_API_KEY"))?;
     let model = model.to_string();
 
     let api_messages: Vec<ApiMessage> = messages
@@ -185,7 +201,7 @@ fn build_request(
                 Role::User => "user",
                 Role::Assistant => "assistant",
             },
-            content: m.content.clone(),
+            content: m.api_content(model_supports_images(&model)),
         })
         .collect();
 
@@ -201,19 +217,24 @@ fn build_request(
     Ok((base_url, api_key, body))
 }
 
-async fn parse_non_stream_response(resp: reqwest::Response) -> Result<(String, Usage)> {
+async fn parse_non_stream_response(
+    resp: reqwest::Response,
+) -> Result<(String, Usage), ProviderError> {
     if !resp.status().is_success() {
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-        return Err(anyhow!("API error {status}: {text}"));
+        return Err(ProviderError::from_response(resp).await);
     }
-    let parsed: ApiResponse = resp.json().await.context("failed to parse API response")?;
-    let usage = parsed.usage.map(|u| u.to_usage()).unwrap_or_default();
+    let rate_limit = RateLimits::from_headers(resp.headers());
+    let parsed: ApiResponse = resp
+        .json()
+        .await
+        .map_err(|e| ProviderError::transport("failed to parse API response", e))?;
+    let mut usage = parsed.usage.map(|u| u.to_usage()).unwrap_or_default();
+    usage.rate_limit = rate_limit;
     let choice = parsed
         .choices
         .into_iter()
         .next()
-        .ok_or_else(|| anyhow!("API returned no choices"))?;
+        .ok_or_else(|| ProviderError::BadResponse("API returned no choices".to_string()))?;
     let text = choice
         .message
         .content
@@ -227,8 +248,11 @@ async fn parse_non_stream_response(resp: reqwest::Response) -> Result<(String, U
                 .map(|d| d.text)
         })
         .unwrap_or_default();
+    check_refusal(choice.finish_reason.as_deref(), &text)?;
     if text.is_empty() {
-        return Err(anyhow!("API returned empty content"));
+        return Err(ProviderError::BadResponse(
+            "API returned empty content".to_string(),
+        ));
     }
     Ok((text, usage))
 }
@@ -298,11 +322,19 @@ fn handle_sse_frame<F, G>(
             continue;
         };
         if let Some(usage) = event.usage {
-            *final_usage = usage.to_usage();
+            // finish_reason 可能先于 usage 到达，覆盖时保留
+            let finish_reason = final_usage.finish_reason.take();
+            *final_usage = Usage {
+                finish_reason,
+                ..usage.to_usage()
+            };
         }
         let Some(choice) = event.choices.into_iter().next() else {
             continue;
         };
+        if let Some(reason) = choice.finish_reason {
+            final_usage.finish_reason = Some(reason);
+        }
         if let Some(text) = choice.delta.content.filter(|t| !t.is_empty()) {
             merged.push_str(&text);
             on_delta(&text);
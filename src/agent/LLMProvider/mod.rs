@@ -1,15 +1,22 @@
 use anyhow::Result;
+use serde::Serialize;
 
+mod error;
 mod glm;
 mod kimi;
 mod mimo;
 mod minimax;
+mod rate_limit;
+mod refusal;
 
 // ── Debug context logger ──────────────────────────────────────────────────────
 /// 若 `GOLDBOT_DEBUG_LOG` 非空，则将每次 LLM 调用前的完整消息列表追加写入
 /// `~/.goldbot/llm_context.log`，方便排查 Sub-Agent / skill 上下文传递问题。
 fn maybe_write_debug_log(messages: &[Message]) {
-    if std::env::var("GOLDBOT_DEBUG_LOG").unwrap_or_default().is_empty() {
+    if std::env::var("GOLDBOT_DEBUG_LOG")
+        .unwrap_or_default()
+        .is_empty()
+    {
         return;
     }
     let log_path = crate::tools::mcp::goldbot_home_dir().join("llm_context.log");
@@ -36,6 +43,9 @@ fn maybe_write_debug_log(messages: &[Message]) {
         });
 }
 
+pub(crate) use self::error::ProviderError;
+pub(crate) use self::rate_limit::{RateLimits, pace_threshold};
+pub(crate) use self::refusal::check_refusal;
 use self::{
     glm::{GlmProvider, base_url_from_env},
     kimi::KimiProvider,
@@ -57,12 +67,18 @@ pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    /// 响应头里的限流额度（provider 返回时才有）
+    pub rate_limit: Option<RateLimits>,
+    /// 最后一个 choice 的 finish_reason（provider 返回时才有）
+    pub finish_reason: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Message {
     pub role: Role,
     pub content: String,
+    /// 随消息发送的图片，仅多模态模型会真正发送。
+    pub images: Vec<ImagePart>,
 }
 
 impl Message {
@@ -70,20 +86,115 @@ impl Message {
         Self {
             role: Role::System,
             content: content.into(),
+            images: Vec::new(),
         }
     }
     pub fn user(content: impl Into<String>) -> Self {
         Self {
             role: Role::User,
             content: content.into(),
+            images: Vec::new(),
         }
     }
     pub fn assistant(content: impl Into<String>) -> Self {
         Self {
             role: Role::Assistant,
             content: content.into(),
+            images: Vec::new(),
         }
     }
+
+    pub fn with_images(mut self, images: Vec<ImagePart>) -> Self {
+        self.images = images;
+        self
+    }
+
+    /// 转成 OpenAI 兼容的 `content` 字段：无图片时为纯字符串，有图片时为 content parts。
+    /// 模型不支持图片时丢弃图片，并在文本末尾注明。
+    pub(crate) fn api_content(&self, allow_images: bool) -> ApiContent {
+        if self.images.is_empty() {
+            return ApiContent::Text(self.content.clone());
+        }
+        if !allow_images {
+            return ApiContent::Text(format!(
+                "{}\n\n[{} image(s) omitted: the current model does not accept image input]",
+                self.content,
+                self.images.len()
+            ));
+        }
+        let mut parts = vec![ContentPart::Text {
+            text: self.content.clone(),
+        }];
+        parts.extend(self.images.iter().map(|img| ContentPart::ImageUrl {
+            image_url: ImageUrl {
+                url: img.url.clone(),
+            },
+        }));
+        ApiContent::Parts(parts)
+    }
+}
+
+// ── Image input ───────────────────────────────────────────────────────────────
+
+/// 支持图片输入的模型。
+const VISION_MODELS: &[&str] = &["glm-5v-turbo", "mimo-v2-omni"];
+
+pub(crate) fn model_supports_images(model: &str) -> bool {
+    VISION_MODELS
+        .iter()
+        .any(|m| m.eq_ignore_ascii_case(model.trim()))
+}
+
+/// 一张图片：`http(s)://` URL 或 `data:<mime>;base64,...`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImagePart {
+    pub url: String,
+}
+
+impl ImagePart {
+    /// 读取本地图片并编码为 base64 data URL。
+    pub(crate) fn from_file(path: &std::path::Path) -> Result<Self> {
+        use anyhow::Context;
+        use base64::Engine;
+
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        let mime = match ext.as_str() {
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "webp" => "image/webp",
+            "bmp" => "image/bmp",
+            _ => "image/png",
+        };
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("failed to read image `{}`", path.display()))?;
+        let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+        Ok(Self {
+            url: format!("data:{mime};base64,{data}"),
+        })
+    }
+}
+
+/// 请求体中的 `content`：纯文本或 content parts（OpenAI 兼容格式）。
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum ApiContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct ImageUrl {
+    url: String,
 }
 
 #[allow(async_fn_in_trait)]
@@ -96,7 +207,7 @@ pub(crate) trait LlmProvider {
         show_thinking: bool,
         on_delta: F,
         on_thinking_delta: G,
-    ) -> Result<(String, Usage)>
+    ) -> Result<(String, Usage), ProviderError>
     where
         F: FnMut(&str),
         G: FnMut(&str);
@@ -148,22 +259,21 @@ const DEFAULT_KIMI_CONTEXT_WINDOW_TOKENS: u32 = 256_000;
 const DEFAULT_MIMO_CONTEXT_WINDOW_TOKENS: u32 = 256_000;
 const DEFAULT_MINIMAX_CONTEXT_WINDOW_TOKENS: u32 = 204_800;
 
-fn default_kimi_model() -> String {
-    let explicit_base = std::env::var("KIMI_BASE_URL").unwrap_or_default();
+fn default_kimi_model(var: &dyn Fn(&str) -> Option<String>) -> String {
+    let explicit_base = var("KIMI_BASE_URL").unwrap_or_default();
     if explicit_base.contains("api.kimi.com/coding") {
         return "kimi-for-coding".to_string();
     }
-    let key = std::env::var("KIMI_API_KEY").unwrap_or_default();
+    let key = var("KIMI_API_KEY").unwrap_or_default();
     if key.starts_with("sk-kimi-") {
         return "kimi-for-coding".to_string();
     }
     "kimi-k2.5".to_string()
 }
 
-fn default_glm_model() -> String {
-    std::env::var("BIGMODEL_MODEL")
-        .or_else(|_| std::env::var("BIGMODEL_CODING_MODEL"))
-        .ok()
+fn default_glm_model(var: &dyn Fn(&str) -> Option<String>) -> String {
+    var("BIGMODEL_MODEL")
+        .or_else(|| var("BIGMODEL_CODING_MODEL"))
         .and_then(|model| normalize_glm_model_name(&model))
         .unwrap_or_else(|| "glm-5".to_string())
 }
@@ -195,54 +305,56 @@ pub(crate) enum LlmBackend {
 }
 
 impl LlmBackend {
-    pub(crate) fn from_env() -> Self {
-        let provider = std::env::var("LLM_PROVIDER")
-            .unwrap_or_default()
-            .to_lowercase();
+    /// 叠加工作区覆盖的后端选择：`overrides`（`.goldbot/backend` 中的项）逐个覆盖进程环境
+    /// （全局 `.env`），只作用于返回值，不写回进程环境。
+    pub(crate) fn with_overrides(overrides: &[(String, String)]) -> Self {
+        Self::from_lookup(&|key| {
+            overrides
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+                .or_else(|| std::env::var(key).ok())
+        })
+    }
+
+    /// 按 `var` 读到的配置选择后端。
+    pub(crate) fn from_lookup(var: &dyn Fn(&str) -> Option<String>) -> Self {
+        let provider = var("LLM_PROVIDER").unwrap_or_default().to_lowercase();
 
         match provider.as_str() {
             "glm-coding" | "glm_coding" | "glmcoding" => {
-                let model = default_glm_model();
+                let model = default_glm_model(var);
                 LlmBackend::Glm(model)
             }
             "kimi" => {
-                let model = std::env::var("KIMI_MODEL").unwrap_or_else(|_| default_kimi_model());
+                let model = var("KIMI_MODEL").unwrap_or_else(|| default_kimi_model(var));
                 LlmBackend::Kimi(model)
             }
             "mimo" => {
-                let model =
-                    std::env::var("MIMO_MODEL").unwrap_or_else(|_| "mimo-v2-pro".to_string());
+                let model = var("MIMO_MODEL").unwrap_or_else(|| "mimo-v2-pro".to_string());
                 LlmBackend::Mimo(model)
             }
             "minimax" => {
-                let model =
-                    std::env::var("MINIMAX_MODEL").unwrap_or_else(|_| "MiniMax-M2.5".to_string());
+                let model = var("MINIMAX_MODEL").unwrap_or_else(|| "MiniMax-M2.5".to_string());
                 LlmBackend::MiniMax(model)
             }
             "glm" => {
-                let model = default_glm_model();
+                let model = default_glm_model(var);
                 LlmBackend::Glm(model)
             }
             _ => {
                 // 自动检测优先级：Kimi > MiniMax > Mimo > GLM
-                if std::env::var("KIMI_API_KEY").is_ok() {
-                    let model =
-                        std::env::var("KIMI_MODEL").unwrap_or_else(|_| default_kimi_model());
+                if var("KIMI_API_KEY").is_some() {
+                    let model = var("KIMI_MODEL").unwrap_or_else(|| default_kimi_model(var));
                     LlmBackend::Kimi(model)
-                } else if std::env::var("MINIMAX_API_KEY").is_ok()
-                    && std::env::var("BIGMODEL_API_KEY").is_err()
-                {
-                    let model = std::env::var("MINIMAX_MODEL")
-                        .unwrap_or_else(|_| "MiniMax-M2.5".to_string());
+                } else if var("MINIMAX_API_KEY").is_some() && var("BIGMODEL_API_KEY").is_none() {
+                    let model = var("MINIMAX_MODEL").unwrap_or_else(|| "MiniMax-M2.5".to_string());
                     LlmBackend::MiniMax(model)
-                } else if std::env::var("MIMO_API_KEY").is_ok()
-                    && std::env::var("BIGMODEL_API_KEY").is_err()
-                {
-                    let model =
-                        std::env::var("MIMO_MODEL").unwrap_or_else(|_| "mimo-v2-pro".to_string());
+                } else if var("MIMO_API_KEY").is_some() && var("BIGMODEL_API_KEY").is_none() {
+                    let model = var("MIMO_MODEL").unwrap_or_else(|| "mimo-v2-pro".to_string());
                     LlmBackend::Mimo(model)
                 } else {
-                    let model = default_glm_model();
+                    let model = default_glm_model(var);
                     LlmBackend::Glm(model)
                 }
             }
@@ -259,6 +371,11 @@ impl LlmBackend {
         }
     }
 
+    /// 当前模型是否接受图片输入。
+    pub(crate) fn supports_images(&self) -> bool {
+        model_supports_images(self.model_name())
+    }
+
     /// 当前选定的模型名。
     pub(crate) fn model_name(&self) -> &str {
         match self {
@@ -291,7 +408,7 @@ impl LlmBackend {
         show_thinking: bool,
         on_delta: F,
         on_thinking_delta: G,
-    ) -> Result<(String, Usage)>
+    ) -> Result<(String, Usage), ProviderError>
     where
         F: FnMut(&str),
         G: FnMut(&str),
@@ -397,7 +514,7 @@ fn env_u32(name: &str) -> Option<u32> {
 
 #[cfg(test)]
 mod tests {
-    use super::BACKEND_PRESETS;
+    use super::{BACKEND_PRESETS, ImagePart, LlmBackend, Message};
 
     #[test]
     fn glm_backend_presets_include_glm_5_1() {
@@ -453,4 +570,31 @@ mod tests {
         assert!(mimo_models.contains(&"mimo-v2-flash"));
         assert!(mimo_models.contains(&"mimo-v2-omni"));
     }
+
+    #[test]
+    fn image_attachment_serializes_as_content_parts_for_vision_models() {
+        assert!(LlmBackend::Glm("glm-5v-turbo".to_string()).supports_images());
+        assert!(!LlmBackend::Kimi("kimi-for-coding".to_string()).supports_images());
+
+        let msg = Message::user("这张截图里有什么报错？").with_images(vec![ImagePart {
+            url: "data:image/png;base64,iVBORw0KGgo=".to_string(),
+        }]);
+        let json = serde_json::to_value(msg.api_content(true)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                { "type": "text", "text": "这张截图里有什么报错？" },
+                {
+                    "type": "image_url",
+                    "image_url": { "url": "data:image/png;base64,iVBORw0KGgo=" }
+                }
+            ])
+        );
+
+        // 不支持图片的模型退化为纯文本并注明
+        let json = serde_json::to_value(msg.api_content(false)).unwrap();
+        assert!(json.as_str().unwrap().contains("1 image(s) omitted"));
+        let plain = serde_json::to_value(Message::user("hi").api_content(true)).unwrap();
+        assert_eq!(plain, serde_json::json!("hi"));
+    }
 }
@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+
+/// 剩余额度响应头（逗号分隔，按顺序与 `GOLDBOT_RATELIMIT_LIMIT_HEADERS` 一一对应）。
+const ENV_REMAINING_HEADERS: &str = "GOLDBOT_RATELIMIT_REMAINING_HEADERS";
+/// 额度上限响应头（逗号分隔）。
+const ENV_LIMIT_HEADERS: &str = "GOLDBOT_RATELIMIT_LIMIT_HEADERS";
+/// 剩余比例低于该值时在下一次调用前放慢节奏（0–1，默认 0.1）。
+const ENV_PACE_BELOW: &str = "GOLDBOT_RATELIMIT_PACE_BELOW";
+
+const DEFAULT_REMAINING_HEADERS: &str =
+    "x-ratelimit-remaining-requests,x-ratelimit-remaining-tokens";
+const DEFAULT_LIMIT_HEADERS: &str = "x-ratelimit-limit-requests,x-ratelimit-limit-tokens";
+const DEFAULT_PACE_BELOW: f64 = 0.1;
+/// 刚低于阈值时的等待时间；额度越接近耗尽，等待越接近 `MAX_PACE_DELAY`。
+const MIN_PACE_DELAY: Duration = Duration::from_secs(1);
+const MAX_PACE_DELAY: Duration = Duration::from_secs(8);
+
+/// 一组额度：剩余量与（若响应头给出）上限。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitWindow {
+    pub header: String,
+    pub remaining: u64,
+    pub limit: Option<u64>,
+}
+
+/// 最近一次成功响应里读到的限流额度。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimits {
+    pub windows: Vec<RateLimitWindow>,
+}
+
+impl RateLimits {
+    /// 按环境变量配置的响应头名读取额度；一个都没有时返回 None。
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        Self::parse(
+            headers,
+            &header_names(ENV_REMAINING_HEADERS, DEFAULT_REMAINING_HEADERS),
+            &header_names(ENV_LIMIT_HEADERS, DEFAULT_LIMIT_HEADERS),
+        )
+    }
+
+    fn parse(headers: &HeaderMap, remaining: &[String], limits: &[String]) -> Option<Self> {
+        let number = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        let windows: Vec<RateLimitWindow> = remaining
+            .iter()
+            .enumerate()
+            .filter_map(|(i, name)| {
+                Some(RateLimitWindow {
+                    header: name.clone(),
+                    remaining: number(name)?,
+                    limit: limits.get(i).and_then(|l| number(l)),
+                })
+            })
+            .collect();
+        (!windows.is_empty()).then_some(Self { windows })
+    }
+
+    /// 各组额度中最低的剩余比例；没有上限的组只在剩余为 0 时计为 0。
+    pub(crate) fn lowest_fraction(&self) -> Option<f64> {
+        self.windows
+            .iter()
+            .filter_map(|w| match w.limit {
+                Some(limit) if limit > 0 => Some(w.remaining as f64 / limit as f64),
+                _ => (w.remaining == 0).then_some(0.0),
+            })
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
+    /// 剩余比例低于 `threshold` 时，下一次调用前应等待的时间。
+    pub(crate) fn pace_delay(&self, threshold: f64) -> Option<Duration> {
+        let fraction = self.lowest_fraction()?;
+        if threshold <= 0.0 || fraction >= threshold {
+            return None;
+        }
+        let pressure = 1.0 - (fraction / threshold).clamp(0.0, 1.0);
+        Some(MIN_PACE_DELAY + (MAX_PACE_DELAY - MIN_PACE_DELAY).mul_f64(pressure))
+    }
+}
+
+fn header_names(env: &str, default: &str) -> Vec<String> {
+    std::env::var(env)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| default.to_string())
+        .split(',')
+        .map(|name| name.trim().to_ascii_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+pub(crate) fn pace_threshold() -> f64 {
+    std::env::var(ENV_PACE_BELOW)
+        .ok()
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|v| (0.0..=1.0).contains(v))
+        .unwrap_or(DEFAULT_PACE_BELOW)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_PACE_DELAY, MIN_PACE_DELAY, RateLimits};
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    fn names(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_headers_and_paces_when_quota_is_low() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-ratelimit-remaining-requests",
+            HeaderValue::from_static("45"),
+        );
+        headers.insert("x-ratelimit-limit-requests", HeaderValue::from_static("50"));
+        headers.insert(
+            "x-ratelimit-remaining-tokens",
+            HeaderValue::from_static("3000"),
+        );
+        headers.insert(
+            "x-ratelimit-limit-tokens",
+            HeaderValue::from_static("100000"),
+        );
+        let remaining = names(&[
+            "x-ratelimit-remaining-requests",
+            "x-ratelimit-remaining-tokens",
+        ]);
+        let limits = names(&["x-ratelimit-limit-requests", "x-ratelimit-limit-tokens"]);
+
+        let parsed = RateLimits::parse(&headers, &remaining, &limits).expect("limits");
+        assert_eq!(parsed.windows.len(), 2);
+        assert_eq!(parsed.windows[1].remaining, 3000);
+        assert_eq!(parsed.windows[1].limit, Some(100000));
+        // tokens 只剩 3%，低于 10% 的阈值
+        assert_eq!(parsed.lowest_fraction(), Some(0.03));
+        let delay = parsed.pace_delay(0.1).expect("should pace");
+        assert!(
+            delay > MIN_PACE_DELAY && delay < MAX_PACE_DELAY,
+            "{delay:?}"
+        );
+        assert_eq!(parsed.pace_delay(0.02), None);
+
+        // 额度充足时不放慢；没有上限头时仅在耗尽时放慢
+        headers.insert(
+            "x-ratelimit-remaining-tokens",
+            HeaderValue::from_static("90000"),
+        );
+        let parsed = RateLimits::parse(&headers, &remaining, &limits).unwrap();
+        assert_eq!(parsed.pace_delay(0.1), None);
+        let only_remaining = names(&["x-ratelimit-remaining-requests"]);
+        headers.insert(
+            "x-ratelimit-remaining-requests",
+            HeaderValue::from_static("0"),
+        );
+        let parsed = RateLimits::parse(&headers, &only_remaining, &[]).unwrap();
+        assert_eq!(parsed.pace_delay(0.1), Some(MAX_PACE_DELAY));
+
+        assert_eq!(
+            RateLimits::parse(&HeaderMap::new(), &remaining, &limits),
+            None
+        );
+    }
+}
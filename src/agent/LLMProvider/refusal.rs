@@ -0,0 +1,104 @@
+use super::ProviderError;
+
+/// 额外的拒答标记（逗号分隔）：与 finish_reason 相等或出现在短回复正文中即视为被过滤。
+const ENV_REFUSAL_MARKERS: &str = "GOLDBOT_REFUSAL_MARKERS";
+
+/// 表示内容被过滤的 finish_reason（OpenAI 兼容的 `content_filter`、GLM 的 `sensitive`）。
+const FILTER_FINISH_REASONS: &[&str] = &["content_filter", "sensitive"];
+/// 各 provider 以 200 返回的拒答正文特征。
+const DEFAULT_TEXT_MARKERS: &[&str] = &[
+    // GLM
+    "系统检测到输入或生成内容可能包含不安全或敏感内容",
+    // Kimi / Moonshot
+    "The request was rejected because it was considered high risk",
+    // MiniMax
+    "new_sensitive",
+];
+/// 只在短回复里找正文标记，避免正常回答恰好提到这些词时误判。
+const MAX_REFUSAL_TEXT_CHARS: usize = 400;
+
+/// 响应被内容过滤时返回 `ProviderError::Refused`，否则原样放行。
+pub(crate) fn check_refusal(finish_reason: Option<&str>, text: &str) -> Result<(), ProviderError> {
+    match refusal_reason(finish_reason, text, &extra_markers()) {
+        Some(reason) => Err(ProviderError::Refused { reason }),
+        None => Ok(()),
+    }
+}
+
+fn extra_markers() -> Vec<String> {
+    std::env::var(ENV_REFUSAL_MARKERS)
+        .unwrap_or_default()
+        .split(',')
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect()
+}
+
+fn refusal_reason(finish_reason: Option<&str>, text: &str, extra: &[String]) -> Option<String> {
+    let text = text.trim();
+    if let Some(finish) = finish_reason.map(str::trim).filter(|f| {
+        FILTER_FINISH_REASONS
+            .iter()
+            .copied()
+            .chain(extra.iter().map(String::as_str))
+            .any(|m| m.eq_ignore_ascii_case(f))
+    }) {
+        return Some(if text.is_empty() {
+            format!("content filtered (finish_reason={finish})")
+        } else {
+            format!("{} (finish_reason={finish})", clip(text))
+        });
+    }
+    if text.chars().count() > MAX_REFUSAL_TEXT_CHARS {
+        return None;
+    }
+    let lower = text.to_lowercase();
+    DEFAULT_TEXT_MARKERS
+        .iter()
+        .copied()
+        .chain(extra.iter().map(String::as_str))
+        .any(|m| lower.contains(&m.to_lowercase()))
+        .then(|| text.to_string())
+}
+
+fn clip(text: &str) -> String {
+    if text.chars().count() <= MAX_REFUSAL_TEXT_CHARS {
+        return text.to_string();
+    }
+    let head: String = text.chars().take(MAX_REFUSAL_TEXT_CHARS).collect();
+    format!("{head}…")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::refusal_reason;
+
+    #[test]
+    fn maps_filtered_responses_to_refusals() {
+        // GLM：200 + finish_reason=sensitive + 固定拒答文案
+        let glm = "系统检测到输入或生成内容可能包含不安全或敏感内容，请您避免输入易产生敏感内容的提示语，感谢您的配合。";
+        let reason = refusal_reason(Some("sensitive"), glm, &[]).expect("glm refusal");
+        assert!(reason.contains("finish_reason=sensitive"), "{reason}");
+        assert_eq!(
+            refusal_reason(Some("content_filter"), "", &[]).as_deref(),
+            Some("content filtered (finish_reason=content_filter)")
+        );
+        // 没有 finish_reason 时按正文标记识别
+        assert_eq!(refusal_reason(None, glm, &[]).as_deref(), Some(glm));
+        let kimi = "The request was rejected because it was considered high risk";
+        assert_eq!(
+            refusal_reason(Some("stop"), kimi, &[]).as_deref(),
+            Some(kimi)
+        );
+
+        // 正常回复、以及长回答里顺带提到标记的都不算
+        assert_eq!(refusal_reason(Some("stop"), "All good.", &[]), None);
+        let long = format!("{} new_sensitive", "x".repeat(500));
+        assert_eq!(refusal_reason(None, &long, &[]), None);
+
+        // 自定义标记同时匹配 finish_reason 与正文
+        let extra = vec!["blocked_by_policy".to_string()];
+        assert!(refusal_reason(Some("BLOCKED_BY_POLICY"), "", &extra).is_some());
+        assert!(refusal_reason(None, "error: blocked_by_policy", &extra).is_some());
+    }
+}
@@ -1,6 +1,6 @@
 //! DAG Scheduler for SubAgent execution.
 
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
@@ -37,6 +37,10 @@ pub struct DagConfig {
     /// 每个元素是一个 skill 的全文（就是 skill_tool_result 的输出）。
     /// Sub-Agent 启动时会将它们插入到初始上下文中，避免重新手动加载。
     pub preloaded_skill_contexts: Vec<String>,
+    /// 观察模式：Shell 与写入类动作只回灌模拟结果，不实际执行。
+    pub observe: bool,
+    /// 父 Agent 禁用的内置工具，Sub-Agent 中同样拒绝执行。
+    pub disabled_tools: BTreeSet<String>,
     pub max_steps: usize,
     pub timeout: Duration,
     pub cancel_flag: Option<Arc<AtomicBool>>,
@@ -56,6 +60,8 @@ impl DagConfig {
             base_system_prompt,
             skills,
             preloaded_skill_contexts: Vec::new(),
+            observe: false,
+            disabled_tools: BTreeSet::new(),
             max_steps: DEFAULT_SUBAGENT_MAX_STEPS,
             timeout: Duration::from_secs(DEFAULT_SUBAGENT_TIMEOUT_SECS),
             cancel_flag: None,
@@ -282,12 +288,21 @@ async fn run_subagent_worker(
                     Ok((_text, actions)) => {
                         let mut found_final = false;
                         for action in actions {
+                            let rejected = rejected_tool_result(&config.disabled_tools, &action);
                             let tool_result = match action {
                                 crate::types::LlmAction::Final { summary } => {
                                     output = summary;
                                     found_final = true;
                                     break;
                                 }
+                                _ if rejected.is_some() => rejected.unwrap_or_default(),
+                                crate::types::LlmAction::Shell { .. }
+                                | crate::types::LlmAction::WriteFile { .. }
+                                | crate::types::LlmAction::UpdateFile { .. }
+                                    if config.observe =>
+                                {
+                                    crate::agent::executor::OBSERVE_CONTEXT.to_string()
+                                }
                                 crate::types::LlmAction::Shell { command } => {
                                     tokio::task::spawn_blocking(move || {
                                         execute_shell_command(command)
@@ -335,6 +350,13 @@ async fn run_subagent_worker(
                                     .await
                                     .unwrap_or_else(|_| "[Glob: task panicked]".to_string())
                                 }
+                                crate::types::LlmAction::DirectoryTree { path, depth, sizes } => {
+                                    tokio::task::spawn_blocking(move || {
+                                        execute_tree(&path, depth, sizes)
+                                    })
+                                    .await
+                                    .unwrap_or_else(|_| "[Tree: task panicked]".to_string())
+                                }
                                 crate::types::LlmAction::WebSearch { query } => {
                                     tokio::task::spawn_blocking(move || execute_web_search(&query))
                                         .await
@@ -417,6 +439,10 @@ fn execute_shell_command(command: String) -> String {
 }
 
 fn execute_read(path: &str, offset: Option<usize>, limit: Option<usize>) -> String {
+    let access = crate::tools::access::for_current_workspace();
+    if let Err(e) = access.check_read(std::path::Path::new(path)) {
+        return format!("[Read error: {e}]");
+    }
     match std::fs::read_to_string(path) {
         Ok(content) => {
             let lines: Vec<&str> = content.lines().collect();
@@ -441,6 +467,10 @@ fn execute_read(path: &str, offset: Option<usize>, limit: Option<usize>) -> Stri
 }
 
 fn execute_write(path: &str, content: &str) -> String {
+    let access = crate::tools::access::for_current_workspace();
+    if let Err(e) = access.check_write(std::path::Path::new(path)) {
+        return format!("[Write error: {e}]");
+    }
     if let Some(parent) = std::path::Path::new(path).parent() {
         let _ = std::fs::create_dir_all(parent);
     }
@@ -528,6 +558,13 @@ fn execute_glob(pattern: &str, path: &str) -> String {
     }
 }
 
+fn execute_tree(path: &str, depth: Option<usize>, sizes: bool) -> String {
+    match crate::tools::fs::directory_tree(path, depth, sizes) {
+        Ok(result) => result.output,
+        Err(e) => format!("[Tree error: {e}]"),
+    }
+}
+
 fn execute_update(path: &str, line_start: usize, line_end: usize, new_string: &str) -> String {
     let content = match std::fs::read_to_string(path) {
         Ok(c) => c,
@@ -725,6 +762,16 @@ pub async fn execute(graph: TaskGraph, config: DagConfig) -> Result<DagResult> {
     })
 }
 
+/// 父 Agent 禁用的工具在 Sub-Agent 中同样拒绝：返回回灌给模型的错误结果。
+fn rejected_tool_result(
+    disabled_tools: &BTreeSet<String>,
+    action: &crate::types::LlmAction,
+) -> Option<String> {
+    crate::agent::executor::builtin_tool_name(action)
+        .filter(|name| disabled_tools.contains(*name))
+        .map(crate::agent::executor::disabled_tool_message)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -774,4 +821,28 @@ mod tests {
         let merged = merge_outputs(&outputs, OutputMerge::First);
         assert_eq!(merged, "out a");
     }
+
+    #[test]
+    fn sub_agents_reject_tools_disabled_in_parent() {
+        use crate::types::LlmAction;
+
+        let disabled: BTreeSet<String> = ["shell".to_string(), "write".to_string()].into();
+        let shell = LlmAction::Shell {
+            command: "rm -rf build".to_string(),
+        };
+        let rejected = rejected_tool_result(&disabled, &shell).expect("shell is disabled");
+        assert!(rejected.contains("tool disabled: `shell`"));
+        let write = LlmAction::WriteFile {
+            path: "a.txt".to_string(),
+            content: "x".to_string(),
+        };
+        assert!(rejected_tool_result(&disabled, &write).is_some());
+        let read = LlmAction::ReadFile {
+            path: "a.txt".to_string(),
+            offset: None,
+            limit: None,
+        };
+        assert_eq!(rejected_tool_result(&disabled, &read), None);
+        assert_eq!(rejected_tool_result(&BTreeSet::new(), &shell), None);
+    }
 }
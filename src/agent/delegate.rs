@@ -0,0 +1,306 @@
+//! `@claude` / `@codex` task prefix: hand a single task to one of the external executors GE
+//! uses instead of the in-process LLM loop, and report its output as the task outcome.
+
+use std::path::Path;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use crossterm::style::Stylize;
+
+use crate::App;
+use crate::agent::executor::finish;
+use crate::agent::provider::Message;
+use crate::consensus::external::{
+    ExecutorRun, kill_running_executor, run_claude, run_codex_execute,
+};
+use crate::types::{AssistMode, Event};
+use crate::ui::format::emit_live_event;
+use crate::ui::i18n::{t, t_with};
+use crate::ui::screen::Screen;
+
+/// External executor selected by a task prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExternalExecutor {
+    Claude,
+    Codex,
+}
+
+impl ExternalExecutor {
+    /// Matches the executor names accepted after `@` (case-insensitive).
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "claude" => Some(Self::Claude),
+            "codex" => Some(Self::Codex),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Claude => "claude",
+            Self::Codex => "codex",
+        }
+    }
+
+    /// Runs `prompt` in `cwd` through the matching GE runner (blocking).
+    pub(crate) fn run(self, cwd: &Path, prompt: &str, cancel: &Arc<AtomicBool>) -> ExecutorRun {
+        match self {
+            Self::Claude => run_claude(cwd, prompt, cancel),
+            Self::Codex => run_codex_execute(cwd, prompt, cancel),
+        }
+    }
+}
+
+/// Splits `@codex do X` into the executor and the task text.  Returns `None` for ordinary input,
+/// unknown executor names, or a prefix with no task after it.
+pub(crate) fn parse_executor_prefix(input: &str) -> Option<(ExternalExecutor, &str)> {
+    let rest = input.trim_start().strip_prefix('@')?;
+    let (name, task) = rest.split_once(char::is_whitespace)?;
+    let executor = ExternalExecutor::from_name(name)?;
+    let task = task.trim();
+    (!task.is_empty()).then_some((executor, task))
+}
+
+/// External executors run with their own permission prompts bypassed, so delegation is refused
+/// whenever GoldBot itself would not run commands. Returns the reason shown to the user.
+pub(crate) fn delegation_blocked(app: &App) -> Option<&'static str> {
+    if app.observe {
+        Some("observe mode is on; nothing executes, so the task was not delegated")
+    } else if app.assist_mode == AssistMode::Plan {
+        Some("plan mode is on; switch modes before delegating a task")
+    } else if app.disabled_tools.contains("shell") {
+        Some("the shell tool is disabled; delegated executors run commands without confirmation")
+    } else {
+        None
+    }
+}
+
+/// Starts a delegated task. External executors run without GoldBot's command confirmations, so
+/// outside Yolo mode the user confirms the delegation first (`resolve_delegation`).
+pub(crate) fn start_external_task(
+    app: &mut App,
+    screen: &mut Screen,
+    executor: ExternalExecutor,
+    task: &str,
+    display: String,
+) {
+    if let Some(reason) = delegation_blocked(app) {
+        screen.emit(&[format!("  @{}: {reason}", executor.label())
+            .dark_yellow()
+            .to_string()]);
+        screen.refresh();
+        return;
+    }
+    if app.assist_mode != AssistMode::Yolo {
+        screen.emit(&[format!(
+            "  {}",
+            t_with("delegate.prompt", &[("name", executor.label())])
+        )]);
+        screen.question_labels = vec![
+            t("delegate.option_run").to_string(),
+            t("delegate.option_cancel").to_string(),
+        ];
+        screen.confirm_selected = Some(0);
+        screen.input_focused = false;
+        app.pending_delegation = Some((executor, task.to_string(), display));
+        screen.refresh();
+        return;
+    }
+    spawn_external_task(app, screen, executor, task, display);
+}
+
+/// Confirm-menu choice for a pending delegation: run it or drop it.
+pub(crate) fn resolve_delegation(app: &mut App, screen: &mut Screen, run: bool) {
+    let Some((executor, task, display)) = app.pending_delegation.take() else {
+        return;
+    };
+    screen.confirm_selected = None;
+    screen.question_labels.clear();
+    screen.input_focused = true;
+    if run {
+        spawn_external_task(app, screen, executor, &task, display);
+    } else {
+        screen.emit(&[format!("  {}", t("delegate.cancelled"))]);
+        screen.refresh();
+    }
+}
+
+/// The conversation records the task like any other, but the external executor runs on a worker
+/// thread and `poll_external_task` finishes the task with its output.
+fn spawn_external_task(
+    app: &mut App,
+    screen: &mut Screen,
+    executor: ExternalExecutor,
+    task: &str,
+    display: String,
+) {
+    crate::agent::executor::start_task(app, screen, display);
+    app.needs_agent_executor = false;
+    app.external_task_running = true;
+    app.external_cancel_flag.store(false, Ordering::SeqCst);
+
+    let ev = Event::ToolCall {
+        label: format!("Delegated to {}", executor.label()),
+        command: task.to_string(),
+        multiline: false,
+    };
+    emit_live_event(screen, &ev);
+    app.task_events.push(ev);
+    screen.status = format!("Waiting for {}... (esc to cancel)", executor.label())
+        .grey()
+        .to_string();
+    screen.refresh();
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let cwd = app.workspace.clone();
+    let prompt = task.to_string();
+    let cancel = Arc::clone(&app.external_cancel_flag);
+    std::thread::spawn(move || {
+        let _ = tx.send(executor.run(&cwd, &prompt, &cancel));
+    });
+    app.external_result_rx = Some(rx);
+}
+
+/// Finishes the delegated task once the external executor returns.
+pub(crate) fn poll_external_task(app: &mut App, screen: &mut Screen) {
+    let Some(rx) = app.external_result_rx.as_mut() else {
+        return;
+    };
+    let run = match rx.try_recv() {
+        Ok(run) => run,
+        Err(tokio::sync::oneshot::error::TryRecvError::Empty) => return,
+        Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+            app.external_result_rx = None;
+            app.external_task_running = false;
+            finish(
+                app,
+                screen,
+                "External executor worker disconnected.".to_string(),
+            );
+            return;
+        }
+    };
+    app.external_result_rx = None;
+    app.external_task_running = false;
+    if !app.running {
+        // Cancelled with Esc meanwhile: drop the late result.
+        return;
+    }
+
+    let output = run.output.trim().to_string();
+    let ev = Event::ToolResult {
+        exit_code: run.exit_code,
+        output: output.clone(),
+    };
+    emit_live_event(screen, &ev);
+    app.task_events.push(ev);
+    app.messages.push(Message::assistant(format!(
+        "[{} executed this task]\n{output}",
+        run.executor
+    )));
+
+    let summary = if run.ok() {
+        output
+    } else {
+        let code = run.error_code.as_deref().unwrap_or("failed");
+        format!(
+            "{} failed ({code}, exit={}).\n\n{output}",
+            run.executor, run.exit_code
+        )
+    };
+    finish(app, screen, summary);
+}
+
+/// Esc / shutdown: stop the external executor process group.
+pub(crate) fn cancel_external_task(app: &mut App) {
+    if !app.external_task_running {
+        return;
+    }
+    app.external_cancel_flag.store(true, Ordering::SeqCst);
+    kill_running_executor();
+    app.external_task_running = false;
+    app.external_result_rx = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExternalExecutor, parse_executor_prefix, resolve_delegation, start_external_task};
+    use crate::App;
+    use crate::types::AssistMode;
+    use crate::ui::screen::Screen;
+    use std::sync::{Arc, atomic::AtomicBool};
+
+    #[test]
+    fn executor_prefix_routes_to_matching_runner() {
+        assert_eq!(
+            parse_executor_prefix("@codex fix the flaky test"),
+            Some((ExternalExecutor::Codex, "fix the flaky test"))
+        );
+        assert_eq!(
+            parse_executor_prefix("  @Claude  refactor parser "),
+            Some((ExternalExecutor::Claude, "refactor parser"))
+        );
+        assert_eq!(parse_executor_prefix("@codex   "), None);
+        assert_eq!(parse_executor_prefix("@src/main.rs explain"), None);
+        assert_eq!(parse_executor_prefix("fix @codex things"), None);
+
+        // A pre-cancelled run never spawns the process but still reports which runner it hit.
+        let cancelled = Arc::new(AtomicBool::new(true));
+        let cwd = std::env::temp_dir();
+        for executor in [ExternalExecutor::Claude, ExternalExecutor::Codex] {
+            let run = executor.run(&cwd, "noop", &cancelled);
+            assert_eq!(run.executor, executor.label());
+            assert!(!run.ok());
+        }
+    }
+
+    #[test]
+    fn delegation_is_refused_when_goldbot_would_not_execute() {
+        let mut screen = Screen::new_headless().expect("headless screen");
+        let setups: [fn(&mut App); 3] = [
+            |app| app.observe = true,
+            |app| app.assist_mode = AssistMode::Plan,
+            |app| {
+                app.disabled_tools.insert("shell".to_string());
+            },
+        ];
+        for setup in setups {
+            let mut app = App::new();
+            setup(&mut app);
+            start_external_task(
+                &mut app,
+                &mut screen,
+                ExternalExecutor::Codex,
+                "fix the flaky test",
+                "@codex fix the flaky test".to_string(),
+            );
+            assert!(!app.external_task_running);
+            assert!(app.task_events.is_empty());
+        }
+    }
+
+    #[test]
+    fn delegation_waits_for_confirmation_outside_yolo() {
+        let mut screen = Screen::new_headless().expect("headless screen");
+        let mut app = App::new();
+        start_external_task(
+            &mut app,
+            &mut screen,
+            ExternalExecutor::Codex,
+            "fix the flaky test",
+            "@codex fix the flaky test".to_string(),
+        );
+        assert!(app.pending_delegation.is_some());
+        assert_eq!(screen.confirm_selected, Some(0));
+        assert!(!app.running && !app.external_task_running);
+        assert!(app.external_result_rx.is_none());
+
+        resolve_delegation(&mut app, &mut screen, false);
+        assert!(app.pending_delegation.is_none());
+        assert!(screen.confirm_selected.is_none());
+        assert!(!app.running && !app.external_task_running);
+        assert!(app.external_result_rx.is_none());
+    }
+}
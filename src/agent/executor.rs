@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::{
     Arc,
     atomic::{AtomicBool, Ordering},
@@ -6,20 +7,26 @@ use std::time::Duration;
 
 use crossterm::style::Stylize;
 use serde_json::Value;
+use unicode_width::UnicodeWidthChar;
 
 use crate::agent::dag::{DagConfig, build_dag_tree, execute as execute_dag};
-use crate::agent::plan::is_plan_echo;
-use crate::agent::provider::Message;
-use crate::agent::react::parse_llm_response;
+use crate::agent::plan::{is_plan_echo, parse_plan_steps};
+use crate::agent::provider::{Message, ProviderError, Usage, pace_threshold};
+use crate::agent::react::{extract_final_text, final_tags, final_was_deferred, parse_llm_response};
 use crate::memory::Session;
 use crate::memory::project::ProjectStore;
-use crate::tools::safety::{RiskLevel, assess_command};
+use crate::tools::safety::{RiskLevel, assess_command_severity, set_current_branch};
 use crate::tools::shell::{clear_running_shell_cancel_request, request_cancel_running_shell_commands};
 use crate::tools::skills::skill_tool_result;
-use crate::types::{AssistMode, Event, LlmAction, Mode};
+use crate::tools::git::current_git_branch;
+use crate::types::{
+    AssistMode, CompactConfirm, ConfirmTimeoutAction, Event, LlmAction, McpRollback, Mode,
+};
 use crate::ui::format::{
     collapsed_lines, emit_live_event, sanitize_final_summary_for_tui, shorten_text,
 };
+use crate::ui::i18n::{t, t_with};
+use crate::ui::input::KeyAction;
 use crate::ui::screen::Screen;
 use crate::{App, KEEP_RECENT_MESSAGES_AFTER_COMPACTION, MAX_COMPACTION_SUMMARY_ITEMS};
 
@@ -27,9 +34,46 @@ const MIN_RECENT_MESSAGES_AFTER_COMPACTION: usize = 6;
 const MIN_COMPACT_RESERVE_TOKENS: u32 = 8_192;
 const MAX_COMPACT_RESERVE_TOKENS: u32 = 32_768;
 const COMPLETION_RESERVE_MULTIPLIER: u32 = 3;
+/// compact 时保留被折叠区间内最近几条失败（exit≠0）的工具结果，避免压缩后重蹈已见过的错误。
+const MAX_COMPACTION_FAILED_RESULTS: usize = 3;
+/// 每条保留的失败输出的字节上限（保留结尾，错误信息通常在最后）。
+const COMPACTION_FAILED_RESULT_BYTES: usize = 800;
 
 pub(crate) const LLM_MAX_RETRIES: usize = 3;
 const LLM_RETRY_BASE_DELAY_MS: u64 = 500;
+/// 服务端 `Retry-After` 的上限，避免一次限流把任务挂起太久
+const LLM_MAX_RETRY_AFTER_SECS: u64 = 30;
+/// 限流额度偏低、主动放慢下一次调用时的状态栏文案
+const RATE_LIMIT_PACING_STATUS: &str = "⏳ approaching rate limit, pacing…";
+/// 没有命令执行时，每隔这么久重新查询一次当前 git 分支（捕获用户在别处切换分支）。
+const GIT_BRANCH_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+/// 流式原文只保留结尾这么多字节，实时预览从中提取。
+const LLM_STREAM_BUFFER_MAX_BYTES: usize = 16_384;
+/// 状态行思考预览的上限：字符数与显示宽度（CJK 字符占两列）都不超过这些值。
+const LLM_PREVIEW_MAX_CHARS: usize = 240;
+const LLM_PREVIEW_MAX_WIDTH: usize = 240;
+/// 连续多少次响应无法解析后放弃当前任务（`GOLDBOT_PARSE_RETRY_LIMIT` 可覆盖）。
+const DEFAULT_PARSE_RETRY_LIMIT: usize = 3;
+/// 响应无法解析时回灌给模型的纠正提示；`{error}` 替换为具体解析错误。
+/// 可通过 `GOLDBOT_PARSE_RETRY_PROMPT` 覆盖（支持 `\n` 转义）。
+const DEFAULT_PARSE_RETRY_PROMPT: &str = "Your last response could not be parsed ({error}). Use one of:\n\
+     <thought>…</thought><tool>shell</tool><command>…</command>\n\
+     <thought>…</thought><tool>mcp_…</tool><arguments>{}</arguments>\n\
+     <thought>…</thought><final>…</final>";
+/// 设为 0 时 `<create_mcp>` 写入配置后不再试启动服务端校验。
+const ENV_MCP_VERIFY_ON_CREATE: &str = "GOLDBOT_MCP_VERIFY_ON_CREATE";
+/// 估算 prompt 超过该 token 数时先确认再发送（默认关闭）。
+const ENV_PROMPT_WARN_TOKENS: &str = "GOLDBOT_PROMPT_WARN_TOKENS";
+/// 覆盖确认提示里的输入单价（美元 / 百万 token）。
+const ENV_PROMPT_PRICE_PER_MTOK: &str = "GOLDBOT_PROMPT_PRICE_PER_MTOK";
+/// 各后端输入单价的粗略估算（美元 / 百万 token），只用于大 prompt 确认提示。
+const INPUT_PRICE_PER_MTOK: &[(&str, f64)] =
+    &[("GLM", 0.6), ("Kimi", 0.6), ("Mimo", 0.1), ("MiniMax", 0.3)];
+/// 观察模式下代替工具输出展示给用户的说明。
+const OBSERVE_NOTE: &str = "(observe mode: not executed)";
+/// 观察模式下回灌给模型的工具结果，让模型按执行成功继续规划。
+pub(crate) const OBSERVE_CONTEXT: &str = "(observe mode: not executed) This call was simulated and \
+     nothing ran. Assume it succeeded with exit code 0 and no output, then continue.";
 
 #[derive(Debug, Clone, Copy)]
 struct ContextBudget {
@@ -55,29 +99,52 @@ pub(crate) fn start_task(app: &mut App, screen: &mut Screen, task: String) {
         screen.emit(&[String::new()]);
     }
     screen.reset_task_lines();
+    screen.thinking_text.clear();
+    screen.final_preview.clear();
 
     app.task = task.clone();
+    seal_task_snapshot(app, screen);
+    // git 仓库由 git 负责撤销，不做快照
+    app.task_snapshot = (!crate::tools::git::is_git_workspace(&app.workspace)).then(|| {
+        crate::tools::snapshot::TaskSnapshot::new(
+            &crate::tools::snapshot::snapshots_dir(),
+            &app.workspace,
+            &task,
+            crate::tools::snapshot::snapshot_max_bytes(),
+        )
+    });
 
     // 每次新任务重建 system prompt（使 AGENTS.md 变更立即生效）。
     app.rebuild_system_message();
     app.steps_taken = 0;
+    app.parse_failures = 0;
     app.running = true;
     app.llm_stream_preview.clear();
     app.llm_preview_shown.clear();
     app.llm_call_started_at = None;
     app.task_started_at = Some(std::time::Instant::now());
+    app.task_time_warned = false;
+    app.prompt_warn_accepted = false;
     app.last_task_elapsed = None;
     app.needs_agent_executor = true;
+    app.paused = false;
     app.interrupt_llm_loop_requested = false;
     app.interjection_mode = false;
     app.clear_message_queue(screen);
     app.pending_confirm = None;
+    app.pending_mcp_create = None;
+    app.pending_mcp_call = None;
+    app.pending_edit = None;
+    app.pending_prompt_warn = None;
+    app.pending_ask_secret = None;
+    screen.mask_input = false;
+    app.mcp_registry.clear_call_cache();
 
     app.pending_confirm_note = false;
     app.current_phase_summary = None;
     screen.confirm_selected = None;
     screen.input_focused = true;
-    app.task_events.clear();
+    crate::ui::transcript::archive_task_events(&mut app.transcript_ring, &mut app.task_events);
     app.final_summary = None;
     app.task_collapsed = false;
     app.todo_items.clear();
@@ -97,7 +164,9 @@ pub(crate) fn start_task(app: &mut App, screen: &mut Screen, task: String) {
             None => task.clone(),
         }
     };
-    app.messages.push(Message::user(user_content));
+    let images = std::mem::take(&mut app.pending_images);
+    app.messages
+        .push(Message::user(user_content).with_images(images));
     sync_context_budget(app, screen);
 
     // TUI 显示用 override（如命令展开时只显示占位符），否则显示完整 task
@@ -105,30 +174,85 @@ pub(crate) fn start_task(app: &mut App, screen: &mut Screen, task: String) {
     emit_live_event(screen, &Event::UserTask { text: display });
 }
 
+/// 响应中的 <final> 因同时存在工具调用而被推迟时，生成一条 Thinking 说明事件。
+fn deferred_final_note(response: &str, actions: &[LlmAction]) -> Option<Event> {
+    final_was_deferred(response, actions).then(|| Event::Thinking {
+        text: "Deferred <final>: response also contained a tool call; running the tool first."
+            .to_string(),
+    })
+}
+
+fn parse_retry_limit() -> usize {
+    std::env::var("GOLDBOT_PARSE_RETRY_LIMIT")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_PARSE_RETRY_LIMIT)
+}
+
+/// 生成解析失败后的纠正提示，确保包含具体错误信息。
+fn parse_correction_message(error: &str) -> String {
+    let template = std::env::var("GOLDBOT_PARSE_RETRY_PROMPT")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| v.replace("\\n", "\n"))
+        .unwrap_or_else(|| DEFAULT_PARSE_RETRY_PROMPT.to_string());
+    build_parse_correction(&template, error)
+}
+
+fn build_parse_correction(template: &str, error: &str) -> String {
+    if template.contains("{error}") {
+        template.replace("{error}", error)
+    } else {
+        format!("{template}\nParse error: {error}")
+    }
+}
+
 pub(crate) fn process_llm_result(
     app: &mut App,
     screen: &mut Screen,
-    result: anyhow::Result<(String, crate::agent::provider::Usage)>,
+    result: Result<(String, Usage), ProviderError>,
 ) {
     app.steps_taken += 1;
 
     let (response, usage) = match result {
         Ok((r, u)) => (r, u),
+        // Empty-content errors are often transient; retry once automatically.
+        Err(ProviderError::BadResponse(msg)) if msg.contains("empty content") => {
+            let sym = crate::ui::symbols::Symbols::current();
+            screen.status = format!("{} API 返回空响应，自动重试{}", sym.warning, sym.ellipsis)
+                .dark_yellow()
+                .to_string();
+            screen.refresh();
+            app.needs_agent_executor = true;
+            return;
+        }
+        // Key 无效或缺失：进入 Key 输入流程，提交后自动重试当前任务
+        Err(ProviderError::Unauthorized(msg)) => {
+            crate::ui::input::request_api_key_reentry(app, screen, &msg);
+            return;
+        }
+        Err(ProviderError::Cancelled) => {
+            app.running = false;
+            return;
+        }
+        // 内容被过滤：重试只会得到同样的结果，直接结束任务并说明原因
+        Err(e @ ProviderError::Refused { .. }) => {
+            finish(app, screen, e.to_string());
+            return;
+        }
         Err(e) => {
-            let msg = e.to_string();
-            // Empty-content errors are often transient; retry once automatically.
-            if msg.contains("empty content") {
-                let sym = crate::ui::symbols::Symbols::current();
-                screen.status = format!("{} API 返回空响应，自动重试{}", sym.warning, sym.ellipsis)
-                    .dark_yellow()
-                    .to_string();
-                screen.refresh();
-                app.needs_agent_executor = true;
-                return;
-            }
-            let ev = Event::Thinking {
-                text: format!("[LLM error] {e}"),
+            // 可重试的错误走到这里说明 worker 已重试用尽或已开始流式输出
+            let text = if e.is_retryable() {
+                format!("[LLM error] {e} (gave up after {LLM_MAX_RETRIES} retries)")
+            } else {
+                format!("[LLM error] {e}")
             };
+            // headless 无人接手，直接结束并报告错误
+            if app.headless {
+                app.headless_error = Some(text.clone());
+                app.quit = true;
+            }
+            let ev = Event::Thinking { text };
             emit_live_event(screen, &ev);
             app.task_events.push(ev);
             app.running = false;
@@ -140,27 +264,58 @@ pub(crate) fn process_llm_result(
     app.total_usage.prompt_tokens += usage.prompt_tokens;
     app.total_usage.completion_tokens += usage.completion_tokens;
     app.total_usage.total_tokens += usage.total_tokens;
+    if usage.rate_limit.is_some() {
+        app.rate_limits = usage.rate_limit;
+    }
+
+    // 先于解析记录原文，解析失败时也能看到模型到底输出了什么
+    if app.show_raw {
+        let ev = Event::RawResponse {
+            text: response.clone(),
+        };
+        emit_live_event(screen, &ev);
+        app.task_events.push(ev);
+    }
 
     let (_thought, actions) = match parse_llm_response(&response) {
         Ok(parsed) => parsed,
         Err(e) => {
             app.messages.push(Message::assistant(response));
-            app.messages.push(Message::user(
-                "Your last response could not be parsed. Use one of:\n\
-                 <thought>…</thought><tool>shell</tool><command>…</command>\n\
-                 <thought>…</thought><tool>mcp_…</tool><arguments>{}</arguments>\n\
-                 <thought>…</thought><final>…</final>"
-                    .to_string(),
-            ));
-            screen.status = format!("↻ Retrying invalid response format: {e}")
-                .grey()
-                .to_string();
+            app.parse_failures += 1;
+            let limit = parse_retry_limit();
+            if app.parse_failures > limit {
+                app.parse_failures = 0;
+                let ev = Event::Thinking {
+                    text: format!(
+                        "[LLM error] response could not be parsed after {limit} retries: {e}"
+                    ),
+                };
+                emit_live_event(screen, &ev);
+                app.task_events.push(ev);
+                app.running = false;
+                return;
+            }
+            app.messages
+                .push(Message::user(parse_correction_message(&format!("{e}"))));
+            screen.status = format!(
+                "↻ Retrying invalid response format ({}/{limit}): {e}",
+                app.parse_failures
+            )
+            .grey()
+            .to_string();
             sync_context_budget(app, screen);
             screen.refresh();
             app.needs_agent_executor = true;
             return;
         }
     };
+    app.parse_failures = 0;
+
+    // 同一响应里既有工具调用又有 <final> 时，先执行工具，记录一条说明
+    if let Some(ev) = deferred_final_note(&response, &actions) {
+        emit_live_event(screen, &ev);
+        app.task_events.push(ev);
+    }
 
     // 结构化 <thought> 不再写入持久事件，避免在工具调用前刷屏；
     // 但仍保留在 assistant 原始响应里回灌到下一轮上下文，维持轨迹完整性。
@@ -183,11 +338,20 @@ pub(crate) fn process_llm_result(
     // we must kick the next LLM call ourselves.
     let mut had_non_blocking_only = false;
 
-    'actions: for action in actions {
+    let mut actions = actions.into_iter().peekable();
+    'actions: while let Some(action) = actions.next() {
+        if let Some(name) = builtin_tool_name(&action)
+            && app.disabled_tools.contains(name)
+        {
+            plan_shown_without_followup = false;
+            had_non_blocking_only = false;
+            reject_disabled_tool(app, screen, name);
+            break 'actions;
+        }
         match action {
             LlmAction::Plan { content } => {
                 if !plan_is_echo {
-                    render_plan(screen, &content);
+                    show_plan(app, screen, &content);
                     // Push immediately so the LLM knows the plan was shown in this turn,
                     // even when a question follows in the same response.
                     app.messages.push(Message::user("[plan shown]".to_string()));
@@ -203,24 +367,41 @@ pub(crate) fn process_llm_result(
                 had_non_blocking_only = true;
             }
             LlmAction::Memory { note } => {
-                let _ = ProjectStore::current().append_memory(&note);
+                if let Err(e) = ProjectStore::current().append_memory(&note) {
+                    screen.emit(&[crate::ui::format::save_warning_line(&e)]);
+                }
                 had_non_blocking_only = true;
             }
             LlmAction::Shell { command } => {
                 plan_shown_without_followup = false;
                 had_non_blocking_only = false;
-                let (risk, _reason) = assess_command(&command);
+                let (risk, severity, _reason) = assess_command_severity(&command);
                 match risk {
                     RiskLevel::Safe => {
                         execute_command(app, screen, &command);
                     }
+                    // 观察模式下不会真正执行，无需确认
+                    RiskLevel::Confirm if app.observe => {
+                        execute_command(app, screen, &command);
+                    }
                     RiskLevel::Confirm => {
+                        let auto = auto_confirms(
+                            app.assist_mode,
+                            !app.confirm_edits,
+                            severity,
+                            app.auto_confirm_below,
+                        );
                         if matches!(app.mode, Mode::GeInterview | Mode::GeRun | Mode::GeIdle)
-                            || app.assist_mode == AssistMode::Yolo
+                            || auto
                         {
                             let ev = Event::Thinking {
                                 text: if app.assist_mode == AssistMode::Yolo {
                                     format!("Yolo approved: {command}")
+                                } else if auto {
+                                    format!(
+                                        "Auto-confirmed (severity {severity} < {}): {command}",
+                                        app.auto_confirm_below
+                                    )
                                 } else {
                                     format!("GE auto-approved confirm command: {command}")
                                 },
@@ -237,6 +418,7 @@ pub(crate) fn process_llm_result(
                             emit_live_event(screen, &ev);
                             app.task_events.push(ev);
                             app.pending_confirm = Some(command);
+                            app.pending_confirm_started_at = Some(std::time::Instant::now());
                             app.pending_confirm_note = false;
                             screen.confirm_selected = Some(0);
                             screen.input_focused = false;
@@ -261,6 +443,16 @@ pub(crate) fn process_llm_result(
                 }
                 break 'actions;
             }
+            action @ (LlmAction::UpdateFile { .. } | LlmAction::WriteFile { .. })
+                if edit_needs_confirmation(app) =>
+            {
+                plan_shown_without_followup = false;
+                had_non_blocking_only = false;
+                request_edit_confirmation(app, screen, action);
+                // 等菜单选择后再继续
+                app.needs_agent_executor = false;
+                break 'actions;
+            }
             LlmAction::UpdateFile {
                 path,
                 line_start,
@@ -294,6 +486,13 @@ pub(crate) fn process_llm_result(
                 app.needs_agent_executor = true;
                 break 'actions;
             }
+            LlmAction::DirectoryTree { path, depth, sizes } => {
+                plan_shown_without_followup = false;
+                had_non_blocking_only = false;
+                execute_directory_tree(app, screen, &path, depth, sizes);
+                app.needs_agent_executor = true;
+                break 'actions;
+            }
             LlmAction::ReadFile {
                 path,
                 offset,
@@ -359,8 +558,23 @@ pub(crate) fn process_llm_result(
             LlmAction::Mcp { tool, arguments } => {
                 plan_shown_without_followup = false;
                 had_non_blocking_only = false;
-                execute_mcp_tool(app, screen, &tool, &arguments);
-                app.needs_agent_executor = true;
+                // 紧随其后、发往同一本地服务端的调用合并成一批，共用一次服务端启动
+                let mut batch = vec![(tool, arguments)];
+                while let Some(LlmAction::Mcp { tool: next, .. }) = actions.peek()
+                    && !app.observe
+                    && app.mcp_registry.can_batch(&batch[0].0, next)
+                    && let Some(LlmAction::Mcp { tool, arguments }) = actions.next()
+                {
+                    batch.push((tool, arguments));
+                }
+                if batch.len() > 1 {
+                    execute_mcp_batch(app, screen, batch);
+                } else {
+                    let (tool, arguments) = &batch[0];
+                    execute_mcp_tool(app, screen, tool, arguments);
+                }
+                // 破坏性工具需要用户确认时等菜单选择后再继续
+                app.needs_agent_executor = app.pending_confirm.is_none();
                 break 'actions;
             }
             LlmAction::Skill { name } => {
@@ -370,11 +584,19 @@ pub(crate) fn process_llm_result(
                 app.needs_agent_executor = true;
                 break 'actions;
             }
+            LlmAction::AskSecret { name } => {
+                plan_shown_without_followup = false;
+                had_non_blocking_only = false;
+                let retry = last_failed_shell_command(&app.task_events);
+                crate::ui::input::request_secret_input(app, screen, &name, retry);
+                break 'actions;
+            }
             LlmAction::CreateMcp { config } => {
                 plan_shown_without_followup = false;
                 had_non_blocking_only = false;
                 create_mcp(app, screen, &config);
-                app.needs_agent_executor = true;
+                // 需要用户确认时等菜单选择后再继续
+                app.needs_agent_executor = app.pending_confirm.is_none();
                 break 'actions;
             }
             LlmAction::Todo { items } => {
@@ -428,6 +650,8 @@ pub(crate) fn process_llm_result(
                     Arc::new(app.skills.clone()),
                 );
                 dag_config.preloaded_skill_contexts = app.active_skill_contents.clone();
+                dag_config.observe = app.observe;
+                dag_config.disabled_tools = app.disabled_tools.clone();
                 dag_config.cancel_flag = Some(Arc::clone(&app.dag_cancel_flag));
                 dag_config.progress_tx = Some(progress_tx);
                 app.dag_progress_rx = Some(progress_rx);
@@ -475,6 +699,26 @@ fn apply_assist_mode_change(app: &mut App, screen: &mut Screen, mode: AssistMode
     screen.refresh();
 }
 
+/// 有编号步骤的计划作为 `Event::Plan` 面板显示；再次发出的计划原地替换上一版，
+/// 让折叠视图顶部始终是最新的勾选状态。没有步骤的自由文本计划按 Markdown 渲染。
+fn show_plan(app: &mut App, screen: &mut Screen, content: &str) {
+    let (steps, done) = parse_plan_steps(content);
+    if steps.is_empty() {
+        render_plan(screen, content);
+        return;
+    }
+    let ev = Event::Plan { steps, done };
+    emit_live_event(screen, &ev);
+    match app
+        .task_events
+        .iter_mut()
+        .find(|existing| matches!(existing, Event::Plan { .. }))
+    {
+        Some(existing) => *existing = ev,
+        None => app.task_events.push(ev),
+    }
+}
+
 fn render_plan(screen: &mut Screen, content: &str) {
     use crate::ui::format::{
         is_markdown_rule_pub, render_inline_markdown_pub, split_key_value_parts_pub,
@@ -627,7 +871,89 @@ fn record_tool_result_with_display<C: Into<String>, D: Into<String>>(
     emit_tool_result_event(app, screen, exit_code, display_output);
 }
 
+/// `GOLDBOT_OBSERVE=1` 时启动即进入观察模式。
+pub(crate) fn observe_mode_from_env() -> bool {
+    matches!(
+        std::env::var("GOLDBOT_OBSERVE").unwrap_or_default().trim(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// `GOLDBOT_SAFE=1`（或 `--safe`）时以安全模式启动：不加载 MCP 与技能，便于排查集成问题。
+pub(crate) fn safe_mode_from_env() -> bool {
+    matches!(
+        std::env::var("GOLDBOT_SAFE").unwrap_or_default().trim(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// `GOLDBOT_DISABLE_TOOLS=shell,web_search`：启动时禁用的内置工具，以及无法识别的名称。
+pub(crate) fn disabled_tools_from_env() -> (BTreeSet<String>, Vec<String>) {
+    crate::agent::react::parse_disabled_tools(
+        &std::env::var("GOLDBOT_DISABLE_TOOLS").unwrap_or_default(),
+    )
+}
+
+/// 动作对应的可禁用内置工具名（与 `DISABLEABLE_TOOLS` 一致）。
+pub(crate) fn builtin_tool_name(action: &LlmAction) -> Option<&'static str> {
+    Some(match action {
+        LlmAction::Shell { .. } => "shell",
+        LlmAction::ReadFile { .. } => "read",
+        LlmAction::WriteFile { .. } => "write",
+        LlmAction::UpdateFile { .. } => "update",
+        LlmAction::SearchFiles { .. } => "search",
+        LlmAction::GlobFiles { .. } => "glob",
+        LlmAction::DirectoryTree { .. } => "tree",
+        LlmAction::WebSearch { .. } => "web_search",
+        LlmAction::Task { .. } => "task",
+        LlmAction::SubAgent { .. } => "sub_agent",
+        LlmAction::Mcp { .. } => "mcp",
+        _ => return None,
+    })
+}
+
+/// 模型调用了已禁用的工具：不执行，回灌一条错误结果让它换个做法。
+fn reject_disabled_tool(app: &mut App, screen: &mut Screen, name: &str) {
+    let msg = disabled_tool_message(name);
+    record_tool_result(app, screen, "Tool result (exit=-1):", -1, &msg);
+    app.needs_agent_executor = true;
+}
+
+/// 禁用工具被调用时回灌给模型的结果（主 Agent 与 Sub-Agent 共用）。
+pub(crate) fn disabled_tool_message(name: &str) -> String {
+    format!(
+        "tool disabled: `{name}` is turned off for this session. \
+         Use another available tool or finish with <final>."
+    )
+}
+
+/// 观察模式：只记录工具调用并回灌一条模拟的成功结果，不做任何实际执行。
+fn simulate_tool_call(app: &mut App, screen: &mut Screen, label: String, command: String) {
+    let call_ev = Event::ToolCall {
+        label,
+        command,
+        multiline: false,
+    };
+    emit_live_event(screen, &call_ev);
+    app.task_events.push(call_ev);
+    record_tool_result_with_display(
+        app,
+        screen,
+        "Tool result (exit=0):",
+        0,
+        OBSERVE_CONTEXT,
+        OBSERVE_NOTE,
+    );
+    sync_context_budget(app, screen);
+    app.needs_agent_executor = true;
+}
+
 pub(crate) fn execute_command(app: &mut App, screen: &mut Screen, cmd: &str) {
+    if app.observe {
+        let label = crate::tools::shell::classify_command(cmd).label();
+        simulate_tool_call(app, screen, label, cmd.to_string());
+        return;
+    }
     if app.shell_task_running {
         let msg = "Another shell task is still running. Please wait.";
         record_tool_result(app, screen, "Tool result (exit=-1):", -1, msg);
@@ -649,6 +975,12 @@ pub(crate) fn execute_command(app: &mut App, screen: &mut Screen, cmd: &str) {
     screen.status = format!("Running: {short_cmd}");
     screen.refresh();
     crate::tools::shell::clear_running_shell_cancel_request();
+    if app.task_snapshot.is_some() {
+        let root = std::env::current_dir().unwrap_or_else(|_| app.workspace.clone());
+        for path in crate::tools::shell::predicted_write_targets(&root, cmd) {
+            capture_before_edit(app, screen, &path);
+        }
+    }
 
     let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
     app.shell_exec_rx = Some(rx);
@@ -662,18 +994,138 @@ pub(crate) fn execute_command(app: &mut App, screen: &mut Screen, cmd: &str) {
     });
 }
 
+/// 本轮任务中最近一次失败的 shell 命令，`<ask_secret>` 拿到凭据后用它重试。
+fn last_failed_shell_command(events: &[Event]) -> Option<String> {
+    let events = events
+        .iter()
+        .rposition(|ev| matches!(ev, Event::UserTask { .. }))
+        .map_or(events, |start| &events[start..]);
+    events.windows(2).rev().find_map(|pair| {
+        let [
+            Event::ToolCall { label, command, .. },
+            Event::ToolResult { exit_code, .. },
+        ] = pair
+        else {
+            return None;
+        };
+        // 只重试 shell 命令：其标签由命令分类得出，MCP / 文件编辑等不匹配
+        let is_shell = *label == crate::tools::shell::classify_command(command).label();
+        (*exit_code != 0 && is_shell).then(|| command.clone())
+    })
+}
+
 pub(crate) fn execute_mcp_tool(app: &mut App, screen: &mut Screen, tool: &str, arguments: &Value) {
     let args_text = serde_json::to_string(arguments).unwrap_or_else(|_| "{}".to_string());
+    let label = format!("MCP({})", app.mcp_registry.display_name(tool));
+    if app.observe {
+        simulate_tool_call(app, screen, label, args_text);
+        return;
+    }
+    if mcp_call_needs_confirmation(app, tool) {
+        let preview = format!("{tool} {args_text}");
+        let ev = Event::NeedsConfirmation {
+            command: preview.clone(),
+            reason: label,
+        };
+        emit_live_event(screen, &ev);
+        app.task_events.push(ev);
+        app.pending_mcp_call = Some((preview.clone(), tool.to_string(), arguments.clone()));
+        app.pending_confirm = Some(preview);
+        app.pending_confirm_started_at = Some(std::time::Instant::now());
+        app.pending_confirm_note = false;
+        screen.confirm_selected = Some(0);
+        screen.input_focused = false;
+        screen.refresh();
+        return;
+    }
+    call_mcp_tool(app, screen, tool, arguments, label, args_text);
+}
+
+/// 批量调用同一本地服务端上无需确认的工具，按原顺序逐条记录结果。
+fn execute_mcp_batch(app: &mut App, screen: &mut Screen, calls: Vec<(String, Value)>) {
+    screen.status = format!("MCP: {} calls…", calls.len());
+    screen.refresh();
+    let registry = &app.mcp_registry;
+    let results = run_blocking_compat(|| registry.execute_tools_batch(&calls));
+
+    // 调用与结果逐条交错记录，每条结果注明工具名，LLM 才能把结果对回各自的调用
+    // 服务端 -> 任一报告了工具列表变化的工具名（刷新时按它找到服务端）
+    let mut changed_servers = BTreeMap::new();
+    for ((tool, arguments), result) in calls.iter().zip(results) {
+        let label = format!("MCP({})", app.mcp_registry.display_name(tool));
+        let call_ev = Event::ToolCall {
+            label: label.clone(),
+            command: serde_json::to_string(arguments).unwrap_or_else(|_| "{}".to_string()),
+            multiline: false,
+        };
+        emit_live_event(screen, &call_ev);
+        app.task_events.push(call_ev);
+        match result {
+            Ok(out) => {
+                if out.tools_changed
+                    && let Some(server) = app.mcp_registry.server_for_action(tool)
+                {
+                    changed_servers
+                        .entry(server)
+                        .or_insert_with(|| tool.clone());
+                }
+                let header = format!("Tool result for {label} (exit={}):", out.exit_code);
+                record_tool_result(app, screen, &header, out.exit_code, out.output);
+            }
+            Err(e) => {
+                let err = format!("MCP execution failed: {e}");
+                let header = format!("Tool result for {label} (exit=-1):");
+                record_tool_result(app, screen, &header, -1, err);
+            }
+        }
+    }
+    for tool in changed_servers.into_values() {
+        refresh_mcp_server_tools(app, screen, &tool);
+    }
+}
+
+/// 服务端标了 `destructiveHint` 的 MCP 工具先确认；Yolo 与 GE 直接放行（与 CreateMCP 一致）。
+fn mcp_call_needs_confirmation(app: &App, tool: &str) -> bool {
+    app.mcp_registry.is_destructive(tool)
+        && app.assist_mode != AssistMode::Yolo
+        && !matches!(app.mode, Mode::GeInterview | Mode::GeRun | Mode::GeIdle)
+}
+
+/// 确认菜单选择执行后，调用待确认的破坏性 MCP 工具。
+pub(crate) fn apply_mcp_call(app: &mut App, screen: &mut Screen, tool: &str, arguments: &Value) {
+    let args_text = serde_json::to_string(arguments).unwrap_or_else(|_| "{}".to_string());
+    let label = format!("MCP({})", app.mcp_registry.display_name(tool));
+    call_mcp_tool(app, screen, tool, arguments, label, args_text);
+    app.needs_agent_executor = true;
+}
+
+fn call_mcp_tool(
+    app: &mut App,
+    screen: &mut Screen,
+    tool: &str,
+    arguments: &Value,
+    label: String,
+    args_text: String,
+) {
     let call_ev = Event::ToolCall {
-        label: format!("MCP({tool})"),
+        label,
         command: args_text,
         multiline: false,
     };
     emit_live_event(screen, &call_ev);
     app.task_events.push(call_ev);
 
-    match run_blocking_compat(|| app.mcp_registry.execute_tool(tool, arguments)) {
+    // 调用期间事件循环被阻塞，进度通知直接刷新状态栏
+    let registry = &app.mcp_registry;
+    let mut on_progress = |p: crate::tools::mcp::McpProgress| {
+        screen.status = format!("MCP: {}…", p.label());
+        screen.refresh();
+    };
+    match run_blocking_compat(|| registry.execute_tool(tool, arguments, &mut on_progress)) {
         Ok(out) => {
+            if out.tools_changed {
+                refresh_mcp_server_tools(app, screen, tool);
+            }
             let header = format!("Tool result (exit={}):", out.exit_code);
             record_tool_result(app, screen, &header, out.exit_code, out.output);
         }
@@ -684,6 +1136,24 @@ pub(crate) fn execute_mcp_tool(app: &mut App, screen: &mut Screen, tool: &str, a
     }
 }
 
+/// 服务端通知工具列表变化后，重新发现该服务端的工具并刷新 system prompt。
+pub(crate) fn refresh_mcp_server_tools(app: &mut App, screen: &mut Screen, action_name: &str) {
+    let Some(server) = app.mcp_registry.server_for_action(action_name) else {
+        return;
+    };
+    match run_blocking_compat(|| app.mcp_registry.refresh_server_tools(&server)) {
+        Ok(_) => {
+            app.rebuild_system_message();
+            screen.emit(&[format!("  🔌 {server} tools updated")
+                .dark_grey()
+                .to_string()]);
+        }
+        Err(e) => screen.emit(&[format!("  🔌 {server} tools refresh failed: {e}")
+            .dark_grey()
+            .to_string()]),
+    }
+}
+
 pub(crate) fn handle_shell_exec_result(
     app: &mut App,
     screen: &mut Screen,
@@ -692,6 +1162,7 @@ pub(crate) fn handle_shell_exec_result(
     match result {
         ShellExecResult::Command { result } => match result {
             Ok(out) => {
+                record_shell_created_files(app, screen, &out.output);
                 let header = format!("Tool result (exit={}):", out.exit_code);
                 record_tool_result(app, screen, &header, out.exit_code, out.output);
             }
@@ -731,6 +1202,10 @@ pub(crate) fn execute_update_file(
     line_end: usize,   // 1-based, inclusive
     new_string: &str,
 ) {
+    if app.observe {
+        simulate_tool_call(app, screen, format!("Update({path})"), path.to_string());
+        return;
+    }
     let call_ev = Event::ToolCall {
         label: format!("Update({path})"),
         command: path.to_string(),
@@ -744,6 +1219,11 @@ pub(crate) fn execute_update_file(
     } else {
         app.workspace.join(path)
     };
+    if let Err(e) = crate::tools::access::for_workspace(&app.workspace).check_write(&abs_path) {
+        record_tool_result(app, screen, "Tool result:", 1, e.to_string());
+        return;
+    }
+    capture_before_edit(app, screen, &abs_path);
 
     // 返回 (old_with_ctx, new_with_ctx, ctx_start, norm_new) 用于 diff
     let result: Result<(String, String, usize, String), std::io::Error> = (|| {
@@ -836,7 +1316,106 @@ pub(crate) fn execute_update_file(
     }
 }
 
+/// 非 git 工作区：写入前把文件原状态存入本任务快照，失败或超出大小上限时只提示、不阻止写入。
+fn capture_before_edit(app: &mut App, screen: &mut Screen, abs_path: &std::path::Path) {
+    let Some(snapshot) = app.task_snapshot.as_mut() else {
+        return;
+    };
+    match snapshot.capture(abs_path) {
+        Ok(crate::tools::snapshot::CaptureOutcome::OverCap) => {
+            let note = t_with(
+                "undo.over_cap",
+                &[("path", &abs_path.display().to_string())],
+            );
+            screen.emit(&[format!(
+                "  {} {note}",
+                crate::ui::symbols::Symbols::current().warning
+            )
+            .dark_yellow()
+            .to_string()]);
+        }
+        Ok(_) => {}
+        Err(e) => screen.emit(&[crate::ui::format::save_warning_line(&e)]),
+    }
+}
+
+/// 任务结束时记下快照中各文件的状态，之后被手动改过的文件 `/undo` 不会直接覆盖。
+pub(crate) fn seal_task_snapshot(app: &mut App, screen: &mut Screen) {
+    if let Some(snapshot) = app.task_snapshot.as_mut()
+        && let Err(e) = snapshot.seal()
+    {
+        screen.emit(&[crate::ui::format::save_warning_line(&e)]);
+    }
+}
+
+/// 非 git 工作区：shell 命令新建的文件（执行前无法预见）补记到本任务快照，`/undo` 时删除。
+fn record_shell_created_files(app: &mut App, screen: &mut Screen, output: &str) {
+    let Some(snapshot) = app.task_snapshot.as_mut() else {
+        return;
+    };
+    let Some(changes) = crate::ui::format::parse_fs_changes(output) else {
+        return;
+    };
+    let root = std::env::current_dir().unwrap_or_else(|_| app.workspace.clone());
+    for path in changes.created {
+        if let Err(e) = snapshot.record_created(&root.join(path)) {
+            screen.emit(&[crate::ui::format::save_warning_line(&e)]);
+        }
+    }
+}
+
+/// 结构化编辑是否要先确认：仅 `GOLDBOT_CONFIRM_EDITS` 开启的 agent 模式；
+/// Yolo、GE 与观察模式（不会真正写入）直接执行。
+fn edit_needs_confirmation(app: &App) -> bool {
+    app.confirm_edits
+        && !app.observe
+        && app.assist_mode != AssistMode::Yolo
+        && !matches!(app.mode, Mode::GeInterview | Mode::GeRun | Mode::GeIdle)
+}
+
+/// 弹出确认菜单，展示编辑前后的 diff；确认后由 `apply_pending_edit` 执行。
+fn request_edit_confirmation(app: &mut App, screen: &mut Screen, action: LlmAction) {
+    let reason = match &action {
+        LlmAction::WriteFile { path, .. } => format!("Write({path})"),
+        LlmAction::UpdateFile { path, .. } => format!("Update({path})"),
+        _ => return,
+    };
+    let preview = crate::tools::fs::preview_edit(&action, &app.workspace);
+    let ev = Event::NeedsConfirmation {
+        command: preview.clone(),
+        reason,
+    };
+    emit_live_event(screen, &ev);
+    app.task_events.push(ev);
+    app.pending_edit = Some((preview.clone(), action));
+    app.pending_confirm = Some(preview);
+    app.pending_confirm_started_at = Some(std::time::Instant::now());
+    app.pending_confirm_note = false;
+    screen.confirm_selected = Some(0);
+    screen.input_focused = false;
+    screen.refresh();
+}
+
+/// 确认菜单选择执行后，真正写入待确认的编辑。
+pub(crate) fn apply_pending_edit(app: &mut App, screen: &mut Screen, action: LlmAction) {
+    match action {
+        LlmAction::WriteFile { path, content } => execute_write_file(app, screen, &path, &content),
+        LlmAction::UpdateFile {
+            path,
+            line_start,
+            line_end,
+            new_string,
+        } => execute_update_file(app, screen, &path, line_start, line_end, &new_string),
+        _ => return,
+    }
+    app.needs_agent_executor = true;
+}
+
 pub(crate) fn execute_write_file(app: &mut App, screen: &mut Screen, path: &str, content: &str) {
+    if app.observe {
+        simulate_tool_call(app, screen, "Write".to_string(), path.to_string());
+        return;
+    }
     let call_ev = Event::ToolCall {
         label: "Write".to_string(),
         command: path.to_string(),
@@ -850,6 +1429,11 @@ pub(crate) fn execute_write_file(app: &mut App, screen: &mut Screen, path: &str,
     } else {
         app.workspace.join(path)
     };
+    if let Err(e) = crate::tools::access::for_workspace(&app.workspace).check_write(&abs_path) {
+        record_tool_result(app, screen, "Tool result:", 1, e.to_string());
+        return;
+    }
+    capture_before_edit(app, screen, &abs_path);
 
     let result: Result<(String, String), std::io::Error> = (|| {
         // 读取旧内容（文件不存在则视为空）
@@ -908,6 +1492,10 @@ pub(crate) fn execute_read_file(
     } else {
         app.workspace.join(path)
     };
+    if let Err(e) = crate::tools::access::for_workspace(&app.workspace).check_read(&abs_path) {
+        record_tool_result(app, screen, "Tool result:", 1, e.to_string());
+        return;
+    }
 
     let result: Result<String, std::io::Error> = (|| {
         let raw = std::fs::read_to_string(&abs_path)?;
@@ -1039,6 +1627,39 @@ pub(crate) fn execute_glob_files(app: &mut App, screen: &mut Screen, pattern: &s
     }
 }
 
+pub(crate) fn execute_directory_tree(
+    app: &mut App,
+    screen: &mut Screen,
+    path: &str,
+    depth: Option<usize>,
+    sizes: bool,
+) {
+    let call_ev = Event::ToolCall {
+        label: format!("Tree({})", shorten_text(path, 40)),
+        command: path.to_string(),
+        multiline: false,
+    };
+    emit_live_event(screen, &call_ev);
+    app.task_events.push(call_ev);
+
+    match run_blocking_compat(|| crate::tools::fs::directory_tree(path, depth, sizes)) {
+        Ok(result) => {
+            let summary = format!(
+                "{} entr{}{}",
+                result.node_count,
+                if result.node_count == 1 { "y" } else { "ies" },
+                if result.truncated { " (truncated)" } else { "" }
+            );
+            let llm_msg = format!("{}\n{}", summary, result.output);
+            record_tool_result(app, screen, "Tool result (exit=0):", 0, llm_msg);
+        }
+        Err(e) => {
+            let err = format!("tree failed: {e}");
+            record_tool_result(app, screen, "Tool result (exit=-1):", -1, err);
+        }
+    }
+}
+
 pub(crate) fn execute_task(
     app: &mut App,
     screen: &mut Screen,
@@ -1106,6 +1727,8 @@ pub(crate) fn execute_task(
         Arc::new(app.skills.clone()),
     );
     dag_config.preloaded_skill_contexts = app.active_skill_contents.clone();
+    dag_config.observe = app.observe;
+    dag_config.disabled_tools = app.disabled_tools.clone();
     dag_config.cancel_flag = Some(Arc::clone(&app.dag_cancel_flag));
     dag_config.progress_tx = Some(progress_tx);
     app.dag_progress_rx = Some(progress_rx);
@@ -1159,6 +1782,49 @@ pub(crate) fn load_skill(app: &mut App, screen: &mut Screen, name: &str) {
 }
 
 pub(crate) fn create_mcp(app: &mut App, screen: &mut Screen, config: &serde_json::Value) {
+    if app.observe {
+        let config = serde_json::to_string(config).unwrap_or_default();
+        simulate_tool_call(app, screen, "CreateMCP".to_string(), config);
+        return;
+    }
+
+    let name = mcp_server_name(config);
+    // 先 dry-run 得到规范化后的配置，确认菜单里展示的就是将要写入的内容
+    let preview = match crate::tools::mcp::create_mcp_server(&name, config, true) {
+        Ok((path, spec)) => format!(
+            "{}\n\"{name}\": {}",
+            path.display(),
+            serde_json::to_string(&spec).unwrap_or_default()
+        ),
+        Err(_) => {
+            apply_create_mcp(app, screen, config);
+            return;
+        }
+    };
+    if matches!(app.mode, Mode::GeInterview | Mode::GeRun | Mode::GeIdle)
+        || app.assist_mode == AssistMode::Yolo
+    {
+        apply_create_mcp(app, screen, config);
+        return;
+    }
+
+    let ev = Event::NeedsConfirmation {
+        command: preview.clone(),
+        reason: "CreateMCP".to_string(),
+    };
+    emit_live_event(screen, &ev);
+    app.task_events.push(ev);
+    app.pending_mcp_create = Some((preview.clone(), config.clone()));
+    app.pending_confirm = Some(preview);
+    app.pending_confirm_started_at = Some(std::time::Instant::now());
+    app.pending_confirm_note = false;
+    screen.confirm_selected = Some(0);
+    screen.input_focused = false;
+    screen.refresh();
+}
+
+/// 确认后（或 Yolo / GE 自动放行时）真正写入 MCP 配置，并试启动一次校验配置可用。
+pub(crate) fn apply_create_mcp(app: &mut App, screen: &mut Screen, config: &serde_json::Value) {
     let call_ev = Event::ToolCall {
         label: "CreateMCP".to_string(),
         command: serde_json::to_string(config).unwrap_or_default(),
@@ -1167,26 +1833,138 @@ pub(crate) fn create_mcp(app: &mut App, screen: &mut Screen, config: &serde_json
     emit_live_event(screen, &call_ev);
     app.task_events.push(call_ev);
 
-    let name = config
-        .get("name")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
+    let name = mcp_server_name(config);
+    let path = crate::tools::mcp::mcp_servers_file_path();
+    let previous = std::fs::read_to_string(&path).ok();
 
     // create_mcp_server handles spec cleanup (strips name, type, empty fields).
-    let (exit_code, result_msg) = match crate::tools::mcp::create_mcp_server(&name, config) {
-        Ok(path) => (
-            0,
-            format!(
-                "MCP server `{name}` saved to `{}`. Restart GoldBot to activate it.",
-                path.display()
-            ),
-        ),
-        Err(e) => (1, format!("Failed to create MCP server: {e}")),
-    };
-
-    record_tool_result(app, screen, "Tool result:", exit_code, result_msg);
-}
+    let spec = match crate::tools::mcp::create_mcp_server(&name, config, false) {
+        Ok((_, spec)) => spec,
+        Err(e) => {
+            let msg = format!("Failed to create MCP server: {e}");
+            record_tool_result(app, screen, "Tool result:", 1, msg);
+            app.needs_agent_executor = true;
+            return;
+        }
+    };
+    let saved = format!("MCP server `{name}` saved to `{}`.", path.display());
+    if !mcp_verify_on_create_enabled() {
+        let msg = format!("{saved} Restart GoldBot to activate it.");
+        record_tool_result(app, screen, "Tool result:", 0, msg);
+        app.needs_agent_executor = true;
+        return;
+    }
+
+    screen.status = format!("Verifying MCP server `{name}`…").grey().to_string();
+    screen.refresh();
+    // 首次启动可能要下载包（`npx -y`），放到后台线程，结果由 `poll_mcp_verify` 处理
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(crate::tools::mcp::verify_mcp_server(&spec));
+    });
+    app.mcp_verify_rx = Some(rx);
+    app.mcp_verify_target = Some(McpRollback {
+        name,
+        path,
+        previous,
+    });
+    app.needs_agent_executor = false;
+}
+
+/// 后台校验结束后回灌结果：成功则继续任务，失败则弹出回滚菜单。
+pub(crate) fn poll_mcp_verify(app: &mut App, screen: &mut Screen) {
+    let Some(rx) = app.mcp_verify_rx.as_mut() else {
+        return;
+    };
+    let result = match rx.try_recv() {
+        Ok(result) => result,
+        Err(tokio::sync::oneshot::error::TryRecvError::Empty) => return,
+        Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+            Err(anyhow::anyhow!("verification worker exited"))
+        }
+    };
+    app.mcp_verify_rx = None;
+    let Some(target) = app.mcp_verify_target.take() else {
+        return;
+    };
+    screen.status.clear();
+    if !app.running {
+        // 任务已取消：配置已写入，只是不再报告校验结果
+        screen.refresh();
+        return;
+    }
+    let name = target.name.clone();
+    let saved = format!("MCP server `{name}` saved to `{}`.", target.path.display());
+    match result {
+        Ok(count) => {
+            let msg = format!(
+                "{saved}\n✓ server responds with {count} tools. Restart GoldBot to activate it."
+            );
+            record_tool_result(app, screen, "Tool result:", 0, msg);
+            app.needs_agent_executor = true;
+            screen.refresh();
+        }
+        Err(e) => {
+            let msg = format!("{saved}\n✗ server failed to start: {e:#}");
+            record_tool_result(app, screen, "Tool result:", 1, msg);
+            screen.emit(&[format!(
+                "  {}",
+                t_with("mcp.rollback_prompt", &[("name", &name)])
+            )]);
+            screen.question_labels = vec![
+                t("mcp.option_rollback").to_string(),
+                t("mcp.option_keep").to_string(),
+            ];
+            screen.confirm_selected = Some(0);
+            screen.input_focused = false;
+            app.pending_mcp_rollback = Some(target);
+            app.needs_agent_executor = false;
+            screen.refresh();
+        }
+    }
+}
+
+fn mcp_verify_on_create_enabled() -> bool {
+    !matches!(
+        std::env::var(ENV_MCP_VERIFY_ON_CREATE)
+            .unwrap_or_default()
+            .trim(),
+        "0" | "false" | "no" | "off"
+    )
+}
+
+/// 处理 MCP 回滚菜单：回滚则恢复写入前的配置文件，随后把结果告诉 LLM 继续任务。
+pub(crate) fn resolve_mcp_rollback(app: &mut App, screen: &mut Screen, rollback: bool) {
+    let Some(pending) = app.pending_mcp_rollback.take() else {
+        return;
+    };
+    screen.confirm_selected = None;
+    screen.question_labels.clear();
+    screen.input_focused = true;
+    let name = pending.name.as_str();
+    let note = if !rollback {
+        t_with("mcp.kept", &[("name", name)])
+    } else {
+        match crate::tools::mcp::restore_mcp_config(&pending.path, pending.previous.as_deref()) {
+            Ok(()) => t_with("mcp.rolled_back", &[("name", name)]),
+            Err(e) => format!("{e:#}"),
+        }
+    };
+    screen.emit(&[format!("  {note}")]);
+    app.messages
+        .push(Message::user(format!("[create_mcp] {note}")));
+    sync_context_budget(app, screen);
+    app.needs_agent_executor = app.running;
+    screen.refresh();
+}
+
+fn mcp_server_name(config: &serde_json::Value) -> String {
+    config
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string()
+}
 
 pub(crate) fn finish(app: &mut App, screen: &mut Screen, summary: String) {
     let mut summary = sanitize_final_summary_for_tui(&summary);
@@ -1202,7 +1980,9 @@ pub(crate) fn finish(app: &mut App, screen: &mut Screen, summary: String) {
     let session_task = session_task_for_round(&app.task, &app.task_events).to_string();
 
     if !app.message_queue.is_empty() {
-        let _ = Session::current().append_to_session(&session_task, &summary);
+        if let Err(e) = Session::current().append_to_session(&session_task, &summary) {
+            screen.emit(&[crate::ui::format::save_warning_line(&e)]);
+        }
         let ev = Event::Final {
             summary: summary.clone(),
         };
@@ -1218,18 +1998,29 @@ pub(crate) fn finish(app: &mut App, screen: &mut Screen, summary: String) {
     app.final_summary = Some(summary.clone());
     app.task_collapsed = true;
 
-    screen.collapse_to(&collapsed_lines(app));
-
-    let _ = Session::current().append_to_session(&session_task, &summary);
-
+    // 折叠视图要显示起止时间，先记下耗时与结束时刻
     let total_elapsed = app.task_started_at.map(|t| t.elapsed());
     app.last_task_elapsed = total_elapsed;
+    app.task_finished_clock = Some(chrono::Local::now());
     app.task_started_at = None;
 
+    screen.collapse_to(&collapsed_lines(app));
+
+    if let Err(e) = Session::current().append_to_session(&session_task, &summary) {
+        screen.emit(&[crate::ui::format::save_warning_line(&e)]);
+    }
+    if !app.no_memory {
+        promote_short_term_memory(app, screen, false);
+    }
+    seal_task_snapshot(app, screen);
+
     app.running = false;
     app.llm_stream_preview.clear();
     app.llm_preview_shown.clear();
     app.pending_confirm = None;
+    app.pending_mcp_create = None;
+    app.pending_mcp_call = None;
+    app.pending_edit = None;
 
     app.pending_confirm_note = false;
     app.current_phase_summary = None;
@@ -1241,6 +2032,13 @@ pub(crate) fn finish(app: &mut App, screen: &mut Screen, summary: String) {
             .to_string(),
         None => "[Ctrl+d] full details".grey().to_string(),
     };
+    // GOLDBOT_FINAL_PAGER=1：有改动时在外部 pager 中打开完整 diff
+    if !app.headless
+        && crate::ui::pager::final_pager_enabled()
+        && let Err(e) = crate::ui::pager::open_diff_in_pager(&app.workspace, screen)
+    {
+        screen.emit(&[format!("  ⚠ pager: {e:#}")]);
+    }
     // headless 模式（-p 启动）：任务完成后自动退出
     if app.headless {
         app.quit = true;
@@ -1249,6 +2047,63 @@ pub(crate) fn finish(app: &mut App, screen: &mut Screen, summary: String) {
     screen.refresh();
 }
 
+/// 把反复出现的短期任务提升为长期记忆：默认词集相似度 ≥60% 的任务在所有已存会话中
+/// 累计出现 3 次即提升（`GOLDBOT_MEMORY_PROMOTE_SIMILARITY` / `GOLDBOT_MEMORY_PROMOTE_AT`）。
+/// 要读遍全部会话文件，放到后台线程，结果由 `poll_memory_promotion` 输出；
+/// `report_idle` 为 true（手动 `/memory promote`）时，无可提升内容也给出反馈。
+pub(crate) fn promote_short_term_memory(app: &mut App, screen: &mut Screen, report_idle: bool) {
+    // 上一轮还在跑时不重复启动，手动触发则让它结束后给出反馈
+    if let Some((_, pending_report)) = app.memory_promote_rx.as_mut() {
+        *pending_report |= report_idle;
+        return;
+    }
+    // headless 完成后立即退出，等不到后台线程，直接同步执行
+    if app.headless {
+        report_memory_promotion(screen, promote_repeated_tasks(), report_idle);
+        return;
+    }
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(promote_repeated_tasks());
+    });
+    app.memory_promote_rx = Some((rx, report_idle));
+}
+
+fn promote_repeated_tasks() -> anyhow::Result<usize> {
+    let tasks = Session::current().short_term_tasks();
+    ProjectStore::current().promote_repeated_short_term_to_long_term(&tasks)
+}
+
+/// 后台记忆提升结束后在滚动区提示结果。
+pub(crate) fn poll_memory_promotion(app: &mut App, screen: &mut Screen) {
+    let Some((rx, report_idle)) = app.memory_promote_rx.as_mut() else {
+        return;
+    };
+    let result = match rx.try_recv() {
+        Ok(result) => result,
+        Err(tokio::sync::oneshot::error::TryRecvError::Empty) => return,
+        Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+            Err(anyhow::anyhow!("promotion worker exited"))
+        }
+    };
+    let report_idle = *report_idle;
+    app.memory_promote_rx = None;
+    report_memory_promotion(screen, result, report_idle);
+    screen.refresh();
+}
+
+fn report_memory_promotion(screen: &mut Screen, result: anyhow::Result<usize>, report_idle: bool) {
+    match result {
+        Ok(0) if report_idle => screen.emit(&["  🧠 暂无达到阈值的重复任务".to_string()]),
+        Ok(0) => {}
+        Ok(n) => screen.emit(&[format!("  🧠 promoted {n} patterns to long-term")
+            .dark_grey()
+            .to_string()]),
+        Err(e) if report_idle => screen.emit(&[format!("  ✗ 记忆提升失败：{e:#}")]),
+        Err(_) => {}
+    }
+}
+
 fn session_task_for_round<'a>(root_task: &'a str, events: &'a [Event]) -> &'a str {
     events
         .iter()
@@ -1268,14 +2123,14 @@ pub(crate) fn handle_llm_thinking_delta(app: &mut App, screen: &mut Screen, chun
     }
 
     app.llm_stream_preview.push_str(chunk);
-    trim_left_to_max_bytes(&mut app.llm_stream_preview, 16_384);
+    trim_left_to_max_bytes(&mut app.llm_stream_preview, LLM_STREAM_BUFFER_MAX_BYTES);
 
     let collapsed = app
         .llm_stream_preview
         .split_whitespace()
         .collect::<Vec<_>>()
         .join(" ");
-    let preview = tail_chars(&collapsed, 240);
+    let preview = tail_preview(&collapsed);
     if preview.is_empty() {
         return;
     }
@@ -1304,14 +2159,26 @@ pub(crate) fn handle_llm_stream_delta(app: &mut App, screen: &mut Screen, delta:
     }
 
     app.llm_stream_preview.push_str(delta);
-    trim_left_to_max_bytes(&mut app.llm_stream_preview, 16_384);
-
-    // When native thinking is on, preview comes from ThinkingDelta events; skip here.
-    if app.show_thinking {
-        return;
-    }
-
-    let preview = extract_live_preview(&app.llm_stream_preview);
+    trim_left_to_max_bytes(&mut app.llm_stream_preview, LLM_STREAM_BUFFER_MAX_BYTES);
+
+    let preview = match extract_live_preview(&app.llm_stream_preview) {
+        LivePreview::Final(text) => {
+            // 最终总结在管理区原地增长；按行或每 24 个字符刷新一次，避免逐 token 重绘
+            let shown = &screen.final_preview;
+            let grew_by = text.chars().count().saturating_sub(shown.chars().count());
+            if shown.is_empty()
+                || !text.starts_with(shown.as_str())
+                || grew_by >= 24
+                || text.ends_with('\n')
+            {
+                screen.set_final_preview(&text);
+            }
+            return;
+        }
+        // When native thinking is on, preview comes from ThinkingDelta events; skip here.
+        LivePreview::Thought(_) if app.show_thinking => return,
+        LivePreview::Thought(preview) => preview,
+    };
     if preview.is_empty() {
         return;
     }
@@ -1334,15 +2201,38 @@ pub(crate) fn handle_llm_stream_delta(app: &mut App, screen: &mut Screen, delta:
     refresh_llm_status(app, screen);
 }
 
+/// 暂停中的状态行，提示当前绑定的暂停键。
+pub(crate) fn pause_status(app: &App) -> String {
+    let key = app.keymap.key_label(KeyAction::Pause);
+    t_with("pause.on", &[("key", &key)])
+        .dark_yellow()
+        .to_string()
+}
+
 pub(crate) fn refresh_llm_status(app: &mut App, screen: &mut Screen) {
     if !app.llm_calling {
         return;
     }
-    let base = if app.llm_preview_shown.is_empty() {
+    let pacing = app
+        .rate_limit_pace_until
+        .is_some_and(|until| std::time::Instant::now() < until);
+    let pause_key = app.keymap.key_label(KeyAction::Pause);
+    let after_step;
+    let base = if app.paused {
+        after_step = t_with("pause.after_step", &[("key", &pause_key)]);
+        &after_step
+    } else if pacing {
+        RATE_LIMIT_PACING_STATUS
+    } else if app.llm_preview_shown.is_empty() || screen.thinking_panel {
         "Thinking..."
     } else {
         &app.llm_preview_shown
     };
+    // 面板布局下流式预览写入 Thinking 面板，状态行只保留计时
+    if screen.thinking_panel && !app.llm_preview_shown.is_empty() {
+        let preview = app.llm_preview_shown.clone();
+        screen.set_thinking(&preview);
+    }
     let started_at = app.task_started_at.or(app.llm_call_started_at);
     screen.status = format_llm_status_with_elapsed(base, started_at);
     sync_context_budget(app, screen);
@@ -1357,7 +2247,7 @@ fn format_llm_status_with_elapsed(base: &str, started_at: Option<std::time::Inst
     format!("{base} ({elapsed} • esc to interrupt)")
 }
 
-fn format_elapsed_short(d: std::time::Duration) -> String {
+pub(crate) fn format_elapsed_short(d: std::time::Duration) -> String {
     let secs = d.as_secs();
     if secs < 60 {
         return format!("{secs}s");
@@ -1373,7 +2263,7 @@ fn format_elapsed_short(d: std::time::Duration) -> String {
     format!("{h}h {m:02}m {s:02}s")
 }
 
-fn run_blocking_compat<T>(f: impl FnOnce() -> T) -> T {
+pub(crate) fn run_blocking_compat<T>(f: impl FnOnce() -> T) -> T {
     match tokio::runtime::Handle::try_current() {
         Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
             tokio::task::block_in_place(f)
@@ -1382,8 +2272,29 @@ fn run_blocking_compat<T>(f: impl FnOnce() -> T) -> T {
     }
 }
 
-fn extract_live_preview(raw: &str) -> String {
-    let mut s = if let Some(start) = raw.rfind("<thought>") {
+/// 流式预览的来源：`<final>` 开标签出现前是思考（状态行单行预览），之后是最终总结正文。
+#[derive(Debug, PartialEq, Eq)]
+enum LivePreview {
+    Thought(String),
+    Final(String),
+}
+
+fn extract_live_preview(raw: &str) -> LivePreview {
+    let thought_start = raw.rfind("<thought>");
+    if let Some(start) = raw.rfind("<final>")
+        && thought_start.is_none_or(|t| t < start)
+    {
+        let body = &raw[start + "<final>".len()..];
+        let body = body.find("</final>").map_or(body, |end| &body[..end]);
+        // 尚未收全的闭合标签（如 `</fin`）先不显示
+        let body = match body.rfind('<') {
+            Some(lt) if "</final>".starts_with(&body[lt..]) => &body[..lt],
+            _ => body,
+        };
+        return LivePreview::Final(body.trim_start().to_string());
+    }
+
+    let mut s = if let Some(start) = thought_start {
         &raw[start + "<thought>".len()..]
     } else {
         raw
@@ -1394,9 +2305,10 @@ fn extract_live_preview(raw: &str) -> String {
 
     let no_tags = strip_xml_tags(s);
     let collapsed = no_tags.split_whitespace().collect::<Vec<_>>().join(" ");
-    tail_chars(&collapsed, 240)
+    LivePreview::Thought(tail_preview(&collapsed))
 }
 
+/// 只删除字符，输出不会比输入长；输入本身已被限制在 `LLM_STREAM_BUFFER_MAX_BYTES` 内。
 fn strip_xml_tags(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     let mut in_tag = false;
@@ -1411,6 +2323,22 @@ fn strip_xml_tags(s: &str) -> String {
     out
 }
 
+/// 预览取结尾部分，字符数和显示宽度都有上限，超宽内容不会撑长状态行。
+fn tail_preview(s: &str) -> String {
+    let tail = tail_chars(s, LLM_PREVIEW_MAX_CHARS);
+    let mut width = 0;
+    let start = tail
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| {
+            width += UnicodeWidthChar::width(*ch).unwrap_or(0);
+            width <= LLM_PREVIEW_MAX_WIDTH
+        })
+        .last()
+        .map_or(tail.len(), |(idx, _)| idx);
+    tail[start..].to_string()
+}
+
 fn tail_chars(s: &str, max_chars: usize) -> String {
     if max_chars == 0 {
         return String::new();
@@ -1549,14 +2477,148 @@ fn format_token_count_short(tokens: u32) -> String {
     tokens.to_string()
 }
 
-/// 执行 compact 的核心逻辑，返回 (summary, messages_dropped)
-async fn do_compact(app: &mut App, screen: &mut Screen) -> Option<(String, usize)> {
-    let prefix_end = app.messages.len().min(1);
-    if app.messages.len() <= prefix_end + 1 {
+/// `/context` 中单个角色的消息统计。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RoleStats {
+    pub count: usize,
+    pub chars: usize,
+    pub tokens: u32,
+}
+
+impl RoleStats {
+    fn add(&mut self, msg: &Message) {
+        self.count += 1;
+        self.chars += msg.content.chars().count();
+        self.tokens = self
+            .tokens
+            .saturating_add(estimate_text_tokens(&msg.content))
+            .saturating_add(8);
+    }
+}
+
+/// `/context` 展示的消息栈构成；token 数沿用 compact 预算的字符估算（未乘校准系数）。
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct ContextBreakdown {
+    pub total: usize,
+    pub system: RoleStats,
+    pub user: RoleStats,
+    pub assistant: RoleStats,
+    /// 作为 user 消息回灌的工具结果条数（已计入 `user`）。
+    pub tool_results: usize,
+    /// system prompt 所在下标（通常为 0）。
+    pub system_prompt_index: Option<usize>,
+}
+
+pub(crate) fn context_breakdown(messages: &[Message]) -> ContextBreakdown {
+    use crate::agent::provider::Role;
+
+    let mut breakdown = ContextBreakdown {
+        total: messages.len(),
+        ..ContextBreakdown::default()
+    };
+    for (i, msg) in messages.iter().enumerate() {
+        match msg.role {
+            Role::System => {
+                breakdown.system_prompt_index.get_or_insert(i);
+                breakdown.system.add(msg);
+            }
+            Role::User => {
+                if msg.content.starts_with("Tool result") {
+                    breakdown.tool_results += 1;
+                }
+                breakdown.user.add(msg);
+            }
+            Role::Assistant => breakdown.assistant.add(msg),
+        }
+    }
+    breakdown
+}
+
+/// `/context` 输出：角色分布、估算 token、工具结果数与下一次调用是否会触发 compact。
+pub(crate) fn context_report_lines(app: &App, verbose: bool) -> Vec<String> {
+    let breakdown = context_breakdown(&app.messages);
+    let budget = current_context_budget(app);
+    let role_line = |name: &str, stats: &RoleStats| {
+        format!(
+            "    {name:<10} {:>4} 条  {:>8} 字符  ~{} tokens",
+            stats.count,
+            stats.chars,
+            format_token_count_short(stats.tokens)
+        )
+    };
+    let mut lines = vec![
+        format!("  上下文：{} 条消息", breakdown.total),
+        role_line("system", &breakdown.system),
+        role_line("user", &breakdown.user),
+        role_line("assistant", &breakdown.assistant),
+        format!("  其中工具结果：{} 条（计入 user）", breakdown.tool_results),
+    ];
+    lines.push(match breakdown.system_prompt_index {
+        Some(i) => format!(
+            "  System prompt：#{i}（{} 字符）",
+            app.messages[i].content.chars().count()
+        ),
+        None => "  System prompt：无".to_string(),
+    });
+    lines.push(format!(
+        "  预算：{} used / {} left（窗口 {}，compact 预留 {}）",
+        format_token_count_short(budget.used_prompt_tokens),
+        format_token_count_short(budget.hard_left_tokens),
+        format_token_count_short(budget.context_window_tokens),
+        format_token_count_short(budget.compact_reserve_tokens)
+    ));
+    lines.push(if budget.should_compact {
+        "  下一次调用前将自动 compact（剩余 ≤ 预留）".to_string()
+    } else {
+        "  下一次调用不会触发 compact".to_string()
+    });
+    if verbose {
+        lines.push(String::new());
+        for (i, msg) in app.messages.iter().enumerate() {
+            let role = format!("{:?}", msg.role).to_lowercase();
+            let first_line = msg.content.lines().find(|l| !l.trim().is_empty());
+            lines.push(format!(
+                "    #{i:<3} {role:<9} {}",
+                shorten_text(first_line.unwrap_or(""), 80)
+            ));
+        }
+    }
+    lines
+}
+
+/// `/prompt` 的输出：逐段列出系统提示词的字符数与估算 token；`full` 时附上完整文本。
+pub(crate) fn prompt_report_lines(app: &App, full: bool) -> Vec<String> {
+    let builder = crate::agent::prompt::SystemPromptBuilder::for_app(app);
+    let mut lines = vec![format!("  System prompt：{} 段", builder.sections().len())];
+    for section in builder.sections() {
+        lines.push(format!(
+            "    {:<10} {:>8} 字符  ~{} tokens",
+            section.name,
+            section.content.chars().count(),
+            format_token_count_short(estimate_text_tokens(&section.content))
+        ));
+    }
+    let prompt = builder.build();
+    lines.push(format!(
+        "  合计：{} 字符  ~{} tokens",
+        prompt.chars().count(),
+        format_token_count_short(estimate_text_tokens(&prompt))
+    ));
+    if full {
+        lines.push(String::new());
+        lines.extend(prompt.lines().map(|l| format!("  {l}")));
+    }
+    lines
+}
+
+/// 计算 compact 的切分点 (prefix_end, split_at, max_split_at)；消息太少无需压缩时返回 None。
+fn compaction_split_points(messages: &[Message]) -> Option<(usize, usize, usize)> {
+    let prefix_end = messages.len().min(1);
+    if messages.len() <= prefix_end + 1 {
         return None;
     }
 
-    let total = app.messages.len();
+    let total = messages.len();
     let preferred_recent =
         KEEP_RECENT_MESSAGES_AFTER_COMPACTION.min(total.saturating_sub(prefix_end));
     let min_recent = MIN_RECENT_MESSAGES_AFTER_COMPACTION.min(total.saturating_sub(prefix_end));
@@ -1565,11 +2627,319 @@ async fn do_compact(app: &mut App, screen: &mut Screen) -> Option<(String, usize
         return None;
     }
 
-    let mut chosen_split_at = total.saturating_sub(preferred_recent);
-    if chosen_split_at <= prefix_end {
-        chosen_split_at = prefix_end + 1;
-    }
-    chosen_split_at = chosen_split_at.min(max_split_at);
+    let mut chosen_split_at = total.saturating_sub(preferred_recent);
+    if chosen_split_at <= prefix_end {
+        chosen_split_at = prefix_end + 1;
+    }
+    Some((prefix_end, chosen_split_at.min(max_split_at), max_split_at))
+}
+
+/// 用摘要替换 `[prefix_end, split_at)` 区间；若仍超出预算则继续后移 split 点。
+/// 返回 (压缩后的消息, 最终 split 点)，不修改 `app.messages`。
+fn build_compacted_messages(
+    app: &App,
+    summary: &str,
+    prefix_end: usize,
+    mut split_at: usize,
+    max_split_at: usize,
+) -> (Vec<Message>, usize) {
+    let budget = current_context_budget(app);
+    loop {
+        let mut candidate = app.messages[..prefix_end].to_vec();
+        candidate.push(Message::user(format!("[Context compacted]\n{summary}")));
+        candidate.extend_from_slice(&app.messages[split_at..]);
+
+        let candidate_tokens = estimate_prompt_tokens(&candidate, app.prompt_token_scale);
+        if candidate_tokens.saturating_add(budget.compact_reserve_tokens)
+            <= budget.context_window_tokens
+            || split_at >= max_split_at
+        {
+            return (candidate, split_at);
+        }
+        split_at += 1;
+    }
+}
+
+/// compact 预览：将被摘要替换的消息区间与本地摘要。
+#[derive(Debug, Clone)]
+pub(crate) struct CompactionPreview {
+    pub summary: String,
+    /// 将被丢弃的消息下标区间 `[start, end)`（下标 0 为 system prompt）。
+    pub dropped: std::ops::Range<usize>,
+    pub total: usize,
+}
+
+/// 生成 compact 预览，不修改 `app.messages`。
+/// 预览使用本地提取的摘要；真正执行时会改用 LLM 生成摘要，实际区间可能略有出入。
+pub(crate) fn preview_compaction(app: &App) -> Option<CompactionPreview> {
+    let (prefix_end, split_at, max_split_at) = compaction_split_points(&app.messages)?;
+    let folded = &app.messages[prefix_end..split_at];
+    let summary = with_failed_tool_results(summarize_for_compaction_fallback(folded), folded);
+    let (_, split_at) = build_compacted_messages(app, &summary, prefix_end, split_at, max_split_at);
+    Some(CompactionPreview {
+        summary,
+        dropped: prefix_end..split_at,
+        total: app.messages.len(),
+    })
+}
+
+/// `GOLDBOT_CONFIRM_EDITS=1` 时 WriteFile / UpdateFile 在 agent 模式下需要确认。
+pub(crate) fn confirm_edits_from_env() -> bool {
+    matches!(
+        std::env::var("GOLDBOT_CONFIRM_EDITS")
+            .unwrap_or_default()
+            .trim(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// `GOLDBOT_AUTO_CONFIRM_BELOW`（默认 0，即关闭）：accept-edits 模式下数值风险低于它的
+/// Confirm 命令直接执行。
+pub(crate) fn auto_confirm_below_from_env() -> u8 {
+    std::env::var("GOLDBOT_AUTO_CONFIRM_BELOW")
+        .ok()
+        .and_then(|v| v.trim().parse::<u8>().ok())
+        .unwrap_or(0)
+}
+
+/// Confirm 级命令是否免确认：Yolo 全部放行；agent 模式在直接应用文件编辑时（即
+/// accept-edits，未开启 `GOLDBOT_CONFIRM_EDITS`）放行低于阈值的命令；plan 模式总是询问。
+fn auto_confirms(
+    assist_mode: AssistMode,
+    accepts_edits: bool,
+    severity: u8,
+    threshold: u8,
+) -> bool {
+    match assist_mode {
+        AssistMode::Yolo => true,
+        AssistMode::Off => {
+            accepts_edits
+                && RiskLevel::Confirm.severity_band().contains(&severity)
+                && severity < threshold
+        }
+        AssistMode::Plan => false,
+    }
+}
+
+// ── 确认超时 ──────────────────────────────────────────────────────────────────
+
+/// `GOLDBOT_CONFIRM_TIMEOUT_SECS`（默认关闭）开启确认超时；
+/// `GOLDBOT_CONFIRM_TIMEOUT_ACTION` 取 `skip`（默认）或 `abort`，其它值一律按 skip 处理。
+pub(crate) fn confirm_timeout_from_env() -> Option<(Duration, ConfirmTimeoutAction)> {
+    let secs = std::env::var("GOLDBOT_CONFIRM_TIMEOUT_SECS")
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|secs| *secs > 0)?;
+    let action = match std::env::var("GOLDBOT_CONFIRM_TIMEOUT_ACTION")
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "abort" => ConfirmTimeoutAction::Abort,
+        _ => ConfirmTimeoutAction::Skip,
+    };
+    Some((Duration::from_secs(secs), action))
+}
+
+/// 跳过待确认命令，并把结果回传给 LLM。
+pub(crate) fn skip_pending_confirm(app: &mut App, screen: &mut Screen, timed_out: bool) {
+    screen.confirm_selected = None;
+    screen.input_focused = true;
+    app.pending_confirm_note = false;
+    app.pending_confirm_started_at = None;
+    let Some(cmd) = app.pending_confirm.take() else {
+        screen.refresh();
+        return;
+    };
+    let msg = if timed_out {
+        format!("Confirmation timed out, so this command was skipped: {cmd}")
+    } else {
+        format!("User chose to skip this command: {cmd}")
+    };
+    push_tool_result_to_llm(app, "Tool result:", &msg);
+    let ev = Event::ToolResult {
+        exit_code: 0,
+        output: msg,
+    };
+    emit_live_event(screen, &ev);
+    app.task_events.push(ev);
+    app.needs_agent_executor = true;
+}
+
+/// 放弃待确认命令并结束任务。
+pub(crate) fn abort_pending_confirm(app: &mut App, screen: &mut Screen, summary: String) {
+    screen.confirm_selected = None;
+    app.pending_confirm_note = false;
+    app.pending_confirm = None;
+    app.pending_confirm_started_at = None;
+    finish(app, screen, summary);
+}
+
+/// 主循环每个 tick 调用：刷新倒计时，超时后按配置自动 Skip / Abort。
+pub(crate) fn tick_confirm_timeout(app: &mut App, screen: &mut Screen) {
+    apply_confirm_timeout(app, screen, std::time::Instant::now());
+}
+
+fn apply_confirm_timeout(app: &mut App, screen: &mut Screen, now: std::time::Instant) -> bool {
+    let (Some((timeout, action)), Some(started)) =
+        (app.confirm_timeout, app.pending_confirm_started_at)
+    else {
+        return false;
+    };
+    if app.pending_confirm.is_none() || app.pending_confirm_note {
+        // 用户正在输入补充说明：说明人在场，重新计时
+        app.pending_confirm_started_at = app.pending_confirm.as_ref().map(|_| now);
+        screen.confirm_countdown = None;
+        return false;
+    }
+
+    let elapsed = now.saturating_duration_since(started);
+    if elapsed < timeout {
+        let left = (timeout - elapsed).as_secs_f64().ceil() as u64;
+        if screen.confirm_countdown != Some((left, action.label())) {
+            screen.confirm_countdown = Some((left, action.label()));
+            screen.refresh();
+        }
+        return false;
+    }
+
+    screen.confirm_countdown = None;
+    let ev = Event::Thinking {
+        text: format!(
+            "Confirmation timed out after {}s; auto-selected {}",
+            timeout.as_secs(),
+            action.label()
+        ),
+    };
+    emit_live_event(screen, &ev);
+    app.task_events.push(ev);
+    match action {
+        ConfirmTimeoutAction::Skip => skip_pending_confirm(app, screen, true),
+        ConfirmTimeoutAction::Abort => abort_pending_confirm(
+            app,
+            screen,
+            "Task aborted: confirmation timed out".to_string(),
+        ),
+    }
+    true
+}
+
+// ── 任务时长预算 ──────────────────────────────────────────────────────────────
+
+/// `GOLDBOT_TASK_TIME_BUDGET_SECS`（默认关闭）设置单个任务的时长预算；
+/// `GOLDBOT_TASK_TIME_HARD=1` 时超出预算会在当前步骤结束后自动结束任务，否则只提示。
+pub(crate) fn task_time_budget_from_env() -> Option<(Duration, bool)> {
+    let secs = std::env::var("GOLDBOT_TASK_TIME_BUDGET_SECS")
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|secs| *secs > 0)?;
+    let hard = matches!(
+        std::env::var("GOLDBOT_TASK_TIME_HARD")
+            .unwrap_or_default()
+            .trim(),
+        "1" | "true" | "yes" | "on"
+    );
+    Some((Duration::from_secs(secs), hard))
+}
+
+/// 主循环每个 tick 调用：超出预算时提示一次（硬限制在步骤之间生效）。
+pub(crate) fn tick_task_time_budget(app: &mut App, screen: &mut Screen) {
+    apply_task_time_budget(app, screen, std::time::Instant::now(), false);
+}
+
+/// 检查任务时长预算。`between_steps` 为 true（即将发起下一次 LLM 调用）且开启硬限制时结束任务，
+/// 不会打断正在执行的命令。返回 true 表示任务已被结束。
+fn apply_task_time_budget(
+    app: &mut App,
+    screen: &mut Screen,
+    now: std::time::Instant,
+    between_steps: bool,
+) -> bool {
+    let (Some((budget, hard)), Some(started)) = (app.task_time_budget, app.task_started_at) else {
+        return false;
+    };
+    if !app.running {
+        return false;
+    }
+    let elapsed = now.saturating_duration_since(started);
+    if elapsed < budget {
+        return false;
+    }
+    if hard && between_steps {
+        let ev = Event::Thinking {
+            text: format!(
+                "Time budget exceeded after {}; stopping before the next step",
+                format_elapsed_short(elapsed)
+            ),
+        };
+        emit_live_event(screen, &ev);
+        app.task_events.push(ev);
+        finish(
+            app,
+            screen,
+            format!(
+                "Task stopped: time budget exceeded ({}s limit, ran {})",
+                budget.as_secs(),
+                format_elapsed_short(elapsed)
+            ),
+        );
+        return true;
+    }
+    if !app.task_time_warned {
+        app.task_time_warned = true;
+        let note = if hard {
+            "will stop after the current step"
+        } else {
+            "still running"
+        };
+        let ev = Event::Thinking {
+            text: format!(
+                "⚠ Task time budget of {}s exceeded ({note})",
+                budget.as_secs()
+            ),
+        };
+        emit_live_event(screen, &ev);
+        app.task_events.push(ev);
+        screen.status = format!("⚠ Time budget {}s exceeded", budget.as_secs())
+            .dark_yellow()
+            .to_string();
+        screen.refresh();
+    }
+    false
+}
+
+/// 是否在自动 compact 前暂停并请求确认（`GOLDBOT_COMPACT_CONFIRM=1`）。
+fn compact_confirm_enabled() -> bool {
+    matches!(
+        std::env::var("GOLDBOT_COMPACT_CONFIRM")
+            .unwrap_or_default()
+            .trim(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// 把 assistant 消息中的 `<memory>` 笔记写入长期记忆，返回新写入的条数。
+fn flush_memory_notes(store: &ProjectStore, messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .filter(|m| m.role == crate::agent::provider::Role::Assistant)
+        .filter_map(|m| parse_llm_response(&m.content).ok())
+        .flat_map(|(_, actions)| actions)
+        .filter(|action| match action {
+            LlmAction::Memory { note } => store.append_memory(note).unwrap_or(false),
+            _ => false,
+        })
+        .count()
+}
+
+/// 执行 compact 的核心逻辑，返回 (summary, messages_dropped)
+async fn do_compact(app: &mut App, screen: &mut Screen) -> Option<(String, usize)> {
+    let (prefix_end, chosen_split_at, max_split_at) = compaction_split_points(&app.messages)?;
 
     // 在确定 split 点后，用 LLM 生成摘要（同步等待）
     let older = app.messages[prefix_end..chosen_split_at].to_vec();
@@ -1580,23 +2950,20 @@ async fn do_compact(app: &mut App, screen: &mut Screen) -> Option<(String, usize
         Some(client) => llm_summarize_for_compaction(&older, client, &app.backend).await,
         None => summarize_for_compaction_fallback(&older),
     };
+    let summary = with_failed_tool_results(summary, &older);
 
-    let budget = current_context_budget(app);
-    let compacted = loop {
-        let mut candidate = app.messages[..prefix_end].to_vec();
-        candidate.push(Message::user(format!("[Context compacted]\n{summary}")));
-        candidate.extend_from_slice(&app.messages[chosen_split_at..]);
+    let (compacted, chosen_split_at) =
+        build_compacted_messages(app, &summary, prefix_end, chosen_split_at, max_split_at);
 
-        let candidate_tokens = estimate_prompt_tokens(&candidate, app.prompt_token_scale);
-        if candidate_tokens.saturating_add(budget.compact_reserve_tokens)
-            <= budget.context_window_tokens
-            || chosen_split_at >= max_split_at
-        {
-            break candidate;
-        }
-        chosen_split_at += 1;
+    // 折叠前把被丢弃消息里的 <memory> 笔记补写入长期记忆（已存在的会被去重）
+    let notes_flushed = if app.no_memory {
+        0
+    } else {
+        flush_memory_notes(
+            &ProjectStore::current(),
+            &app.messages[prefix_end..chosen_split_at],
+        )
     };
-
     let messages_dropped = chosen_split_at - prefix_end;
     app.messages = compacted;
     sync_context_budget(app, screen);
@@ -1604,6 +2971,7 @@ async fn do_compact(app: &mut App, screen: &mut Screen) -> Option<(String, usize
     let ev = Event::ConversationCompacted {
         summary: summary.clone(),
         messages_dropped,
+        notes_flushed,
     };
     emit_live_event(screen, &ev);
     app.task_events.push(ev);
@@ -1619,12 +2987,115 @@ async fn do_compact(app: &mut App, screen: &mut Screen) -> Option<(String, usize
     Some((summary, messages_dropped))
 }
 
-pub(crate) async fn maybe_flush_and_compact_before_call(app: &mut App, screen: &mut Screen) {
+/// 调用前按预算自动 compact。返回 false 表示正在等待用户确认，本轮不应发起调用。
+pub(crate) async fn maybe_flush_and_compact_before_call(
+    app: &mut App,
+    screen: &mut Screen,
+) -> bool {
     let budget = current_context_budget(app);
     if !budget.should_compact {
-        return;
+        return true;
+    }
+    if app.skip_next_auto_compact {
+        app.skip_next_auto_compact = false;
+        return true;
+    }
+    if compact_confirm_enabled() {
+        if let Some(preview) = preview_compaction(app) {
+            crate::ui::input::open_compact_confirm(app, screen, &preview, CompactConfirm::Auto);
+            return false;
+        }
+        return true;
     }
     do_compact(app, screen).await;
+    true
+}
+
+/// 估算 prompt 超过阈值时返回估算的 token 数；未配置阈值时总是 None。
+fn prompt_over_warn_threshold(
+    messages: &[Message],
+    scale: f32,
+    threshold: Option<u32>,
+) -> Option<u32> {
+    let threshold = threshold?;
+    let estimated = estimate_prompt_tokens(messages, scale);
+    (estimated > threshold).then_some(estimated)
+}
+
+fn prompt_warn_tokens() -> Option<u32> {
+    std::env::var(ENV_PROMPT_WARN_TOKENS)
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .filter(|&n| n > 0)
+}
+
+/// 当前后端的输入单价（美元 / 百万 token）。
+fn input_price_per_mtok(backend_label: &str) -> f64 {
+    std::env::var(ENV_PROMPT_PRICE_PER_MTOK)
+        .ok()
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|p| *p >= 0.0)
+        .or_else(|| {
+            INPUT_PRICE_PER_MTOK
+                .iter()
+                .find(|(label, _)| *label == backend_label)
+                .map(|&(_, price)| price)
+        })
+        .unwrap_or(0.0)
+}
+
+/// 调用前检查 prompt 体积。返回 false 表示正在等待 发送/裁剪/取消 确认，本轮不应发起调用。
+fn maybe_confirm_large_prompt(app: &mut App, screen: &mut Screen) -> bool {
+    if app.prompt_warn_accepted {
+        return true;
+    }
+    let Some(tokens) =
+        prompt_over_warn_threshold(&app.messages, app.prompt_token_scale, prompt_warn_tokens())
+    else {
+        return true;
+    };
+    let cost = tokens as f64 / 1_000_000.0 * input_price_per_mtok(app.backend.backend_label());
+    screen.emit(&[format!(
+        "  {}",
+        t_with(
+            "prompt_warn.prompt",
+            &[
+                ("tokens", &tokens.to_string()),
+                ("cost", &format!("{cost:.3}")),
+            ],
+        )
+    )
+    .dark_yellow()
+    .to_string()]);
+    screen.question_labels = vec![
+        t("prompt_warn.option_send").to_string(),
+        t("prompt_warn.option_trim").to_string(),
+        t("prompt_warn.option_cancel").to_string(),
+    ];
+    screen.confirm_selected = Some(0);
+    screen.input_focused = false;
+    app.pending_prompt_warn = Some(tokens);
+    screen.refresh();
+    false
+}
+
+/// 大 prompt 确认菜单：0 发送（本任务不再询问）、1 先压缩上下文再重新检查、其余取消任务。
+pub(crate) fn resolve_prompt_warn(app: &mut App, screen: &mut Screen, choice: usize) {
+    if app.pending_prompt_warn.take().is_none() {
+        return;
+    }
+    screen.confirm_selected = None;
+    screen.question_labels.clear();
+    screen.input_focused = true;
+    match choice {
+        0 => app.prompt_warn_accepted = true,
+        1 => app.pending_manual_compact = true,
+        _ => {
+            finish(app, screen, t("prompt_warn.cancelled").to_string());
+            return;
+        }
+    }
+    screen.refresh();
 }
 
 /// 用户手动触发 compact（/compact 命令）
@@ -1697,39 +3168,17 @@ async fn llm_summarize_for_compaction(
 
 // ── LLM 重试策略 ──────────────────────────────────────────────────────────────
 
-pub(crate) fn parse_retryable_http_status(message: &str) -> Option<u16> {
-    let marker = "API error ";
-    let start = message.find(marker)? + marker.len();
-    let digits: String = message[start..]
-        .chars()
-        .take_while(|c| c.is_ascii_digit())
-        .collect();
-    if digits.len() != 3 {
-        return None;
-    }
-    digits.parse().ok()
+/// 已开始流式输出后不再重试，避免重复内容；其余按错误类型判断。
+pub(crate) fn should_retry_llm_error(err: &ProviderError, streamed_any: bool) -> bool {
+    !streamed_any && err.is_retryable()
 }
 
-pub(crate) fn should_retry_llm_error(message: &str, streamed_any: bool) -> bool {
-    if streamed_any {
-        return false;
-    }
-    if matches!(parse_retryable_http_status(message), Some(status) if (500..600).contains(&status))
-    {
-        return true;
+/// 限流时优先遵循服务端的 `Retry-After`，否则按指数退避。
+pub(crate) fn retry_delay_for_error(err: &ProviderError, retry_attempt: usize) -> Duration {
+    match err.retry_after() {
+        Some(after) => after.min(Duration::from_secs(LLM_MAX_RETRY_AFTER_SECS)),
+        None => retry_delay_for_attempt(retry_attempt),
     }
-    [
-        "HTTP request failed",
-        "failed reading stream chunk",
-        "error sending request for url",
-        "connection error",
-        "connection reset",
-        "connection closed",
-        "timed out",
-        "dns error",
-    ]
-    .iter()
-    .any(|needle| message.contains(needle))
 }
 
 // ── LLM Worker ────────────────────────────────────────────────────────────────
@@ -1737,7 +3186,7 @@ pub(crate) fn should_retry_llm_error(message: &str, streamed_any: bool) -> bool
 pub(crate) enum LlmWorkerEvent {
     Delta(String),
     ThinkingDelta(String),
-    Done(anyhow::Result<(String, crate::agent::provider::Usage)>),
+    Done(Result<(String, Usage), ProviderError>),
 }
 
 pub(crate) async fn maybe_spawn_llm_worker(
@@ -1748,6 +3197,9 @@ pub(crate) async fn maybe_spawn_llm_worker(
 ) -> Option<tokio::task::JoinHandle<()>> {
     if !(app.running
         && app.pending_confirm.is_none()
+        && app.pending_compact_confirm.is_none()
+        && app.pending_prompt_warn.is_none()
+        && app.pending_mcp_rollback.is_none()
         && app.needs_agent_executor
         && !app.llm_calling
         && !app.shell_task_running)
@@ -1755,13 +3207,47 @@ pub(crate) async fn maybe_spawn_llm_worker(
         return None;
     }
 
+    // 暂停只挡住下一步；上一次调用结束后状态行保持暂停提示
+    if app.paused {
+        let status = pause_status(app);
+        if screen.status != status {
+            screen.status = status;
+            screen.refresh_status_only();
+        }
+        return None;
+    }
+
+    // 时长预算的硬限制只在步骤之间生效
+    if apply_task_time_budget(app, screen, std::time::Instant::now(), true) {
+        return None;
+    }
+
+    // Ctrl+B 在运行中选中的后端，在发起下一次调用前切换
+    if app.pending_backend_switch.is_some() {
+        crate::ui::input::apply_pending_backend_switch(app, screen);
+        if !app.running {
+            return None;
+        }
+    }
+
     // 在 compact 之前写入长期记忆
-    maybe_flush_and_compact_before_call(app, screen).await;
+    if !maybe_flush_and_compact_before_call(app, screen).await {
+        return None;
+    }
+    if !maybe_confirm_large_prompt(app, screen) {
+        return None;
+    }
     app.needs_agent_executor = false;
     app.llm_calling = true;
     app.llm_call_started_at = Some(std::time::Instant::now());
     app.llm_stream_preview.clear();
     app.llm_preview_shown.clear();
+    // 额度快用完时先等一会儿再发起调用，而不是等到 429 才退避
+    let pace = app
+        .rate_limits
+        .as_ref()
+        .and_then(|limits| limits.pace_delay(pace_threshold()));
+    app.rate_limit_pace_until = pace.map(|delay| std::time::Instant::now() + delay);
     refresh_llm_status(app, screen);
 
     let tx_done = tx.clone();
@@ -1772,6 +3258,9 @@ pub(crate) async fn maybe_spawn_llm_worker(
     let backend = app.backend.clone();
 
     Some(tokio::spawn(async move {
+        if let Some(delay) = pace {
+            tokio::time::sleep(delay).await;
+        }
         let mut retry_index = 0usize;
         let result = loop {
             let streamed_any = Arc::new(AtomicBool::new(false));
@@ -1797,13 +3286,14 @@ pub(crate) async fn maybe_spawn_llm_worker(
                 Ok(response) => break Ok(response),
                 Err(err)
                     if retry_index < LLM_MAX_RETRIES
-                        && should_retry_llm_error(
-                            &err.to_string(),
-                            streamed_any.load(Ordering::Relaxed),
-                        ) =>
+                        && should_retry_llm_error(&err, streamed_any.load(Ordering::Relaxed)) =>
                 {
+                    // 主循环已不再接收结果（如正在退出），不必继续重试
+                    if tx_done.is_closed() {
+                        break Err(ProviderError::Cancelled);
+                    }
                     retry_index += 1;
-                    tokio::time::sleep(retry_delay_for_attempt(retry_index)).await;
+                    tokio::time::sleep(retry_delay_for_error(&err, retry_index)).await;
                 }
                 Err(err) => break Err(err),
             }
@@ -1819,6 +3309,7 @@ pub(crate) fn retry_delay_for_attempt(retry_attempt: usize) -> Duration {
 
 /// 本地降级摘要：提取 <final>、<phase>、user 任务等关键信息。
 fn summarize_for_compaction_fallback(messages: &[Message]) -> String {
+    let final_tags = final_tags();
     let mut items = Vec::new();
     for msg in messages.iter().rev() {
         match msg.role {
@@ -1835,7 +3326,7 @@ fn summarize_for_compaction_fallback(messages: &[Message]) -> String {
                 items.push(format!("- user: {}", shorten_text(&one_line, 120)));
             }
             crate::agent::provider::Role::Assistant => {
-                if let Some(final_text) = extract_last_tag_text(&msg.content, "final") {
+                if let Some(final_text) = extract_final_text(&msg.content, &final_tags) {
                     let one_line = final_text.split_whitespace().collect::<Vec<_>>().join(" ");
                     items.push(format!("- final: {}", shorten_text(&one_line, 120)));
                 }
@@ -1850,13 +3341,59 @@ fn summarize_for_compaction_fallback(messages: &[Message]) -> String {
     items.join("\n")
 }
 
-fn extract_last_tag_text(text: &str, tag: &str) -> Option<String> {
-    let open = format!("<{tag}>");
-    let close = format!("</{tag}>");
-    let end = text.rfind(&close)?;
-    let head = &text[..end];
-    let start = head.rfind(&open)? + open.len();
-    Some(head[start..].trim().to_string())
+/// 在摘要后附上被折叠区间内最近的失败工具结果；成功的输出照常丢弃。
+fn with_failed_tool_results(summary: String, folded: &[Message]) -> String {
+    let mut kept: Vec<String> = folded
+        .iter()
+        .rev()
+        .filter(|m| m.role == crate::agent::provider::Role::User)
+        .filter_map(|m| failed_tool_result(&m.content))
+        .take(MAX_COMPACTION_FAILED_RESULTS)
+        .collect();
+    if kept.is_empty() {
+        return summary;
+    }
+    kept.reverse();
+    let block = format!(
+        "Failed tool results kept from compacted history (oldest first):\n{}",
+        kept.join("\n")
+    );
+    if summary.trim().is_empty() {
+        block
+    } else {
+        format!("{summary}\n\n{block}")
+    }
+}
+
+/// `Tool result (exit=N):` 且 N≠0 的消息，返回带标签的输出结尾。
+fn failed_tool_result(content: &str) -> Option<String> {
+    let (header, body) = content.split_once('\n').unwrap_or((content, ""));
+    let exit_code = header
+        .strip_prefix("Tool result (exit=")?
+        .strip_suffix("):")?
+        .trim()
+        .parse::<i32>()
+        .ok()?;
+    if exit_code == 0 {
+        return None;
+    }
+    let body = body.trim();
+    let tail = truncate_utf8_suffix(body, COMPACTION_FAILED_RESULT_BYTES);
+    let marker = if tail.len() < body.len() { "…" } else { "" };
+    Some(format!(
+        "[failed tool result, exit={exit_code}]\n{marker}{tail}"
+    ))
+}
+
+fn truncate_utf8_suffix(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let mut cut = s.len() - max_bytes;
+    while cut < s.len() && !s.is_char_boundary(cut) {
+        cut += 1;
+    }
+    &s[cut..]
 }
 
 fn truncate_utf8_prefix(s: &str, max_bytes: usize) -> &str {
@@ -1973,6 +3510,35 @@ pub(crate) fn should_run_pending_manual_compact(app: &App) -> bool {
         && !app.dag_task_running
 }
 
+/// 运行中排队的输入按 FIFO 逐条作为插话发送：每次只取一条，等当前步骤
+/// （LLM 调用、命令执行、确认）结束后再取下一条。任务结束时队列非空会保持运行，
+/// 于是剩下的输入接着作为下一轮发送。
+pub(crate) fn consume_queued_message(app: &mut App, screen: &mut Screen) -> bool {
+    if !app.running
+        || app.pending_confirm.is_some()
+        || app.llm_calling
+        || app.shell_task_running
+        || app.message_queue.is_empty()
+        || app.needs_agent_executor
+    {
+        return false;
+    }
+    if let Some(msg) = app.dequeue_message(screen) {
+        let wrapped = crate::agent::react::build_interjection_user_message(&msg, None);
+        let images = std::mem::take(&mut app.pending_images);
+        app.messages
+            .push(Message::user(wrapped).with_images(images));
+        let ev = Event::UserTask { text: msg };
+        emit_live_event(screen, &ev);
+        app.task_events.push(ev);
+    }
+    sync_context_budget(app, screen);
+    app.needs_agent_executor = true;
+    screen.status = "Interjection sent. Continuing...".to_string();
+    screen.refresh();
+    true
+}
+
 pub(crate) fn poll_shell_exec_result(app: &mut App, screen: &mut Screen) {
     let Some(rx) = app.shell_exec_rx.as_mut() else {
         return;
@@ -1984,6 +3550,8 @@ pub(crate) fn poll_shell_exec_result(app: &mut App, screen: &mut Screen) {
             app.shell_task_running = false;
             clear_running_shell_cancel_request();
             handle_shell_exec_result(app, screen, result);
+            // 命令可能切换了分支（`git checkout -b`、`git switch` 或调用 git 的脚本）
+            sync_git_branch(app, screen, true);
         }
         Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => {
             app.shell_exec_rx = None;
@@ -2001,6 +3569,27 @@ pub(crate) fn poll_shell_exec_result(app: &mut App, screen: &mut Screen) {
     }
 }
 
+/// 刷新当前 git 分支：显示在模式提示行，并交给强制推送保护。`force` 为 false 时
+/// 只在距上次查询超过 `GIT_BRANCH_REFRESH_INTERVAL` 后才查询。
+pub(crate) fn sync_git_branch(app: &mut App, screen: &mut Screen, force: bool) {
+    let now = std::time::Instant::now();
+    if !force
+        && app
+            .git_branch_checked_at
+            .is_some_and(|at| now.duration_since(at) < GIT_BRANCH_REFRESH_INTERVAL)
+    {
+        return;
+    }
+    app.git_branch_checked_at = Some(now);
+    let branch = current_git_branch(&app.workspace);
+    if branch == screen.git_branch {
+        return;
+    }
+    set_current_branch(branch.clone());
+    screen.git_branch = branch;
+    screen.refresh();
+}
+
 pub(crate) fn interrupt_active_llm_loop(
     app: &mut App,
     screen: &mut Screen,
@@ -2020,6 +3609,7 @@ pub(crate) fn interrupt_active_llm_loop(
         app.dag_output_nodes.clear();
         screen.dag_tree = None;
     }
+    crate::agent::delegate::cancel_external_task(app);
     app.interrupt_llm_loop_requested = false;
     app.running = false;
     app.needs_agent_executor = false;
@@ -2027,6 +3617,7 @@ pub(crate) fn interrupt_active_llm_loop(
     app.llm_call_started_at = None;
     app.llm_stream_preview.clear();
     app.llm_preview_shown.clear();
+    screen.final_preview.clear();
     screen.status.clear();
     if let Some(handle) = llm_task_handle.take() {
         handle.abort();
@@ -2034,6 +3625,76 @@ pub(crate) fn interrupt_active_llm_loop(
     screen.refresh();
 }
 
+// ── Ctrl+C 退出 ───────────────────────────────────────────────────────────────
+
+/// 运行中第一次 Ctrl+C 后，在此窗口内再按一次则立即退出，不再保存。
+const FORCE_QUIT_WINDOW: Duration = Duration::from_millis(1500);
+/// 优雅退出时等待被取消的命令收尾的最长时间。
+const GRACEFUL_QUIT_SHELL_WAIT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CtrlCAction {
+    /// 空闲时直接退出。
+    Quit,
+    /// 任务运行中：取消当前调用，保存记忆与会话后退出。
+    GracefulStop,
+    /// 优雅退出期间再次按下：立即退出。
+    ForceQuit,
+}
+
+pub(crate) fn ctrl_c_action(
+    running: bool,
+    graceful_since: Option<std::time::Instant>,
+    now: std::time::Instant,
+) -> CtrlCAction {
+    match graceful_since {
+        Some(since) if now.saturating_duration_since(since) <= FORCE_QUIT_WINDOW => {
+            CtrlCAction::ForceQuit
+        }
+        Some(_) => CtrlCAction::GracefulStop,
+        None if running => CtrlCAction::GracefulStop,
+        None => CtrlCAction::Quit,
+    }
+}
+
+/// 运行中的 Ctrl+C：请求主循环中止 LLM 调用 / 取消命令，然后等待保存。
+pub(crate) fn begin_graceful_quit(app: &mut App, screen: &mut Screen, now: std::time::Instant) {
+    app.graceful_quit_at = Some(now);
+    app.interrupt_llm_loop_requested = true;
+    screen.emit(&[format!(
+        "  {}",
+        "Saving session… (Ctrl+C again to quit immediately)".dark_yellow()
+    )]);
+}
+
+/// 被取消的命令已结束（或等待超时）时即可保存并退出。
+pub(crate) fn graceful_quit_ready(
+    app: &App,
+    since: std::time::Instant,
+    now: std::time::Instant,
+) -> bool {
+    !app.shell_task_running || now.saturating_duration_since(since) >= GRACEFUL_QUIT_SHELL_WAIT
+}
+
+/// 把中断任务里已能确定的长期笔记写入记忆，并在会话中记一条中断快照，便于之后恢复。
+pub(crate) fn save_interrupted_task(app: &mut App, screen: &mut Screen) {
+    if app.task.is_empty() {
+        return;
+    }
+    if !app.no_memory {
+        flush_memory_notes(&ProjectStore::current(), &app.messages);
+    }
+    let session_task = session_task_for_round(&app.task, &app.task_events).to_string();
+    let summary = format!(
+        "[Interrupted by Ctrl+C after {} steps; the task did not finish]",
+        app.steps_taken
+    );
+    // 马上就要退出，保存失败也要留下一行提示
+    if let Err(e) = Session::current().append_to_session(&session_task, &summary) {
+        screen.emit(&[crate::ui::format::save_warning_line(&e)]);
+    }
+}
+
 pub(crate) async fn shutdown_background_work(
     app: &mut App,
     screen: &mut Screen,
@@ -2048,6 +3709,7 @@ pub(crate) async fn shutdown_background_work(
         }
     }
 
+    crate::agent::delegate::cancel_external_task(app);
     if let Some(handle) = llm_task_handle.take() {
         handle.abort();
     }
@@ -2056,12 +3718,166 @@ pub(crate) async fn shutdown_background_work(
 #[cfg(test)]
 mod tests {
     use super::{
-        COMPLETION_RESERVE_MULTIPLIER, MIN_COMPACT_RESERVE_TOKENS,
-        dynamic_compact_reserve_tokens, estimate_prompt_tokens_raw, format_token_count_short,
-        session_task_for_round, truncate_utf8_prefix,
+        COMPLETION_RESERVE_MULTIPLIER, CtrlCAction, DEFAULT_PARSE_RETRY_LIMIT,
+        DEFAULT_PARSE_RETRY_PROMPT, FORCE_QUIT_WINDOW, LLM_PREVIEW_MAX_CHARS,
+        LLM_PREVIEW_MAX_WIDTH, LLM_STREAM_BUFFER_MAX_BYTES, LivePreview,
+        MIN_COMPACT_RESERVE_TOKENS, OBSERVE_NOTE, apply_confirm_timeout, apply_task_time_budget,
+        build_parse_correction, consume_queued_message, context_breakdown, context_report_lines,
+        ctrl_c_action, deferred_final_note, dynamic_compact_reserve_tokens,
+        estimate_prompt_tokens_raw, execute_read_file, execute_write_file, extract_live_preview,
+        flush_memory_notes, format_token_count_short, process_llm_result,
+        prompt_over_warn_threshold, session_task_for_round, truncate_utf8_prefix,
+        with_failed_tool_results,
     };
-    use crate::agent::provider::Message;
-    use crate::types::{Event, TodoItem, TodoStatus};
+    use crate::App;
+    use crate::agent::provider::{Message, Usage};
+    use crate::types::{ConfirmTimeoutAction, Event, TodoItem, TodoStatus};
+    use crate::ui::screen::Screen;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn queued_inputs_drain_in_order_once_the_step_completes() {
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.running = true;
+        app.llm_calling = true;
+        for text in ["first", "second", "third"] {
+            screen.input = text.to_string();
+            screen.input_cursor = text.len();
+            crate::ui::input::handle_key(&mut app, &mut screen, KeyCode::Enter, KeyModifiers::NONE);
+        }
+        assert_eq!(screen.message_queue_labels, ["first", "second", "third"]);
+        assert!(screen.input.is_empty());
+
+        // 当前步骤未结束时不发送
+        assert!(!consume_queued_message(&mut app, &mut screen));
+        app.llm_calling = false;
+
+        let last_user = |app: &App| app.messages.last().map(|m| m.content.clone()).unwrap();
+        assert!(consume_queued_message(&mut app, &mut screen));
+        assert!(last_user(&app).contains("first"));
+        // 一次只发一条，等这一轮 LLM 调用结束再取下一条
+        assert!(!consume_queued_message(&mut app, &mut screen));
+        app.needs_agent_executor = false;
+        assert!(consume_queued_message(&mut app, &mut screen));
+        assert!(last_user(&app).contains("second"));
+        assert_eq!(screen.message_queue_labels, ["third"]);
+
+        // Ctrl+X 清空剩余队列
+        crate::ui::input::handle_key(
+            &mut app,
+            &mut screen,
+            KeyCode::Char('x'),
+            KeyModifiers::CONTROL,
+        );
+        assert!(app.message_queue.is_empty());
+        assert!(screen.message_queue_labels.is_empty());
+    }
+
+    #[test]
+    fn confirm_timeout_auto_skips_and_never_executes() {
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        let started = Instant::now();
+        app.confirm_timeout = Some((Duration::from_secs(30), ConfirmTimeoutAction::Skip));
+        app.pending_confirm = Some("rm -rf target".to_string());
+        app.pending_confirm_started_at = Some(started);
+        screen.confirm_selected = Some(0);
+
+        // 窗口内：只更新倒计时
+        assert!(!apply_confirm_timeout(
+            &mut app,
+            &mut screen,
+            started + Duration::from_secs(10)
+        ));
+        assert_eq!(screen.confirm_countdown, Some((20, "Skip")));
+        assert!(app.pending_confirm.is_some());
+
+        // 超时：自动 Skip，记录超时并把结果回传给 LLM，而不是执行命令
+        assert!(apply_confirm_timeout(
+            &mut app,
+            &mut screen,
+            started + Duration::from_secs(31)
+        ));
+        assert!(app.pending_confirm.is_none());
+        assert!(app.needs_agent_executor);
+        assert!(!app.shell_task_running);
+        assert!(app.task_events.iter().any(|ev| matches!(
+            ev,
+            Event::Thinking { text } if text.contains("timed out after 30s; auto-selected Skip")
+        )));
+        let last = app.messages.last().expect("tool result message");
+        assert!(last.content.contains("Confirmation timed out"));
+    }
+
+    #[test]
+    fn time_budget_warns_once_and_hard_stops_only_between_steps() {
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        let started = Instant::now();
+        app.running = true;
+        app.task_started_at = Some(started);
+        app.task_time_budget = Some((Duration::from_secs(300), true));
+
+        // 预算内：什么都不做
+        let before = started + Duration::from_secs(299);
+        assert!(!apply_task_time_budget(&mut app, &mut screen, before, true));
+        assert!(!app.task_time_warned);
+
+        // 超出预算但命令仍在执行（tick 中）：只提示一次，不结束任务
+        let over = started + Duration::from_secs(301);
+        assert!(!apply_task_time_budget(&mut app, &mut screen, over, false));
+        assert!(!apply_task_time_budget(&mut app, &mut screen, over, false));
+        assert!(app.running && app.task_time_warned);
+        let warnings = app
+            .task_events
+            .iter()
+            .filter(|ev| matches!(ev, Event::Thinking { text } if text.contains("time budget")))
+            .count();
+        assert_eq!(warnings, 1);
+
+        // 步骤之间：硬限制结束任务
+        assert!(apply_task_time_budget(&mut app, &mut screen, over, true));
+        assert!(!app.running);
+        let summary = app.final_summary.as_deref().unwrap_or_default();
+        assert!(summary.contains("time budget exceeded"), "{summary}");
+
+        // 软限制：步骤之间也只提示
+        app.running = true;
+        app.task_started_at = Some(started);
+        app.task_time_warned = false;
+        app.task_time_budget = Some((Duration::from_secs(300), false));
+        assert!(!apply_task_time_budget(&mut app, &mut screen, over, true));
+        assert!(app.running && app.task_time_warned);
+    }
+
+    #[test]
+    fn second_ctrl_c_within_window_forces_quit() {
+        let t0 = Instant::now();
+        assert_eq!(ctrl_c_action(false, None, t0), CtrlCAction::Quit);
+        assert_eq!(ctrl_c_action(true, None, t0), CtrlCAction::GracefulStop);
+        let soon = t0 + FORCE_QUIT_WINDOW - Duration::from_millis(1);
+        assert_eq!(ctrl_c_action(false, Some(t0), soon), CtrlCAction::ForceQuit);
+        let late = t0 + FORCE_QUIT_WINDOW + Duration::from_millis(1);
+        assert_eq!(
+            ctrl_c_action(false, Some(t0), late),
+            CtrlCAction::GracefulStop
+        );
+
+        // 经由按键处理：第一次只请求优雅退出，紧接着第二次才真正退出
+        use crossterm::event::{KeyCode, KeyModifiers};
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.running = true;
+        let ctrl_c = |app: &mut App, screen: &mut Screen| {
+            crate::ui::input::handle_key(app, screen, KeyCode::Char('c'), KeyModifiers::CONTROL)
+        };
+        assert!(!ctrl_c(&mut app, &mut screen));
+        assert!(app.graceful_quit_at.is_some() && app.interrupt_llm_loop_requested);
+        assert!(ctrl_c(&mut app, &mut screen));
+    }
 
     #[test]
     fn parse_todo_json_roundtrip() {
@@ -2108,6 +3924,21 @@ mod tests {
         assert!(estimate_prompt_tokens_raw(&messages) > 16);
     }
 
+    #[test]
+    fn prompt_warn_threshold_triggers_only_for_large_prompts() {
+        let small = vec![Message::system("BASE"), Message::user("hello")];
+        let mut large = small.clone();
+        large.push(Message::user("x".repeat(40_000)));
+
+        // 未配置阈值时从不询问
+        assert_eq!(prompt_over_warn_threshold(&large, 1.0, None), None);
+        assert_eq!(prompt_over_warn_threshold(&small, 1.0, Some(5_000)), None);
+        let tokens = prompt_over_warn_threshold(&large, 1.0, Some(5_000)).expect("over threshold");
+        assert!(tokens > 10_000, "{tokens}");
+        // 按观测到的 token 比例缩放后再比较
+        assert_eq!(prompt_over_warn_threshold(&large, 0.5, Some(tokens)), None);
+    }
+
     #[test]
     fn format_token_count_short_uses_compact_suffixes() {
         assert_eq!(format_token_count_short(980), "980");
@@ -2157,4 +3988,500 @@ mod tests {
         }];
         assert_eq!(session_task_for_round("最初任务", &events), "最初任务");
     }
+
+    #[test]
+    fn deferred_final_note_recorded_only_when_tool_wins() {
+        let raw = "<tool>shell</tool><command>ls</command><final>done</final>";
+        let (_, actions) = crate::agent::react::parse_llm_response(raw).expect("parse");
+        match deferred_final_note(raw, &actions) {
+            Some(Event::Thinking { text }) => assert!(text.contains("Deferred <final>")),
+            other => panic!("expected deferred-final note, got {other:?}"),
+        }
+
+        let raw = "<final>done</final>";
+        let (_, actions) = crate::agent::react::parse_llm_response(raw).expect("parse");
+        assert!(deferred_final_note(raw, &actions).is_none());
+    }
+
+    #[test]
+    fn parse_correction_includes_error_detail() {
+        let msg = build_parse_correction(DEFAULT_PARSE_RETRY_PROMPT, "missing <tool> tag");
+        assert!(msg.starts_with("Your last response could not be parsed (missing <tool> tag)."));
+        assert!(msg.contains("<arguments>{}</arguments>"));
+
+        // 自定义模板没有 {error} 占位符时，错误详情追加在末尾
+        let msg = build_parse_correction("回复格式错误，请按协议重新输出。", "no action found");
+        assert_eq!(
+            msg,
+            "回复格式错误，请按协议重新输出。\nParse error: no action found"
+        );
+    }
+
+    #[test]
+    fn parse_retries_stop_after_limit() {
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.running = true;
+        let garbage = || Ok(("I think I should run ls".to_string(), Usage::default()));
+
+        for attempt in 1..=DEFAULT_PARSE_RETRY_LIMIT {
+            process_llm_result(&mut app, &mut screen, garbage());
+            assert!(app.running && app.needs_agent_executor);
+            assert_eq!(app.parse_failures, attempt);
+            let correction = &app.messages.last().unwrap().content;
+            assert!(correction.contains("could not be parsed ("), "{correction}");
+            app.needs_agent_executor = false;
+        }
+
+        process_llm_result(&mut app, &mut screen, garbage());
+        assert!(!app.running && !app.needs_agent_executor);
+        assert_eq!(app.parse_failures, 0);
+        assert!(matches!(
+            app.task_events.last(),
+            Some(Event::Thinking { text }) if text.contains("could not be parsed after 3 retries")
+        ));
+    }
+
+    #[test]
+    fn compaction_reports_folded_messages_and_flushed_notes() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-compact-test-{nanos}"));
+        std::fs::create_dir_all(ws.join(".goldbot")).unwrap();
+        let store = crate::memory::project::ProjectStore::new(&ws);
+        store.append_memory("用 pnpm 安装依赖").unwrap();
+
+        let folded = vec![
+            Message::user("装一下依赖".to_string()),
+            Message::assistant(
+                "<thought>记下来</thought><memory>用 pnpm 安装依赖</memory>\
+                 <memory>测试前先启动 redis</memory><tool>shell</tool><command>pnpm i</command>"
+                    .to_string(),
+            ),
+            Message::user("<memory>用户消息里的标签不算</memory>".to_string()),
+            Message::assistant("<memory>CI 用 Node 20</memory><final>done</final>".to_string()),
+        ];
+        // 已存在的笔记被去重，只统计新写入的
+        assert_eq!(flush_memory_notes(&store, &folded), 2);
+        assert_eq!(flush_memory_notes(&store, &folded), 0);
+
+        let ev = Event::ConversationCompacted {
+            summary: "装好了依赖".to_string(),
+            messages_dropped: folded.len(),
+            notes_flushed: 2,
+        };
+        let lines =
+            crate::ui::format::format_event_for_verbosity(&ev, crate::types::Verbosity::Quiet);
+        let banner = crate::ui::screen::strip_ansi(&lines[0]);
+        let expected =
+            crate::ui::i18n::t_with("compact.banner", &[("messages", "4"), ("notes", "2")]);
+        assert!(banner.ends_with(&expected), "{banner}");
+        let _ = std::fs::remove_dir_all(ws);
+    }
+
+    #[test]
+    fn compaction_keeps_failed_tool_results_and_drops_successful_ones() {
+        let long_error = format!("{}\nerror[E0308]: mismatched types", "note: ".repeat(400));
+        let folded = vec![
+            Message::user("修一下编译错误".to_string()),
+            Message::user("Tool result (exit=0):\nverbose build log line".to_string()),
+            Message::user(format!("Tool result (exit=101):\n{long_error}")),
+            Message::user("Tool result (exit=0):\nall tests passed".to_string()),
+        ];
+        let summary = with_failed_tool_results("- user: 修一下编译错误".to_string(), &folded);
+        assert!(summary.starts_with("- user: 修一下编译错误\n\n"));
+        assert!(summary.contains("Failed tool results kept from compacted history"));
+        assert!(summary.contains("[failed tool result, exit=101]\n…"));
+        assert!(summary.ends_with("error[E0308]: mismatched types"));
+        assert!(!summary.contains("verbose build log line"));
+        assert!(!summary.contains("all tests passed"));
+
+        // 没有失败结果时摘要保持不变
+        let ok_only = &folded[..2];
+        assert_eq!(with_failed_tool_results("s".to_string(), ok_only), "s");
+    }
+
+    #[test]
+    fn observe_mode_simulates_commands_without_running_them() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("goldbot-observe-test-{nanos}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        let victim = dir.join("keep.txt");
+        std::fs::write(&victim, "still here").unwrap();
+
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.observe = true;
+        app.running = true;
+        // rm 属于需确认的命令；观察模式下跳过确认，也不会派发到真实的 shell runner
+        // （真实路径依赖 tokio runtime，本测试没有 runtime）
+        let response = format!(
+            "<thought>clean up</thought><tool>shell</tool><command>rm {}</command>",
+            victim.display()
+        );
+        process_llm_result(&mut app, &mut screen, Ok((response, Usage::default())));
+
+        assert!(victim.exists());
+        assert!(app.pending_confirm.is_none() && screen.confirm_selected.is_none());
+        assert!(!app.shell_task_running && app.shell_exec_rx.is_none());
+        assert!(app.needs_agent_executor);
+        assert!(matches!(
+            app.task_events.last(),
+            Some(Event::ToolResult { exit_code: 0, output }) if output == OBSERVE_NOTE
+        ));
+        let fed_back = &app.messages.last().unwrap().content;
+        assert!(fed_back.starts_with("Tool result (exit=0):"), "{fed_back}");
+        assert!(fed_back.contains("simulated"), "{fed_back}");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn disabled_tool_is_rejected_at_execution() {
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.disabled_tools.insert("shell".to_string());
+        app.running = true;
+        let response = "<thought>list</thought><tool>shell</tool><command>ls</command>".to_string();
+        process_llm_result(&mut app, &mut screen, Ok((response, Usage::default())));
+
+        assert!(!app.shell_task_running && app.shell_exec_rx.is_none());
+        assert!(app.needs_agent_executor);
+        let fed_back = &app.messages.last().unwrap().content;
+        assert!(fed_back.starts_with("Tool result (exit=-1):"), "{fed_back}");
+        assert!(fed_back.contains("tool disabled: `shell`"), "{fed_back}");
+
+        // `mcp` 覆盖所有 MCP 工具
+        app.disabled_tools.insert("mcp".to_string());
+        let response = "<tool>mcp_github_search_issues</tool><arguments>{}</arguments>".to_string();
+        process_llm_result(&mut app, &mut screen, Ok((response, Usage::default())));
+        let fed_back = &app.messages.last().unwrap().content;
+        assert!(fed_back.contains("tool disabled: `mcp`"), "{fed_back}");
+    }
+
+    #[test]
+    fn raw_mode_emits_raw_response_alongside_parsed_events() {
+        let response = "<thought>look around</thought>\n<tool>shell</tool>\n<command>ls</command>";
+        for show_raw in [false, true] {
+            let mut app = App::new();
+            let mut screen = Screen::new_headless().expect("headless screen");
+            app.observe = true;
+            app.running = true;
+            app.show_raw = show_raw;
+            process_llm_result(
+                &mut app,
+                &mut screen,
+                Ok((response.into(), Usage::default())),
+            );
+
+            let raw_events: Vec<_> = app
+                .task_events
+                .iter()
+                .filter_map(|ev| match ev {
+                    Event::RawResponse { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            // 无头屏幕不保留输出，按实时视图的格式重放事件
+            let rendered: Vec<String> = app
+                .task_events
+                .iter()
+                .flat_map(crate::ui::format::format_event_live)
+                .map(|line| crate::ui::screen::strip_ansi(&line))
+                .collect();
+            // 解析后的工具调用照常渲染
+            assert!(matches!(
+                app.task_events.last(),
+                Some(Event::ToolResult { .. })
+            ));
+            assert!(rendered.iter().any(|l| l.contains("ls")));
+            if show_raw {
+                assert_eq!(raw_events, [response]);
+                assert!(matches!(app.task_events[0], Event::RawResponse { .. }));
+                for raw_line in response.lines() {
+                    assert!(
+                        rendered.iter().any(|l| l == &format!("    │ {raw_line}")),
+                        "{rendered:?}"
+                    );
+                }
+            } else {
+                assert!(raw_events.is_empty());
+                assert!(!rendered.iter().any(|l| l.contains("<tool>shell</tool>")));
+            }
+        }
+
+        // 超长响应在实时视图中截断，完整视图保留全部行
+        let long = (0..100)
+            .map(|i| format!("line {i}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let ev = Event::RawResponse { text: long };
+        let live = crate::ui::format::format_event_live(&ev);
+        assert!(
+            live.len() < 50 && crate::ui::screen::strip_ansi(live.last().unwrap()).contains("60")
+        );
+        assert_eq!(crate::ui::format::format_event(&ev).len(), 101);
+    }
+
+    #[test]
+    fn context_breakdown_counts_roles_and_tool_results() {
+        let messages = vec![
+            Message::system("You are GoldBot."),
+            Message::user("修复登录页的样式"),
+            Message::assistant("<tool>shell</tool><command>ls</command>"),
+            Message::user("Tool result (exit=0):\nsrc\nREADME.md"),
+            Message::assistant("<final>done</final>"),
+        ];
+        let breakdown = context_breakdown(&messages);
+
+        assert_eq!(breakdown.total, 5);
+        assert_eq!(breakdown.system_prompt_index, Some(0));
+        assert_eq!((breakdown.system.count, breakdown.system.chars), (1, 16));
+        assert_eq!((breakdown.user.count, breakdown.tool_results), (2, 1));
+        assert_eq!(breakdown.assistant.count, 2);
+        // 各角色 token 之和 + 3 与 compact 预算使用的估算一致
+        let role_tokens =
+            breakdown.system.tokens + breakdown.user.tokens + breakdown.assistant.tokens;
+        assert_eq!(role_tokens + 3, estimate_prompt_tokens_raw(&messages));
+
+        let mut app = App::new();
+        app.messages = messages;
+        let verbose = context_report_lines(&app, true);
+        assert!(
+            verbose
+                .iter()
+                .any(|l| l.contains("#3") && l.contains("Tool result"))
+        );
+        assert!(verbose.len() > context_report_lines(&app, false).len());
+    }
+
+    #[test]
+    fn live_preview_detects_final_open_tag_mid_stream() {
+        assert_eq!(
+            extract_live_preview("<thought>check the config</thought>\n<fin"),
+            LivePreview::Thought("check the config".to_string())
+        );
+        assert_eq!(
+            extract_live_preview("<thought>done</thought>\n<final>\n## Summary\n- fixed a < b"),
+            LivePreview::Final("## Summary\n- fixed a < b".to_string())
+        );
+        // 半截闭合标签与完整闭合标签都不进入正文
+        assert_eq!(
+            extract_live_preview("<final>All good.</fi"),
+            LivePreview::Final("All good.".to_string())
+        );
+        assert_eq!(
+            extract_live_preview("<final>All good.</final>"),
+            LivePreview::Final("All good.".to_string())
+        );
+        // <final> 之后又开始新的思考时回到思考预览
+        assert!(matches!(
+            extract_live_preview("<final>x</final><thought>more"),
+            LivePreview::Thought(_)
+        ));
+
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.llm_calling = true;
+        for delta in [
+            "<thought>plan</thought>",
+            "\n<final>",
+            "Updated README.md\n",
+        ] {
+            super::handle_llm_stream_delta(&mut app, &mut screen, delta);
+        }
+        assert_eq!(screen.final_preview, "Updated README.md\n");
+    }
+
+    #[test]
+    fn live_preview_stays_bounded_for_huge_nested_tag_streams() {
+        use unicode_width::UnicodeWidthStr;
+
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.llm_calling = true;
+        app.show_thinking = false;
+        // 约 1MB 的嵌套假标签，夹着 CJK 宽字符，按流式小块送入
+        let chunk = "<a<b<c>>思考中</c>>text<<x>>宽字符测试 <<<<".repeat(64);
+        let started = Instant::now();
+        let mut fed = 0;
+        while fed < 1024 * 1024 {
+            super::handle_llm_stream_delta(&mut app, &mut screen, &chunk);
+            fed += chunk.len();
+        }
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(app.llm_stream_preview.len() <= LLM_STREAM_BUFFER_MAX_BYTES);
+        assert!(!app.llm_preview_shown.is_empty());
+        assert!(app.llm_preview_shown.chars().count() <= LLM_PREVIEW_MAX_CHARS);
+        assert!(UnicodeWidthStr::width(app.llm_preview_shown.as_str()) <= LLM_PREVIEW_MAX_WIDTH);
+        assert!(UnicodeWidthStr::width(screen.status.as_str()) <= LLM_PREVIEW_MAX_WIDTH);
+    }
+
+    #[tokio::test]
+    async fn paused_app_does_not_spawn_next_llm_worker() {
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        let (tx, _rx) = tokio::sync::mpsc::channel(8);
+        let client = reqwest::Client::new();
+        app.messages.push(Message::user("list the files"));
+        app.running = true;
+        app.needs_agent_executor = true;
+        app.paused = true;
+
+        let handle = super::maybe_spawn_llm_worker(&mut app, &mut screen, &tx, &client).await;
+        assert!(handle.is_none());
+        // 下一步仍在等待，继续后从原处接着跑
+        assert!(app.needs_agent_executor && !app.llm_calling);
+        assert!(screen.status.contains("⏸"));
+
+        app.paused = false;
+        let handle = super::maybe_spawn_llm_worker(&mut app, &mut screen, &tx, &client).await;
+        assert!(app.llm_calling && !app.needs_agent_executor);
+        handle.expect("worker spawned").abort();
+    }
+
+    #[test]
+    fn mcp_verification_result_is_applied_when_the_worker_finishes() {
+        use crate::types::McpRollback;
+
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.running = true;
+        let target = McpRollback {
+            name: "slow".to_string(),
+            path: std::path::PathBuf::from("mcp_servers.json"),
+            previous: None,
+        };
+
+        // 校验还没结束：任务停在这一步，不进入下一轮 LLM 调用
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        app.mcp_verify_rx = Some(rx);
+        app.mcp_verify_target = Some(target.clone());
+        super::poll_mcp_verify(&mut app, &mut screen);
+        assert!(app.mcp_verify_rx.is_some() && !app.needs_agent_executor);
+
+        tx.send(Err(anyhow::anyhow!("npx: not found"))).unwrap();
+        super::poll_mcp_verify(&mut app, &mut screen);
+        assert!(app.mcp_verify_rx.is_none());
+        assert_eq!(app.pending_mcp_rollback.as_ref(), Some(&target));
+        let reported = &app.messages.last().unwrap().content;
+        assert!(reported.contains("npx: not found"));
+
+        app.pending_mcp_rollback = None;
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        app.mcp_verify_rx = Some(rx);
+        app.mcp_verify_target = Some(target);
+        tx.send(Ok(3)).unwrap();
+        super::poll_mcp_verify(&mut app, &mut screen);
+        assert!(app.needs_agent_executor && app.pending_mcp_rollback.is_none());
+        assert!(app.messages.last().unwrap().content.contains("3 tools"));
+    }
+
+    #[test]
+    fn tracked_git_branch_renders_in_status_and_follows_checkout() {
+        use crate::ui::screen::strip_ansi;
+
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-exec-branch-{nanos}"));
+        std::fs::create_dir_all(&ws).unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .current_dir(&ws)
+                .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {args:?}");
+        };
+
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.workspace = ws.clone();
+        // 不是 git 仓库时不显示分支
+        super::sync_git_branch(&mut app, &mut screen, true);
+        assert_eq!(screen.git_branch, None);
+
+        git(&["init", "-q"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&["checkout", "-q", "-b", "trunk"]);
+        super::sync_git_branch(&mut app, &mut screen, true);
+        assert!(strip_ansi(&screen.mode_hint_line()).contains("branch: trunk"));
+
+        // 模拟 agent 执行 `git checkout -b`：间隔内的定期刷新不查询，命令结束后的强制刷新立即更新
+        git(&["checkout", "-q", "-b", "feature/login"]);
+        super::sync_git_branch(&mut app, &mut screen, false);
+        assert_eq!(screen.git_branch.as_deref(), Some("trunk"));
+        super::sync_git_branch(&mut app, &mut screen, true);
+        let hint = strip_ansi(&screen.mode_hint_line());
+        assert!(hint.contains("branch: feature/login"), "{hint}");
+        assert!(!hint.contains("trunk"));
+
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+
+    #[test]
+    fn file_tools_refuse_goldbotignore_paths() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-exec-ignore-{nanos}"));
+        std::fs::create_dir_all(ws.join("secrets")).unwrap();
+        std::fs::write(ws.join("secrets/prod.key"), "hunter2\n").unwrap();
+        std::fs::write(ws.join(".goldbotignore"), "secrets/\n").unwrap();
+
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.workspace = ws.clone();
+
+        let last = |app: &App| app.messages.last().unwrap().content.clone();
+        execute_read_file(&mut app, &mut screen, "secrets/prod.key", None, None);
+        assert!(last(&app).contains("path is in .goldbotignore"));
+        assert!(!last(&app).contains("hunter2"));
+
+        execute_write_file(&mut app, &mut screen, "secrets/prod.key", "leaked");
+        assert!(last(&app).contains("path is in .goldbotignore"));
+        assert_eq!(
+            std::fs::read_to_string(ws.join("secrets/prod.key")).unwrap(),
+            "hunter2\n"
+        );
+        // Agent 不能改写 .goldbotignore 来解除限制
+        execute_write_file(&mut app, &mut screen, ".goldbotignore", "");
+        assert!(last(&app).contains("edited by the user"));
+        assert!(matches!(
+            app.task_events.last(),
+            Some(Event::ToolResult { exit_code: 1, .. })
+        ));
+
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+
+    #[test]
+    fn auto_confirm_threshold_depends_on_assist_mode() {
+        use super::auto_confirms;
+        use crate::tools::safety::assess_command_severity;
+        use crate::types::AssistMode;
+
+        let (_, mv, _) = assess_command_severity("mv notes.txt docs/notes.txt");
+        let (_, rm, _) = assess_command_severity("rm -rf build");
+
+        // accept-edits（agent 模式且编辑直接应用）：只放行低于阈值的命令
+        assert!(auto_confirms(AssistMode::Off, true, mv, 30));
+        assert!(!auto_confirms(AssistMode::Off, true, rm, 30));
+        // 等于阈值仍要确认；阈值为 0（默认）时全部确认
+        assert!(!auto_confirms(AssistMode::Off, true, mv, mv));
+        assert!(!auto_confirms(AssistMode::Off, true, mv, 0));
+        // 编辑也需确认（GOLDBOT_CONFIRM_EDITS）时不算 accept-edits
+        assert!(!auto_confirms(AssistMode::Off, false, mv, 30));
+        // Yolo 全部放行，plan 总是询问
+        assert!(auto_confirms(AssistMode::Yolo, true, rm, 0));
+        assert!(!auto_confirms(AssistMode::Plan, true, mv, 100));
+    }
 }
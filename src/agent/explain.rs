@@ -0,0 +1,205 @@
+//! `/explain`: ask the LLM to diagnose the last failed tool result. The request uses its own
+//! short prompt and the answer is only shown on screen, so the task conversation is untouched.
+
+use crossterm::style::Stylize;
+
+use crate::App;
+use crate::agent::provider::{Message, ProviderError};
+use crate::types::Event;
+use crate::ui::format::format_final_lines;
+use crate::ui::i18n::{t, t_with};
+use crate::ui::screen::Screen;
+use crate::ui::symbols::Symbols;
+
+/// Only the tail of long outputs is sent; the error is almost always at the end.
+const EXPLAIN_OUTPUT_MAX_CHARS: usize = 6000;
+
+const EXPLAIN_SYSTEM_PROMPT: &str = "\
+You diagnose a failed shell command for a developer.
+Reply in the user's language with:
+1. A short diagnosis of the most likely cause.
+2. A suggested fix (commands or code changes).
+Be concise (under 150 words). Plain text or simple markdown lists only; no XML tags.";
+
+/// The last failed tool result: (command, exit code, output). The command is taken from the
+/// closest preceding tool call, falling back to its label for tools without a command line.
+pub(crate) fn last_failed_tool_result(events: &[Event]) -> Option<(String, i32, String)> {
+    let idx = events
+        .iter()
+        .rposition(|ev| matches!(ev, Event::ToolResult { exit_code, .. } if *exit_code != 0))?;
+    let Event::ToolResult { exit_code, output } = &events[idx] else {
+        return None;
+    };
+    let command = events[..idx]
+        .iter()
+        .rev()
+        .find_map(|ev| match ev {
+            Event::ToolCall { label, command, .. } if command.trim().is_empty() => {
+                Some(label.clone())
+            }
+            Event::ToolCall { command, .. } => Some(command.clone()),
+            _ => None,
+        })
+        .unwrap_or_default();
+    Some((command, *exit_code, output.clone()))
+}
+
+/// The isolated diagnostic conversation for one failed command.
+pub(crate) fn explain_messages(command: &str, exit_code: i32, output: &str) -> Vec<Message> {
+    let output = output.trim_end();
+    let skip = output
+        .chars()
+        .count()
+        .saturating_sub(EXPLAIN_OUTPUT_MAX_CHARS);
+    let tail: String = output.chars().skip(skip).collect();
+    let truncated = if skip > 0 {
+        "[…earlier output omitted]\n"
+    } else {
+        ""
+    };
+    vec![
+        Message::system(EXPLAIN_SYSTEM_PROMPT),
+        Message::user(format!(
+            "Command:\n{}\n\nExit code: {exit_code}\n\nOutput:\n{truncated}{tail}",
+            command.trim()
+        )),
+    ]
+}
+
+/// Starts the diagnosis in the background; `poll_explain` shows the answer.
+pub(crate) fn start_explain(app: &mut App, screen: &mut Screen) {
+    if app.llm_calling || app.shell_task_running || app.explain_rx.is_some() {
+        screen.emit(&[format!("  {}", t("explain.busy"))]);
+        return;
+    }
+    let Some((command, exit_code, output)) = last_failed_tool_result(&app.task_events) else {
+        screen.emit(&[format!("  {}", t("explain.none"))]);
+        return;
+    };
+    let Some(client) = app.http_client.clone() else {
+        return;
+    };
+    let messages = explain_messages(&command, exit_code, &output);
+    let backend = app.backend.clone();
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    tokio::spawn(async move {
+        let result = backend
+            .chat_stream_with(&client, &messages, false, |_| {}, |_| {})
+            .await
+            .map(|(answer, _)| answer);
+        let _ = tx.send(result);
+    });
+    app.explain_rx = Some(rx);
+    app.explain_command = command;
+    screen.status = t("explain.waiting").grey().to_string();
+    screen.refresh();
+}
+
+/// Shows the diagnosis once it arrives. Nothing is added to the task events or messages.
+pub(crate) fn poll_explain(app: &mut App, screen: &mut Screen) {
+    let Some(rx) = app.explain_rx.as_mut() else {
+        return;
+    };
+    let result = match rx.try_recv() {
+        Ok(result) => result,
+        Err(tokio::sync::oneshot::error::TryRecvError::Empty) => return,
+        Err(tokio::sync::oneshot::error::TryRecvError::Closed) => Err(ProviderError::Cancelled),
+    };
+    app.explain_rx = None;
+    if !app.llm_calling {
+        screen.status.clear();
+    }
+    let command = std::mem::take(&mut app.explain_command);
+    let first_line = command.lines().next().unwrap_or("");
+    let mut lines = vec![
+        format!(
+            "  {} {}",
+            Symbols::current().record,
+            t_with("explain.header", &[("command", first_line)])
+        )
+        .magenta()
+        .bold()
+        .to_string(),
+    ];
+    match result {
+        Ok(answer) => lines.extend(format_final_lines(answer.trim())),
+        Err(e) => lines.push(
+            format!(
+                "  {} {}",
+                Symbols::current().warning,
+                t_with("explain.failed", &[("error", &e.to_string())])
+            )
+            .dark_yellow()
+            .to_string(),
+        ),
+    }
+    screen.emit(&lines);
+    screen.refresh();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{explain_messages, last_failed_tool_result, start_explain};
+    use crate::App;
+    use crate::types::Event;
+    use crate::ui::screen::Screen;
+
+    fn call(command: &str) -> Event {
+        Event::ToolCall {
+            label: "Run".to_string(),
+            command: command.to_string(),
+            multiline: false,
+        }
+    }
+
+    fn result(exit_code: i32, output: &str) -> Event {
+        Event::ToolResult {
+            exit_code,
+            output: output.to_string(),
+        }
+    }
+
+    #[test]
+    fn explain_builds_prompt_from_last_failed_tool_result() {
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+
+        // 没有失败的工具结果时只给出提示，不发起调用
+        app.task_events = vec![call("cargo build"), result(0, "Finished")];
+        start_explain(&mut app, &mut screen);
+        assert!(app.explain_rx.is_none());
+
+        app.task_events.extend([
+            call("npm test"),
+            result(1, "ECONNRESET"),
+            Event::Thinking {
+                text: "retry with a clean cache".to_string(),
+            },
+            call("npm ci && npm test"),
+            result(1, "Error: Cannot find module 'left-pad'"),
+            call("git status"),
+            result(0, "clean"),
+        ]);
+        let (command, exit_code, output) =
+            last_failed_tool_result(&app.task_events).expect("failed result");
+        assert_eq!(command, "npm ci && npm test");
+        assert_eq!(exit_code, 1);
+
+        let messages = explain_messages(&command, exit_code, &output);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].content.contains("diagnos"));
+        let request = &messages[1].content;
+        assert!(request.contains("npm ci && npm test"));
+        assert!(request.contains("Exit code: 1"));
+        assert!(request.contains("Cannot find module 'left-pad'"));
+        // 只带最后一次失败，不带任务对话或更早的失败
+        assert!(!request.contains("ECONNRESET"));
+        assert!(!request.contains("retry with a clean cache"));
+
+        // 超长输出只保留结尾
+        let long = format!("{}\nfatal: the real error", "noise\n".repeat(5000));
+        let request = &explain_messages("make", 2, &long)[1].content;
+        assert!(request.contains("earlier output omitted"));
+        assert!(request.ends_with("fatal: the real error"));
+    }
+}
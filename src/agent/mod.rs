@@ -1,6 +1,9 @@
 pub mod dag;
+pub(crate) mod delegate;
 pub mod executor;
+pub(crate) mod explain;
 pub(crate) mod plan;
+pub(crate) mod prompt;
 #[path = "LLMProvider/mod.rs"]
 pub mod provider;
 pub mod react;
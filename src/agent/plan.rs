@@ -10,6 +10,7 @@ Plan mode is enabled. Use the plan workflow proactively.
 - If information is missing or the task is ambiguous, use <tool>question</tool> first (ask one key question at a time).
 - Once information is sufficient and the task is complex, use <tool>plan</tool> to produce a complete plan with todo-style step breakdowns.
 - After <tool>plan</tool>, immediately ask for confirmation with <tool>question</tool>.
+- Number the top-level steps (`1. ...`). While executing, re-emit <tool>plan</tool> with `[x]` after the number of every finished step (`1. [x] ...`); the plan panel is updated in place.
 - If the user confirms the plan content only (not execution), reply in <final> with the full plan content (do not summarize or rewrite it).
 - If the user confirms execution, your NEXT response MUST include <tool>set_mode</tool> before any execution tool call or <final>.
 - Default execution mode after confirmation is <mode>agent</mode> unless the user explicitly requests another mode.
@@ -96,6 +97,46 @@ pub(crate) fn parse_tool_action(tool: &str, text: &str) -> Result<Option<LlmActi
     Ok(action)
 }
 
+/// Splits a `<plan>` body into its numbered (or bulleted) top-level steps, paired with
+/// whether each is checked off (`1. [x] ...`). Deeper lines stay attached to their step as
+/// detail lines; headings and text before the first step are dropped.
+pub(crate) fn parse_plan_steps(content: &str) -> (Vec<String>, Vec<bool>) {
+    let mut steps: Vec<String> = Vec::new();
+    let mut done = Vec::new();
+    let mut step_indent = None;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        let item = crate::ui::format::strip_ordered_marker_pub(trimmed)
+            .or_else(|| trimmed.strip_prefix("- "))
+            .or_else(|| trimmed.strip_prefix("* "));
+        match item {
+            Some(item) if step_indent.is_none_or(|top| indent <= top) => {
+                step_indent = Some(indent);
+                let (text, checked) = match item.strip_prefix("[ ] ") {
+                    Some(rest) => (rest, false),
+                    None => item
+                        .strip_prefix("[x] ")
+                        .or_else(|| item.strip_prefix("[X] "))
+                        .map_or((item, false), |rest| (rest, true)),
+                };
+                steps.push(text.trim().to_string());
+                done.push(checked);
+            }
+            _ => {
+                if let Some(step) = steps.last_mut() {
+                    step.push('\n');
+                    step.push_str(trimmed);
+                }
+            }
+        }
+    }
+    (steps, done)
+}
+
 pub(crate) fn is_plan_echo(actions: &[LlmAction]) -> bool {
     actions.iter().any(|a| matches!(a, LlmAction::Final { .. }))
         && !actions
@@ -165,7 +206,8 @@ fn extract_last_tag(text: &str, tag: &str) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::{is_plan_echo, parse_tool_action};
+    use super::{is_plan_echo, parse_plan_steps, parse_tool_action};
+    use crate::agent::react::parse_llm_response;
     use crate::types::LlmAction;
 
     #[test]
@@ -207,4 +249,32 @@ mod tests {
         ];
         assert!(!is_plan_echo(&b));
     }
+
+    #[test]
+    fn plan_block_is_split_into_checkable_steps() {
+        let raw = "<tool>plan</tool><plan>## 计划\n先看看结构。\n\
+            1. [x] 阅读 `src/parser.rs`\n\
+            2. 修复解析错误\n   - 处理空输入\n   - 补充测试\n\
+            3. [ ] 运行 cargo test\n</plan>";
+        let (_, actions) = parse_llm_response(raw).expect("parse ok");
+        let [LlmAction::Plan { content }] = actions.as_slice() else {
+            panic!("expected a single plan action, got {actions:?}");
+        };
+        let (steps, done) = parse_plan_steps(content);
+        assert_eq!(
+            steps,
+            vec![
+                "阅读 `src/parser.rs`".to_string(),
+                "修复解析错误\n- 处理空输入\n- 补充测试".to_string(),
+                "运行 cargo test".to_string(),
+            ]
+        );
+        assert_eq!(done, vec![true, false, false]);
+
+        // 没有编号步骤的自由文本计划不产生步骤
+        assert_eq!(
+            parse_plan_steps("## 计划\n直接改就行").0,
+            Vec::<String>::new()
+        );
+    }
 }
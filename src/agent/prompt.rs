@@ -0,0 +1,229 @@
+use crate::App;
+use std::collections::BTreeSet;
+
+use crate::agent::react::{
+    agents_md_prompt, build_system_prompt, memory_rules_prompt, plan_mode_prompt,
+    strip_disabled_tools, workspace_prompt,
+};
+use crate::tools::skills::skills_system_prompt;
+use crate::types::AssistMode;
+
+/// 系统提示词中的一个命名片段；`content` 自带与前一片段之间的分隔符。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PromptSection {
+    pub name: &'static str,
+    pub content: String,
+}
+
+/// 按固定顺序拼装 messages[0]，便于 `/prompt` 逐段查看各部分的体积。
+#[derive(Debug, Default)]
+pub(crate) struct SystemPromptBuilder {
+    sections: Vec<PromptSection>,
+}
+
+impl SystemPromptBuilder {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加一个片段；内容为空时跳过（例如没有技能、不在 Plan 模式）。
+    pub(crate) fn section(mut self, name: &'static str, content: String) -> Self {
+        if !content.is_empty() {
+            self.sections.push(PromptSection { name, content });
+        }
+        self
+    }
+
+    pub(crate) fn sections(&self) -> &[PromptSection] {
+        &self.sections
+    }
+
+    pub(crate) fn build(&self) -> String {
+        self.sections.iter().map(|s| s.content.as_str()).collect()
+    }
+
+    /// 当前会话的完整系统提示词：基础规则 → 技能 → MCP 工具 → 工作区 → 记忆规则 → AGENTS.md → Plan 附录。
+    pub(crate) fn for_app(app: &App) -> Self {
+        Self::for_parts(
+            &app.disabled_tools,
+            &skills_system_prompt(&app.skills),
+            &app.mcp_registry.tools_prompt_section(),
+            &app.workspace,
+            app.assist_mode,
+        )
+    }
+
+    fn for_parts(
+        disabled_tools: &BTreeSet<String>,
+        skills: &str,
+        mcp_tools: &str,
+        workspace: &std::path::Path,
+        assist_mode: AssistMode,
+    ) -> Self {
+        Self::new()
+            .section(
+                "base",
+                strip_disabled_tools(&build_system_prompt(), disabled_tools),
+            )
+            .section("skills", skills.to_string())
+            .section(
+                "mcp_tools",
+                if disabled_tools.contains("mcp") {
+                    String::new()
+                } else {
+                    mcp_tools.to_string()
+                },
+            )
+            .section("workspace", format!("\n\n{}", workspace_prompt(workspace)))
+            .section("memory", memory_rules_prompt(workspace))
+            .section("agents_md", agents_md_prompt(workspace))
+            .section("plan_mode", plan_mode_prompt(assist_mode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SystemPromptBuilder;
+    use crate::agent::react::parse_disabled_tools;
+    use crate::agent::react::{build_system_prompt, build_workspace_context};
+    use crate::types::AssistMode;
+    use std::collections::BTreeSet;
+    use std::path::PathBuf;
+
+    fn temp_workspace(tag: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("goldbot-prompt-{tag}-{nanos}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn names(builder: &SystemPromptBuilder) -> Vec<&'static str> {
+        builder.sections().iter().map(|s| s.name).collect()
+    }
+
+    #[test]
+    fn build_matches_legacy_assembly() {
+        let ws = temp_workspace("legacy");
+        std::fs::write(ws.join("AGENTS.md"), "Use tabs.\n").unwrap();
+        let skills = "\n\n## Available Skills\n- demo: demo skill\n";
+        let mcp = "\n\n## Available MCP tools\n- mcp_fs_read: read\n";
+        let none = BTreeSet::new();
+        for mode in [AssistMode::Off, AssistMode::Plan] {
+            let legacy = format!(
+                "{}{skills}{mcp}\n\n{}",
+                build_system_prompt(),
+                build_workspace_context(&ws, mode)
+            );
+            let built = SystemPromptBuilder::for_parts(&none, skills, mcp, &ws, mode).build();
+            assert_eq!(built, legacy);
+        }
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+
+    #[test]
+    fn base_and_workspace_sections_always_present() {
+        let ws = temp_workspace("base");
+        let builder =
+            SystemPromptBuilder::for_parts(&BTreeSet::new(), "", "", &ws, AssistMode::Off);
+        assert_eq!(names(&builder), ["base", "workspace", "memory"]);
+        let sections = builder.sections();
+        assert_eq!(sections[0].content, build_system_prompt());
+        assert!(sections[1].content.starts_with("\n\nCurrent workspace: `"));
+        assert!(sections[2].content.contains("project memory"));
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+
+    #[test]
+    fn optional_sections_appear_only_when_populated() {
+        let ws = temp_workspace("optional");
+        std::fs::write(ws.join("AGENTS.md"), "Run cargo fmt.\n").unwrap();
+        let builder = SystemPromptBuilder::for_parts(
+            &BTreeSet::new(),
+            "\n\n## Skills",
+            "\n\n## MCP",
+            &ws,
+            AssistMode::Plan,
+        );
+        assert_eq!(
+            names(&builder),
+            [
+                "base",
+                "skills",
+                "mcp_tools",
+                "workspace",
+                "memory",
+                "agents_md",
+                "plan_mode"
+            ]
+        );
+        let agents = &builder.sections()[5].content;
+        assert!(agents.contains("Run cargo fmt."));
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+
+    #[test]
+    fn safe_mode_prompt_has_no_mcp_or_skill_sections() {
+        let mut app = crate::App::new_with_safe_mode(true);
+        assert!(app.safe_mode);
+        assert!(app.skills.is_empty());
+        assert!(!app.mcp_registry.has_servers());
+        app.rebuild_system_message();
+        let builder = SystemPromptBuilder::for_app(&app);
+        let names = names(&builder);
+        assert!(
+            names.starts_with(&["base", "workspace", "memory"]),
+            "{names:?}"
+        );
+        assert!(!names.contains(&"skills") && !names.contains(&"mcp_tools"));
+        let prompt = &app.messages[0].content;
+        assert!(
+            !prompt.contains("## Available MCP tools") && !prompt.contains("## Available Skills")
+        );
+    }
+
+    #[test]
+    fn disabled_tools_are_absent_from_prompt() {
+        let ws = temp_workspace("disabled");
+        let (disabled, unknown) = parse_disabled_tools("shell, web_search,bogus,mcp");
+        assert_eq!(unknown, ["bogus"]);
+        let mcp_section = "\n\n## Available MCP tools\n- mcp_fs_read";
+        let prompt =
+            SystemPromptBuilder::for_parts(&disabled, "", mcp_section, &ws, AssistMode::Off)
+                .build();
+        assert!(!prompt.contains("mcp_fs_read"));
+        assert!(!prompt.contains("<tool>shell</tool>"));
+        assert!(!prompt.contains("<tool>web_search</tool>"));
+        assert!(prompt.contains("<tool>read</tool>"));
+        assert!(prompt.contains("<tool>sub_agent</tool>"));
+        assert!(prompt.contains("# Response format"));
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+
+    #[test]
+    fn remembered_notes_appear_in_rebuilt_prompt() {
+        let ws = temp_workspace("remember");
+        // workspace profile：记忆写在临时目录里，不碰全局 ~/.goldbot
+        std::fs::create_dir_all(ws.join(".goldbot")).unwrap();
+        let store = crate::memory::project::ProjectStore::new(&ws);
+        let build = || {
+            SystemPromptBuilder::for_parts(&BTreeSet::new(), "", "", &ws, AssistMode::Off).build()
+        };
+        assert!(!build().contains("Corrections the user asked me to always follow"));
+
+        assert!(
+            store
+                .pin_memory("Always run cargo fmt before committing")
+                .unwrap()
+        );
+        let prompt = build();
+        assert!(prompt.contains("Corrections the user asked me to always follow"));
+        assert!(prompt.contains("- Always run cargo fmt before committing."));
+
+        store.forget_memory("cargo fmt").unwrap();
+        assert!(!build().contains("cargo fmt before committing"));
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+}
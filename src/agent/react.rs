@@ -7,6 +7,7 @@ use crate::{
 };
 use anyhow::{Result, anyhow};
 use serde_json::Value;
+use std::collections::BTreeSet;
 
 const SYSTEM_PROMPT_TEMPLATE: &str = "\
 You are GoldBot, a terminal automation agent. Complete tasks step by step using the tools below, Think before Act.
@@ -96,6 +97,13 @@ Glob (find files by name pattern, respects .gitignore, sorted by modification ti
 <pattern>glob pattern (e.g. **/*.rs, src/**/*.toml, *.md)</pattern>
 <path>optional/path/to/search (default: .)</path>
 
+Directory tree (repo structure in one call instead of many ls/find; skips build and hidden dirs, capped entries):
+<thought>reasoning</thought>
+<tool>tree</tool>
+<path>optional/dir (default: .)</path>
+<depth>levels to expand (optional, default 3)</depth>
+<sizes>true to show file sizes (optional)</sizes>
+
 Shell command: prefer native tool: read, search, write/update.
 <thought>reasoning</thought>
 <tool>shell</tool>
@@ -125,6 +133,11 @@ Load a skill (only if listed later in this prompt):
 <thought>reasoning</thought>
 <skill>skill-name</skill>
 
+Ask the user for a missing credential (only after a command failed because an env var such as GITHUB_TOKEN is unset; never ask for secrets in a <question>):
+<thought>reasoning</thought>
+<ask_secret>GITHUB_TOKEN</ask_secret>
+The user types the value into a masked prompt. It is injected into the environment of your commands and the failed command is re-run; you never see the value.
+
 Sub-agent DAG (dispatch parallel/sequential sub-tasks to specialized agents; use when the task can be decomposed into independent or dependent subtasks):
 <thought>reasoning</thought>
 <tool>sub_agent</tool>
@@ -175,27 +188,132 @@ pub fn build_system_prompt() -> String {
         .replace("{SHELL_HINT}", shell_hint)
 }
 
+/// Tools that can be turned off via `GOLDBOT_DISABLE_TOOLS` or `/tools disable`;
+/// `mcp` covers every MCP tool.
+pub(crate) const DISABLEABLE_TOOLS: &[&str] = &[
+    "shell",
+    "read",
+    "write",
+    "update",
+    "search",
+    "glob",
+    "tree",
+    "web_search",
+    "task",
+    "sub_agent",
+    "mcp",
+];
+
+/// Parse a comma/space separated tool list; returns (known tools, unknown names).
+pub(crate) fn parse_disabled_tools(raw: &str) -> (BTreeSet<String>, Vec<String>) {
+    let mut tools = BTreeSet::new();
+    let mut unknown = Vec::new();
+    for name in raw
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|n| n.trim().to_ascii_lowercase())
+        .filter(|n| !n.is_empty())
+    {
+        if DISABLEABLE_TOOLS.contains(&name.as_str()) {
+            tools.insert(name);
+        } else {
+            unknown.push(name);
+        }
+    }
+    (tools, unknown)
+}
+
+/// Drop the usage blocks of disabled tools from the `## Tools` part of the system prompt.
+pub(crate) fn strip_disabled_tools(prompt: &str, disabled: &BTreeSet<String>) -> String {
+    if disabled.is_empty() {
+        return prompt.to_string();
+    }
+    let Some(tools_at) = prompt.find("## Tools\n") else {
+        return prompt.to_string();
+    };
+    let (head, tools) = prompt.split_at(tools_at);
+    let kept: Vec<&str> = tools
+        .split("\n\n")
+        .filter(|block| {
+            !disabled
+                .iter()
+                .any(|name| block.contains(&format!("<tool>{name}</tool>")))
+        })
+        .collect();
+    format!("{head}{}", kept.join("\n\n"))
+}
+
+/// How much of the last tool result an "include last output" interjection embeds (tail kept).
+const INTERJECTION_OUTPUT_MAX_CHARS: usize = 2_000;
+
 /// Build the user-role wrapper message used when the user interrupts the loop and interjects
 /// mid-task.  Keeping the wording here makes LLM-facing prompts easier to review in one place.
-pub fn build_interjection_user_message(task: &str) -> String {
+///
+/// With `last_output`, the most recent tool result is quoted so the instruction is grounded in
+/// that exact output; otherwise only the lightweight recap is sent.
+pub fn build_interjection_user_message(task: &str, last_output: Option<&str>) -> String {
+    let grounding = match last_output {
+        Some(output) => {
+            let total = output.chars().count();
+            let shown = if total > INTERJECTION_OUTPUT_MAX_CHARS {
+                let tail: String = output
+                    .chars()
+                    .skip(total - INTERJECTION_OUTPUT_MAX_CHARS)
+                    .collect();
+                format!("…(earlier output truncated)\n{tail}")
+            } else {
+                output.to_string()
+            };
+            format!(
+                "The user inspected the last tool result and wants to continue from this exact output:\n\
+                 <last_tool_result>\n{shown}\n</last_tool_result>\n\
+                 \n"
+            )
+        }
+        None => String::new(),
+    };
     format!(
         "User interrupted the current LLM loop and is interjecting mid-task.\n\
          Continue from the current conversation context.\n\
          \n\
-         User interjection:\n{task}"
+         {grounding}User interjection:\n{task}"
     )
 }
 
 /// Build workspace-specific context (paths, memory rules, AGENTS.md, plan-mode addendum)
 /// that is appended to the system prompt in messages[0].
 pub fn build_workspace_context(workspace: &std::path::Path, assist_mode: AssistMode) -> String {
+    let mut out = workspace_prompt(workspace);
+    out.push_str(&memory_rules_prompt(workspace));
+    out.push_str(&agents_md_prompt(workspace));
+    out.push_str(&plan_mode_prompt(assist_mode));
+    out
+}
+
+/// Workspace path section (first part of the workspace context).
+pub(crate) fn workspace_prompt(workspace: &std::path::Path) -> String {
+    format!(
+        "Current workspace: `{}`\n\
+         All shell commands run in this directory, and file paths are resolved relative to it.",
+        workspace.display()
+    )
+}
+
+/// Project-memory rules section; starts with its own blank-line separator.
+pub(crate) fn memory_rules_prompt(workspace: &std::path::Path) -> String {
     let project_store = crate::memory::project::ProjectStore::new(workspace);
     let memory_path = project_store.memory_path_display();
-    let workspace_display = workspace.display();
-    let mut out = format!(
-        "Current workspace: `{workspace_display}`\n\
-         All shell commands run in this directory, and file paths are resolved relative to it.\n\
-         \n\
+    let pinned = project_store.pinned_notes();
+    let pinned = if pinned.is_empty() {
+        String::new()
+    } else {
+        let notes: Vec<String> = pinned.iter().map(|n| format!("- {n}")).collect();
+        format!(
+            "\n\nCorrections the user asked me to always follow:\n{}",
+            notes.join("\n")
+        )
+    };
+    format!(
+        "\n\n\
          I maintain a project memory at `{memory_path}`.\n\
          On every response, evaluate whether new information should be saved with the <memory> \
          tag for future tasks.\n\
@@ -211,14 +329,24 @@ pub fn build_workspace_context(workspace: &std::path::Path, assist_mode: AssistM
          - If information comes from memory, answer naturally (e.g., \"I remember ...\") \
          without saying you read a memory file.\n\
          - When asked about past events, preferences, or prior agreements, check memory first \
-         using case-insensitive search (prefer `rg -n -i`)."
-    );
+         using case-insensitive search (prefer `rg -n -i`).{pinned}"
+    )
+}
+
+/// Nearest `AGENTS.md` content; empty when none is found.
+pub(crate) fn agents_md_prompt(workspace: &std::path::Path) -> String {
+    let mut out = String::new();
     append_workspace_agents_md(&mut out, workspace);
+    out
+}
+
+/// Plan-mode addendum; empty outside plan mode.
+pub(crate) fn plan_mode_prompt(assist_mode: AssistMode) -> String {
     if assist_mode == AssistMode::Plan {
-        out.push_str("\n\n");
-        out.push_str(plan::PLAN_MODE_ASSIST_CONTEXT_APPENDIX);
+        format!("\n\n{}", plan::PLAN_MODE_ASSIST_CONTEXT_APPENDIX)
+    } else {
+        String::new()
     }
-    out
 }
 
 fn append_workspace_agents_md(out: &mut String, workspace: &std::path::Path) {
@@ -281,20 +409,69 @@ fn nearest_git_root(start: &std::path::Path) -> Option<std::path::PathBuf> {
 /// one is parsed into an [`LlmAction`].  The caller is responsible for executing them in
 /// sequence, stopping at the first "blocking" action (shell, question, final, …).
 pub fn parse_llm_response(text: &str) -> Result<(String, Vec<LlmAction>)> {
+    parse_llm_response_with_final_tags(text, &final_tags())
+}
+
+/// Tags accepted as the final answer: always `final`, plus any synonyms listed in
+/// `GOLDBOT_FINAL_TAGS` (comma-separated, e.g. `answer,result`).
+pub(crate) fn final_tags() -> Vec<String> {
+    let mut tags = vec!["final".to_string()];
+    for tag in std::env::var("GOLDBOT_FINAL_TAGS")
+        .unwrap_or_default()
+        .split(',')
+    {
+        let tag = tag.trim().to_ascii_lowercase();
+        let valid = !tag.is_empty()
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if valid && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Extract the text of the last final-like block (`<final>` or a configured synonym).
+/// When several tags are present, the one closed last in the response wins.
+pub(crate) fn extract_final_text(text: &str, tags: &[String]) -> Option<String> {
+    tags.iter()
+        .filter_map(|tag| {
+            let end = text.rfind(&format!("</{tag}>"))?;
+            Some((end, extract_last_tag(text, tag)?))
+        })
+        .max_by_key(|(end, _)| *end)
+        .map(|(_, summary)| summary)
+}
+
+fn parse_llm_response_with_final_tags(
+    text: &str,
+    final_tags: &[String],
+) -> Result<(String, Vec<LlmAction>)> {
     let thought = extract_last_tag(text, "thought").unwrap_or_default();
 
     // Collect memory actions first — they are non-blocking and may appear alongside any other tag.
     let mut memory_actions = collect_memory_actions(text);
 
     // These special tags are never wrapped in <tool>; handle them first.
-    if let Some(summary) = extract_last_tag(text, "final") {
-        memory_actions.push(LlmAction::Final { summary });
-        return Ok((thought, memory_actions));
+    if let Some(summary) = extract_final_text(text, final_tags) {
+        let actions = resolve_final_with_tools(text, memory_actions, summary);
+        return Ok((thought, actions));
     }
     if let Some(name) = extract_last_tag(text, "skill") {
         memory_actions.push(LlmAction::Skill { name });
         return Ok((thought, memory_actions));
     }
+    if let Some(name) = extract_last_tag(text, "ask_secret") {
+        let name = name.trim().to_string();
+        if !is_env_var_name(&name) {
+            return Err(anyhow!(
+                "<ask_secret> must contain an environment variable name, got `{name}`"
+            ));
+        }
+        memory_actions.push(LlmAction::AskSecret { name });
+        return Ok((thought, memory_actions));
+    }
     if let Some(raw) = extract_last_tag(text, "create_mcp") {
         let config: Value =
             serde_json::from_str(&raw).map_err(|e| anyhow!("invalid <create_mcp> JSON: {e}"))?;
@@ -331,6 +508,60 @@ pub fn parse_llm_response(text: &str) -> Result<(String, Vec<LlmAction>)> {
     ))
 }
 
+/// Resolve a response that contains a final answer, possibly alongside `<tool>` calls.
+///
+/// Rule: if any accompanying tool is an action (shell, read, MCP, question, …), the task is
+/// not done yet — the tools run and the premature final is dropped; the executor notes the
+/// deferral via [`final_was_deferred`].  Non-blocking tools (plan/todo/set_mode/phase) are
+/// kept and run before the final.  If any tool fails to parse, the final alone wins.
+fn resolve_final_with_tools(
+    text: &str,
+    mut actions: Vec<LlmAction>,
+    summary: String,
+) -> Vec<LlmAction> {
+    let tools: Result<Vec<LlmAction>> = extract_all_tags(text, "tool")
+        .iter()
+        .map(|tool| parse_tool_action(text, tool))
+        .collect();
+    match tools {
+        Ok(tools) if tools.iter().any(|a| !is_non_blocking_action(a)) => {
+            actions.extend(tools);
+        }
+        Ok(tools) => {
+            actions.extend(tools);
+            actions.push(LlmAction::Final { summary });
+        }
+        Err(_) => actions.push(LlmAction::Final { summary }),
+    }
+    actions
+}
+
+fn is_env_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_non_blocking_action(action: &LlmAction) -> bool {
+    matches!(
+        action,
+        LlmAction::Plan { .. }
+            | LlmAction::Todo { .. }
+            | LlmAction::SetMode { .. }
+            | LlmAction::Phase { .. }
+            | LlmAction::Memory { .. }
+    )
+}
+
+/// True when the raw response contained a final answer that parsing deferred in favour of a
+/// tool call (see [`resolve_final_with_tools`]).
+pub(crate) fn final_was_deferred(text: &str, actions: &[LlmAction]) -> bool {
+    !actions.iter().any(|a| matches!(a, LlmAction::Final { .. }))
+        && extract_final_text(text, &final_tags()).is_some()
+}
+
 /// Extract all `<memory>` tags from the response as non-blocking Memory actions.
 fn collect_memory_actions(text: &str) -> Vec<LlmAction> {
     extract_all_tags(text, "memory")
@@ -406,6 +637,14 @@ fn parse_tool_action(text: &str, tool: &str) -> Result<LlmAction> {
             let path = extract_last_tag(text, "path").unwrap_or_else(|| ".".to_string());
             Ok(LlmAction::GlobFiles { pattern, path })
         }
+        "tree" => {
+            let path = extract_last_tag(text, "path").unwrap_or_else(|| ".".to_string());
+            let depth =
+                extract_last_tag(text, "depth").and_then(|s| s.trim().parse::<usize>().ok());
+            let sizes = extract_last_tag(text, "sizes")
+                .is_some_and(|s| matches!(s.trim(), "1" | "true" | "yes"));
+            Ok(LlmAction::DirectoryTree { path, depth, sizes })
+        }
         "task" => {
             let description = extract_last_tag(text, "description")
                 .ok_or_else(|| anyhow!("missing <description> for task tool call"))?;
@@ -597,7 +836,10 @@ fn extract_last_tag_preserve_block(text: &str, tag: &str) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::{build_system_prompt, build_workspace_context, parse_llm_response};
+    use super::{
+        build_interjection_user_message, build_system_prompt, build_workspace_context,
+        final_was_deferred, parse_llm_response, parse_llm_response_with_final_tags,
+    };
     use crate::types::{AssistMode, LlmAction};
     use serde_json::json;
     use std::{
@@ -617,6 +859,25 @@ mod tests {
         ))
     }
 
+    #[test]
+    fn interjection_embeds_last_tool_result_only_when_requested() {
+        let recap = build_interjection_user_message("use the second path", None);
+        assert!(recap.ends_with("User interjection:\nuse the second path"));
+        assert!(!recap.contains("<last_tool_result>"));
+
+        let grounded =
+            build_interjection_user_message("use the second path", Some("a/b.rs\nc/d.rs"));
+        assert!(grounded.contains("<last_tool_result>\na/b.rs\nc/d.rs\n</last_tool_result>"));
+        assert!(grounded.ends_with("User interjection:\nuse the second path"));
+
+        // Long outputs keep only the tail.
+        let long = format!("{}END", "x".repeat(5_000));
+        let grounded = build_interjection_user_message("go on", Some(&long));
+        assert!(grounded.contains("…(earlier output truncated)"));
+        assert!(grounded.contains("END\n</last_tool_result>"));
+        assert!(grounded.len() < 3_000);
+    }
+
     #[test]
     fn parse_final_prefers_last_closed_tag() {
         let raw = "<thought>ok</thought><final>bad <final>good</final>";
@@ -628,6 +889,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_answer_tag_only_when_configured_as_final_synonym() {
+        let raw = "<thought>done</thought><answer>all good</answer>";
+        let tags = vec!["final".to_string(), "answer".to_string()];
+        let (_, actions) = parse_llm_response_with_final_tags(raw, &tags).expect("synonym");
+        match &actions[..] {
+            [LlmAction::Final { summary }] => assert_eq!(summary, "all good"),
+            other => panic!("expected final action, got {other:?}"),
+        }
+
+        let err = parse_llm_response_with_final_tags(raw, &["final".to_string()])
+            .expect_err("answer is not a final tag by default");
+        assert!(err.to_string().contains("cannot parse LLM response"));
+    }
+
+    #[test]
+    fn parse_final_synonyms_pick_last_closed_block() {
+        let raw = "<final>first</final> then <result>second</result>";
+        let tags = vec!["final".to_string(), "result".to_string()];
+        let (_, actions) = parse_llm_response_with_final_tags(raw, &tags).expect("final");
+        match &actions[..] {
+            [LlmAction::Final { summary }] => assert_eq!(summary, "second"),
+            other => panic!("expected single final action, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_ask_secret_requires_env_var_name() {
+        let raw = "<thought>gh needs auth</thought><ask_secret> GITHUB_TOKEN </ask_secret>";
+        let (_, actions) = parse_llm_response(raw).expect("should parse");
+        match &actions[..] {
+            [LlmAction::AskSecret { name }] => assert_eq!(name, "GITHUB_TOKEN"),
+            other => panic!("expected ask_secret action, got {other:?}"),
+        }
+
+        let err = parse_llm_response("<ask_secret>my token</ask_secret>").expect_err("not a name");
+        assert!(err.to_string().contains("environment variable name"));
+    }
+
+    #[test]
+    fn parse_tool_and_final_prefers_tool_and_defers_final() {
+        let raw = "<thought>check</thought><tool>shell</tool><command>cargo test</command><final>all done</final>";
+        let (_, actions) = parse_llm_response(raw).expect("should parse");
+        match &actions[..] {
+            [LlmAction::Shell { command }] => assert_eq!(command, "cargo test"),
+            other => panic!("expected shell action only, got {other:?}"),
+        }
+        assert!(final_was_deferred(raw, &actions));
+    }
+
+    #[test]
+    fn parse_todo_and_final_keeps_final() {
+        let raw =
+            r#"<tool>todo</tool><todo>[{"label":"a","status":"done"}]</todo><final>ok</final>"#;
+        let (_, actions) = parse_llm_response(raw).expect("should parse");
+        assert!(matches!(actions[0], LlmAction::Todo { .. }));
+        assert!(matches!(actions.last(), Some(LlmAction::Final { .. })));
+        assert!(!final_was_deferred(raw, &actions));
+    }
+
     #[test]
     fn parse_error_does_not_echo_raw_text() {
         let raw = "plain response without tags";
@@ -1,38 +1,213 @@
-/// 解析 CLI 参数，返回 (prompt, yolo_mode, no_memory)。
-/// 支持的标志：
-///   -p / --prompt <text>   启动时发送的初始任务消息。
-///   -y / --yes             进入 Yolo 模式，自动执行所有 Confirm 级别的命令（非 Block）。
-///   -M / --no-memory       禁用记忆注入（不向 LLM 上下文注入历史记忆）。
-pub(crate) fn parse_cli_args() -> (Option<String>, bool, bool) {
-    let args: Vec<String> = std::env::args().collect();
-    let mut prompt = None;
-    let mut yes = false;
-    let mut no_memory = false;
-    let mut i = 1;
-    while i < args.len() {
-        if (args[i] == "-p" || args[i] == "--prompt") && i + 1 < args.len() {
-            prompt = Some(args[i + 1].clone());
-            i += 2;
-        } else if args[i] == "-y" || args[i] == "--yes" {
-            yes = true;
-            i += 1;
-        } else if args[i] == "-M" || args[i] == "--no-memory" {
-            no_memory = true;
-            i += 1;
-        } else {
-            i += 1;
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+
+const USAGE: &str = "\
+Usage: goldbot [OPTIONS]
+
+Options:
+  -p, --prompt <TEXT>     Send TEXT as the first task on startup
+      --task <TEXT>       Same as --prompt; overrides GOLDBOT_TASK
+  -y, --yes               Start in Yolo mode (auto-run Confirm-level commands)
+  -M, --no-memory         Do not inject memories into the context
+  -w, --workspace <DIR>   Workspace directory; overrides GOLDBOT_WORKSPACE
+      --safe              Safe mode: skip MCP servers and skills (same as GOLDBOT_SAFE=1)
+      --headless          Run the task without the TUI and print the result to stdout
+      --format <FMT>      Headless result format: text (default) or json
+  -V, --version           Print version
+  -h, --help              Print this help
+
+Environment:
+  GOLDBOT_WORKSPACE       Workspace directory (default: current directory)
+  GOLDBOT_TASK            Task for --headless when --task/--prompt is not given
+  GOLDBOT_SAFE            Start in safe mode when set to 1
+  LLM_PROVIDER            LLM provider; see ~/.goldbot/.env for the rest
+";
+
+/// headless 模式下最终结果的输出格式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// 解析后的启动参数；未给出的项沿用环境变量配置。
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct CliArgs {
+    pub prompt: Option<String>,
+    pub yes: bool,
+    pub no_memory: bool,
+    pub workspace: Option<PathBuf>,
+    pub safe: bool,
+    pub headless: bool,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum CliCommand {
+    Run(CliArgs),
+    Version,
+    Help,
+}
+
+pub(crate) fn usage() -> &'static str {
+    USAGE
+}
+
+/// 解析进程参数；未知参数或缺少取值时返回错误（调用方打印用法后退出）。
+pub(crate) fn parse_cli_args() -> Result<CliCommand> {
+    parse_args(std::env::args().skip(1))
+}
+
+fn parse_args<I>(args: I) -> Result<CliCommand>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut cli = CliArgs::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // 同时支持 `--flag value` 与 `--flag=value`
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+        let mut value = |name: &str| match inline.clone().or_else(|| args.next()) {
+            Some(v) => Ok(v),
+            None => bail!("{name} requires a value"),
+        };
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(CliCommand::Help),
+            "-V" | "--version" => return Ok(CliCommand::Version),
+            "-p" | "--prompt" | "--task" => cli.prompt = Some(value(&flag)?),
+            "-y" | "--yes" => cli.yes = true,
+            "-M" | "--no-memory" => cli.no_memory = true,
+            "-w" | "--workspace" => cli.workspace = Some(PathBuf::from(value(&flag)?)),
+            "--safe" => cli.safe = true,
+            "--headless" => cli.headless = true,
+            "--format" => {
+                cli.format = match value(&flag)?.to_ascii_lowercase().as_str() {
+                    "text" => OutputFormat::Text,
+                    "json" => OutputFormat::Json,
+                    other => bail!("unknown --format `{other}` (expected text or json)"),
+                }
+            }
+            other => bail!("unknown argument `{other}`"),
+        }
+    }
+    Ok(CliCommand::Run(cli))
+}
+
+/// headless 运行结束后写到 stdout 的结果。json 格式总是输出一个对象（带 status/error），
+/// 脚本不必区分“没有输出”与“失败”；text 格式只有最终答案，没有时返回 None。
+pub(crate) fn headless_report(
+    format: OutputFormat,
+    workspace: &str,
+    summary: Option<&str>,
+    error: Option<&str>,
+) -> Option<String> {
+    match format {
+        OutputFormat::Text => summary.map(str::to_string),
+        OutputFormat::Json => {
+            let error = error.map(str::to_string).or_else(|| {
+                summary
+                    .is_none()
+                    .then(|| "task ended without a result".to_string())
+            });
+            Some(
+                serde_json::json!({
+                    "workspace": workspace,
+                    "status": if error.is_none() { "ok" } else { "error" },
+                    "summary": summary,
+                    "error": error,
+                })
+                .to_string(),
+            )
         }
     }
-    (prompt, yes, no_memory)
 }
 
 /// 若 `~/.goldbot/.env` 不存在，则从内置模板创建。
-pub(crate) fn ensure_dot_env() {
-    let home = crate::tools::mcp::goldbot_home_dir();
-    let env_path = home.join(".env");
+pub(crate) fn ensure_dot_env() -> Result<()> {
+    let env_path = crate::tools::mcp::goldbot_home_dir().join(".env");
     if env_path.exists() {
-        return;
+        return Ok(());
+    }
+    crate::config::write_config_file(&env_path, include_str!("../.env.example"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CliArgs, CliCommand, OutputFormat, headless_report, parse_args};
+    use std::path::PathBuf;
+
+    fn parse(args: &[&str]) -> anyhow::Result<CliCommand> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn parses_flags_values_and_rejects_unknown_arguments() {
+        assert_eq!(parse(&[]).unwrap(), CliCommand::Run(CliArgs::default()));
+        assert_eq!(parse(&["-y", "--version"]).unwrap(), CliCommand::Version);
+        assert_eq!(parse(&["--help", "--bogus"]).unwrap(), CliCommand::Help);
+
+        let CliCommand::Run(cli) = parse(&[
+            "--task",
+            "fix the build",
+            "-y",
+            "--workspace=/tmp/proj",
+            "--safe",
+            "--headless",
+            "--format",
+            "JSON",
+        ])
+        .unwrap() else {
+            panic!("expected run");
+        };
+        assert_eq!(
+            cli,
+            CliArgs {
+                prompt: Some("fix the build".to_string()),
+                yes: true,
+                no_memory: false,
+                workspace: Some(PathBuf::from("/tmp/proj")),
+                safe: true,
+                headless: true,
+                format: OutputFormat::Json,
+            }
+        );
+
+        assert!(parse(&["--bogus"]).is_err());
+        assert!(parse(&["-p"]).is_err());
+        assert!(parse(&["--format", "yaml"]).is_err());
+    }
+
+    #[test]
+    fn json_report_is_always_an_object_with_status() {
+        let report = |summary, error| {
+            let raw = headless_report(OutputFormat::Json, "/ws", summary, error).unwrap();
+            serde_json::from_str::<serde_json::Value>(&raw).unwrap()
+        };
+        let ok = report(Some("done"), None);
+        assert_eq!(ok["status"], "ok");
+        assert_eq!(ok["summary"], "done");
+        assert!(ok["error"].is_null());
+
+        let empty = report(None, None);
+        assert_eq!(empty["status"], "error");
+        assert!(empty["summary"].is_null());
+        assert_eq!(empty["error"], "task ended without a result");
+
+        let failed = report(None, Some("[LLM error] boom"));
+        assert_eq!(failed["status"], "error");
+        assert_eq!(failed["workspace"], "/ws");
+        assert_eq!(failed["error"], "[LLM error] boom");
+
+        assert_eq!(
+            headless_report(OutputFormat::Text, "/ws", None, Some("x")),
+            None
+        );
     }
-    let _ = std::fs::create_dir_all(&home);
-    let _ = std::fs::write(&env_path, include_str!("../.env.example"));
 }
@@ -0,0 +1,108 @@
+//! Helpers for GoldBot's settings files (`~/.goldbot/` and a workspace's `.goldbot/`).
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+
+/// Writes a settings file (creating its directory). Every persister goes through here so a
+/// read-only home or a permission problem comes back as an error the caller can show,
+/// instead of a setting that silently fails to stick.
+pub(crate) fn write_config_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("couldn't create {}", display_config_path(parent)))?;
+    }
+    fs::write(path, content)
+        .with_context(|| format!("couldn't save to {}", display_config_path(path)))
+}
+
+/// Path for messages, with the home directory shortened to `~`.
+pub(crate) fn display_config_path(path: &Path) -> String {
+    let shown = crate::tools::home_dir()
+        .and_then(|home| {
+            path.strip_prefix(home)
+                .ok()
+                .map(|rest| Path::new("~").join(rest))
+        })
+        .unwrap_or_else(|| path.to_path_buf());
+    shown.to_string_lossy().replace('\\', "/")
+}
+
+/// Whether `write_config_file` could create files in `dir`. Nothing is written: a missing
+/// `dir` is judged by its nearest existing ancestor, which `write_config_file` would create
+/// the rest under.
+pub(crate) fn config_dir_writable(dir: &Path) -> bool {
+    let Some(existing) = dir.ancestors().find(|p| p.exists()) else {
+        return false;
+    };
+    existing.is_dir() && dir_writable(existing)
+}
+
+#[cfg(unix)]
+fn dir_writable(dir: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // access(2) also reports read-only mounts, which the permission bits don't show.
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+fn dir_writable(dir: &Path) -> bool {
+    fs::metadata(dir).is_ok_and(|m| !m.permissions().readonly())
+}
+
+/// Sets `entries` in a `KEY=VALUE` file, replacing existing lines for those keys
+/// and keeping every other line (including comments) as is.
+pub(crate) fn upsert_env_file(path: &Path, entries: &[(&str, &str)]) -> Result<()> {
+    let raw = fs::read_to_string(path).unwrap_or_default();
+    let mut lines: Vec<String> = raw.lines().map(str::to_string).collect();
+    for (key, value) in entries {
+        let entry = format!("{key}={value}");
+        let existing = lines.iter_mut().find(|line| {
+            let line = line.trim_start();
+            line.strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+        });
+        match existing {
+            Some(line) => *line = entry,
+            None => lines.push(entry),
+        }
+    }
+    write_config_file(path, &(lines.join("\n") + "\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{config_dir_writable, upsert_env_file, write_config_file};
+
+    #[test]
+    fn write_config_file_reports_unwritable_paths() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("goldbot-config-test-{nanos}"));
+        // Checking a missing directory must not create it.
+        assert!(config_dir_writable(&dir.join("nested")));
+        assert!(!dir.exists());
+        write_config_file(&dir.join("nested/.env"), "A=1\n").unwrap();
+        assert!(config_dir_writable(&dir));
+
+        // A regular file where the config directory should be: fails even when run as root.
+        let blocker = dir.join("not-a-dir");
+        fs::write(&blocker, "").unwrap();
+        let target = blocker.join(".env");
+        let err = write_config_file(&target, "A=1\n").unwrap_err();
+        let message = format!("{err:#}");
+        assert!(message.starts_with("couldn't create "), "{message}");
+        assert!(message.contains("not-a-dir"), "{message}");
+        assert!(!config_dir_writable(&blocker));
+        assert!(upsert_env_file(&target, &[("A", "1")]).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
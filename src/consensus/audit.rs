@@ -5,13 +5,16 @@ use std::{
 };
 
 use anyhow::{Context, Result};
-use chrono::Local;
+use chrono::{DateTime, Local};
+use serde::Deserialize;
 use serde_json::json;
 
 use crate::types::{AuditEventKind, ConsensusTrigger, ExecutorOutcome, Mode};
 
 const SUMMARY_LIMIT_CHARS: usize = 600;
 const COMMAND_LIMIT_CHARS: usize = 260;
+/// 时间线中每条事件摘要保留的字符数。
+const TIMELINE_SUMMARY_CHARS: usize = 120;
 
 #[derive(Debug, Clone)]
 pub struct AuditLogger {
@@ -33,6 +36,32 @@ pub struct AuditRecord<'a> {
     pub error_code: Option<&'a str>,
 }
 
+/// `GE_LOG.jsonl` 中的一行，`AuditLogger::write` 的反序列化形式。
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditEntry {
+    #[serde(default)]
+    pub ts: String,
+    #[serde(default)]
+    pub run_id: String,
+    #[serde(default)]
+    pub event: String,
+    pub todo_id: Option<String>,
+    pub trigger: Option<String>,
+    pub executor: Option<String>,
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub status: String,
+    pub summary: Option<String>,
+    pub error_code: Option<String>,
+}
+
+/// 解析审计日志；损坏的行直接跳过。
+pub fn parse_entries(raw: &str) -> Vec<AuditEntry> {
+    raw.lines()
+        .filter_map(|line| serde_json::from_str(line.trim()).ok())
+        .collect()
+}
+
 impl AuditLogger {
     pub fn new(consensus_path: &Path) -> Self {
         let dir = consensus_path.parent().unwrap_or_else(|| Path::new("."));
@@ -105,3 +134,216 @@ fn mode_str(mode: Mode) -> &'static str {
         Mode::GeIdle => "GeIdle",
     }
 }
+
+// ── Timeline ──────────────────────────────────────────────────────────────────
+
+enum TimelineItem {
+    /// 不属于任何 todo 的事件；连续相同的事件折叠为一条并计数。
+    Run {
+        label: String,
+        count: usize,
+    },
+    Todo(usize),
+}
+
+struct TodoTimeline {
+    id: String,
+    lines: Vec<String>,
+}
+
+/// `GE timeline [todo-id]`：读取工作区的审计日志并渲染为时间线。
+pub fn timeline_lines(cwd: &Path, todo_filter: Option<&str>) -> Vec<String> {
+    let path = AuditLogger::new(&crate::consensus::model::consensus_file_path(cwd))
+        .path()
+        .to_path_buf();
+    match fs::read_to_string(&path) {
+        Ok(raw) => render_timeline(&parse_entries(&raw), todo_filter),
+        Err(_) => vec![format!("  GE: no audit log at `{}`.", path.display())],
+    }
+}
+
+/// 按运行分段、按 todo 分组的时间线；周期扫描等重复事件会被折叠。
+pub fn render_timeline(entries: &[AuditEntry], todo_filter: Option<&str>) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    while start < entries.len() {
+        let run_id = &entries[start].run_id;
+        let end = entries[start..]
+            .iter()
+            .position(|e| e.run_id != *run_id)
+            .map_or(entries.len(), |n| start + n);
+        render_run(&entries[start..end], todo_filter, &mut lines);
+        start = end;
+    }
+    if lines.is_empty() {
+        let msg = match todo_filter {
+            Some(id) => format!("  GE: no audit records for todo `{id}`."),
+            None => "  GE: audit log is empty.".to_string(),
+        };
+        lines.push(msg);
+    }
+    lines
+}
+
+fn render_run(entries: &[AuditEntry], todo_filter: Option<&str>, out: &mut Vec<String>) {
+    let mut items: Vec<TimelineItem> = Vec::new();
+    let mut todos: Vec<TodoTimeline> = Vec::new();
+    for entry in entries {
+        match entry.todo_id.as_deref().filter(|id| !id.is_empty()) {
+            Some(id) => {
+                if todo_filter.is_some_and(|f| f != id) {
+                    continue;
+                }
+                let idx = match todos.iter().position(|t| t.id == id) {
+                    Some(idx) => idx,
+                    None => {
+                        todos.push(TodoTimeline {
+                            id: id.to_string(),
+                            lines: Vec::new(),
+                        });
+                        items.push(TimelineItem::Todo(todos.len() - 1));
+                        todos.len() - 1
+                    }
+                };
+                todos[idx].lines.push(timeline_event(entry));
+            }
+            None if todo_filter.is_some() => {}
+            None => {
+                let label = timeline_event(entry);
+                match items.last_mut() {
+                    Some(TimelineItem::Run { label: last, count })
+                        if collapse_key(last) == collapse_key(&label) =>
+                    {
+                        *count += 1
+                    }
+                    _ => items.push(TimelineItem::Run { label, count: 1 }),
+                }
+            }
+        }
+    }
+    if items.is_empty() {
+        return;
+    }
+
+    let run_id = entries.first().map_or("", |e| e.run_id.as_str());
+    out.push(format!(
+        "  ── run {} ──",
+        if run_id.is_empty() { "?" } else { run_id }
+    ));
+    for item in items {
+        match item {
+            TimelineItem::Run { label, count: 1 } => out.push(format!("  {label}")),
+            TimelineItem::Run { label, count } => out.push(format!("  {label} (×{count})")),
+            TimelineItem::Todo(idx) => {
+                let todo = &todos[idx];
+                out.push(format!("  todo {}", todo.id));
+                out.extend(todo.lines.iter().map(|line| format!("    {line}")));
+            }
+        }
+    }
+}
+
+/// 折叠比较时忽略开头的时间戳。
+fn collapse_key(label: &str) -> &str {
+    label.split_once(' ').map_or(label, |(_, rest)| rest)
+}
+
+fn timeline_event(entry: &AuditEntry) -> String {
+    let time = DateTime::parse_from_rfc3339(&entry.ts)
+        .map(|t| t.format("%H:%M:%S").to_string())
+        .unwrap_or_else(|_| entry.ts.clone());
+    let mut label = match entry.event.as_str() {
+        "todo_selected" => "selected".to_string(),
+        "claude_exec" => "claude exec".to_string(),
+        "codex_exec" => "codex review".to_string(),
+        "git_commit" => "commit".to_string(),
+        "todo_checked" => "checked ✓".to_string(),
+        "todo_deferred" => "deferred".to_string(),
+        "trigger" => format!("trigger: {}", entry.trigger.as_deref().unwrap_or("?")),
+        "preflight" => format!("preflight: {}", entry.executor.as_deref().unwrap_or("?")),
+        other => other.replace('_', " "),
+    };
+    let mut detail = Vec::new();
+    if !entry.status.is_empty() && entry.status != "success" {
+        detail.push(entry.status.clone());
+    }
+    if let Some(code) = entry.exit_code {
+        detail.push(format!("exit {code}"));
+    }
+    if let Some(code) = entry.error_code.as_deref() {
+        detail.push(code.to_string());
+    }
+    if !detail.is_empty() {
+        label.push_str(&format!(" ({})", detail.join(", ")));
+    }
+    let show_summary = matches!(
+        entry.event.as_str(),
+        "todo_selected" | "todo_deferred" | "error"
+    ) || (!entry.status.is_empty() && entry.status != "success");
+    if show_summary
+        && let Some(summary) = entry.summary.as_deref()
+        && let Some(first) = summary.lines().find(|l| !l.trim().is_empty())
+    {
+        label.push_str(&format!(
+            " — {}",
+            truncate_chars(first.trim(), TIMELINE_SUMMARY_CHARS)
+        ));
+    }
+    format!("{time} {label}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_entries, render_timeline};
+
+    fn record(ts: &str, event: &str, todo: Option<&str>, extra: &str) -> String {
+        let todo = todo.map_or("null".to_string(), |t| format!("\"{t}\""));
+        format!(
+            r#"{{"ts":"2026-03-01T{ts}+08:00","run_id":"ge-1","event":"{event}","todo_id":{todo},"status":"success"{extra}}}"#
+        )
+    }
+
+    #[test]
+    fn timeline_groups_by_todo_and_collapses_periodic_scans() {
+        let raw = [
+            record("10:00:00", "trigger", None, r#","trigger":"manual""#),
+            record("10:00:01", "todo_selected", Some("T1"), r#","summary":"Add CLI flag""#),
+            record("10:00:02", "todo_selected", Some("T2"), r#","summary":"Write docs""#),
+            record("10:01:00", "claude_exec", Some("T1"), r#","exit_code":0"#),
+            record("10:02:00", "trigger", None, r#","trigger":"periodic""#),
+            record("10:03:00", "trigger", None, r#","trigger":"periodic""#),
+            record("10:04:00", "codex_exec", Some("T1"), r#","exit_code":0"#),
+            record("10:05:00", "validation", Some("T1"), ""),
+            record("10:05:30", "git_commit", Some("T1"), ""),
+            record("10:05:31", "todo_checked", Some("T1"), ""),
+            r#"{"ts":"2026-03-01T10:06:00+08:00","run_id":"ge-1","event":"todo_deferred","todo_id":"T2","status":"failed","summary":"validation failed: cargo test\nmore"}"#.to_string(),
+            "not json".to_string(),
+        ]
+        .join("\n");
+        let entries = parse_entries(&raw);
+        assert_eq!(entries.len(), 11);
+
+        assert_eq!(
+            render_timeline(&entries, None),
+            [
+                "  ── run ge-1 ──",
+                "  10:00:00 trigger: manual",
+                "  todo T1",
+                "    10:00:01 selected — Add CLI flag",
+                "    10:01:00 claude exec (exit 0)",
+                "    10:04:00 codex review (exit 0)",
+                "    10:05:00 validation",
+                "    10:05:30 commit",
+                "    10:05:31 checked ✓",
+                "  todo T2",
+                "    10:00:02 selected — Write docs",
+                "    10:06:00 deferred (failed) — validation failed: cargo test",
+                "  10:02:00 trigger: periodic (×2)",
+            ]
+        );
+
+        let only_t2 = render_timeline(&entries, Some("T2"));
+        assert_eq!(only_t2.len(), 4);
+        assert_eq!(only_t2[1], "  todo T2");
+    }
+}
@@ -1,8 +1,8 @@
 use std::{
-    collections::hash_map::DefaultHasher,
+    collections::{BTreeSet, HashMap, hash_map::DefaultHasher},
     fs,
     hash::{Hash, Hasher},
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{
         Arc,
         atomic::{AtomicBool, Ordering},
@@ -10,16 +10,20 @@ use std::{
     time::{Duration, Instant},
 };
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use chrono::Local;
 use serde_json::Value;
+use similar::TextDiff;
 
 use crate::{
     consensus::{
         audit::{AuditLogger, AuditRecord},
         evaluate::{
             ExecDecision, ReviewDecision, claude_exec_decision, codex_review_decision, commit_todo,
-            latest_commit_context, self_review, validate_done_when,
+            deferred_branch_name, delete_deferred_branch, detect_project_checks, dirty_paths,
+            git_context_depth, head_commit, latest_commit_context, self_review,
+            stash_deferred_changes, stash_deferred_enabled, todo_commit_landed, tracked_file_diff,
+            validate_done_when,
         },
         external::{
             ExecutorRun, build_clarify_questions_prompt, build_claude_prompt,
@@ -27,7 +31,14 @@ use crate::{
             build_followup_clarify_questions_prompt, build_todo_planner_prompt, preflight,
             run_claude, run_codex_execute, summarize_output,
         },
-        model::{ConsensusDoc, TodoItem, build_from_interview, consensus_file_path, load, save},
+        inflight::{
+            InflightMarker, InflightStage, ResumeAction, clear_inflight, read_inflight,
+            resume_action, write_inflight,
+        },
+        model::{
+            ConsensusDoc, TodoItem, build_from_interview, consensus_file_path, load, load_lenient,
+            save,
+        },
     },
     types::{AuditEventKind, ConsensusTrigger, ExecutorOutcome, GeQuestionStep, Mode},
 };
@@ -36,9 +47,23 @@ const PERIODIC_SCAN_INTERVAL: Duration = Duration::from_secs(30 * 60);
 const FILE_SCAN_INTERVAL: Duration = Duration::from_secs(5);
 const IDLE_TICK_INTERVAL: Duration = Duration::from_secs(2);
 const MAX_CLARIFY_ROUNDS: usize = 4;
+/// 工作区无法识别构建系统时，生成 todo 的兜底 done_when。
+const REVIEW_DONE_WHEN: &str = "Completed and verified by Codex review";
 const MAX_CLARIFY_QUESTIONS_PER_BATCH: usize = 8;
+/// GE 执行器输出的默认预览预算，可通过 `GOLDBOT_GE_PREVIEW_CHARS` / `GOLDBOT_GE_PREVIEW_LINES` 覆盖。
 const EXECUTOR_OUTPUT_PREVIEW_CHARS: usize = 2800;
 const EXECUTOR_PREVIEW_MAX_LINES: usize = 40;
+const EXECUTOR_PREVIEW_CHARS_RANGE: (usize, usize) = (200, 20_000);
+const EXECUTOR_PREVIEW_LINES_RANGE: (usize, usize) = (1, 200);
+/// `GE diff` 在控制台显示的最多行数。
+const CONSENSUS_DIFF_MAX_LINES: usize = 400;
+/// Claude 遇到限流时同一 todo 的最多自动重试次数；默认 0 表示沿用原有的回退/延期流程。
+const ENV_RATE_LIMIT_RETRIES: &str = "GOLDBOT_GE_RATE_LIMIT_RETRIES";
+const RATE_LIMIT_RETRIES_MAX: u32 = 20;
+/// 没有 retry-after 提示时的首次等待，之后每次翻倍。
+const RATE_LIMIT_BASE_DELAY: Duration = Duration::from_secs(60);
+/// 单次等待上限（包括 retry-after 提示给出的时间）。
+const RATE_LIMIT_MAX_DELAY: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Debug, Clone)]
 struct InterviewState {
@@ -88,6 +113,9 @@ pub struct GeRuntime {
     logger: AuditLogger,
     interview: Option<InterviewState>,
     last_hash: Option<u64>,
+    /// `last_hash` 对应的文件内容，以及它之前的一版；未被 git 跟踪时 `GE diff` 与上一版比较。
+    last_content: Option<String>,
+    previous_content: Option<String>,
     next_periodic_scan: Instant,
     next_file_scan: Instant,
     next_action: Instant,
@@ -96,6 +124,20 @@ pub struct GeRuntime {
     preflight_done: bool,
     last_prompt: Option<PromptSnapshot>,
     last_result: Option<ResultSnapshot>,
+    /// 因限流而排队重试的 todo：id -> (已重试次数, 下次重试时间)。
+    rate_limit_retries: HashMap<String, RateLimitRetry>,
+    /// `GE run <todo-id>` 指定的下一个 todo，只在下一次 run_once 中生效。
+    next_todo_override: Option<String>,
+    /// 崩溃前已通过审查与校验、只差提交的 todo：下一次 run_once 直接从提交阶段继续。
+    resume_commit: Option<String>,
+    /// 当前 todo 执行前工作区已有改动的路径；延期暂存时不动这些用户改动。
+    pre_exec_dirty: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RateLimitRetry {
+    attempts: u32,
+    due: Instant,
 }
 
 impl GeRuntime {
@@ -115,6 +157,8 @@ impl GeRuntime {
             logger,
             interview: None,
             last_hash: None,
+            last_content: None,
+            previous_content: None,
             next_periodic_scan: now + PERIODIC_SCAN_INTERVAL,
             next_file_scan: now + FILE_SCAN_INTERVAL,
             next_action: now,
@@ -123,6 +167,10 @@ impl GeRuntime {
             preflight_done: false,
             last_prompt: None,
             last_result: None,
+            rate_limit_retries: HashMap::new(),
+            next_todo_override: None,
+            resume_commit: None,
+            pre_exec_dirty: BTreeSet::new(),
         };
 
         runtime.log(AuditRecord {
@@ -139,8 +187,9 @@ impl GeRuntime {
         });
 
         if runtime.consensus_path.exists() {
-            let doc = load(&runtime.consensus_path)?;
-            runtime.last_hash = hash_file(&runtime.consensus_path).ok();
+            let mut doc = load(&runtime.consensus_path)?;
+            runtime.recover_inflight(&mut doc, &mut lines)?;
+            runtime.remember_consensus();
             runtime.mode = if doc.all_done() {
                 Mode::GeIdle
             } else {
@@ -483,7 +532,6 @@ impl GeRuntime {
         if now < self.next_action {
             return Ok(());
         }
-
         if let Some(trigger) = self.pending_trigger.take() {
             self.run_once(trigger, &mut emit)?;
             return Ok(());
@@ -499,7 +547,7 @@ impl GeRuntime {
             self.next_file_scan = now + FILE_SCAN_INTERVAL;
             let current_hash = hash_file(&self.consensus_path).ok();
             if current_hash.is_some() && current_hash != self.last_hash {
-                self.last_hash = current_hash;
+                self.remember_consensus();
                 self.run_once(ConsensusTrigger::FileChanged, &mut emit)?;
                 return Ok(());
             }
@@ -511,6 +559,45 @@ impl GeRuntime {
         Ok(())
     }
 
+    /// 限流后不延期 todo，而是记录重试时间并在到期后以同一触发重跑。
+    fn schedule_rate_limit_retry<F>(
+        &mut self,
+        todo_id: &str,
+        attempts: u32,
+        delay: Duration,
+        trigger: ConsensusTrigger,
+        emit: &mut F,
+    ) where
+        F: FnMut(String),
+    {
+        let max_retries = rate_limit_max_retries();
+        self.rate_limit_retries.insert(
+            todo_id.to_string(),
+            RateLimitRetry {
+                attempts,
+                due: Instant::now() + delay,
+            },
+        );
+        self.pending_trigger = Some(trigger);
+        let summary = format!(
+            "Claude rate limited; retry {attempts}/{max_retries} in {}s.",
+            delay.as_secs()
+        );
+        emit_line(emit, format!("  {todo_id} {summary}"));
+        self.log(AuditRecord {
+            mode: self.mode,
+            event: AuditEventKind::RateLimitWait,
+            todo_id: Some(todo_id),
+            trigger: Some(trigger),
+            executor: Some("claude"),
+            command: None,
+            exit_code: None,
+            status: ExecutorOutcome::Failed,
+            summary: Some(&summary),
+            error_code: Some("rate_limit"),
+        });
+    }
+
     pub fn exit(&mut self) -> Vec<String> {
         self.log(AuditRecord {
             mode: self.mode,
@@ -563,6 +650,32 @@ impl GeRuntime {
         lines
     }
 
+    /// `GE diff`：CONSENSUS.md 相对 HEAD 的改动；文件未被 git 跟踪时与上一次记录的内容比较。
+    pub fn diff_consensus(&self) -> Vec<String> {
+        let Ok(current) = fs::read_to_string(&self.consensus_path) else {
+            return vec!["  GE: no CONSENSUS.md to diff yet.".to_string()];
+        };
+        if let Some(diff) = tracked_file_diff(&self.cwd, &self.consensus_path) {
+            return consensus_diff_lines(&diff, "HEAD");
+        }
+        let before = self
+            .previous_content
+            .as_deref()
+            .or(self.last_content.as_deref())
+            .unwrap_or_default();
+        render_consensus_diff(before, &current, "previous snapshot")
+    }
+
+    /// 记录 CONSENSUS.md 的当前哈希；内容变了时把上一版留下来给 `GE diff` 用。
+    fn remember_consensus(&mut self) {
+        self.last_hash = hash_file(&self.consensus_path).ok();
+        let content = fs::read_to_string(&self.consensus_path).ok();
+        if content != self.last_content {
+            self.previous_content = self.last_content.take();
+            self.last_content = content;
+        }
+    }
+
     pub fn replan_todos(&mut self) -> Result<(bool, Vec<String>)> {
         let mut lines = Vec::new();
         let mut doc = load(&self.consensus_path)?;
@@ -588,7 +701,7 @@ impl GeRuntime {
         doc.append_status(format!("- {} Todos replanned to finer steps.", now_hms()));
         doc.append_journal(format!("- {} todo replan: {}", now_hms(), note));
         save(&self.consensus_path, &doc)?;
-        self.last_hash = hash_file(&self.consensus_path).ok();
+        self.remember_consensus();
         self.pending_trigger = Some(ConsensusTrigger::Manual);
         self.next_action = Instant::now();
         self.mode = if doc.all_done() {
@@ -600,6 +713,72 @@ impl GeRuntime {
         Ok((true, lines))
     }
 
+    /// `GE run <todo-id>`：下一轮直接执行指定的未完成 todo，而不是第一个未完成的。
+    pub fn run_todo(&mut self, id: &str) -> Result<Vec<String>> {
+        if self.mode == Mode::GeInterview {
+            bail!("GE is still planning; finish the interview before `GE run <todo-id>`.");
+        }
+        let doc = load(&self.consensus_path)?;
+        let Some(todo) = doc.todos.iter().find(|t| t.id.eq_ignore_ascii_case(id)) else {
+            bail!("todo `{id}` not found in CONSENSUS.md");
+        };
+        if todo.checked {
+            bail!(
+                "todo `{}` is already checked; uncheck it in CONSENSUS.md to run it again",
+                todo.id
+            );
+        }
+        self.log(AuditRecord {
+            mode: self.mode,
+            event: AuditEventKind::GeInput,
+            todo_id: Some(&todo.id),
+            trigger: Some(ConsensusTrigger::Manual),
+            executor: Some("goldbot"),
+            command: None,
+            exit_code: None,
+            status: ExecutorOutcome::Success,
+            summary: Some("Manual resume at this todo."),
+            error_code: None,
+        });
+        self.next_todo_override = Some(todo.id.clone());
+        self.pending_trigger = Some(ConsensusTrigger::Manual);
+        self.next_action = Instant::now();
+        self.mode = Mode::GeRun;
+        Ok(vec![format!(
+            "  GE: next run starts at {} {}",
+            todo.id, todo.text
+        )])
+    }
+
+    /// 本轮要执行的 todo：优先消费 `GE run` 指定的（仍未完成时），否则取第一个未完成、
+    /// 也不在等待限流重试的；其它触发不会抢先重跑正在等待的 todo。
+    fn take_next_todo_index(&mut self, doc: &ConsensusDoc) -> Option<usize> {
+        let requested = self
+            .next_todo_override
+            .take()
+            .and_then(|id| doc.todos.iter().position(|t| t.id == id && !t.checked));
+        let now = Instant::now();
+        requested.or_else(|| {
+            doc.todos.iter().position(|t| {
+                !t.checked
+                    && self
+                        .rate_limit_retries
+                        .get(&t.id)
+                        .is_none_or(|retry| now >= retry.due)
+            })
+        })
+    }
+
+    /// 尚未到期的限流重试中最早的一个。
+    fn earliest_retry_due(&self) -> Option<Instant> {
+        let now = Instant::now();
+        self.rate_limit_retries
+            .values()
+            .map(|retry| retry.due)
+            .filter(|due| now < *due)
+            .min()
+    }
+
     fn ask_current_clarify_question(&self) -> Option<Vec<String>> {
         let interview = self.interview.as_ref()?;
         if interview.step != GeQuestionStep::Clarify {
@@ -662,7 +841,7 @@ impl GeRuntime {
             truncate_text(&note, 220)
         ));
         save(&self.consensus_path, &doc)?;
-        self.last_hash = hash_file(&self.consensus_path).ok();
+        self.remember_consensus();
         self.mode = if doc.all_done() {
             Mode::GeIdle
         } else {
@@ -732,8 +911,28 @@ impl GeRuntime {
             emit_line(emit, "  GE hard exit requested; aborting current step.");
             return Ok(());
         }
-        let mut doc = match load(&self.consensus_path) {
-            Ok(d) => d,
+        let mut doc = match load_lenient(&self.consensus_path) {
+            Ok(loaded) => {
+                let mut doc = loaded.doc;
+                if !loaded.issues.is_empty() {
+                    // 文档可恢复：写回修复后的版本并在 journal 中记录
+                    doc.append_journal(format!(
+                        "- {} Repaired CONSENSUS.md: {}",
+                        now_hms(),
+                        loaded.issues.join("; ")
+                    ));
+                    save(&self.consensus_path, &doc)?;
+                    self.remember_consensus();
+                    emit_line(
+                        emit,
+                        format!(
+                            "  GE: repaired CONSENSUS.md ({} issue(s)); see Bot Journal.",
+                            loaded.issues.len()
+                        ),
+                    );
+                }
+                doc
+            }
             Err(e) => {
                 self.mode = Mode::GeInterview;
                 self.interview = Some(InterviewState {
@@ -755,10 +954,10 @@ impl GeRuntime {
                     command: None,
                     exit_code: None,
                     status: ExecutorOutcome::Failed,
-                    summary: Some("CONSENSUS.md missing; switching to interview."),
+                    summary: Some("CONSENSUS.md missing or unrecoverable; switching to interview."),
                     error_code: Some("missing_consensus"),
                 });
-                emit_line(emit, format!("  GE: {}", e.to_string().trim()));
+                emit_line(emit, format!("  GE: {e:#}"));
                 emit_line(emit, "  GE Q1/3: What is the purpose/goal?");
                 return Ok(());
             }
@@ -781,14 +980,20 @@ impl GeRuntime {
             self.mode = Mode::GeIdle;
             doc.append_status(format!("- {} All todos completed. Waiting.", now_hms()));
             save(&self.consensus_path, &doc)?;
-            self.last_hash = hash_file(&self.consensus_path).ok();
+            self.remember_consensus();
             self.next_action = Instant::now() + IDLE_TICK_INTERVAL;
             emit_line(emit, "  GE idle: all todos completed.");
             return Ok(());
         }
 
         self.mode = Mode::GeRun;
-        let Some(todo_idx) = doc.first_open_todo_index() else {
+        let Some(todo_idx) = self.take_next_todo_index(&doc) else {
+            // 剩下的 todo 都在等限流重试：到最早的重试时间再以同一触发重跑
+            if let Some(due) = self.earliest_retry_due() {
+                self.pending_trigger = Some(trigger);
+                self.next_action = due;
+                return Ok(());
+            }
             self.mode = Mode::GeIdle;
             self.next_action = Instant::now() + IDLE_TICK_INTERVAL;
             return Ok(());
@@ -807,7 +1012,19 @@ impl GeRuntime {
             summary: Some(&todo.text),
             error_code: None,
         });
-        let git_context = latest_commit_context();
+        let head = head_commit(&self.cwd);
+        if stash_deferred_enabled() {
+            self.pre_exec_dirty = dirty_paths(&self.cwd).unwrap_or_default();
+        }
+        if self.resume_commit.take().is_some_and(|id| id == todo.id) {
+            emit_line(
+                emit,
+                format!("  {} resuming at git commit after interruption.", todo.id),
+            );
+            let work_summary = "exec+review: completed before interruption";
+            return self.commit_and_check(doc, &todo, trigger, head.as_deref(), work_summary, emit);
+        }
+        let git_context = latest_commit_context(git_context_depth());
 
         let claude_prompt = build_claude_prompt(
             &doc.purpose_lines,
@@ -826,6 +1043,7 @@ impl GeRuntime {
             git_context.as_deref(),
         );
 
+        self.mark_inflight(&todo.id, InflightStage::Execute, head.as_deref(), emit);
         emit_stage_header(emit, &todo.id, "Claude execute");
         self.cache_prompt(&todo.id, "Claude execute", &claude_prompt);
         emit_executor_prompt(emit, &todo.id, "Claude execute", &claude_prompt);
@@ -844,6 +1062,20 @@ impl GeRuntime {
             Some(&todo.id),
             execution.clone(),
         );
+        if execution.error_code.as_deref() == Some("rate_limit") {
+            let attempts = self
+                .rate_limit_retries
+                .get(&todo.id)
+                .map_or(0, |retry| retry.attempts);
+            if let Some(delay) =
+                rate_limit_retry_delay(attempts, rate_limit_max_retries(), &execution.output)
+            {
+                self.schedule_rate_limit_retry(&todo.id, attempts + 1, delay, trigger, emit);
+                return Ok(());
+            }
+            // 重试次数用完：走原有的 Codex 回退 / 延期流程
+        }
+        self.rate_limit_retries.remove(&todo.id);
 
         let mut claude_fallback_reason: Option<String> = None;
         let mut fallback_codex_opt: Option<ExecutorRun> = None;
@@ -903,6 +1135,8 @@ impl GeRuntime {
         if execution.outcome == ExecutorOutcome::BlockedConfirm {
             defer_todo(
                 &mut doc,
+                &self.cwd,
+                &self.pre_exec_dirty,
                 &todo.id,
                 format!("- {} {} blocked by manual confirm.", now_hms(), todo.id),
             );
@@ -922,8 +1156,7 @@ impl GeRuntime {
                 summary: Some("Execution blocked by manual confirm."),
                 error_code: Some("manual_confirm"),
             });
-            save(&self.consensus_path, &doc)?;
-            self.last_hash = hash_file(&self.consensus_path).ok();
+            self.save_todo_outcome(&doc)?;
             self.next_action = Instant::now() + PERIODIC_SCAN_INTERVAL;
             return Ok(());
         }
@@ -931,6 +1164,8 @@ impl GeRuntime {
         if !execution.ok() {
             defer_todo(
                 &mut doc,
+                &self.cwd,
+                &self.pre_exec_dirty,
                 &todo.id,
                 format!(
                     "- {} {} execution failed: {}",
@@ -940,12 +1175,12 @@ impl GeRuntime {
                 ),
             );
             emit_line(emit, format!("  {} deferred: execution failed.", todo.id));
-            save(&self.consensus_path, &doc)?;
-            self.last_hash = hash_file(&self.consensus_path).ok();
+            self.save_todo_outcome(&doc)?;
             self.next_action = Instant::now() + IDLE_TICK_INTERVAL;
             return Ok(());
         }
 
+        self.mark_inflight(&todo.id, InflightStage::Review, head.as_deref(), emit);
         emit_stage_header(emit, &todo.id, "Codex optimize+review");
         let codex_opt = if let Some(run) = fallback_codex_opt.take() {
             emit_line(
@@ -978,6 +1213,8 @@ impl GeRuntime {
         if codex_opt.outcome == ExecutorOutcome::BlockedConfirm {
             defer_todo(
                 &mut doc,
+                &self.cwd,
+                &self.pre_exec_dirty,
                 &todo.id,
                 format!(
                     "- {} {} blocked by codex optimize+review confirm.",
@@ -992,14 +1229,15 @@ impl GeRuntime {
                     todo.id
                 ),
             );
-            save(&self.consensus_path, &doc)?;
-            self.last_hash = hash_file(&self.consensus_path).ok();
+            self.save_todo_outcome(&doc)?;
             self.next_action = Instant::now() + PERIODIC_SCAN_INTERVAL;
             return Ok(());
         }
         if !codex_opt.ok() {
             defer_todo(
                 &mut doc,
+                &self.cwd,
+                &self.pre_exec_dirty,
                 &todo.id,
                 format!("- {} {} codex optimize+review failed.", now_hms(), todo.id),
             );
@@ -1007,8 +1245,7 @@ impl GeRuntime {
                 emit,
                 format!("  {} deferred: Codex optimize+review failed.", todo.id),
             );
-            save(&self.consensus_path, &doc)?;
-            self.last_hash = hash_file(&self.consensus_path).ok();
+            self.save_todo_outcome(&doc)?;
             self.next_action = Instant::now() + IDLE_TICK_INTERVAL;
             return Ok(());
         }
@@ -1017,6 +1254,8 @@ impl GeRuntime {
         if let ReviewDecision::Blocked(reason) = review_decision {
             defer_todo(
                 &mut doc,
+                &self.cwd,
+                &self.pre_exec_dirty,
                 &todo.id,
                 format!(
                     "- {} {} codex optimize+review reported blockers: {}",
@@ -1047,14 +1286,20 @@ impl GeRuntime {
                     todo.id, reason
                 ),
             );
-            save(&self.consensus_path, &doc)?;
-            self.last_hash = hash_file(&self.consensus_path).ok();
+            self.save_todo_outcome(&doc)?;
             self.next_action = Instant::now() + IDLE_TICK_INTERVAL;
             return Ok(());
         }
 
         emit_line(emit, format!("  {} done_when validation started.", todo.id));
-        let validation = validate_done_when(&todo.done_when, &self.cwd);
+        let validation = validate_done_when(&todo.done_when, &self.cwd, &self.cancel_flag);
+        if validation.outcome == ExecutorOutcome::Cancelled {
+            emit_line(
+                emit,
+                format!("  {} hard-exit requested; validation cancelled.", todo.id),
+            );
+            return Ok(());
+        }
         self.log(AuditRecord {
             mode: self.mode,
             event: AuditEventKind::Validation,
@@ -1070,6 +1315,8 @@ impl GeRuntime {
         if validation.outcome != ExecutorOutcome::Success {
             defer_todo(
                 &mut doc,
+                &self.cwd,
+                &self.pre_exec_dirty,
                 &todo.id,
                 format!(
                     "- {} {} validation failed: {}",
@@ -1082,14 +1329,20 @@ impl GeRuntime {
                 emit,
                 format!("  {} deferred: done_when validation failed.", todo.id),
             );
-            save(&self.consensus_path, &doc)?;
-            self.last_hash = hash_file(&self.consensus_path).ok();
+            self.save_todo_outcome(&doc)?;
             self.next_action = Instant::now() + IDLE_TICK_INTERVAL;
             return Ok(());
         }
 
         emit_line(emit, format!("  {} GoldBot self-review started.", todo.id));
-        let self_review_report = self_review(&self.cwd);
+        let self_review_report = self_review(&self.cwd, &self.cancel_flag);
+        if self_review_report.outcome == ExecutorOutcome::Cancelled {
+            emit_line(
+                emit,
+                format!("  {} hard-exit requested; self-review cancelled.", todo.id),
+            );
+            return Ok(());
+        }
         self.log(AuditRecord {
             mode: self.mode,
             event: AuditEventKind::SelfReview,
@@ -1105,6 +1358,8 @@ impl GeRuntime {
         if self_review_report.outcome != ExecutorOutcome::Success {
             defer_todo(
                 &mut doc,
+                &self.cwd,
+                &self.pre_exec_dirty,
                 &todo.id,
                 format!(
                     "- {} {} self review failed: {}",
@@ -1117,14 +1372,42 @@ impl GeRuntime {
                 emit,
                 format!("  {} deferred: GoldBot self-review failed.", todo.id),
             );
-            save(&self.consensus_path, &doc)?;
-            self.last_hash = hash_file(&self.consensus_path).ok();
+            self.save_todo_outcome(&doc)?;
             self.next_action = Instant::now() + IDLE_TICK_INTERVAL;
             return Ok(());
         }
 
+        let work_summary = format!(
+            "exec: {} | codex(opt+review): {}",
+            summarize_output(&execution.output, 2),
+            summarize_output(&codex_opt.output, 2)
+        );
+        self.commit_and_check(doc, &todo, trigger, head.as_deref(), &work_summary, emit)
+    }
+
+    /// 执行、审查与校验都通过后：提交、勾选 todo 并保存；崩溃恢复时也从这里接续。
+    fn commit_and_check<F>(
+        &mut self,
+        mut doc: ConsensusDoc,
+        todo: &TodoItem,
+        trigger: ConsensusTrigger,
+        head: Option<&str>,
+        work_summary: &str,
+        emit: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(String),
+    {
+        self.mark_inflight(&todo.id, InflightStage::Commit, head, emit);
         emit_line(emit, format!("  {} Git commit started.", todo.id));
-        let commit = commit_todo(&todo.id, &todo.text);
+        let commit = commit_todo(&self.cwd, &todo.id, &todo.text, &self.cancel_flag);
+        if commit.outcome == ExecutorOutcome::Cancelled {
+            emit_line(
+                emit,
+                format!("  {} hard-exit requested; git commit cancelled.", todo.id),
+            );
+            return Ok(());
+        }
         self.log(AuditRecord {
             mode: self.mode,
             event: AuditEventKind::GitCommit,
@@ -1140,6 +1423,8 @@ impl GeRuntime {
         if commit.outcome != ExecutorOutcome::Success {
             defer_todo(
                 &mut doc,
+                &self.cwd,
+                &self.pre_exec_dirty,
                 &todo.id,
                 format!(
                     "- {} {} git commit failed: {}",
@@ -1149,20 +1434,25 @@ impl GeRuntime {
                 ),
             );
             emit_line(emit, format!("  {} deferred: git commit failed.", todo.id));
-            save(&self.consensus_path, &doc)?;
-            self.last_hash = hash_file(&self.consensus_path).ok();
+            self.save_todo_outcome(&doc)?;
             self.next_action = Instant::now() + IDLE_TICK_INTERVAL;
             return Ok(());
         }
 
         let _ = doc.mark_checked(&todo.id);
         doc.append_status(format!("- {} {} checked.", now_hms(), todo.id));
+        if delete_deferred_branch(&self.cwd, &todo.id) {
+            doc.append_journal(format!(
+                "- {} {} removed branch `{}` after successful retry.",
+                now_hms(),
+                todo.id,
+                deferred_branch_name(&todo.id)
+            ));
+        }
         doc.append_journal(format!(
-            "- {} {} done. exec: {} | codex(opt+review): {} | commit: {}",
+            "- {} {} done. {work_summary} | commit: {}",
             now_hms(),
             todo.id,
-            summarize_output(&execution.output, 2),
-            summarize_output(&codex_opt.output, 2),
             commit.summary
         ));
         self.log(AuditRecord {
@@ -1177,8 +1467,7 @@ impl GeRuntime {
             summary: Some("Todo checked after validation and review."),
             error_code: None,
         });
-        save(&self.consensus_path, &doc)?;
-        self.last_hash = hash_file(&self.consensus_path).ok();
+        self.save_todo_outcome(&doc)?;
         self.pending_trigger = Some(ConsensusTrigger::TaskDone);
         self.next_action = Instant::now() + IDLE_TICK_INTERVAL;
         emit_line(emit, format!("  {} checked.", todo.id));
@@ -1385,7 +1674,7 @@ impl GeRuntime {
             );
         }
         if claude.ok()
-            && let Some(generated) = parse_consensus_payload_json(&claude.output)
+            && let Some(generated) = parse_consensus_payload_json(&claude.output, &self.cwd)
         {
             return (
                 build_consensus_doc_from_generated(generated, purpose, rules, scope),
@@ -1416,7 +1705,7 @@ impl GeRuntime {
             );
         }
         if codex.ok()
-            && let Some(generated) = parse_consensus_payload_json(&codex.output)
+            && let Some(generated) = parse_consensus_payload_json(&codex.output, &self.cwd)
         {
             return (
                 build_consensus_doc_from_generated(generated, purpose, rules, scope),
@@ -1460,7 +1749,7 @@ impl GeRuntime {
         );
         self.log_executor_run(AuditEventKind::TodoPlanGenerated, None, claude.clone());
         if claude.ok()
-            && let Some(todos) = parse_todo_plan_json(&claude.output)
+            && let Some(todos) = parse_todo_plan_json(&claude.output, &self.cwd)
         {
             return (todos, "generated by claude planner".to_string());
         }
@@ -1482,7 +1771,7 @@ impl GeRuntime {
         );
         self.log_executor_run(AuditEventKind::TodoPlanGenerated, None, codex.clone());
         if codex.ok()
-            && let Some(todos) = parse_todo_plan_json(&codex.output)
+            && let Some(todos) = parse_todo_plan_json(&codex.output, &self.cwd)
         {
             return (todos, "generated by codex planner (fallback)".to_string());
         }
@@ -1493,6 +1782,97 @@ impl GeRuntime {
         )
     }
 
+    /// 进入破坏性阶段前写下在途标记，崩溃重启后 `enter` 据此接续。
+    fn mark_inflight<F>(
+        &self,
+        todo_id: &str,
+        stage: InflightStage,
+        head: Option<&str>,
+        emit: &mut F,
+    ) where
+        F: FnMut(String),
+    {
+        let marker = InflightMarker {
+            todo_id: todo_id.to_string(),
+            stage,
+            head: head.map(str::to_string),
+        };
+        if let Err(e) = write_inflight(&self.consensus_path, &marker) {
+            emit_line(emit, format!("  GE: {e:#}"));
+        }
+    }
+
+    /// 保存 todo 的结果（勾选或延期）；保存成功后该 todo 不再在途。
+    fn save_todo_outcome(&mut self, doc: &ConsensusDoc) -> Result<()> {
+        save(&self.consensus_path, doc)?;
+        self.remember_consensus();
+        clear_inflight(&self.consensus_path);
+        Ok(())
+    }
+
+    /// 上次运行在破坏性阶段中途中断时，按在途标记与 git 记录决定如何接续。
+    fn recover_inflight(&mut self, doc: &mut ConsensusDoc, lines: &mut Vec<String>) -> Result<()> {
+        let Some(marker) = read_inflight(&self.consensus_path) else {
+            return Ok(());
+        };
+        let id = marker.todo_id.clone();
+        let stage = marker.stage.as_str();
+        let todo_checked = doc.todos.iter().find(|t| t.id == id).map(|t| t.checked);
+        let landed = todo_commit_landed(&self.cwd, &id, marker.head.as_deref());
+        let (summary, line) = match resume_action(&marker, todo_checked, landed) {
+            ResumeAction::Discard => {
+                clear_inflight(&self.consensus_path);
+                return Ok(());
+            }
+            ResumeAction::RecordCommit => {
+                doc.mark_checked(&id);
+                doc.append_status(format!("- {} {} checked.", now_hms(), id));
+                doc.append_journal(format!(
+                    "- {} {} recovered after interruption at {stage}: commit had already landed.",
+                    now_hms(),
+                    id
+                ));
+                save(&self.consensus_path, doc)?;
+                clear_inflight(&self.consensus_path);
+                (
+                    "Recovered interrupted todo: commit already landed.",
+                    format!(
+                        "  GE: {id} was interrupted at {stage} but its commit landed; checked."
+                    ),
+                )
+            }
+            ResumeAction::Commit => {
+                self.next_todo_override = Some(id.clone());
+                self.resume_commit = Some(id.clone());
+                (
+                    "Resuming interrupted todo at git commit.",
+                    format!("  GE: {id} was interrupted before its commit; resuming at commit."),
+                )
+            }
+            ResumeAction::Rerun => {
+                self.next_todo_override = Some(id.clone());
+                (
+                    "Re-running interrupted todo.",
+                    format!("  GE: {id} was interrupted at {stage}; re-running it first."),
+                )
+            }
+        };
+        self.log(AuditRecord {
+            mode: self.mode,
+            event: AuditEventKind::GeInput,
+            todo_id: Some(&id),
+            trigger: Some(ConsensusTrigger::Manual),
+            executor: Some("goldbot"),
+            command: None,
+            exit_code: None,
+            status: ExecutorOutcome::Success,
+            summary: Some(summary),
+            error_code: None,
+        });
+        lines.push(line);
+        Ok(())
+    }
+
     fn log_executor_run(&self, event: AuditEventKind, todo_id: Option<&str>, run: ExecutorRun) {
         self.log(AuditRecord {
             mode: self.mode,
@@ -1537,9 +1917,28 @@ fn now_hms() -> String {
     Local::now().format("%H:%M:%S").to_string()
 }
 
-fn defer_todo(doc: &mut ConsensusDoc, todo_id: &str, msg: String) {
+fn defer_todo(
+    doc: &mut ConsensusDoc,
+    cwd: &Path,
+    preexisting: &BTreeSet<String>,
+    todo_id: &str,
+    msg: String,
+) {
     doc.append_status(msg.clone());
     doc.append_journal(format!("- {}", msg.trim_start_matches("- ")));
+    if stash_deferred_enabled() {
+        match stash_deferred_changes(cwd, todo_id, preexisting) {
+            Ok(Some(branch)) => doc.append_journal(format!(
+                "- {} {todo_id} partial work saved to branch `{branch}`.",
+                now_hms()
+            )),
+            Ok(None) => {}
+            Err(e) => doc.append_journal(format!(
+                "- {} {todo_id} failed to stash partial work: {e:#}",
+                now_hms()
+            )),
+        }
+    }
     if let Some(todo) = doc.todos.iter_mut().find(|t| t.id == todo_id) {
         todo.checked = false;
     }
@@ -1600,10 +1999,12 @@ where
         ),
     );
 
+    let (max_chars, lines_override) = executor_preview_budget();
     let (summary_lines, hidden_line_count) = summarize_executor_output_for_console(
         &run.output,
-        EXECUTOR_OUTPUT_PREVIEW_CHARS,
+        max_chars,
         EXECUTOR_PREVIEW_MAX_LINES,
+        lines_override,
     );
     if summary_lines.is_empty() {
         emit_line(emit, "    (no concise output)");
@@ -1644,70 +2045,186 @@ fn extract_executor_verdict(output: &str) -> Option<String> {
     None
 }
 
+/// 读取执行器预览预算 (chars, 显式设置的行数)；未设置、无法解析或超出范围时
+/// 字符数用默认值，行数为 None（沿用默认摘要行数）。
+fn executor_preview_budget() -> (usize, Option<usize>) {
+    let chars = read_budget_env("GOLDBOT_GE_PREVIEW_CHARS", EXECUTOR_PREVIEW_CHARS_RANGE)
+        .unwrap_or(EXECUTOR_OUTPUT_PREVIEW_CHARS);
+    let lines = read_budget_env("GOLDBOT_GE_PREVIEW_LINES", EXECUTOR_PREVIEW_LINES_RANGE);
+    (chars, lines)
+}
+
+fn rate_limit_max_retries() -> u32 {
+    std::env::var(ENV_RATE_LIMIT_RETRIES)
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .unwrap_or(0)
+        .min(RATE_LIMIT_RETRIES_MAX)
+}
+
+/// 已重试 `attempts` 次后是否再等一次：优先采用输出里的 retry-after 提示，否则指数退避；
+/// 达到上限返回 None。
+fn rate_limit_retry_delay(attempts: u32, max_retries: u32, output: &str) -> Option<Duration> {
+    if attempts >= max_retries {
+        return None;
+    }
+    let backoff = RATE_LIMIT_BASE_DELAY.saturating_mul(1u32 << attempts.min(16));
+    Some(
+        retry_after_hint(output)
+            .unwrap_or(backoff)
+            .min(RATE_LIMIT_MAX_DELAY),
+    )
+}
+
+/// 从执行器输出中读取 `retry-after: 30`、`try again in 5 minutes` 之类的等待提示。
+fn retry_after_hint(output: &str) -> Option<Duration> {
+    const MARKERS: [&str; 5] = [
+        "retry-after",
+        "retry after",
+        "try again in",
+        "retry in",
+        "resets in",
+    ];
+    let lower = output.to_lowercase();
+    MARKERS.iter().find_map(|marker| {
+        let idx = lower.find(marker)?;
+        let rest = lower[idx + marker.len()..].trim_start_matches([' ', ':', '=']);
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        let value: u64 = digits.parse().ok()?;
+        let unit: String = rest[digits.len()..]
+            .trim_start()
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        let secs = match unit.as_str() {
+            "ms" | "millisecond" | "milliseconds" => value.div_ceil(1000),
+            "m" | "min" | "mins" | "minute" | "minutes" => value.saturating_mul(60),
+            "h" | "hr" | "hrs" | "hour" | "hours" => value.saturating_mul(3600),
+            _ => value,
+        };
+        Some(Duration::from_secs(secs.max(1)))
+    })
+}
+
+fn read_budget_env(key: &str, (min, max): (usize, usize)) -> Option<usize> {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|v| (min..=max).contains(v))
+}
+
 fn summarize_executor_output_for_console(
     output: &str,
     max_chars: usize,
     max_lines: usize,
+    lines_override: Option<usize>,
 ) -> (Vec<String>, usize) {
     if max_chars == 0 || max_lines == 0 {
         return (Vec::new(), 0);
     }
 
-    let body = strip_executor_trailer(output);
+    let trailers = executor_markers_from_env("GOLDBOT_EXECUTOR_TRAILERS");
+    let noise = executor_markers_from_env("GOLDBOT_EXECUTOR_NOISE");
+    let body = strip_executor_trailer(output, &trailers);
     let line_max = max_chars.clamp(80, 220);
     let summary_limit = max_lines.min(10);
+    // `GOLDBOT_GE_PREVIEW_LINES` 显式设置时以它为准
+    let summary_limit = lines_override.unwrap_or(summary_limit);
     let mut total_kept = 0usize;
+    let mut used_chars = 0usize;
     let mut shown = Vec::new();
     for raw in body.lines() {
         let trimmed = raw.trim();
-        if trimmed.is_empty() || is_executor_noise_line(trimmed) {
+        if trimmed.is_empty() || is_executor_noise_line(trimmed, &noise) {
             continue;
         }
         if trimmed.starts_with("GE_REVIEW_VERDICT:") || trimmed.starts_with("GE_EXEC_VERDICT:") {
             continue;
         }
         total_kept += 1;
-        if shown.len() >= summary_limit {
+        if shown.len() >= summary_limit || used_chars >= max_chars {
             continue;
         }
-        shown.push(truncate_text(trimmed, line_max));
+        let line = truncate_text(trimmed, line_max);
+        used_chars += line.chars().count();
+        shown.push(line);
     }
 
     let hidden = total_kept.saturating_sub(shown.len());
     (shown, hidden)
 }
 
-fn strip_executor_trailer(output: &str) -> &str {
-    let mut cut = output.len();
-    for marker in [
-        "\nOpenAI Codex v",
-        "\nOpenAI Claude",
-        "\nuser\n",
-        "\ntokens used",
-    ] {
-        if let Some(idx) = output.find(marker) {
-            cut = cut.min(idx);
-        }
-    }
-    if output.starts_with("OpenAI Codex v") || output.starts_with("OpenAI Claude") {
-        cut = 0;
+/// 从该行起视为执行器 CLI 尾部样板的行前缀（大小写不敏感）；
+/// 可通过 `GOLDBOT_EXECUTOR_TRAILERS`（逗号分隔）追加。
+const BUILTIN_EXECUTOR_TRAILERS: &[&str] = &[
+    "OpenAI Codex v",
+    "OpenAI Claude",
+    "tokens used",
+    "Total duration",
+    "Token usage:",
+    "run id:",
+    "run-id:",
+    "run_id:",
+];
+
+/// 执行器输出中的噪声行前缀（大小写不敏感）；可通过 `GOLDBOT_EXECUTOR_NOISE` 追加。
+const BUILTIN_EXECUTOR_NOISE_PREFIXES: &[&str] = &[
+    "workdir:",
+    "model:",
+    "provider:",
+    "approval:",
+    "sandbox:",
+    "reasoning effort:",
+    "reasoning summaries:",
+    "session id:",
+    "mcp:",
+    "duration:",
+    "cost:",
+];
+
+/// 读取逗号分隔的标记列表环境变量。
+fn executor_markers_from_env(key: &str) -> Vec<String> {
+    std::env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn starts_with_ignore_case(line: &str, prefix: &str) -> bool {
+    line.get(..prefix.len())
+        .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+}
+
+/// 在第一处尾部标记行截断输出；`extra` 为用户追加的标记。
+fn strip_executor_trailer<'a>(output: &'a str, extra: &[String]) -> &'a str {
+    let markers = BUILTIN_EXECUTOR_TRAILERS
+        .iter()
+        .copied()
+        .chain(extra.iter().map(String::as_str));
+    let mut offset = 0usize;
+    for line in output.split_inclusive('\n') {
+        let t = line.trim_end();
+        let is_user_echo = offset > 0 && t == "user";
+        if is_user_echo || markers.clone().any(|m| starts_with_ignore_case(t, m)) {
+            return &output[..offset.saturating_sub(1)];
+        }
+        offset += line.len();
     }
-    &output[..cut]
+    output
 }
 
-fn is_executor_noise_line(line: &str) -> bool {
+fn is_executor_noise_line(line: &str, extra: &[String]) -> bool {
     let t = line.trim();
     t == "--------"
-        || t.starts_with("workdir:")
-        || t.starts_with("model:")
-        || t.starts_with("provider:")
-        || t.starts_with("approval:")
-        || t.starts_with("sandbox:")
-        || t.starts_with("reasoning effort:")
-        || t.starts_with("reasoning summaries:")
-        || t.starts_with("session id:")
-        || t.starts_with("mcp:")
         || t.eq_ignore_ascii_case("thinking")
+        || BUILTIN_EXECUTOR_NOISE_PREFIXES
+            .iter()
+            .copied()
+            .chain(extra.iter().map(String::as_str))
+            .any(|p| starts_with_ignore_case(t, p))
 }
 
 fn preview_block_lines(text: &str, max_chars: usize, max_lines: usize) -> Vec<String> {
@@ -1751,6 +2268,36 @@ fn preview_block_lines(text: &str, max_chars: usize, max_lines: usize) -> Vec<St
     lines
 }
 
+/// 把两版 CONSENSUS.md 渲染成 unified diff（与 `git diff` 同一格式，便于统一着色）。
+fn render_consensus_diff(before: &str, after: &str, base: &str) -> Vec<String> {
+    let diff = TextDiff::from_lines(before, after)
+        .unified_diff()
+        .context_radius(3)
+        .header("a/CONSENSUS.md", "b/CONSENSUS.md")
+        .to_string();
+    consensus_diff_lines(&diff, base)
+}
+
+fn consensus_diff_lines(diff: &str, base: &str) -> Vec<String> {
+    if diff.trim().is_empty() {
+        return vec![format!("  GE: CONSENSUS.md unchanged since {base}.")];
+    }
+    let mut lines = vec![format!("  GE DIFF [CONSENSUS.md] vs {base}")];
+    let total = diff.lines().count();
+    lines.extend(
+        diff.lines()
+            .take(CONSENSUS_DIFF_MAX_LINES)
+            .map(|line| format!("    {line}")),
+    );
+    if total > CONSENSUS_DIFF_MAX_LINES {
+        lines.push(format!(
+            "    ...(truncated, {} more lines)",
+            total - CONSENSUS_DIFF_MAX_LINES
+        ));
+    }
+    lines
+}
+
 fn hash_file(path: &PathBuf) -> Result<u64> {
     let bytes = fs::read(path).with_context(|| format!("failed to read `{}`", path.display()))?;
     let mut hasher = DefaultHasher::new();
@@ -1777,7 +2324,7 @@ fn parse_option_choice(text: &str, option_count: usize) -> Option<usize> {
 
 fn parse_clarify_questions_json(raw: &str) -> Option<Vec<ClarifyQuestion>> {
     let json = extract_json_object(raw)?;
-    let value: Value = serde_json::from_str(&json).ok()?;
+    let value = parse_json_lenient(&json)?;
     let items = value.get("questions")?.as_array()?;
     if items.is_empty() {
         return Some(Vec::new());
@@ -1806,9 +2353,9 @@ fn parse_clarify_questions_json(raw: &str) -> Option<Vec<ClarifyQuestion>> {
     Some(out)
 }
 
-fn parse_consensus_payload_json(raw: &str) -> Option<GeneratedConsensus> {
+fn parse_consensus_payload_json(raw: &str, cwd: &Path) -> Option<GeneratedConsensus> {
     let json = extract_json_object(raw)?;
-    let value: Value = serde_json::from_str(&json).ok()?;
+    let value = parse_json_lenient(&json)?;
     let purpose_lines = value
         .get("purpose_lines")
         .and_then(Value::as_array)
@@ -1839,7 +2386,7 @@ fn parse_consensus_payload_json(raw: &str) -> Option<GeneratedConsensus> {
         .map(str::trim)
         .unwrap_or("")
         .to_string();
-    let todos = parse_todos_from_value(value.get("todos")?)?;
+    let todos = parse_todos_from_value(value.get("todos")?, &default_done_when(cwd))?;
 
     if todos.is_empty() {
         return None;
@@ -1852,7 +2399,19 @@ fn parse_consensus_payload_json(raw: &str) -> Option<GeneratedConsensus> {
     })
 }
 
-fn parse_todos_from_value(todos_value: &Value) -> Option<Vec<TodoItem>> {
+/// 未给出 done_when 的 todo 默认使用检测到的项目校验命令，检测不到时退回人工 review 文案。
+fn default_done_when(cwd: &Path) -> Vec<String> {
+    let checks = detect_project_checks(cwd);
+    if checks.is_empty() {
+        return vec![REVIEW_DONE_WHEN.to_string()];
+    }
+    checks.iter().map(|cmd| format!("cmd: {cmd}")).collect()
+}
+
+fn parse_todos_from_value(
+    todos_value: &Value,
+    default_done_when: &[String],
+) -> Option<Vec<TodoItem>> {
     let todos = todos_value.as_array()?;
     let mut out = Vec::new();
     for (idx, item) in todos.iter().enumerate() {
@@ -1895,7 +2454,7 @@ fn parse_todos_from_value(todos_value: &Value) -> Option<Vec<TodoItem>> {
             text: text.to_string(),
             checked: false,
             done_when: if done_when.is_empty() {
-                vec!["Completed and verified by Codex review".to_string()]
+                default_done_when.to_vec()
             } else {
                 done_when
             },
@@ -1981,10 +2540,10 @@ fn lines_from_text(text: &str) -> Vec<String> {
         .collect::<Vec<_>>()
 }
 
-fn parse_todo_plan_json(raw: &str) -> Option<Vec<crate::consensus::model::TodoItem>> {
+fn parse_todo_plan_json(raw: &str, cwd: &Path) -> Option<Vec<crate::consensus::model::TodoItem>> {
     let json = extract_json_object(raw)?;
-    let value: Value = serde_json::from_str(&json).ok()?;
-    parse_todos_from_value(value.get("todos")?)
+    let value = parse_json_lenient(&json)?;
+    parse_todos_from_value(value.get("todos")?, &default_done_when(cwd))
 }
 
 fn extract_json_object(raw: &str) -> Option<String> {
@@ -2010,6 +2569,62 @@ fn extract_json_object(raw: &str) -> Option<String> {
     }
 }
 
+/// 先按严格 JSON 解析；失败时去掉注释与尾随逗号后再试一次。
+/// LLM 生成的规划 JSON 常带这两类瑕疵，修复后可省去一次兜底 executor 调用。
+fn parse_json_lenient(json: &str) -> Option<Value> {
+    serde_json::from_str(json)
+        .ok()
+        .or_else(|| serde_json::from_str(&normalize_lenient_json(json)).ok())
+}
+
+/// 移除字符串之外的 `//`、`/* */` 注释，以及 `}` / `]` 之前的尾随逗号。
+fn normalize_lenient_json(json: &str) -> String {
+    let mut out = String::with_capacity(json.len());
+    let mut chars = json.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        out.push(escaped);
+                    }
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|&n| n != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = '\0';
+                for n in chars.by_ref() {
+                    if prev == '*' && n == '/' {
+                        break;
+                    }
+                    prev = n;
+                }
+            }
+            ('}' | ']', _) => {
+                let trimmed_len = out.trim_end().len();
+                if out[..trimmed_len].ends_with(',') {
+                    out.truncate(trimmed_len - 1);
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
 fn truncate_text(s: &str, max: usize) -> String {
     if s.chars().count() <= max {
         return s.to_string();
@@ -2041,7 +2656,71 @@ fn extract_scope(purpose_lines: &[String]) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_clarify_questions_json, parse_consensus_payload_json, parse_todo_plan_json};
+    use super::{
+        GeRuntime, RATE_LIMIT_BASE_DELAY, RATE_LIMIT_MAX_DELAY, REVIEW_DONE_WHEN, RateLimitRetry,
+        is_executor_noise_line, parse_clarify_questions_json, parse_consensus_payload_json,
+        parse_todo_plan_json, rate_limit_retry_delay, render_consensus_diff,
+        strip_executor_trailer, summarize_executor_output_for_console,
+    };
+    use crate::consensus::model::{consensus_file_path, load};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, atomic::AtomicBool};
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    fn temp_project(tag: &str, markers: &[&str]) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("goldbot-ge-checks-{tag}-{nanos}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        for marker in markers {
+            std::fs::write(dir.join(marker), "").unwrap();
+        }
+        dir
+    }
+
+    /// 8 个 todo，其中 T002 没有给出 done_when。
+    fn plan_with_missing_done_when() -> String {
+        let todos = (1..=8)
+            .map(|i| {
+                let done_when = if i == 2 {
+                    String::new()
+                } else {
+                    r#","done_when":["step works"]"#.to_string()
+                };
+                format!(r#"{{"id":"T{i:03}","text":"step {i}"{done_when}}}"#)
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(r#"{{"todos":[{todos}]}}"#)
+    }
+
+    #[test]
+    fn missing_done_when_defaults_to_detected_rust_checks() {
+        // Cargo.toml 优先于同时存在的 Makefile
+        let dir = temp_project("rust", &["Cargo.toml", "Makefile"]);
+        let todos = parse_todo_plan_json(&plan_with_missing_done_when(), &dir).unwrap();
+        assert_eq!(todos[1].done_when, ["cmd: cargo check", "cmd: cargo test"]);
+        assert_eq!(todos[0].done_when, ["step works"]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn missing_done_when_defaults_to_detected_node_checks() {
+        let dir = temp_project("node", &["package.json"]);
+        let todos = parse_todo_plan_json(&plan_with_missing_done_when(), &dir).unwrap();
+        assert_eq!(todos[1].done_when, ["cmd: npm test"]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn missing_done_when_falls_back_to_review_in_bare_directory() {
+        let dir = temp_project("bare", &[]);
+        let todos = parse_todo_plan_json(&plan_with_missing_done_when(), &dir).unwrap();
+        assert_eq!(todos[1].done_when, [REVIEW_DONE_WHEN]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
 
     #[test]
     fn parse_todo_plan_json_accepts_valid_payload() {
@@ -2055,7 +2734,7 @@ mod tests {
             {"id":"T007","text":"Run validation checks","done_when":["cmd: cargo check"],"assist":"codex"},
             {"id":"T008","text":"Document outcome and next steps","done_when":["journal updated"],"assist":"auto"}
         ]}"#;
-        let todos = parse_todo_plan_json(raw).expect("should parse todos");
+        let todos = parse_todo_plan_json(raw, Path::new(".")).expect("should parse todos");
         assert_eq!(todos.len(), 8);
         assert_eq!(todos[0].id, "T001");
         assert_eq!(todos[0].assist.as_deref(), Some("claude"));
@@ -2065,7 +2744,34 @@ mod tests {
     fn parse_todo_plan_json_rejects_out_of_range_count() {
         let raw =
             r#"{"todos":[{"id":"T001","text":"only one","done_when":["x"],"assist":"auto"}]}"#;
-        assert!(parse_todo_plan_json(raw).is_none());
+        assert!(parse_todo_plan_json(raw, Path::new(".")).is_none());
+    }
+
+    #[test]
+    fn planner_json_with_trailing_commas_and_comments_parses() {
+        let todos = (1..=8)
+            .map(|i| {
+                format!(
+                    "    // step {i}\n    {{\"id\":\"T{i:03}\",\"text\":\"step {i} // keep\",\"done_when\":[\"ok\",],}},"
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let raw = format!("```json\n{{\"todos\":[\n{todos}\n  ], /* planner note */\n}}\n```");
+        let todos = parse_todo_plan_json(&raw, Path::new(".")).expect("should parse lenient json");
+        assert_eq!(todos.len(), 8);
+        assert_eq!(todos[0].text, "step 1 // keep");
+        assert_eq!(todos[7].done_when, ["ok"]);
+
+        let raw = r#"{
+            // 只问一个问题
+            "questions": [
+                {"question": "Pick stack", "options": ["Rust", "Go", "Zig",],},
+            ],
+        }"#;
+        let questions = parse_clarify_questions_json(raw).expect("should parse lenient json");
+        assert_eq!(questions.len(), 1);
+        assert_eq!(questions[0].options[2], "Zig");
     }
 
     #[test]
@@ -2099,7 +2805,8 @@ mod tests {
             {"id":"T007","text":"Add folder navigation","done_when":["navigate files"],"assist":"auto"},
             {"id":"T008","text":"Run final checks","done_when":["cmd: cargo check"],"assist":"codex"}
         ]}"#;
-        let payload = parse_consensus_payload_json(raw).expect("should parse payload");
+        let payload =
+            parse_consensus_payload_json(raw, Path::new(".")).expect("should parse payload");
         assert_eq!(payload.purpose_lines.len(), 2);
         assert_eq!(payload.rules_lines.len(), 2);
         assert_eq!(payload.todos.len(), 8);
@@ -2113,4 +2820,187 @@ mod tests {
         assert_eq!(super::parse_option_choice("3）", 3), Some(2));
         assert_eq!(super::parse_option_choice("自定义", 3), None);
     }
+
+    #[test]
+    fn summarize_executor_output_honors_custom_line_budget() {
+        let output = (1..=30)
+            .map(|i| format!("step {i} done"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        // 未设置时默认只显示前 10 行
+        let (shown, hidden) = summarize_executor_output_for_console(&output, 20_000, 40, None);
+        assert_eq!((shown.len(), hidden), (10, 20));
+        let (shown, hidden) = summarize_executor_output_for_console(&output, 20_000, 40, Some(25));
+        assert_eq!(shown.len(), 25);
+        assert_eq!(hidden, 5);
+        let (shown, hidden) = summarize_executor_output_for_console(&output, 20_000, 40, Some(3));
+        assert_eq!(shown, vec!["step 1 done", "step 2 done", "step 3 done"]);
+        assert_eq!(hidden, 27);
+    }
+
+    #[test]
+    fn strip_executor_trailer_honors_builtin_and_custom_markers() {
+        let output = "changed src/lib.rs\nall tests pass\nTotal duration: 12s\nfoo";
+        assert_eq!(
+            strip_executor_trailer(output, &[]),
+            "changed src/lib.rs\nall tests pass"
+        );
+
+        let output = "done\n== acme-cli footer ==\nrun 42";
+        assert_eq!(strip_executor_trailer(output, &[]), output);
+        let extra = vec!["== acme-cli".to_string()];
+        assert_eq!(strip_executor_trailer(output, &extra), "done");
+    }
+
+    #[test]
+    fn executor_noise_line_matches_builtin_and_extra_prefixes() {
+        assert!(is_executor_noise_line("Session ID: abc", &[]));
+        assert!(is_executor_noise_line("  Model: gpt-x", &[]));
+        assert!(!is_executor_noise_line("cache hit: 3", &[]));
+        let extra = vec!["cache hit:".to_string()];
+        assert!(is_executor_noise_line("Cache Hit: 3", &extra));
+        assert!(!is_executor_noise_line("edited main.rs", &extra));
+    }
+
+    #[test]
+    fn rate_limit_retry_backs_off_honors_hint_and_caps_attempts() {
+        let plain = "Error: 429 Too Many Requests";
+        assert_eq!(
+            rate_limit_retry_delay(0, 3, plain),
+            Some(RATE_LIMIT_BASE_DELAY)
+        );
+        assert_eq!(
+            rate_limit_retry_delay(2, 3, plain),
+            Some(RATE_LIMIT_BASE_DELAY * 4)
+        );
+        // 次数用完后交回原有的回退 / 延期流程
+        assert_eq!(rate_limit_retry_delay(3, 3, plain), None);
+        assert_eq!(rate_limit_retry_delay(0, 0, plain), None);
+
+        let hinted = "rate limit exceeded. Retry-After: 90";
+        assert_eq!(
+            rate_limit_retry_delay(0, 3, hinted),
+            Some(Duration::from_secs(90))
+        );
+        let minutes = "Usage limit reached, please try again in 5 minutes.";
+        assert_eq!(
+            rate_limit_retry_delay(1, 3, minutes),
+            Some(Duration::from_secs(300))
+        );
+        let too_long = "quota exhausted; resets in 10 hours";
+        assert_eq!(
+            rate_limit_retry_delay(0, 3, too_long),
+            Some(RATE_LIMIT_MAX_DELAY)
+        );
+    }
+
+    #[test]
+    fn rate_limited_todo_waits_without_blocking_other_todos() {
+        let dir = temp_project("rate-limit-gate", &[]);
+        std::fs::write(
+            consensus_file_path(&dir),
+            "## Purpose\n- Ship\n\n## Rules\n- Test\n\n## Todo\n- [ ] T001 Limited\n- [ ] T002 Other\n\n## Bot Status\n- idle\n",
+        )
+        .unwrap();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (mut runtime, _) = GeRuntime::enter(dir.clone(), "", cancel).unwrap();
+        let doc = load(&consensus_file_path(&dir)).unwrap();
+        let due = Instant::now() + Duration::from_secs(3600);
+        runtime
+            .rate_limit_retries
+            .insert("T001".to_string(), RateLimitRetry { attempts: 1, due });
+
+        // 等待中的 T001 不挡住 T002
+        assert_eq!(runtime.take_next_todo_index(&doc), Some(1));
+        assert_eq!(runtime.earliest_retry_due(), Some(due));
+
+        // 全部在等待时没有可执行的 todo；`GE run` 显式指定时仍可立即执行
+        runtime
+            .rate_limit_retries
+            .insert("T002".to_string(), RateLimitRetry { attempts: 1, due });
+        assert_eq!(runtime.take_next_todo_index(&doc), None);
+        runtime.run_todo("T001").unwrap();
+        assert_eq!(runtime.take_next_todo_index(&doc), Some(0));
+
+        // 到期后重新可选
+        runtime.rate_limit_retries.insert(
+            "T001".to_string(),
+            RateLimitRetry {
+                attempts: 1,
+                due: Instant::now(),
+            },
+        );
+        assert_eq!(runtime.take_next_todo_index(&doc), Some(0));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn run_todo_override_selects_requested_open_todo_once() {
+        let dir = temp_project("run-todo", &[]);
+        std::fs::write(
+            consensus_file_path(&dir),
+            "## Purpose\n- Ship\n\n## Rules\n- Test\n\n## Todo\n- [x] T001 Done\n- [ ] T002 Next\n- [ ] T003 Unblocked later\n\n## Bot Status\n- idle\n",
+        )
+        .unwrap();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (mut runtime, _) = GeRuntime::enter(dir.clone(), "", cancel).unwrap();
+
+        let err = runtime.run_todo("T009").unwrap_err().to_string();
+        assert!(err.contains("not found"), "{err}");
+        let err = runtime.run_todo("T001").unwrap_err().to_string();
+        assert!(err.contains("already checked"), "{err}");
+
+        let lines = runtime.run_todo("t003").unwrap();
+        assert!(lines[0].contains("T003 Unblocked later"), "{lines:?}");
+        let doc = load(&consensus_file_path(&dir)).unwrap();
+        // 覆盖只生效一次，之后回到第一个未完成的 todo
+        assert_eq!(runtime.take_next_todo_index(&doc), Some(2));
+        assert_eq!(runtime.take_next_todo_index(&doc), Some(1));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn consensus_diff_shows_checked_todos_and_journal_entries() {
+        let before = "## Todo\n- [ ] T001 Parser\n- [ ] T002 Tests\n\n## Journal\n- started\n";
+        let after = "## Todo\n- [x] T001 Parser\n- [ ] T002 Tests\n\n## Journal\n- started\n- T001 checked\n";
+        let lines = render_consensus_diff(before, after, "HEAD");
+        assert_eq!(lines[0], "  GE DIFF [CONSENSUS.md] vs HEAD");
+        assert!(
+            lines.contains(&"    --- a/CONSENSUS.md".to_string()),
+            "{lines:?}"
+        );
+        assert!(
+            lines.contains(&"    -- [ ] T001 Parser".to_string()),
+            "{lines:?}"
+        );
+        assert!(
+            lines.contains(&"    +- [x] T001 Parser".to_string()),
+            "{lines:?}"
+        );
+        assert!(
+            lines.contains(&"    +- T001 checked".to_string()),
+            "{lines:?}"
+        );
+        assert!(lines.iter().any(|line| line.starts_with("    @@ ")));
+        assert_eq!(
+            render_consensus_diff(after, after, "HEAD"),
+            ["  GE: CONSENSUS.md unchanged since HEAD."]
+        );
+
+        // 不在 git 仓库里时与上一次记录的内容比较
+        let dir = temp_project("diff", &[]);
+        let path = consensus_file_path(&dir);
+        std::fs::write(&path, before).unwrap();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (mut runtime, _) = GeRuntime::enter(dir.clone(), "", cancel).unwrap();
+        std::fs::write(&path, after).unwrap();
+        runtime.remember_consensus();
+        let lines = runtime.diff_consensus();
+        assert_eq!(lines[0], "  GE DIFF [CONSENSUS.md] vs previous snapshot");
+        assert!(
+            lines.contains(&"    +- T001 checked".to_string()),
+            "{lines:?}"
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
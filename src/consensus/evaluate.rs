@@ -1,6 +1,17 @@
-use std::path::Path;
+use std::{
+    collections::BTreeSet,
+    path::Path,
+    process::Command,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use anyhow::{Context, Result, bail};
 
 use crate::{
+    consensus::external::{ExecutorRun, run_check_command},
     tools::{
         safety::{RiskLevel, assess_command},
         shell::run_command,
@@ -8,6 +19,32 @@ use crate::{
     types::ExecutorOutcome,
 };
 
+/// 按顺序检测项目构建系统：(标志文件, 对应的校验命令)，命中第一个即停止。
+const PROJECT_CHECKS: &[(&str, &[&str])] = &[
+    ("Cargo.toml", &["cargo check", "cargo test"]),
+    ("package.json", &["npm test"]),
+    ("pyproject.toml", &["pytest"]),
+    ("Makefile", &["make"]),
+];
+
+/// 默认只提供最近一个提交作为 git 上下文。
+const DEFAULT_GIT_CONTEXT_DEPTH: usize = 1;
+const GIT_CONTEXT_MAX_DEPTH: usize = 20;
+/// 每个提交的上下文字符上限。
+const COMMIT_CONTEXT_CHARS: usize = 500;
+/// 延期 todo 的半成品改动所存放的分支前缀。
+const DEFERRED_BRANCH_PREFIX: &str = "goldbot/deferred/";
+/// 暂存半成品改动时不纳入的文件：GE 自身文件（由引擎单独维护）与 `.goldbot/`
+/// （凭据、工作区记忆等，绝不能被提交或删除）。
+const DEFERRED_EXCLUDES: &[&str] = &[
+    ":(exclude)GE_LOG.jsonl",
+    ":(exclude)CONSENSUS.md",
+    ":(exclude).ge_inflight",
+    ":(exclude).goldbot",
+];
+/// 检查崩溃前提交是否落地时，HEAD 未知（旧仓库无提交）最多回看的提交数。
+const COMMIT_LANDED_LOOKBACK: &str = "20";
+
 #[derive(Debug, Clone)]
 pub struct ValidationReport {
     pub outcome: ExecutorOutcome,
@@ -34,8 +71,24 @@ pub enum ExecDecision {
     Fail(String),
 }
 
-pub fn validate_done_when(done_when: &[String], cwd: &Path) -> ValidationReport {
-    let _ = cwd;
+/// hard exit 中止时的报告：引擎据此直接结束本轮，不延期 todo。
+fn cancelled_report(stage: &str) -> ValidationReport {
+    ValidationReport {
+        outcome: ExecutorOutcome::Cancelled,
+        summary: format!("{stage} cancelled by GE hard exit"),
+        exit_code: 130,
+    }
+}
+
+fn was_cancelled(run: &ExecutorRun, cancel: &AtomicBool) -> bool {
+    cancel.load(Ordering::SeqCst) || run.error_code.as_deref() == Some("cancelled")
+}
+
+pub fn validate_done_when(
+    done_when: &[String],
+    cwd: &Path,
+    cancel: &Arc<AtomicBool>,
+) -> ValidationReport {
     if done_when.is_empty() {
         return ValidationReport {
             outcome: ExecutorOutcome::Success,
@@ -65,27 +118,27 @@ pub fn validate_done_when(done_when: &[String], cwd: &Path) -> ValidationReport
                 };
             }
 
-            match run_command(cmd) {
-                Ok(out) => {
-                    notes.push(format!("cmd `{cmd}` => exit {}", out.exit_code));
-                    if out.exit_code != 0 {
-                        return ValidationReport {
-                            outcome: ExecutorOutcome::Failed,
-                            summary: format!(
-                                "done_when command failed: `{cmd}` | {}",
-                                truncate(&out.output, 240)
-                            ),
-                            exit_code: out.exit_code,
-                        };
-                    }
-                }
-                Err(e) => {
-                    return ValidationReport {
-                        outcome: ExecutorOutcome::Failed,
-                        summary: format!("failed to run done_when command `{cmd}`: {e}"),
-                        exit_code: -1,
-                    };
-                }
+            let out = run_check_command(cwd, cmd, cancel);
+            if was_cancelled(&out, cancel) {
+                return cancelled_report("done_when validation");
+            }
+            if out.error_code.as_deref() == Some("exec_failed") {
+                return ValidationReport {
+                    outcome: ExecutorOutcome::Failed,
+                    summary: format!("failed to run done_when command `{cmd}`: {}", out.output),
+                    exit_code: -1,
+                };
+            }
+            notes.push(format!("cmd `{cmd}` => exit {}", out.exit_code));
+            if out.exit_code != 0 {
+                return ValidationReport {
+                    outcome: ExecutorOutcome::Failed,
+                    summary: format!(
+                        "done_when command failed: `{cmd}` | {}",
+                        truncate(&out.output, 240)
+                    ),
+                    exit_code: out.exit_code,
+                };
             }
         } else {
             notes.push(format!("semantic: {trimmed}"));
@@ -99,6 +152,15 @@ pub fn validate_done_when(done_when: &[String], cwd: &Path) -> ValidationReport
     }
 }
 
+/// 根据工作区里的构建文件推断可客观校验的命令；未识别时返回空列表。
+pub fn detect_project_checks(cwd: &Path) -> Vec<String> {
+    PROJECT_CHECKS
+        .iter()
+        .find(|(marker, _)| cwd.join(marker).is_file())
+        .map(|(_, cmds)| cmds.iter().map(ToString::to_string).collect())
+        .unwrap_or_default()
+}
+
 pub fn codex_review_decision(output: &str, exit_code: i32) -> ReviewDecision {
     if exit_code != 0 {
         return ReviewDecision::Blocked(format!("non_zero_exit:{exit_code}"));
@@ -211,8 +273,12 @@ fn has_any(hay: &str, needles: &[&str]) -> bool {
     needles.iter().any(|n| hay.contains(n))
 }
 
-pub fn self_review(cwd: &Path) -> ValidationReport {
+/// 各步都是很快的 git 查询，只在步骤之间检查 hard exit。
+pub fn self_review(cwd: &Path, cancel: &AtomicBool) -> ValidationReport {
     let _ = cwd;
+    if cancel.load(Ordering::SeqCst) {
+        return cancelled_report("self-review");
+    }
     match run_command("git rev-parse --is-inside-work-tree") {
         Ok(out) if out.exit_code == 0 => {}
         Ok(out) => {
@@ -231,6 +297,9 @@ pub fn self_review(cwd: &Path) -> ValidationReport {
         }
     }
 
+    if cancel.load(Ordering::SeqCst) {
+        return cancelled_report("self-review");
+    }
     match run_command("git diff --check") {
         Ok(out) if out.exit_code == 0 => {}
         Ok(out) => {
@@ -263,10 +332,66 @@ pub fn self_review(cwd: &Path) -> ValidationReport {
     }
 }
 
-pub fn commit_todo(todo_id: &str, todo_text: &str) -> CommitReport {
+/// 把 `self_review` 的报告渲染为 `/review` 在滚动区显示的行。
+pub fn format_review_report(report: &ValidationReport) -> Vec<String> {
+    let indent = |text: &str| -> Vec<String> {
+        text.lines()
+            .map(str::trim_end)
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| format!("    {l}"))
+            .collect()
+    };
+    if report.outcome != ExecutorOutcome::Success {
+        if report.summary.starts_with("not a git repository") {
+            return vec!["  ✗ 当前工作区不是 git 仓库，/review 需要在 git 仓库中使用".to_string()];
+        }
+        let mut lines = vec!["  ✗ self-review 未通过（空白错误或冲突标记）：".to_string()];
+        let detail = report
+            .summary
+            .strip_prefix("git diff --check failed: ")
+            .unwrap_or(&report.summary);
+        lines.extend(indent(detail));
+        return lines;
+    }
+
+    let (status, stat) = report
+        .summary
+        .strip_prefix("status: ")
+        .and_then(|rest| rest.split_once(" | diff: "))
+        .unwrap_or((report.summary.as_str(), ""));
+    let mut lines = vec!["  ✓ self-review 通过：无空白错误".to_string()];
+    if status.trim().is_empty() {
+        lines.push("  工作区无改动".to_string());
+        return lines;
+    }
+    lines.push("  变更文件：".to_string());
+    lines.extend(indent(status));
+    if !stat.trim().is_empty() {
+        lines.push("  统计：".to_string());
+        lines.extend(indent(stat));
+    }
+    lines
+}
+
+/// 提交可能触发耗时的 git hook，因此以可终止的子进程运行。
+pub fn commit_todo(
+    cwd: &Path,
+    todo_id: &str,
+    todo_text: &str,
+    cancel: &Arc<AtomicBool>,
+) -> CommitReport {
+    let cancelled = || CommitReport {
+        outcome: ExecutorOutcome::Cancelled,
+        summary: "git commit cancelled by GE hard exit".to_string(),
+        exit_code: 130,
+    };
+    if cancel.load(Ordering::SeqCst) {
+        return cancelled();
+    }
     let msg = format!("GE({todo_id}): {}", shorten_for_commit(todo_text));
     let quoted = shell_single_quote(&msg);
-    if let Err(e) = run_command("git add -A -- . ':(exclude)GE_LOG.jsonl'") {
+    if let Err(e) = run_command("git add -A -- . ':(exclude)GE_LOG.jsonl' ':(exclude).ge_inflight'")
+    {
         return CommitReport {
             outcome: ExecutorOutcome::Failed,
             summary: format!("git add failed: {e}"),
@@ -275,16 +400,17 @@ pub fn commit_todo(todo_id: &str, todo_text: &str) -> CommitReport {
     }
 
     let commit_cmd = format!("git commit --allow-empty -m {quoted}");
-    let commit_out = match run_command(&commit_cmd) {
-        Ok(out) => out,
-        Err(e) => {
-            return CommitReport {
-                outcome: ExecutorOutcome::Failed,
-                summary: format!("git commit failed to start: {e}"),
-                exit_code: -1,
-            };
-        }
-    };
+    let commit_out = run_check_command(cwd, &commit_cmd, cancel);
+    if was_cancelled(&commit_out, cancel) {
+        return cancelled();
+    }
+    if commit_out.error_code.as_deref() == Some("exec_failed") {
+        return CommitReport {
+            outcome: ExecutorOutcome::Failed,
+            summary: format!("git commit failed to start: {}", commit_out.output),
+            exit_code: -1,
+        };
+    }
     if commit_out.exit_code != 0 {
         return CommitReport {
             outcome: ExecutorOutcome::Failed,
@@ -303,12 +429,226 @@ pub fn commit_todo(todo_id: &str, todo_text: &str) -> CommitReport {
     }
 }
 
-pub fn latest_commit_context() -> Option<String> {
-    let out = run_command("git show --stat --oneline --no-color -1").ok()?;
+/// `GOLDBOT_GE_STASH_DEFERRED=1` 时，延期 todo 的改动转存到独立分支。
+pub fn stash_deferred_enabled() -> bool {
+    std::env::var("GOLDBOT_GE_STASH_DEFERRED").is_ok_and(|v| v.trim() == "1")
+}
+
+/// `goldbot/deferred/<todo-id>`；todo id 中不适合作为 ref 的字符替换为 `-`。
+pub fn deferred_branch_name(todo_id: &str) -> String {
+    let id: String = todo_id
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let id = id.trim_matches('-');
+    format!(
+        "{DEFERRED_BRANCH_PREFIX}{}",
+        if id.is_empty() { "todo" } else { id }
+    )
+}
+
+/// 把执行器改动过的文件提交到 `goldbot/deferred/<todo-id>` 分支（不切换当前分支），
+/// 然后只还原这些文件，让下一个 todo 从干净的状态开始。`preexisting` 是执行器开始前
+/// 已有改动的路径（见 [`dirty_paths`]），属于用户，既不暂存也不还原。没有可暂存的改动时返回 `None`。
+pub fn stash_deferred_changes(
+    cwd: &Path,
+    todo_id: &str,
+    preexisting: &BTreeSet<String>,
+) -> Result<Option<String>> {
+    let touched: Vec<String> = dirty_paths(cwd)?
+        .into_iter()
+        .filter(|path| !preexisting.contains(path))
+        .collect();
+    if touched.is_empty() {
+        return Ok(None);
+    }
+    let branch = deferred_branch_name(todo_id);
+    let paths: Vec<&str> = touched.iter().map(String::as_str).collect();
+
+    // 用临时 index 生成快照，避免动到用户当前的暂存区
+    let index = std::env::temp_dir().join(format!(
+        "goldbot-deferred-index-{}-{}",
+        std::process::id(),
+        deferred_branch_name(todo_id).replace('/', "-")
+    ));
+    let _ = std::fs::remove_file(&index);
+    let snapshot = (|| -> Result<String> {
+        git_with_index(cwd, &index, &["read-tree", "HEAD"])?;
+        git_with_index(cwd, &index, &[&["add", "-A", "--"], &paths[..]].concat())?;
+        let tree = git_with_index(cwd, &index, &["write-tree"])?;
+        let message = format!("GE deferred {todo_id}: partial work");
+        git(cwd, &["commit-tree", &tree, "-p", "HEAD", "-m", &message])
+    })();
+    let _ = std::fs::remove_file(&index);
+    let commit = snapshot?;
+    git(cwd, &["branch", "-f", &branch, &commit])?;
+
+    // 逐个还原：HEAD 中有的文件取回 HEAD 版本，执行器新建的文件直接删除
+    for path in &touched {
+        if git(cwd, &["cat-file", "-e", &format!("HEAD:{path}")]).is_ok() {
+            git(cwd, &["checkout", "HEAD", "--", path])?;
+        } else {
+            let _ = git(
+                cwd,
+                &["rm", "-q", "--cached", "--ignore-unmatch", "--", path],
+            );
+            match std::fs::remove_file(cwd.join(path)) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).with_context(|| format!("failed to remove {path}")),
+            }
+        }
+    }
+    Ok(Some(branch))
+}
+
+/// todo 重试成功后删除其延期分支；分支不存在时返回 `false`。
+pub fn delete_deferred_branch(cwd: &Path, todo_id: &str) -> bool {
+    let branch = deferred_branch_name(todo_id);
+    git(cwd, &["rev-parse", "--verify", "--quiet", &branch]).is_ok()
+        && git(cwd, &["branch", "-D", &branch]).is_ok()
+}
+
+/// 当前 HEAD 的提交哈希；不是 git 仓库或还没有提交时返回 None。
+pub fn head_commit(cwd: &Path) -> Option<String> {
+    git(cwd, &["rev-parse", "--verify", "--quiet", "HEAD"]).ok()
+}
+
+/// `since` 之后（None 时为最近若干个提交）是否已有 `commit_todo` 为该 todo 生成的提交。
+pub fn todo_commit_landed(cwd: &Path, todo_id: &str, since: Option<&str>) -> bool {
+    let range = since.map(|head| format!("{head}..HEAD"));
+    let args: Vec<&str> = match range.as_deref() {
+        Some(range) => vec!["log", "--format=%s", range],
+        None => vec!["log", "--format=%s", "-n", COMMIT_LANDED_LOOKBACK],
+    };
+    let prefix = format!("GE({todo_id}):");
+    git(cwd, &args).is_ok_and(|log| log.lines().any(|subject| subject.starts_with(&prefix)))
+}
+
+/// `path` 相对 HEAD 的 diff；不在 git 仓库、还没有提交或文件未被跟踪时返回 None。
+pub fn tracked_file_diff(cwd: &Path, path: &Path) -> Option<String> {
+    let path = path.to_str()?;
+    git(cwd, &["ls-files", "--error-unmatch", "--", path]).ok()?;
+    git(
+        cwd,
+        &["--no-pager", "diff", "--no-color", "HEAD", "--", path],
+    )
+    .ok()
+}
+
+/// 除 GE 自身文件外，工作区没有任何改动（含未跟踪文件）。
+/// 相对 HEAD 有改动（含暂存与未跟踪文件）的路径，不含 [`DEFERRED_EXCLUDES`]。
+/// 执行器开始前记录一次，延期时据此区分执行器的改动和用户自己的改动。
+pub fn dirty_paths(cwd: &Path) -> Result<BTreeSet<String>> {
+    let status = git_raw(
+        cwd,
+        &[
+            &["status", "--porcelain", "-z", "--untracked-files=all", "--"],
+            &deferred_pathspec()[..],
+        ]
+        .concat(),
+    )?;
+    let mut paths = BTreeSet::new();
+    let mut records = status.split('\0').filter(|r| !r.is_empty());
+    while let Some(record) = records.next() {
+        let Some((code, path)) = record.split_at_checked(3) else {
+            continue;
+        };
+        paths.insert(path.to_string());
+        // 重命名/复制后面跟着原路径，原路径同样算作改动
+        if code.starts_with(['R', 'C'])
+            && let Some(origin) = records.next()
+        {
+            paths.insert(origin.to_string());
+        }
+    }
+    Ok(paths)
+}
+
+fn deferred_pathspec() -> Vec<&'static str> {
+    std::iter::once(".")
+        .chain(DEFERRED_EXCLUDES.iter().copied())
+        .collect()
+}
+
+fn git(cwd: &Path, args: &[&str]) -> Result<String> {
+    git_raw(cwd, args).map(|out| out.trim().to_string())
+}
+
+/// 不裁剪首尾空白的输出；`status --porcelain` 的状态列以空格开头。
+fn git_raw(cwd: &Path, args: &[&str]) -> Result<String> {
+    run_git(Command::new("git").current_dir(cwd).args(args), args)
+}
+
+fn git_with_index(cwd: &Path, index: &Path, args: &[&str]) -> Result<String> {
+    run_git(
+        Command::new("git")
+            .current_dir(cwd)
+            .env("GIT_INDEX_FILE", index)
+            .args(args),
+        args,
+    )
+    .map(|out| out.trim().to_string())
+}
+
+fn run_git(command: &mut Command, args: &[&str]) -> Result<String> {
+    let out = command
+        .output()
+        .with_context(|| format!("failed to run git {}", args.join(" ")))?;
+    if !out.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or(""),
+            truncate(String::from_utf8_lossy(&out.stderr).trim(), 220)
+        );
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// `GOLDBOT_GE_GIT_CONTEXT`：喂给 executor 的最近提交数，0 表示不提供 git 上下文。
+pub fn git_context_depth() -> usize {
+    std::env::var("GOLDBOT_GE_GIT_CONTEXT")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .map(|depth| depth.min(GIT_CONTEXT_MAX_DEPTH))
+        .unwrap_or(DEFAULT_GIT_CONTEXT_DEPTH)
+}
+
+/// 最近 `depth` 个提交的标题与 `--stat`，供 executor prompt 使用。
+pub fn latest_commit_context(depth: usize) -> Option<String> {
+    if depth == 0 {
+        return None;
+    }
+    let cmd = format!("git log -{depth} --stat --no-color --format='%x1e%h %s'");
+    let out = run_command(&cmd).ok()?;
     if out.exit_code != 0 {
         return None;
     }
-    Some(truncate(&out.output, 500))
+    // 第一个分隔符之前只可能是 shell 的杂项输出，丢弃
+    let commits: Vec<&str> = out.output.split('\x1e').skip(1).collect();
+    format_commit_context(&commits, depth)
+}
+
+fn format_commit_context(commits: &[&str], depth: usize) -> Option<String> {
+    let blocks: Vec<String> = commits
+        .iter()
+        .map(|c| c.trim())
+        .filter(|c| !c.is_empty())
+        .take(depth)
+        .map(|c| truncate(c, COMMIT_CONTEXT_CHARS))
+        .collect();
+    if blocks.is_empty() {
+        None
+    } else {
+        Some(blocks.join("\n\n"))
+    }
 }
 
 fn truncate(s: &str, max: usize) -> String {
@@ -330,7 +670,258 @@ fn shell_single_quote(s: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{ExecDecision, ReviewDecision, claude_exec_decision, codex_review_decision};
+    use super::{
+        ExecDecision, ReviewDecision, ValidationReport, claude_exec_decision,
+        codex_review_decision, deferred_branch_name, delete_deferred_branch, dirty_paths,
+        format_commit_context, format_review_report, git, head_commit, stash_deferred_changes,
+        todo_commit_landed, validate_done_when,
+    };
+    use crate::types::ExecutorOutcome;
+    use std::collections::BTreeSet;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    };
+    use std::time::{Duration, Instant};
+
+    #[cfg(unix)]
+    #[test]
+    fn cancelled_validation_returns_cancelled_instead_of_blocking() {
+        let cwd = std::env::temp_dir();
+        let done_when = vec!["cmd: sleep 30".to_string()];
+
+        // 已经 hard exit：不启动命令
+        let cancel = Arc::new(AtomicBool::new(true));
+        let report = validate_done_when(&done_when, &cwd, &cancel);
+        assert_eq!(report.outcome, ExecutorOutcome::Cancelled);
+
+        // 校验进行中 hard exit：立即终止命令
+        let cancel = Arc::new(AtomicBool::new(false));
+        let canceller = {
+            let cancel = cancel.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(300));
+                cancel.store(true, Ordering::SeqCst);
+            })
+        };
+        let started = Instant::now();
+        let report = validate_done_when(&done_when, &cwd, &cancel);
+        canceller.join().unwrap();
+        assert_eq!(
+            report.outcome,
+            ExecutorOutcome::Cancelled,
+            "{}",
+            report.summary
+        );
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        let ok = validate_done_when(
+            &["cmd: true".to_string()],
+            &cwd,
+            &Arc::new(AtomicBool::new(false)),
+        );
+        assert_eq!(ok.outcome, ExecutorOutcome::Success, "{}", ok.summary);
+    }
+
+    #[test]
+    fn format_commit_context_respects_depth() {
+        let commits = [
+            "a1b2c3d Add parser\n\n src/parser.rs | 40 ++++\n 1 file changed, 40 insertions(+)\n",
+            "d4e5f6a Fix lexer\n\n src/lexer.rs | 2 +-\n",
+            "0a0b0c0 Init\n",
+            "9f9f9f9 Older commit\n",
+        ];
+        assert_eq!(format_commit_context(&commits, 0), None);
+        assert_eq!(
+            format_commit_context(&commits, 1).as_deref(),
+            Some(
+                "a1b2c3d Add parser\n\n src/parser.rs | 40 ++++\n 1 file changed, 40 insertions(+)"
+            )
+        );
+        let three = format_commit_context(&commits, 3).unwrap();
+        assert!(three.starts_with("a1b2c3d Add parser"));
+        assert!(three.contains("(+)\n\nd4e5f6a Fix lexer"));
+        assert!(three.ends_with(" src/lexer.rs | 2 +-\n\n0a0b0c0 Init"));
+        assert!(!three.contains("Older commit"));
+        assert_eq!(format_commit_context(&[""], 3), None);
+    }
+
+    #[test]
+    fn format_review_report_renders_status_and_stat() {
+        let report = ValidationReport {
+            outcome: ExecutorOutcome::Success,
+            summary: "status:  M src/main.rs\n?? notes.md | diff:  src/main.rs | 3 ++-\n 1 file changed, 2 insertions(+), 1 deletion(-)".to_string(),
+            exit_code: 0,
+        };
+        assert_eq!(
+            format_review_report(&report),
+            [
+                "  ✓ self-review 通过：无空白错误",
+                "  变更文件：",
+                "     M src/main.rs",
+                "    ?? notes.md",
+                "  统计：",
+                "     src/main.rs | 3 ++-",
+                "     1 file changed, 2 insertions(+), 1 deletion(-)",
+            ]
+        );
+
+        let not_repo = ValidationReport {
+            outcome: ExecutorOutcome::Failed,
+            summary: "not a git repository: fatal: not a git repository".to_string(),
+            exit_code: 128,
+        };
+        let lines = format_review_report(&not_repo);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("不是 git 仓库"));
+
+        let whitespace = ValidationReport {
+            outcome: ExecutorOutcome::Failed,
+            summary: "git diff --check failed: src/lib.rs:3: trailing whitespace.".to_string(),
+            exit_code: 2,
+        };
+        assert_eq!(
+            format_review_report(&whitespace)[1],
+            "    src/lib.rs:3: trailing whitespace."
+        );
+    }
+
+    #[test]
+    fn deferred_branch_name_sanitizes_todo_id() {
+        assert_eq!(deferred_branch_name("T003"), "goldbot/deferred/T003");
+        assert_eq!(deferred_branch_name(" T 0:4~^ "), "goldbot/deferred/T-0-4");
+        assert_eq!(deferred_branch_name("../"), "goldbot/deferred/todo");
+    }
+
+    #[test]
+    fn stash_deferred_changes_leaves_clean_tree_and_recoverable_branch() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let repo = std::env::temp_dir().join(format!("goldbot-ge-deferred-{nanos}"));
+        std::fs::create_dir_all(&repo).unwrap();
+        let run = |args: &[&str]| git(&repo, args).unwrap();
+        run(&["init", "-q"]);
+        run(&["config", "user.email", "ge@example.com"]);
+        run(&["config", "user.name", "GE"]);
+        std::fs::write(repo.join("lib.rs"), "fn a() {}\n").unwrap();
+        std::fs::write(repo.join("notes.md"), "draft\n").unwrap();
+        std::fs::write(repo.join("CONSENSUS.md"), "# Consensus\n").unwrap();
+        run(&["add", "-A"]);
+        run(&["commit", "-qm", "init"]);
+
+        // 干净的工作区：不创建分支
+        assert!(dirty_paths(&repo).unwrap().is_empty());
+        assert_eq!(
+            stash_deferred_changes(&repo, "T001", &BTreeSet::new()).unwrap(),
+            None
+        );
+
+        // 执行器开始前用户已有的改动、未跟踪文件与 .goldbot/ 都不属于执行器
+        std::fs::write(repo.join("notes.md"), "draft\nuser edit\n").unwrap();
+        std::fs::write(repo.join("scratch.txt"), "mine\n").unwrap();
+        std::fs::create_dir_all(repo.join(".goldbot")).unwrap();
+        std::fs::write(repo.join(".goldbot/exec_env"), "TOKEN=secret\n").unwrap();
+        let preexisting = dirty_paths(&repo).unwrap();
+        let names: Vec<&str> = preexisting.iter().map(String::as_str).collect();
+        assert_eq!(names, ["notes.md", "scratch.txt"]);
+        assert_eq!(
+            stash_deferred_changes(&repo, "T001", &preexisting).unwrap(),
+            None
+        );
+
+        std::fs::write(repo.join("lib.rs"), "fn a() { todo!() }\n").unwrap();
+        std::fs::create_dir_all(repo.join("src")).unwrap();
+        std::fs::write(repo.join("src/new.rs"), "fn b() {}\n").unwrap();
+        std::fs::write(repo.join("CONSENSUS.md"), "# Consensus\n- T001 deferred\n").unwrap();
+        assert!(!dirty_paths(&repo).unwrap().is_empty());
+
+        let branch = stash_deferred_changes(&repo, "T001", &preexisting).unwrap();
+        assert_eq!(branch.as_deref(), Some("goldbot/deferred/T001"));
+        assert_eq!(dirty_paths(&repo).unwrap(), preexisting);
+        assert!(!repo.join("src/new.rs").exists());
+        assert_eq!(
+            std::fs::read_to_string(repo.join("lib.rs")).unwrap(),
+            "fn a() {}\n"
+        );
+        // 用户的改动与 .goldbot/ 原样保留，也不进入延期分支
+        assert_eq!(
+            std::fs::read_to_string(repo.join("notes.md")).unwrap(),
+            "draft\nuser edit\n"
+        );
+        assert!(repo.join("scratch.txt").exists());
+        assert!(repo.join(".goldbot/exec_env").exists());
+        assert_eq!(run(&["show", "goldbot/deferred/T001:notes.md"]), "draft");
+        assert!(
+            git(
+                &repo,
+                &["cat-file", "-e", "goldbot/deferred/T001:scratch.txt"]
+            )
+            .is_err()
+        );
+        assert!(
+            git(
+                &repo,
+                &["cat-file", "-e", "goldbot/deferred/T001:.goldbot/exec_env"]
+            )
+            .is_err()
+        );
+        // GE 自己的文档不受影响
+        assert!(
+            std::fs::read_to_string(repo.join("CONSENSUS.md"))
+                .unwrap()
+                .contains("T001 deferred")
+        );
+        assert_eq!(
+            run(&["show", "goldbot/deferred/T001:src/new.rs"]),
+            "fn b() {}"
+        );
+
+        assert!(delete_deferred_branch(&repo, "T001"));
+        assert!(!delete_deferred_branch(&repo, "T001"));
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[test]
+    fn todo_commit_landed_only_counts_ge_commits_after_marker_head() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let repo = std::env::temp_dir().join(format!("goldbot-ge-landed-{nanos}"));
+        std::fs::create_dir_all(&repo).unwrap();
+        let run = |args: &[&str]| git(&repo, args).unwrap();
+        run(&["init", "-q"]);
+        run(&["config", "user.email", "ge@example.com"]);
+        run(&["config", "user.name", "GE"]);
+        assert_eq!(head_commit(&repo), None);
+        assert!(!todo_commit_landed(&repo, "T001", None));
+
+        run(&[
+            "commit",
+            "-q",
+            "--allow-empty",
+            "-m",
+            "GE(T001): old attempt",
+        ]);
+        let head = head_commit(&repo).expect("head");
+        // 标记记录的 HEAD 之前的同名提交不算
+        assert!(!todo_commit_landed(&repo, "T001", Some(&head)));
+        assert!(todo_commit_landed(&repo, "T001", None));
+
+        run(&[
+            "commit",
+            "-q",
+            "--allow-empty",
+            "-m",
+            "GE(T002): add parser",
+        ]);
+        assert!(todo_commit_landed(&repo, "T002", Some(&head)));
+        assert!(!todo_commit_landed(&repo, "T001", Some(&head)));
+        assert!(!todo_commit_landed(&repo, "T00", Some(&head)));
+        let _ = std::fs::remove_dir_all(repo);
+    }
 
     #[test]
     fn codex_review_blocks_ignores_prompt_echo_blocking_phrase() {
@@ -1,10 +1,11 @@
 use std::{
+    collections::BTreeMap,
     fs,
     path::{Path, PathBuf},
     process::{Command, Stdio},
     sync::{
         Arc,
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU32, Ordering},
     },
     thread,
     time::{Duration, SystemTime, UNIX_EPOCH},
@@ -12,6 +13,10 @@ use std::{
 
 use crate::types::ExecutorOutcome;
 
+/// 当前正在运行的 Claude/Codex 子进程 PID（0 表示没有）。
+/// 子进程独占一个进程组，hard exit 时可连同其派生的子进程一起终止。
+static RUNNING_EXECUTOR_PID: AtomicU32 = AtomicU32::new(0);
+
 #[derive(Debug, Clone)]
 pub struct ExecutorRun {
     pub executor: &'static str,
@@ -28,6 +33,51 @@ impl ExecutorRun {
     }
 }
 
+/// 立即终止正在运行的 executor 进程组，返回被终止的 PID。
+/// 子进程由 `run_process` 的等待循环回收，不会留下僵尸进程。
+pub fn kill_running_executor() -> Option<u32> {
+    let pid = RUNNING_EXECUTOR_PID.load(Ordering::SeqCst);
+    if pid == 0 {
+        return None;
+    }
+    terminate_process_group(pid);
+    Some(pid)
+}
+
+/// 在 `run_process` 返回前清除登记的 PID（含提前返回的错误路径）。
+struct RunningExecutorGuard(u32);
+
+impl RunningExecutorGuard {
+    fn register(pid: u32) -> Self {
+        RUNNING_EXECUTOR_PID.store(pid, Ordering::SeqCst);
+        Self(pid)
+    }
+}
+
+impl Drop for RunningExecutorGuard {
+    fn drop(&mut self) {
+        let _ =
+            RUNNING_EXECUTOR_PID.compare_exchange(self.0, 0, Ordering::SeqCst, Ordering::SeqCst);
+    }
+}
+
+fn terminate_process_group(pid: u32) {
+    #[cfg(target_os = "windows")]
+    let _ = Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    #[cfg(not(target_os = "windows"))]
+    let _ = Command::new("kill")
+        .args(["-KILL", "--", &format!("-{pid}")])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
 pub fn preflight(cwd: &Path, cancel: &Arc<AtomicBool>) -> Vec<ExecutorRun> {
     const PREFLIGHT_PROMPT: &str =
         "Preflight check. Do not edit files or run tools. Reply with one line: OK";
@@ -74,6 +124,27 @@ pub fn run_codex_execute(cwd: &Path, prompt: &str, cancel: &Arc<AtomicBool>) ->
     )
 }
 
+/// 以与 executor 相同的可终止方式运行 GE 的校验命令（done_when / self-review / commit），
+/// hard exit 时连同进程组一起终止，返回 `error_code = "cancelled"`。
+pub fn run_check_command(cwd: &Path, cmd: &str, cancel: &Arc<AtomicBool>) -> ExecutorRun {
+    let (program, flag) = if cfg!(target_os = "windows") {
+        ("powershell", "-Command")
+    } else {
+        ("bash", "-lc")
+    };
+    // 与工具命令一致，注入 `.goldbot/exec_env`，去掉 init.sh unset 的变量
+    let env = crate::memory::profile::exec_env(cwd)
+        .into_iter()
+        .map(|(key, value)| (key, Some(value)))
+        .chain(
+            crate::memory::profile::exec_env_removed(cwd)
+                .into_iter()
+                .map(|key| (key, None)),
+        )
+        .collect();
+    run_process_with_env(program, &[flag, cmd], cwd, &env, "goldbot", cmd, cancel)
+}
+
 fn run_process(
     program: &str,
     args: &[&str],
@@ -81,6 +152,27 @@ fn run_process(
     executor: &'static str,
     command_line: &str,
     cancel: &Arc<AtomicBool>,
+) -> ExecutorRun {
+    run_process_with_env(
+        program,
+        args,
+        cwd,
+        &BTreeMap::new(),
+        executor,
+        command_line,
+        cancel,
+    )
+}
+
+fn run_process_with_env(
+    program: &str,
+    args: &[&str],
+    cwd: &Path,
+    // None 表示从子进程环境中去掉该变量
+    env: &BTreeMap<String, Option<String>>,
+    executor: &'static str,
+    command_line: &str,
+    cancel: &Arc<AtomicBool>,
 ) -> ExecutorRun {
     if cancel.load(Ordering::SeqCst) {
         return ExecutorRun {
@@ -122,13 +214,23 @@ fn run_process(
         }
     };
 
-    let mut child = match Command::new(program)
+    let mut command = Command::new(program);
+    command
         .args(args)
         .current_dir(cwd)
+        .stdin(Stdio::null())
         .stdout(Stdio::from(stdout_file))
-        .stderr(Stdio::from(stderr_file))
-        .spawn()
-    {
+        .stderr(Stdio::from(stderr_file));
+    for (key, value) in env {
+        match value {
+            Some(value) => command.env(key, value),
+            None => command.env_remove(key),
+        };
+    }
+    // 独立进程组：取消时可一并终止 executor 派生的子进程
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = match command.spawn() {
         Ok(c) => c,
         Err(e) => {
             let _ = fs::remove_file(&stdout_path);
@@ -144,10 +246,12 @@ fn run_process(
         }
     };
 
+    let _running = RunningExecutorGuard::register(child.id());
     let mut cancelled = false;
     let exit_code = loop {
-        if cancel.load(Ordering::SeqCst) {
+        if cancel.load(Ordering::SeqCst) && !cancelled {
             cancelled = true;
+            terminate_process_group(child.id());
             let _ = child.kill();
         }
         match child.try_wait() {
@@ -517,6 +621,59 @@ fn read_capture_file(path: &Path) -> String {
 mod tests {
     use super::detect_error_code;
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn cancel_kills_whole_executor_process_group() {
+        use super::{RUNNING_EXECUTOR_PID, kill_running_executor, run_process};
+        use std::sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        };
+        use std::time::{Duration, Instant};
+
+        let dir = std::env::temp_dir().join(format!("goldbot-ge-kill-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let canceller = {
+            let cancel = cancel.clone();
+            std::thread::spawn(move || {
+                let deadline = Instant::now() + Duration::from_secs(5);
+                while RUNNING_EXECUTOR_PID.load(Ordering::SeqCst) == 0 && Instant::now() < deadline
+                {
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+                std::thread::sleep(Duration::from_millis(200));
+                let killed = kill_running_executor();
+                cancel.store(true, Ordering::SeqCst);
+                killed
+            })
+        };
+
+        // 后台 sleep 模拟 executor 派生的长时间子进程
+        let started = Instant::now();
+        let run = run_process(
+            "sh",
+            &["-c", "sleep 30 & echo $! > grandchild.pid; wait"],
+            &dir,
+            "claude",
+            "sh -c <sleep>",
+            &cancel,
+        );
+        assert!(canceller.join().unwrap().is_some());
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(run.error_code.as_deref(), Some("cancelled"));
+        assert_eq!(RUNNING_EXECUTOR_PID.load(Ordering::SeqCst), 0);
+
+        // 孙进程也必须被终止（已退出或仅剩待 init 回收的僵尸）
+        let grandchild = std::fs::read_to_string(dir.join("grandchild.pid")).unwrap();
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", grandchild.trim()));
+        if let Ok(stat) = stat {
+            let state = stat.rsplit(')').next().unwrap_or("").trim_start();
+            assert!(state.starts_with('Z'), "grandchild still alive: {stat}");
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
     #[test]
     fn detect_error_code_does_not_mark_approval_never_as_manual_confirm() {
         let out = "approval: never\nsandbox: danger-full-access\ncompleted";
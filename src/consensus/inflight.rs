@@ -0,0 +1,169 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// 在途标记文件名，与 CONSENSUS.md / GE_LOG.jsonl 放在同一目录；提交与暂存时都排除它。
+pub const INFLIGHT_FILE_NAME: &str = ".ge_inflight";
+
+/// run_once 中会改动工作区或仓库的阶段，按执行顺序排列。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InflightStage {
+    Execute,
+    Review,
+    Commit,
+}
+
+impl InflightStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Execute => "execute",
+            Self::Review => "review",
+            Self::Commit => "commit",
+        }
+    }
+}
+
+/// 破坏性阶段开始前写下的意图：哪个 todo、哪个阶段，以及开始执行该 todo 时的 HEAD。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InflightMarker {
+    pub todo_id: String,
+    pub stage: InflightStage,
+    /// 开始执行时的 HEAD，用来判断崩溃前提交是否已落地；仓库还没有提交时为 None。
+    pub head: Option<String>,
+}
+
+/// 重启后对中断 todo 的处理方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeAction {
+    /// 标记指向的 todo 已勾选或已不存在：直接丢弃标记。
+    Discard,
+    /// 提交已落地但 CONSENSUS.md 未记录：只补勾选与保存，不再重跑。
+    RecordCommit,
+    /// 执行、审查与校验都已通过，只差提交：从提交阶段继续。
+    Commit,
+    /// 改动可能只做了一半：从头重跑该 todo。
+    Rerun,
+}
+
+pub fn inflight_path(consensus_path: &Path) -> PathBuf {
+    consensus_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(INFLIGHT_FILE_NAME)
+}
+
+pub fn write_inflight(consensus_path: &Path, marker: &InflightMarker) -> Result<()> {
+    let path = inflight_path(consensus_path);
+    let raw = serde_json::to_string(marker)?;
+    fs::write(&path, raw).with_context(|| format!("failed to write `{}`", path.display()))
+}
+
+/// 读取在途标记；文件不存在或内容损坏时返回 None（损坏的标记无法指导恢复）。
+pub fn read_inflight(consensus_path: &Path) -> Option<InflightMarker> {
+    let raw = fs::read_to_string(inflight_path(consensus_path)).ok()?;
+    serde_json::from_str(raw.trim()).ok()
+}
+
+pub fn clear_inflight(consensus_path: &Path) {
+    let _ = fs::remove_file(inflight_path(consensus_path));
+}
+
+/// `todo_checked` 为 None 表示 CONSENSUS.md 中已没有这个 todo；`commit_landed` 表示 git log
+/// 中已有该 todo 在 `marker.head` 之后的 GE 提交。
+pub fn resume_action(
+    marker: &InflightMarker,
+    todo_checked: Option<bool>,
+    commit_landed: bool,
+) -> ResumeAction {
+    match todo_checked {
+        None | Some(true) => ResumeAction::Discard,
+        Some(false) if commit_landed => ResumeAction::RecordCommit,
+        Some(false) if marker.stage == InflightStage::Commit => ResumeAction::Commit,
+        Some(false) => ResumeAction::Rerun,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        InflightMarker, InflightStage, ResumeAction, clear_inflight, inflight_path, read_inflight,
+        resume_action, write_inflight,
+    };
+
+    fn marker(stage: InflightStage) -> InflightMarker {
+        InflightMarker {
+            todo_id: "T003".to_string(),
+            stage,
+            head: Some("abc123".to_string()),
+        }
+    }
+
+    #[test]
+    fn inflight_marker_is_written_next_to_consensus_and_cleared() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("goldbot-ge-inflight-{nanos}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        let consensus = dir.join("CONSENSUS.md");
+        assert_eq!(inflight_path(&consensus), dir.join(".ge_inflight"));
+        assert_eq!(read_inflight(&consensus), None);
+
+        write_inflight(&consensus, &marker(InflightStage::Execute)).unwrap();
+        // 进入下一阶段时覆盖写入
+        write_inflight(&consensus, &marker(InflightStage::Commit)).unwrap();
+        assert_eq!(
+            read_inflight(&consensus),
+            Some(marker(InflightStage::Commit))
+        );
+        let raw = std::fs::read_to_string(dir.join(".ge_inflight")).unwrap();
+        assert!(raw.contains(r#""stage":"commit""#), "{raw}");
+
+        clear_inflight(&consensus);
+        assert_eq!(read_inflight(&consensus), None);
+        // 重复清除不报错；损坏的标记视为不存在
+        clear_inflight(&consensus);
+        std::fs::write(dir.join(".ge_inflight"), "{not json").unwrap();
+        assert_eq!(read_inflight(&consensus), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn resume_action_follows_stage_and_git_state() {
+        use InflightStage::*;
+        // 提交已落地：无论记录的是哪个阶段，都只补记录
+        for stage in [Execute, Review, Commit] {
+            assert_eq!(
+                resume_action(&marker(stage), Some(false), true),
+                ResumeAction::RecordCommit
+            );
+        }
+        assert_eq!(
+            resume_action(&marker(Commit), Some(false), false),
+            ResumeAction::Commit
+        );
+        assert_eq!(
+            resume_action(&marker(Execute), Some(false), false),
+            ResumeAction::Rerun
+        );
+        assert_eq!(
+            resume_action(&marker(Review), Some(false), false),
+            ResumeAction::Rerun
+        );
+        // 已勾选或已删除的 todo 不再处理
+        assert_eq!(
+            resume_action(&marker(Commit), Some(true), true),
+            ResumeAction::Discard
+        );
+        assert_eq!(
+            resume_action(&marker(Execute), None, false),
+            ResumeAction::Discard
+        );
+    }
+}
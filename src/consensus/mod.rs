@@ -2,5 +2,6 @@ pub mod audit;
 pub mod engine;
 pub mod evaluate;
 pub mod external;
+pub mod inflight;
 pub mod model;
 pub mod subagent;
@@ -4,7 +4,7 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 
 pub const CONSENSUS_FILE_NAME: &str = "CONSENSUS.md";
 
@@ -23,6 +23,13 @@ pub struct TodoItem {
     pub assist: Option<String>,
 }
 
+/// `load_lenient` 的结果：尽力解析出的文档，以及解析时跳过或补全的问题描述。
+#[derive(Debug, Clone)]
+pub struct LenientLoad {
+    pub doc: ConsensusDoc,
+    pub issues: Vec<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ConsensusDoc {
     pub purpose_lines: Vec<String>,
@@ -127,10 +134,6 @@ impl ConsensusDoc {
         out
     }
 
-    pub fn first_open_todo_index(&self) -> Option<usize> {
-        self.todos.iter().position(|t| !t.checked)
-    }
-
     pub fn all_done(&self) -> bool {
         !self.todos.is_empty() && self.todos.iter().all(|t| t.checked)
     }
@@ -164,6 +167,50 @@ pub fn load(path: &Path) -> Result<ConsensusDoc> {
     Ok(ConsensusDoc::parse(&raw))
 }
 
+/// 宽松加载：尽量解析文档，并报告损坏的行/缺失的段落。
+/// 只有在文档无法恢复（没有 Todo 段，或 Todo 段只剩损坏行）时才返回错误。
+pub fn load_lenient(path: &Path) -> Result<LenientLoad> {
+    let raw =
+        fs::read_to_string(path).with_context(|| format!("failed to read `{}`", path.display()))?;
+    parse_lenient(&raw).with_context(|| format!("`{}` is unrecoverable", path.display()))
+}
+
+pub fn parse_lenient(text: &str) -> Result<LenientLoad> {
+    let sections = split_sections(text);
+    if !sections.contains_key(TODO_SECTION) {
+        bail!("missing `## {TODO_SECTION}` section");
+    }
+
+    let mut issues = Vec::new();
+    for name in [PURPOSE_SECTION, RULES_SECTION] {
+        if !sections.contains_key(name) {
+            issues.push(format!("missing `## {name}` section; using default"));
+        }
+    }
+
+    // 逐行检查 Todo 段，记录无法识别的条目（行号从 1 开始）
+    let mut in_todo = false;
+    for (idx, line) in text.lines().enumerate() {
+        if let Some(rest) = line.strip_prefix("## ") {
+            in_todo = rest.trim() == TODO_SECTION;
+            continue;
+        }
+        if in_todo && !is_recognized_todo_line(line) {
+            issues.push(format!(
+                "line {}: skipped malformed todo `{}`",
+                idx + 1,
+                line.trim()
+            ));
+        }
+    }
+
+    let doc = ConsensusDoc::parse(text);
+    if doc.todos.is_empty() && !issues.is_empty() {
+        bail!("`## {TODO_SECTION}` section has no parseable todos");
+    }
+    Ok(LenientLoad { doc, issues })
+}
+
 pub fn save(path: &Path, doc: &ConsensusDoc) -> Result<()> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
@@ -357,6 +404,16 @@ fn parse_todos(lines: &[String]) -> Vec<TodoItem> {
     out
 }
 
+fn is_recognized_todo_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.trim().is_empty()
+        || trimmed.starts_with("- [ ] ")
+        || trimmed.starts_with("- [x] ")
+        || trimmed.starts_with("- [X] ")
+        || trimmed.starts_with("- done_when:")
+        || trimmed.starts_with("- assist:")
+}
+
 fn looks_like_todo_id(s: &str) -> bool {
     let Some(rest) = s.strip_prefix('T') else {
         return false;
@@ -388,7 +445,7 @@ fn trim_lines(lines: &mut Vec<String>, max: usize) {
 
 #[cfg(test)]
 mod tests {
-    use super::{ConsensusDoc, build_from_interview};
+    use super::{ConsensusDoc, build_from_interview, parse_lenient};
 
     #[test]
     fn parse_and_render_roundtrip_has_todos() {
@@ -410,4 +467,21 @@ mod tests {
         assert_eq!(doc.todos[0].id, "T001");
         assert!(!doc.todos[0].checked);
     }
+
+    #[test]
+    fn lenient_parse_skips_single_bad_todo_line() {
+        let raw = "## Purpose\n- Ship\n\n## Rules\n- Test\n\n## Todo\n- [ ] T001 Good one\n  - done_when: cmd: ls\n- [?] T002 Broken checkbox\n- [ ] T003 Another\n\n## Bot Status\n- idle\n";
+        let loaded = parse_lenient(raw).expect("salvageable");
+        assert_eq!(loaded.doc.todos.len(), 2);
+        assert_eq!(loaded.doc.todos[1].id, "T003");
+        assert_eq!(loaded.issues.len(), 1);
+        assert!(loaded.issues[0].contains("line 10"), "{:?}", loaded.issues);
+    }
+
+    #[test]
+    fn lenient_parse_rejects_missing_todo_section() {
+        let raw = "## Purpose\n- Ship\n\n## Rules\n- Test\n\n## Bot Status\n- idle\n";
+        let err = parse_lenient(raw).unwrap_err();
+        assert!(err.to_string().contains("Todo"));
+    }
 }
@@ -11,7 +11,10 @@ use std::{
 
 use anyhow::Result;
 
-use crate::{consensus::engine::GeRuntime, types::Mode};
+use crate::{
+    consensus::{engine::GeRuntime, external::kill_running_executor},
+    types::Mode,
+};
 
 const SUBAGENT_LOOP_INTERVAL: Duration = Duration::from_millis(120);
 const RUN_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(3 * 60);
@@ -21,8 +24,10 @@ const HEARTBEAT_POLL_INTERVAL: Duration = Duration::from_millis(200);
 pub enum GeAgentCommand {
     InterviewReply(String),
     ReplanTodos,
+    RunTodo(String),
     ExpandLastPrompt,
     ExpandLastResult,
+    DiffConsensus,
     Exit,
 }
 
@@ -36,6 +41,7 @@ pub enum GeAgentEvent {
 
 pub struct GeSubagent {
     cmd_tx: Sender<GeAgentCommand>,
+    evt_tx: Sender<GeAgentEvent>,
     evt_rx: Receiver<GeAgentEvent>,
     cancel_flag: Arc<AtomicBool>,
 }
@@ -50,10 +56,12 @@ impl GeSubagent {
         send_lines(&evt_tx, initial_lines);
         let _ = evt_tx.send(GeAgentEvent::ModeChanged(initial_mode));
 
-        thread::spawn(move || run_worker(runtime, cmd_rx, evt_tx));
+        let worker_tx = evt_tx.clone();
+        thread::spawn(move || run_worker(runtime, cmd_rx, worker_tx));
 
         Ok(Self {
             cmd_tx,
+            evt_tx,
             evt_rx,
             cancel_flag,
         })
@@ -65,6 +73,12 @@ impl GeSubagent {
 
     pub fn hard_exit(&self) -> bool {
         self.cancel_flag.store(true, Ordering::SeqCst);
+        // 不等下一次取消检查，直接终止正在运行的 Claude/Codex 进程组
+        if let Some(pid) = kill_running_executor() {
+            let _ = self.evt_tx.send(GeAgentEvent::OutputLines(vec![format!(
+                "  GE: executor terminated (pid {pid})"
+            )]));
+        }
         self.cmd_tx.send(GeAgentCommand::Exit).is_ok()
     }
 
@@ -110,6 +124,12 @@ fn run_worker(
                         let _ = evt_tx.send(GeAgentEvent::Error(format!("GE replan failed: {e}")));
                     }
                 },
+                GeAgentCommand::RunTodo(id) => match runtime.run_todo(&id) {
+                    Ok(lines) => send_lines(&evt_tx, lines),
+                    Err(e) => {
+                        let _ = evt_tx.send(GeAgentEvent::Error(format!("GE run failed: {e}")));
+                    }
+                },
                 GeAgentCommand::ExpandLastPrompt => {
                     let lines = runtime.expand_last_prompt();
                     send_lines(&evt_tx, lines);
@@ -118,6 +138,10 @@ fn run_worker(
                     let lines = runtime.expand_last_result();
                     send_lines(&evt_tx, lines);
                 }
+                GeAgentCommand::DiffConsensus => {
+                    let lines = runtime.diff_consensus();
+                    send_lines(&evt_tx, lines);
+                }
                 GeAgentCommand::Exit => {
                     let lines = runtime.exit();
                     send_lines(&evt_tx, lines);
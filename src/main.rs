@@ -1,5 +1,6 @@
 mod agent;
 mod cli;
+mod config;
 mod consensus;
 mod memory;
 mod tools;
@@ -24,11 +25,14 @@ use agent::{
         maybe_spawn_llm_worker, perform_manual_compact,
         poll_dag_result, poll_shell_exec_result,
         process_llm_result, refresh_llm_status,
+        graceful_quit_ready, save_interrupted_task,
         should_run_pending_manual_compact, shutdown_background_work,
-        start_task, sync_context_budget,
+        start_task, sync_context_budget, sync_git_branch, tick_confirm_timeout,
+        tick_task_time_budget,
     },
+    prompt::SystemPromptBuilder,
     provider::{LlmBackend, Message, build_http_client},
-    react::{build_system_prompt, build_workspace_context},
+    react::{build_system_prompt, build_workspace_context, strip_disabled_tools},
 };
 use crossterm::{
     event::{DisableBracketedPaste, EnableBracketedPaste},
@@ -56,6 +60,8 @@ pub(crate) struct App {
     pub messages: Vec<Message>,
     pub task: String,
     pub steps_taken: usize,
+    /// 连续无法解析的 LLM 响应次数；解析成功或新任务开始时清零。
+    pub parse_failures: usize,
     pub llm_calling: bool,
     /// Start time of the current in-flight LLM request (for status elapsed display).
     pub llm_call_started_at: Option<std::time::Instant>,
@@ -63,15 +69,34 @@ pub(crate) struct App {
     pub task_started_at: Option<std::time::Instant>,
     /// Total elapsed time of the last finished task, for post-final UI display.
     pub last_task_elapsed: Option<std::time::Duration>,
+    /// 上一个任务结束的本地时间，时间戳开启时折叠视图显示起止时间。
+    pub task_finished_clock: Option<chrono::DateTime<chrono::Local>>,
     pub llm_stream_preview: String,
     pub llm_preview_shown: String,
     pub needs_agent_executor: bool,
+    /// Space / p 暂停：不再发起下一次 LLM 调用，正在进行的调用照常完成。
+    pub paused: bool,
+    /// 上次查询当前 git 分支的时间，见 `sync_git_branch`。
+    pub git_branch_checked_at: Option<std::time::Instant>,
     pub shell_task_running: bool,
     pub shell_exec_rx: Option<tokio::sync::mpsc::UnboundedReceiver<ShellExecResult>>,
     pub dag_task_running: bool,
     pub dag_result_rx: Option<tokio::sync::oneshot::Receiver<anyhow::Result<DagResult>>>,
     pub dag_progress_rx: Option<tokio::sync::mpsc::UnboundedReceiver<agent::dag::NodeProgress>>,
     pub dag_cancel_flag: Arc<AtomicBool>,
+    /// `@claude` / `@codex` 前缀把当前任务交给外部 executor 执行中。
+    pub external_task_running: bool,
+    pub external_result_rx:
+        Option<tokio::sync::oneshot::Receiver<crate::consensus::external::ExecutorRun>>,
+    pub external_cancel_flag: Arc<AtomicBool>,
+    /// 委派前的确认菜单（外部 executor 不经 GoldBot 确认执行命令）：(executor, 任务, 显示文本)
+    pub pending_delegation: Option<(agent::delegate::ExternalExecutor, String, String)>,
+    /// `/explain` 的诊断结果（后台单独调用 LLM，不进入任务对话）。
+    pub explain_rx: Option<
+        tokio::sync::oneshot::Receiver<Result<String, crate::agent::provider::ProviderError>>,
+    >,
+    /// 正在诊断的失败命令，结果标题中显示。
+    pub explain_command: String,
     /// `task_events` 中 DAG 树 ToolCall 事件的索引，用于原地刷新展示。
     pub dag_tree_event_idx: Option<usize>,
     /// node_id -> (success, elapsed_secs)
@@ -82,18 +107,56 @@ pub(crate) struct App {
     pub dag_output_nodes: Vec<String>,
     /// User pressed Esc to stop the active LLM loop; run_loop should abort the in-flight worker.
     pub interrupt_llm_loop_requested: bool,
+    /// 运行中按下 Ctrl+C 的时间：正在优雅退出（取消调用、写入记忆与会话后退出）。
+    pub graceful_quit_at: Option<std::time::Instant>,
     /// Next normal user input should be sent as an in-conversation interjection, not a new task.
     pub interjection_mode: bool,
+    /// 插话时附上最近一次工具结果（插话模式下按 Ctrl+L 切换，发送后复位）。
+    pub interjection_include_output: bool,
     pub running: bool,
     pub quit: bool,
     pub pending_confirm: Option<String>,
     pub pending_confirm_note: bool,
+    /// 待确认的 `<create_mcp>`：(确认菜单中展示的预览, 原始配置)；确认后才真正写入配置文件
+    pub pending_mcp_create: Option<(String, serde_json::Value)>,
+    /// 待确认的破坏性 MCP 调用（`destructiveHint`）：(确认菜单中展示的预览, 工具名, 参数)
+    pub pending_mcp_call: Option<(String, String, serde_json::Value)>,
+    /// `/mcp call` 直接调用破坏性工具前的确认菜单：(工具名, 参数)
+    pub pending_mcp_direct_call: Option<(String, serde_json::Value)>,
+    /// 待确认的 WriteFile / UpdateFile：(确认菜单中展示的 diff 预览, 原始动作)
+    pub pending_edit: Option<(String, types::LlmAction)>,
+    /// `GOLDBOT_CONFIRM_EDITS` 开启时，agent 模式下的结构化文件编辑也要先确认
+    pub confirm_edits: bool,
+    /// `GOLDBOT_AUTO_CONFIRM_BELOW`：accept-edits 模式下低于该数值风险的命令免确认
+    pub auto_confirm_below: u8,
+    /// 非 git 工作区中当前任务的编辑前快照，供 `/undo` 恢复；git 仓库中为 None
+    pub task_snapshot: Option<tools::snapshot::TaskSnapshot>,
+    /// 新建 MCP 服务端校验失败后等待用户选择是否回滚配置
+    pub pending_mcp_rollback: Option<types::McpRollback>,
+    /// `<create_mcp>` 写入后在后台试启动服务端；结果到达前任务暂停在这一步。
+    pub mcp_verify_rx: Option<tokio::sync::oneshot::Receiver<anyhow::Result<usize>>>,
+    /// 正在校验的服务端，校验失败时转为 `pending_mcp_rollback`。
+    pub mcp_verify_target: Option<types::McpRollback>,
+    /// 任务结束后在后台做短期记忆提升：(结果, 是否手动 `/memory promote` 触发)
+    pub memory_promote_rx: Option<(tokio::sync::oneshot::Receiver<anyhow::Result<usize>>, bool)>,
+    /// 当前确认菜单弹出的时间，用于超时自动决策
+    pub pending_confirm_started_at: Option<std::time::Instant>,
+    /// `GOLDBOT_CONFIRM_TIMEOUT_SECS` 开启时的超时时长与默认动作
+    pub confirm_timeout: Option<(Duration, types::ConfirmTimeoutAction)>,
+    /// `GOLDBOT_TASK_TIME_BUDGET_SECS` 开启时的单任务时长预算；bool 为 `GOLDBOT_TASK_TIME_HARD`（超时后自动结束）
+    pub task_time_budget: Option<(Duration, bool)>,
+    /// 本任务是否已提示过超出时长预算（只提示一次）
+    pub task_time_warned: bool,
     /// 当前阶段摘要。
     /// 可由 LLM 的 `phase` 工具显式设置，也可由运行时自动生成用于中间进度展示。
     pub current_phase_summary: Option<String>,
     /// 当前任务的展示事件日志，仅供 TUI 渲染。
     /// 这里允许折叠、美化、压缩，绝不能作为 LLM 上下文来源。
     pub task_events: Vec<Event>,
+    /// 本会话之前任务的展示事件（有上限），与 `task_events` 一起供全屏转录视图查看。
+    pub transcript_ring: std::collections::VecDeque<Event>,
+    /// 全屏转录视图（F2）打开时的状态。
+    pub transcript: Option<crate::ui::transcript::TranscriptView>,
     pub final_summary: Option<String>,
     pub task_collapsed: bool,
     pub show_thinking: bool,
@@ -105,6 +168,8 @@ pub(crate) struct App {
     pub answering_question: bool,
     /// When Some, input is treated as an API key value for this env var name.
     pub pending_api_key_name: Option<String>,
+    /// `<ask_secret>` 凭据输入流程（遮罩输入 → 选择仅本次会话 / 保存 → 重试失败命令）。
+    pub pending_ask_secret: Option<crate::ui::input::PendingSecret>,
     pub message_queue: InputQueue,
     pub mcp_registry: crate::tools::mcp::McpRegistry,
     pub mcp_discovery_rx:
@@ -118,11 +183,25 @@ pub(crate) struct App {
     pub workspace: std::path::PathBuf,
     pub backend: LlmBackend,
     pub ge_agent: Option<crate::consensus::subagent::GeSubagent>,
+    /// GE 紧凑视图（GE 模式下 Ctrl+E 切换）：每次 todo 状态变化只显示一行。
+    pub ge_compact: bool,
     pub todo_items: Vec<crate::types::TodoItem>,
     /// True when launched with -p: auto-quit after task finishes, print final_summary to stdout.
     pub headless: bool,
+    /// headless 运行中止的原因（如 LLM 调用失败），随 json 结果输出。
+    pub headless_error: Option<String>,
     /// True when memory injection is disabled (--no-memory flag or /nomemory command).
     pub no_memory: bool,
+    /// 观察模式（`GOLDBOT_OBSERVE=1` 或 /observe）：命令与写入类工具只模拟、不执行。
+    pub observe: bool,
+    /// 安全模式（`GOLDBOT_SAFE=1` 或 `--safe`）：启动时不加载 MCP 与技能，可用 /mcp reload、/skills reload 单独恢复。
+    pub safe_mode: bool,
+    /// 事件时间戳（`GOLDBOT_TIMESTAMPS=1` 或 /timestamps）：实时事件行前显示 `HH:MM:SS`。
+    pub timestamps: bool,
+    /// 原始响应显示（`GOLDBOT_SHOW_RAW=1` 或 /raw）：每轮 LLM 回复的原文以暗色块写入滚动区。
+    pub show_raw: bool,
+    /// 已禁用的内置工具（`GOLDBOT_DISABLE_TOOLS` 或 /tools disable）：不出现在系统提示词中，调用时直接拒绝。
+    pub disabled_tools: std::collections::BTreeSet<String>,
 
     /// 覆盖 UserTask 事件的 TUI 显示文本（命令展开时显示占位符而非完整内容）。
     pub task_display_override: Option<String>,
@@ -131,8 +210,10 @@ pub(crate) struct App {
     pub at_file: AtFilePickerState,
     /// 全量文件路径索引（后台扫描一次，之后内存过滤）
     pub at_file_index: Vec<std::path::PathBuf>,
-    /// 后台扫描线程的结果接收端
+    /// 后台扫描线程的结果接收端（可能分多批到达，断开即扫描结束）
     pub at_file_index_rx: Option<std::sync::mpsc::Receiver<Vec<std::path::PathBuf>>>,
+    /// @ 附加的图片，随下一条 user 消息一起发送
+    pub pending_images: Vec<agent::provider::ImagePart>,
 
     // ── / command picker ───────────────────────────────────────────────────────
     /// 用户通过 COMMAND.md 自定义的命令列表（启动时加载一次）。
@@ -141,18 +222,47 @@ pub(crate) struct App {
 
     // ── /model picker ──────────────────────────────────────────────────────────
     pub model_picker: ModelPickerState,
+    /// 当前后端来自工作区 `.goldbot/backend`（而非全局 `~/.goldbot/.env`）
+    pub backend_from_workspace: bool,
+    /// Ctrl+B 在任务运行中选中的收藏后端 (backend, model)，下一次 LLM 调用前生效。
+    pub pending_backend_switch: Option<(String, String)>,
 
     // ── /Session picker ────────────────────────────────────────────────────────
     /// Session IDs shown in the /Session picker (None = picker not active).
     pub pending_session_list: Option<Vec<String>>,
 
     pub total_usage: crate::agent::provider::Usage,
+    /// 最近一次响应头里的限流额度
+    pub rate_limits: Option<crate::agent::provider::RateLimits>,
+    /// 额度偏低时主动放慢：在此时刻之前的下一次调用处于等待中
+    pub rate_limit_pace_until: Option<std::time::Instant>,
     pub prompt_token_scale: f32,
     pub recent_completion_tokens_ema: u32,
     /// HTTP client shared with SubAgent DAG executor
     pub http_client: Option<reqwest::Client>,
     /// 用户通过 /compact 命令请求手动压缩
     pub pending_manual_compact: bool,
+    /// compact 预览确认菜单（Some 表示菜单正在显示）。
+    pub pending_compact_confirm: Option<crate::types::CompactConfirm>,
+    /// 用户拒绝了一次自动 compact 确认，下一次调用前跳过自动压缩。
+    pub skip_next_auto_compact: bool,
+    /// 超过 `GOLDBOT_PROMPT_WARN_TOKENS` 的调用等待确认（Some 为估算的 prompt token 数）。
+    pub pending_prompt_warn: Option<u32>,
+    /// 本任务已确认发送过大 prompt，后续调用不再询问。
+    pub prompt_warn_accepted: bool,
+    /// 疑似含密钥、等待确认的用户输入（Some 表示确认菜单正在显示）。
+    pub pending_secret_input: Option<String>,
+    /// 等待确认的未信任工作区初始化脚本（`.goldbot/init.sh`）。
+    pub pending_init_script: Option<std::path::PathBuf>,
+    /// 正在后台运行的 `.goldbot/init.sh`：(结果, 显示用路径)
+    pub init_script_rx: Option<(
+        tokio::sync::oneshot::Receiver<anyhow::Result<tools::init_env::InitOutcome>>,
+        String,
+    )>,
+    /// init.sh 运行期间提交的任务，脚本报告结果后再开始（多次提交合并为一条）
+    pub task_after_init: Option<String>,
+    /// 快捷键映射；启动时从 `~/.goldbot/keybindings.toml` 加载。
+    pub keymap: crate::ui::input::KeyMap,
     /// 当前任务内已加载的 skill 完整内容列表。
     /// 在 start_task 时清空，每次 load_skill 时追加，
     /// 派发 Sub-Agent 时注入到子代理初始上下文，确保 skill 上下文不丢失。
@@ -171,6 +281,8 @@ pub(crate) struct AtFileChunk {
     pub placeholder: String,
     /// Resolved path to the file (relative to workspace).
     pub path: std::path::PathBuf,
+    /// 图片附件：只传路径并标注为二进制，避免模型 `cat` 其内容。
+    pub is_image: bool,
 }
 
 #[derive(Debug, Default, PartialEq, Eq)]
@@ -193,6 +305,8 @@ pub(crate) struct ModelPickerState {
     pub sel: usize,
     /// 第一级选定的后端 label（进入第二级后使用）
     pub pending_backend: Option<String>,
+    /// `/model --global`：即使工作区有 `.goldbot/backend` 也写入全局 `.env`
+    pub save_global: bool,
 }
 
 #[derive(Debug, Default)]
@@ -205,6 +319,9 @@ pub(crate) struct CmdPickerState {
     pub sel: usize,
     /// 用户选中模板命令后暂存的 (占位符, 模板内容)，提交时把占位符替换成内容。
     pub pending_template: Option<(String, String)>,
+    /// 在已有草稿的空行上输入 `/` 打开选择器时，记录 `/` 的字节位置；
+    /// 执行内置命令时只移除 `/query` 这一段，草稿与附件保持不变。
+    pub draft_at: Option<usize>,
 }
 
 #[derive(Debug, Default)]
@@ -223,18 +340,10 @@ pub(crate) struct AtFilePickerState {
 
 impl App {
     fn new() -> Self {
-        let backend = LlmBackend::from_env();
-        let (mut mcp_registry, mcp_warnings) = crate::tools::mcp::McpRegistry::from_env();
-        mcp_registry.inject_builtin_for_backend(backend.backend_label());
-        for warning in mcp_warnings {
-            eprintln!("[mcp] {warning}");
-        }
-        let skills = discover_skills();
-        // base_prompt = SYSTEM_PROMPT + skills section.
-        // MCP tools are appended later after background discovery.
-        let skills_section = skills_system_prompt(&skills);
-        let base_prompt = format!("{}{skills_section}", build_system_prompt());
+        Self::new_with_safe_mode(agent::executor::safe_mode_from_env())
+    }
 
+    fn new_with_safe_mode(safe_mode: bool) -> Self {
         // Determine workspace: GOLDBOT_WORKSPACE env var, or current directory.
         let workspace = std::env::var("GOLDBOT_WORKSPACE")
             .ok()
@@ -245,6 +354,37 @@ impl App {
             })
             .or_else(|| std::env::current_dir().ok())
             .unwrap_or_else(|| std::path::PathBuf::from("."));
+        // 工作区 .goldbot/backend 覆盖全局 .env 的后端选择
+        let backend_overrides = crate::memory::profile::workspace_backend(&workspace);
+        let backend = LlmBackend::with_overrides(&backend_overrides);
+        // 安全模式跳过 MCP 配置与技能发现，系统提示词只剩基础规则与记忆等内置片段。
+        let mcp_registry = if safe_mode {
+            crate::tools::mcp::McpRegistry::default()
+        } else {
+            let (mut registry, warnings) = crate::tools::mcp::McpRegistry::from_env();
+            registry.inject_builtin_for_backend(backend.backend_label());
+            for warning in warnings {
+                eprintln!("[mcp] {warning}");
+            }
+            registry
+        };
+        let skills = if safe_mode {
+            Vec::new()
+        } else {
+            discover_skills(&workspace)
+        };
+        // base_prompt = SYSTEM_PROMPT + skills section.
+        // MCP tools are appended later after background discovery.
+        let skills_section = skills_system_prompt(&skills);
+        let (disabled_tools, unknown_tools) = crate::agent::executor::disabled_tools_from_env();
+        for name in unknown_tools {
+            eprintln!("[tools] unknown tool in GOLDBOT_DISABLE_TOOLS: {name}");
+        }
+        let base_prompt = format!(
+            "{}{skills_section}",
+            strip_disabled_tools(&build_system_prompt(), &disabled_tools)
+        );
+
         // chdir into workspace so all shell commands run relative to it.
         let _ = std::env::set_current_dir(&workspace);
 
@@ -262,32 +402,61 @@ impl App {
 
             task: String::new(),
             steps_taken: 0,
+            parse_failures: 0,
             llm_calling: false,
             llm_call_started_at: None,
             task_started_at: None,
             last_task_elapsed: None,
+            task_finished_clock: None,
             llm_stream_preview: String::new(),
             llm_preview_shown: String::new(),
             needs_agent_executor: false,
+            paused: false,
+            git_branch_checked_at: None,
             shell_task_running: false,
             shell_exec_rx: None,
             dag_task_running: false,
             dag_result_rx: None,
             dag_progress_rx: None,
             dag_cancel_flag: Arc::new(AtomicBool::new(false)),
+            external_task_running: false,
+            external_result_rx: None,
+            pending_delegation: None,
+            explain_rx: None,
+            explain_command: String::new(),
+            external_cancel_flag: Arc::new(AtomicBool::new(false)),
             dag_tree_event_idx: None,
             dag_node_done: std::collections::HashMap::new(),
             dag_graph_nodes: Vec::new(),
             dag_output_nodes: Vec::new(),
             interrupt_llm_loop_requested: false,
+            graceful_quit_at: None,
             interjection_mode: false,
+            interjection_include_output: false,
             running: false,
             quit: false,
             pending_confirm: None,
 
             pending_confirm_note: false,
+            pending_mcp_create: None,
+            pending_mcp_call: None,
+            pending_mcp_direct_call: None,
+            pending_edit: None,
+            confirm_edits: agent::executor::confirm_edits_from_env(),
+            auto_confirm_below: agent::executor::auto_confirm_below_from_env(),
+            task_snapshot: None,
+            pending_mcp_rollback: None,
+            mcp_verify_rx: None,
+            mcp_verify_target: None,
+            memory_promote_rx: None,
+            pending_confirm_started_at: None,
+            confirm_timeout: agent::executor::confirm_timeout_from_env(),
+            task_time_budget: agent::executor::task_time_budget_from_env(),
+            task_time_warned: false,
             current_phase_summary: None,
             task_events: Vec::new(),
+            transcript_ring: std::collections::VecDeque::new(),
+            transcript: None,
             final_summary: None,
             task_collapsed: false,
             show_thinking: true,
@@ -296,6 +465,7 @@ impl App {
             pending_question: None,
             answering_question: false,
             pending_api_key_name: None,
+            pending_ask_secret: None,
             message_queue: InputQueue::default(),
             mcp_registry,
             mcp_discovery_rx: None,
@@ -305,35 +475,84 @@ impl App {
             assist_mode,
             workspace,
             ge_agent: None,
+            ge_compact: false,
             todo_items: Vec::new(),
             backend,
             headless: false,
+            headless_error: None,
             no_memory: false,
+            observe: crate::agent::executor::observe_mode_from_env(),
+            safe_mode,
+            timestamps: crate::ui::format::timestamps_from_env(),
+            show_raw: crate::ui::format::show_raw_from_env(),
+            disabled_tools,
             task_display_override: None,
             at_file: AtFilePickerState::default(),
             at_file_index: Vec::new(),
             at_file_index_rx: None,
+            pending_images: Vec::new(),
             user_commands: Vec::new(),
             cmd_picker: CmdPickerState::default(),
             model_picker: ModelPickerState::default(),
+            backend_from_workspace: !backend_overrides.is_empty(),
+            pending_backend_switch: None,
             pending_session_list: None,
             total_usage: Default::default(),
+            rate_limits: None,
+            rate_limit_pace_until: None,
             prompt_token_scale: 1.0,
             recent_completion_tokens_ema: 0,
             http_client: None,
             pending_manual_compact: false,
+            pending_compact_confirm: None,
+            skip_next_auto_compact: false,
+            pending_prompt_warn: None,
+            prompt_warn_accepted: false,
+            pending_secret_input: None,
+            pending_init_script: None,
+            init_script_rx: None,
+            task_after_init: None,
+            keymap: crate::ui::input::KeyMap::default(),
             active_skill_contents: Vec::new(),
         }
     }
-    /// Rebuild messages[0] (system prompt) with the latest base_prompt + MCP tools + workspace context.
+    /// Rebuild messages[0] (system prompt) from its named sections (base, skills, MCP tools, workspace context).
     pub(crate) fn rebuild_system_message(&mut self) {
-        let system = self.mcp_registry.augment_system_prompt(&self.base_prompt);
-        let context = build_workspace_context(&self.workspace, self.assist_mode);
+        let content = SystemPromptBuilder::for_app(self).build();
         if let Some(msg) = self.messages.first_mut() {
-            msg.content = format!("{system}\n\n{context}");
+            msg.content = content;
         }
     }
 
+    /// 后台发现 MCP 工具；结果经 `mcp_discovery_rx` 在 run_loop 中应用。
+    pub(crate) fn start_mcp_discovery(&mut self) {
+        if !self.mcp_registry.has_servers() {
+            return;
+        }
+        let registry = self.mcp_registry.clone();
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(registry.run_discovery());
+        });
+        self.mcp_discovery_rx = Some(rx);
+    }
+
+    /// /mcp reload：重新读取 MCP 配置并启动发现，返回配置告警。
+    pub(crate) fn reload_mcp(&mut self) -> Vec<String> {
+        let (mut registry, warnings) = crate::tools::mcp::McpRegistry::from_env();
+        registry.inject_builtin_for_backend(self.backend.backend_label());
+        self.mcp_registry = registry;
+        self.rebuild_system_message();
+        self.start_mcp_discovery();
+        warnings
+    }
+
+    /// /skills reload：重新发现技能并更新系统提示词。
+    pub(crate) fn reload_skills(&mut self) {
+        self.skills = discover_skills(&self.workspace);
+        self.rebuild_system_message();
+    }
+
     pub(crate) fn sync_message_queue_labels(&self, screen: &mut Screen) {
         screen.message_queue_labels = self.message_queue.labels();
     }
@@ -374,13 +593,49 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
-    let (cli_prompt, cli_yes, cli_no_memory) = cli::parse_cli_args();
-    let headless = cli_prompt.is_some();
+    let cli = match cli::parse_cli_args() {
+        Ok(cli::CliCommand::Run(cli)) => cli,
+        Ok(cli::CliCommand::Version) => {
+            println!("goldbot {}", env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+        Ok(cli::CliCommand::Help) => {
+            print!("{}", cli::usage());
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{}", cli::usage());
+            std::process::exit(2);
+        }
+    };
 
     // 先确保 ~/.goldbot/.env 存在，再在启动早期加载，
     // 避免 App::new() 和 HTTP client 初始化读到的是未加载配置的默认环境。
-    cli::ensure_dot_env();
+    let dot_env_error = cli::ensure_dot_env().err();
     let _ = dotenvy::from_path(crate::tools::mcp::goldbot_home_dir().join(".env"));
+    // 命令行参数优先于环境变量
+    if let Some(workspace) = &cli.workspace {
+        if !workspace.is_dir() {
+            anyhow::bail!("--workspace {} is not a directory", workspace.display());
+        }
+        unsafe {
+            std::env::set_var("GOLDBOT_WORKSPACE", workspace);
+        }
+    }
+    if cli.safe {
+        unsafe {
+            std::env::set_var("GOLDBOT_SAFE", "1");
+        }
+    }
+    let cli_prompt = cli.prompt.clone().or_else(|| {
+        cli.headless
+            .then(|| std::env::var("GOLDBOT_TASK").ok())
+            .flatten()
+    });
+    if cli.headless && cli_prompt.is_none() {
+        anyhow::bail!("--headless needs a task (--task <TEXT> or GOLDBOT_TASK)");
+    }
+    let headless = cli_prompt.is_some();
     let http_client = build_http_client()?;
     let mut app = App::new();
     app.http_client = Some(http_client.clone());
@@ -392,11 +647,29 @@ async fn main() -> anyhow::Result<()> {
     let mut screen = if headless {
         Screen::new_headless()?
     } else {
-        Screen::new()?
+        Screen::new(&app.backend)?
     };
     screen.workspace = app.workspace.to_string_lossy().replace('\\', "/");
     screen.assist_mode = app.assist_mode;
+    screen.observe = app.observe;
+    screen.timestamps = app.timestamps;
     sync_context_budget(&app, &mut screen);
+    if app.observe {
+        screen.emit(&[format!(
+            "  👁 {}",
+            crossterm::style::Stylize::yellow(
+                "Observe mode: commands and file writes are simulated, nothing executes"
+            )
+        )]);
+    }
+    if app.safe_mode {
+        screen.emit(&[format!(
+            "  🛟 {}",
+            crossterm::style::Stylize::yellow(
+                "SAFE MODE: MCP servers and skills are not loaded (/mcp reload, /skills reload)"
+            )
+        )]);
+    }
 
     // Display discovered skills below the banner.
     let skill_names: Vec<String> = app.skills.iter().map(|s| s.name.clone()).collect();
@@ -404,31 +677,52 @@ async fn main() -> anyhow::Result<()> {
         screen.emit(&[line]);
     }
 
+    if let Some(e) = &dot_env_error {
+        screen.emit(&[crate::ui::format::save_warning_line(e)]);
+    }
+
+    // 加载自定义快捷键；配置有误时提示并沿用默认键位。
+    let (keymap, keymap_warnings) = crate::ui::input::KeyMap::load();
+    app.keymap = keymap;
+    for warning in keymap_warnings {
+        screen.emit(&[format!("  ⚠ keybindings: {warning}")]);
+    }
+
+    // 工作区初始化脚本：导出的环境作用于之后的工具命令；未信任时先确认。
+    crate::ui::input::start_init_script(&mut app, &mut screen);
+
     // Discover user-defined slash commands.
-    app.user_commands = discover_commands();
+    app.user_commands = discover_commands(&app.workspace);
 
     // Start MCP discovery in background; results arrive via channel in run_loop.
-    if app.mcp_registry.has_servers() {
-        let registry = app.mcp_registry.clone();
-        let (tx, rx) = std::sync::mpsc::channel();
-        std::thread::spawn(move || {
-            let _ = tx.send(registry.run_discovery());
-        });
-        app.mcp_discovery_rx = Some(rx);
-    }
+    app.start_mcp_discovery();
 
-    app.no_memory = cli_no_memory;
-    let run_result = run_loop(&mut app, &mut screen, http_client, cli_prompt, cli_yes).await;
+    app.no_memory = cli.no_memory;
+    let run_result = run_loop(&mut app, &mut screen, http_client, cli_prompt, cli.yes).await;
 
     if !headless {
+        // 退出时仍在备用屏幕（如 F2 全屏视图）要先切回，否则终端停留在空白屏幕
+        screen.close_overlay();
         let _ = execute!(io::stdout(), DisableBracketedPaste);
         let _ = disable_raw_mode();
         let _ = execute!(io::stdout(), crossterm::cursor::Show, Print("\r\n"));
     }
     // headless 模式：直接把最终答案打印到 stdout
     if app.headless {
-        if let Some(summary) = &app.final_summary {
-            println!("{summary}");
+        let error = match &run_result {
+            Err(e) => Some(format!("{e:#}")),
+            Ok(()) => app.headless_error.clone(),
+        };
+        if let Some(error) = &error {
+            eprintln!("{error}");
+        }
+        if let Some(report) = cli::headless_report(
+            cli.format,
+            &app.workspace.to_string_lossy(),
+            app.final_summary.as_deref(),
+            error.as_deref(),
+        ) {
+            println!("{report}");
         }
     }
     run_result
@@ -441,14 +735,13 @@ async fn run_loop(
     initial_task: Option<String>,
     yolo_mode: bool,
 ) -> anyhow::Result<()> {
-    let startup_task = initial_task.or_else(|| std::env::var("GOLDBOT_TASK").ok());
     // -y / --yes 直接进入 Yolo 模式，自动执行非 Block 命令。
     if yolo_mode {
         app.assist_mode = AssistMode::Yolo;
         app.rebuild_system_message();
         screen.assist_mode = app.assist_mode;
     }
-    if let Some(task) = startup_task {
+    if let Some(task) = initial_task {
         app.headless = true;
         start_task(app, screen, task);
     }
@@ -481,16 +774,26 @@ async fn run_loop(
             app.mcp_discovery_rx = None;
         }
 
-        // 轮询 @ 文件索引后台扫描结果
+        // 轮询 @ 文件索引后台扫描结果（并行扫描时分批到达）
         if let Some(rx) = &app.at_file_index_rx {
-            if let Ok(index) = rx.try_recv() {
-                app.at_file_index = index;
-                app.at_file_index_rx = None;
-                // @ picker 仍然活跃时，用新索引刷新候选
-                if app.at_file.query.is_some() {
-                    ui::input::apply_at_file_filter(app, screen);
+            let mut received = false;
+            loop {
+                match rx.try_recv() {
+                    Ok(batch) => {
+                        app.at_file_index.extend(batch);
+                        received = true;
+                    }
+                    Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                        app.at_file_index_rx = None;
+                        break;
+                    }
                 }
             }
+            // @ picker 仍然活跃时，用新索引刷新候选
+            if received && app.at_file.query.is_some() {
+                ui::input::apply_at_file_filter(app, screen);
+            }
         }
 
         if app.interrupt_llm_loop_requested {
@@ -510,6 +813,7 @@ async fn run_loop(
                     app.llm_call_started_at = None;
                     app.llm_stream_preview.clear();
                     app.llm_preview_shown.clear();
+                    screen.final_preview.clear();
                     screen.status.clear();
                     process_llm_result(app, screen, result);
                 }
@@ -517,7 +821,15 @@ async fn run_loop(
         }
 
         poll_shell_exec_result(app, screen);
+        sync_git_branch(app, screen, false);
+        tick_confirm_timeout(app, screen);
+        tick_task_time_budget(app, screen);
         poll_dag_result(app, screen);
+        agent::executor::poll_mcp_verify(app, screen);
+        agent::executor::poll_memory_promotion(app, screen);
+        ui::input::poll_init_script(app, screen);
+        agent::delegate::poll_external_task(app, screen);
+        agent::explain::poll_explain(app, screen);
 
         drain_ge_events(app, screen);
 
@@ -527,25 +839,7 @@ async fn run_loop(
         }
 
         // Consume queued user messages as interjections before the next LLM call
-        if app.running
-            && app.pending_confirm.is_none()
-            && !app.llm_calling
-            && !app.shell_task_running
-            && !app.message_queue.is_empty()
-            && !app.needs_agent_executor
-        {
-            if let Some(msg) = app.dequeue_message(screen) {
-                let wrapped = agent::react::build_interjection_user_message(&msg);
-                app.messages.push(agent::provider::Message::user(wrapped));
-                let ev = Event::UserTask { text: msg };
-                ui::format::emit_live_event(screen, &ev);
-                app.task_events.push(ev);
-            }
-            agent::executor::sync_context_budget(app, screen);
-            app.needs_agent_executor = true;
-            screen.status = "Interjection sent. Continuing...".to_string();
-            screen.refresh();
-        }
+        agent::executor::consume_queued_message(app, screen);
 
         if let Some(handle) =
             maybe_spawn_llm_worker(app, screen, &tx, &http_client).await
@@ -567,6 +861,15 @@ async fn run_loop(
 
         handle_terminal_events(app, screen).await?;
 
+        // Ctrl+C 优雅退出：等正在执行的命令收尾后保存，再退出
+        if !app.quit
+            && let Some(since) = app.graceful_quit_at
+            && graceful_quit_ready(app, since, std::time::Instant::now())
+        {
+            save_interrupted_task(app, screen);
+            app.quit = true;
+        }
+
         if app.quit {
             shutdown_background_work(app, screen, &mut llm_task_handle).await;
             // 退出时删除 debug 日志（仅在开启了 GOLDBOT_DEBUG_LOG 时才有该文件）。
@@ -587,20 +890,26 @@ async fn run_loop(
 #[cfg(test)]
 mod tests {
     use crate::agent::executor::{
-        parse_retryable_http_status, retry_delay_for_attempt,
+        retry_delay_for_attempt, retry_delay_for_error,
         should_retry_llm_error, should_run_pending_manual_compact,
     };
+    use crate::agent::provider::ProviderError;
     use crate::App;
+    use reqwest::StatusCode;
     use std::time::Duration;
 
+    fn http_error(status: StatusCode) -> ProviderError {
+        ProviderError::from_status(status, None, "boom")
+    }
+
     #[test]
     fn retries_server_errors_before_streaming() {
         assert!(should_retry_llm_error(
-            "API error 500 Internal Server Error: boom",
+            &http_error(StatusCode::INTERNAL_SERVER_ERROR),
             false,
         ));
         assert!(should_retry_llm_error(
-            "API error 503 Service Unavailable: boom",
+            &http_error(StatusCode::SERVICE_UNAVAILABLE),
             false,
         ));
     }
@@ -608,32 +917,38 @@ mod tests {
     #[test]
     fn does_not_retry_client_errors() {
         assert!(!should_retry_llm_error(
-            "API error 400 Bad Request: boom",
+            &http_error(StatusCode::BAD_REQUEST),
             false,
         ));
         assert!(!should_retry_llm_error(
-            "API error 429 Too Many Requests: boom",
+            &http_error(StatusCode::UNAUTHORIZED),
             false,
         ));
     }
 
     #[test]
-    fn does_not_retry_after_streaming_started() {
-        assert!(!should_retry_llm_error("failed reading stream chunk", true,));
-    }
-
-    #[test]
-    fn parses_http_status_from_provider_errors() {
-        assert_eq!(
-            parse_retryable_http_status("API error 502 Bad Gateway: upstream down"),
-            Some(502)
+    fn retries_rate_limits_honoring_retry_after() {
+        let err = ProviderError::from_status(
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(3)),
+            "slow down",
         );
+        assert!(should_retry_llm_error(&err, false));
+        assert_eq!(retry_delay_for_error(&err, 1), Duration::from_secs(3));
         assert_eq!(
-            parse_retryable_http_status("HTTP request failed: timeout"),
-            None
+            retry_delay_for_error(&http_error(StatusCode::BAD_GATEWAY), 2),
+            Duration::from_secs(1)
         );
     }
 
+    #[test]
+    fn does_not_retry_after_streaming_started() {
+        assert!(!should_retry_llm_error(
+            &ProviderError::Network("failed reading stream chunk".to_string()),
+            true,
+        ));
+    }
+
     #[test]
     fn backs_off_between_retries() {
         assert_eq!(retry_delay_for_attempt(1), Duration::from_millis(500));
@@ -1,3 +1,4 @@
+pub mod profile;
 pub mod project;
 pub mod session;
 pub mod store;
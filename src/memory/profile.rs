@@ -0,0 +1,526 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+use crate::config::{display_config_path, upsert_env_file, write_config_file};
+
+/// Name of the per-workspace profile directory (created by the user inside the project).
+const WORKSPACE_PROFILE_DIR: &str = ".goldbot";
+/// Subdir of the workspace profile that opts memory and sessions into the workspace.
+/// `.goldbot/` alone is not enough: other features create it for their own files.
+const WORKSPACE_MEMORY_DIR: &str = "memory";
+/// One skill name per line; `#` starts a comment. In a workspace profile,
+/// `+name` re-enables a skill disabled globally.
+const DISABLED_SKILLS_FILE: &str = "disabled_skills.txt";
+/// `KEY=VALUE` lines injected into every tool command's environment; `#` starts a
+/// comment. The workspace file overrides keys from the global one.
+const EXEC_ENV_FILE: &str = "exec_env";
+/// `LLM_PROVIDER=` plus the provider's `*_MODEL=` line. Overrides the backend
+/// selected in `~/.goldbot/.env` for this workspace.
+const BACKEND_FILE: &str = "backend";
+/// Executable in the workspace profile, sourced once at startup so the variables it
+/// exports (venv, `nvm use`, …) reach every tool command.
+const INIT_SCRIPT_FILE: &str = "init.sh";
+/// `<script hash> <workspace>` lines in the global data dir: init scripts the user agreed
+/// to run. Editing a script changes its hash, so it has to be confirmed again.
+const TRUSTED_INIT_FILE: &str = "trusted_init";
+/// Env names containing any of these are always redacted when displayed.
+const SECRET_ENV_NAME_HINTS: &[&str] = &["KEY", "TOKEN", "SECRET", "PASSWORD", "PASSWD"];
+
+/// Credentials provided through `<ask_secret>` for this process only; never written to disk.
+static SESSION_EXEC_ENV: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+/// Variables the workspace `init.sh` exported at startup; the lowest `exec_env` layer.
+static INIT_EXEC_ENV: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+/// Variables the workspace `init.sh` unset at startup; see [`exec_env_removed`].
+static INIT_UNSET_ENV: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+// ── Profile resolution ────────────────────────────────────────────────────────
+
+/// Returns the workspace profile directory when one exists.
+///
+/// Looks for `.goldbot/` in the workspace itself, then at its git root. The
+/// global data dir (`~/.goldbot`) never counts as a workspace profile, so
+/// running from `$HOME` still uses the global profile.
+pub(crate) fn workspace_profile_dir(workspace: &Path) -> Option<PathBuf> {
+    let global = crate::memory::store::default_memory_base_dir();
+    let mut candidates = vec![workspace.to_path_buf()];
+    if let Some(root) = git_root(workspace).filter(|root| root != workspace) {
+        candidates.push(root);
+    }
+    candidates
+        .into_iter()
+        .map(|dir| dir.join(WORKSPACE_PROFILE_DIR))
+        .find(|dir| dir.is_dir() && *dir != global)
+}
+
+/// Memory/session base for a workspace profile (`<profile>/memory`), only when the user
+/// created that directory.
+pub(crate) fn workspace_memory_base(workspace: &Path) -> Option<PathBuf> {
+    workspace_profile_dir(workspace)
+        .map(|dir| dir.join(WORKSPACE_MEMORY_DIR))
+        .filter(|dir| dir.is_dir())
+}
+
+fn git_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+// ── Disabled skills ───────────────────────────────────────────────────────────
+
+/// Disabled skill names: the global list with workspace overrides layered on top.
+pub(crate) fn disabled_skills(workspace: &Path) -> BTreeSet<String> {
+    let global = crate::memory::store::default_memory_base_dir().join(DISABLED_SKILLS_FILE);
+    let local = workspace_profile_dir(workspace).map(|dir| dir.join(DISABLED_SKILLS_FILE));
+    let read = |path: &Path| fs::read_to_string(path).unwrap_or_default();
+    layer_disabled_skills(&read(&global), local.as_deref().map(read).as_deref())
+}
+
+fn layer_disabled_skills(global: &str, workspace: Option<&str>) -> BTreeSet<String> {
+    let mut disabled = BTreeSet::new();
+    for raw in std::iter::once(global).chain(workspace) {
+        for line in raw.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if let Some(name) = line.strip_prefix('+') {
+                disabled.remove(name.trim());
+            } else if !line.is_empty() {
+                disabled.insert(line.to_string());
+            }
+        }
+    }
+    disabled
+}
+
+// ── Exec env ──────────────────────────────────────────────────────────────────
+
+/// Extra env vars for tool commands: whatever the workspace `init.sh` exported, the global
+/// `exec_env` with the workspace one layered on top, then any session-only credentials.
+pub(crate) fn exec_env(workspace: &Path) -> BTreeMap<String, String> {
+    let global = crate::memory::store::default_memory_base_dir().join(EXEC_ENV_FILE);
+    let local = workspace_profile_dir(workspace).map(|dir| dir.join(EXEC_ENV_FILE));
+    let mut env = INIT_EXEC_ENV
+        .lock()
+        .map(|init| init.clone())
+        .unwrap_or_default();
+    for path in std::iter::once(global).chain(local) {
+        let raw = fs::read_to_string(path).unwrap_or_default();
+        env.extend(parse_exec_env(&raw));
+    }
+    if let Ok(session) = SESSION_EXEC_ENV.lock() {
+        env.extend(session.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    env
+}
+
+/// Variables tool commands run without: the ones `init.sh` unset, unless a later layer of
+/// [`exec_env`] sets them again.
+pub(crate) fn exec_env_removed(workspace: &Path) -> Vec<String> {
+    let Ok(removed) = INIT_UNSET_ENV.lock().map(|removed| removed.clone()) else {
+        return Vec::new();
+    };
+    if removed.is_empty() {
+        return Vec::new();
+    }
+    let env = exec_env(workspace);
+    removed
+        .into_iter()
+        .filter(|key| !env.contains_key(key))
+        .collect()
+}
+
+/// The `exec_env` file a saved credential goes to: the workspace profile's when one
+/// exists, otherwise the global one.
+pub(crate) fn exec_env_file(workspace: &Path) -> PathBuf {
+    workspace_profile_dir(workspace)
+        .unwrap_or_else(crate::memory::store::default_memory_base_dir)
+        .join(EXEC_ENV_FILE)
+}
+
+/// Where a credential from `<ask_secret>` is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SecretScope {
+    /// In memory until GoldBot exits.
+    Session,
+    /// Written to [`exec_env_file`].
+    Saved,
+}
+
+/// Makes `name` available to tool commands. The value always takes effect for this
+/// session, so a failed save still lets the retry succeed; the error is returned for display.
+pub(crate) fn provide_secret(
+    workspace: &Path,
+    name: &str,
+    value: &str,
+    scope: SecretScope,
+) -> Result<()> {
+    if let Ok(mut session) = SESSION_EXEC_ENV.lock() {
+        session.insert(name.to_string(), value.to_string());
+    }
+    match scope {
+        SecretScope::Session => Ok(()),
+        SecretScope::Saved => upsert_env_file(&exec_env_file(workspace), &[(name, value)]),
+    }
+}
+
+fn parse_exec_env(raw: &str) -> Vec<(String, String)> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return None;
+            }
+            let value = value.trim();
+            let value = ['"', '\'']
+                .iter()
+                .find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q))
+                .unwrap_or(value);
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// `KEY=VALUE` for display, with secret-looking values redacted.
+pub(crate) fn describe_exec_env(env: &BTreeMap<String, String>) -> Vec<String> {
+    env.iter()
+        .map(|(key, value)| {
+            let upper = key.to_ascii_uppercase();
+            let value = if SECRET_ENV_NAME_HINTS.iter().any(|h| upper.contains(h)) {
+                "[REDACTED]".to_string()
+            } else {
+                crate::tools::secrets::redact_secrets(value)
+            };
+            format!("{key}={value}")
+        })
+        .collect()
+}
+
+// ── Init script ───────────────────────────────────────────────────────────────
+
+/// The workspace `init.sh`, when the workspace profile has one and it is executable.
+pub(crate) fn init_script(workspace: &Path) -> Option<PathBuf> {
+    let path = workspace_profile_dir(workspace)?.join(INIT_SCRIPT_FILE);
+    is_executable(&path).then_some(path)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+/// Init scripts are sourced by bash, so they only run on Unix.
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    false
+}
+
+/// Whether the user already agreed to run this exact script for this workspace.
+pub(crate) fn init_script_trusted(workspace: &Path, script: &Path) -> bool {
+    let Some(entry) = trusted_init_entry(workspace, script) else {
+        return false;
+    };
+    let path = crate::memory::store::default_memory_base_dir().join(TRUSTED_INIT_FILE);
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .any(|line| line == entry)
+}
+
+/// Remembers the script's current content as trusted, replacing any older entry for
+/// the same workspace.
+pub(crate) fn trust_init_script(workspace: &Path, script: &Path) -> Result<()> {
+    let entry = trusted_init_entry(workspace, script)
+        .with_context(|| format!("couldn't read {}", display_config_path(script)))?;
+    let path = crate::memory::store::default_memory_base_dir().join(TRUSTED_INIT_FILE);
+    let suffix = format!(" {}", workspace.display());
+    let raw = fs::read_to_string(&path).unwrap_or_default();
+    let mut lines: Vec<&str> = raw
+        .lines()
+        .filter(|line| !line.ends_with(&suffix))
+        .collect();
+    lines.push(&entry);
+    write_config_file(&path, &(lines.join("\n") + "\n"))
+}
+
+fn trusted_init_entry(workspace: &Path, script: &Path) -> Option<String> {
+    let content = fs::read(script).ok()?;
+    let digest = Sha256::digest(&content);
+    Some(format!("{digest:x} {}", workspace.display()))
+}
+
+/// Makes the variables captured from the init script part of [`exec_env`] for the rest of
+/// the session; `None` values are the ones it unset.
+pub(crate) fn apply_init_env(env: BTreeMap<String, Option<String>>) {
+    let mut set = BTreeMap::new();
+    let mut unset = BTreeSet::new();
+    for (key, value) in env {
+        match value {
+            Some(value) => {
+                set.insert(key, value);
+            }
+            None => {
+                unset.insert(key);
+            }
+        }
+    }
+    if let Ok(mut init) = INIT_EXEC_ENV.lock() {
+        *init = set;
+    }
+    if let Ok(mut removed) = INIT_UNSET_ENV.lock() {
+        *removed = unset;
+    }
+}
+
+// ── Workspace backend ─────────────────────────────────────────────────────────
+
+/// The workspace `backend` file, when the workspace profile has one.
+pub(crate) fn workspace_backend_file(workspace: &Path) -> Option<PathBuf> {
+    workspace_profile_dir(workspace)
+        .map(|dir| dir.join(BACKEND_FILE))
+        .filter(|path| path.is_file())
+}
+
+/// Backend selection from the workspace `backend` file. Only `LLM_PROVIDER` and
+/// `*_MODEL` entries count; API keys and base URLs stay in the global `.env`.
+pub(crate) fn workspace_backend(workspace: &Path) -> Vec<(String, String)> {
+    workspace_backend_file(workspace)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|raw| {
+            parse_exec_env(&raw)
+                .into_iter()
+                .filter(|(key, _)| key == "LLM_PROVIDER" || key.ends_with("_MODEL"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// ── /profile ──────────────────────────────────────────────────────────────────
+
+/// Human-readable summary of the active profile, shown by `/profile`.
+pub(crate) fn describe_profile(workspace: &Path) -> Vec<String> {
+    let global = crate::memory::store::default_memory_base_dir();
+    let memory_base = crate::memory::project::project_base_for(workspace);
+    let mut lines = match workspace_profile_dir(workspace) {
+        Some(dir) => vec![
+            "  当前 profile：workspace".to_string(),
+            format!("  profile 目录：{}", dir.display()),
+            format!("  全局基础配置：{}", global.display()),
+        ],
+        None => vec![
+            "  当前 profile：global".to_string(),
+            format!("  profile 目录：{}", global.display()),
+            format!("  （在项目中创建 {WORKSPACE_PROFILE_DIR}/ 目录即可启用 workspace profile）"),
+        ],
+    };
+    lines.push(format!("  记忆与会话：{}", memory_base.display()));
+    if workspace_memory_base(workspace).is_none() {
+        lines.push(format!(
+            "  （创建 {WORKSPACE_PROFILE_DIR}/{WORKSPACE_MEMORY_DIR}/ 目录即可把记忆与会话存到工作区）"
+        ));
+    }
+    if let Some(script) = init_script(workspace) {
+        let trusted = if init_script_trusted(workspace, &script) {
+            "已信任"
+        } else {
+            "未信任"
+        };
+        lines.push(format!("  初始化脚本：{}（{trusted}）", script.display()));
+    }
+    let disabled = disabled_skills(workspace);
+    if !disabled.is_empty() {
+        let names: Vec<&str> = disabled.iter().map(String::as_str).collect();
+        lines.push(format!("  已禁用 Skill：{}", names.join(", ")));
+    }
+    lines
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::project::ProjectStore;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_workspace(tag: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-profile-test-{tag}-{nanos}"));
+        fs::create_dir_all(ws.join(WORKSPACE_PROFILE_DIR)).unwrap();
+        ws
+    }
+
+    #[test]
+    fn workspace_profiles_isolate_memory() {
+        let ws_a = temp_workspace("a");
+        let ws_b = temp_workspace("b");
+        // `.goldbot/` alone (e.g. just an exec_env) keeps memory in the global dir
+        fs::write(
+            ws_a.join(WORKSPACE_PROFILE_DIR).join(EXEC_ENV_FILE),
+            "A=1\n",
+        )
+        .unwrap();
+        assert_eq!(workspace_memory_base(&ws_a), None);
+        fs::create_dir_all(ws_a.join(WORKSPACE_PROFILE_DIR).join(WORKSPACE_MEMORY_DIR)).unwrap();
+        assert_eq!(
+            workspace_memory_base(&ws_a),
+            Some(ws_a.join(".goldbot").join("memory"))
+        );
+
+        assert!(
+            ProjectStore::new(&ws_a)
+                .append_memory("use pnpm in this repo")
+                .unwrap()
+        );
+        let mem_a = ProjectStore::new(&ws_a).build_memory_message(None).unwrap();
+        assert!(mem_a.contains("use pnpm in this repo"));
+        assert!(
+            ProjectStore::new(&ws_b)
+                .build_memory_message(None)
+                .is_none()
+        );
+
+        let _ = fs::remove_dir_all(ws_a);
+        let _ = fs::remove_dir_all(ws_b);
+    }
+
+    #[test]
+    fn session_secret_stays_in_memory_and_saved_secret_goes_to_exec_env() {
+        let ws = temp_workspace("secret");
+        let file = ws.join(WORKSPACE_PROFILE_DIR).join(EXEC_ENV_FILE);
+        assert_eq!(exec_env_file(&ws), file);
+
+        let session_key = "GOLDBOT_TEST_SESSION_SECRET";
+        provide_secret(&ws, session_key, "ghp_session", SecretScope::Session).unwrap();
+        assert_eq!(exec_env(&ws)[session_key], "ghp_session");
+        assert!(!file.exists());
+
+        fs::write(&file, "# tools\nFOO=bar\n").unwrap();
+        let saved_key = "GOLDBOT_TEST_SAVED_SECRET";
+        provide_secret(&ws, saved_key, "ghp_saved", SecretScope::Saved).unwrap();
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "# tools\nFOO=bar\nGOLDBOT_TEST_SAVED_SECRET=ghp_saved\n"
+        );
+        let env = exec_env(&ws);
+        assert_eq!(env[saved_key], "ghp_saved");
+        assert_eq!(env["FOO"], "bar");
+        // The session-only value never reaches the file.
+        assert!(!fs::read_to_string(&file).unwrap().contains(session_key));
+
+        let _ = fs::remove_dir_all(ws);
+    }
+
+    #[test]
+    fn workspace_disabled_skills_layer_over_global() {
+        let disabled =
+            layer_disabled_skills("pdf\ndocx # 很少用\n", Some("+pdf\nfrontend-design\n"));
+        let names: Vec<&str> = disabled.iter().map(String::as_str).collect();
+        assert_eq!(names, ["docx", "frontend-design"]);
+    }
+
+    #[test]
+    fn workspace_backend_overrides_global_and_accepts_model_writes() {
+        let ws = temp_workspace("backend");
+        assert!(workspace_backend(&ws).is_empty());
+
+        let file = ws.join(WORKSPACE_PROFILE_DIR).join(BACKEND_FILE);
+        fs::write(
+            &file,
+            "# docs project\nLLM_PROVIDER=kimi\nKIMI_MODEL=kimi-k2.5\nKIMI_API_KEY=ignored\n",
+        )
+        .unwrap();
+        let local: BTreeMap<String, String> = workspace_backend(&ws).into_iter().collect();
+        assert_eq!(local.len(), 2);
+
+        // Global .env picks GLM; the workspace file wins key by key.
+        let global: BTreeMap<&str, &str> =
+            [("LLM_PROVIDER", "glm"), ("BIGMODEL_MODEL", "glm-5.1")].into();
+        let lookup = |key: &str| {
+            local
+                .get(key)
+                .cloned()
+                .or_else(|| global.get(key).map(|v| v.to_string()))
+        };
+        let backend = crate::agent::provider::LlmBackend::from_lookup(&lookup);
+        assert_eq!(backend.backend_label(), "Kimi");
+        assert_eq!(backend.model_name(), "kimi-k2.5");
+        // App 启动时的选择：覆盖只作用于返回的后端，不写进进程环境
+        let backend = crate::agent::provider::LlmBackend::with_overrides(&workspace_backend(&ws));
+        assert_eq!(backend.backend_label(), "Kimi");
+        assert_eq!(backend.model_name(), "kimi-k2.5");
+        assert_ne!(
+            std::env::var("KIMI_MODEL").ok().as_deref(),
+            Some("kimi-k2.5")
+        );
+
+        // /model writes into the workspace file, keeping comments and other lines.
+        upsert_env_file(
+            &file,
+            &[("LLM_PROVIDER", "glm"), ("BIGMODEL_MODEL", "glm-5")],
+        )
+        .unwrap();
+        let raw = fs::read_to_string(&file).unwrap();
+        assert!(
+            raw.starts_with("# docs project\nLLM_PROVIDER=glm\n"),
+            "{raw}"
+        );
+        assert!(raw.ends_with("BIGMODEL_MODEL=glm-5\n"), "{raw}");
+        let local: BTreeMap<String, String> = workspace_backend(&ws).into_iter().collect();
+        assert_eq!(local["LLM_PROVIDER"], "glm");
+        assert_eq!(local["BIGMODEL_MODEL"], "glm-5");
+        let _ = fs::remove_dir_all(&ws);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn init_script_is_trusted_by_content_and_layers_its_env() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let ws = temp_workspace("init");
+        let script = ws.join(WORKSPACE_PROFILE_DIR).join(INIT_SCRIPT_FILE);
+        fs::write(
+            &script,
+            "echo activating\nexport GOLDBOT_TEST_INIT_VAR=\"from init\"\nLOCAL_ONLY=1\n",
+        )
+        .unwrap();
+        // 不可执行的脚本不会运行
+        assert_eq!(init_script(&ws), None);
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(init_script(&ws), Some(script.clone()));
+        // 信任记录是脚本内容的 SHA-256
+        let entry = trusted_init_entry(&ws, &script).unwrap();
+        let (digest, rest) = entry.split_once(' ').unwrap();
+        assert_eq!(digest.len(), 64);
+        assert_eq!(rest, ws.display().to_string());
+
+        // init.sh 导出的变量是最低一层；它 unset 的变量从工具命令的环境里去掉
+        apply_init_env(BTreeMap::from([
+            (
+                "GOLDBOT_TEST_INIT_VAR".to_string(),
+                Some("from init".to_string()),
+            ),
+            ("GOLDBOT_TEST_INIT_UNSET".to_string(), None),
+        ]));
+        assert_eq!(exec_env(&ws)["GOLDBOT_TEST_INIT_VAR"], "from init");
+        assert_eq!(exec_env_removed(&ws), ["GOLDBOT_TEST_INIT_UNSET"]);
+        apply_init_env(BTreeMap::new());
+        assert!(exec_env_removed(&ws).is_empty());
+
+        let _ = fs::remove_dir_all(ws);
+    }
+}
@@ -3,11 +3,21 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 const MAX_MEMORY_NOTE_CHARS: usize = 120;
 const MEMORY_SECTION: &str = "## Memories";
+/// Marks notes added with `/remember`; these are always injected into the system prompt.
+const PINNED_PREFIX: &str = "[pinned] ";
 /// Maximum number of notes injected per LLM call.
 const MEMORY_TOP_N: usize = 5;
+/// Similar short-term tasks must recur this many times before promotion
+/// (`GOLDBOT_MEMORY_PROMOTE_AT`).
+const DEFAULT_PROMOTE_AT: usize = 3;
+/// Token-set Jaccard similarity, in percent, at which two tasks count as the
+/// same pattern (`GOLDBOT_MEMORY_PROMOTE_SIMILARITY`).
+const DEFAULT_PROMOTE_SIMILARITY: usize = 60;
+/// Tasks with fewer tokens than this ("继续", "ok") never form a pattern.
+const MIN_PROMOTE_TOKENS: usize = 3;
 
 // ── Process-level statics ─────────────────────────────────────────────────────
 
@@ -19,12 +29,18 @@ pub fn init_workspace(workspace: PathBuf) {
     let _ = CURRENT_WORKSPACE.set(workspace);
 }
 
+/// The workspace passed to `init_workspace`, if startup has run.
+pub(crate) fn current_workspace() -> Option<&'static Path> {
+    CURRENT_WORKSPACE.get().map(PathBuf::as_path)
+}
+
 // ── ProjectStore ──────────────────────────────────────────────────────────────
 
 /// Project-level long-term memory storage.
 ///
 /// Session logs live in `SessionStore`, but share the same project base path:
-/// `~/.goldbot/projects/<sanitized_workspace_path>`.
+/// `~/.goldbot/projects/<sanitized_workspace_path>`, or `<workspace>/.goldbot/memory`
+/// when the workspace has its own profile.
 pub struct ProjectStore {
     base: PathBuf,
 }
@@ -82,6 +98,101 @@ impl ProjectStore {
         Ok(true)
     }
 
+    /// `/remember`: append a user correction that applies to every task. Shares
+    /// `append_memory`'s dedup, so a note already in memory is not added twice.
+    pub fn pin_memory(&self, note: &str) -> Result<bool> {
+        let note = note.trim();
+        if note.is_empty() {
+            return Ok(false);
+        }
+        self.append_memory(&format!("{PINNED_PREFIX}{note}"))
+    }
+
+    /// Notes added with `/remember`, without the marker.
+    pub fn pinned_notes(&self) -> Vec<String> {
+        let Ok(content) = fs::read_to_string(self.memory_path()) else {
+            return Vec::new();
+        };
+        notes_from_file(&content)
+            .iter()
+            .filter_map(|n| n.strip_prefix(PINNED_PREFIX))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// `/forget`: remove every note containing `pattern` (case-insensitive).
+    /// Returns the removed notes.
+    pub fn forget_memory(&self, pattern: &str) -> Result<Vec<String>> {
+        let pattern = pattern.trim().to_lowercase();
+        let path = self.memory_path();
+        if pattern.is_empty() || !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&path)?;
+        let mut removed = Vec::new();
+        let mut kept = String::with_capacity(content.len());
+        for line in content.lines() {
+            match line.trim_start().strip_prefix("- ").map(str::trim) {
+                Some(note) if note.to_lowercase().contains(&pattern) => {
+                    removed.push(note.strip_prefix(PINNED_PREFIX).unwrap_or(note).to_string());
+                }
+                _ => {
+                    kept.push_str(line);
+                    kept.push('\n');
+                }
+            }
+        }
+        if !removed.is_empty() {
+            fs::write(&path, kept)?;
+        }
+        Ok(removed)
+    }
+
+    // ── Promotion ─────────────────────────────────────────────────────────────
+
+    /// Promote recurring short-term tasks to long-term notes, using the
+    /// thresholds from the environment. Returns the number of new notes.
+    pub fn promote_repeated_short_term_to_long_term(&self, tasks: &[String]) -> Result<usize> {
+        let (promote_at, similarity) = promote_settings();
+        self.promote_patterns(tasks, promote_at, similarity)
+    }
+
+    /// Group tasks whose token sets overlap by at least `similarity` percent and
+    /// record each group seen `promote_at` times or more. Already-known notes
+    /// are skipped by `append_memory`'s dedup, so repeated runs are idempotent.
+    fn promote_patterns(
+        &self,
+        tasks: &[String],
+        promote_at: usize,
+        similarity: usize,
+    ) -> Result<usize> {
+        let mut groups: Vec<(std::collections::HashSet<String>, &str, usize)> = Vec::new();
+        for task in tasks {
+            let task = task.trim();
+            let tokens = tokenize(task);
+            if tokens.len() < MIN_PROMOTE_TOKENS {
+                continue;
+            }
+            match groups
+                .iter_mut()
+                .find(|(rep, _, _)| jaccard_percent(rep, &tokens) >= similarity)
+            {
+                Some(group) => group.2 += 1,
+                None => groups.push((tokens, task, 1)),
+            }
+        }
+
+        let mut promoted = 0;
+        for (_, task, count) in groups {
+            if count >= promote_at
+                && self.append_memory(&format!("Recurring task ({count}x): {task}"))?
+            {
+                promoted += 1;
+            }
+        }
+        Ok(promoted)
+    }
+
     /// Build the memory block injected into the LLM context.
     ///
     /// When `query` is provided and the total note count exceeds `MEMORY_TOP_N`,
@@ -143,6 +254,10 @@ pub(crate) fn project_base_for(workspace: &Path) -> PathBuf {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
 
+    if let Some(base) = crate::memory::profile::workspace_memory_base(workspace) {
+        return base;
+    }
+
     let name = workspace
         .file_name()
         .map(|s| s.to_string_lossy().into_owned())
@@ -162,24 +277,24 @@ pub(crate) fn project_base_for(workspace: &Path) -> PathBuf {
 // ── File helpers ──────────────────────────────────────────────────────────────
 
 fn ensure_memory_file(path: &Path) -> Result<()> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
     if !path.exists() {
-        fs::write(path, format!("# Project Memory\n\n{MEMORY_SECTION}\n"))?;
+        crate::config::write_config_file(path, &format!("# Project Memory\n\n{MEMORY_SECTION}\n"))?;
     }
     Ok(())
 }
 fn append_file(path: PathBuf, content: &str) -> Result<()> {
     use std::io::Write;
+    let shown = crate::config::display_config_path(&path);
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+        fs::create_dir_all(parent).with_context(|| format!("couldn't save to {shown}"))?;
     }
     let mut file = fs::OpenOptions::new()
         .create(true)
         .append(true)
-        .open(path)?;
-    file.write_all(content.as_bytes())?;
+        .open(&path)
+        .with_context(|| format!("couldn't save to {shown}"))?;
+    file.write_all(content.as_bytes())
+        .with_context(|| format!("couldn't save to {shown}"))?;
     Ok(())
 }
 /// Extract bullet-list notes from the `## Memories` section.
@@ -236,7 +351,9 @@ fn normalize_note(text: &str) -> String {
 }
 
 fn canonicalize(text: &str) -> String {
-    text.trim()
+    let text = text.trim();
+    text.strip_prefix(PINNED_PREFIX)
+        .unwrap_or(text)
         .trim_end_matches(['。', '.', '!', '?', ';', '；'])
         .split_whitespace()
         .collect::<Vec<_>>()
@@ -275,6 +392,34 @@ fn tokenize(text: &str) -> std::collections::HashSet<String> {
     tokens
 }
 
+fn jaccard_percent(
+    a: &std::collections::HashSet<String>,
+    b: &std::collections::HashSet<String>,
+) -> usize {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0;
+    }
+    a.intersection(b).count() * 100 / union
+}
+
+fn promote_settings() -> (usize, usize) {
+    let read = |key: &str, default: usize| {
+        std::env::var(key)
+            .ok()
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .unwrap_or(default)
+    };
+    (
+        read("GOLDBOT_MEMORY_PROMOTE_AT", DEFAULT_PROMOTE_AT).max(2),
+        read(
+            "GOLDBOT_MEMORY_PROMOTE_SIMILARITY",
+            DEFAULT_PROMOTE_SIMILARITY,
+        )
+        .clamp(1, 100),
+    )
+}
+
 /// Count how many tokens from `query_tokens` appear in `note`.
 fn keyword_score(query_tokens: &std::collections::HashSet<String>, note: &str) -> usize {
     if query_tokens.is_empty() {
@@ -318,6 +463,36 @@ mod tests {
         (ProjectStore { base: base.clone() }, base)
     }
 
+    #[test]
+    fn recurring_task_is_promoted_at_threshold_only() {
+        let (store, base) = temp_store();
+        let tasks: Vec<String> = [
+            "run cargo clippy and fix warnings",
+            "run cargo clippy then fix the warnings",
+            "update README install section",
+            "run cargo clippy, fix warnings",
+            "继续",
+            "继续",
+            "继续",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        // 低于阈值：不提升
+        assert_eq!(store.promote_patterns(&tasks, 4, 50).unwrap(), 0);
+        assert!(store.build_memory_message(None).is_none());
+
+        // 达到阈值：提升一次；重复执行保持幂等
+        assert_eq!(store.promote_patterns(&tasks, 3, 50).unwrap(), 1);
+        assert_eq!(store.promote_patterns(&tasks, 3, 50).unwrap(), 0);
+        let mem = store.build_memory_message(None).unwrap();
+        assert!(mem.contains("Recurring task (3x): run cargo clippy and fix warnings"));
+        assert!(!mem.contains("继续"));
+        assert!(!mem.contains("README"));
+        let _ = fs::remove_dir_all(base);
+    }
+
     #[test]
     fn append_memory_deduplicates() {
         let (store, base) = temp_store();
@@ -366,4 +541,25 @@ mod tests {
         );
         let _ = fs::remove_dir_all(base);
     }
+
+    #[test]
+    fn pinned_notes_dedup_and_forget_by_substring() {
+        let (store, base) = temp_store();
+        store.append_memory("默认用中文回答").unwrap();
+        // 与已有记忆重复的不再写入
+        assert!(!store.pin_memory("默认用中文回答").unwrap());
+        assert!(store.pin_memory("Never run `cargo update`").unwrap());
+        assert!(!store.pin_memory("never run cargo update.").unwrap());
+        assert_eq!(store.pinned_notes(), ["Never run cargo update."]);
+
+        assert_eq!(
+            store.forget_memory("CARGO UPDATE").unwrap(),
+            ["Never run cargo update."]
+        );
+        assert!(store.pinned_notes().is_empty());
+        assert!(store.forget_memory("no such note").unwrap().is_empty());
+        let content = fs::read_to_string(base.join("MEMORY.md")).unwrap();
+        assert!(content.contains(MEMORY_SECTION) && content.contains("默认用中文回答"));
+        let _ = fs::remove_dir_all(base);
+    }
 }
@@ -5,7 +5,7 @@ use std::{
     sync::RwLock,
 };
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::{Duration, Local};
 use crossterm::style::Stylize;
 
@@ -24,6 +24,8 @@ use super::project::current_project_base;
 pub const MAX_SESSION_FINAL_CHARS: usize = 4000;
 /// Session files older than this are deleted at startup.
 pub const SESSION_RETENTION_DAYS: i64 = 15;
+/// Maximum characters of the one-line summary shown by `/history`.
+const SHORT_TERM_SUMMARY_CHARS: usize = 80;
 
 // ── Process-level session ID ──────────────────────────────────────────────────
 
@@ -51,6 +53,16 @@ fn switch_active_session(id: &str) {
 
 // ── Session ─────────────────────────────────────────────────────────────────
 
+/// One task record from the current session (short-term memory).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortTermEntry {
+    /// Time the task finished (`HH:MM:SS`).
+    pub time: String,
+    pub task: String,
+    /// First non-empty line of the final answer.
+    pub summary: String,
+}
+
 /// Manages session file storage for a project.
 ///
 /// Sessions are stored in `<base>/sessions/<id>.md`.
@@ -131,6 +143,27 @@ impl Session {
         append_file(path, &block)
     }
 
+    /// Return up to `limit` task records from the current session, newest first.
+    ///
+    /// Diff and compaction sections are skipped; read-only.
+    pub fn recent_short_term(&self, limit: usize) -> Vec<ShortTermEntry> {
+        let content = fs::read_to_string(self.current_session_path()).unwrap_or_default();
+        let mut entries = parse_short_term_entries(&content);
+        entries.reverse();
+        entries.truncate(limit);
+        entries
+    }
+
+    /// Every task recorded across all stored sessions, oldest first.
+    pub fn short_term_tasks(&self) -> Vec<String> {
+        self.list_sessions()
+            .iter()
+            .filter_map(|id| self.read_session(id).ok())
+            .flat_map(|content| parse_short_term_entries(&content))
+            .map(|entry| entry.task)
+            .collect()
+    }
+
     /// Overwrite the current session file with the compaction summary.
     ///
     /// After context compaction the old session history is no longer accurate.
@@ -282,11 +315,13 @@ impl Session {
         app.pending_confirm_note = false;
         app.current_phase_summary = None;
         app.task_events.clear();
+        app.transcript_ring.clear();
         app.final_summary = None;
         app.task_collapsed = false;
         app.pending_question = None;
         app.answering_question = false;
         app.pending_api_key_name = None;
+        app.pending_ask_secret = None;
         app.paste_counter = 0;
         app.paste_chunks.clear();
         app.task_display_override = None;
@@ -325,6 +360,7 @@ impl Session {
         screen.input.clear();
         screen.input_cursor = 0;
         screen.input_focused = true;
+        screen.mask_input = false;
         screen.reset_task_lines();
         screen.clear_screen();
     }
@@ -371,27 +407,27 @@ impl Session {
 // ── File helpers ──────────────────────────────────────────────────────────────
 
 fn ensure_session_header(path: &Path) -> Result<()> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
     if !path.exists() {
         let active_session_id = Session::active_id();
         let ts = Session::format_session_timestamp(&active_session_id);
-        fs::write(path, format!("# Session {ts}\n"))?;
+        crate::config::write_config_file(path, &format!("# Session {ts}\n"))?;
     }
     Ok(())
 }
 
 fn append_file(path: PathBuf, content: &str) -> Result<()> {
     use std::io::Write;
+    let shown = crate::config::display_config_path(&path);
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+        fs::create_dir_all(parent).with_context(|| format!("couldn't save to {shown}"))?;
     }
     let mut file = fs::OpenOptions::new()
         .create(true)
         .append(true)
-        .open(path)?;
-    file.write_all(content.as_bytes())?;
+        .open(&path)
+        .with_context(|| format!("couldn't save to {shown}"))?;
+    file.write_all(content.as_bytes())
+        .with_context(|| format!("couldn't save to {shown}"))?;
     Ok(())
 }
 
@@ -446,6 +482,50 @@ fn parse_restored_task_section(lines: &[&str], idx: &mut usize, heading: &str) -
     out
 }
 
+fn parse_short_term_entries(content: &str) -> Vec<ShortTermEntry> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut entries = Vec::new();
+    let mut idx = 0;
+    while idx < lines.len() {
+        let Some(heading) = lines[idx].trim_end().strip_prefix("## ") else {
+            idx += 1;
+            continue;
+        };
+        idx += 1;
+        if heading.contains('[') {
+            continue;
+        }
+        let mut task = None;
+        let mut final_text = None;
+        while idx < lines.len() && !lines[idx].starts_with("## ") {
+            match lines[idx].trim() {
+                "- **Task**" => {
+                    idx += 1;
+                    task = read_fenced_block(&lines, &mut idx);
+                }
+                "- **Final**" => {
+                    idx += 1;
+                    final_text = read_fenced_block(&lines, &mut idx);
+                }
+                _ => idx += 1,
+            }
+        }
+        let Some(task) = task.filter(|t| !t.trim().is_empty()) else {
+            continue;
+        };
+        let summary = final_text
+            .as_deref()
+            .and_then(|t| t.lines().map(str::trim).find(|l| !l.is_empty()))
+            .unwrap_or("");
+        entries.push(ShortTermEntry {
+            time: heading.trim().to_string(),
+            task: task.trim().to_string(),
+            summary: truncate_chars(summary, SHORT_TERM_SUMMARY_CHARS),
+        });
+    }
+    entries
+}
+
 fn parse_restored_diff_section(lines: &[&str], idx: &mut usize, heading: &str) -> Vec<String> {
     let mut command = String::new();
     let mut files: Vec<(String, String)> = Vec::new();
@@ -545,6 +625,42 @@ mod tests {
         let _ = fs::remove_dir_all(base);
     }
 
+    #[test]
+    fn recent_short_term_is_newest_first_and_limited() {
+        let _guard = SESSION_TEST_LOCK
+            .get_or_init(|| Mutex::new(()))
+            .lock()
+            .unwrap();
+        let old_active = Session::active_id();
+        Session::switch_active("20260405-090000");
+        let (store, base) = temp_store();
+        assert!(store.recent_short_term(5).is_empty());
+
+        store
+            .append_to_session("整理 README", "README 已更新\n细节")
+            .unwrap();
+        store
+            .append_diff_to_session("sed -i s/a/b/ x", &[("x".into(), "-a\n+b".into())])
+            .unwrap();
+        store
+            .append_to_session("修复登录 bug", "\n已修复 token 过期")
+            .unwrap();
+        store.append_to_session("跑测试", "全部通过").unwrap();
+
+        let entries = store.recent_short_term(2);
+        let tasks: Vec<&str> = entries.iter().map(|e| e.task.as_str()).collect();
+        assert_eq!(tasks, ["跑测试", "修复登录 bug"]);
+        assert_eq!(entries[1].summary, "已修复 token 过期");
+        assert_eq!(entries[0].time.len(), "HH:MM:SS".len());
+
+        let all = store.recent_short_term(10);
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[2].summary, "README 已更新");
+
+        Session::switch_active(&old_active);
+        let _ = fs::remove_dir_all(base);
+    }
+
     #[test]
     fn format_session_timestamp_parses_correctly() {
         let ts = Session::format_session_timestamp("20260331-142530");
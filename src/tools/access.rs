@@ -0,0 +1,175 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use anyhow::{Result, bail};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// 工作区根目录下的访问禁区列表（gitignore 语法）：命中的路径不进入 `@` 索引，
+/// 文件工具拒绝读写，shell 命令的目标命中时直接拦截。
+pub(crate) const IGNORE_FILE_NAME: &str = ".goldbotignore";
+
+/// 工作区根目录、`.goldbotignore` 的修改时间、解析结果。
+type CacheEntry = (PathBuf, Option<SystemTime>, Arc<AccessIgnore>);
+
+/// 按工作区缓存的解析结果；`.goldbotignore` 的修改时间变化后重新解析。
+static CACHE: Mutex<Vec<CacheEntry>> = Mutex::new(Vec::new());
+
+/// `.goldbotignore` 解析后的匹配器，各个访问入口共用同一份。
+pub(crate) struct AccessIgnore {
+    root: PathBuf,
+    matcher: Gitignore,
+}
+
+impl AccessIgnore {
+    pub(crate) fn parse(root: &Path, raw: &str) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        for line in raw.lines() {
+            // 单行语法错误只跳过该行，其余规则照常生效
+            let _ = builder.add_line(None, line);
+        }
+        Self {
+            root: root.to_path_buf(),
+            matcher: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.matcher.is_empty()
+    }
+
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// `path` 可为绝对路径或相对工作区的路径；工作区之外的路径不受限制。
+    pub(crate) fn is_ignored(&self, path: &Path) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let abs = normalize_lexically(&self.root.join(path));
+        let Ok(rel) = abs.strip_prefix(&self.root) else {
+            return false;
+        };
+        if rel.as_os_str().is_empty() {
+            return false;
+        }
+        self.matcher
+            .matched_path_or_any_parents(rel, abs.is_dir())
+            .is_ignore()
+    }
+
+    /// 读取类工具的访问检查。
+    pub(crate) fn check_read(&self, path: &Path) -> Result<()> {
+        if self.is_ignored(path) {
+            bail!("path is in {IGNORE_FILE_NAME}: {}", path.display());
+        }
+        Ok(())
+    }
+
+    /// 写入类工具的访问检查：另外禁止改写 `.goldbotignore` 本身，Agent 不能自行解除限制。
+    pub(crate) fn check_write(&self, path: &Path) -> Result<()> {
+        self.check_read(path)?;
+        if is_ignore_file(&self.root, path) {
+            bail!("{IGNORE_FILE_NAME} can only be edited by the user");
+        }
+        Ok(())
+    }
+}
+
+/// 工作区的访问禁区；没有 `.goldbotignore` 时为空匹配器。
+pub(crate) fn for_workspace(root: &Path) -> Arc<AccessIgnore> {
+    let file = root.join(IGNORE_FILE_NAME);
+    let mtime = fs::metadata(&file).and_then(|m| m.modified()).ok();
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, _, hit)) = cache.iter().find(|(r, m, _)| r == root && *m == mtime) {
+        return Arc::clone(hit);
+    }
+    let raw = fs::read_to_string(&file).unwrap_or_default();
+    let parsed = Arc::new(AccessIgnore::parse(root, &raw));
+    cache.retain(|(r, _, _)| r != root);
+    cache.push((root.to_path_buf(), mtime, Arc::clone(&parsed)));
+    parsed
+}
+
+/// 以启动时确定的工作区（即 `app.workspace`）为根，与文件工具用同一个根；
+/// 尚未初始化工作区时（如单元测试）退回当前目录。
+pub(crate) fn for_current_workspace() -> Arc<AccessIgnore> {
+    match crate::memory::project::current_workspace() {
+        Some(root) => for_workspace(root),
+        None => for_workspace(&std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))),
+    }
+}
+
+/// `path` 是否就是工作区的 `.goldbotignore`（相对路径按工作区解析）。
+pub(crate) fn is_ignore_file(root: &Path, path: &Path) -> bool {
+    normalize_lexically(&root.join(path)) == root.join(IGNORE_FILE_NAME)
+}
+
+/// 不访问文件系统地消解 `.` 与 `..`，目标文件尚不存在时也能判断。
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{AccessIgnore, IGNORE_FILE_NAME, for_workspace};
+
+    #[test]
+    fn gitignore_rules_cover_files_dirs_and_escapes() {
+        let root = Path::new("/work/app");
+        let ignore = AccessIgnore::parse(
+            root,
+            "# 生产配置\n.env.production\nsecrets/\n*.pem\n!public.pem\n",
+        );
+        assert!(ignore.is_ignored(Path::new(".env.production")));
+        assert!(ignore.is_ignored(Path::new("/work/app/.env.production")));
+        assert!(ignore.is_ignored(Path::new("secrets/db/password.txt")));
+        assert!(ignore.is_ignored(Path::new("config/server.pem")));
+        assert!(!ignore.is_ignored(Path::new("config/public.pem")));
+        assert!(!ignore.is_ignored(Path::new(".env")));
+        // `..` 绕不开规则；工作区之外不受限制
+        assert!(ignore.is_ignored(Path::new("src/../secrets/key")));
+        assert!(!ignore.is_ignored(Path::new("/elsewhere/.env.production")));
+
+        assert!(ignore.check_read(Path::new("README.md")).is_ok());
+        let err = ignore.check_read(Path::new("secrets/key")).unwrap_err();
+        assert!(err.to_string().contains("path is in .goldbotignore"));
+        assert!(ignore.check_read(Path::new(IGNORE_FILE_NAME)).is_ok());
+        assert!(ignore.check_write(Path::new(IGNORE_FILE_NAME)).is_err());
+    }
+
+    #[test]
+    fn workspace_matcher_is_reparsed_after_edit() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-access-{nanos}"));
+        std::fs::create_dir_all(&ws).unwrap();
+        assert!(!for_workspace(&ws).is_ignored(Path::new("notes.txt")));
+
+        let file = ws.join(IGNORE_FILE_NAME);
+        std::fs::write(&file, "notes.txt\n").unwrap();
+        let first = for_workspace(&ws);
+        assert!(first.is_ignored(Path::new("notes.txt")));
+        // 未修改时复用同一份解析结果
+        assert!(std::sync::Arc::ptr_eq(&first, &for_workspace(&ws)));
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+}
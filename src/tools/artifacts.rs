@@ -0,0 +1,170 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// artifacts 目录最多保留的输出文件数，更早的在新文件落盘时清理。
+const MAX_KEPT_ARTIFACTS: usize = 50;
+/// 超过这个时间的输出文件同样清理（7 天）。
+const ARTIFACT_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// 预览之后追加的文件路径说明最多占这么多字符，调用方生成预览时预留出来。
+pub(crate) const SPILL_NOTE_MAX_CHARS: usize = 512;
+
+/// 超长工具结果的完整内容存放目录。
+pub(crate) fn artifacts_dir() -> PathBuf {
+    crate::tools::mcp::goldbot_home_dir().join("artifacts")
+}
+
+/// 工具结果超出预算时不只留下截断版本：`full` 写到 `dir` 下的文件，返回 `preview`
+/// 加文件路径，模型需要时再用 read 工具按行读取。写文件失败时只返回 `preview`。
+pub(crate) fn spill_to_artifact(full: &str, dir: &Path, preview: String) -> String {
+    let name = format!(
+        "output-{}-{}.log",
+        chrono::Local::now().format("%Y%m%d-%H%M%S-%f"),
+        std::process::id()
+    );
+    let path = dir.join(name);
+    if write_private(dir, &path, full).is_err() {
+        return preview;
+    }
+    prune_artifacts(dir, MAX_KEPT_ARTIFACTS, ARTIFACT_MAX_AGE);
+    format!(
+        "{preview}\n(full output saved to {}, {} lines; use <tool>read</tool> with <offset>/<limit> to inspect line ranges)",
+        path.display(),
+        full.lines().count()
+    )
+}
+
+/// 只按字节预算截断的工具结果（glob、web_search）：超出 `max_bytes` 时结果里保留开头，
+/// 完整内容同样落盘。
+pub(crate) fn spill_truncated(text: String, max_bytes: usize, dir: &Path) -> String {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut cut = max_bytes;
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    let preview = format!("{}\n...[truncated]", &text[..cut]);
+    spill_to_artifact(&text, dir, preview)
+}
+
+/// 工具输出可能含有密钥等敏感内容：目录仅本人可访问（0700），文件仅本人可读写（0600）。
+fn write_private(dir: &Path, path: &Path, text: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(text.as_bytes())
+}
+
+/// 只保留最新的 `keep` 个输出文件，并删除修改时间早于 `max_age` 的文件。
+fn prune_artifacts(dir: &Path, keep: usize, max_age: Duration) {
+    let mut files: Vec<(PathBuf, Option<std::time::SystemTime>)> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| {
+                    let name = e.file_name();
+                    let name = name.to_string_lossy();
+                    name.starts_with("output-") && name.ends_with(".log")
+                })
+                .map(|e| (e.path(), e.metadata().and_then(|m| m.modified()).ok()))
+                .collect()
+        })
+        .unwrap_or_default();
+    // 文件名以时间戳开头，按名称排序即按创建时间排序
+    files.sort();
+    let excess = files.len().saturating_sub(keep);
+    for (index, (path, modified)) in files.iter().enumerate() {
+        let expired = modified
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+        if index < excess || expired {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prune_artifacts, spill_to_artifact, spill_truncated};
+
+    #[test]
+    fn spilled_output_is_private_and_old_files_are_pruned() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("goldbot-artifacts-prune-{nanos}"));
+
+        let result = spill_to_artifact("a\nb\n", &dir, "a…".to_string());
+        let files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert_eq!(std::fs::read_to_string(&files[0]).unwrap(), "a\nb\n");
+        let reference = format!("a…\n(full output saved to {}, 2 lines;", files[0].display());
+        assert!(result.starts_with(&reference), "{result}");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&files[0]).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // 超过保留数量时删除最早的文件
+        for i in 0..3 {
+            std::fs::write(dir.join(format!("output-0000-old-{i}.log")), "x").unwrap();
+        }
+        prune_artifacts(&dir, 2, std::time::Duration::from_secs(3600));
+        let mut left: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            vec![dir.join("output-0000-old-2.log"), files[0].clone()]
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn truncated_results_cut_on_char_boundary_and_keep_the_full_text() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("goldbot-artifacts-truncated-{nanos}"));
+
+        assert_eq!(spill_truncated("短".to_string(), 8, &dir), "短");
+        assert!(!dir.exists());
+
+        // "金" 占 3 字节，切在第 4 字节时退回到 3
+        let text = "金金金金".to_string();
+        let result = spill_truncated(text.clone(), 4, &dir);
+        assert!(
+            result.starts_with("金\n...[truncated]\n(full output saved to "),
+            "{result}"
+        );
+        let files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        assert_eq!(std::fs::read_to_string(&files[0]).unwrap(), text);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
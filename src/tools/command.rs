@@ -23,44 +23,164 @@ pub enum CommandAction {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BuiltinCommand {
     Help,
+    Attachments,
     Clear,
     Compact,
+    CompactPreview,
+    Context,
+    Diff,
+    Explain,
+    Export,
+    History,
     Memory,
     NoMemory,
+    Observe,
+    Profile,
+    Prompt,
+    Raw,
+    Remember,
+    Forget,
+    Rerun,
+    Review,
     Session,
     Thinking,
+    Timestamps,
     Skills,
     Mcp,
+    Tools,
     Status,
     Model,
+    Undo,
 }
 
 // ── 内置命令列表（单一数据源）────────────────────────────────────────────────
 
 /// (variant, name, description)
 const BUILTIN_COMMANDS: &[(BuiltinCommand, &str, &str)] = &[
+    (
+        BuiltinCommand::Attachments,
+        "attachments",
+        "列出当前草稿中的 @ 文件与粘贴内容（/attachments remove <序号> 移除）",
+    ),
     (BuiltinCommand::Clear, "clear", "清除会话历史，重新开始对话"),
     (
         BuiltinCommand::Compact,
         "compact",
         "立即压缩上下文（节省 Token）",
     ),
+    (
+        BuiltinCommand::CompactPreview,
+        "compact-preview",
+        "预览压缩将丢弃的消息与摘要，确认后再压缩",
+    ),
+    (
+        BuiltinCommand::Context,
+        "context",
+        "查看当前上下文的消息构成与 compact 预算（/context --verbose 逐条列出）",
+    ),
+    (
+        BuiltinCommand::Diff,
+        "diff",
+        "在外部 pager（$PAGER，默认 less -R）中查看工作区的完整 diff",
+    ),
+    (
+        BuiltinCommand::Explain,
+        "explain",
+        "让 LLM 诊断上一条失败命令的原因并给出修复建议（一次性回答，不影响当前任务）",
+    ),
+    (
+        BuiltinCommand::Export,
+        "export",
+        "导出当前任务记录为 Markdown（/export [路径]）",
+    ),
+    (
+        BuiltinCommand::Forget,
+        "forget",
+        "删除包含指定文字的长期记忆（/forget <关键词>）",
+    ),
     (BuiltinCommand::Help, "help", "显示键位绑定和可用命令列表"),
-    (BuiltinCommand::Mcp, "mcp", "列出所有已注册的 MCP 工具"),
-    (BuiltinCommand::Memory, "memory", "查看当前项目记忆内容"),
+    (
+        BuiltinCommand::History,
+        "history",
+        "查看本会话最近的任务记录（/history [数量] 或 /history search <关键词>）",
+    ),
+    (
+        BuiltinCommand::Mcp,
+        "mcp",
+        "列出所有已注册的 MCP 工具（/mcp reload 重新加载配置，/mcp call 直接调用工具）",
+    ),
+    (
+        BuiltinCommand::Memory,
+        "memory",
+        "查看当前项目记忆内容（/memory promote 手动提升重复任务）",
+    ),
     (BuiltinCommand::NoMemory, "nomemory", "切换无记忆模式（跳过记忆注入）"),
     (BuiltinCommand::Model, "model", "切换 LLM 后端与模型"),
+    (
+        BuiltinCommand::Observe,
+        "observe",
+        "切换观察模式（命令与写入只模拟、不执行）",
+    ),
+    (
+        BuiltinCommand::Profile,
+        "profile",
+        "显示当前生效的 profile（全局 / workspace）及存储位置",
+    ),
+    (
+        BuiltinCommand::Prompt,
+        "prompt",
+        "按段查看系统提示词的构成与体积（/prompt full 输出全文）",
+    ),
+    (
+        BuiltinCommand::Raw,
+        "raw",
+        "切换原始响应显示（每轮 LLM 回复的完整原文，调试提示词与解析失败用）",
+    ),
+    (
+        BuiltinCommand::Remember,
+        "remember",
+        "把一条纠正直接写入长期记忆，本会话立即生效（/remember <内容>）",
+    ),
+    (
+        BuiltinCommand::Rerun,
+        "rerun",
+        "用当前模型以全新上下文重跑上一个任务，便于对比（先用 /model 切换）",
+    ),
+    (
+        BuiltinCommand::Review,
+        "review",
+        "对当前工作区运行提交前检查（空白错误、变更文件、diff 统计）",
+    ),
     (BuiltinCommand::Session, "Session", "浏览并恢复历史会话"),
-    (BuiltinCommand::Skills, "skills", "列出所有已发现的 Skill"),
+    (
+        BuiltinCommand::Skills,
+        "skills",
+        "列出所有已发现的 Skill（/skills reload 重新发现）",
+    ),
     (
         BuiltinCommand::Status,
         "status",
         "显示 workspace、模型、环境配置摘要",
     ),
+    (
+        BuiltinCommand::Timestamps,
+        "timestamps",
+        "切换事件时间戳（实时事件行前显示 HH:MM:SS）",
+    ),
+    (
+        BuiltinCommand::Tools,
+        "tools",
+        "查看或切换内置工具（/tools disable <名称> / /tools enable <名称>）",
+    ),
+    (
+        BuiltinCommand::Undo,
+        "undo",
+        "非 git 工作区：撤销上一个任务写入的文件（恢复编辑前快照）",
+    ),
     (
         BuiltinCommand::Thinking,
         "thinking",
-        "切换原生 Thinking 模式（同 Tab）",
+        "切换原生 Thinking 模式（同 Tab）；/thinking concise 隐藏客套话",
     ),
 ];
 
@@ -72,18 +192,24 @@ pub fn goldbot_command_dir() -> PathBuf {
 }
 
 /// 扫描用户命令目录，返回所有有效的用户自定义命令。
-/// 优先级：`~/.goldbot/commands/` → `~/.claude/commands/`
-pub fn discover_commands() -> Vec<Command> {
+/// 优先级：工作区 `.goldbot/commands/` → `~/.goldbot/commands/` → `~/.claude/commands/`
+pub fn discover_commands(workspace: &Path) -> Vec<Command> {
     let mut commands = Vec::new();
     let mut seen: HashSet<String> = HashSet::new();
 
+    // 工作区命令：无 frontmatter 时首行为描述、其余为模板
+    if let Some(dir) = crate::memory::profile::workspace_profile_dir(workspace) {
+        scan_flat_dir(&dir.join("commands"), true, &mut commands, &mut seen);
+    }
+
     // GoldBot 专属目录
-    scan_flat_dir(&goldbot_command_dir(), &mut commands, &mut seen);
+    scan_flat_dir(&goldbot_command_dir(), false, &mut commands, &mut seen);
 
     // Claude Code 兼容目录
     if let Some(home) = crate::tools::home_dir() {
         scan_flat_dir(
             &home.join(".claude").join("commands"),
+            false,
             &mut commands,
             &mut seen,
         );
@@ -106,23 +232,98 @@ pub fn all_commands(user_commands: &[Command]) -> Vec<Command> {
     out
 }
 
+/// 把命令输入拆成 (命令名, 参数)，如 `export notes.md` → (`export`, `notes.md`)。
+pub fn split_command_args(query: &str) -> (&str, &str) {
+    match query.trim_start().split_once(char::is_whitespace) {
+        Some((name, args)) => (name, args.trim()),
+        None => (query.trim(), ""),
+    }
+}
+
 /// 按 query 包含匹配过滤命令列表（大小写不敏感）。query 为空返回全部。
+/// query 中第一个空白之后的内容视为参数，不参与匹配。
 pub fn filter_commands<'a>(commands: &'a [Command], query: &str) -> Vec<&'a Command> {
-    if query.is_empty() {
+    let (name, args) = split_command_args(query);
+    if !args.is_empty() {
+        // 已在输入参数：只保留命令名完全匹配的项
+        return commands
+            .iter()
+            .filter(|c| c.name.eq_ignore_ascii_case(name))
+            .collect();
+    }
+    if name.is_empty() {
         return commands.iter().collect();
     }
-    let q = query.to_lowercase();
+    let q = name.to_lowercase();
     commands
         .iter()
         .filter(|c| c.name.to_lowercase().contains(&q) || c.description.to_lowercase().contains(&q))
         .collect()
 }
 
+/// 模板里是否有 `$ARGUMENTS` 或 `$1`、`$2` 等参数占位符。
+pub fn template_takes_args(template: &str) -> bool {
+    template.contains("$ARGUMENTS")
+        || template
+            .split('$')
+            .skip(1)
+            .any(|rest| rest.starts_with(|c: char| c.is_ascii_digit() && c != '0'))
+}
+
+/// 展开模板参数：`$ARGUMENTS` 为命令后的全部文本，`$1`、`$2`… 为按空白拆分的各项，
+/// 缺少的位置参数替换为空。
+pub fn expand_template_args(template: &str, args: &str) -> String {
+    let positional: Vec<&str> = args.split_whitespace().collect();
+    let mut out = String::with_capacity(template.len() + args.len());
+    let mut rest = template;
+    while let Some(at) = rest.find('$') {
+        out.push_str(&rest[..at]);
+        let after = &rest[at + 1..];
+        if let Some(tail) = after.strip_prefix("ARGUMENTS") {
+            out.push_str(args);
+            rest = tail;
+            continue;
+        }
+        let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        match after[..digits].parse::<usize>() {
+            Ok(n) if n > 0 => {
+                out.push_str(positional.get(n - 1).copied().unwrap_or(""));
+                rest = &after[digits..];
+            }
+            _ => {
+                out.push('$');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 提交模板命令时的最终任务文本。模板带参数占位符时，`/name foo bar` 的 `foo bar`
+/// 代入模板；否则沿用原行为，把输入中的 `/name` 替换为模板内容。
+pub fn expand_template_input(raw: &str, placeholder: &str, template: &str) -> String {
+    if template_takes_args(template)
+        && let Some(args) = raw.trim_start().strip_prefix(placeholder)
+        && (args.is_empty() || args.starts_with(char::is_whitespace))
+    {
+        return expand_template_args(template, args.trim())
+            .trim()
+            .to_string();
+    }
+    raw.replace(placeholder, template).trim().to_string()
+}
+
 /// 若存在用户自定义命令，返回启动时状态行提示字符串。
 // ── 私有辅助 ──────────────────────────────────────────────────────────────────
 
 /// 扫描平铺目录：每个 `<name>.md` 文件即一个命令。
-fn scan_flat_dir(dir: &Path, commands: &mut Vec<Command>, seen: &mut HashSet<String>) {
+fn scan_flat_dir(
+    dir: &Path,
+    first_line_description: bool,
+    commands: &mut Vec<Command>,
+    seen: &mut HashSet<String>,
+) {
     let Ok(entries) = fs::read_dir(dir) else {
         return;
     };
@@ -131,7 +332,7 @@ fn scan_flat_dir(dir: &Path, commands: &mut Vec<Command>, seen: &mut HashSet<Str
         if path.extension().and_then(|e| e.to_str()) != Some("md") {
             continue;
         }
-        if let Some(cmd) = parse_flat_command(&path)
+        if let Some(cmd) = parse_flat_command(&path, first_line_description)
             && seen.insert(cmd.name.clone())
         {
             commands.push(cmd);
@@ -142,8 +343,9 @@ fn scan_flat_dir(dir: &Path, commands: &mut Vec<Command>, seen: &mut HashSet<Str
 /// 解析平铺命令文件（`<name>.md`）。
 /// - 文件名（不含扩展名）作为命令名
 /// - frontmatter 中 `description` 作为描述（可选）
-/// - frontmatter body 作为模板内容；无 frontmatter 则整个文件作为模板
-fn parse_flat_command(file: &Path) -> Option<Command> {
+/// - frontmatter body 作为模板内容；无 frontmatter 则整个文件作为模板，
+///   `first_line_description` 时首行（去掉 `#`）为描述、其余为模板
+fn parse_flat_command(file: &Path, first_line_description: bool) -> Option<Command> {
     let name = file.file_stem()?.to_str()?.to_string();
     if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
         return None;
@@ -155,6 +357,11 @@ fn parse_flat_command(file: &Path) -> Option<Command> {
             .map(|s| s.trim().to_string())
             .unwrap_or_default();
         (desc, body.trim().to_string())
+    } else if first_line_description {
+        let raw = raw.trim_start_matches('\u{feff}').trim_start();
+        let (first, body) = raw.split_once('\n').unwrap_or((raw, ""));
+        let desc = first.trim().trim_start_matches('#').trim().to_string();
+        (desc, body.trim().to_string())
     } else {
         (String::new(), raw.trim().to_string())
     };
@@ -200,3 +407,65 @@ fn strip_yaml_quotes(s: &str) -> String {
         s.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CommandAction, discover_commands, expand_template_args, expand_template_input,
+        template_takes_args,
+    };
+
+    #[test]
+    fn template_args_substitute_positional_and_all() {
+        let template = "Review $1 against $2.\nContext: $ARGUMENTS ($3)";
+        assert!(template_takes_args(template));
+        assert_eq!(
+            expand_template_args(template, "src/a.rs  main"),
+            "Review src/a.rs against main.\nContext: src/a.rs  main ()"
+        );
+        // 非参数的 `$` 原样保留
+        assert_eq!(
+            expand_template_args("cost $0 or $ x", "a"),
+            "cost $0 or $ x"
+        );
+        assert!(!template_takes_args("price $0, $HOME"));
+
+        // 提交时：带占位符的模板代入参数，否则沿用整体替换
+        assert_eq!(
+            expand_template_input("/fix foo bar", "/fix", "Fix $1 then $2"),
+            "Fix foo then bar"
+        );
+        assert_eq!(
+            expand_template_input("/fix", "/fix", "Fix $ARGUMENTS now"),
+            "Fix  now"
+        );
+        assert_eq!(
+            expand_template_input("/plain extra", "/plain", "Do it"),
+            "Do it extra"
+        );
+    }
+
+    #[test]
+    fn workspace_commands_use_first_line_as_description() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-commands-test-{nanos}"));
+        let dir = ws.join(".goldbot/commands");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("port.md"), "# Port a module\nPort $1 to $2.\n").unwrap();
+
+        let commands = discover_commands(&ws);
+        let port = commands.iter().find(|c| c.name == "port").expect("port");
+        assert_eq!(port.description, "Port a module");
+        let CommandAction::Template(body) = &port.action else {
+            panic!("not a template");
+        };
+        assert_eq!(
+            expand_template_input("/port lexer rust", "/port", body),
+            "Port lexer to rust."
+        );
+        let _ = std::fs::remove_dir_all(ws);
+    }
+}
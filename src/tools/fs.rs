@@ -0,0 +1,699 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicUsize, Ordering},
+        mpsc::Sender,
+    },
+};
+
+use anyhow::Result;
+
+use crate::{
+    tools::{access::AccessIgnore, shell::render_unified_diff},
+    types::LlmAction,
+};
+
+/// 内容嗅探最多读取的字节数。
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+/// 控制字符占比超过该比例即视为二进制（百分比）。
+const BINARY_CONTROL_PERCENT: usize = 10;
+
+/// 常见图片扩展名：将来可交给支持视觉输入的后端处理。
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp"];
+/// 无需嗅探即可判定为二进制的扩展名。
+const BINARY_EXTENSIONS: &[&str] = &[
+    "a", "bin", "class", "dll", "dylib", "exe", "gz", "ico", "jar", "mov", "mp3", "mp4", "o",
+    "obj", "otf", "pdf", "pyc", "rlib", "so", "sqlite", "tar", "ttf", "wasm", "woff", "woff2",
+    "xz", "zip", "7z",
+];
+
+/// `@` 文件索引默认最多收录的文件数（`GOLDBOT_INDEX_MAX_FILES` 可调整）。
+const DEFAULT_INDEX_MAX_FILES: usize = 20_000;
+const INDEX_MAX_DEPTH: usize = 6;
+/// 并行扫描的线程数；缺省 1（单线程，扫描完一次性返回）。
+const ENV_INDEX_THREADS: &str = "GOLDBOT_INDEX_THREADS";
+const INDEX_MAX_THREADS: usize = 32;
+/// 始终跳过的目录名（隐藏目录另外统一跳过）。
+const INDEX_SKIP_DIRS: &[&str] = &[
+    "target",
+    "node_modules",
+    "dist",
+    "build",
+    "out",
+    "obj",
+    "vendor",
+    "__pycache__",
+    "Binaries",
+    "Saved",
+    "Intermediate",
+    "DerivedDataCache",
+];
+/// workspace profile 中的额外忽略列表：每行一个目录名或 glob，`#` 开头为注释。
+const INDEX_IGNORE_FILE: &str = "ignore";
+/// `tree` 工具缺省展开的层数。
+const TREE_DEFAULT_DEPTH: usize = 3;
+/// `tree` 工具最多列出的条目数，超出后追加截断提示。
+const TREE_MAX_NODES: usize = 400;
+
+/// 按扩展名判断是否为常见图片格式。
+pub fn is_image_path(path: &Path) -> bool {
+    IMAGE_EXTENSIONS.contains(&extension_lower(path).as_str())
+}
+
+/// 判断文件是否为二进制：先看扩展名，再嗅探前几 KB 内容。
+/// 读取失败时按文本处理，交给后续流程报错。
+pub fn looks_binary(path: &Path) -> bool {
+    let ext = extension_lower(path);
+    if IMAGE_EXTENSIONS.contains(&ext.as_str()) || BINARY_EXTENSIONS.contains(&ext.as_str()) {
+        return true;
+    }
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    let mut head = Vec::with_capacity(BINARY_SNIFF_BYTES);
+    if file
+        .take(BINARY_SNIFF_BYTES as u64)
+        .read_to_end(&mut head)
+        .is_err()
+    {
+        return false;
+    }
+    sniff_binary(&head)
+}
+
+fn extension_lower(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+fn sniff_binary(head: &[u8]) -> bool {
+    if head.is_empty() {
+        return false;
+    }
+    if head.contains(&0) {
+        return true;
+    }
+    // 允许 \t \n \r 与 ESC（ANSI 日志），其余 C0 控制字符计入可疑比例
+    let control = head
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+        .count();
+    control * 100 > head.len() * BINARY_CONTROL_PERCENT
+}
+
+/// 索引扫描的跳过规则：内置目录 + `.goldbot/ignore` + `.goldbotignore`，以及文件数上限。
+pub(crate) struct FileIndexFilter {
+    patterns: Vec<glob::Pattern>,
+    access: Option<Arc<AccessIgnore>>,
+    max_files: usize,
+}
+
+impl FileIndexFilter {
+    pub(crate) fn for_workspace(workspace: &std::path::Path) -> Self {
+        let ignore = crate::memory::profile::workspace_profile_dir(workspace)
+            .and_then(|dir| std::fs::read_to_string(dir.join(INDEX_IGNORE_FILE)).ok())
+            .unwrap_or_default();
+        let max_files = std::env::var("GOLDBOT_INDEX_MAX_FILES")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(DEFAULT_INDEX_MAX_FILES);
+        Self {
+            access: Some(crate::tools::access::for_workspace(workspace)),
+            ..Self::from_ignore_list(&ignore, max_files)
+        }
+    }
+
+    pub(crate) fn from_ignore_list(raw: &str, max_files: usize) -> Self {
+        let patterns = raw
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .map(|line| line.trim_start_matches("./").trim_end_matches('/'))
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| glob::Pattern::new(line).ok())
+            .collect();
+        Self {
+            patterns,
+            access: None,
+            max_files,
+        }
+    }
+
+    /// `name` 为条目名，`rel` 为相对工作区的路径（`/` 分隔）；两者任一命中即跳过。
+    /// `path` 为条目的完整路径，用于 `.goldbotignore` 判断。
+    fn skips(&self, name: &str, rel: &str, path: &Path) -> bool {
+        name.starts_with('.')
+            || INDEX_SKIP_DIRS.contains(&name)
+            || self
+                .patterns
+                .iter()
+                .any(|p| p.matches(name) || p.matches(rel))
+            || self.access.as_ref().is_some_and(|a| a.is_ignored(path))
+    }
+}
+
+pub(crate) fn collect_all_files(
+    base: &std::path::Path,
+    dir: &std::path::Path,
+    results: &mut Vec<std::path::PathBuf>,
+    depth: usize,
+    filter: &FileIndexFilter,
+) {
+    if depth > INDEX_MAX_DEPTH || results.len() >= filter.max_files {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if results.len() >= filter.max_files {
+            return;
+        }
+        let path = entry.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let rel = path
+            .strip_prefix(base)
+            .map(|r| r.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        if filter.skips(name, &rel, &path) {
+            continue;
+        }
+        if path.is_dir() {
+            collect_all_files(base, &path, results, depth + 1, filter);
+        } else if path.is_file() {
+            if let Ok(rel) = path.strip_prefix(base) {
+                results.push(rel.to_path_buf());
+            }
+        }
+    }
+}
+
+pub(crate) fn index_threads() -> usize {
+    std::env::var(ENV_INDEX_THREADS)
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(1)
+        .clamp(1, INDEX_MAX_THREADS)
+}
+
+/// 扫描工作区文件并经 `tx` 回传。单线程时扫描完一次性发送；
+/// 多线程时按目录分批发送，`@` picker 可以边扫边填充。
+pub(crate) fn scan_workspace_files(
+    base: &Path,
+    filter: &FileIndexFilter,
+    threads: usize,
+    tx: &Sender<Vec<PathBuf>>,
+) {
+    if threads <= 1 {
+        let mut results = Vec::new();
+        collect_all_files(base, base, &mut results, 0, filter);
+        let _ = tx.send(results);
+        return;
+    }
+
+    // 待扫描目录队列；pending 含正在处理的目录，归零即全部完成
+    struct ScanQueue {
+        dirs: VecDeque<(PathBuf, usize)>,
+        pending: usize,
+    }
+    let queue = Mutex::new(ScanQueue {
+        dirs: VecDeque::from([(base.to_path_buf(), 0)]),
+        pending: 1,
+    });
+    let ready = Condvar::new();
+    // 所有线程共用的收录计数，保证总数不超过上限
+    let taken = AtomicUsize::new(0);
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                loop {
+                    let (dir, depth) = {
+                        let mut q = queue.lock().unwrap_or_else(|e| e.into_inner());
+                        loop {
+                            if let Some(next) = q.dirs.pop_front() {
+                                break next;
+                            }
+                            if q.pending == 0 {
+                                return;
+                            }
+                            q = ready.wait(q).unwrap_or_else(|e| e.into_inner());
+                        }
+                    };
+                    let (files, subdirs) = scan_index_dir(base, &dir, depth, filter, &taken);
+                    if !files.is_empty() {
+                        let _ = tx.send(files);
+                    }
+                    let mut q = queue.lock().unwrap_or_else(|e| e.into_inner());
+                    if taken.load(Ordering::SeqCst) < filter.max_files {
+                        q.pending += subdirs.len();
+                        q.dirs.extend(subdirs.into_iter().map(|d| (d, depth + 1)));
+                    }
+                    q.pending -= 1;
+                    ready.notify_all();
+                }
+            });
+        }
+    });
+}
+
+/// 扫描单个目录：返回收录的文件（相对 `base`）和需要继续深入的子目录。
+fn scan_index_dir(
+    base: &Path,
+    dir: &Path,
+    depth: usize,
+    filter: &FileIndexFilter,
+    taken: &AtomicUsize,
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut files = Vec::new();
+    let mut subdirs = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return (files, subdirs);
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let rel = path
+            .strip_prefix(base)
+            .map(|r| r.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        if filter.skips(name, &rel, &path) {
+            continue;
+        }
+        if path.is_dir() {
+            if depth < INDEX_MAX_DEPTH {
+                subdirs.push(path);
+            }
+        } else if path.is_file()
+            && let Ok(rel) = path.strip_prefix(base)
+        {
+            if taken.fetch_add(1, Ordering::SeqCst) >= filter.max_files {
+                break;
+            }
+            files.push(rel.to_path_buf());
+        }
+    }
+    (files, subdirs)
+}
+
+#[derive(Debug)]
+pub struct TreeResult {
+    pub output: String,
+    pub node_count: usize,
+    pub truncated: bool,
+}
+
+/// 以缩进树列出 `path` 下的目录结构：目录在前、按名称排序，沿用 `@` 索引的跳过规则。
+/// `depth` 缺省为 3 层，超出层数的目录以 `name/ …` 表示；条目数超过上限时截断。
+pub fn directory_tree(path: &str, depth: Option<usize>, sizes: bool) -> Result<TreeResult> {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let root = if Path::new(path).is_absolute() {
+        PathBuf::from(path)
+    } else {
+        cwd.join(path)
+    };
+    if !root.is_dir() {
+        anyhow::bail!("not a directory: {}", root.display());
+    }
+    crate::tools::access::for_workspace(&cwd).check_read(&root)?;
+    let filter = FileIndexFilter::for_workspace(&cwd);
+    let depth = depth
+        .unwrap_or(TREE_DEFAULT_DEPTH)
+        .clamp(1, INDEX_MAX_DEPTH);
+    Ok(render_tree(
+        &root,
+        &cwd,
+        &filter,
+        depth,
+        TREE_MAX_NODES,
+        sizes,
+    ))
+}
+
+/// 一次 `tree` 遍历的参数。
+struct TreeWalk<'a> {
+    /// 忽略规则按相对工作区的路径匹配，与 `@` 索引一致，不随 `tree` 的起点变化。
+    workspace: &'a Path,
+    filter: &'a FileIndexFilter,
+    depth: usize,
+    max_nodes: usize,
+    sizes: bool,
+}
+
+fn render_tree(
+    root: &Path,
+    workspace: &Path,
+    filter: &FileIndexFilter,
+    depth: usize,
+    max_nodes: usize,
+    sizes: bool,
+) -> TreeResult {
+    let walk = TreeWalk {
+        workspace,
+        filter,
+        depth,
+        max_nodes,
+        sizes,
+    };
+    let mut lines = vec![format!("{}/", root.display())];
+    let truncated = walk.visit(root, 1, &mut lines);
+    let node_count = lines.len() - 1;
+    if truncated {
+        lines.push(format!(
+            "... (truncated at {max_nodes} entries; narrow the path or lower the depth)"
+        ));
+    }
+    TreeResult {
+        output: lines.join("\n"),
+        node_count,
+        truncated,
+    }
+}
+
+impl TreeWalk<'_> {
+    /// 递归写入 `lines`；达到条目上限时返回 true。
+    fn visit(&self, dir: &Path, level: usize, lines: &mut Vec<String>) -> bool {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return false;
+        };
+        let mut entries: Vec<(PathBuf, bool)> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|path| {
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                let rel = path
+                    .strip_prefix(self.workspace)
+                    .map(|r| r.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_default();
+                !self.filter.skips(name, &rel, path)
+            })
+            .map(|path| {
+                let is_dir = path.is_dir();
+                (path, is_dir)
+            })
+            .collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let indent = "  ".repeat(level);
+        for (path, is_dir) in entries {
+            if lines.len() > self.max_nodes {
+                return true;
+            }
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if is_dir {
+                let has_children = std::fs::read_dir(&path).is_ok_and(|mut it| it.next().is_some());
+                if level >= self.depth && has_children {
+                    lines.push(format!("{indent}{name}/ …"));
+                } else {
+                    lines.push(format!("{indent}{name}/"));
+                    if self.visit(&path, level + 1, lines) {
+                        return true;
+                    }
+                }
+            } else if self.sizes {
+                let len = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                lines.push(format!("{indent}{name} ({})", format_size(len)));
+            } else {
+                lines.push(format!("{indent}{name}"));
+            }
+        }
+        false
+    }
+}
+
+pub(crate) fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let b = bytes as f64;
+    if b < KB {
+        format!("{bytes} B")
+    } else if b < KB * KB {
+        format!("{:.1} KB", b / KB)
+    } else {
+        format!("{:.1} MB", b / KB / KB)
+    }
+}
+
+/// 确认菜单里展示的编辑预览：当前文件内容与 WriteFile / UpdateFile 执行后内容的 diff，
+/// 首行为 `Diff <path>:` 标题（新文件标注 `(new file)`，所有行都显示为新增）。
+/// 其它动作返回空串；读取失败或行号越界时返回说明而不是 diff。
+pub(crate) fn preview_edit(action: &LlmAction, workspace: &Path) -> String {
+    let resolve = |path: &str| {
+        if Path::new(path).is_absolute() {
+            PathBuf::from(path)
+        } else {
+            workspace.join(path)
+        }
+    };
+    match action {
+        LlmAction::WriteFile { path, content } => {
+            let old = std::fs::read_to_string(resolve(path)).ok();
+            let header = if old.is_some() {
+                format!("Diff {path}:")
+            } else {
+                format!("Diff {path} (new file):")
+            };
+            let old = old.unwrap_or_default().replace("\r\n", "\n");
+            let new = content.replace("\r\n", "\n");
+            format!("{header}\n{}", render_unified_diff(&old, &new, 0))
+        }
+        LlmAction::UpdateFile {
+            path,
+            line_start,
+            line_end,
+            new_string,
+        } => {
+            let old = match std::fs::read_to_string(resolve(path)) {
+                Ok(raw) => raw.replace("\r\n", "\n"),
+                Err(e) => return format!("Diff {path}:\n(preview unavailable: {e})\n"),
+            };
+            let mut lines: Vec<&str> = old.lines().collect();
+            let total = lines.len();
+            if *line_start == 0 || *line_end < *line_start || *line_end > total {
+                return format!(
+                    "Diff {path}:\n(preview unavailable: lines {line_start}-{line_end} outside 1-{total})\n"
+                );
+            }
+            let new_string = new_string.replace("\r\n", "\n");
+            lines.splice(line_start - 1..*line_end, new_string.lines());
+            let mut new = lines.join("\n");
+            if old.ends_with('\n') {
+                new.push('\n');
+            }
+            format!("Diff {path}:\n{}", render_unified_diff(&old, &new, 0))
+        }
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        FileIndexFilter, collect_all_files, is_image_path, looks_binary, preview_edit, render_tree,
+        scan_workspace_files,
+    };
+    use crate::types::LlmAction;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_file(name: &str, content: &[u8]) -> std::path::PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("goldbot-fs-test-{name}-{nanos}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn text_file_is_not_binary() {
+        let path = temp_file("notes.txt", "# 说明\n\tfn main() {}\r\n".as_bytes());
+        assert!(!looks_binary(&path));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn null_bytes_or_known_extensions_are_binary() {
+        let path = temp_file("artifact", b"\x7fELF\x02\x01\x01\x00\x00\x00");
+        assert!(looks_binary(&path));
+        assert!(!is_image_path(&path));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+
+        // 图片按扩展名识别，不需要读取内容
+        let image = std::path::Path::new("shot.PNG");
+        assert!(looks_binary(image));
+        assert!(is_image_path(image));
+    }
+
+    #[test]
+    fn tree_bounds_depth_skips_ignored_dirs_and_caps_nodes() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("goldbot-tree-test-{nanos}"));
+        for file in [
+            "src/main.rs",
+            "src/ui/deep/mod.rs",
+            "target/debug/app",
+            "gen/api.rs",
+            "README.md",
+        ] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "fn main() {}").unwrap();
+        }
+        let filter = FileIndexFilter::from_ignore_list("gen/", usize::MAX);
+
+        let tree = render_tree(&root, &root, &filter, 2, 100, true);
+        let body: Vec<&str> = tree.output.lines().skip(1).collect();
+        assert_eq!(
+            body,
+            [
+                "  src/",
+                "    ui/ …",
+                "    main.rs (12 B)",
+                "  README.md (12 B)"
+            ]
+        );
+        assert!(!tree.truncated);
+        assert_eq!(tree.node_count, 4);
+
+        // 条目上限：截断并提示
+        let capped = render_tree(&root, &root, &filter, 6, 2, false);
+        assert!(capped.truncated);
+        assert_eq!(capped.node_count, 2);
+        assert!(
+            capped
+                .output
+                .ends_with("narrow the path or lower the depth)"),
+            "{}",
+            capped.output
+        );
+
+        // 从子目录开始的 tree 仍按相对工作区的路径匹配忽略规则
+        let filter = FileIndexFilter::from_ignore_list("src/ui", usize::MAX);
+        let sub = render_tree(&root.join("src"), &root, &filter, 3, 100, false);
+        let body: Vec<&str> = sub.output.lines().skip(1).collect();
+        assert_eq!(body, ["  main.rs"]);
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn goldbotignore_paths_are_excluded_from_index_and_tree() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-access-index-{nanos}"));
+        for file in ["src/main.rs", "secrets/prod.key", "config/server.pem"] {
+            let path = ws.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        std::fs::write(ws.join(".goldbotignore"), "secrets/\n*.pem\n").unwrap();
+
+        let filter = FileIndexFilter::for_workspace(&ws);
+        let mut files = Vec::new();
+        collect_all_files(&ws, &ws, &mut files, 0, &filter);
+        let names: Vec<String> = files
+            .iter()
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .collect();
+        assert_eq!(names, ["src/main.rs"]);
+
+        let tree = render_tree(&ws, &ws, &filter, 3, 100, false);
+        let body: Vec<&str> = tree.output.lines().skip(1).collect();
+        assert_eq!(body, ["  config/", "  src/", "    main.rs"]);
+        let _ = std::fs::remove_dir_all(ws);
+    }
+
+    #[test]
+    fn parallel_index_scan_matches_sequential_and_honors_cap() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("goldbot-index-scan-{nanos}"));
+        for a in 0..4 {
+            for b in 0..3 {
+                let dir = root.join(format!("pkg{a}/mod{b}"));
+                std::fs::create_dir_all(&dir).unwrap();
+                for f in 0..5 {
+                    std::fs::write(dir.join(format!("f{f}.rs")), "").unwrap();
+                }
+            }
+        }
+        std::fs::create_dir_all(root.join("node_modules/dep")).unwrap();
+        std::fs::write(root.join("node_modules/dep/index.js"), "").unwrap();
+        std::fs::write(root.join("README.md"), "").unwrap();
+
+        let scan = |filter: &FileIndexFilter, threads: usize| {
+            let (tx, rx) = std::sync::mpsc::channel();
+            scan_workspace_files(&root, filter, threads, &tx);
+            drop(tx);
+            let mut files: Vec<_> = rx.iter().flatten().collect();
+            files.sort();
+            files
+        };
+        let filter = FileIndexFilter::from_ignore_list("pkg3/mod2", usize::MAX);
+        let mut sequential = Vec::new();
+        collect_all_files(&root, &root, &mut sequential, 0, &filter);
+        sequential.sort();
+        assert_eq!(sequential.len(), 4 * 3 * 5 - 5 + 1);
+        assert_eq!(scan(&filter, 1), sequential);
+        assert_eq!(scan(&filter, 4), sequential);
+
+        // 上限跨线程共享
+        let capped = FileIndexFilter::from_ignore_list("", 7);
+        assert_eq!(scan(&capped, 4).len(), 7);
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn edit_preview_diffs_modification_and_new_file() {
+        let file = temp_file("preview.txt", b"one\ntwo\nthree\n");
+        let dir = file.parent().unwrap();
+
+        let update = LlmAction::UpdateFile {
+            path: "preview.txt".to_string(),
+            line_start: 2,
+            line_end: 2,
+            new_string: "TWO".to_string(),
+        };
+        let preview = preview_edit(&update, dir);
+        let lines: Vec<&str> = preview.lines().collect();
+        assert_eq!(lines[0], "Diff preview.txt:");
+        assert!(lines.iter().any(|l| l.ends_with("2 - two")), "{preview}");
+        assert!(lines.iter().any(|l| l.ends_with("2 + TWO")), "{preview}");
+        assert!(lines.iter().any(|l| l.ends_with("1   one")), "{preview}");
+        // 预览不落盘
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "one\ntwo\nthree\n");
+
+        let create = LlmAction::WriteFile {
+            path: "fresh/new.rs".to_string(),
+            content: "fn a() {}\nfn b() {}\n".to_string(),
+        };
+        let preview = preview_edit(&create, dir);
+        let lines: Vec<&str> = preview.lines().collect();
+        assert_eq!(lines[0], "Diff fresh/new.rs (new file):");
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1..].iter().all(|l| l.contains(" + ")), "{preview}");
+        assert!(!dir.join("fresh").exists());
+
+        let out_of_range = LlmAction::UpdateFile {
+            path: "preview.txt".to_string(),
+            line_start: 3,
+            line_end: 9,
+            new_string: String::new(),
+        };
+        assert!(preview_edit(&out_of_range, dir).contains("preview unavailable"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
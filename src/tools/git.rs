@@ -0,0 +1,21 @@
+use std::{path::Path, process::Command};
+
+/// 工作区（或其任一上级目录）有 `.git` 时视为 git 仓库。
+pub(crate) fn is_git_workspace(workspace: &Path) -> bool {
+    workspace.ancestors().any(|dir| dir.join(".git").exists())
+}
+
+/// 工作区当前所在的分支（`git rev-parse --abbrev-ref HEAD`）；不是 git 仓库、
+/// 还没有提交或处于 detached HEAD 时返回 None。
+pub(crate) fn current_git_branch(workspace: &Path) -> Option<String> {
+    if !is_git_workspace(workspace) {
+        return None;
+    }
+    let out = Command::new("git")
+        .current_dir(workspace)
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .ok()?;
+    let branch = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !branch.is_empty() && branch != "HEAD").then_some(branch)
+}
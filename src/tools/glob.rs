@@ -2,6 +2,8 @@ use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 
+use crate::tools::artifacts::{artifacts_dir, spill_truncated};
+
 const MAX_GLOB_RESULTS: usize = 200;
 const MAX_OUTPUT_CHARS: usize = 10_000;
 
@@ -26,9 +28,11 @@ pub fn glob_files(pattern: &str, path: &str) -> Result<GlobResult> {
     let gp = glob::Pattern::new(pattern)
         .map_err(|e| anyhow::anyhow!("invalid glob pattern '{pattern}': {e}"))?;
 
+    let access = crate::tools::access::for_workspace(&cwd);
     let walker = ignore::WalkBuilder::new(&root)
         .hidden(true)
         .git_ignore(true)
+        .filter_entry(move |e| !access.is_ignored(e.path()))
         .build();
 
     let mut matched: Vec<(String, std::time::SystemTime)> = Vec::new();
@@ -72,10 +76,7 @@ pub fn glob_files(pattern: &str, path: &str) -> Result<GlobResult> {
     if total > MAX_GLOB_RESULTS {
         output.push_str(&format!("... ({} more files)\n", total - MAX_GLOB_RESULTS));
     }
-    if output.len() > MAX_OUTPUT_CHARS {
-        output.truncate(MAX_OUTPUT_CHARS);
-        output.push_str("\n... (output truncated)\n");
-    }
+    let output = spill_truncated(output, MAX_OUTPUT_CHARS, &artifacts_dir());
 
     Ok(GlobResult {
         output,
@@ -0,0 +1,206 @@
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{Context, Result};
+
+/// An init script still running after this long is killed and its environment dropped.
+const INIT_SCRIPT_TIMEOUT_SECS: u64 = 60;
+/// Separates the env snapshots taken before and after sourcing the init script.
+const INIT_ENV_MARKER: &str = "__GOLDBOT_INIT_ENV__";
+/// Maintained by the shell itself, so they differ between the snapshots without the
+/// script exporting anything.
+const INIT_ENV_IGNORED: &[&str] = &["_", "SHLVL", "PWD", "OLDPWD"];
+
+/// Result of sourcing the init script.
+pub(crate) struct InitOutcome {
+    /// Exit status; None when the script timed out or was killed by a signal.
+    pub(crate) status: Option<i32>,
+    /// Variables the script added or changed (`Some`) or unset (`None`). Empty unless it
+    /// returned normally.
+    pub(crate) env: BTreeMap<String, Option<String>>,
+    /// Everything the script printed (stdout and stderr).
+    pub(crate) output: String,
+}
+
+/// Sources `script` in a bash started the same way as tool commands and diffs the
+/// environment before and after it, like direnv.
+pub(crate) fn capture_init_env(script: &Path, workspace: &Path) -> Result<InitOutcome> {
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    // 脚本自己的 stdout 也并到 stderr，stdout 只留给两份环境快照；
+    // stderr 里的标记之前是登录 profile 的输出，不算脚本的
+    let wrapper = format!(
+        "printf '\\0%s\\0' {INIT_ENV_MARKER}; env -0; echo {INIT_ENV_MARKER} >&2; \
+         . \"$0\" >&2 </dev/null; s=$?; \
+         printf '\\0%s\\0' {INIT_ENV_MARKER}; env -0; exit $s"
+    );
+    let mut child = Command::new("bash")
+        .arg("-lc")
+        .arg(wrapper)
+        .arg(script)
+        .current_dir(workspace)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("couldn't start bash")?;
+    // 读取放在线程里：脚本留下的后台进程可能一直占着管道，不能无限等待
+    let readers = [
+        child
+            .stdout
+            .take()
+            .map(|pipe| Box::new(pipe) as Box<dyn Read + Send>),
+        child
+            .stderr
+            .take()
+            .map(|pipe| Box::new(pipe) as Box<dyn Read + Send>),
+    ]
+    .map(|pipe| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        if let Some(mut pipe) = pipe {
+            std::thread::spawn(move || {
+                let mut buf = Vec::new();
+                let _ = pipe.read_to_end(&mut buf);
+                let _ = tx.send(buf);
+            });
+        }
+        rx
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(INIT_SCRIPT_TIMEOUT_SECS);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status.code();
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let [stdout, stderr] =
+        readers.map(|rx| rx.recv_timeout(Duration::from_secs(1)).unwrap_or_default());
+
+    let mut snapshots = parse_env_snapshots(&stdout).into_iter();
+    let env = match (status, snapshots.next(), snapshots.next()) {
+        (Some(0), Some(before), Some(after)) => env_diff(&before, &after),
+        _ => BTreeMap::new(),
+    };
+    let stderr = String::from_utf8_lossy(&stderr);
+    let output = stderr
+        .split_once(&format!("{INIT_ENV_MARKER}\n"))
+        .map_or(&*stderr, |(_, script)| script);
+    Ok(InitOutcome {
+        status,
+        env,
+        output: output.trim_end().to_string(),
+    })
+}
+
+/// `env -0` dumps that follow each marker; anything bash printed before the first
+/// marker (e.g. from a login profile) is skipped.
+fn parse_env_snapshots(raw: &[u8]) -> Vec<BTreeMap<String, String>> {
+    let raw = String::from_utf8_lossy(raw);
+    let mut snapshots: Vec<BTreeMap<String, String>> = Vec::new();
+    for entry in raw.split('\0') {
+        if entry == INIT_ENV_MARKER {
+            snapshots.push(BTreeMap::new());
+        } else if let (Some(current), Some((key, value))) =
+            (snapshots.last_mut(), entry.split_once('='))
+        {
+            current.insert(key.to_string(), value.to_string());
+        }
+    }
+    snapshots
+}
+
+/// Variables added or changed between two snapshots, plus the removed ones as `None`.
+fn env_diff(
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, String>,
+) -> BTreeMap<String, Option<String>> {
+    let changed = after
+        .iter()
+        .filter(|(key, value)| before.get(*key) != Some(*value))
+        .map(|(key, value)| (key.clone(), Some(value.clone())));
+    let removed = before
+        .keys()
+        .filter(|key| !after.contains_key(*key))
+        .map(|key| (key.clone(), None));
+    changed
+        .chain(removed)
+        .filter(|(key, _)| !INIT_ENV_IGNORED.contains(&key.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{capture_init_env, env_diff};
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    #[test]
+    fn env_diff_reports_changes_and_removals() {
+        let before = BTreeMap::from([
+            ("KEEP".to_string(), "1".to_string()),
+            ("CHANGE".to_string(), "old".to_string()),
+            ("DROP".to_string(), "1".to_string()),
+            ("OLDPWD".to_string(), "/".to_string()),
+        ]);
+        let after = BTreeMap::from([
+            ("KEEP".to_string(), "1".to_string()),
+            ("CHANGE".to_string(), "new".to_string()),
+            ("ADD".to_string(), "1".to_string()),
+        ]);
+        assert_eq!(
+            env_diff(&before, &after),
+            BTreeMap::from([
+                ("ADD".to_string(), Some("1".to_string())),
+                ("CHANGE".to_string(), Some("new".to_string())),
+                ("DROP".to_string(), None),
+            ])
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn init_script_exports_are_captured_as_env_diff() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-init-env-test-{nanos}"));
+        fs::create_dir_all(&ws).unwrap();
+        let script = ws.join("init.sh");
+        fs::write(
+            &script,
+            "echo activating\nexport GOLDBOT_TEST_INIT_VAR=\"from init\"\nLOCAL_ONLY=1\nunset HOME\n",
+        )
+        .unwrap();
+
+        let outcome = capture_init_env(&script, &ws).unwrap();
+        assert_eq!(outcome.status, Some(0));
+        assert_eq!(outcome.output, "activating");
+        // 只有脚本新导出和 unset 的变量；未 export 的 shell 变量和 bash 自己维护的变量不算
+        assert_eq!(
+            outcome.env,
+            BTreeMap::from([
+                (
+                    "GOLDBOT_TEST_INIT_VAR".to_string(),
+                    Some("from init".to_string())
+                ),
+                ("HOME".to_string(), None),
+            ])
+        );
+
+        // 失败的脚本只报告退出码，不带回环境
+        fs::write(&script, "export GOLDBOT_TEST_INIT_VAR=x\nreturn 3\n").unwrap();
+        let outcome = capture_init_env(&script, &ws).unwrap();
+        assert_eq!(outcome.status, Some(3));
+        assert!(outcome.env.is_empty());
+
+        let _ = fs::remove_dir_all(ws);
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::{Condvar, Mutex, OnceLock};
+
+/// 同时进行的后台子进程任务上限（`GOLDBOT_MAX_BG_JOBS` 可调整，默认 CPU 核数）。
+const ENV_MAX_BG_JOBS: &str = "GOLDBOT_MAX_BG_JOBS";
+
+/// 有界的后台任务槽位：超出上限的任务阻塞等待，直到有槽位释放。
+pub(crate) struct JobPool {
+    max: usize,
+    active: Mutex<usize>,
+    freed: Condvar,
+}
+
+/// 持有期间占用一个槽位，drop 时释放。
+pub(crate) struct JobPermit<'a> {
+    pool: &'a JobPool,
+}
+
+impl JobPool {
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            max: max.max(1),
+            active: Mutex::new(0),
+            freed: Condvar::new(),
+        }
+    }
+
+    pub(crate) fn acquire(&self) -> JobPermit<'_> {
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        while *active >= self.max {
+            active = self.freed.wait(active).unwrap_or_else(|e| e.into_inner());
+        }
+        *active += 1;
+        JobPermit { pool: self }
+    }
+}
+
+impl Drop for JobPermit<'_> {
+    fn drop(&mut self) {
+        let mut active = self.pool.active.lock().unwrap_or_else(|e| e.into_inner());
+        *active = active.saturating_sub(1);
+        self.pool.freed.notify_one();
+    }
+}
+
+/// 进程内共享的后台任务池；MCP 服务端的发现与调用在启动子进程前从这里取槽位，
+/// 避免配置了很多 `npx` 服务端时同时拉起一堆 node 进程。
+pub(crate) fn background_jobs() -> &'static JobPool {
+    static POOL: OnceLock<JobPool> = OnceLock::new();
+    POOL.get_or_init(|| JobPool::new(max_background_jobs()))
+}
+
+fn max_background_jobs() -> usize {
+    std::env::var(ENV_MAX_BG_JOBS)
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JobPool;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn pool_bounds_concurrent_jobs() {
+        let pool = JobPool::new(2);
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let finished = AtomicUsize::new(0);
+
+        std::thread::scope(|s| {
+            for _ in 0..6 {
+                s.spawn(|| {
+                    let _permit = pool.acquire();
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    finished.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        assert_eq!(finished.load(Ordering::SeqCst), 6);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+}
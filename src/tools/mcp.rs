@@ -11,16 +11,24 @@ mod util;
 #[allow(unused_imports)]
 pub use self::config::{
     create_mcp_assist_prompt_appendix, create_mcp_server, goldbot_home_dir, mcp_servers_file_path,
+    restore_mcp_config,
 };
+pub use self::discovery::verify_mcp_server;
 #[allow(unused_imports)]
-pub use self::types::{McpCallResult, McpRegistry, McpStartupStatus, McpToolSpec};
+pub use self::types::{
+    McpCallResult, McpProgress, McpRegistry, McpServerHealth, McpStartupStatus, McpToolSpec,
+};
+pub use self::util::parse_call_arguments;
 
 const ENV_MCP_SERVERS: &str = "GOLDBOT_MCP_SERVERS";
 const ENV_MCP_SERVERS_FILE: &str = "GOLDBOT_MCP_SERVERS_FILE";
 const ENV_MCP_DISCOVERY_TIMEOUT_MS: &str = "GOLDBOT_MCP_DISCOVERY_TIMEOUT_MS";
+const ENV_MCP_CALL_TIMEOUT_MS: &str = "GOLDBOT_MCP_CALL_TIMEOUT_MS";
 const ENV_MEMORY_DIR: &str = "GOLDBOT_MEMORY_DIR";
 const DEFAULT_MCP_SERVERS_FILENAME: &str = "mcp_servers.json";
 const DEFAULT_MCP_DISCOVERY_TIMEOUT_MS: u64 = 3000;
+/// 本地工具调用期间服务端持续静默多久算无响应；进度通知等任何消息都会重新计时。
+const DEFAULT_MCP_CALL_TIMEOUT_MS: u64 = 120_000;
 
 // Global MCP config files relative to $HOME. GoldBot's own file is checked first.
 const GLOBAL_MCP_CONFIG_FILES: &[&str] = &[
@@ -1,7 +1,7 @@
 use std::{
     collections::{BTreeMap, HashMap},
     fs,
-    path::PathBuf,
+    path::{Path, PathBuf},
     time::Duration,
 };
 
@@ -9,19 +9,19 @@ use serde::Deserialize;
 use serde_json::Value;
 
 use super::{
-    CREATE_MCP_ASSIST_PROMPT_APPENDIX_TEMPLATE, DEFAULT_MCP_DISCOVERY_TIMEOUT_MS,
-    DEFAULT_MCP_SERVERS_FILENAME, ENV_MCP_DISCOVERY_TIMEOUT_MS, ENV_MCP_SERVERS_FILE,
-    ENV_MEMORY_DIR,
+    CREATE_MCP_ASSIST_PROMPT_APPENDIX_TEMPLATE, DEFAULT_MCP_CALL_TIMEOUT_MS,
+    DEFAULT_MCP_DISCOVERY_TIMEOUT_MS, DEFAULT_MCP_SERVERS_FILENAME, ENV_MCP_CALL_TIMEOUT_MS,
+    ENV_MCP_DISCOVERY_TIMEOUT_MS, ENV_MCP_SERVERS_FILE, ENV_MEMORY_DIR,
 };
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(untagged)]
 pub(super) enum RawServerEntry {
     Disabled(bool),
     Config(RawServerConfig),
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub(super) struct RawServerConfig {
     #[serde(default = "default_server_type")]
     pub(super) r#type: String,
@@ -41,7 +41,7 @@ pub(super) struct RawServerConfig {
     pub(super) url: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(untagged)]
 pub(super) enum RawCommand {
     String(String),
@@ -228,9 +228,25 @@ pub fn create_mcp_assist_prompt_appendix() -> String {
 
 /// Add or overwrite a server entry in the MCP config file.
 /// `config` must be a JSON object with at minimum a `command` field.
-/// Returns the path of the config file that was written.
-pub fn create_mcp_server(name: &str, config: &serde_json::Value) -> anyhow::Result<PathBuf> {
-    use anyhow::{Context, bail};
+/// Returns the config file path and the normalized server spec; with `dry_run` nothing is
+/// written, so the caller can preview exactly what would be saved.
+pub fn create_mcp_server(
+    name: &str,
+    config: &serde_json::Value,
+    dry_run: bool,
+) -> anyhow::Result<(PathBuf, serde_json::Value)> {
+    let path = mcp_servers_file_path();
+    let spec = write_mcp_server(&path, name, config, dry_run)?;
+    Ok((path, spec))
+}
+
+pub(super) fn write_mcp_server(
+    path: &Path,
+    name: &str,
+    config: &serde_json::Value,
+    dry_run: bool,
+) -> anyhow::Result<serde_json::Value> {
+    use anyhow::bail;
 
     if name.trim().is_empty() {
         bail!("MCP server name must not be empty");
@@ -242,23 +258,6 @@ pub fn create_mcp_server(name: &str, config: &serde_json::Value) -> anyhow::Resu
         bail!("MCP server config requires a `command` field");
     }
 
-    let path = mcp_servers_file_path();
-
-    // Read existing config, or start fresh.
-    let mut root: serde_json::Map<String, serde_json::Value> = match fs::read_to_string(&path)
-        .ok()
-        .filter(|s| !s.trim().is_empty())
-    {
-        Some(text) => match serde_json::from_str::<serde_json::Value>(&text)
-            .ok()
-            .and_then(|v| v.as_object().cloned())
-        {
-            Some(obj) => obj,
-            None => serde_json::Map::new(),
-        },
-        None => serde_json::Map::new(),
-    };
-
     // Normalise into canonical format: command array, explicit type + enabled.
     let mut spec = config.as_object().cloned().unwrap_or_default();
     spec.remove("name");
@@ -297,27 +296,53 @@ pub fn create_mcp_server(name: &str, config: &serde_json::Value) -> anyhow::Resu
     }
 
     let spec_value = serde_json::Value::Object(spec);
+    if dry_run {
+        return Ok(spec_value);
+    }
+
+    // Read existing config, or start fresh.
+    let mut root: serde_json::Map<String, serde_json::Value> = match fs::read_to_string(path)
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+    {
+        Some(text) => match serde_json::from_str::<serde_json::Value>(&text)
+            .ok()
+            .and_then(|v| v.as_object().cloned())
+        {
+            Some(obj) => obj,
+            None => serde_json::Map::new(),
+        },
+        None => serde_json::Map::new(),
+    };
 
     // Insert server at the right level (handle mcpServers/mcp wrappers).
     if let Some(inner) = root.get_mut("mcpServers").and_then(|v| v.as_object_mut()) {
-        inner.insert(name.to_string(), spec_value);
+        inner.insert(name.to_string(), spec_value.clone());
     } else if let Some(inner) = root.get_mut("mcp").and_then(|v| v.as_object_mut()) {
-        inner.insert(name.to_string(), spec_value);
+        inner.insert(name.to_string(), spec_value.clone());
     } else {
-        root.insert(name.to_string(), spec_value);
+        root.insert(name.to_string(), spec_value.clone());
     }
 
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("failed to create config dir `{}`", parent.display()))?;
-    }
-    fs::write(
-        &path,
-        serde_json::to_string_pretty(&serde_json::Value::Object(root))?,
-    )
-    .with_context(|| format!("failed to write MCP config `{}`", path.display()))?;
+    crate::config::write_config_file(
+        path,
+        &serde_json::to_string_pretty(&serde_json::Value::Object(root))?,
+    )?;
+
+    Ok(spec_value)
+}
 
-    Ok(path)
+/// Undo a `create_mcp_server` write: restore the previous file content, or remove the file
+/// if it did not exist before.
+pub fn restore_mcp_config(path: &Path, previous: Option<&str>) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    match previous {
+        Some(text) => crate::config::write_config_file(path, text)
+            .with_context(|| format!("failed to restore MCP config `{}`", path.display())),
+        None => fs::remove_file(path)
+            .with_context(|| format!("failed to remove MCP config `{}`", path.display())),
+    }
 }
 
 fn resolve_mcp_servers_file_path() -> PathBuf {
@@ -340,6 +365,15 @@ pub(super) fn mcp_discovery_timeout() -> Duration {
     Duration::from_millis(ms)
 }
 
+pub(super) fn mcp_call_timeout() -> Duration {
+    let ms = std::env::var(ENV_MCP_CALL_TIMEOUT_MS)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_MCP_CALL_TIMEOUT_MS);
+    Duration::from_millis(ms)
+}
+
 fn default_memory_base_dir() -> PathBuf {
     if let Some(dir) = std::env::var_os(ENV_MEMORY_DIR) {
         let p = PathBuf::from(dir);
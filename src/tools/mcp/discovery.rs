@@ -1,10 +1,11 @@
-use std::{sync::mpsc, thread, time::Duration};
+use std::{path::PathBuf, sync::mpsc, thread, time::Duration};
 
 use anyhow::{Context, Result, bail};
 use serde_json::{Value, json};
 
 use super::{
     ENV_MCP_DISCOVERY_TIMEOUT_MS,
+    config::{RawServerConfig, extract_local_command_and_args, mcp_discovery_timeout},
     protocol::{RemoteMcpSession, StdioMcpSession, extract_jsonrpc_error},
     types::{DiscoveredTool, LocalServerSpec, RemoteServerSpec, ServerSpec},
 };
@@ -46,26 +47,38 @@ pub(super) fn parse_discovered_tools_response(response: &Value) -> Result<Vec<Di
             .get("inputSchema")
             .cloned()
             .unwrap_or_else(|| json!({"type":"object"}));
-        let read_only_hint = item
-            .pointer("/annotations/readOnlyHint")
-            .and_then(Value::as_bool)
-            .or_else(|| {
-                item.pointer("/annotations/read_only_hint")
-                    .and_then(Value::as_bool)
-            })
-            .unwrap_or(false);
+        let annotations = item.get("annotations");
+        let title = annotations
+            .and_then(|a| a.get("title"))
+            .or_else(|| item.get("title"))
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string);
 
         discovered.push(DiscoveredTool {
             tool_name: name.to_string(),
             description,
             input_schema,
-            read_only_hint,
+            title,
+            read_only_hint: annotation_hint(annotations, "readOnlyHint", "read_only_hint"),
+            destructive_hint: annotation_hint(annotations, "destructiveHint", "destructive_hint"),
+            idempotent_hint: annotation_hint(annotations, "idempotentHint", "idempotent_hint"),
         });
     }
 
     Ok(discovered)
 }
 
+/// Boolean tool annotation under its camelCase name or the snake_case spelling some servers use;
+/// absent hints count as `false`.
+fn annotation_hint(annotations: Option<&Value>, name: &str, snake: &str) -> bool {
+    annotations
+        .and_then(|a| a.get(name).or_else(|| a.get(snake)))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
 pub(super) fn list_tools_once(spec: &LocalServerSpec) -> Result<Vec<DiscoveredTool>> {
     let mut session = StdioMcpSession::spawn(spec)?;
     session.initialize()?;
@@ -78,6 +91,9 @@ pub(super) fn list_tools_with_timeout(
     spec: &LocalServerSpec,
     timeout: Duration,
 ) -> Result<Vec<DiscoveredTool>> {
+    // 先排队拿到后台任务槽位再计时，排队时间不计入发现超时；
+    // 超时后即释放槽位，挂起的服务端不会一直占着名额。
+    let _permit = crate::tools::jobs::background_jobs().acquire();
     let spec = spec.clone();
     let (tx, rx) = mpsc::channel();
 
@@ -109,3 +125,33 @@ pub(super) fn list_tools_remote(
     let response = session.request("tools/list", json!({}))?;
     parse_discovered_tools_response(&response)
 }
+
+/// Lower bound for the create-time check: `npx -y` / `uvx` servers download their package
+/// on first start, which routinely takes longer than the discovery timeout.
+const MCP_VERIFY_MIN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Spawn a freshly created local server and run `initialize` + `tools/list` once, so a bad
+/// command or missing package shows up right away instead of after a restart.
+/// Returns the number of tools the server advertises. Blocking; run it off the UI thread.
+pub fn verify_mcp_server(spec: &Value) -> Result<usize> {
+    verify_local_server(spec, mcp_discovery_timeout().max(MCP_VERIFY_MIN_TIMEOUT))
+}
+
+pub(super) fn verify_local_server(spec: &Value, timeout: Duration) -> Result<usize> {
+    let cfg: RawServerConfig =
+        serde_json::from_value(spec.clone()).context("invalid MCP server spec")?;
+    let (command, args) =
+        extract_local_command_and_args(&cfg).context("MCP server spec has no valid command")?;
+    let mut env = cfg.env.clone();
+    for (k, v) in &cfg.headers {
+        env.entry(k.clone()).or_insert_with(|| v.clone());
+    }
+    let spec = LocalServerSpec {
+        command,
+        args,
+        env,
+        cwd: cfg.cwd.map(PathBuf::from),
+        transport: cfg.transport,
+    };
+    Ok(list_tools_with_timeout(&spec, timeout)?.len())
+}
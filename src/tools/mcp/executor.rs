@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{path::Path, time::Duration};
 
 use anyhow::{Context, Result};
 use serde_json::{Value, json};
@@ -6,15 +6,21 @@ use serde_json::{Value, json};
 use super::{
     MAX_OUTPUT_CHARS,
     protocol::{RemoteMcpSession, StdioMcpSession, extract_jsonrpc_error},
-    types::{LocalServerSpec, McpCallResult, RemoteServerSpec},
-    util::truncate_chars,
+    types::{LocalServerSpec, McpCallResult, McpProgress, McpToolSpec, RemoteServerSpec},
+    util::{summarize_input_schema, truncate_chars, truncate_tool_output},
 };
+use crate::tools::artifacts::{SPILL_NOTE_MAX_CHARS, artifacts_dir, spill_to_artifact};
 
-pub(super) fn parse_tool_call_response(response: &Value) -> Result<McpCallResult> {
+/// 超出 `MAX_OUTPUT_CHARS` 的结果完整写到 `artifacts` 目录下的文件，输出里是截断预览加文件路径。
+pub(super) fn parse_tool_call_response(
+    response: &Value,
+    artifacts: &Path,
+) -> Result<McpCallResult> {
     if let Some(msg) = extract_jsonrpc_error(response) {
         return Ok(McpCallResult {
             exit_code: 1,
             output: truncate_chars(&format!("MCP tools/call error: {msg}"), MAX_OUTPUT_CHARS),
+            tools_changed: false,
         });
     }
 
@@ -45,14 +51,11 @@ pub(super) fn parse_tool_call_response(response: &Value) -> Result<McpCallResult
         }
     }
 
-    if let Some(structured) = result.get("structuredContent") {
-        sections.push(format!(
-            "structuredContent:\n{}",
-            serde_json::to_string_pretty(structured).unwrap_or_else(|_| structured.to_string())
-        ));
-    }
+    let structured = result.get("structuredContent").map(|structured| {
+        serde_json::to_string_pretty(structured).unwrap_or_else(|_| structured.to_string())
+    });
 
-    if sections.is_empty() {
+    if sections.is_empty() && structured.is_none() {
         sections.push(
             serde_json::to_string_pretty(result)
                 .unwrap_or_else(|_| result.to_string())
@@ -61,34 +64,142 @@ pub(super) fn parse_tool_call_response(response: &Value) -> Result<McpCallResult
         );
     }
 
-    let mut output = sections.join("\n");
+    let full = fit_sections(&sections, structured.as_deref(), usize::MAX);
+    let mut output = if full.chars().count() > MAX_OUTPUT_CHARS {
+        let preview = fit_sections(
+            &sections,
+            structured.as_deref(),
+            MAX_OUTPUT_CHARS - SPILL_NOTE_MAX_CHARS,
+        );
+        spill_to_artifact(&full, artifacts, preview)
+    } else {
+        full
+    };
     if output.trim().is_empty() {
         output = "(no output)".to_string();
     }
-    output = truncate_chars(&output, MAX_OUTPUT_CHARS);
 
     Ok(McpCallResult {
         exit_code: if is_error { 1 } else { 0 },
         output,
+        tools_changed: false,
     })
 }
 
+/// Joins the content sections (plus `structuredContent`, labelled) within `max_chars`.  When
+/// over budget, each section gets a share of what is left, smallest first, so short sections
+/// stay intact and large JSON/text blobs are truncated structure-aware.
+fn fit_sections(sections: &[String], structured: Option<&str>, max_chars: usize) -> String {
+    const STRUCTURED_LABEL: &str = "structuredContent:\n";
+    let mut parts: Vec<&str> = sections.iter().map(String::as_str).collect();
+    parts.extend(structured);
+    let label_chars = if structured.is_some() {
+        STRUCTURED_LABEL.len()
+    } else {
+        0
+    };
+    let total: usize = parts.iter().map(|p| p.chars().count()).sum::<usize>()
+        + parts.len().saturating_sub(1)
+        + label_chars;
+    let mut fitted: Vec<String> = parts.iter().map(|p| p.to_string()).collect();
+    if total > max_chars {
+        let mut remaining = max_chars.saturating_sub(parts.len().saturating_sub(1) + label_chars);
+        let mut order: Vec<usize> = (0..parts.len()).collect();
+        order.sort_by_key(|&i| parts[i].chars().count());
+        for (done, &i) in order.iter().enumerate() {
+            let share = remaining / (parts.len() - done);
+            fitted[i] = truncate_tool_output(parts[i], share);
+            remaining = remaining.saturating_sub(fitted[i].chars().count());
+        }
+    }
+    if structured.is_some()
+        && let Some(last) = fitted.last_mut()
+    {
+        last.insert_str(0, STRUCTURED_LABEL);
+    }
+    fitted.join("\n")
+}
+
+/// Tool result returned when arguments do not match the schema: the mismatch
+/// plus the expected schema summary, so the model can fix its next attempt.
+pub(super) fn argument_error_result(
+    tool: &McpToolSpec,
+    mismatches: &[String],
+    server_error: Option<&str>,
+) -> McpCallResult {
+    let mut lines = vec![format!(
+        "MCP arguments for `{}` do not match the tool schema:",
+        tool.action_name
+    )];
+    lines.extend(mismatches.iter().map(|m| format!("- {m}")));
+    if let Some(err) = server_error {
+        lines.push(err.trim().to_string());
+    }
+    lines.push(format!(
+        "Expected arguments (* = required): {}",
+        summarize_input_schema(&tool.input_schema)
+    ));
+    McpCallResult {
+        exit_code: 1,
+        output: truncate_chars(&lines.join("\n"), MAX_OUTPUT_CHARS),
+        tools_changed: false,
+    }
+}
+
 pub(super) fn call_tool_once(
     spec: &LocalServerSpec,
     tool_name: &str,
     arguments: &Value,
+    on_progress: &mut dyn FnMut(McpProgress),
 ) -> Result<McpCallResult> {
+    let _permit = crate::tools::jobs::background_jobs().acquire();
     let mut session = StdioMcpSession::spawn(spec)?;
     session.initialize()?;
 
-    let response = session.request(
+    let response = session.request_with_progress(
         "tools/call",
         json!({
             "name": tool_name,
             "arguments": arguments
         }),
+        on_progress,
     )?;
-    parse_tool_call_response(&response)
+    let mut result = parse_tool_call_response(&response, &artifacts_dir())?;
+    result.tools_changed = session.tools_list_changed();
+    Ok(result)
+}
+
+/// 同一本地服务端的多个调用共用一个会话，经 `request_many` 流水线发送。
+pub(super) fn call_tools_many(
+    spec: &LocalServerSpec,
+    calls: &[(&str, &Value)],
+) -> Result<Vec<McpCallResult>> {
+    let _permit = crate::tools::jobs::background_jobs().acquire();
+    let mut session = StdioMcpSession::spawn(spec)?;
+    session.initialize()?;
+
+    let requests = calls
+        .iter()
+        .map(|(tool_name, arguments)| {
+            (
+                "tools/call",
+                json!({
+                    "name": tool_name,
+                    "arguments": arguments
+                }),
+            )
+        })
+        .collect();
+    let responses = session.request_many(requests)?;
+    let tools_changed = session.tools_list_changed();
+    responses
+        .iter()
+        .map(|response| {
+            let mut result = parse_tool_call_response(response, &artifacts_dir())?;
+            result.tools_changed = tools_changed;
+            Ok(result)
+        })
+        .collect()
 }
 
 pub(super) fn call_tool_remote(
@@ -114,5 +225,5 @@ pub(super) fn call_tool_remote(
         }),
     )?;
 
-    parse_tool_call_response(&response)
+    parse_tool_call_response(&response, &artifacts_dir())
 }
@@ -2,6 +2,8 @@ use std::{
     collections::HashMap,
     io::{BufRead, BufReader, Read, Write},
     process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
     time::Duration,
 };
 
@@ -9,16 +11,31 @@ use anyhow::{Context, Result, bail};
 use serde_json::{Value, json};
 
 use super::{
-    MCP_PROTOCOL_VERSION,
-    types::{LocalServerSpec, RemoteServerSpec},
+    ENV_MCP_CALL_TIMEOUT_MS, MCP_PROTOCOL_VERSION,
+    config::mcp_call_timeout,
+    types::{LocalServerSpec, McpProgress, RemoteServerSpec},
 };
 
 pub(super) struct StdioMcpSession {
     child: Child,
     stdin: ChildStdin,
-    stdout: BufReader<ChildStdout>,
+    /// 后台线程逐条解析 stdout，读取时才能设超时。
+    incoming: Receiver<Result<Value>>,
+    /// 等待期间连续这么久没收到任何消息就放弃，避免服务端漏回某个 id 时一直挂起。
+    response_timeout: Duration,
     next_id: u64,
     wire_format: StdioWireFormat,
+    /// 服务端在 initialize 中声明了 `capabilities.tools.listChanged`。
+    list_changed_supported: bool,
+    /// 会话期间收到过 `notifications/tools/list_changed`。
+    tools_list_changed: bool,
+}
+
+/// 等待响应期间关心的服务端通知。
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum McpNotification {
+    Progress(McpProgress),
+    ToolsListChanged,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -52,13 +69,27 @@ impl StdioMcpSession {
 
         let stdin = child.stdin.take().context("MCP server has no stdin")?;
         let stdout = child.stdout.take().context("MCP server has no stdout")?;
+        let (tx, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            let mut stdout = BufReader::new(stdout);
+            loop {
+                let message = read_message(&mut stdout, wire_format);
+                let closed = message.is_err();
+                if tx.send(message).is_err() || closed {
+                    break;
+                }
+            }
+        });
 
         Ok(Self {
             child,
             stdin,
-            stdout: BufReader::new(stdout),
+            incoming,
+            response_timeout: mcp_call_timeout(),
             next_id: 1,
             wire_format,
+            list_changed_supported: false,
+            tools_list_changed: false,
         })
     }
 
@@ -78,6 +109,10 @@ impl StdioMcpSession {
         if let Some(msg) = extract_jsonrpc_error(&response) {
             bail!("initialize error: {msg}");
         }
+        self.list_changed_supported = response
+            .pointer("/result/capabilities/tools/listChanged")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
 
         self.notify("notifications/initialized", json!({}))
     }
@@ -91,9 +126,54 @@ impl StdioMcpSession {
     }
 
     pub(super) fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        self.request_many(vec![(method, params)])?
+            .pop()
+            .context("missing JSON-RPC response")
+    }
+
+    /// 流水线发送多个请求：先全部写出再读取，响应可按任意顺序到达，按 id 对回请求顺序。
+    /// N 个互不依赖的调用只需约一次往返的延迟。
+    pub(super) fn request_many(&mut self, calls: Vec<(&str, Value)>) -> Result<Vec<Value>> {
+        let mut ids = Vec::with_capacity(calls.len());
+        for (method, params) in calls {
+            let id = self.next_id;
+            self.next_id += 1;
+            self.send(&json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": method,
+                "params": params
+            }))?;
+            ids.push(id);
+        }
+
+        let mut list_changed = false;
+        let responses = await_responses(&ids, || self.read(), &mut |notification| {
+            if notification == McpNotification::ToolsListChanged {
+                list_changed = true;
+            }
+        });
+        self.tools_list_changed |= list_changed;
+        responses
+    }
+
+    /// 发送请求并等待响应；期间收到的 `notifications/progress` 交给 `on_progress`。
+    /// 请求以自身 id 作为 `progressToken`，其它通知一律忽略。
+    pub(super) fn request_with_progress(
+        &mut self,
+        method: &str,
+        mut params: Value,
+        on_progress: &mut dyn FnMut(McpProgress),
+    ) -> Result<Value> {
         let id = self.next_id;
         self.next_id += 1;
 
+        if let Some(obj) = params.as_object_mut() {
+            let meta = obj.entry("_meta").or_insert_with(|| json!({}));
+            if let Some(meta) = meta.as_object_mut() {
+                meta.insert("progressToken".to_string(), json!(id));
+            }
+        }
         self.send(&json!({
             "jsonrpc": "2.0",
             "id": id,
@@ -101,12 +181,18 @@ impl StdioMcpSession {
             "params": params
         }))?;
 
-        loop {
-            let message = self.read()?;
-            if message.get("id").and_then(Value::as_u64) == Some(id) {
-                return Ok(message);
-            }
-        }
+        let mut list_changed = false;
+        let response = await_response(id, || self.read(), &mut |notification| match notification {
+            McpNotification::Progress(p) => on_progress(p),
+            McpNotification::ToolsListChanged => list_changed = true,
+        });
+        self.tools_list_changed |= list_changed;
+        response
+    }
+
+    /// 服务端声明支持 `listChanged` 且会话期间通知过工具列表变化。
+    pub(super) fn tools_list_changed(&self) -> bool {
+        self.list_changed_supported && self.tools_list_changed
     }
 
     fn send(&mut self, message: &Value) -> Result<()> {
@@ -127,61 +213,161 @@ impl StdioMcpSession {
     }
 
     fn read(&mut self) -> Result<Value> {
-        match self.wire_format {
-            StdioWireFormat::Framed => self.read_framed(),
-            StdioWireFormat::LineDelimited => self.read_line_delimited(),
+        match self.incoming.recv_timeout(self.response_timeout) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => bail!(
+                "MCP server sent nothing for {}ms while a response was pending (raise {})",
+                self.response_timeout.as_millis(),
+                ENV_MCP_CALL_TIMEOUT_MS
+            ),
+            Err(RecvTimeoutError::Disconnected) => bail!("MCP server closed output stream"),
         }
     }
 
-    fn read_framed(&mut self) -> Result<Value> {
-        let mut content_length: Option<usize> = None;
-        let mut line = String::new();
+    #[cfg(test)]
+    pub(super) fn with_response_timeout(mut self, timeout: Duration) -> Self {
+        self.response_timeout = timeout;
+        self
+    }
+}
+
+fn read_message(stdout: &mut BufReader<ChildStdout>, format: StdioWireFormat) -> Result<Value> {
+    match format {
+        StdioWireFormat::Framed => read_framed(stdout),
+        StdioWireFormat::LineDelimited => read_line_delimited(stdout),
+    }
+}
 
-        loop {
-            line.clear();
-            let n = self.stdout.read_line(&mut line)?;
-            if n == 0 {
-                bail!("MCP server closed output stream");
-            }
-            let trimmed = line.trim_end_matches(['\r', '\n']);
-            if trimmed.is_empty() {
-                break;
-            }
-            if let Some(rest) = trimmed.strip_prefix("Content-Length:") {
-                let len = rest
-                    .trim()
-                    .parse::<usize>()
-                    .context("invalid Content-Length header")?;
-                content_length = Some(len);
-            }
+fn read_framed(stdout: &mut BufReader<ChildStdout>) -> Result<Value> {
+    let mut content_length: Option<usize> = None;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let n = stdout.read_line(&mut line)?;
+        if n == 0 {
+            bail!("MCP server closed output stream");
+        }
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+        if trimmed.is_empty() {
+            break;
         }
+        if let Some(rest) = trimmed.strip_prefix("Content-Length:") {
+            let len = rest
+                .trim()
+                .parse::<usize>()
+                .context("invalid Content-Length header")?;
+            content_length = Some(len);
+        }
+    }
 
-        let len = content_length.context("missing Content-Length header")?;
-        let mut body = vec![0u8; len];
-        self.stdout.read_exact(&mut body)?;
-        serde_json::from_slice::<Value>(&body).context("invalid JSON-RPC payload")
+    let len = content_length.context("missing Content-Length header")?;
+    let mut body = vec![0u8; len];
+    stdout.read_exact(&mut body)?;
+    serde_json::from_slice::<Value>(&body).context("invalid JSON-RPC payload")
+}
+
+fn read_line_delimited(stdout: &mut BufReader<ChildStdout>) -> Result<Value> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let n = stdout.read_line(&mut line)?;
+        if n == 0 {
+            bail!("MCP server closed output stream");
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Ok(v) = serde_json::from_str::<Value>(trimmed) {
+            return Ok(v);
+        }
+        if let Ok(v) = serde_json::from_str::<Value>(&line) {
+            return Ok(v);
+        }
     }
+}
 
-    fn read_line_delimited(&mut self) -> Result<Value> {
-        let mut line = String::new();
-        loop {
-            line.clear();
-            let n = self.stdout.read_line(&mut line)?;
-            if n == 0 {
-                bail!("MCP server closed output stream");
-            }
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-            if let Ok(v) = serde_json::from_str::<Value>(trimmed) {
-                return Ok(v);
+/// 读取消息直到出现 `id` 对应的响应；期间的进度与工具列表变化通知转交回调。
+pub(super) fn await_response(
+    id: u64,
+    mut read: impl FnMut() -> Result<Value>,
+    on_notification: &mut dyn FnMut(McpNotification),
+) -> Result<Value> {
+    loop {
+        let message = read()?;
+        if message.get("id").and_then(Value::as_u64) == Some(id) {
+            return Ok(message);
+        }
+        if let Some(notification) = parse_notification(&message, id) {
+            on_notification(notification);
+        }
+    }
+}
+
+/// 读取消息直到 `ids` 全部收到响应，按 `ids` 的顺序返回；期间的通知转交回调。
+pub(super) fn await_responses(
+    ids: &[u64],
+    mut read: impl FnMut() -> Result<Value>,
+    on_notification: &mut dyn FnMut(McpNotification),
+) -> Result<Vec<Value>> {
+    let mut pending: HashMap<u64, Option<Value>> = ids.iter().map(|id| (*id, None)).collect();
+    let mut remaining = pending.len();
+    while remaining > 0 {
+        let message = read()?;
+        // 带 method 的是服务端发来的请求或通知，不是响应
+        let response_id = message
+            .get("id")
+            .and_then(Value::as_u64)
+            .filter(|_| message.get("method").is_none());
+        match response_id.and_then(|id| pending.get_mut(&id)) {
+            Some(slot @ None) => {
+                *slot = Some(message);
+                remaining -= 1;
             }
-            if let Ok(v) = serde_json::from_str::<Value>(&line) {
-                return Ok(v);
+            Some(Some(_)) => {}
+            None => {
+                if let Some(notification) = parse_notification(&message, 0) {
+                    on_notification(notification);
+                }
             }
         }
     }
+    Ok(ids
+        .iter()
+        .filter_map(|id| pending.get_mut(id).and_then(Option::take))
+        .collect())
+}
+
+fn parse_notification(message: &Value, token: u64) -> Option<McpNotification> {
+    match message.get("method").and_then(Value::as_str)? {
+        "notifications/progress" => {
+            parse_progress_notification(message, token).map(McpNotification::Progress)
+        }
+        "notifications/tools/list_changed" => Some(McpNotification::ToolsListChanged),
+        _ => None,
+    }
+}
+
+fn parse_progress_notification(message: &Value, token: u64) -> Option<McpProgress> {
+    let params = message.get("params")?;
+    // progressToken 可能被服务端回写为数字或字符串
+    let matches = match params.get("progressToken")? {
+        Value::Number(n) => n.as_u64() == Some(token),
+        Value::String(s) => s.parse::<u64>().ok() == Some(token),
+        _ => false,
+    };
+    if !matches {
+        return None;
+    }
+    Some(McpProgress {
+        progress: params.get("progress").and_then(Value::as_f64)?,
+        total: params.get("total").and_then(Value::as_f64),
+        message: params
+            .get("message")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+    })
 }
 
 impl Drop for StdioMcpSession {
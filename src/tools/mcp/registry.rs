@@ -1,13 +1,13 @@
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     fs,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::mpsc,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use anyhow::{Result, bail};
+use anyhow::{Result, anyhow, bail};
 use serde_json::Value;
 
 use super::{
@@ -18,17 +18,58 @@ use super::{
         parse_server_entries, parse_toml_mcp_servers, walk_to_git_root_mcp,
     },
     discovery::list_tools_for_server,
-    executor::{call_tool_once, call_tool_remote},
+    executor::{argument_error_result, call_tool_once, call_tool_remote, call_tools_many},
     types::{
-        DiscoveredTool, LocalServerSpec, McpCallResult, McpRegistry, McpStartupStatus, McpToolSpec,
-        RemoteServerSpec, ServerSpec,
+        DiscoveredTool, LocalServerSpec, McpCallResult, McpProgress, McpRegistry, McpServerHealth,
+        McpStartupStatus, McpToolSpec, RemoteServerSpec, ServerSpec,
     },
     util::{
-        fallback_if_empty, normalize_action_name_for_lookup, normalize_arguments_for_tool,
-        resolve_env_var_refs, summarize_input_schema, truncate_chars, unique_action_name,
+        argument_mismatches, fallback_if_empty, is_invalid_params_error,
+        normalize_action_name_for_lookup, normalize_arguments_for_tool, resolve_env_var_refs,
+        summarize_input_schema, truncate_chars, unique_action_name,
     },
 };
 
+/// 校验过的调用：要么已有结果（命中缓存），要么可以发给服务端。
+/// 与 schema 不符的参数只记下来，由服务端决定是否接受。
+enum PreparedCall<'a> {
+    Done(McpCallResult),
+    Ready {
+        tool: &'a McpToolSpec,
+        arguments: Value,
+        cache_key: Option<String>,
+        mismatches: Vec<String>,
+    },
+}
+
+/// Merges one discovered config file into `merged`, keeping the first definition of each server.
+/// A later file that defines the same server differently is reported so the shadowing is visible;
+/// identical duplicates are silent.
+pub(super) fn merge_config_source(
+    merged: &mut BTreeMap<String, (RawServerEntry, PathBuf)>,
+    entries: BTreeMap<String, RawServerEntry>,
+    source: &Path,
+    warnings: &mut Vec<String>,
+) {
+    for (name, entry) in entries {
+        match merged.get(&name) {
+            Some((kept, kept_source)) => {
+                if *kept != entry {
+                    warnings.push(format!(
+                        "MCP server `{name}` is defined differently in `{}` and `{}`; using `{}`.",
+                        kept_source.display(),
+                        source.display(),
+                        kept_source.display()
+                    ));
+                }
+            }
+            None => {
+                merged.insert(name, (entry, source.to_path_buf()));
+            }
+        }
+    }
+}
+
 impl McpRegistry {
     pub fn from_env() -> (Self, Vec<String>) {
         let mut warnings = Vec::new();
@@ -77,7 +118,7 @@ impl McpRegistry {
         }
 
         // Multi-file discovery: merge all found entries, first-server-name-wins.
-        let mut merged: BTreeMap<String, RawServerEntry> = BTreeMap::new();
+        let mut merged: BTreeMap<String, (RawServerEntry, PathBuf)> = BTreeMap::new();
 
         // Project-local: walk from cwd up to git root.
         if let Ok(cwd) = std::env::current_dir() {
@@ -88,9 +129,7 @@ impl McpRegistry {
                         if !text.trim().is_empty() {
                             match parse_server_entries(&text) {
                                 Ok(entries) => {
-                                    for (k, v) in entries {
-                                        merged.entry(k).or_insert(v);
-                                    }
+                                    merge_config_source(&mut merged, entries, &path, &mut warnings)
                                 }
                                 Err(e) => warnings.push(format!(
                                     "MCP config `{}` is invalid: {e}. Skipped.",
@@ -111,9 +150,7 @@ impl McpRegistry {
                     if !text.trim().is_empty() {
                         match parse_server_entries(&text) {
                             Ok(entries) => {
-                                for (k, v) in entries {
-                                    merged.entry(k).or_insert(v);
-                                }
+                                merge_config_source(&mut merged, entries, &path, &mut warnings)
                             }
                             Err(e) => warnings.push(format!(
                                 "MCP config `{}` is invalid: {e}. Skipped.",
@@ -129,9 +166,7 @@ impl McpRegistry {
                     if !text.trim().is_empty() {
                         match parse_toml_mcp_servers(&text) {
                             Ok(entries) => {
-                                for (k, v) in entries {
-                                    merged.entry(k).or_insert(v);
-                                }
+                                merge_config_source(&mut merged, entries, &path, &mut warnings)
                             }
                             Err(e) => warnings.push(format!(
                                 "MCP config `{}` is invalid: {e}. Skipped.",
@@ -148,6 +183,10 @@ impl McpRegistry {
         }
 
         let mut registry = Self::default();
+        let merged = merged
+            .into_iter()
+            .map(|(name, (entry, _))| (name, entry))
+            .collect();
         Self::populate_from_entries(&mut registry, merged, &mut warnings);
         (registry, warnings)
     }
@@ -226,7 +265,7 @@ impl McpRegistry {
         self.servers.retain(|k, _| !k.starts_with("builtin_"));
         self.tools
             .retain(|_, v| !v.server_name.starts_with("builtin_"));
-        self.failed.retain(|k| !k.starts_with("builtin_"));
+        self.failed.retain(|k, _| !k.starts_with("builtin_"));
 
         match backend_label {
             "MiniMax" => {
@@ -303,22 +342,50 @@ impl McpRegistry {
         let ok = self
             .servers
             .keys()
-            .filter(|name| !self.failed.contains(*name))
+            .filter(|name| !self.failed.contains_key(*name))
             .map(|name| (name.clone(), *tool_counts.get(name.as_str()).unwrap_or(&0)))
             .collect();
         McpStartupStatus {
             ok,
-            failed: self.failed.clone(),
+            failed: self.failed.keys().cloned().collect(),
+        }
+    }
+
+    /// Per-server reachability, tool count, discovery error and last `tools/call` latency.
+    pub fn server_health(&self) -> Vec<McpServerHealth> {
+        let latencies = self
+            .last_latency
+            .lock()
+            .map(|l| l.clone())
+            .unwrap_or_default();
+        self.servers
+            .keys()
+            .map(|name| McpServerHealth {
+                name: name.clone(),
+                tool_count: self
+                    .tools
+                    .values()
+                    .filter(|tool| &tool.server_name == name)
+                    .count(),
+                failure: self.failed.get(name).cloned(),
+                last_latency: latencies.get(name).copied(),
+            })
+            .collect()
+    }
+
+    pub(super) fn record_latency(&self, server_name: &str, latency: Duration) {
+        if let Ok(mut latencies) = self.last_latency.lock() {
+            latencies.insert(server_name.to_string(), latency);
         }
     }
 
-    pub fn augment_system_prompt(&self, base_prompt: &str) -> String {
+    /// System-prompt section listing discovered MCP tools; empty when there are none.
+    pub fn tools_prompt_section(&self) -> String {
         if self.tools.is_empty() {
-            return base_prompt.to_string();
+            return String::new();
         }
 
         let mut out = String::new();
-        out.push_str(base_prompt);
         out.push_str(
             "\n\n## Available MCP tools\n\
              Use the MCP call format above. `<tool>` must be exactly one name from this list;\
@@ -329,11 +396,18 @@ impl McpRegistry {
         for tool in self.tools.values().take(MAX_PROMPT_TOOLS) {
             let desc = truncate_chars(&tool.description, MAX_DESC_CHARS);
             let args = summarize_input_schema(&tool.input_schema);
-            let ro = if tool.read_only_hint {
+            let ro = if tool.destructive_hint {
+                "destructive"
+            } else if tool.read_only_hint {
                 "read-only"
             } else {
                 "read/write"
             };
+            let title = tool
+                .title
+                .as_deref()
+                .map(|t| format!(" \"{t}\""))
+                .unwrap_or_default();
             let extra = if tool.server_name == "context7" && tool.tool_name == "resolve-library-id"
             {
                 " | note: provide BOTH `libraryName` and `query`"
@@ -341,7 +415,7 @@ impl McpRegistry {
                 ""
             };
             out.push_str(&format!(
-                "- {} => server=`{}` tool=`{}` ({ro}), args: {}, desc: {}{}\n",
+                "- {}{title} => server=`{}` tool=`{}` ({ro}), args: {}, desc: {}{}\n",
                 tool.action_name,
                 tool.server_name,
                 tool.tool_name,
@@ -361,7 +435,122 @@ impl McpRegistry {
         out
     }
 
-    pub fn execute_tool(&self, action_name: &str, arguments: &Value) -> Result<McpCallResult> {
+    /// 调用 MCP 工具；本地 stdio 服务端的进度通知通过 `on_progress` 实时回调。
+    pub fn execute_tool(
+        &self,
+        action_name: &str,
+        arguments: &Value,
+        on_progress: &mut dyn FnMut(McpProgress),
+    ) -> Result<McpCallResult> {
+        let (tool, arguments, cache_key, mismatches) =
+            match self.prepare_call(action_name, arguments)? {
+                PreparedCall::Done(result) => return Ok(result),
+                PreparedCall::Ready {
+                    tool,
+                    arguments,
+                    cache_key,
+                    mismatches,
+                } => (tool, arguments, cache_key, mismatches),
+            };
+        let Some(server) = self.servers.get(&tool.server_name) else {
+            bail!(
+                "MCP server `{}` is not available for tool `{}`",
+                tool.server_name,
+                action_name
+            );
+        };
+        let started = Instant::now();
+        let result = match server {
+            ServerSpec::Local(server) => {
+                call_tool_once(server, &tool.tool_name, &arguments, on_progress)
+            }
+            ServerSpec::Remote(server) => call_tool_remote(server, &tool.tool_name, &arguments),
+        }?;
+        Ok(self.finish_call(tool, cache_key, &mismatches, started, result))
+    }
+
+    /// 同一本地服务端上、无需确认的连续调用可以合并成一个批次。
+    pub fn can_batch(&self, first: &str, next: &str) -> bool {
+        let (Some(a), Some(b)) = (self.resolve_tool_spec(first), self.resolve_tool_spec(next))
+        else {
+            return false;
+        };
+        a.server_name == b.server_name
+            && !a.destructive_hint
+            && !b.destructive_hint
+            && matches!(self.servers.get(&a.server_name), Some(ServerSpec::Local(_)))
+    }
+
+    /// 批量调用同一本地服务端的工具：只启动一次服务端，请求流水线发送。
+    /// 结果与 `calls` 一一对应；命中缓存的调用不会发给服务端。
+    pub fn execute_tools_batch(&self, calls: &[(String, Value)]) -> Vec<Result<McpCallResult>> {
+        let mut results: Vec<Option<Result<McpCallResult>>> = Vec::with_capacity(calls.len());
+        let mut ready = Vec::new();
+        for (index, (action_name, arguments)) in calls.iter().enumerate() {
+            match self.prepare_call(action_name, arguments) {
+                Ok(PreparedCall::Ready {
+                    tool,
+                    arguments,
+                    cache_key,
+                    mismatches,
+                }) => {
+                    results.push(None);
+                    ready.push((index, tool, arguments, cache_key, mismatches));
+                }
+                Ok(PreparedCall::Done(result)) => results.push(Some(Ok(result))),
+                Err(e) => results.push(Some(Err(e))),
+            }
+        }
+
+        let server = ready
+            .first()
+            .and_then(|(_, tool, ..)| self.servers.get(&tool.server_name));
+        let same_server = ready
+            .iter()
+            .all(|(_, tool, ..)| ready[0].1.server_name == tool.server_name);
+        match server {
+            Some(ServerSpec::Local(server)) if same_server => {
+                let started = Instant::now();
+                let requests: Vec<(&str, &Value)> = ready
+                    .iter()
+                    .map(|(_, tool, arguments, ..)| (tool.tool_name.as_str(), arguments))
+                    .collect();
+                match call_tools_many(server, &requests) {
+                    Ok(outputs) => {
+                        for ((index, tool, _, cache_key, mismatches), output) in
+                            ready.into_iter().zip(outputs)
+                        {
+                            results[index] = Some(Ok(self.finish_call(
+                                tool,
+                                cache_key,
+                                &mismatches,
+                                started,
+                                output,
+                            )));
+                        }
+                    }
+                    Err(e) => {
+                        for (index, ..) in ready {
+                            results[index] = Some(Err(anyhow!("{e:#}")));
+                        }
+                    }
+                }
+            }
+            _ => {
+                for (index, tool, ..) in ready {
+                    results[index] =
+                        Some(self.execute_tool(&tool.action_name, &calls[index].1, &mut |_| {}));
+                }
+            }
+        }
+        results
+            .into_iter()
+            .map(|r| r.unwrap_or_else(|| Err(anyhow!("missing MCP batch result"))))
+            .collect()
+    }
+
+    /// 解析工具名并校验、规范化参数；参数不符或命中缓存时直接给出结果。
+    fn prepare_call(&self, action_name: &str, arguments: &Value) -> Result<PreparedCall<'_>> {
         let Some(tool) = self.resolve_tool_spec(action_name) else {
             let suggestions = self.suggest_tool_names(action_name, 5);
             if suggestions.is_empty() {
@@ -377,26 +566,115 @@ impl McpRegistry {
             bail!("MCP <arguments> must be a JSON object");
         }
 
-        let normalized_arguments = normalize_arguments_for_tool(tool, arguments);
-        let tool_name = tool.tool_name.clone();
-        let server_name = tool.server_name.clone();
+        let arguments = normalize_arguments_for_tool(tool, arguments);
+        let mismatches = argument_mismatches(&tool.input_schema, &arguments);
+        let cache_key = tool
+            .idempotent_hint
+            .then(|| format!("{}\u{0}{arguments}", tool.action_name));
+        if let Some(cached) = cache_key.as_ref().and_then(|key| self.cached_call(key)) {
+            return Ok(PreparedCall::Done(cached));
+        }
+        Ok(PreparedCall::Ready {
+            tool,
+            arguments,
+            cache_key,
+            mismatches,
+        })
+    }
 
-        if let Some(server) = self.servers.get(&server_name) {
-            return match server {
-                ServerSpec::Local(server) => {
-                    call_tool_once(server, &tool_name, &normalized_arguments)
-                }
-                ServerSpec::Remote(server) => {
-                    call_tool_remote(server, &tool_name, &normalized_arguments)
-                }
-            };
+    /// 记录延迟、补充参数错误提示并写入幂等调用缓存。
+    /// `mismatches` 是本地按 schema 发现的问题：服务端拒绝参数时并入错误提示，
+    /// 服务端接受时只作为警告附在结果后（有些服务端比声明的 schema 宽松）。
+    fn finish_call(
+        &self,
+        tool: &McpToolSpec,
+        cache_key: Option<String>,
+        mismatches: &[String],
+        started: Instant,
+        mut result: McpCallResult,
+    ) -> McpCallResult {
+        if result.exit_code == 0 {
+            self.record_latency(&tool.server_name, started.elapsed());
+        }
+        // 服务端拒绝参数时，附上期望的 schema 摘要
+        if result.exit_code != 0 && is_invalid_params_error(&result.output) {
+            return argument_error_result(tool, mismatches, Some(&result.output));
+        }
+        if !mismatches.is_empty() {
+            result.output.push_str(&format!(
+                "\n[warning] arguments do not match the declared schema: {}",
+                mismatches.join("; ")
+            ));
+        }
+        if let Some(key) = cache_key
+            && result.exit_code == 0
+            && !result.tools_changed
+            && let Ok(mut cache) = self.call_cache.lock()
+        {
+            cache.insert(key, result.clone());
         }
+        result
+    }
 
-        bail!(
-            "MCP server `{}` is not available for tool `{}`",
-            server_name,
-            action_name
-        );
+    fn cached_call(&self, key: &str) -> Option<McpCallResult> {
+        let cached = self.call_cache.lock().ok()?.get(key).cloned()?;
+        Some(McpCallResult {
+            output: format!(
+                "[cached result of an identical idempotent call]\n{}",
+                cached.output
+            ),
+            ..cached
+        })
+    }
+
+    /// Drops cached idempotent results; called when a new task starts.
+    pub fn clear_call_cache(&self) {
+        if let Ok(mut cache) = self.call_cache.lock() {
+            cache.clear();
+        }
+    }
+
+    /// Whether calling this action must go through the write confirmation
+    /// (the server marked the tool with `destructiveHint: true`).
+    pub fn is_destructive(&self, action_name: &str) -> bool {
+        self.resolve_tool_spec(action_name)
+            .is_some_and(|tool| tool.destructive_hint)
+    }
+
+    /// Name shown in the UI: the tool's `title` annotation when present, otherwise the action name.
+    pub fn display_name(&self, action_name: &str) -> String {
+        self.resolve_tool_spec(action_name)
+            .and_then(|tool| tool.title.clone())
+            .unwrap_or_else(|| action_name.to_string())
+    }
+
+    /// Server that provides the given action, if known.
+    pub fn server_for_action(&self, action_name: &str) -> Option<String> {
+        self.resolve_tool_spec(action_name)
+            .map(|tool| tool.server_name.clone())
+    }
+
+    /// Re-runs `tools/list` for one server (after `notifications/tools/list_changed`)
+    /// and replaces its tools. Returns the new tool count.
+    pub fn refresh_server_tools(&mut self, server_name: &str) -> Result<usize> {
+        let Some(server) = self.servers.get(server_name).cloned() else {
+            bail!("MCP server `{server_name}` is not configured");
+        };
+        let tools = list_tools_for_server(&server, mcp_discovery_timeout())?;
+        Ok(self.replace_server_tools(server_name, tools))
+    }
+
+    pub(super) fn replace_server_tools(
+        &mut self,
+        server_name: &str,
+        tools: Vec<DiscoveredTool>,
+    ) -> usize {
+        self.tools.retain(|_, tool| tool.server_name != server_name);
+        self.clear_call_cache();
+        let mut used_names: BTreeSet<String> = self.tools.keys().cloned().collect();
+        let count = tools.len();
+        self.register_discovered_tools(server_name, tools, &mut used_names);
+        count
     }
 
     fn resolve_tool_spec(&self, action_name: &str) -> Option<&McpToolSpec> {
@@ -447,7 +725,10 @@ impl McpRegistry {
                     server_name: server_name.to_string(),
                     tool_name: tool.tool_name,
                     description: tool.description,
+                    title: tool.title,
                     read_only_hint: tool.read_only_hint,
+                    destructive_hint: tool.destructive_hint,
+                    idempotent_hint: tool.idempotent_hint,
                     input_schema: tool.input_schema,
                 },
             );
@@ -460,7 +741,8 @@ impl McpRegistry {
         error: anyhow::Error,
         warnings: &mut Vec<String>,
     ) {
-        self.failed.push(server_name.to_string());
+        self.failed
+            .insert(server_name.to_string(), format!("{error:#}"));
         warnings.push(format!(
             "Failed to load MCP tools from `{server_name}`: {error}. Server skipped."
         ));
@@ -478,7 +760,8 @@ impl McpRegistry {
         }
 
         // Launch all server discoveries in parallel so N remote servers each
-        // costing `timeout` ms don't block each other serially.
+        // costing `timeout` ms don't block each other serially. Local servers
+        // additionally queue on the shared background job pool before spawning.
         let (tx, rx) = mpsc::channel::<(String, anyhow::Result<Vec<DiscoveredTool>>)>();
         for (server_name, server) in server_entries {
             let tx = tx.clone();
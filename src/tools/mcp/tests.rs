@@ -1,13 +1,21 @@
 ﻿use super::{
-    config::{RawServerEntry, extract_local_command_and_args, parse_server_entries},
-    types::McpToolSpec,
+    config::{
+        RawServerEntry, extract_local_command_and_args, parse_server_entries, write_mcp_server,
+    },
+    discovery::{parse_discovered_tools_response, verify_local_server},
+    executor::parse_tool_call_response,
+    parse_call_arguments,
+    protocol::{McpNotification, StdioMcpSession, await_response},
+    registry::merge_config_source,
+    types::{LocalServerSpec, McpProgress, McpRegistry, McpToolSpec, ServerSpec},
     util::{
         normalize_action_name_for_lookup, normalize_arguments_for_tool, sanitize_token,
         summarize_input_schema, unique_action_name,
     },
 };
 use serde_json::json;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
 
 #[test]
 fn sanitize_token_collapses_symbols() {
@@ -54,6 +62,43 @@ fn parse_server_entries_accepts_mcp_wrapper() {
     ));
 }
 
+#[test]
+fn conflicting_duplicate_servers_warn_with_both_sources() {
+    let project = parse_server_entries(
+        r#"{"mcpServers": {"context7": {"command": "npx", "args": ["-y", "ctx7@1"]}, "fs": {"command": "mcp-fs"}}}"#,
+    )
+    .unwrap();
+    let global = parse_server_entries(
+        r#"{"mcpServers": {"context7": {"command": "npx", "args": ["-y", "ctx7@2"]}, "fs": {"command": "mcp-fs"}}}"#,
+    )
+    .unwrap();
+
+    let mut merged = BTreeMap::new();
+    let mut warnings = Vec::new();
+    merge_config_source(
+        &mut merged,
+        project,
+        Path::new("/repo/.mcp.json"),
+        &mut warnings,
+    );
+    merge_config_source(
+        &mut merged,
+        global,
+        Path::new("/home/u/.claude.json"),
+        &mut warnings,
+    );
+
+    // 只有定义不同的 context7 会告警；相同的 fs 保持静默
+    assert_eq!(warnings.len(), 1, "{warnings:?}");
+    assert!(warnings[0].contains("`context7`"));
+    assert!(
+        warnings[0].contains("/repo/.mcp.json") && warnings[0].contains("/home/u/.claude.json")
+    );
+    assert!(warnings[0].ends_with("using `/repo/.mcp.json`."));
+    assert_eq!(merged["context7"].1, Path::new("/repo/.mcp.json"));
+    assert_eq!(merged.len(), 2);
+}
+
 #[test]
 fn command_array_is_supported() {
     let raw = r#"{
@@ -71,6 +116,63 @@ fn command_array_is_supported() {
     assert_eq!(args, vec!["-y", "@upstash/context7-mcp", "--api-key", "k"]);
 }
 
+#[test]
+fn create_mcp_dry_run_returns_normalized_spec_without_writing() {
+    let path =
+        std::env::temp_dir().join(format!("goldbot-mcp-dry-run-{}.json", std::process::id()));
+    let original = r#"{"mcpServers":{"existing":{"type":"local","command":["srv"]}}}"#;
+    std::fs::write(&path, original).unwrap();
+    let config = json!({
+        "name": "context7",
+        "command": "npx",
+        "args": ["-y", "@upstash/context7-mcp"],
+        "env": {}
+    });
+
+    let spec = write_mcp_server(&path, "context7", &config, true).expect("dry run");
+    assert_eq!(
+        spec,
+        json!({
+            "command": ["npx", "-y", "@upstash/context7-mcp"],
+            "type": "local",
+            "enabled": true
+        })
+    );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+
+    // 真正写入时落盘的正是预览里的配置
+    let written = write_mcp_server(&path, "context7", &config, false).expect("write");
+    assert_eq!(written, spec);
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["mcpServers"]["context7"], spec);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn verify_created_server_reports_tool_count_or_failure() {
+    let script = r#"while IFS= read -r line; do
+  case "$line" in
+    *'"initialize"'*) echo '{"jsonrpc":"2.0","id":1,"result":{"capabilities":{}}}' ;;
+    *'"tools/list"'*) echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"search"},{"name":"fetch"}]}}' ;;
+  esac
+done"#;
+    let timeout = std::time::Duration::from_secs(5);
+    let ok = json!({ "command": ["sh", "-c", script], "type": "local", "enabled": true });
+    assert_eq!(
+        verify_local_server(&ok, timeout).expect("server responds"),
+        2
+    );
+
+    let missing = json!({ "command": ["goldbot-no-such-mcp-server"], "type": "local" });
+    let err = verify_local_server(&missing, timeout).expect_err("spawn should fail");
+    assert!(format!("{err:#}").contains("failed to spawn"), "{err:#}");
+
+    // 进程能启动但立即退出，同样算校验失败
+    let exits = json!({ "command": ["sh", "-c", "exit 1"], "type": "local" });
+    assert!(verify_local_server(&exits, timeout).is_err());
+}
+
 #[test]
 fn normalize_action_name_handles_double_underscore() {
     assert_eq!(
@@ -86,10 +188,598 @@ fn context7_resolve_library_id_autofills_query() {
         server_name: "context7".to_string(),
         tool_name: "resolve-library-id".to_string(),
         description: String::new(),
+        title: None,
         read_only_hint: true,
+        destructive_hint: false,
+        idempotent_hint: false,
         input_schema: json!({}),
     };
     let args = json!({ "libraryName": "tokio" });
     let normalized = normalize_arguments_for_tool(&spec, &args);
     assert_eq!(normalized, json!({"libraryName":"tokio","query":"tokio"}));
 }
+
+#[test]
+fn progress_notifications_are_forwarded_while_waiting() {
+    let mut incoming = vec![
+        json!({"jsonrpc": "2.0", "method": "notifications/progress",
+               "params": {"progressToken": 7, "progress": 3, "total": 10, "message": "indexing"}}),
+        json!({"jsonrpc": "2.0", "method": "notifications/message", "params": {"level": "info"}}),
+        json!({"jsonrpc": "2.0", "method": "notifications/progress",
+               "params": {"progressToken": 99, "progress": 1}}),
+        json!({"jsonrpc": "2.0", "id": 7, "result": {"content": []}}),
+    ]
+    .into_iter();
+
+    let mut seen = Vec::new();
+    let response = await_response(
+        7,
+        || Ok(incoming.next().expect("response should end the loop")),
+        &mut |n| seen.push(n),
+    )
+    .expect("response");
+
+    assert_eq!(response["id"], 7);
+    let progress = McpProgress {
+        progress: 3.0,
+        total: Some(10.0),
+        message: Some("indexing".to_string()),
+    };
+    assert_eq!(progress.label(), "3/10 indexing");
+    assert_eq!(seen, [McpNotification::Progress(progress)]);
+}
+
+#[cfg(unix)]
+#[test]
+fn schema_mismatches_defer_to_the_server() {
+    // 服务端缺 libraryId 时拒绝参数，其它情况（包括 tokens 类型不符）都接受
+    let script = r#"while IFS= read -r line; do
+  case "$line" in
+    *'"initialize"'*) echo '{"jsonrpc":"2.0","id":1,"result":{"capabilities":{}}}' ;;
+    *'"libraryId"'*) echo '{"jsonrpc":"2.0","id":2,"result":{"content":[{"type":"text","text":"docs"}]}}' ;;
+    *'"tools/call"'*) echo '{"jsonrpc":"2.0","id":2,"error":{"code":-32602,"message":"Invalid params"}}' ;;
+  esac
+done"#;
+    let spec = McpToolSpec {
+        action_name: "mcp_context7_get_docs".to_string(),
+        server_name: "context7".to_string(),
+        tool_name: "get-library-docs".to_string(),
+        description: String::new(),
+        title: None,
+        read_only_hint: true,
+        destructive_hint: false,
+        idempotent_hint: false,
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "libraryId": { "type": "string" },
+                "tokens": { "type": "integer" }
+            },
+            "required": ["libraryId"]
+        }),
+    };
+    let mut registry = McpRegistry::default();
+    registry.servers.insert(
+        "context7".to_string(),
+        ServerSpec::Local(LocalServerSpec {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: Default::default(),
+            cwd: None,
+            transport: None,
+        }),
+    );
+    registry.tools.insert(spec.action_name.clone(), spec);
+
+    // 服务端拒绝：错误里带上本地发现的问题和 schema 摘要
+    let result = registry
+        .execute_tool(
+            "mcp_context7_get_docs",
+            &json!({ "tokens": "5000" }),
+            &mut |_| {},
+        )
+        .expect("tool result");
+    assert_eq!(result.exit_code, 1);
+    assert!(result.output.contains("missing required field `libraryId`"));
+    assert!(
+        result
+            .output
+            .contains("field `tokens` should be integer, got string")
+    );
+    assert!(result.output.contains("Invalid params"));
+    assert!(result.output.contains("libraryId:string*, tokens:integer"));
+
+    // 宽松的服务端接受了类型不符的参数：照常返回结果，只附一条警告
+    let result = registry
+        .execute_tool(
+            "mcp_context7_get_docs",
+            &json!({ "libraryId": "/tokio", "tokens": "5000" }),
+            &mut |_| {},
+        )
+        .expect("tool result");
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(
+        result.output,
+        "docs\n[warning] arguments do not match the declared schema: \
+         field `tokens` should be integer, got string"
+    );
+}
+
+#[test]
+fn tools_prompt_section_lists_registered_tools() {
+    let mut registry = McpRegistry::default();
+    assert_eq!(registry.tools_prompt_section(), "");
+
+    registry.tools.insert(
+        "mcp_fs_read".to_string(),
+        McpToolSpec {
+            action_name: "mcp_fs_read".to_string(),
+            server_name: "fs".to_string(),
+            tool_name: "read".to_string(),
+            description: "Read a file".to_string(),
+            title: None,
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: false,
+            input_schema: json!({"type": "object"}),
+        },
+    );
+    let section = registry.tools_prompt_section();
+    assert!(section.starts_with("\n\n## Available MCP tools\n"));
+    assert!(section.contains("- mcp_fs_read => server=`fs` tool=`read` (read-only)"));
+}
+
+#[test]
+fn tool_annotations_drive_title_and_destructive_gating() {
+    let response = json!({"jsonrpc": "2.0", "id": 2, "result": {"tools": [
+        {"name": "delete_repo", "annotations": {"title": "Delete repository", "destructiveHint": true}},
+        {"name": "get_issue", "annotations": {"readOnlyHint": true, "idempotentHint": true}},
+        {"name": "comment", "annotations": {"idempotent_hint": true, "title": "  "}},
+        {"name": "push"}
+    ]}});
+    let tools = parse_discovered_tools_response(&response).expect("tools");
+    let flags: Vec<_> = tools
+        .iter()
+        .map(|t| {
+            (
+                t.tool_name.as_str(),
+                t.title.as_deref(),
+                t.read_only_hint,
+                t.destructive_hint,
+                t.idempotent_hint,
+            )
+        })
+        .collect();
+    assert_eq!(
+        flags,
+        [
+            ("delete_repo", Some("Delete repository"), false, true, false),
+            ("get_issue", None, true, false, true),
+            ("comment", None, false, false, true),
+            ("push", None, false, false, false),
+        ]
+    );
+
+    let mut registry = McpRegistry::default();
+    registry.replace_server_tools("github", tools);
+    // 只有显式 destructiveHint 的工具需要确认；没有 readOnlyHint 的普通工具照旧直接调用
+    assert!(registry.is_destructive("mcp_github_delete_repo"));
+    assert!(!registry.is_destructive("mcp_github_push"));
+    assert!(!registry.is_destructive("mcp_github_get_issue"));
+    assert!(!registry.is_destructive("mcp_unknown_tool"));
+
+    assert_eq!(
+        registry.display_name("mcp_github_delete_repo"),
+        "Delete repository"
+    );
+    assert_eq!(registry.display_name("mcp_github_push"), "mcp_github_push");
+    let section = registry.tools_prompt_section();
+    assert!(section.contains(
+        "- mcp_github_delete_repo \"Delete repository\" => server=`github` tool=`delete_repo` (destructive)"
+    ));
+    assert!(
+        section.contains("- mcp_github_get_issue => server=`github` tool=`get_issue` (read-only)")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn idempotent_tool_results_are_cached_until_cleared() {
+    let script = r#"while IFS= read -r line; do
+  case "$line" in
+    *'"initialize"'*) echo '{"jsonrpc":"2.0","id":1,"result":{"capabilities":{}}}' ;;
+    *'"tools/call"'*) echo '{"jsonrpc":"2.0","id":2,"result":{"content":[{"type":"text","text":"ok"}]}}' ;;
+  esac
+done"#;
+    let mut registry = McpRegistry::default();
+    registry.servers.insert(
+        "kb".to_string(),
+        ServerSpec::Local(LocalServerSpec {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: Default::default(),
+            cwd: None,
+            transport: None,
+        }),
+    );
+    let response = json!({"result": {"tools": [
+        {"name": "lookup", "annotations": {"idempotentHint": true}},
+        {"name": "append"}
+    ]}});
+    registry.replace_server_tools("kb", parse_discovered_tools_response(&response).unwrap());
+
+    let call = |name: &str, args| {
+        registry
+            .execute_tool(name, &args, &mut |_| {})
+            .expect("tool result")
+            .output
+    };
+    assert_eq!(call("mcp_kb_lookup", json!({"q": "a"})), "ok");
+    assert!(call("mcp_kb_lookup", json!({"q": "a"})).starts_with("[cached result"));
+    assert_eq!(call("mcp_kb_lookup", json!({"q": "b"})), "ok");
+    assert_eq!(call("mcp_kb_append", json!({})), "ok");
+    assert_eq!(call("mcp_kb_append", json!({})), "ok");
+
+    registry.clear_call_cache();
+    assert_eq!(call("mcp_kb_lookup", json!({"q": "a"})), "ok");
+}
+
+#[cfg(unix)]
+#[test]
+fn direct_call_returns_result_and_unknown_action_suggests_names() {
+    let script = r#"while IFS= read -r line; do
+  case "$line" in
+    *'"initialize"'*) echo '{"jsonrpc":"2.0","id":1,"result":{"capabilities":{}}}' ;;
+    *'"tools/call"'*) echo '{"jsonrpc":"2.0","id":2,"result":{"content":[{"type":"text","text":"3 issues"}]}}' ;;
+  esac
+done"#;
+    let mut registry = McpRegistry::default();
+    registry.servers.insert(
+        "github".to_string(),
+        ServerSpec::Local(LocalServerSpec {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: Default::default(),
+            cwd: None,
+            transport: None,
+        }),
+    );
+    let response = json!({"result": {"tools": [{"name": "search_issues"}]}});
+    registry.replace_server_tools(
+        "github",
+        parse_discovered_tools_response(&response).unwrap(),
+    );
+
+    // `/mcp call mcp_github_search_issues {"q":"bug"}`
+    let args = parse_call_arguments(r#" {"q": "bug"} "#).expect("json args");
+    let result = registry
+        .execute_tool("mcp_github_search_issues", &args, &mut |_| {})
+        .expect("tool result");
+    assert_eq!((result.exit_code, result.output.as_str()), (0, "3 issues"));
+
+    let err = registry
+        .execute_tool("mcp_github_search", &json!({}), &mut |_| {})
+        .expect_err("unknown action");
+    assert!(
+        err.to_string()
+            .contains("Try one of: mcp_github_search_issues"),
+        "{err}"
+    );
+
+    assert_eq!(parse_call_arguments("").unwrap(), json!({}));
+    let bad = parse_call_arguments("{q: bug}").expect_err("invalid json");
+    assert!(
+        format!("{bad:#}").starts_with("invalid JSON arguments: "),
+        "{bad:#}"
+    );
+    let not_object = parse_call_arguments("[1]").expect_err("array");
+    assert_eq!(
+        not_object.to_string(),
+        "arguments must be a JSON object, got array"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn tools_list_changed_triggers_rediscovery() {
+    // 最小 stdio MCP 服务端：声明 listChanged，tools/call 时先发出变更通知
+    let script = r#"while IFS= read -r line; do
+  case "$line" in
+    *'"initialize"'*) echo '{"jsonrpc":"2.0","id":1,"result":{"capabilities":{"tools":{"listChanged":true}}}}' ;;
+    *'"tools/call"'*) echo '{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}'
+      echo '{"jsonrpc":"2.0","id":2,"result":{"content":[{"type":"text","text":"ok"}]}}' ;;
+    *'"tools/list"'*) echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"search"},{"name":"create_issue"}]}}' ;;
+  esac
+done"#;
+    let mut registry = McpRegistry::default();
+    registry.servers.insert(
+        "github".to_string(),
+        ServerSpec::Local(LocalServerSpec {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: Default::default(),
+            cwd: None,
+            transport: None,
+        }),
+    );
+    registry.tools.insert(
+        "mcp_github_search".to_string(),
+        McpToolSpec {
+            action_name: "mcp_github_search".to_string(),
+            server_name: "github".to_string(),
+            tool_name: "search".to_string(),
+            description: String::new(),
+            title: None,
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: false,
+            input_schema: json!({"type": "object"}),
+        },
+    );
+
+    let result = registry
+        .execute_tool("mcp_github_search", &json!({}), &mut |_| {})
+        .expect("tool result");
+    assert_eq!(result.output, "ok");
+    assert!(result.tools_changed);
+
+    assert_eq!(registry.refresh_server_tools("github").expect("refresh"), 2);
+    let names: Vec<&str> = registry.tools.keys().map(String::as_str).collect();
+    assert_eq!(names, ["mcp_github_create_issue", "mcp_github_search"]);
+}
+
+#[test]
+fn pipelined_requests_match_out_of_order_responses_by_id() {
+    // 先读完三条请求再倒序回应，中间夹一条通知；随后的单个请求走同一会话
+    let script = r#"read -r a; read -r b; read -r c
+echo '{"jsonrpc":"2.0","id":3,"result":{"n":"third"}}'
+echo '{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}'
+echo '{"jsonrpc":"2.0","id":1,"result":{"n":"first"}}'
+echo '{"jsonrpc":"2.0","id":2,"result":{"n":"second"}}'
+read -r d
+echo '{"jsonrpc":"2.0","id":4,"result":{"n":"single"}}'"#;
+    let mut session = StdioMcpSession::spawn(&LocalServerSpec {
+        command: "sh".to_string(),
+        args: vec!["-c".to_string(), script.to_string()],
+        env: Default::default(),
+        cwd: None,
+        transport: None,
+    })
+    .expect("spawn mock server");
+
+    let responses = session
+        .request_many(vec![
+            ("tools/call", json!({"name": "a"})),
+            ("tools/call", json!({"name": "b"})),
+            ("tools/call", json!({"name": "c"})),
+        ])
+        .expect("pipelined responses");
+    let names: Vec<&str> = responses
+        .iter()
+        .filter_map(|r| r.pointer("/result/n").and_then(|v| v.as_str()))
+        .collect();
+    assert_eq!(names, ["first", "second", "third"]);
+
+    let single = session.request("tools/list", json!({})).expect("single");
+    assert_eq!(single["id"], 4);
+    assert_eq!(single["result"]["n"], "single");
+}
+
+#[cfg(unix)]
+#[test]
+fn pending_response_times_out_when_server_drops_an_id() {
+    let script = r#"read -r a; read -r b
+echo '{"jsonrpc":"2.0","id":1,"result":{}}'
+sleep 5"#;
+    let mut session = StdioMcpSession::spawn(&LocalServerSpec {
+        command: "sh".to_string(),
+        args: vec!["-c".to_string(), script.to_string()],
+        env: Default::default(),
+        cwd: None,
+        transport: None,
+    })
+    .expect("spawn mock server")
+    .with_response_timeout(std::time::Duration::from_millis(300));
+
+    let started = std::time::Instant::now();
+    let err = session
+        .request_many(vec![
+            ("tools/call", json!({"name": "a"})),
+            ("tools/call", json!({"name": "b"})),
+        ])
+        .expect_err("id 2 never arrives");
+    assert!(err.to_string().contains("sent nothing for 300ms"), "{err}");
+    assert!(started.elapsed() < std::time::Duration::from_secs(3));
+}
+
+#[cfg(unix)]
+#[test]
+fn batched_calls_share_one_session_and_keep_order() {
+    // 两个调用都读到后才倒序回应：逐个启动会话时第一个调用会一直等不到响应
+    let script = r#"read -r init
+echo '{"jsonrpc":"2.0","id":1,"result":{"capabilities":{}}}'
+read -r initialized; read -r a; read -r b
+echo '{"jsonrpc":"2.0","id":3,"result":{"content":[{"type":"text","text":"beta"}]}}'
+echo '{"jsonrpc":"2.0","id":2,"result":{"content":[{"type":"text","text":"alpha"}]}}'"#;
+    let mut registry = McpRegistry::default();
+    registry.servers.insert(
+        "docs".to_string(),
+        ServerSpec::Local(LocalServerSpec {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: Default::default(),
+            cwd: None,
+            transport: None,
+        }),
+    );
+    for name in ["alpha", "beta"] {
+        registry.tools.insert(
+            format!("mcp_docs_{name}"),
+            McpToolSpec {
+                action_name: format!("mcp_docs_{name}"),
+                server_name: "docs".to_string(),
+                tool_name: name.to_string(),
+                description: String::new(),
+                title: None,
+                read_only_hint: true,
+                destructive_hint: false,
+                idempotent_hint: false,
+                input_schema: json!({"type": "object"}),
+            },
+        );
+    }
+    assert!(registry.can_batch("mcp_docs_alpha", "mcp_docs_beta"));
+    assert!(!registry.can_batch("mcp_docs_alpha", "mcp_docs_missing"));
+
+    let results = registry.execute_tools_batch(&[
+        ("mcp_docs_alpha".to_string(), json!({})),
+        ("mcp_docs_missing".to_string(), json!({})),
+        ("mcp_docs_beta".to_string(), json!({})),
+    ]);
+    let outputs: Vec<String> = results
+        .into_iter()
+        .map(|r| match r {
+            Ok(out) => out.output,
+            Err(e) => e.to_string(),
+        })
+        .collect();
+    assert_eq!(outputs[0], "alpha");
+    assert!(outputs[1].starts_with("unknown MCP tool"), "{}", outputs[1]);
+    assert_eq!(outputs[2], "beta");
+}
+
+#[test]
+fn oversized_json_result_is_truncated_to_valid_json_with_marker() {
+    let rows: Vec<_> = (0..2000)
+        .map(|i| json!({"id": i, "name": format!("row-{i}"), "tags": ["a", "b"]}))
+        .collect();
+    let payload = json!({"total": 2000, "rows": rows}).to_string();
+    let response = json!({"result": {"content": [{"type": "text", "text": payload}]}});
+
+    let dir = temp_artifacts_dir("json");
+    let result = parse_tool_call_response(&response, &dir).expect("parse");
+    assert!(result.output.chars().count() <= super::MAX_OUTPUT_CHARS);
+    // 截断预览仍是合法 JSON，后面跟着完整结果的文件路径
+    let (preview, _) = result
+        .output
+        .split_once("\n(full output saved to ")
+        .expect("spilled");
+    let parsed: serde_json::Value = serde_json::from_str(preview).expect("valid JSON");
+    assert_eq!(parsed["total"], 2000);
+    let kept = parsed["rows"].as_array().expect("rows stays an array");
+    assert_eq!(kept[0]["name"], "row-0");
+    let marker = kept.last().unwrap()["_truncated"].as_u64().expect("marker");
+    assert_eq!(marker as usize + kept.len() - 1, 2000);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn oversized_text_result_keeps_head_and_tail() {
+    let text = format!("HEAD-LINE\n{}\nTAIL-LINE", "x".repeat(30_000));
+    let response = json!({"result": {"content": [{"type": "text", "text": text}]}});
+
+    let dir = temp_artifacts_dir("text");
+    let result = parse_tool_call_response(&response, &dir).expect("parse");
+    assert!(result.output.chars().count() <= super::MAX_OUTPUT_CHARS);
+    let (preview, note) = result
+        .output
+        .split_once("\n(full output saved to ")
+        .expect("spilled");
+    assert!(preview.starts_with("HEAD-LINE"));
+    assert!(preview.ends_with("TAIL-LINE"));
+    assert!(preview.contains("chars omitted"));
+
+    // 完整结果落盘，输出里给出路径和行数，模型可以用 read 按行读取
+    let files: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    assert_eq!(files.len(), 1);
+    assert_eq!(std::fs::read_to_string(&files[0]).unwrap(), text);
+    assert!(
+        note.starts_with(&format!("{}, 3 lines;", files[0].display())),
+        "{note}"
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn small_result_is_not_spilled() {
+    let dir = temp_artifacts_dir("small");
+    let response = json!({"result": {"content": [{"type": "text", "text": "ok"}]}});
+    let result = parse_tool_call_response(&response, &dir).expect("parse");
+    assert_eq!(result.output, "ok");
+    assert!(!dir.exists());
+}
+
+fn temp_artifacts_dir(name: &str) -> std::path::PathBuf {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    std::env::temp_dir().join(format!("goldbot-mcp-artifacts-{name}-{nanos}"))
+}
+
+#[test]
+fn server_health_reports_call_latency_and_discovery_failure() {
+    let script = r#"while read -r line; do
+  case "$line" in
+    *'"initialize"'*) echo '{"jsonrpc":"2.0","id":1,"result":{"capabilities":{}}}' ;;
+    *'"tools/call"'*) echo '{"jsonrpc":"2.0","id":2,"result":{"content":[{"type":"text","text":"ok"}]}}' ;;
+  esac
+done"#;
+    let local = |command: &str, args: Vec<String>| {
+        ServerSpec::Local(LocalServerSpec {
+            command: command.to_string(),
+            args,
+            env: Default::default(),
+            cwd: None,
+            transport: None,
+        })
+    };
+    let mut registry = McpRegistry::default();
+    registry.servers.insert(
+        "echo".to_string(),
+        local("sh", vec!["-c".to_string(), script.to_string()]),
+    );
+    registry.servers.insert(
+        "missing".to_string(),
+        local("goldbot-definitely-missing-mcp", Vec::new()),
+    );
+    registry.tools.insert(
+        "mcp_echo_ping".to_string(),
+        McpToolSpec {
+            action_name: "mcp_echo_ping".to_string(),
+            server_name: "echo".to_string(),
+            tool_name: "ping".to_string(),
+            description: String::new(),
+            title: None,
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: false,
+            input_schema: json!({"type": "object"}),
+        },
+    );
+    registry
+        .failed
+        .insert("missing".to_string(), "failed to spawn".to_string());
+
+    let before = registry.server_health();
+    assert_eq!(before[0].last_latency, None);
+
+    let result = registry
+        .execute_tool("mcp_echo_ping", &json!({}), &mut |_| {})
+        .expect("tool result");
+    assert_eq!(result.exit_code, 0);
+
+    // 克隆（例如交给发现线程）后仍能看到同一份耗时记录
+    let health = registry.clone().server_health();
+    assert_eq!(health.len(), 2);
+    assert_eq!(health[0].name, "echo");
+    assert_eq!(health[0].tool_count, 1);
+    assert_eq!(health[0].failure, None);
+    assert!(health[0].last_latency.is_some());
+    assert_eq!(health[1].name, "missing");
+    assert_eq!(health[1].failure.as_deref(), Some("failed to spawn"));
+    assert_eq!(health[1].last_latency, None);
+}
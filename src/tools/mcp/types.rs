@@ -1,6 +1,8 @@
 use std::{
     collections::{BTreeMap, HashMap},
     path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use serde_json::Value;
@@ -9,7 +11,14 @@ use serde_json::Value;
 pub struct McpRegistry {
     pub(super) servers: BTreeMap<String, ServerSpec>,
     pub(super) tools: BTreeMap<String, McpToolSpec>,
-    pub(super) failed: Vec<String>,
+    /// Servers whose discovery failed, with the error that was reported.
+    pub(super) failed: BTreeMap<String, String>,
+    /// Latency of the last successful `tools/call` per server.  Shared between clones so the
+    /// numbers survive the registry being handed to and returned from the discovery thread.
+    pub(super) last_latency: Arc<Mutex<BTreeMap<String, Duration>>>,
+    /// Successful results of `idempotentHint` tools keyed by action name and arguments, reused
+    /// for identical calls until the next task starts or the server's tool list changes.
+    pub(super) call_cache: Arc<Mutex<HashMap<String, McpCallResult>>>,
 }
 
 pub struct McpStartupStatus {
@@ -17,13 +26,29 @@ pub struct McpStartupStatus {
     pub failed: Vec<String>,
 }
 
+/// Runtime health of one configured server, for `/mcp` and `/status`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct McpServerHealth {
+    pub name: String,
+    pub tool_count: usize,
+    /// Discovery error when the server could not be reached.
+    pub failure: Option<String>,
+    pub last_latency: Option<Duration>,
+}
+
 #[derive(Debug, Clone)]
 pub struct McpToolSpec {
     pub action_name: String,
     pub server_name: String,
     pub tool_name: String,
     pub description: String,
+    /// Human-readable name from `annotations.title`, shown instead of the action name.
+    pub title: Option<String>,
     pub read_only_hint: bool,
+    /// `annotations.destructiveHint`: calls go through the write confirmation.
+    pub destructive_hint: bool,
+    /// `annotations.idempotentHint`: identical calls may be answered from the call cache.
+    pub idempotent_hint: bool,
     pub input_schema: Value,
 }
 
@@ -31,6 +56,31 @@ pub struct McpToolSpec {
 pub struct McpCallResult {
     pub exit_code: i32,
     pub output: String,
+    /// 调用期间服务端通知工具列表已变化，调用方应重新发现该服务端的工具。
+    pub tools_changed: bool,
+}
+
+/// `notifications/progress` 中的一次进度更新。
+#[derive(Debug, Clone, PartialEq)]
+pub struct McpProgress {
+    pub progress: f64,
+    pub total: Option<f64>,
+    pub message: Option<String>,
+}
+
+impl McpProgress {
+    /// 状态栏显示用：`3/10 indexing…` 或 `3`。
+    pub fn label(&self) -> String {
+        let mut out = match self.total {
+            Some(total) => format!("{}/{}", self.progress, total),
+            None => self.progress.to_string(),
+        };
+        if let Some(msg) = self.message.as_deref().filter(|m| !m.trim().is_empty()) {
+            out.push(' ');
+            out.push_str(msg.trim());
+        }
+        out
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -59,5 +109,8 @@ pub(super) struct DiscoveredTool {
     pub(super) tool_name: String,
     pub(super) description: String,
     pub(super) input_schema: Value,
+    pub(super) title: Option<String>,
     pub(super) read_only_hint: bool,
+    pub(super) destructive_hint: bool,
+    pub(super) idempotent_hint: bool,
 }
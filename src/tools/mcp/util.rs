@@ -1,5 +1,6 @@
 use std::collections::BTreeSet;
 
+use anyhow::{Context, Result, bail};
 use serde_json::Value;
 
 use super::{MAX_FIELD_CHARS, MAX_SCHEMA_FIELDS, types::McpToolSpec};
@@ -125,6 +126,113 @@ pub(super) fn truncate_chars(text: &str, max_chars: usize) -> String {
     out
 }
 
+/// Fits a tool result section into `max_chars` without cutting structured content mid-token:
+/// JSON objects/arrays are shrunk element-wise (see [`shrink_json`]); anything else keeps its
+/// head and tail around an elision note.
+pub(super) fn truncate_tool_output(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    if let Ok(value) = serde_json::from_str::<Value>(text.trim())
+        && (value.is_array() || value.is_object())
+        && let Some(shrunk) = shrink_json(&value, max_chars)
+    {
+        return shrunk;
+    }
+    truncate_middle(text, max_chars)
+}
+
+/// Keeps roughly two thirds of the budget from the start and the rest from the end.
+pub(super) fn truncate_middle(text: &str, max_chars: usize) -> String {
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text.to_string();
+    }
+    let note = |omitted: usize| format!("\n… [{omitted} chars omitted] …\n");
+    // The note length depends on the omitted count; reserve for the widest case.
+    let budget = max_chars.saturating_sub(note(count).chars().count());
+    if budget == 0 {
+        return truncate_chars(text, max_chars);
+    }
+    let head = budget * 2 / 3;
+    let tail = budget - head;
+    let head_text: String = text.chars().take(head).collect();
+    let tail_text: String = text.chars().skip(count - tail).collect();
+    format!("{head_text}{}{tail_text}", note(count - head - tail))
+}
+
+/// Limits applied while shrinking a JSON value; halved each round until the result fits.
+struct JsonLimits {
+    items: usize,
+    string_chars: usize,
+    depth: usize,
+}
+
+/// Renders `value` compactly within `max_chars`, keeping its top-level shape.  Long arrays and
+/// objects keep their first entries plus a `"_truncated": N` marker, long strings keep their
+/// head, and containers nested too deeply collapse to a marker.  Returns `None` when even the
+/// tightest limits do not fit.
+pub(super) fn shrink_json(value: &Value, max_chars: usize) -> Option<String> {
+    let mut limits = JsonLimits {
+        items: 256,
+        string_chars: 4096,
+        depth: 8,
+    };
+    loop {
+        let rendered = limit_json(value, &limits, 0).to_string();
+        if rendered.chars().count() <= max_chars {
+            return Some(rendered);
+        }
+        if limits.items == 1 && limits.string_chars == 32 && limits.depth == 1 {
+            return None;
+        }
+        if limits.items == 1 && limits.string_chars == 32 {
+            limits.depth -= 1;
+        }
+        limits.items = (limits.items / 2).max(1);
+        limits.string_chars = (limits.string_chars / 2).max(32);
+    }
+}
+
+fn limit_json(value: &Value, limits: &JsonLimits, depth: usize) -> Value {
+    let marker = |omitted: usize| serde_json::json!({ "_truncated": omitted });
+    match value {
+        Value::Array(items) if depth >= limits.depth && !items.is_empty() => {
+            Value::Array(vec![marker(items.len())])
+        }
+        Value::Object(map) if depth >= limits.depth && !map.is_empty() => marker(map.len()),
+        Value::Array(items) => {
+            let mut kept: Vec<Value> = items
+                .iter()
+                .take(limits.items)
+                .map(|item| limit_json(item, limits, depth + 1))
+                .collect();
+            if items.len() > limits.items {
+                kept.push(marker(items.len() - limits.items));
+            }
+            Value::Array(kept)
+        }
+        Value::Object(map) => {
+            let mut kept: serde_json::Map<String, Value> = map
+                .iter()
+                .take(limits.items)
+                .map(|(k, v)| (k.clone(), limit_json(v, limits, depth + 1)))
+                .collect();
+            if map.len() > limits.items {
+                kept.insert(
+                    "_truncated".to_string(),
+                    Value::from(map.len() - limits.items),
+                );
+            }
+            Value::Object(kept)
+        }
+        Value::String(text) if text.chars().count() > limits.string_chars => {
+            Value::String(truncate_chars(text, limits.string_chars))
+        }
+        other => other.clone(),
+    }
+}
+
 pub(super) fn normalize_arguments_for_tool(tool: &McpToolSpec, arguments: &Value) -> Value {
     let Some(obj) = arguments.as_object() else {
         return arguments.clone();
@@ -150,6 +258,85 @@ pub(super) fn normalize_arguments_for_tool(tool: &McpToolSpec, arguments: &Value
     Value::Object(normalized)
 }
 
+/// Checks arguments against the tool's input schema (required fields and
+/// top-level JSON types only). Returns one human-readable line per mismatch.
+pub(super) fn argument_mismatches(schema: &Value, arguments: &Value) -> Vec<String> {
+    let Some(args) = arguments.as_object() else {
+        return vec!["arguments must be a JSON object".to_string()];
+    };
+    let mut out = Vec::new();
+    if let Some(required) = schema.get("required").and_then(Value::as_array) {
+        for key in required.iter().filter_map(Value::as_str) {
+            if args.get(key).is_none_or(Value::is_null) {
+                out.push(format!("missing required field `{key}`"));
+            }
+        }
+    }
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (key, value) in args {
+        let Some(expected) = properties
+            .and_then(|p| p.get(key))
+            .and_then(|p| p.get("type"))
+            .and_then(Value::as_str)
+        else {
+            continue;
+        };
+        let ok = match expected {
+            "string" => value.is_string(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => true,
+        };
+        if !ok && !value.is_null() {
+            out.push(format!(
+                "field `{key}` should be {expected}, got {}",
+                json_type_name(value)
+            ));
+        }
+    }
+    out
+}
+
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Whether a failed tools/call output looks like the server rejected the arguments.
+pub(super) fn is_invalid_params_error(output: &str) -> bool {
+    let lower = output.to_ascii_lowercase();
+    lower.contains("code=-32602")
+        || lower.contains("invalid params")
+        || lower.contains("invalid arguments")
+        || lower.contains("validation error")
+}
+
+/// Parses the JSON arguments typed after `/mcp call <action>`; empty input means `{}`.
+pub fn parse_call_arguments(text: &str) -> Result<Value> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(Value::Object(Default::default()));
+    }
+    let value: Value = serde_json::from_str(text).context("invalid JSON arguments")?;
+    if !value.is_object() {
+        bail!(
+            "arguments must be a JSON object, got {}",
+            json_type_name(&value)
+        );
+    }
+    Ok(value)
+}
+
 /// Replace `${VAR_NAME}` placeholders in a string with environment variable values.
 pub(super) fn resolve_env_var_refs(s: &str) -> String {
     let mut result = s.to_string();
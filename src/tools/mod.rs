@@ -1,10 +1,18 @@
+pub mod access;
+pub mod artifacts;
 pub mod command;
+pub mod fs;
+pub mod git;
 pub mod glob;
+pub mod init_env;
+pub mod jobs;
 pub mod mcp;
 pub mod safety;
 pub mod search;
+pub mod secrets;
 pub mod shell;
 pub mod skills;
+pub mod snapshot;
 pub mod web_search;
 
 use std::path::PathBuf;
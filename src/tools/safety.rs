@@ -1,3 +1,8 @@
+use std::sync::Mutex;
+
+use crate::tools::access::{self, AccessIgnore, IGNORE_FILE_NAME};
+use crate::tools::shell::GitSubcommand;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RiskLevel {
     Safe,
@@ -5,21 +10,101 @@ pub enum RiskLevel {
     Block,
 }
 
+impl RiskLevel {
+    /// 数值风险（0–100）的分段：Safe 0–19，Confirm 20–79，Block 80–100。
+    pub fn severity_band(self) -> std::ops::RangeInclusive<u8> {
+        match self {
+            Self::Safe => 0..=19,
+            Self::Confirm => 20..=79,
+            Self::Block => 80..=100,
+        }
+    }
+}
+
+/// 各类 Confirm 命令的数值风险，数值越大越危险。
+const SEVERITY_BLOCK: u8 = 100;
+const SEVERITY_ESCALATION: u8 = 79;
+/// 能执行任意代码的解释器（`perl -e`）与未单独分类的命令：无法判断影响，按最高确认风险处理。
+const SEVERITY_ARBITRARY_CODE: u8 = 79;
+const SEVERITY_UNCLASSIFIED: u8 = 79;
+const SEVERITY_HISTORY_REWRITE: u8 = 75;
+const SEVERITY_RECURSIVE_DELETE: u8 = 70;
+const SEVERITY_DELETE: u8 = 60;
+const SEVERITY_GIT_PUBLISH: u8 = 55;
+const SEVERITY_OUTSIDE_WORKSPACE: u8 = 50;
+const SEVERITY_PERMISSIONS: u8 = 50;
+const SEVERITY_NETWORK: u8 = 45;
+const SEVERITY_GIT_WORKTREE: u8 = 40;
+const SEVERITY_IN_PLACE_EDIT: u8 = 35;
+const SEVERITY_REDIRECTION: u8 = 30;
+const SEVERITY_GIT_STAGE: u8 = 25;
+const SEVERITY_WORKSPACE_MOVE: u8 = 20;
+
+/// 提权前缀：无论后面跟什么命令都必须经过确认。
+const PRIVILEGE_ESCALATION_COMMANDS: &[&str] = &["sudo", "su", "doas", "pkexec"];
+/// 提权后以 `-c` 执行命令串的 shell，内层命令串需要重新评估。
+const SHELL_INTERPRETERS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "fish"];
+/// `sudo bash -c "sh -c '…'"` 最多拆开的层数。
+const MAX_SHELL_NESTING: usize = 4;
+/// 禁止强制推送（`--force-with-lease` 除外）的分支；`GOLDBOT_PROTECTED_BRANCHES` 可追加（逗号分隔）。
+const DEFAULT_PROTECTED_BRANCHES: &[&str] = &["main", "master"];
+
+/// 工作区当前所在的 git 分支，由界面刷新；没有写明目标的强制推送按它判断是否推到受保护分支。
+static CURRENT_BRANCH: Mutex<Option<String>> = Mutex::new(None);
+
+/// 展开通配符参数时最多检查的路径数。
+const MAX_GLOB_TARGETS: usize = 1000;
+
+/// 会改写或丢失 git 历史的操作的评估结果。
+enum GitHistoryRisk {
+    Confirm(String),
+    Block(String),
+}
+
+pub(crate) fn set_current_branch(branch: Option<String>) {
+    if let Ok(mut current) = CURRENT_BRANCH.lock() {
+        *current = branch;
+    }
+}
+
+fn current_branch() -> Option<String> {
+    CURRENT_BRANCH.lock().ok().and_then(|branch| branch.clone())
+}
+
 pub fn assess_command(command: &str) -> (RiskLevel, String) {
+    let (risk, _, reason) = assess_command_severity(command);
+    (risk, reason)
+}
+
+/// 与 [`assess_command`] 相同，另外给出数值风险（落在风险等级的 [`RiskLevel::severity_band`] 内）。
+/// 复合命令取各段中的最大值。
+pub fn assess_command_severity(command: &str) -> (RiskLevel, u8, String) {
     let lower = command.to_lowercase();
 
     // Always block obvious shell bomb patterns.
     if lower.contains(":(){") {
-        return (RiskLevel::Block, "已拦截：系统关键命令".into());
+        return (
+            RiskLevel::Block,
+            SEVERITY_BLOCK,
+            "已拦截：系统关键命令".into(),
+        );
     }
 
     let has_output_redirection = contains_unquoted_output_redirection(command);
     let mut should_confirm = has_output_redirection;
+    let mut severity = if has_output_redirection {
+        SEVERITY_REDIRECTION
+    } else {
+        0
+    };
     let mut confirm_reason = if has_output_redirection {
         Some("需要确认：命令包含重定向（> / >>），会写入文件".to_string())
     } else {
         None
     };
+    let mut escalation_reason: Option<String> = None;
+    let mut history_reason: Option<String> = None;
+    let access = access::for_current_workspace();
 
     for segment in split_unquoted_segments(command) {
         let tokens = tokenize_shell(&segment);
@@ -28,12 +113,85 @@ pub fn assess_command(command: &str) -> (RiskLevel, String) {
         };
 
         // Hard blocks
-        if matches!(cmd.as_str(), "sudo" | "format" | "diskpart") {
-            return (RiskLevel::Block, "已拦截：系统关键命令".into());
+        if matches!(cmd.as_str(), "format" | "diskpart") {
+            return (
+                RiskLevel::Block,
+                SEVERITY_BLOCK,
+                "已拦截：系统关键命令".into(),
+            );
+        }
+        if let Some(hit) = ignore_file_target(&tokens, cmd_index, &access) {
+            return (
+                RiskLevel::Block,
+                SEVERITY_BLOCK,
+                format!("已拦截：{hit} 是 {IGNORE_FILE_NAME}，只能由用户修改"),
+            );
+        }
+        if let Some(hit) = ignored_path_target(&tokens, cmd_index, &access) {
+            return (
+                RiskLevel::Block,
+                SEVERITY_BLOCK,
+                format!("已拦截：命令访问 {IGNORE_FILE_NAME} 中的路径 {hit}"),
+            );
+        }
+
+        // 提权：总是确认；被提权的内层命令重新评估，破坏性命令直接拦截
+        if PRIVILEGE_ESCALATION_COMMANDS.contains(&cmd.as_str()) {
+            if let Some(inner) = escalated_inner_command(&cmd, &tokens, cmd_index) {
+                // `sudo bash -c '…'` 评估的是 -c 后的命令串；看不到内容的 shell 直接拦截
+                let inner = match shell_payload(&inner) {
+                    Ok(inner) => inner,
+                    Err(shell) => {
+                        return (
+                            RiskLevel::Block,
+                            SEVERITY_BLOCK,
+                            format!("已拦截：通过 {cmd} 提权启动 {shell}，无法检查它执行的内容"),
+                        );
+                    }
+                };
+                let (inner_risk, inner_reason) = assess_command(&inner);
+                if inner_risk == RiskLevel::Block {
+                    return (RiskLevel::Block, SEVERITY_BLOCK, inner_reason);
+                }
+                if contains_destructive_command(&inner) {
+                    return (
+                        RiskLevel::Block,
+                        SEVERITY_BLOCK,
+                        format!("已拦截：通过 {cmd} 提权执行破坏性命令"),
+                    );
+                }
+            } else if opens_root_shell(&cmd, &tokens, cmd_index) {
+                // `sudo -i`、裸 `su` 与 `sudo bash` 一样：交互式 shell 里执行什么都看不到
+                return (
+                    RiskLevel::Block,
+                    SEVERITY_BLOCK,
+                    format!("已拦截：通过 {cmd} 提权启动交互式 shell，无法检查它执行的内容"),
+                );
+            }
+            should_confirm = true;
+            severity = severity.max(SEVERITY_ESCALATION);
+            escalation_reason.get_or_insert_with(|| format!("需要确认：命令通过 {cmd} 提权执行"));
+            continue;
+        }
+
+        if cmd == "git" {
+            match git_history_risk(&tokens, cmd_index, current_branch().as_deref()) {
+                Some(GitHistoryRisk::Block(reason)) => {
+                    return (RiskLevel::Block, SEVERITY_BLOCK, reason);
+                }
+                Some(GitHistoryRisk::Confirm(reason)) => {
+                    should_confirm = true;
+                    severity = severity.max(SEVERITY_HISTORY_REWRITE);
+                    history_reason.get_or_insert(reason);
+                    continue;
+                }
+                None => {}
+            }
         }
 
         if is_confirm_command(&cmd, &tokens, cmd_index) {
             should_confirm = true;
+            severity = severity.max(confirm_severity(&cmd, &tokens, cmd_index));
             if confirm_reason.is_none() {
                 confirm_reason = Some("需要确认：该命令可能会修改文件或系统状态".to_string());
             }
@@ -41,12 +199,87 @@ pub fn assess_command(command: &str) -> (RiskLevel, String) {
     }
 
     if should_confirm {
-        (RiskLevel::Confirm, confirm_reason.unwrap_or_default())
+        let reason = escalation_reason
+            .or(history_reason)
+            .or(confirm_reason)
+            .unwrap_or_default();
+        (RiskLevel::Confirm, severity, reason)
     } else {
-        (RiskLevel::Safe, "低风险只读命令".into())
+        (RiskLevel::Safe, 0, "低风险只读命令".into())
     }
 }
 
+/// Confirm 级单条命令的数值风险：工作区内的移动/复制最低，删除与提权最高。
+fn confirm_severity(cmd: &str, tokens: &[String], cmd_index: usize) -> u8 {
+    let args = &tokens[cmd_index + 1..];
+    let operands = || args.iter().filter(|a| !a.starts_with('-'));
+    match cmd {
+        "mv" | "ren" | "cp" | "move-item" | "mi" | "rename-item" | "rni" | "copy-item" | "cpi" => {
+            if operands().all(|a| stays_in_workspace(a)) {
+                SEVERITY_WORKSPACE_MOVE
+            } else {
+                SEVERITY_OUTSIDE_WORKSPACE
+            }
+        }
+        "rm" | "del" | "rmdir" | "remove-item" | "ri" => {
+            let recursive = args.iter().any(|a| {
+                a == "--recursive"
+                    || a.eq_ignore_ascii_case("-recurse")
+                    || (a.starts_with('-')
+                        && !a.starts_with("--")
+                        && a.chars().any(|c| matches!(c, 'r' | 'R')))
+            });
+            if recursive {
+                SEVERITY_RECURSIVE_DELETE
+            } else {
+                SEVERITY_DELETE
+            }
+        }
+        "chmod" | "chown" => SEVERITY_PERMISSIONS,
+        "curl" | "wget" => SEVERITY_NETWORK,
+        "perl" => SEVERITY_ARBITRARY_CODE,
+        "git" => match operands()
+            .next()
+            .map(|s| normalize_command_token(s))
+            .as_deref()
+        {
+            Some("add" | "commit") => SEVERITY_GIT_STAGE,
+            Some("push") => SEVERITY_GIT_PUBLISH,
+            // 丢弃未提交的改动或未跟踪文件，与删除同级
+            Some("clean" | "reset" | "restore") => SEVERITY_DELETE,
+            Some("checkout")
+                if args
+                    .iter()
+                    .any(|a| matches!(a.as_str(), "--" | "." | "-f" | "--force")) =>
+            {
+                SEVERITY_DELETE
+            }
+            _ => SEVERITY_GIT_WORKTREE,
+        },
+        // sed -i、tee 与 PowerShell 的写入命令：就地改写文件
+        "sed" | "tee" | "set-content" | "sc" | "out-file" | "add-content" | "ac" | "new-item"
+        | "ni" | "clear-content" | "clc" | "clear-item" => {
+            if operands().all(|a| stays_in_workspace(a)) {
+                SEVERITY_IN_PLACE_EDIT
+            } else {
+                SEVERITY_OUTSIDE_WORKSPACE
+            }
+        }
+        _ => SEVERITY_UNCLASSIFIED,
+    }
+}
+
+/// 相对路径且不以 `..` 跳出工作区；绝对路径、`~` 与变量展开都视为工作区外。
+fn stays_in_workspace(arg: &str) -> bool {
+    let path = std::path::Path::new(arg);
+    !arg.starts_with(['~', '$', '%'])
+        && path.is_relative()
+        && !path.has_root()
+        && path
+            .components()
+            .all(|c| !matches!(c, std::path::Component::ParentDir))
+}
+
 fn is_confirm_command(cmd: &str, tokens: &[String], cmd_index: usize) -> bool {
     if cmd == "sed" {
         // `sed -n ...` is read-only; only in-place edits need confirmation.
@@ -97,6 +330,9 @@ fn is_confirm_command(cmd: &str, tokens: &[String], cmd_index: usize) -> bool {
             .find(|t| !t.starts_with('-'))
             .map(|s| normalize_command_token(s))
         {
+            if GitSubcommand::from_token(&sub).is_read_only() {
+                return false;
+            }
             return matches!(
                 sub.as_str(),
                 "add"
@@ -120,6 +356,294 @@ fn is_confirm_command(cmd: &str, tokens: &[String], cmd_index: usize) -> bool {
     false
 }
 
+/// 识别 `push --force`、`reset --hard`、`rebase`、`branch -D` 等会改写/丢失历史的 git 操作。
+/// 强制推送到受保护分支直接拦截，`--force-with-lease` 降级为确认；没有写明目标分支
+/// （或目标是 `HEAD`）时按 `current_branch` 判断。
+fn git_history_risk(
+    tokens: &[String],
+    cmd_index: usize,
+    current_branch: Option<&str>,
+) -> Option<GitHistoryRisk> {
+    let sub_index = tokens
+        .iter()
+        .enumerate()
+        .skip(cmd_index + 1)
+        .find(|(_, t)| !t.starts_with('-'))
+        .map(|(i, _)| i)?;
+    let sub = normalize_command_token(&tokens[sub_index]);
+    let args = &tokens[sub_index + 1..];
+    let has = |flags: &[&str]| args.iter().any(|a| flags.contains(&a.as_str()));
+    let confirm = |op: &str| {
+        Some(GitHistoryRisk::Confirm(format!(
+            "需要确认：⚠ 会改写/丢失 git 历史（git {op}）"
+        )))
+    };
+
+    match sub.as_str() {
+        "push" => {
+            let positional: Vec<&str> = args
+                .iter()
+                .map(String::as_str)
+                .filter(|a| !a.starts_with('-'))
+                .collect();
+            let refspecs = positional.get(1..).unwrap_or_default();
+            let lease = args.iter().any(|a| a.starts_with("--force-with-lease"));
+            let forced = has(&["--force", "-f"])
+                || args
+                    .iter()
+                    .any(|a| !a.starts_with("--") && a.starts_with('-') && a.contains('f'))
+                || refspecs.iter().any(|r| r.starts_with('+'));
+            if forced {
+                let protected = protected_branches();
+                let targets: Vec<&str> = if refspecs.is_empty() {
+                    current_branch.into_iter().collect()
+                } else {
+                    refspecs
+                        .iter()
+                        .map(|r| match push_target_branch(r) {
+                            "HEAD" => current_branch.unwrap_or("HEAD"),
+                            branch => branch,
+                        })
+                        .collect()
+                };
+                if let Some(branch) = targets
+                    .into_iter()
+                    .find(|b| protected.iter().any(|p| p == b))
+                {
+                    return Some(GitHistoryRisk::Block(format!(
+                        "已拦截：⚠ 强制推送到受保护分支 {branch} 会覆盖共享历史（如确需覆盖请使用 --force-with-lease）"
+                    )));
+                }
+                return confirm("push --force");
+            }
+            if lease {
+                return confirm("push --force-with-lease");
+            }
+            None
+        }
+        "reset" if has(&["--hard"]) => confirm("reset --hard"),
+        "rebase" => confirm("rebase"),
+        "branch" if has(&["-D"]) || (has(&["-d", "--delete"]) && has(&["-f", "--force"])) => {
+            confirm("branch -D")
+        }
+        "filter-branch" | "filter-repo" => confirm(&sub),
+        _ => None,
+    }
+}
+
+/// refspec 的目标分支名：`+src:refs/heads/main` → `main`。
+fn push_target_branch(refspec: &str) -> &str {
+    let refspec = refspec.trim_start_matches('+');
+    let target = refspec.rsplit_once(':').map_or(refspec, |(_, dst)| dst);
+    target.strip_prefix("refs/heads/").unwrap_or(target)
+}
+
+fn protected_branches() -> Vec<String> {
+    let extra = std::env::var("GOLDBOT_PROTECTED_BRANCHES").unwrap_or_default();
+    DEFAULT_PROTECTED_BRANCHES
+        .iter()
+        .map(|b| b.to_string())
+        .chain(
+            extra
+                .split(',')
+                .map(str::trim)
+                .filter(|b| !b.is_empty())
+                .map(ToString::to_string),
+        )
+        .collect()
+}
+
+/// 取出提权前缀后真正执行的命令（重新拼成 shell 文本以便递归评估）。
+/// `sudo -i` / 裸 `su` 这类只开交互 shell 的写法返回 `None`。
+fn escalated_inner_command(cmd: &str, tokens: &[String], cmd_index: usize) -> Option<String> {
+    let args = &tokens[cmd_index + 1..];
+    if cmd == "su" {
+        // su [-] [user] -c "<command>"
+        let pos = args
+            .iter()
+            .position(|t| t == "-c" || t == "--command" || t.starts_with("--command="))?;
+        return match args[pos].strip_prefix("--command=") {
+            Some(inner) => Some(inner.to_string()),
+            None => args.get(pos + 1).cloned(),
+        };
+    }
+
+    // sudo / doas / pkexec：跳过选项（及带值选项的参数），剩下的就是内层命令
+    let takes_value: &[&str] = match cmd {
+        "sudo" => &["-u", "-g", "-h", "-p", "-C", "-D", "-r", "-t", "-U", "-T"],
+        "doas" => &["-u", "-C"],
+        _ => &["--user"],
+    };
+    let mut i = 0;
+    while i < args.len() && args[i].starts_with('-') {
+        if args[i] == "--" {
+            i += 1;
+            break;
+        }
+        if takes_value.contains(&args[i].as_str()) {
+            i += 1;
+        }
+        i += 1;
+    }
+    if i >= args.len() {
+        return None;
+    }
+    let inner: Vec<String> = args[i..].iter().map(|t| shell_quote(t)).collect();
+    Some(inner.join(" "))
+}
+
+/// 没有内层命令时是否启动交互式 root shell：`sudo -i` / `sudo -s`、`doas -s`、
+/// 裸 `su` / `su -` 和裸 `pkexec`。`sudo -l`、`sudo -v` 这类不执行命令的选项不算。
+fn opens_root_shell(cmd: &str, tokens: &[String], cmd_index: usize) -> bool {
+    let short_flag = |arg: &String, flags: &[char]| {
+        arg.starts_with('-') && !arg.starts_with("--") && arg[1..].contains(flags)
+    };
+    let args = &tokens[cmd_index + 1..];
+    match cmd {
+        "su" | "pkexec" => true,
+        "sudo" => args
+            .iter()
+            .any(|a| a == "--login" || a == "--shell" || short_flag(a, &['i', 's'])),
+        "doas" => args.iter().any(|a| short_flag(a, &['s'])),
+        _ => false,
+    }
+}
+
+/// 内层命令是 shell 时逐层取出 `-c` 后的命令串，不是 shell 时原样返回。
+/// 没有 `-c`（交互式 shell、执行脚本或从 stdin 读取）或嵌套过深时返回该 shell 的名字。
+fn shell_payload(command: &str) -> Result<String, String> {
+    let mut command = command.to_string();
+    for _ in 0..MAX_SHELL_NESTING {
+        let tokens = tokenize_shell(&command);
+        let Some((cmd_index, cmd)) = primary_command(&tokens) else {
+            return Ok(command);
+        };
+        if !SHELL_INTERPRETERS.contains(&cmd.as_str()) {
+            return Ok(command);
+        }
+        command = shell_c_argument(&tokens[cmd_index + 1..]).ok_or(cmd)?;
+    }
+    Err("嵌套 shell".to_string())
+}
+
+/// `bash -c '<cmd>'`、`sh -ec '<cmd>'` 中 `-c` 之后的第一个非选项参数。
+fn shell_c_argument(args: &[String]) -> Option<String> {
+    let mut has_c = false;
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "--" {
+            i += 1;
+            break;
+        }
+        if !(arg.starts_with('-') || arg.starts_with('+')) || arg.len() < 2 {
+            break;
+        }
+        if matches!(arg.as_str(), "-o" | "+o" | "-O" | "+O") {
+            i += 1;
+        } else if !arg.starts_with("--") && arg[1..].contains('c') {
+            has_c = true;
+        }
+        i += 1;
+    }
+    if has_c { args.get(i).cloned() } else { None }
+}
+
+fn shell_quote(token: &str) -> String {
+    if token.is_empty()
+        || token
+            .chars()
+            .any(|c| c.is_whitespace() || "'\"$`;&|<>".contains(c))
+    {
+        format!("'{}'", token.replace('\'', r"'\''"))
+    } else {
+        token.to_string()
+    }
+}
+
+/// 提权后不允许执行的破坏性命令：递归/强制删除、格式化、整盘写入等。
+fn contains_destructive_command(command: &str) -> bool {
+    split_unquoted_segments(command).iter().any(|segment| {
+        let tokens = tokenize_shell(segment);
+        let Some((cmd_index, cmd)) = primary_command(&tokens) else {
+            return false;
+        };
+        match cmd.as_str() {
+            "rm" => tokens.iter().skip(cmd_index + 1).any(|t| {
+                t == "--recursive"
+                    || t == "--force"
+                    || (t.starts_with('-')
+                        && !t.starts_with("--")
+                        && t.chars().any(|c| matches!(c, 'r' | 'R' | 'f')))
+            }),
+            "dd" | "shred" | "wipefs" | "format" | "diskpart" => true,
+            c => c.starts_with("mkfs"),
+        }
+    })
+}
+
+/// 命令的路径参数（含 `--opt=path` 的值与重定向目标）命中 `.goldbotignore` 时返回该参数。
+/// 含通配符的参数按工作区展开后逐一检查。
+fn ignored_path_target(
+    tokens: &[String],
+    cmd_index: usize,
+    access: &AccessIgnore,
+) -> Option<String> {
+    if access.is_empty() {
+        return None;
+    }
+    path_target(tokens, cmd_index, access.root(), |path| {
+        access.is_ignored(path)
+    })
+}
+
+/// 命令的路径参数指向 `.goldbotignore` 本身时返回该参数：不论读写一律拦截，
+/// 否则 `echo > .goldbotignore`、`sed -i` 就能让 Agent 自己解除限制。
+fn ignore_file_target(
+    tokens: &[String],
+    cmd_index: usize,
+    access: &AccessIgnore,
+) -> Option<String> {
+    let root = access.root();
+    path_target(tokens, cmd_index, root, |path| {
+        access::is_ignore_file(root, path)
+    })
+}
+
+/// 第一个满足 `hit` 的路径参数；含通配符的参数按 `root` 展开后逐一检查。
+fn path_target(
+    tokens: &[String],
+    cmd_index: usize,
+    root: &std::path::Path,
+    hit: impl Fn(&std::path::Path) -> bool,
+) -> Option<String> {
+    tokens.iter().skip(cmd_index).find_map(|token| {
+        let target = match token.split_once('=') {
+            Some((flag, value)) if flag.starts_with('-') => value,
+            _ if token.starts_with('-') => return None,
+            _ => token.trim_start_matches(|c: char| {
+                c.is_ascii_digit() || c == '<' || c == '>' || c == '&'
+            }),
+        };
+        if target.is_empty() || target.starts_with('$') {
+            return None;
+        }
+        if hit(std::path::Path::new(target)) {
+            return Some(target.to_string());
+        }
+        if !target.contains(['*', '?', '[']) {
+            return None;
+        }
+        let pattern = root.join(target);
+        glob::glob(&pattern.to_string_lossy())
+            .ok()?
+            .flatten()
+            .take(MAX_GLOB_TARGETS)
+            .any(|p| hit(&p))
+            .then(|| target.to_string())
+    })
+}
+
 fn sed_in_place_edit(tokens: &[String], cmd_index: usize) -> bool {
     tokens
         .iter()
@@ -414,7 +938,11 @@ fn is_safe_redirection_target(target: &str) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::{RiskLevel, assess_command};
+    use super::{
+        GitHistoryRisk, RiskLevel, assess_command, assess_command_severity, git_history_risk,
+        ignore_file_target, ignored_path_target, primary_command, tokenize_shell,
+    };
+    use crate::tools::access::AccessIgnore;
 
     #[test]
     fn rm_requires_confirmation() {
@@ -521,10 +1049,240 @@ mod tests {
         assert_eq!(risk, RiskLevel::Confirm);
     }
 
+    #[test]
+    fn sudo_always_requires_confirmation() {
+        let (risk, reason) = assess_command("sudo apt update");
+        assert_eq!(risk, RiskLevel::Confirm);
+        assert!(reason.contains("sudo"), "unexpected reason: {reason}");
+
+        let (risk, _) = assess_command("doas -u root ls /root");
+        assert_eq!(risk, RiskLevel::Confirm);
+        let (risk, _) = assess_command("sudo -v");
+        assert_eq!(risk, RiskLevel::Confirm);
+    }
+
+    #[test]
+    fn escalation_to_an_interactive_shell_is_blocked() {
+        for command in [
+            "sudo -i",
+            "sudo -s",
+            "sudo -u deploy -i",
+            "su",
+            "su -",
+            "doas -s",
+        ] {
+            let (risk, reason) = assess_command(command);
+            assert_eq!(risk, RiskLevel::Block, "{command}");
+            assert!(
+                reason.contains("交互式 shell"),
+                "unexpected reason: {reason}"
+            );
+        }
+        // 带内层命令时照常评估内层命令
+        let (risk, _) = assess_command("sudo -u deploy ls");
+        assert_eq!(risk, RiskLevel::Confirm);
+        let (risk, _) = assess_command("su - deploy -c 'ls'");
+        assert_eq!(risk, RiskLevel::Confirm);
+    }
+
+    #[test]
+    fn escalated_destructive_command_is_blocked() {
+        let (risk, _) = assess_command("sudo rm -rf /");
+        assert_eq!(risk, RiskLevel::Block);
+        let (risk, _) = assess_command("su -c 'rm -rf /var/lib'");
+        assert_eq!(risk, RiskLevel::Block);
+        let (risk, _) = assess_command("pkexec --user root mkfs.ext4 /dev/sda1");
+        assert_eq!(risk, RiskLevel::Block);
+        // 内层是 shell 时评估 -c 的命令串
+        let (risk, _) = assess_command("sudo bash -c 'rm -rf /var/lib'");
+        assert_eq!(risk, RiskLevel::Block);
+        let (risk, _) = assess_command("sudo -u root sh -ec \"bash -c 'mkfs.ext4 /dev/sda1'\"");
+        assert_eq!(risk, RiskLevel::Block);
+        let (risk, _) = assess_command("sudo bash -c 'apt update'");
+        assert_eq!(risk, RiskLevel::Confirm);
+        // 看不到内容的 shell（脚本、交互式）直接拦截
+        let (risk, reason) = assess_command("sudo bash ./install.sh");
+        assert_eq!(risk, RiskLevel::Block);
+        assert!(reason.contains("bash"), "unexpected reason: {reason}");
+    }
+
+    #[test]
+    fn escalation_inside_chain_requires_confirmation() {
+        let (risk, reason) = assess_command("echo && sudo ls");
+        assert_eq!(risk, RiskLevel::Confirm);
+        assert!(reason.contains("提权"), "unexpected reason: {reason}");
+        let (risk, _) = assess_command("cd /tmp && sudo rm -r build");
+        assert_eq!(risk, RiskLevel::Block);
+    }
+
+    #[test]
+    fn force_push_to_protected_branch_is_blocked() {
+        let (risk, reason) = assess_command("git push --force origin main");
+        assert_eq!(risk, RiskLevel::Block);
+        assert!(reason.contains("main"), "unexpected reason: {reason}");
+        let (risk, _) = assess_command("git push origin +HEAD:refs/heads/master");
+        assert_eq!(risk, RiskLevel::Block);
+        let (risk, _) = assess_command("git push -f origin feature/login");
+        assert_eq!(risk, RiskLevel::Confirm);
+    }
+
+    #[test]
+    fn force_push_without_target_uses_current_branch() {
+        let risk = |cmd: &str, branch: Option<&str>| {
+            let tokens = tokenize_shell(cmd);
+            let (cmd_index, _) = primary_command(&tokens).unwrap();
+            git_history_risk(&tokens, cmd_index, branch)
+        };
+        for cmd in [
+            "git push --force",
+            "git push -f origin",
+            "git push -f origin HEAD",
+        ] {
+            assert!(
+                matches!(risk(cmd, Some("main")), Some(GitHistoryRisk::Block(_))),
+                "{cmd}"
+            );
+            assert!(
+                matches!(risk(cmd, Some("feature")), Some(GitHistoryRisk::Confirm(_))),
+                "{cmd}"
+            );
+        }
+        // 分支未知时保持原来的确认
+        assert!(matches!(
+            risk("git push --force", None),
+            Some(GitHistoryRisk::Confirm(_))
+        ));
+        // 写明的目标分支优先于当前分支
+        assert!(matches!(
+            risk("git push -f origin feature", Some("main")),
+            Some(GitHistoryRisk::Confirm(_))
+        ));
+    }
+
+    #[test]
+    fn force_with_lease_push_requires_confirmation() {
+        let (risk, reason) = assess_command("git push --force-with-lease origin main");
+        assert_eq!(risk, RiskLevel::Confirm);
+        assert!(reason.contains("⚠"), "unexpected reason: {reason}");
+    }
+
+    #[test]
+    fn history_rewriting_git_commands_get_dedicated_warning() {
+        for cmd in [
+            "git reset --hard HEAD~1",
+            "git rebase -i main",
+            "git branch -D old",
+        ] {
+            let (risk, reason) = assess_command(cmd);
+            assert_eq!(risk, RiskLevel::Confirm, "{cmd}");
+            assert!(reason.contains("git 历史"), "{cmd}: {reason}");
+        }
+        let (_, reason) = assess_command("git reset --soft HEAD~1");
+        assert!(!reason.contains("git 历史"));
+    }
+
     #[test]
     fn heredoc_without_redirect_is_safe() {
         let cmd = "cat << 'EOF'\nsudo make install\nEOF";
         let (risk, _) = assess_command(cmd);
         assert_eq!(risk, RiskLevel::Safe);
     }
+
+    #[test]
+    fn commands_targeting_goldbotignore_paths_are_blocked() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-safety-ignore-{nanos}"));
+        std::fs::create_dir_all(ws.join("secrets")).unwrap();
+        std::fs::write(ws.join("secrets/prod.key"), "k").unwrap();
+        std::fs::write(ws.join("README.md"), "r").unwrap();
+        let access = AccessIgnore::parse(&ws, ".env.production\nsecrets/\n");
+
+        let hit = |command: &str| {
+            let tokens = tokenize_shell(command);
+            ignored_path_target(&tokens, 0, &access)
+        };
+        assert_eq!(
+            hit("cat .env.production").as_deref(),
+            Some(".env.production")
+        );
+        assert_eq!(hit("grep -r token ./secrets").as_deref(), Some("./secrets"));
+        assert_eq!(
+            hit("python run.py --config=secrets/prod.key").as_deref(),
+            Some("secrets/prod.key")
+        );
+        assert_eq!(
+            hit("echo x >.env.production").as_deref(),
+            Some(".env.production")
+        );
+        // 通配符展开后命中
+        assert_eq!(hit("cat secret*/*.key").as_deref(), Some("secret*/*.key"));
+        assert_eq!(hit("cat README.md src/main.rs"), None);
+        assert_eq!(hit("ls -la"), None);
+
+        // `.goldbotignore` 本身不能被 shell 命令改写
+        std::fs::write(ws.join(".goldbotignore"), "secrets/\n").unwrap();
+        let ignore_file = |command: &str| {
+            let tokens = tokenize_shell(command);
+            ignore_file_target(&tokens, 0, &access)
+        };
+        assert_eq!(
+            ignore_file("echo >.goldbotignore").as_deref(),
+            Some(".goldbotignore")
+        );
+        assert_eq!(
+            ignore_file("sed -i d ./.goldbotignore").as_deref(),
+            Some("./.goldbotignore")
+        );
+        assert_eq!(
+            ignore_file("truncate -s0 .goldbot*").as_deref(),
+            Some(".goldbot*")
+        );
+        assert_eq!(ignore_file("echo x > notes.txt"), None);
+
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+
+    #[test]
+    fn severity_ranks_commands_within_their_risk_band() {
+        let severity = |cmd: &str| {
+            let (risk, severity, _) = assess_command_severity(cmd);
+            assert!(
+                risk.severity_band().contains(&severity),
+                "{cmd}: {severity} outside {risk:?}"
+            );
+            severity
+        };
+        assert_eq!(severity("ls -la"), 0);
+        assert_eq!(severity("format c:"), 100);
+
+        let in_workspace = severity("mv src/old.rs src/new.rs");
+        let outside = severity("mv build.log /tmp/build.log");
+        let escaping = severity("cp ../secrets.txt .");
+        let redirect = severity("echo hi > notes.txt");
+        let delete = severity("rm notes.txt");
+        let recursive = severity("rm -rf target");
+        let history = severity("git reset --hard HEAD~1");
+        let escalation = severity("sudo apt update");
+        assert!(in_workspace < redirect && redirect < outside);
+        assert_eq!(outside, escaping);
+        assert!(outside < delete && delete < recursive);
+        assert!(recursive < history && history < escalation);
+        assert!(severity("git add -A") < severity("git push origin feature"));
+        // 复合命令取最危险的一段
+        assert_eq!(severity("mv a.txt b.txt && rm -r tmp"), recursive);
+
+        // 任意代码与丢弃改动的 git 操作不能和 `sed -i` 一样低
+        let in_place = severity("sed -i s/a/b/ notes.txt");
+        assert!(redirect < in_place && in_place < outside);
+        assert_eq!(severity("tee notes.txt"), in_place);
+        assert_eq!(severity("tee /etc/hosts"), outside);
+        assert_eq!(severity("perl -e 'system(\"id\")'"), escalation);
+        assert_eq!(severity("git checkout -- ."), delete);
+        assert_eq!(severity("git restore src/main.rs"), delete);
+        assert_eq!(severity("git clean -fd"), delete);
+        assert!(severity("git checkout feature") < delete);
+    }
 }
@@ -28,6 +28,7 @@ pub fn search_files(pattern: &str, path: &str) -> anyhow::Result<SearchResult> {
     let re = Regex::new(pattern)?;
 
     let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let access = crate::tools::access::for_workspace(&cwd);
     let search_root = {
         let p = PathBuf::from(path);
         if p.as_os_str().is_empty() || path == "." {
@@ -45,6 +46,7 @@ pub fn search_files(pattern: &str, path: &str) -> anyhow::Result<SearchResult> {
         .unwrap_or(DEFAULT_SEARCH_TIMEOUT_SECS);
     let deadline = Instant::now() + Duration::from_secs(timeout_secs);
 
+    access.check_read(&search_root)?;
     if search_root.is_file() {
         return Ok(search_single_file(&search_root, &re));
     }
@@ -58,6 +60,7 @@ pub fn search_files(pattern: &str, path: &str) -> anyhow::Result<SearchResult> {
 
     WalkBuilder::new(&search_root)
         .standard_filters(true) // 尊重 .gitignore / .ignore，跳过隐藏文件
+        .filter_entry(move |e| !access.is_ignored(e.path()))
         .build_parallel()
         .run(|| {
             let re = Arc::clone(&re);
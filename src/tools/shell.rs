@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fs,
     io::Read,
     path::{Path, PathBuf},
@@ -9,10 +9,14 @@ use std::{
 };
 
 use crate::memory::Session;
+use crate::tools::artifacts::{artifacts_dir, spill_to_artifact};
 use anyhow::Result;
 use similar::{ChangeTag, TextDiff};
 
 const MAX_OUTPUT_CHARS: usize = 10_000;
+/// 输出超过 `MAX_OUTPUT_CHARS` 时完整写入 artifacts 文件，结果里只保留这么多字节的开头与结尾。
+const SPILL_PREVIEW_HEAD_BYTES: usize = 4_000;
+const SPILL_PREVIEW_TAIL_BYTES: usize = 4_000;
 /// Maximum time (in seconds) a shell command is allowed to run before being
 /// killed.  Can be overridden with the `GOLDBOT_CMD_TIMEOUT` environment
 /// variable.
@@ -30,16 +34,76 @@ static SHELL_CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OperationKind {
     Bash,
+    Git(GitSubcommand),
 }
 
 impl OperationKind {
     fn as_str(self) -> &'static str {
         match self {
             Self::Bash => "Bash",
+            Self::Git(sub) => sub.label(),
         }
     }
 }
 
+/// 常见的 git 子命令；其余子命令归为 `Other`，标签退回 `Git`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitSubcommand {
+    Status,
+    Diff,
+    Log,
+    Show,
+    Commit,
+    Push,
+    Pull,
+    Fetch,
+    Add,
+    Checkout,
+    Branch,
+    Other,
+}
+
+impl GitSubcommand {
+    pub fn from_token(token: &str) -> Self {
+        match token {
+            "status" => Self::Status,
+            "diff" => Self::Diff,
+            "log" => Self::Log,
+            "show" => Self::Show,
+            "commit" => Self::Commit,
+            "push" => Self::Push,
+            "pull" => Self::Pull,
+            "fetch" => Self::Fetch,
+            "add" => Self::Add,
+            "checkout" | "switch" => Self::Checkout,
+            "branch" => Self::Branch,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Status => "Git status",
+            Self::Diff => "Git diff",
+            Self::Log => "Git log",
+            Self::Show => "Git show",
+            Self::Commit => "Git commit",
+            Self::Push => "Git push",
+            Self::Pull => "Git pull",
+            Self::Fetch => "Git fetch",
+            Self::Add => "Git add",
+            Self::Checkout => "Git checkout",
+            Self::Branch => "Git branch",
+            Self::Other => "Git",
+        }
+    }
+
+    /// 只查看仓库状态、不改动工作区或历史的子命令。
+    pub fn is_read_only(self) -> bool {
+        matches!(self, Self::Status | Self::Diff | Self::Log | Self::Show)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CommandIntent {
     pub kind: OperationKind,
@@ -70,6 +134,12 @@ struct FileSignature {
 
 pub fn classify_command(cmd: &str) -> CommandIntent {
     let trimmed = cmd.trim();
+    if let Some(sub) = git_subcommand(trimmed) {
+        return CommandIntent {
+            kind: OperationKind::Git(sub),
+            target: None,
+        };
+    }
     let target = extract_target(trimmed).map(|t| absolutize_target_for_display(&t));
     CommandIntent {
         kind: OperationKind::Bash,
@@ -107,8 +177,20 @@ fn terminate_child_process(child: &mut Child) {
 
 pub fn run_command(cmd: &str) -> Result<CommandResult> {
     let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let before_compare = capture_before_compare(&cwd, cmd);
-    let before = snapshot_files(&cwd);
+    // `.goldbot/exec_env` 只作用于工具命令的子进程，不改 GoldBot 自身的环境
+    let extra_env = crate::memory::profile::exec_env(&cwd);
+    let removed_env = crate::memory::profile::exec_env_removed(&cwd);
+    run_command_with_env(&cwd, cmd, &extra_env, &removed_env)
+}
+
+fn run_command_with_env(
+    cwd: &Path,
+    cmd: &str,
+    extra_env: &BTreeMap<String, String>,
+    removed_env: &[String],
+) -> Result<CommandResult> {
+    let before_compare = capture_before_compare(cwd, cmd);
+    let before = snapshot_files(cwd);
 
     let timeout_secs: u64 = std::env::var("GOLDBOT_CMD_TIMEOUT")
         .ok()
@@ -116,21 +198,25 @@ pub fn run_command(cmd: &str) -> Result<CommandResult> {
         .unwrap_or(DEFAULT_CMD_TIMEOUT_SECS);
     let timeout = Duration::from_secs(timeout_secs);
 
-    let mut child = if cfg!(target_os = "windows") {
-        Command::new("powershell")
-            .args(["-NoProfile", "-Command", cmd])
-            .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-Command", cmd]);
+        command
     } else {
-        Command::new("bash")
-            .args(["-lc", cmd])
-            .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?
+        let mut command = Command::new("bash");
+        command.args(["-lc", cmd]);
+        command
     };
+    command
+        .current_dir(cwd)
+        .envs(extra_env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    for key in removed_env {
+        command.env_remove(key);
+    }
+    let mut child = command.spawn()?;
 
     let start = Instant::now();
     let poll_interval = Duration::from_millis(200);
@@ -188,8 +274,8 @@ pub fn run_command(cmd: &str) -> Result<CommandResult> {
             .unwrap_or(-1)
     };
 
-    let after = snapshot_files(&cwd);
-    let (fs_summary, diffs) = build_fs_summary(&cwd, &before, &after, &before_compare);
+    let after = snapshot_files(cwd);
+    let (fs_summary, diffs) = build_fs_summary(cwd, &before, &after, &before_compare);
 
     // 将文件差异写入当前 session 记忆，方便后续查阅或恢复
     if !diffs.is_empty() {
@@ -225,22 +311,46 @@ pub fn run_command(cmd: &str) -> Result<CommandResult> {
         text = "(no output)".to_string();
     }
 
-    if text.len() > MAX_OUTPUT_CHARS {
-        // Find a valid UTF-8 char boundary at or before MAX_OUTPUT_CHARS.
-        let mut cut = MAX_OUTPUT_CHARS;
-        while !text.is_char_boundary(cut) {
-            cut -= 1;
-        }
-        text.truncate(cut);
-        text.push_str("\n...[truncated]");
-    }
-
     Ok(CommandResult {
         exit_code,
-        output: text,
+        output: spill_large_output(text, &artifacts_dir()),
     })
 }
 
+/// 超长输出不再直接截断：完整内容写到 `dir` 下的文件，结果里是首尾预览加文件路径。
+fn spill_large_output(text: String, dir: &Path) -> String {
+    if text.len() <= MAX_OUTPUT_CHARS {
+        return text;
+    }
+    // 预览在行边界处切开；单行超长时按字符边界切
+    let mut head_end = floor_char_boundary(&text, SPILL_PREVIEW_HEAD_BYTES);
+    if let Some(nl) = text[..head_end].rfind('\n') {
+        head_end = nl;
+    }
+    let mut tail_start = floor_char_boundary(&text, text.len() - SPILL_PREVIEW_TAIL_BYTES);
+    if let Some(nl) = text[tail_start..].find('\n') {
+        tail_start += nl + 1;
+    }
+    let middle = &text[head_end..tail_start];
+    let omitted = match middle.matches('\n').count() {
+        0 | 1 => format!("{} bytes", middle.len()),
+        n => format!("{} lines", n - 1),
+    };
+    let preview = format!(
+        "{}\n...[{omitted} omitted]...\n{}",
+        &text[..head_end],
+        text[tail_start..].trim_end()
+    );
+    spill_to_artifact(&text, dir, preview)
+}
+
+fn floor_char_boundary(s: &str, mut idx: usize) -> usize {
+    while !s.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    idx
+}
+
 fn looks_update(trimmed: &str, lower: &str) -> bool {
     contains_write_redirection(trimmed)
         || lower.contains("<<")
@@ -375,6 +485,28 @@ fn extract_target(cmd: &str) -> Option<String> {
     normalize_target(candidate)
 }
 
+/// 单条 `git <subcommand> ...` 命令的子命令；跳过 `-C <path>` / `-c k=v` / `--no-pager`
+/// 等全局选项。管道或多条命令串联时返回 None，仍按普通 Bash 展示。
+pub fn git_subcommand(cmd: &str) -> Option<GitSubcommand> {
+    if ["&&", ";", "|", "\n"].iter().any(|sep| cmd.contains(sep)) {
+        return None;
+    }
+    let mut tokens = cmd.split_whitespace();
+    if tokens.next()? != "git" {
+        return None;
+    }
+    while let Some(token) = tokens.next() {
+        match token {
+            "-C" | "-c" | "--git-dir" | "--work-tree" => {
+                tokens.next();
+            }
+            t if t.starts_with('-') => {}
+            t => return Some(GitSubcommand::from_token(t)),
+        }
+    }
+    None
+}
+
 fn extract_git_target<'a>(tokens: &'a [&'a str]) -> Option<&'a str> {
     let mut i = 1usize;
     while i < tokens.len() && tokens[i].starts_with('-') {
@@ -666,6 +798,36 @@ fn read_preview(root: &Path, rel: &Path) -> Option<String> {
     Some(out)
 }
 
+/// 写操作命令执行前能预见的写入目标：参数、引号中的路径与重定向目标里已存在的工作区文件。
+/// 非 git 工作区据此在执行前保存原内容，供 `/undo` 恢复。
+pub(crate) fn predicted_write_targets(root: &Path, cmd: &str) -> Vec<PathBuf> {
+    let trimmed = cmd.trim();
+    if !looks_update(trimmed, &trimmed.to_lowercase()) {
+        return Vec::new();
+    }
+    let quoted = regex::Regex::new(r#"["']([^"']+)["']"#).expect("valid quoted path regex");
+    let candidates = extract_target(cmd)
+        .into_iter()
+        .chain(trimmed.split_whitespace().filter_map(normalize_target))
+        .chain(
+            quoted
+                .captures_iter(cmd)
+                .map(|caps| caps[1].to_string())
+                .filter(|p| !p.starts_with('-') && !p.contains('$')),
+        );
+    let mut targets: Vec<PathBuf> = Vec::new();
+    for candidate in candidates {
+        let abs = absolutize_for_runtime(root, &candidate);
+        let inside = abs
+            .strip_prefix(root)
+            .is_ok_and(|rel| !should_skip(rel) && !rel.components().any(|c| c.as_os_str() == ".."));
+        if inside && abs.is_file() && !targets.contains(&abs) {
+            targets.push(abs);
+        }
+    }
+    targets
+}
+
 fn capture_before_compare(root: &Path, cmd: &str) -> HashMap<PathBuf, String> {
     let mut out = HashMap::new();
 
@@ -790,11 +952,138 @@ pub(crate) fn render_unified_diff(before: &str, after: &str, line_offset: usize)
 
 #[cfg(test)]
 mod tests {
-    use super::{OperationKind, classify_command};
+    use super::{
+        GitSubcommand, MAX_OUTPUT_CHARS, OperationKind, classify_command, predicted_write_targets,
+        run_command_with_env, spill_large_output,
+    };
 
     #[test]
-    fn classify_any_command_is_bash() {
-        let intent = classify_command("git status");
+    fn predicted_write_targets_cover_existing_workspace_files_of_write_commands() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-write-targets-{nanos}"));
+        std::fs::create_dir_all(&ws).unwrap();
+        for name in ["a.txt", "b.txt", "notes.md"] {
+            std::fs::write(ws.join(name), "x").unwrap();
+        }
+
+        assert_eq!(
+            predicted_write_targets(&ws, "sed -i 's/x/y/' a.txt"),
+            vec![ws.join("a.txt")]
+        );
+        assert_eq!(
+            predicted_write_targets(&ws, "mv a.txt b.txt"),
+            vec![ws.join("b.txt"), ws.join("a.txt")]
+        );
+        assert_eq!(
+            predicted_write_targets(&ws, "echo hi >> 'notes.md'"),
+            vec![ws.join("notes.md")]
+        );
+        // 只读命令、工作区外与尚不存在的路径不记录
+        assert!(predicted_write_targets(&ws, "cat a.txt").is_empty());
+        assert!(predicted_write_targets(&ws, "rm ../a.txt new.txt").is_empty());
+
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+
+    #[test]
+    fn over_budget_output_is_spilled_to_artifact_file() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("goldbot-artifacts-{nanos}"));
+
+        // 预算内的输出原样返回，不写文件
+        assert_eq!(spill_large_output("ok\n".to_string(), &dir), "ok\n");
+        assert!(!dir.exists());
+
+        let full: String = (1..=2000).map(|i| format!("line {i}\n")).collect();
+        let result = spill_large_output(full.clone(), &dir);
+        assert!(result.len() < MAX_OUTPUT_CHARS);
+        assert!(result.starts_with("line 1\nline 2\n"));
+        assert!(result.contains("line 2000\n(full output saved to "));
+        assert!(result.contains(" lines omitted]..."));
+
+        let files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert_eq!(std::fs::read_to_string(&files[0]).unwrap(), full);
+        let reference = format!("(full output saved to {}, 2000 lines;", files[0].display());
+        assert!(result.contains(&reference), "{result}");
+        assert!(result.contains("<offset>/<limit>"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn classify_non_git_command_is_bash() {
+        let intent = classify_command("cargo build");
         assert_eq!(intent.kind, OperationKind::Bash);
+        assert_eq!(intent.label(), "Bash");
+        // 串联命令里即便有 git 也按普通 Bash 展示
+        let chained = classify_command("git add -A && git commit -m wip");
+        assert_eq!(chained.kind, OperationKind::Bash);
+    }
+
+    #[test]
+    fn classify_git_subcommands_into_specific_labels() {
+        let cases = [
+            ("git status", "Git status"),
+            ("git status --short", "Git status"),
+            ("git --no-pager diff HEAD -- src/main.rs", "Git diff"),
+            ("git -C /tmp/repo log --oneline -5", "Git log"),
+            ("git -c core.pager=cat show HEAD", "Git show"),
+            ("git commit -qm \"fix parser\"", "Git commit"),
+            ("git push origin main", "Git push"),
+            ("git switch -c topic", "Git checkout"),
+            ("git stash", "Git"),
+        ];
+        for (cmd, label) in cases {
+            assert_eq!(classify_command(cmd).label(), label, "{cmd}");
+        }
+        assert_eq!(
+            classify_command("git diff").kind,
+            OperationKind::Git(GitSubcommand::Diff)
+        );
+        assert!(GitSubcommand::Status.is_read_only());
+        assert!(!GitSubcommand::Push.is_read_only());
+    }
+
+    #[test]
+    fn run_command_children_see_exec_env() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-exec-env-{nanos}"));
+        std::fs::create_dir_all(ws.join(".goldbot")).unwrap();
+        std::fs::write(
+            ws.join(".goldbot/exec_env"),
+            "# 注释\nGOLDBOT_EXEC_ENV_TEST=\"hello world\"\nexport DEPLOY_TOKEN=abc123\n",
+        )
+        .unwrap();
+
+        let env = crate::memory::profile::exec_env(&ws);
+        assert_eq!(env["GOLDBOT_EXEC_ENV_TEST"], "hello world");
+        let result =
+            run_command_with_env(&ws, "echo \"env:$GOLDBOT_EXEC_ENV_TEST\"", &env, &[]).unwrap();
+        assert_eq!(result.exit_code, 0);
+        assert!(
+            result.output.contains("env:hello world"),
+            "{}",
+            result.output
+        );
+        // 只注入子进程，GoldBot 自身环境不变
+        assert!(std::env::var("GOLDBOT_EXEC_ENV_TEST").is_err());
+
+        let shown = crate::memory::profile::describe_exec_env(&env);
+        assert!(shown.contains(&"DEPLOY_TOKEN=[REDACTED]".to_string()));
+        assert!(shown.contains(&"GOLDBOT_EXEC_ENV_TEST=hello world".to_string()));
+        let _ = std::fs::remove_dir_all(&ws);
     }
 }
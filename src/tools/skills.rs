@@ -55,17 +55,15 @@ pub fn create_skill_assist_prompt_appendix() -> String {
 }
 
 /// Discover all skills. Priority: project-local → GoldBot own → other global.
-/// First occurrence of each name wins.
-pub fn discover_skills() -> Vec<Skill> {
+/// First occurrence of each name wins. Skills disabled by the active profile are dropped.
+pub fn discover_skills(workspace: &Path) -> Vec<Skill> {
     let mut skills = Vec::new();
     let mut seen: HashSet<String> = HashSet::new();
 
-    // Project-local: walk from cwd up to git root.
-    if let Ok(cwd) = std::env::current_dir() {
-        for dir in walk_to_git_root(&cwd) {
-            for sub in LOCAL_SUBDIRS {
-                scan_dir(&dir.join(sub), &mut skills, &mut seen);
-            }
+    // Project-local: walk from the workspace up to git root.
+    for dir in walk_to_git_root(workspace) {
+        for sub in LOCAL_SUBDIRS {
+            scan_dir(&dir.join(sub), &mut skills, &mut seen);
         }
     }
 
@@ -79,6 +77,8 @@ pub fn discover_skills() -> Vec<Skill> {
         }
     }
 
+    let disabled = crate::memory::profile::disabled_skills(workspace);
+    skills.retain(|s| !disabled.contains(&s.name));
     skills
 }
 
@@ -0,0 +1,395 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// 单个任务快照的总大小上限（字节），超过后不再为新文件保存副本。
+const ENV_SNAPSHOT_MAX_BYTES: &str = "GOLDBOT_SNAPSHOT_MAX_BYTES";
+const DEFAULT_SNAPSHOT_MAX_BYTES: u64 = 50 * 1024 * 1024;
+/// 最多保留的快照目录数，更早的在新快照落盘时清理。
+const MAX_KEPT_SNAPSHOTS: usize = 20;
+const MANIFEST_FILE: &str = "manifest.json";
+const FILES_DIR: &str = "files";
+
+/// 快照根目录：`~/.goldbot/snapshots/`，每个任务一个子目录。
+pub(crate) fn snapshots_dir() -> PathBuf {
+    crate::tools::mcp::goldbot_home_dir().join("snapshots")
+}
+
+pub(crate) fn snapshot_max_bytes() -> u64 {
+    std::env::var(ENV_SNAPSHOT_MAX_BYTES)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_SNAPSHOT_MAX_BYTES)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Manifest {
+    workspace: PathBuf,
+    task: String,
+    entries: Vec<SnapshotEntry>,
+    /// 任务结束时记录过各文件的 `left_hash`；之后的改动会让 `/undo` 拒绝覆盖。
+    #[serde(default)]
+    sealed: bool,
+}
+
+/// 一个被编辑文件在任务开始前的状态。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SnapshotEntry {
+    path: PathBuf,
+    /// 编辑前文件是否存在；不存在时撤销即删除该文件。
+    existed: bool,
+    /// 原内容的 FNV-1a 哈希，恢复前校验副本是否完好。
+    hash: u64,
+    size: u64,
+    /// 任务结束时文件内容的哈希；文件已不存在时为 None。
+    #[serde(default)]
+    left_hash: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CaptureOutcome {
+    Captured,
+    /// 本任务已记录过该文件（只保留第一次编辑前的版本）。
+    AlreadyCaptured,
+    /// 超过快照大小上限，未保存副本。
+    OverCap,
+}
+
+/// 非 git 工作区中当前任务的编辑前快照。第一次捕获时才创建目录。
+pub(crate) struct TaskSnapshot {
+    root: PathBuf,
+    dir: PathBuf,
+    manifest: Manifest,
+    bytes: u64,
+    max_bytes: u64,
+}
+
+impl TaskSnapshot {
+    pub fn new(root: &Path, workspace: &Path, task: &str, max_bytes: u64) -> Self {
+        let id = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+        Self {
+            root: root.to_path_buf(),
+            dir: root.join(id),
+            manifest: Manifest {
+                workspace: workspace.to_path_buf(),
+                task: task.to_string(),
+                entries: Vec::new(),
+                sealed: false,
+            },
+            bytes: 0,
+            max_bytes,
+        }
+    }
+
+    /// 在写入 `path` 之前调用：保存其当前内容（或记录它尚不存在）。
+    pub fn capture(&mut self, path: &Path) -> Result<CaptureOutcome> {
+        if self.manifest.entries.iter().any(|e| e.path == path) {
+            return Ok(CaptureOutcome::AlreadyCaptured);
+        }
+        let original = match fs::read(path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to read `{}`", path.display()));
+            }
+        };
+        let size = original.as_ref().map_or(0, |b| b.len() as u64);
+        if self.bytes + size > self.max_bytes {
+            return Ok(CaptureOutcome::OverCap);
+        }
+        self.push_entry(path, original)?;
+        Ok(CaptureOutcome::Captured)
+    }
+
+    /// shell 命令执行后调用：记录命令新建、执行前没能预见的文件，撤销时删除。
+    pub fn record_created(&mut self, path: &Path) -> Result<()> {
+        if !path.is_file() || self.manifest.entries.iter().any(|e| e.path == path) {
+            return Ok(());
+        }
+        self.push_entry(path, None)
+    }
+
+    /// 任务结束时记下各文件的当前哈希，之后被改过的文件 `/undo` 不会直接覆盖。
+    pub fn seal(&mut self) -> Result<()> {
+        if self.manifest.sealed || self.manifest.entries.is_empty() {
+            return Ok(());
+        }
+        for entry in &mut self.manifest.entries {
+            entry.left_hash = current_hash(&entry.path);
+        }
+        self.manifest.sealed = true;
+        self.write_manifest()
+    }
+
+    fn push_entry(&mut self, path: &Path, original: Option<Vec<u8>>) -> Result<()> {
+        let size = original.as_ref().map_or(0, |b| b.len() as u64);
+        let first = self.manifest.entries.is_empty();
+        let files = self.dir.join(FILES_DIR);
+        fs::create_dir_all(&files)
+            .with_context(|| format!("failed to create `{}`", files.display()))?;
+        let index = self.manifest.entries.len();
+        if let Some(bytes) = &original {
+            let copy = files.join(index.to_string());
+            fs::write(&copy, bytes)
+                .with_context(|| format!("failed to write `{}`", copy.display()))?;
+        }
+        self.manifest.entries.push(SnapshotEntry {
+            path: path.to_path_buf(),
+            existed: original.is_some(),
+            hash: original.as_deref().map_or(0, fnv1a),
+            size,
+            left_hash: None,
+        });
+        self.bytes += size;
+        // 任务继续编辑后需要重新记录结束时的状态
+        self.manifest.sealed = false;
+        // 每次捕获后都落盘清单，崩溃后仍可 /undo
+        self.write_manifest()?;
+        if first {
+            prune_snapshots(&self.root, MAX_KEPT_SNAPSHOTS);
+        }
+        Ok(())
+    }
+
+    fn write_manifest(&self) -> Result<()> {
+        let manifest = self.dir.join(MANIFEST_FILE);
+        fs::write(&manifest, serde_json::to_string_pretty(&self.manifest)?)
+            .with_context(|| format!("failed to write `{}`", manifest.display()))
+    }
+}
+
+fn current_hash(path: &Path) -> Option<u64> {
+    fs::read(path).ok().map(|bytes| fnv1a(&bytes))
+}
+
+/// `/undo` 的结果：恢复的文件、删除的新建文件、因副本缺失或损坏而跳过的文件。
+/// `changed` 非空时表示任务结束后这些文件又被改过，本次没有做任何恢复。
+#[derive(Debug, Default)]
+pub(crate) struct UndoReport {
+    pub task: String,
+    pub restored: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub skipped: Vec<PathBuf>,
+    pub changed: Vec<PathBuf>,
+}
+
+/// 恢复 `workspace` 最近一次任务快照并删除该快照；没有快照时返回 None。
+/// 任务结束后有文件又被改过时不做恢复、保留快照，除非 `force`。
+pub(crate) fn undo_latest(
+    root: &Path,
+    workspace: &Path,
+    force: bool,
+) -> Result<Option<UndoReport>> {
+    let Some((dir, manifest)) = snapshot_dirs(root)
+        .into_iter()
+        .rev()
+        .filter_map(|dir| read_manifest(&dir).map(|m| (dir, m)))
+        .find(|(_, m)| m.workspace == workspace)
+    else {
+        return Ok(None);
+    };
+
+    let mut report = UndoReport {
+        task: manifest.task.clone(),
+        ..UndoReport::default()
+    };
+    if manifest.sealed && !force {
+        report.changed = manifest
+            .entries
+            .iter()
+            .filter(|entry| current_hash(&entry.path) != entry.left_hash)
+            .map(|entry| entry.path.clone())
+            .collect();
+        if !report.changed.is_empty() {
+            return Ok(Some(report));
+        }
+    }
+    for (index, entry) in manifest.entries.iter().enumerate() {
+        if !entry.existed {
+            match fs::remove_file(&entry.path) {
+                Ok(()) => report.removed.push(entry.path.clone()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("failed to remove `{}`", entry.path.display()));
+                }
+            }
+            continue;
+        }
+        let copy = dir.join(FILES_DIR).join(index.to_string());
+        match fs::read(&copy) {
+            Ok(bytes) if fnv1a(&bytes) == entry.hash && bytes.len() as u64 == entry.size => {
+                if let Some(parent) = entry.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&entry.path, bytes)
+                    .with_context(|| format!("failed to restore `{}`", entry.path.display()))?;
+                report.restored.push(entry.path.clone());
+            }
+            _ => report.skipped.push(entry.path.clone()),
+        }
+    }
+    fs::remove_dir_all(&dir).with_context(|| format!("failed to remove `{}`", dir.display()))?;
+    Ok(Some(report))
+}
+
+/// 按名称（即创建时间）升序排列的快照目录。
+fn snapshot_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+fn read_manifest(dir: &Path) -> Option<Manifest> {
+    let raw = fs::read_to_string(dir.join(MANIFEST_FILE)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn prune_snapshots(root: &Path, keep: usize) {
+    let dirs = snapshot_dirs(root);
+    for dir in dirs.iter().take(dirs.len().saturating_sub(keep)) {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CaptureOutcome, TaskSnapshot, prune_snapshots, snapshot_dirs, undo_latest};
+
+    fn temp_dir(tag: &str) -> std::path::PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("goldbot-snapshot-{tag}-{nanos}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn undo_restores_modified_file_and_removes_created_one() {
+        let root = temp_dir("root");
+        let workspace = temp_dir("ws");
+        let modified = workspace.join("notes.txt");
+        let created = workspace.join("sub").join("new.rs");
+        std::fs::write(&modified, "original\n").unwrap();
+
+        let mut snapshot = TaskSnapshot::new(&root, &workspace, "edit notes", 1024);
+        assert_eq!(
+            snapshot.capture(&modified).unwrap(),
+            CaptureOutcome::Captured
+        );
+        std::fs::write(&modified, "first edit\n").unwrap();
+        // 同一任务内再次编辑只保留任务开始前的版本
+        assert_eq!(
+            snapshot.capture(&modified).unwrap(),
+            CaptureOutcome::AlreadyCaptured
+        );
+        std::fs::write(&modified, "second edit\n").unwrap();
+        assert_eq!(
+            snapshot.capture(&created).unwrap(),
+            CaptureOutcome::Captured
+        );
+        std::fs::create_dir_all(created.parent().unwrap()).unwrap();
+        std::fs::write(&created, "fn main() {}\n").unwrap();
+
+        // 其他工作区没有快照
+        assert!(undo_latest(&root, &root, false).unwrap().is_none());
+
+        let report = undo_latest(&root, &workspace, false)
+            .unwrap()
+            .expect("snapshot");
+        assert_eq!(report.task, "edit notes");
+        assert_eq!(report.restored, vec![modified.clone()]);
+        assert_eq!(report.removed, vec![created.clone()]);
+        assert!(report.skipped.is_empty());
+        assert_eq!(std::fs::read_to_string(&modified).unwrap(), "original\n");
+        assert!(!created.exists());
+        // 撤销后快照被删除，不会重复恢复
+        assert!(undo_latest(&root, &workspace, false).unwrap().is_none());
+
+        let _ = std::fs::remove_dir_all(&root);
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
+
+    #[test]
+    fn undo_refuses_files_changed_after_the_task_unless_forced() {
+        let root = temp_dir("sealed");
+        let workspace = temp_dir("sealed-ws");
+        let edited = workspace.join("config.toml");
+        let generated = workspace.join("out.log");
+        std::fs::write(&edited, "a = 1\n").unwrap();
+
+        let mut snapshot = TaskSnapshot::new(&root, &workspace, "shell edit", 1024);
+        snapshot.capture(&edited).unwrap();
+        std::fs::write(&edited, "a = 2\n").unwrap();
+        // shell 命令新建的文件在执行后补记
+        std::fs::write(&generated, "log\n").unwrap();
+        snapshot.record_created(&generated).unwrap();
+        snapshot.seal().unwrap();
+
+        // 任务结束后用户又改了文件：拒绝撤销，快照保留
+        std::fs::write(&edited, "a = 3\n").unwrap();
+        let report = undo_latest(&root, &workspace, false)
+            .unwrap()
+            .expect("snapshot");
+        assert_eq!(report.changed, vec![edited.clone()]);
+        assert!(report.restored.is_empty() && report.removed.is_empty());
+        assert_eq!(std::fs::read_to_string(&edited).unwrap(), "a = 3\n");
+
+        let report = undo_latest(&root, &workspace, true)
+            .unwrap()
+            .expect("snapshot");
+        assert_eq!(report.restored, vec![edited.clone()]);
+        assert_eq!(report.removed, vec![generated.clone()]);
+        assert_eq!(std::fs::read_to_string(&edited).unwrap(), "a = 1\n");
+
+        let _ = std::fs::remove_dir_all(&root);
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
+
+    #[test]
+    fn capture_respects_size_cap_and_old_snapshots_are_pruned() {
+        let root = temp_dir("cap");
+        let workspace = temp_dir("cap-ws");
+        let big = workspace.join("big.bin");
+        let small = workspace.join("small.txt");
+        std::fs::write(&big, vec![b'x'; 64]).unwrap();
+        std::fs::write(&small, "ok").unwrap();
+
+        let mut snapshot = TaskSnapshot::new(&root, &workspace, "cap", 16);
+        assert_eq!(snapshot.capture(&big).unwrap(), CaptureOutcome::OverCap);
+        assert_eq!(snapshot.capture(&small).unwrap(), CaptureOutcome::Captured);
+        assert!(snapshot_dirs(&root).len() == 1);
+
+        for i in 0..3 {
+            std::fs::create_dir_all(root.join(format!("0000-old-{i}"))).unwrap();
+        }
+        prune_snapshots(&root, 2);
+        let left = snapshot_dirs(&root);
+        assert_eq!(left.len(), 2);
+        // 保留最新的（含本次任务的快照）
+        assert!(left.contains(&snapshot.dir));
+
+        let _ = std::fs::remove_dir_all(&root);
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
+}
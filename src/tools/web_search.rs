@@ -1,6 +1,8 @@
 use anyhow::{Result, anyhow};
 use serde_json::Value;
 
+use crate::tools::artifacts::{artifacts_dir, spill_truncated};
+
 const BOCHA_API_URL: &str = "https://api.bocha.cn/v1/web-search";
 const MAX_RESULT_CHARS: usize = 8_000;
 
@@ -38,11 +40,11 @@ pub fn search(query: &str) -> Result<SearchResult> {
     let json: Value =
         serde_json::from_str(&text).map_err(|e| anyhow!("Failed to parse Bocha response: {e}"))?;
 
-    let mut output = format_results(&json, query);
-    if output.len() > MAX_RESULT_CHARS {
-        output.truncate(MAX_RESULT_CHARS);
-        output.push_str("\n...[truncated]");
-    }
+    let output = spill_truncated(
+        format_results(&json, query),
+        MAX_RESULT_CHARS,
+        &artifacts_dir(),
+    );
 
     Ok(SearchResult { output })
 }
@@ -52,6 +52,38 @@ impl InputQueue {
     }
 }
 
+/// compact 确认菜单的来源：用户 `/compact-preview`，或 `GOLDBOT_COMPACT_CONFIRM` 下的自动压缩。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CompactConfirm {
+    Manual,
+    Auto,
+}
+
+/// 新建的 MCP 服务端启动校验失败后，可撤销的那次配置写入。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct McpRollback {
+    pub name: String,
+    pub path: std::path::PathBuf,
+    /// 写入前的文件内容；None 表示文件原本不存在
+    pub previous: Option<String>,
+}
+
+/// 确认菜单超时后的自动选择；出于安全考虑永远不会自动 Execute。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConfirmTimeoutAction {
+    Skip,
+    Abort,
+}
+
+impl ConfirmTimeoutAction {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Skip => "Skip",
+            Self::Abort => "Abort",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     Normal,
@@ -96,6 +128,51 @@ impl AssistMode {
     }
 }
 
+/// 实时事件流的详细程度（`GOLDBOT_VERBOSITY`，Ctrl+O 循环切换）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verbosity {
+    /// 只显示工具调用与结果，隐藏思考
+    Quiet,
+    #[default]
+    Normal,
+    /// 思考内容完整内联显示
+    Verbose,
+}
+
+impl Verbosity {
+    pub fn from_env() -> Self {
+        std::env::var("GOLDBOT_VERBOSITY")
+            .ok()
+            .and_then(|raw| Self::parse(&raw))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "quiet" => Some(Self::Quiet),
+            "normal" => Some(Self::Normal),
+            "verbose" => Some(Self::Verbose),
+            _ => None,
+        }
+    }
+
+    pub fn cycle(self) -> Self {
+        match self {
+            Self::Quiet => Self::Normal,
+            Self::Normal => Self::Verbose,
+            Self::Verbose => Self::Quiet,
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::Quiet => "quiet",
+            Self::Normal => "normal",
+            Self::Verbose => "verbose",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GeQuestionStep {
     Purpose,
@@ -129,6 +206,8 @@ pub enum ExecutorOutcome {
     Failed,
     BlockedConfirm,
     BlockedSafety,
+    /// GE hard exit 中止了这一步。
+    Cancelled,
 }
 
 impl ExecutorOutcome {
@@ -138,6 +217,7 @@ impl ExecutorOutcome {
             Self::Failed => "failed",
             Self::BlockedConfirm => "blocked_confirm",
             Self::BlockedSafety => "blocked_safety",
+            Self::Cancelled => "cancelled",
         }
     }
 }
@@ -163,6 +243,7 @@ pub enum AuditEventKind {
     Validation,
     TodoChecked,
     TodoDeferred,
+    RateLimitWait,
     Error,
 }
 
@@ -188,6 +269,7 @@ impl AuditEventKind {
             Self::Validation => "validation",
             Self::TodoChecked => "todo_checked",
             Self::TodoDeferred => "todo_deferred",
+            Self::RateLimitWait => "rate_limit_wait",
             Self::Error => "error",
         }
     }
@@ -205,6 +287,13 @@ pub enum Event {
     PhaseSummary {
         text: String,
     },
+    /// `<plan>` 中解析出的编号步骤；Agent 再次发出 `<plan>` 时原地更新，勾选已完成的步骤。
+    Plan {
+        /// 每个步骤的文本；首行为标题，其余行为细节。
+        steps: Vec<String>,
+        /// 与 `steps` 一一对应，步骤前写了 `[x]` 的为已完成。
+        done: Vec<bool>,
+    },
     ToolCall {
         label: String,
         command: String,
@@ -216,6 +305,10 @@ pub enum Event {
         exit_code: i32,
         output: String,
     },
+    /// 调试用：本轮 LLM 的原始响应（`GOLDBOT_SHOW_RAW=1` 或 /raw 开启时记录）。
+    RawResponse {
+        text: String,
+    },
     NeedsConfirmation {
         command: String,
         #[allow(dead_code)]
@@ -228,6 +321,8 @@ pub enum Event {
     ConversationCompacted {
         summary: String,
         messages_dropped: usize,
+        /// 压缩前从被折叠消息中补写入长期记忆的条数。
+        notes_flushed: usize,
     },
 }
 
@@ -259,6 +354,10 @@ pub enum LlmAction {
     Skill {
         name: String,
     },
+    /// 命令因缺少凭据失败时向用户索取环境变量（遮罩输入，值不进入对话）。
+    AskSecret {
+        name: String,
+    },
     CreateMcp {
         config: Value,
     },
@@ -283,6 +382,11 @@ pub enum LlmAction {
         pattern: String,
         path: String,
     },
+    DirectoryTree {
+        path: String,
+        depth: Option<usize>,
+        sizes: bool,
+    },
     Task {
         description: String,
         subagent_type: String,
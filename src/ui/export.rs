@@ -0,0 +1,290 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::Local;
+
+use crate::App;
+use crate::consensus::audit::parse_entries;
+use crate::consensus::model::consensus_file_path;
+use crate::types::Event;
+use crate::ui::ge::is_ge_mode;
+use crate::ui::screen::strip_ansi;
+
+/// 导出时单条工具结果最多保留的行数，超出部分只记录省略行数。
+const MAX_EXPORT_RESULT_LINES: usize = 80;
+/// GE 导出时附带的最近审计记录条数。
+const MAX_EXPORT_AUDIT_ENTRIES: usize = 20;
+
+/// 默认导出路径：`~/.goldbot/exports/<timestamp>.md`
+pub(crate) fn default_export_path() -> PathBuf {
+    crate::tools::mcp::goldbot_home_dir()
+        .join("exports")
+        .join(format!("{}.md", Local::now().format("%Y%m%d-%H%M%S")))
+}
+
+/// `/export [path]`：普通模式导出当前任务的事件流与最终总结；
+/// GE 模式导出 CONSENSUS.md 与审计摘要。返回实际写入的路径。
+pub(crate) fn export_transcript(app: &App, path: Option<&str>) -> Result<PathBuf> {
+    let path = match path.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(p) => resolve_export_path(&app.workspace, p),
+        None => default_export_path(),
+    };
+    let markdown = if is_ge_mode(app.mode) {
+        render_ge_markdown(&app.workspace)?
+    } else {
+        render_transcript_markdown(&app.task_events, app.final_summary.as_deref())
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create `{}`", parent.display()))?;
+    }
+    fs::write(&path, markdown).with_context(|| format!("failed to write `{}`", path.display()))?;
+    Ok(path)
+}
+
+fn resolve_export_path(workspace: &Path, raw: &str) -> PathBuf {
+    let path = match raw.strip_prefix("~/") {
+        Some(rest) => crate::tools::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| PathBuf::from(raw)),
+        None => PathBuf::from(raw),
+    };
+    if path.is_absolute() {
+        path
+    } else {
+        workspace.join(path)
+    }
+}
+
+/// 把任务事件渲染为 Markdown：任务为标题、思考为引用、工具调用/结果为代码块，
+/// 最终总结原样附在末尾。所有文本先去掉 ANSI 转义。
+pub(crate) fn render_transcript_markdown(events: &[Event], final_summary: Option<&str>) -> String {
+    let mut out = String::new();
+    let mut last_final: Option<&str> = None;
+    for event in events {
+        match event {
+            Event::UserTask { text } => {
+                let text = strip_ansi(text);
+                let mut lines = text.trim().lines();
+                let title = lines.next().unwrap_or("Task");
+                out.push_str(&format!("## {}\n\n", title.trim()));
+                let rest: Vec<&str> = lines.collect();
+                if !rest.is_empty() {
+                    out.push_str(rest.join("\n").trim());
+                    out.push_str("\n\n");
+                }
+            }
+            Event::Thinking { text } => {
+                for line in strip_ansi(text).trim().lines() {
+                    if line.trim().is_empty() {
+                        out.push_str(">\n");
+                    } else {
+                        out.push_str(&format!("> {line}\n"));
+                    }
+                }
+                out.push('\n');
+            }
+            Event::PhaseSummary { text } => {
+                out.push_str(&format!("**{}**\n\n", strip_ansi(text).trim()));
+            }
+            Event::Plan { steps, done } => {
+                out.push_str("**Plan**\n\n");
+                for (i, step) in steps.iter().enumerate() {
+                    let mark = if done.get(i).copied().unwrap_or(false) {
+                        "x"
+                    } else {
+                        " "
+                    };
+                    let step = strip_ansi(step);
+                    let mut step_lines = step.trim().lines();
+                    let title = step_lines.next().unwrap_or("");
+                    out.push_str(&format!("{}. [{mark}] {title}\n", i + 1));
+                    for detail in step_lines {
+                        out.push_str(&format!("   {detail}\n"));
+                    }
+                }
+                out.push('\n');
+            }
+            Event::ToolCall { label, command, .. } => {
+                out.push_str(&format!("**{}**\n\n", strip_ansi(label).trim()));
+                push_fenced(&mut out, "bash", &strip_ansi(command));
+            }
+            Event::ToolResult { exit_code, output } => {
+                let output = strip_ansi(output);
+                let lines: Vec<&str> = output.trim_end().lines().collect();
+                let kept = lines.len().min(MAX_EXPORT_RESULT_LINES);
+                push_fenced(&mut out, "text", &lines[..kept].join("\n"));
+                if lines.len() > kept {
+                    out.push_str(&format!(
+                        "_…({} more lines truncated)_\n\n",
+                        lines.len() - kept
+                    ));
+                }
+                if *exit_code != 0 {
+                    out.push_str(&format!("_exit code: {exit_code}_\n\n"));
+                }
+            }
+            Event::NeedsConfirmation { command, reason } => {
+                out.push_str(&format!(
+                    "> Needs confirmation ({}): `{}`\n\n",
+                    strip_ansi(reason).trim(),
+                    strip_ansi(command).trim()
+                ));
+            }
+            Event::ConversationCompacted {
+                messages_dropped,
+                notes_flushed,
+                ..
+            } => {
+                out.push_str(&format!(
+                    "> Context compacted ({messages_dropped} messages summarized, \
+                     {notes_flushed} memory notes flushed)\n\n"
+                ));
+            }
+            // 原始响应只用于调试，导出时不重复一遍
+            Event::RawResponse { .. } => {}
+            Event::Final { summary } => last_final = Some(summary),
+        }
+    }
+    if let Some(summary) = final_summary.or(last_final) {
+        out.push_str("## Summary\n\n");
+        out.push_str(strip_ansi(summary).trim());
+        out.push('\n');
+    }
+    out
+}
+
+/// 代码块围栏长度随内容中最长的反引号串增长，避免内容提前闭合围栏。
+fn push_fenced(out: &mut String, lang: &str, body: &str) {
+    let longest = body.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    out.push_str(&format!("{fence}{lang}\n{}\n{fence}\n\n", body.trim_end()));
+}
+
+/// GE 模式导出：CONSENSUS.md 原文 + GE_LOG.jsonl 的审计摘要。
+fn render_ge_markdown(workspace: &Path) -> Result<String> {
+    let consensus_path = consensus_file_path(workspace);
+    let consensus = fs::read_to_string(&consensus_path)
+        .with_context(|| format!("failed to read `{}`", consensus_path.display()))?;
+    let audit_path = consensus_path.with_file_name("GE_LOG.jsonl");
+    let audit = fs::read_to_string(&audit_path).unwrap_or_default();
+
+    let mut out = String::new();
+    out.push_str("# GE Export\n\n## CONSENSUS.md\n\n");
+    out.push_str(consensus.trim());
+    out.push_str("\n\n## Audit Summary\n\n");
+    out.push_str(&render_audit_summary(&audit));
+    Ok(out)
+}
+
+fn render_audit_summary(raw: &str) -> String {
+    let entries = parse_entries(raw);
+    if entries.is_empty() {
+        return "(no audit records)\n".to_string();
+    }
+
+    let field = |value: &str| -> String {
+        if value.is_empty() {
+            "-".to_string()
+        } else {
+            value.to_string()
+        }
+    };
+    let mut by_event: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in &entries {
+        *by_event.entry(field(&entry.event)).or_default() += 1;
+    }
+
+    let mut out = format!("- records: {}\n", entries.len());
+    for (event, count) in &by_event {
+        out.push_str(&format!("- {event}: {count}\n"));
+    }
+    out.push_str("\n| time | event | todo | status | summary |\n|---|---|---|---|---|\n");
+    let skip = entries.len().saturating_sub(MAX_EXPORT_AUDIT_ENTRIES);
+    for entry in &entries[skip..] {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            field(&entry.ts),
+            field(&entry.event),
+            field(entry.todo_id.as_deref().unwrap_or_default()),
+            field(&entry.status),
+            field(entry.summary.as_deref().unwrap_or_default())
+                .replace('|', "\\|")
+                .replace('\n', " ")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_audit_summary, render_transcript_markdown};
+    use crate::types::Event;
+
+    #[test]
+    fn render_transcript_markdown_formats_each_event_kind() {
+        let events = vec![
+            Event::UserTask {
+                text: "Fix the build".to_string(),
+            },
+            Event::Thinking {
+                text: "\u{1b}[90mcheck cargo first\u{1b}[0m".to_string(),
+            },
+            Event::ToolCall {
+                label: "Bash(.)".to_string(),
+                command: "cargo build".to_string(),
+                multiline: false,
+            },
+            Event::ToolResult {
+                exit_code: 101,
+                output: "error[E0425]: cannot find value".to_string(),
+            },
+            Event::Final {
+                summary: "stale".to_string(),
+            },
+        ];
+
+        let md = render_transcript_markdown(&events, Some("Build fixed."));
+
+        let expected = "## Fix the build\n\n\
+> check cargo first\n\n\
+**Bash(.)**\n\n\
+```bash\ncargo build\n```\n\n\
+```text\nerror[E0425]: cannot find value\n```\n\n\
+_exit code: 101_\n\n\
+## Summary\n\nBuild fixed.\n";
+        assert_eq!(md, expected);
+    }
+
+    #[test]
+    fn render_transcript_markdown_truncates_long_results() {
+        let output = (0..100).map(|i| format!("line {i}")).collect::<Vec<_>>();
+        let events = vec![Event::ToolResult {
+            exit_code: 0,
+            output: output.join("\n"),
+        }];
+        let md = render_transcript_markdown(&events, None);
+        assert!(md.contains("line 79\n"));
+        assert!(!md.contains("line 80\n"));
+        assert!(md.contains("_…(20 more lines truncated)_"));
+    }
+
+    #[test]
+    fn render_audit_summary_uses_the_shared_parser() {
+        let raw = concat!(
+            r#"{"ts":"10:00","event":"todo_started","todo_id":"T001","status":"running"}"#,
+            "\nnot json\n",
+            r#"{"ts":"10:01","event":"todo_checked","todo_id":"T001","status":"success","summary":"a | b"}"#,
+            "\n",
+        );
+        let out = render_audit_summary(raw);
+        assert!(out.starts_with("- records: 2\n"), "{out}");
+        assert!(out.contains("| 10:00 | todo_started | T001 | running | - |"));
+        assert!(out.contains("| 10:01 | todo_checked | T001 | success | a \\| b |"));
+        assert_eq!(render_audit_summary(""), "(no audit records)\n");
+    }
+}
@@ -3,8 +3,45 @@ use std::path::Path;
 use crossterm::style::Stylize;
 use unicode_width::UnicodeWidthStr;
 
-use crate::types::Event;
+use crate::types::{Event, Verbosity};
+use crate::ui::i18n::{t, t_with};
+use crate::ui::palette::Palette;
 use crate::ui::symbols::Symbols;
+use crate::ui::thinking_filter::filter_thinking;
+
+/// `shorten_text` 回退寻找空白断点的最大字素数。
+const WORD_BREAK_LOOKBACK: usize = 12;
+
+/// 实时视图中单个工具参数值的最大显示宽度。
+const ARG_VALUE_PREVIEW_CHARS: usize = 80;
+
+/// 确认菜单中编辑预览（diff）显示的行数；折叠键可展开完整 diff。
+const EDIT_PREVIEW_LINES: usize = 24;
+/// 折叠视图中编辑预览显示的行数。
+const EDIT_PREVIEW_COMPACT_LINES: usize = 8;
+
+/// 设为 1 时，实时事件行前加上暗色的 `HH:MM:SS` 时间戳（也可用 /timestamps 切换）。
+const ENV_TIMESTAMPS: &str = "GOLDBOT_TIMESTAMPS";
+/// 时间戳格式；续行用同宽空白对齐。
+const TIMESTAMP_FORMAT: &str = "%H:%M:%S";
+
+/// 设为 1 时，每轮 LLM 的原始响应以暗色块写入滚动区（也可用 /raw 切换），调试提示词用。
+const ENV_SHOW_RAW: &str = "GOLDBOT_SHOW_RAW";
+/// 实时视图中原始响应最多显示的行数；任务结束后 Ctrl+d 展开完整原文。
+const RAW_PREVIEW_LINES: usize = 40;
+
+/// 字段名包含这些片段时视为危险字段（会写入、覆盖或删除）。
+const DESTRUCTIVE_ARG_KEYS: &[&str] = &[
+    "path",
+    "file",
+    "dir",
+    "force",
+    "delete",
+    "remove",
+    "overwrite",
+    "recursive",
+    "drop",
+];
 
 pub(crate) fn format_event(event: &Event) -> Vec<String> {
     let sym = Symbols::current();
@@ -17,7 +54,11 @@ pub(crate) fn format_event(event: &Event) -> Vec<String> {
             }
         }),
         Event::Thinking { text } => {
-            lines_with(text, |_, line| format!("  {}", line).grey().to_string())
+            let text = filter_thinking(text);
+            if text.is_empty() {
+                return Vec::new();
+            }
+            lines_with(&text, |_, line| format!("  {}", line).grey().to_string())
         }
         Event::PhaseSummary { text } => lines_with(text, |i, line| {
             if i == 0 {
@@ -28,6 +69,10 @@ pub(crate) fn format_event(event: &Event) -> Vec<String> {
         }),
         Event::ToolCall { label, command, .. } => {
             let mut lines = vec![format!("  {} {}", sym.record, label).cyan().to_string()];
+            if let Some(args) = mcp_call_arguments(label, command) {
+                lines.extend(format_tool_arguments(&args, None));
+                return lines;
+            }
             lines.extend(lines_with(command, |_, line| {
                 format!("    {}", line).grey().to_string()
             }));
@@ -35,14 +80,18 @@ pub(crate) fn format_event(event: &Event) -> Vec<String> {
         }
         Event::ToolResult { output, exit_code } => {
             let ok = *exit_code == 0;
+            // 首行带 ✓/✗ 标记，色盲用户不必靠红绿分辨成败
+            let marker = if ok { sym.ok } else { sym.fail };
             lines_with(output, |i, line| {
                 let pfx = if i == 0 {
-                    format!("  {} ", sym.corner)
+                    format!("  {} {} ", sym.corner, marker)
                 } else {
                     "    ".to_string()
                 };
                 if !ok {
-                    format!("{}{}", pfx, line).red().to_string()
+                    format!("{}{}", pfx, line)
+                        .with(Palette::current().error())
+                        .to_string()
                 } else {
                     style_tool_result_line(&pfx, line)
                 }
@@ -54,10 +103,14 @@ pub(crate) fn format_event(event: &Event) -> Vec<String> {
                     .cyan()
                     .bold()
                     .to_string(),
-                format!("  {} 需要确认", sym.warning)
+                format!("  {} {}", sym.warning, t("confirm.needs_confirmation"))
                     .dark_yellow()
                     .to_string(),
             ];
+            if let Some(preview) = edit_preview_lines(command, EDIT_PREVIEW_LINES, "    ") {
+                lines.extend(preview);
+                return lines;
+            }
             for line in command.lines().take(6) {
                 lines.push(format!("    {}", line).cyan().to_string());
             }
@@ -66,18 +119,18 @@ pub(crate) fn format_event(event: &Event) -> Vec<String> {
             }
             lines
         }
+        Event::Plan { steps, done } => plan_lines(steps, done, false),
+        Event::RawResponse { text } => raw_response_lines(text, None),
         Event::Final { summary } => format_final_lines(summary),
         Event::ConversationCompacted {
             summary,
             messages_dropped,
+            notes_flushed,
         } => {
             let mut lines = vec![
-                format!(
-                    "  {} ── 上下文已压缩 · 丢弃 {} 条消息 ──",
-                    sym.bullet, messages_dropped
-                )
-                .dark_grey()
-                .to_string(),
+                compaction_banner(*messages_dropped, *notes_flushed)
+                    .dark_grey()
+                    .to_string(),
             ];
             if !summary.is_empty() {
                 let excerpt: String = summary.lines().take(4).collect::<Vec<_>>().join(" ");
@@ -95,8 +148,8 @@ pub(crate) fn format_event(event: &Event) -> Vec<String> {
 pub(crate) fn format_event_live(event: &Event) -> Vec<String> {
     let sym = Symbols::current();
     match event {
-        Event::UserTask { .. } | Event::Final { .. } => format_event(event),
-        Event::Thinking { text } => text
+        Event::UserTask { .. } | Event::Final { .. } | Event::Plan { .. } => format_event(event),
+        Event::Thinking { text } => filter_thinking(text)
             .lines()
             .filter(|l| !l.trim().is_empty())
             .take(3)
@@ -113,7 +166,9 @@ pub(crate) fn format_event_live(event: &Event) -> Vec<String> {
             multiline,
         } => {
             let mut lines = vec![format!("  {} {}", sym.record, label).cyan().to_string()];
-            if *multiline {
+            if let Some(args) = mcp_call_arguments(label, command) {
+                lines.extend(format_tool_arguments(&args, Some(ARG_VALUE_PREVIEW_CHARS)));
+            } else if *multiline {
                 for line in command.lines() {
                     lines.push(format!("    {}", line).grey().to_string());
                 }
@@ -123,19 +178,183 @@ pub(crate) fn format_event_live(event: &Event) -> Vec<String> {
             lines
         }
         Event::ToolResult { output, exit_code } => compact_tool_result_lines(*exit_code, output),
+        Event::RawResponse { text } => raw_response_lines(text, Some(RAW_PREVIEW_LINES)),
         Event::NeedsConfirmation { .. } => format_event(event),
         Event::ConversationCompacted { .. } => format_event(event),
     }
 }
 
+/// 计划面板：洋红色标题与竖线，步骤编号，已完成的打勾变绿；`compact` 时每步只留首行。
+fn plan_lines(steps: &[String], done: &[bool], compact: bool) -> Vec<String> {
+    let sym = Symbols::current();
+    let finished = done.iter().filter(|d| **d).count();
+    let mut lines = vec![
+        format!(
+            "  {} {}",
+            sym.record,
+            t_with(
+                "plan.header",
+                &[
+                    ("done", &finished.to_string()),
+                    ("total", &steps.len().to_string()),
+                ],
+            )
+        )
+        .magenta()
+        .bold()
+        .to_string(),
+    ];
+    let gutter = "│".magenta();
+    for (i, step) in steps.iter().enumerate() {
+        let checked = done.get(i).copied().unwrap_or(false);
+        let mark = if checked { sym.ok } else { sym.pending };
+        let mut step_lines = step.lines();
+        let title = step_lines.next().unwrap_or("");
+        let number = format!("{}.", i + 1);
+        let head = if compact {
+            format!("{number} {mark} {}", shorten_text(title, 100))
+        } else {
+            format!("{number} {mark} {title}")
+        };
+        let head = if checked {
+            head.green().to_string()
+        } else {
+            head.white().to_string()
+        };
+        lines.push(format!("  {gutter} {head}"));
+        if compact {
+            continue;
+        }
+        let pad = " ".repeat(UnicodeWidthStr::width(number.as_str()) + 1);
+        for detail in step_lines {
+            lines.push(format!("  {gutter} {pad}{}", detail.grey()));
+        }
+    }
+    lines
+}
+
+/// 原始响应块：暗色、带竖线前缀，原样保留标签与空行；`max_lines` 为 Some 时截断并提示展开方式。
+fn raw_response_lines(text: &str, max_lines: Option<usize>) -> Vec<String> {
+    let all: Vec<&str> = text.lines().collect();
+    let shown = max_lines.map_or(all.len(), |max| all.len().min(max));
+    let mut lines = vec![
+        format!(
+            "  {} {}",
+            Symbols::current().record,
+            t_with("raw.header", &[("lines", &all.len().to_string())])
+        )
+        .dark_grey()
+        .to_string(),
+    ];
+    lines.extend(
+        all[..shown]
+            .iter()
+            .map(|line| format!("    │ {line}").dark_grey().to_string()),
+    );
+    if shown < all.len() {
+        let more = (all.len() - shown).to_string();
+        lines.push(
+            format!("    │ {}", t_with("raw.truncated", &[("count", &more)]))
+                .dark_grey()
+                .to_string(),
+        );
+    }
+    lines
+}
+
+/// 按详细程度选择实时事件的格式：quiet 隐藏思考，verbose 完整显示思考。
+pub(crate) fn format_event_for_verbosity(event: &Event, verbosity: Verbosity) -> Vec<String> {
+    match (verbosity, event) {
+        (Verbosity::Quiet, Event::Thinking { .. } | Event::PhaseSummary { .. }) => Vec::new(),
+        (Verbosity::Verbose, Event::Thinking { .. }) => format_event(event),
+        _ => format_event_live(event),
+    }
+}
+
+/// 压缩完成后留在滚动区的分隔行：折叠了多少消息、补写了多少条长期记忆。
+fn compaction_banner(messages_dropped: usize, notes_flushed: usize) -> String {
+    format!(
+        "  {} {}",
+        Symbols::current().bullet,
+        t_with(
+            "compact.banner",
+            &[
+                ("messages", &messages_dropped.to_string()),
+                ("notes", &notes_flushed.to_string()),
+            ],
+        )
+    )
+}
+
 pub(crate) fn emit_live_event(screen: &mut super::screen::Screen, event: &Event) {
-    screen.emit(&format_event_live(event));
+    // 面板布局下 Thinking 只进固定面板，不写入滚动区
+    if screen.thinking_panel
+        && let Event::Thinking { text } = event
+    {
+        screen.set_thinking(&filter_thinking(text));
+        return;
+    }
+    let stamp = screen
+        .timestamps
+        .then(|| chrono::Local::now().format(TIMESTAMP_FORMAT).to_string());
+    let lines = live_event_lines(event, screen.verbosity, stamp.as_deref());
+    if !lines.is_empty() {
+        screen.emit(&lines);
+    }
+}
+
+/// 实时视图中一个事件的输出行；`stamp` 为 Some 时加时间戳前缀。
+fn live_event_lines(event: &Event, verbosity: Verbosity, stamp: Option<&str>) -> Vec<String> {
+    let lines = format_event_for_verbosity(event, verbosity);
+    match stamp {
+        Some(stamp) => with_timestamp(lines, stamp),
+        None => lines,
+    }
+}
+
+/// 配置或记忆写入失败时的单行警告，如 `⚠ couldn't save to ~/.goldbot/.env: Permission denied`。
+pub(crate) fn save_warning_line(err: &anyhow::Error) -> String {
+    format!("  {} {err:#}", Symbols::current().warning)
+        .dark_yellow()
+        .to_string()
+}
+
+pub(crate) fn show_raw_from_env() -> bool {
+    matches!(
+        std::env::var(ENV_SHOW_RAW).unwrap_or_default().trim(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+pub(crate) fn timestamps_from_env() -> bool {
+    matches!(
+        std::env::var(ENV_TIMESTAMPS).unwrap_or_default().trim(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// 事件首行加暗色时间戳，其余行以等宽空白缩进；空行保持为空，避免行尾多出空格。
+fn with_timestamp(lines: Vec<String>, stamp: &str) -> Vec<String> {
+    let pad = " ".repeat(UnicodeWidthStr::width(stamp));
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                format!("{} {line}", stamp.dark_grey())
+            } else if line.is_empty() {
+                line
+            } else {
+                format!("{pad} {line}")
+            }
+        })
+        .collect()
 }
 
 pub(crate) fn format_event_compact(event: &Event) -> Vec<String> {
     let sym = Symbols::current();
     match event {
-        Event::Thinking { text } => text
+        Event::Thinking { text } => filter_thinking(text)
             .lines()
             .filter(|l| !l.trim().is_empty())
             .take(3)
@@ -148,6 +367,7 @@ pub(crate) fn format_event_compact(event: &Event) -> Vec<String> {
                     .to_string(),
             ]
         }
+        Event::Plan { steps, done } => plan_lines(steps, done, true),
         Event::ToolCall { label, .. } => {
             vec![format!("  {} {}", sym.bullet, label).cyan().to_string()]
         }
@@ -158,10 +378,15 @@ pub(crate) fn format_event_compact(event: &Event) -> Vec<String> {
                     .cyan()
                     .bold()
                     .to_string(),
-                format!("    {} 需要确认", sym.warning)
+                format!("    {} {}", sym.warning, t("confirm.needs_confirmation"))
                     .dark_yellow()
                     .to_string(),
             ];
+            if let Some(preview) = edit_preview_lines(command, EDIT_PREVIEW_COMPACT_LINES, "      ")
+            {
+                lines.extend(preview);
+                return lines;
+            }
             for line in command.lines().take(4) {
                 lines.push(
                     format!("      {}", shorten_text(line, 72))
@@ -174,20 +399,16 @@ pub(crate) fn format_event_compact(event: &Event) -> Vec<String> {
             }
             lines
         }
-        Event::Final { .. } | Event::UserTask { .. } => Vec::new(),
+        Event::Final { .. } | Event::UserTask { .. } | Event::RawResponse { .. } => Vec::new(),
         Event::ConversationCompacted {
-            messages_dropped, ..
-        } => {
-            let sym = Symbols::current();
-            vec![
-                format!(
-                    "  {} ── 上下文已压缩 · 丢弃 {} 条消息 ──",
-                    sym.bullet, messages_dropped
-                )
+            messages_dropped,
+            notes_flushed,
+            ..
+        } => vec![
+            compaction_banner(*messages_dropped, *notes_flushed)
                 .dark_grey()
                 .to_string(),
-            ]
-        }
+        ],
     }
 }
 
@@ -199,10 +420,101 @@ fn lines_with(text: &str, f: impl Fn(usize, &str) -> String) -> Vec<String> {
     v.iter().enumerate().map(|(i, l)| f(i, l)).collect()
 }
 
-fn format_final_lines(summary: &str) -> Vec<String> {
+/// MCP 调用的参数（`MCP(tool)` 事件里存的是紧凑 JSON）；只处理对象参数。
+fn mcp_call_arguments(label: &str, command: &str) -> Option<serde_json::Value> {
+    if !label.starts_with("MCP(") {
+        return None;
+    }
+    serde_json::from_str::<serde_json::Value>(command)
+        .ok()
+        .filter(|v| v.as_object().is_some_and(|o| !o.is_empty()))
+}
+
+/// 字段名看起来会改动或删除东西时高亮（路径、force、delete 等）。
+fn is_destructive_arg_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    DESTRUCTIVE_ARG_KEYS.iter().any(|k| key.contains(k))
+}
+
+/// 把工具参数渲染成 `key: value` 行：键加粗，危险字段标黄，嵌套对象逐层缩进。
+/// `max_value_chars` 为 Some 时长字符串折叠成一行并截断（Ctrl+d 展开后显示完整值）。
+pub(crate) fn format_tool_arguments(
+    args: &serde_json::Value,
+    max_value_chars: Option<usize>,
+) -> Vec<String> {
+    let mut out = Vec::new();
+    if let Some(map) = args.as_object() {
+        for (key, value) in map {
+            push_argument_lines(&mut out, key, value, 2, max_value_chars);
+        }
+    }
+    out
+}
+
+fn push_argument_lines(
+    out: &mut Vec<String>,
+    key: &str,
+    value: &serde_json::Value,
+    depth: usize,
+    max_value_chars: Option<usize>,
+) {
+    use serde_json::Value;
+
+    let indent = "  ".repeat(depth);
+    let key_styled = if is_destructive_arg_key(key) {
+        key.yellow().bold().to_string()
+    } else {
+        key.bold().to_string()
+    };
+    let nested = match value {
+        Value::Object(map) if !map.is_empty() => {
+            Some(map.iter().map(|(k, v)| (k.clone(), v)).collect::<Vec<_>>())
+        }
+        Value::Array(items) if items.iter().any(|v| v.is_object() || v.is_array()) => Some(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (format!("[{i}]"), v))
+                .collect::<Vec<_>>(),
+        ),
+        _ => None,
+    };
+    if let Some(children) = nested {
+        out.push(format!("{indent}{key_styled}:"));
+        for (child_key, child) in children {
+            push_argument_lines(out, &child_key, child, depth + 1, max_value_chars);
+        }
+        return;
+    }
+
+    let text = match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let value_lines: Vec<&str> = text.lines().collect();
+    match max_value_chars {
+        Some(max) => {
+            let ellipsis = Symbols::current().ellipsis;
+            let mut shown = shorten_text(value_lines.first().copied().unwrap_or(""), max);
+            if value_lines.len() > 1 && !shown.ends_with(ellipsis) {
+                shown.push_str(ellipsis);
+            }
+            out.push(format!("{indent}{key_styled}: {shown}"));
+        }
+        None if value_lines.len() > 1 => {
+            out.push(format!("{indent}{key_styled}:"));
+            for line in value_lines {
+                out.push(format!("{indent}  {line}").grey().to_string());
+            }
+        }
+        None => out.push(format!("{indent}{key_styled}: {text}")),
+    }
+}
+
+pub(crate) fn format_final_lines(summary: &str) -> Vec<String> {
     let lines: Vec<&str> = summary.lines().collect();
     if lines.is_empty() {
-        return vec!["  ✓ Done".green().bold().to_string()];
+        return vec!["  ✓ Done".with(Palette::current().ok()).bold().to_string()];
     }
 
     let has_diff = looks_like_diff_block(&lines);
@@ -216,7 +528,7 @@ fn format_final_lines(summary: &str) -> Vec<String> {
         if i == 0 {
             out.push(format!(
                 "  {} {}",
-                "✓".green().bold(),
+                "✓".with(Palette::current().ok()).bold(),
                 render_inline_markdown(trimmed).bold()
             ));
             continue;
@@ -246,24 +558,9 @@ fn format_final_lines(summary: &str) -> Vec<String> {
 
         let rendered = format!("    {}", line);
 
-        if has_diff {
-            if trimmed.starts_with('+') && !trimmed.starts_with("+++") {
-                out.push(rendered.green().to_string());
-                continue;
-            }
-            if trimmed.starts_with('-') && !trimmed.starts_with("---") {
-                out.push(rendered.red().to_string());
-                continue;
-            }
-            if trimmed.starts_with("@@")
-                || trimmed.starts_with("diff --git")
-                || trimmed.starts_with("index ")
-                || trimmed.starts_with("--- ")
-                || trimmed.starts_with("+++ ")
-            {
-                out.push(rendered.dark_yellow().to_string());
-                continue;
-            }
+        if has_diff && let Some(styled) = style_diff_line(&rendered) {
+            out.push(styled);
+            continue;
         }
 
         if is_markdown_rule(trimmed) {
@@ -321,6 +618,26 @@ fn format_final_lines(summary: &str) -> Vec<String> {
     out
 }
 
+/// unified diff 的一行：增删行用调色板的 ok / error 色，文件头与 hunk 头暗黄；其它行返回 None。
+pub(crate) fn style_diff_line(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('+') && !trimmed.starts_with("+++") {
+        return Some(line.with(Palette::current().ok()).to_string());
+    }
+    if trimmed.starts_with('-') && !trimmed.starts_with("---") {
+        return Some(line.with(Palette::current().error()).to_string());
+    }
+    if trimmed.starts_with("@@")
+        || trimmed.starts_with("diff --git")
+        || trimmed.starts_with("index ")
+        || trimmed.starts_with("--- ")
+        || trimmed.starts_with("+++ ")
+    {
+        return Some(line.dark_yellow().to_string());
+    }
+    None
+}
+
 fn looks_like_diff_block(lines: &[&str]) -> bool {
     lines.iter().any(|line| {
         let t = line.trim_start();
@@ -465,6 +782,37 @@ fn format_bullet_line(item: &str) -> String {
     )
 }
 
+/// 以 `Diff <path>:` 开头的确认内容是 WriteFile / UpdateFile 的编辑预览：按 diff 着色，
+/// 超过 `max_lines` 时截断并提示可展开；其它确认内容返回 None。
+pub(crate) fn edit_preview_lines(
+    command: &str,
+    max_lines: usize,
+    indent: &str,
+) -> Option<Vec<String>> {
+    let first = command.lines().next()?.trim();
+    if !(first.starts_with("Diff ") && first.ends_with(':')) {
+        return None;
+    }
+    let total = command.lines().count();
+    let mut lines: Vec<String> = command
+        .lines()
+        .take(max_lines)
+        .map(|line| style_tool_result_line(indent, line))
+        .collect();
+    if total > max_lines {
+        let more = t_with(
+            "confirm.diff_more",
+            &[("count", &(total - max_lines).to_string())],
+        );
+        lines.push(
+            format!("{indent}{} {more}", Symbols::current().ellipsis)
+                .grey()
+                .to_string(),
+        );
+    }
+    Some(lines)
+}
+
 /// Detects our line-numbered diff format: `"NNN - content"` or `"NNN + content"`.
 /// Returns `Some('-')` / `Some('+')` on a match, `None` otherwise.
 fn numbered_diff_marker(t: &str) -> Option<char> {
@@ -483,7 +831,8 @@ fn numbered_diff_marker(t: &str) -> Option<char> {
 
 /// Render a diff line with colored background starting at `content` (after `prefix`).
 /// The prefix is left uncolored; the content + trailing padding fill the rest of the
-/// terminal width with a dark red (delete) or dark green (insert) background.
+/// terminal width with the palette's delete / insert background (dark red / dark green
+/// by default, orange / blue with `GOLDBOT_PALETTE=deuteranopia`).
 /// White foreground is applied so text is readable on both dark backgrounds.
 fn bg_fill_after_prefix(prefix: &str, content: &str, is_delete: bool) -> String {
     let term_width = crossterm::terminal::size()
@@ -498,10 +847,11 @@ fn bg_fill_after_prefix(prefix: &str, content: &str, is_delete: bool) -> String
         String::new()
     };
     let padded_content = format!("{content}{padding}");
+    let palette = Palette::current();
     let colored = if is_delete {
-        padded_content.white().on_dark_red().to_string()
+        padded_content.white().on(palette.removed_bg()).to_string()
     } else {
-        padded_content.white().on_dark_green().to_string()
+        padded_content.white().on(palette.added_bg()).to_string()
     };
     format!("{prefix}{colored}")
 }
@@ -522,11 +872,12 @@ fn style_tool_result_line(pfx: &str, raw_line: &str) -> String {
     if t.starts_with('─') {
         return rendered.dark_grey().to_string();
     }
+    let palette = Palette::current();
     if t.starts_with('+') {
-        return rendered.green().to_string();
+        return rendered.with(palette.ok()).to_string();
     }
     if t.starts_with('-') {
-        return rendered.red().to_string();
+        return rendered.with(palette.error()).to_string();
     }
     if t.starts_with('~') {
         return rendered.cyan().to_string();
@@ -535,13 +886,13 @@ fn style_tool_result_line(pfx: &str, raw_line: &str) -> String {
         return rendered.dark_yellow().bold().to_string();
     }
     if t.starts_with("created (") {
-        return rendered.green().to_string();
+        return rendered.with(palette.ok()).to_string();
     }
     if t.starts_with("updated (") {
         return rendered.cyan().to_string();
     }
     if t.starts_with("deleted (") {
-        return rendered.red().to_string();
+        return rendered.with(palette.error()).to_string();
     }
     if t.starts_with("Diff ") || t.starts_with("Preview ") {
         return rendered.dark_yellow().to_string();
@@ -714,14 +1065,22 @@ fn compact_tool_result_lines(exit_code: i32, output: &str) -> Vec<String> {
         }
     }
 
-    // Summary lines: colored by exit code.
+    // Summary lines: colored by exit code; the first one also carries a ✓/✗ marker.
+    let sym = crate::ui::symbols::Symbols::current();
     let mut result: Vec<String> = summary
         .into_iter()
-        .map(|line| {
+        .enumerate()
+        .map(|(i, line)| {
+            let line = if i == 0 {
+                let marker = if exit_code == 0 { sym.ok } else { sym.fail };
+                line.replacen(sym.corner, &format!("{} {marker}", sym.corner), 1)
+            } else {
+                line
+            };
             if exit_code == 0 {
                 line.grey().to_string()
             } else {
-                line.red().to_string()
+                line.with(Palette::current().error()).to_string()
             }
         })
         .collect();
@@ -768,7 +1127,7 @@ fn parse_diff_blocks(output: &str) -> Vec<Vec<String>> {
     result
 }
 
-fn parse_fs_changes(output: &str) -> Option<FsChangeSummary> {
+pub(crate) fn parse_fs_changes(output: &str) -> Option<FsChangeSummary> {
     let mut in_section = false;
     let mut fs = FsChangeSummary::default();
 
@@ -871,16 +1230,103 @@ fn is_emoji(c: char) -> bool {
     )
 }
 
+/// 按显示宽度截断文本，结果（含省略号）宽度不超过 `max_chars`。
+/// 不会拆开字素簇（组合音标、ZWJ 表情序列、肤色修饰符等），
+/// 若截断点附近 `WORD_BREAK_LOOKBACK` 个字素内有空白，则优先在空白处断开。
 pub(crate) fn shorten_text(s: &str, max_chars: usize) -> String {
     let trimmed = s.trim();
-    if trimmed.chars().count() <= max_chars {
+    let clusters = grapheme_clusters(trimmed);
+    let total: usize = clusters.iter().map(|c| cluster_width(c)).sum();
+    if total <= max_chars {
         return trimmed.to_string();
     }
-    let mut out: String = trimmed.chars().take(max_chars.saturating_sub(1)).collect();
-    out.push_str(Symbols::current().ellipsis);
+
+    let ellipsis = Symbols::current().ellipsis;
+    let ellipsis_width = UnicodeWidthStr::width(ellipsis);
+    let (budget, ellipsis) = if ellipsis_width <= max_chars {
+        (max_chars - ellipsis_width, ellipsis)
+    } else {
+        (max_chars, "")
+    };
+
+    let mut taken = 0usize;
+    let mut width = 0usize;
+    for cluster in &clusters {
+        let w = cluster_width(cluster);
+        if width + w > budget {
+            break;
+        }
+        width += w;
+        taken += 1;
+    }
+
+    // 截断点落在单词中间时，回退到最近的空白处
+    let mid_word = taken > 0
+        && !is_whitespace_cluster(clusters[taken - 1])
+        && !is_whitespace_cluster(clusters[taken]);
+    if mid_word {
+        let floor = taken.saturating_sub(WORD_BREAK_LOOKBACK);
+        if let Some(pos) = (floor..taken)
+            .rev()
+            .find(|&i| is_whitespace_cluster(clusters[i]))
+            && pos > 0
+        {
+            taken = pos;
+        }
+    }
+
+    let mut out: String = clusters[..taken].concat().trim_end().to_string();
+    out.push_str(ellipsis);
     out
 }
 
+/// 将文本切分为近似的扩展字素簇。
+/// 仓库未引入分词 crate，这里只处理终端显示中最常见的组合情况。
+fn grapheme_clusters(s: &str) -> Vec<&str> {
+    let mut out: Vec<&str> = Vec::new();
+    let mut start = 0usize;
+    let mut prev: Option<char> = None;
+    for (idx, ch) in s.char_indices() {
+        let joins = prev.is_some_and(|p| p == '\u{200D}') || is_grapheme_extend(ch);
+        if idx > 0 && !joins {
+            out.push(&s[start..idx]);
+            start = idx;
+        }
+        prev = Some(ch);
+    }
+    if start < s.len() {
+        out.push(&s[start..]);
+    }
+    out
+}
+
+fn is_grapheme_extend(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'     // Combining Diacritical Marks
+        | '\u{1AB0}'..='\u{1AFF}'   // Combining Diacritical Marks Extended
+        | '\u{1DC0}'..='\u{1DFF}'   // Combining Diacritical Marks Supplement
+        | '\u{20D0}'..='\u{20FF}'   // Combining Marks for Symbols
+        | '\u{FE20}'..='\u{FE2F}'   // Combining Half Marks
+        | '\u{200D}'                // ZWJ
+        | '\u{FE00}'..='\u{FE0F}'   // Variation Selectors
+        | '\u{1F3FB}'..='\u{1F3FF}' // Emoji skin tone modifiers
+        | '\u{E0020}'..='\u{E007F}' // Emoji tag sequences
+    )
+}
+
+/// 字素簇的显示宽度：取簇内最宽字符（ZWJ 序列在终端中整体占一个表情宽度）。
+fn cluster_width(cluster: &str) -> usize {
+    cluster
+        .chars()
+        .map(|c| unicode_width::UnicodeWidthChar::width(c).unwrap_or(0))
+        .max()
+        .unwrap_or(0)
+}
+
+fn is_whitespace_cluster(cluster: &str) -> bool {
+    cluster.chars().all(char::is_whitespace)
+}
+
 fn parse_tool_label(label: &str) -> (&str, Option<&str>) {
     let Some(open) = label.find('(') else {
         return (label, None);
@@ -893,18 +1339,91 @@ fn parse_tool_label(label: &str) -> (&str, Option<&str>) {
     (kind, Some(target))
 }
 
+/// 可以折叠成一行的连续只读调用：读文件、查看 git 状态/差异/历史。返回折叠摘要的文案键。
+fn collapse_group(kind: &str) -> Option<&'static str> {
+    match kind {
+        "Read" => Some("events.reading_files"),
+        "Git status" | "Git diff" | "Git log" | "Git show" => Some("events.inspecting_git"),
+        _ => None,
+    }
+}
+
+/// 从 `start` 开始连续、命令与结果都相同的 ToolCall+ToolResult 对（中间可夹 Thinking）：
+/// 返回 (次数, 之后的下标)；不足两次时返回 None。常见于 Agent 反复重试同一条失败命令。
+fn identical_tool_runs(events: &[Event], start: usize) -> Option<(usize, usize)> {
+    let pair = |at: usize| match events.get(at..at + 2)? {
+        [
+            Event::ToolCall { label, command, .. },
+            Event::ToolResult { exit_code, output },
+        ] => Some((label, command, *exit_code, output)),
+        _ => None,
+    };
+    let first = pair(start)?;
+    let mut count = 1usize;
+    let mut end = start + 2;
+    let mut k = end;
+    while k < events.len() {
+        match &events[k] {
+            Event::Thinking { .. } | Event::RawResponse { .. } => k += 1,
+            _ if pair(k) == Some(first) => {
+                count += 1;
+                k += 2;
+                end = k;
+            }
+            _ => break,
+        }
+    }
+    (count >= 2).then_some((count, end))
+}
+
 pub(crate) fn collapsed_task_event_lines(events: &[Event]) -> Vec<String> {
-    let mut lines = Vec::new();
+    // 计划面板固定在任务区顶部，只显示最新的一版
+    let mut lines = events
+        .iter()
+        .rev()
+        .find(|ev| matches!(ev, Event::Plan { .. }))
+        .map(format_event_compact)
+        .unwrap_or_default();
     let mut i = 0usize;
 
     while i < events.len() {
-        if let Event::ToolCall { label, .. } = &events[i] {
+        if matches!(events[i], Event::Plan { .. }) {
+            i += 1;
+            continue;
+        }
+        if let Some((count, next)) = identical_tool_runs(events, i)
+            && let (Event::ToolCall { label, command, .. }, Event::ToolResult { exit_code, output }) =
+                (&events[i], &events[i + 1])
+        {
+            let shown = if command.trim().is_empty() {
+                label
+            } else {
+                command
+            };
+            let summary = format!(
+                "  {} {}",
+                Symbols::current().bullet,
+                t_with(
+                    "events.repeated_run",
+                    &[
+                        ("command", &shorten_text(shown.trim(), 80)),
+                        ("count", &count.to_string()),
+                    ],
+                )
+            );
+            lines.push(summary.cyan().to_string());
+            lines.extend(compact_tool_result_lines(*exit_code, output));
+            i = next;
+            continue;
+        }
+        if let Event::ToolCall { label, command, .. } = &events[i] {
             let (kind, target) = parse_tool_label(label);
-            if kind == "Read" {
+            if let Some(group) = collapse_group(kind) {
                 let mut count = 1usize;
                 let mut j = i + 1;
                 let mut had_error = false;
-                let mut last_target = target.map(str::to_string);
+                // git 标签不带目标，摘要下方改为显示最后一条命令
+                let mut last_target = target.unwrap_or(command).to_string();
 
                 while j < events.len() {
                     match &events[j] {
@@ -912,19 +1431,17 @@ pub(crate) fn collapsed_task_event_lines(events: &[Event]) -> Vec<String> {
                             had_error |= *exit_code != 0;
                             j += 1;
                         }
-                        Event::ToolCall { label, .. } => {
+                        Event::ToolCall { label, command, .. } => {
                             let (next_kind, next_target) = parse_tool_label(label);
-                            if next_kind != "Read" {
+                            if collapse_group(next_kind) != Some(group) {
                                 break;
                             }
                             count += 1;
-                            if let Some(target) = next_target {
-                                last_target = Some(target.to_string());
-                            }
+                            last_target = next_target.unwrap_or(command).to_string();
                             j += 1;
                         }
-                        Event::Thinking { .. } => j += 1,
-                        Event::PhaseSummary { .. } => break,
+                        Event::Thinking { .. } | Event::RawResponse { .. } => j += 1,
+                        Event::PhaseSummary { .. } | Event::Plan { .. } => break,
                         Event::NeedsConfirmation { .. }
                         | Event::Final { .. }
                         | Event::UserTask { .. }
@@ -934,21 +1451,25 @@ pub(crate) fn collapsed_task_event_lines(events: &[Event]) -> Vec<String> {
 
                 if count >= 2 {
                     let summary = format!(
-                        "  {} Reading {count} files... (Ctrl+d 查看详情)",
-                        crate::ui::symbols::Symbols::current().bullet
+                        "  {} {}",
+                        crate::ui::symbols::Symbols::current().bullet,
+                        t_with(group, &[("count", &count.to_string())])
                     );
                     if had_error {
-                        lines.push(summary.red().to_string());
-                    } else {
-                        lines.push(summary.cyan().to_string());
-                    }
-                    if let Some(target) = last_target {
+                        let sym = crate::ui::symbols::Symbols::current();
                         lines.push(
-                            format!("    └ {}", shorten_text(&target, 110))
-                                .grey()
+                            format!("{summary} {}", sym.fail)
+                                .with(Palette::current().error())
                                 .to_string(),
                         );
+                    } else {
+                        lines.push(summary.cyan().to_string());
                     }
+                    lines.push(
+                        format!("    └ {}", shorten_text(&last_target, 110))
+                            .grey()
+                            .to_string(),
+                    );
                     i = j;
                     continue;
                 }
@@ -977,6 +1498,21 @@ pub(crate) fn collapsed_lines(app: &crate::App) -> Vec<String> {
     lines.extend(format_event(&Event::Final {
         summary: summary.to_string(),
     }));
+    if app.timestamps
+        && let (Some(end), Some(elapsed)) = (app.task_finished_clock, app.last_task_elapsed)
+    {
+        let start = end - chrono::Duration::from_std(elapsed).unwrap_or_default();
+        lines.push(
+            format!(
+                "  {} → {} ({})",
+                start.format(TIMESTAMP_FORMAT),
+                end.format(TIMESTAMP_FORMAT),
+                crate::agent::executor::format_elapsed_short(elapsed)
+            )
+            .dark_grey()
+            .to_string(),
+        );
+    }
     lines.push(String::new());
     lines
 }
@@ -1014,10 +1550,113 @@ pub(crate) fn toggle_collapse(app: &mut crate::App, screen: &mut super::screen::
     screen.refresh();
 }
 
+/// 重绘时的任务区内容：已结束的任务按当前折叠状态从 app 重建，
+/// 运行中的任务沿用屏幕上保留的 `task_rendered`。
+pub(crate) fn redraw_lines(app: &crate::App, retained: &[String]) -> Vec<String> {
+    if app.final_summary.is_none() {
+        return retained.to_vec();
+    }
+    if app.task_collapsed {
+        collapsed_lines(app)
+    } else {
+        expanded_lines(app)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn timestamped_lines_count_prefix_width_and_can_be_disabled() {
+        use crate::ui::screen::{rows_for_width, strip_ansi};
+
+        let event = Event::ToolCall {
+            label: "Shell".to_string(),
+            command: "x".repeat(70),
+            multiline: false,
+        };
+        let plain = live_event_lines(&event, Verbosity::Normal, None);
+        let stamped = live_event_lines(&event, Verbosity::Normal, Some("09:05:07"));
+        assert_eq!(plain.len(), stamped.len());
+        assert!(strip_ansi(&stamped[0]).starts_with("09:05:07 "));
+        // 续行用等宽空白对齐，不重复时间戳
+        assert_eq!(
+            strip_ansi(&stamped[1]),
+            format!("{}{}", " ".repeat(9), strip_ansi(&plain[1]))
+        );
+        assert!(!strip_ansi(&plain[0]).contains("09:05:07"));
+
+        // 74 列的参数行在 80 列终端放得下，加上 9 列前缀后折成两行
+        assert_eq!(rows_for_width(&plain[1], 80), 1);
+        assert_eq!(rows_for_width(&stamped[1], 80), 2);
+        assert_eq!(rows_for_width(&stamped[1], 83), 1);
+    }
+
+    #[test]
+    fn mcp_arguments_render_nested_objects_as_key_value_lines() {
+        let args = serde_json::json!({
+            "repo": "GOLDhjy/GoldBot",
+            "options": { "force": true, "target": { "path": "src/main.rs" } },
+            "body": "line one\nline two",
+        });
+        let plain = |lines: Vec<String>| -> Vec<String> {
+            lines
+                .iter()
+                .map(|l| crate::ui::screen::strip_ansi(l))
+                .collect()
+        };
+        let ell = Symbols::current().ellipsis;
+        assert_eq!(
+            plain(format_tool_arguments(&args, Some(80))),
+            [
+                "    body: line one".to_string() + ell,
+                "    options:".to_string(),
+                "      force: true".to_string(),
+                "      target:".to_string(),
+                "        path: src/main.rs".to_string(),
+                "    repo: GOLDhjy/GoldBot".to_string(),
+            ]
+        );
+        // 完整视图展开多行字符串
+        let full = plain(format_tool_arguments(&args, None));
+        assert_eq!(full[..3], ["    body:", "      line one", "      line two"]);
+
+        // 危险字段标黄，普通字段只加粗
+        let styled = format_tool_arguments(&args, Some(80));
+        assert!(styled[2].contains(&"force".yellow().bold().to_string()));
+        assert!(styled[5].contains(&"repo".bold().to_string()));
+
+        let ev = Event::ToolCall {
+            label: "MCP(mcp_github_create_issue)".to_string(),
+            command: args.to_string(),
+            multiline: false,
+        };
+        assert_eq!(plain(format_event_live(&ev)).len(), 7);
+    }
+
+    #[test]
+    fn quiet_verbosity_hides_thinking_but_keeps_tool_io() {
+        let thinking = Event::Thinking {
+            text: "先看看目录结构".to_string(),
+        };
+        let result = Event::ToolResult {
+            exit_code: 0,
+            output: "src\nCargo.toml".to_string(),
+        };
+        assert!(format_event_for_verbosity(&thinking, Verbosity::Quiet).is_empty());
+        assert!(!format_event_for_verbosity(&result, Verbosity::Quiet).is_empty());
+        assert!(!format_event_for_verbosity(&thinking, Verbosity::Normal).is_empty());
+
+        // verbose 不截断多行思考
+        let long = Event::Thinking {
+            text: "1\n2\n3\n4\n5".to_string(),
+        };
+        let normal = format_event_for_verbosity(&long, Verbosity::Normal);
+        let verbose = format_event_for_verbosity(&long, Verbosity::Verbose);
+        assert_eq!((normal.len(), verbose.len()), (3, 5));
+    }
+
     #[test]
     fn sanitize_final_keeps_markdown_structure_and_fences() {
         let raw = "## Title\n- **a**\n- `b`\n```bash\nls -la\n```\n1. item";
@@ -1040,6 +1679,43 @@ mod tests {
         assert!(lines.iter().any(|l| l.contains("+new line")));
     }
 
+    #[test]
+    fn tool_results_carry_status_symbol_independent_of_color() {
+        use crate::ui::screen::strip_ansi;
+
+        let sym = Symbols::current();
+        let failed = Event::ToolResult {
+            exit_code: 1,
+            output: "error: no such file\nsecond line".to_string(),
+        };
+        let ok = Event::ToolResult {
+            exit_code: 0,
+            output: "done".to_string(),
+        };
+        // 去掉颜色后仍能分辨成败
+        let failed_lines: Vec<String> = format_event(&failed)
+            .iter()
+            .map(|l| strip_ansi(l))
+            .collect();
+        let ok_lines: Vec<String> = format_event(&ok).iter().map(|l| strip_ansi(l)).collect();
+        assert!(
+            failed_lines[0].contains(&format!("{} error: no such file", sym.fail)),
+            "{failed_lines:?}"
+        );
+        assert!(!failed_lines[0].contains(sym.ok));
+        assert!(
+            ok_lines[0].contains(&format!("{} done", sym.ok)),
+            "{ok_lines:?}"
+        );
+        assert!(!ok_lines[0].contains(sym.fail));
+
+        let compact: Vec<String> = format_event_compact(&failed)
+            .iter()
+            .map(|l| strip_ansi(l))
+            .collect();
+        assert!(compact[0].contains(sym.fail), "{compact:?}");
+    }
+
     #[test]
     fn collapsed_groups_consecutive_reads() {
         let events = vec![
@@ -1078,6 +1754,165 @@ mod tests {
         assert!(!lines.contains("Read(/tmp/a.rs)"));
     }
 
+    #[test]
+    fn collapsed_groups_consecutive_git_reads() {
+        let git = |label: &str, command: &str| Event::ToolCall {
+            label: label.to_string(),
+            command: command.to_string(),
+            multiline: false,
+        };
+        let ok = || Event::ToolResult {
+            exit_code: 0,
+            output: String::new(),
+        };
+        let events = vec![
+            git("Git status", "git status --short"),
+            ok(),
+            git("Git diff", "git diff -- src/main.rs"),
+            ok(),
+            git("Git commit", "git commit -qm wip"),
+            ok(),
+        ];
+
+        let lines = collapsed_task_event_lines(&events).join("\n");
+        let summary = t_with("events.inspecting_git", &[("count", "2")]);
+        assert!(lines.contains(&summary), "{lines}");
+        assert!(lines.contains("git diff -- src/main.rs"));
+        assert!(lines.contains("Git commit"));
+        assert!(!lines.contains("Reading 2 files"));
+    }
+
+    #[test]
+    fn collapsed_folds_identical_retries_into_one_line() {
+        let run = |command: &str, exit_code: i32, output: &str| {
+            [
+                Event::ToolCall {
+                    label: "Run".to_string(),
+                    command: command.to_string(),
+                    multiline: false,
+                },
+                Event::ToolResult {
+                    exit_code,
+                    output: output.to_string(),
+                },
+            ]
+        };
+        let mut events = Vec::new();
+        for _ in 0..3 {
+            events.extend(run("npm test", 1, "ECONNRESET"));
+            events.push(Event::Thinking {
+                text: "retrying".to_string(),
+            });
+        }
+        events.extend(run("npm test", 1, "2 tests failed"));
+        events.extend(run("npm run lint", 0, "ok"));
+
+        let lines: Vec<String> = collapsed_task_event_lines(&events)
+            .iter()
+            .map(|l| crate::ui::screen::strip_ansi(l))
+            .collect();
+        let text = lines.join("\n");
+        let folded = t_with(
+            "events.repeated_run",
+            &[("command", "npm test"), ("count", "3")],
+        );
+        assert_eq!(text.matches(&folded).count(), 1, "{text}");
+        assert_eq!(text.matches("ECONNRESET").count(), 1, "{text}");
+        // 结果不同、命令不同的不折叠
+        assert!(text.contains("2 tests failed"));
+        assert!(text.contains("ok"));
+        assert!(!text.contains("×2"), "{text}");
+        // 原始事件保留，完整视图仍逐次显示
+        assert_eq!(events.len(), 13);
+    }
+
+    #[test]
+    fn plan_event_renders_numbered_panel_in_full_and_compact_views() {
+        let plan = Event::Plan {
+            steps: vec![
+                "Read the parser".to_string(),
+                "Fix empty input\n- add a regression test".to_string(),
+            ],
+            done: vec![true, false],
+        };
+        let plain = |lines: Vec<String>| -> Vec<String> {
+            lines
+                .iter()
+                .map(|l| crate::ui::screen::strip_ansi(l))
+                .collect()
+        };
+        let sym = Symbols::current();
+        let header = format!(
+            "  {} {}",
+            sym.record,
+            t_with("plan.header", &[("done", "1"), ("total", "2")])
+        );
+
+        let full = plain(format_event(&plan));
+        assert_eq!(
+            full,
+            vec![
+                header.clone(),
+                format!("  │ 1. {} Read the parser", sym.ok),
+                format!("  │ 2. {} Fix empty input", sym.pending),
+                "  │    - add a regression test".to_string(),
+            ]
+        );
+        // 紧凑视图只保留每步首行
+        let compact = plain(format_event_compact(&plan));
+        assert_eq!(compact, full[..3].to_vec());
+
+        // 折叠视图中计划固定在顶部，即使它出现在其它事件之后
+        let events = vec![
+            Event::Thinking {
+                text: "looking around".to_string(),
+            },
+            plan,
+        ];
+        let collapsed = plain(collapsed_task_event_lines(&events));
+        assert_eq!(collapsed[0], header);
+        assert_eq!(collapsed.last().unwrap(), "  looking around");
+    }
+
+    #[test]
+    fn redraw_rebuilds_task_lines_from_app_state() {
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        let mut app = crate::App::new();
+        let mut screen = crate::ui::screen::Screen::new_headless().expect("headless screen");
+        app.task = "list files".to_string();
+        app.task_events = vec![
+            Event::ToolCall {
+                label: "Read".to_string(),
+                command: "cat a.txt".to_string(),
+                multiline: false,
+            },
+            Event::ToolResult {
+                exit_code: 0,
+                output: "hello".to_string(),
+            },
+        ];
+
+        // 运行中：沿用屏幕保留的内容，即使停在确认菜单也能重绘
+        app.running = true;
+        screen.task_rendered = vec!["live line".to_string()];
+        screen.confirm_selected = Some(0);
+        crate::ui::input::handle_key(&mut app, &mut screen, KeyCode::F(5), KeyModifiers::NONE);
+        assert_eq!(screen.task_rendered, vec!["live line".to_string()]);
+        screen.confirm_selected = None;
+
+        // 已结束：按折叠状态从 app 重建
+        app.running = false;
+        app.final_summary = Some("done".to_string());
+        app.task_collapsed = true;
+        crate::ui::input::handle_key(&mut app, &mut screen, KeyCode::F(5), KeyModifiers::NONE);
+        assert_eq!(screen.task_rendered, collapsed_lines(&app));
+
+        app.task_collapsed = false;
+        assert_eq!(redraw_lines(&app, &[]), expanded_lines(&app));
+        assert!(expanded_lines(&app).join("\n").contains("hello"));
+    }
+
     #[test]
     fn collapsed_keeps_single_read_detail() {
         let events = vec![
@@ -1141,4 +1976,34 @@ mod tests {
         let tool_idx = lines.find("Read(README.md)").expect("missing tool call");
         assert!(thinking_idx < tool_idx);
     }
+
+    #[test]
+    fn shorten_text_keeps_emoji_modifier_sequence_intact() {
+        let input = "ok 👍🏽👍🏽👍🏽";
+        let got = shorten_text(input, 7);
+        assert!(UnicodeWidthStr::width(got.as_str()) <= 7, "got: {got}");
+        // 不能只留下肤色修饰符前半截
+        let body = got.trim_end_matches(Symbols::current().ellipsis);
+        assert!(!body.ends_with('👍'), "modifier split: {got}");
+        assert_eq!(
+            body.matches('\u{1F3FD}').count(),
+            body.matches('👍').count()
+        );
+    }
+
+    #[test]
+    fn shorten_text_respects_cjk_display_width() {
+        let input = "这是一个非常长的中文句子需要被截断";
+        let got = shorten_text(input, 10);
+        assert!(UnicodeWidthStr::width(got.as_str()) <= 10, "got: {got}");
+        assert!(got.starts_with("这是一"));
+        assert!(got.ends_with(Symbols::current().ellipsis));
+    }
+
+    #[test]
+    fn shorten_text_prefers_word_boundary() {
+        let got = shorten_text("hello wonderful world", 14);
+        assert_eq!(got, format!("hello{}", Symbols::current().ellipsis));
+        assert_eq!(shorten_text("short", 14), "short");
+    }
 }
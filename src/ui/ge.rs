@@ -2,6 +2,7 @@ use crossterm::style::Stylize;
 
 use crate::App;
 use crate::types::Mode;
+use crate::ui::format::style_diff_line;
 use crate::ui::screen::{Screen, strip_ansi};
 
 pub(crate) fn drain_ge_events(app: &mut App, screen: &mut Screen) {
@@ -19,8 +20,10 @@ pub(crate) fn drain_ge_events(app: &mut App, screen: &mut Screen) {
                     if strip_ansi(&screen.status).starts_with("GE: processing interview input") {
                         screen.status.clear();
                     }
-                    let styled = stylize_ge_lines(&lines);
-                    screen.emit(&styled);
+                    let styled = stylize_ge_lines(&ge_output_lines(&lines, app.ge_compact));
+                    if !styled.is_empty() {
+                        screen.emit(&styled);
+                    }
                 }
             }
             Ok(crate::consensus::subagent::GeAgentEvent::ModeChanged(mode)) => {
@@ -95,8 +98,81 @@ pub(crate) fn parse_ge_command(task: &str) -> Option<&str> {
     Some(rest.trim_start_matches(|c: char| c.is_whitespace() || c == '\u{3000}'))
 }
 
+/// 紧凑视图下筛选一批 GE 输出；展开的 prompt / 结果和 `GE diff` 是用户主动请求的，始终完整显示。
+fn ge_output_lines(lines: &[String], compact: bool) -> Vec<String> {
+    let expanded = lines.iter().any(|line| {
+        let trimmed = line.trim_start();
+        trimmed.starts_with("GE PROMPT EXPANDED [")
+            || trimmed.starts_with("GE RESULT EXPANDED [")
+            || trimmed.starts_with("GE DIFF [")
+    });
+    if !compact || expanded {
+        return lines.to_vec();
+    }
+    lines
+        .iter()
+        .filter_map(|line| compact_ge_line(line))
+        .collect()
+}
+
+/// 紧凑视图：todo 状态变化改写为一行，阶段标题、prompt/结果折叠和中间步骤隐藏，其余原样保留。
+/// GE 的 todo 编号：`T` 后跟数字，如 `T005`。
+fn is_todo_id(word: &str) -> bool {
+    word.strip_prefix('T')
+        .is_some_and(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn compact_ge_line(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    if let Some(rest) = trimmed.strip_prefix("GE running ") {
+        let (id, text) = rest.split_once(' ').unwrap_or((rest, ""));
+        return Some(format!("  {id} ▶ {text}"));
+    }
+    if let Some((id, rest)) = trimmed.split_once(' ')
+        && is_todo_id(id)
+    {
+        if rest == "checked." {
+            return Some(format!("  {id} ✓ checked"));
+        }
+        if let Some(reason) = rest.strip_prefix("deferred: ") {
+            return Some(format!(
+                "  {id} ⚠ deferred: {}",
+                reason.trim_end_matches('.')
+            ));
+        }
+        let is_step = rest.ends_with(" started.")
+            || (rest.starts_with("->") && rest.contains("prompt:"))
+            || (rest.starts_with("<-") && rest.contains("result:"));
+        if is_step {
+            return None;
+        }
+    }
+    let bullet = crate::ui::symbols::Symbols::current().bullet;
+    let noise = trimmed.starts_with("================================")
+        || trimmed.starts_with("GE STAGE [")
+        || trimmed.starts_with("GE: use `GE 展开提示词`")
+        || trimmed.starts_with("GE: Working on current todo")
+        || trimmed == "Summary:"
+        || trimmed == "(no concise output)"
+        || trimmed.starts_with("...(collapsed ")
+        || trimmed.starts_with("...(truncated in console view;")
+        || (line.starts_with("    ") && trimmed.starts_with(bullet));
+    (!noise).then(|| line.to_string())
+}
+
+/// `GE DIFF [` 标题之后的行是 CONSENSUS.md 的 unified diff，按 diff 着色。
 fn stylize_ge_lines(lines: &[String]) -> Vec<String> {
-    lines.iter().map(|line| stylize_ge_line(line)).collect()
+    let mut in_diff = false;
+    lines
+        .iter()
+        .map(|line| {
+            if in_diff && let Some(styled) = style_diff_line(line) {
+                return styled;
+            }
+            in_diff |= line.trim_start().starts_with("GE DIFF [");
+            stylize_ge_line(line)
+        })
+        .collect()
 }
 
 fn stylize_ge_line(line: &str) -> String {
@@ -139,7 +215,7 @@ fn stylize_ge_line(line: &str) -> String {
     if trimmed.starts_with("GE PROMPT EXPANDED [") {
         return line.yellow().bold().to_string();
     }
-    if trimmed.starts_with("GE RESULT EXPANDED [") {
+    if trimmed.starts_with("GE RESULT EXPANDED [") || trimmed.starts_with("GE DIFF [") {
         return line.yellow().bold().to_string();
     }
     if trimmed.starts_with("GE ->") && trimmed.contains("prompt:") {
@@ -187,6 +263,15 @@ fn stylize_ge_line(line: &str) -> String {
     if trimmed.starts_with("GE running ") {
         return line.cyan().bold().to_string();
     }
+    if trimmed.starts_with('T') && trimmed.contains(" ✓ checked") {
+        return line.green().bold().to_string();
+    }
+    if trimmed.starts_with('T') && trimmed.contains(" ⚠ deferred:") {
+        return line.dark_yellow().to_string();
+    }
+    if trimmed.starts_with('T') && trimmed.contains(" ▶ ") {
+        return line.cyan().bold().to_string();
+    }
     if trimmed.starts_with('T') && trimmed.contains(" started.") {
         return line.cyan().to_string();
     }
@@ -210,7 +295,7 @@ fn stylize_ge_line(line: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::parse_ge_command;
+    use super::{compact_ge_line, ge_output_lines, is_todo_id, parse_ge_command};
 
     #[test]
     fn parse_ge_command_supports_exit_variants() {
@@ -224,4 +309,56 @@ mod tests {
         assert_eq!(parse_ge_command("get status"), None);
         assert_eq!(parse_ge_command("general"), None);
     }
+
+    #[test]
+    fn compact_view_keeps_one_line_per_todo_transition() {
+        let lines: Vec<String> = [
+            "  GE running T005 Add parser tests",
+            "  ================================================",
+            "  GE STAGE [T005] Claude execute",
+            "  ================================================",
+            "  T005 -> Claude execute prompt: [collapsed, 40 lines, 1800 chars]",
+            "  GE: use `GE 展开提示词` (or `GE expand prompt`) to expand.",
+            "  T005 <- Claude execute result: status=success exit=0 error=none",
+            "    Summary:",
+            "    • wrote tests",
+            "  T005 done_when validation started.",
+            "  T005 checked.",
+            "  T003 deferred: done_when validation failed.",
+            "  GE idle: all todos completed.",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+
+        assert_eq!(ge_output_lines(&lines, false), lines);
+        assert_eq!(
+            ge_output_lines(&lines, true),
+            vec![
+                "  T005 ▶ Add parser tests",
+                "  T005 ✓ checked",
+                "  T003 ⚠ deferred: done_when validation failed",
+                "  GE idle: all todos completed.",
+            ]
+        );
+
+        // 用户主动展开的 prompt 在紧凑视图下也完整显示
+        let expanded: Vec<String> = [
+            "  ================================================",
+            "  GE PROMPT EXPANDED [T005] Claude execute",
+            "  ================================================",
+            "    full prompt line",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+        assert_eq!(ge_output_lines(&expanded, true), expanded);
+
+        // 只有 `T` + 数字才是 todo 编号
+        assert_eq!(
+            compact_ge_line("  Tests checked."),
+            Some("  Tests checked.".to_string())
+        );
+        assert!(is_todo_id("T12") && !is_todo_id("T") && !is_todo_id("Tests"));
+    }
 }
@@ -0,0 +1,653 @@
+use std::sync::OnceLock;
+
+/// 界面语言：`GOLDBOT_LANG`（en / zh）优先，其次系统 locale；缺省英文。
+const ENV_LANG: &str = "GOLDBOT_LANG";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Lang {
+    En,
+    Zh,
+}
+
+/// 英文表：所有键的兜底，其他语言缺的键回落到这里。
+const EN: &[(&str, &str)] = &[
+    ("confirm.needs_confirmation", "Needs confirmation"),
+    (
+        "confirm.diff_more",
+        "{count} more diff lines (Ctrl+D shows the full diff)",
+    ),
+    (
+        "confirm.hint",
+        "{prompt} Type a note, or ↑/↓ to choose and Enter{countdown}",
+    ),
+    ("confirm.countdown", " (auto {action} in {left}s)"),
+    (
+        "confirm.note_prompt",
+        "✍ Type a note and press Enter; Esc returns to the menu",
+    ),
+    (
+        "question.hint",
+        "{prompt} ↑/↓ to choose, Enter to confirm, or type your own answer",
+    ),
+    ("question.type_answer", "✍ Type your answer and press Enter"),
+    (
+        "compact.banner",
+        "── Context compacted · folded {messages} messages · saved {notes} long-term notes ──",
+    ),
+    ("compact.preview_title", "/compact preview:"),
+    (
+        "compact.preview_title_auto",
+        "Context is about to exceed the budget, auto-compact preview:",
+    ),
+    (
+        "compact.preview_range",
+        "Messages {start}–{end} will be replaced by a summary ({total} total, keeping the latest {kept})",
+    ),
+    (
+        "compact.preview_summary",
+        "Summary preview (extracted locally; the LLM regenerates it after you confirm):",
+    ),
+    ("compact.preview_empty", "(nothing worth extracting)"),
+    ("compact.option_compact", "Compact"),
+    ("compact.option_cancel", "Cancel"),
+    (
+        "compact.cancelled",
+        "/compact: cancelled, history left unchanged.",
+    ),
+    (
+        "events.reading_files",
+        "Reading {count} files... (Ctrl+d for details)",
+    ),
+    (
+        "events.inspecting_git",
+        "Inspecting git {count} times... (Ctrl+d for details)",
+    ),
+    (
+        "events.repeated_run",
+        "ran {command} ×{count} (same result, Ctrl+d for each attempt)",
+    ),
+    ("picker.files", "Files ({count})"),
+    ("session.empty", "(no saved sessions)"),
+    ("session.current", "← current"),
+    (
+        "session.picker_title",
+        "Sessions (↑↓ to choose, Enter to resume):",
+    ),
+    ("help.keys_title", "Key bindings:"),
+    ("help.quit", "Quit"),
+    ("help.navigate", "Navigate menu options"),
+    ("help.enter", "Confirm selection / submit input"),
+    ("help.esc", "Leave input focus / close menu"),
+    ("help.at", "Search and attach files"),
+    ("screen.line_truncated", "(line truncated, {count} chars)"),
+    (
+        "help.slash",
+        "Open the command picker (empty input or a new draft line)",
+    ),
+    ("help.ge", "Enter Golden Experience supervision mode"),
+    ("help.ge_key", "GE <goal>"),
+    ("help.builtins", "Built-in commands:"),
+    ("key.collapse", "Expand / collapse task details"),
+    ("key.toggle_thinking", "Toggle native Thinking ON/OFF"),
+    ("key.cycle_mode", "Cycle assist mode (agent / Yolo / plan)"),
+    ("key.interrupt", "Interrupt the LLM"),
+    ("key.help", "Show help"),
+    ("key.cycle_verbosity", "Cycle output verbosity"),
+    ("key.compact_now", "Compact context now"),
+    ("key.cycle_backend", "Cycle favorite backends / models"),
+    (
+        "key.include_last_output",
+        "While interjecting, include the last tool output",
+    ),
+    (
+        "interject.include_output",
+        "Interjection will include the last tool output",
+    ),
+    (
+        "interject.recap_only",
+        "Interjection will send your message only",
+    ),
+    (
+        "key.toggle_thinking_panel",
+        "Toggle thinking layout (interleaved / panel)",
+    ),
+    (
+        "key.redraw",
+        "Clear and redraw the screen (fixes a garbled display)",
+    ),
+    ("key.clear_queue", "Clear inputs queued during a run"),
+    (
+        "key.transcript",
+        "Full-screen transcript of this session (scroll and search)",
+    ),
+    (
+        "key.toggle_ge_view",
+        "GE mode: toggle verbose / compact view",
+    ),
+    (
+        "key.draft_command",
+        "Open the command picker without clearing the draft",
+    ),
+    (
+        "key.pause",
+        "While a task runs: pause / resume the agent loop",
+    ),
+    ("queue.cleared", "Cleared {count} queued inputs"),
+    ("pause.on", "⏸ paused — {key} to resume"),
+    (
+        "pause.after_step",
+        "⏸ pausing after this step — {key} to resume",
+    ),
+    ("pause.off", "▶ resumed"),
+    ("thinking.layout_panel", "Thinking layout: panel"),
+    (
+        "thinking.layout_interleaved",
+        "Thinking layout: interleaved",
+    ),
+    (
+        "rerun.banner",
+        "── /rerun · {backend} · {model} · fresh context ──",
+    ),
+    (
+        "rerun.busy",
+        "/rerun: a task is still running; wait for it or press Esc first.",
+    ),
+    ("rerun.no_task", "/rerun: no previous task to rerun."),
+    (
+        "explain.busy",
+        "/explain: wait for the current command or LLM call to finish.",
+    ),
+    (
+        "explain.none",
+        "/explain: no failed command in the last task.",
+    ),
+    ("explain.waiting", "Diagnosing the last failure..."),
+    ("explain.header", "Diagnosis: {command}"),
+    ("explain.failed", "Couldn't get a diagnosis: {error}"),
+    (
+        "undo.busy",
+        "/undo: a task is still running; wait for it or press Esc first.",
+    ),
+    (
+        "undo.git_repo",
+        "/undo: this workspace is a git repo; revert with git (e.g. git restore).",
+    ),
+    ("undo.none", "/undo: no task snapshot to restore."),
+    ("undo.done", "✓ Undid file changes from task: {task}"),
+    ("undo.restored", "restored"),
+    ("undo.removed", "removed (created by the task)"),
+    ("undo.skipped", "skipped (snapshot copy missing or corrupt)"),
+    (
+        "undo.changed",
+        "/undo: these files changed after the task ended; nothing was restored. Use /undo --force to overwrite them:",
+    ),
+    (
+        "undo.over_cap",
+        "Snapshot size cap reached; changes to {path} cannot be undone with /undo.",
+    ),
+    (
+        "secret.requested",
+        "The agent needs {name} to continue (characters are hidden while you type).",
+    ),
+    (
+        "secret.hint",
+        "Paste {name} and press Enter; Esc to decline.",
+    ),
+    ("secret.waiting", "Waiting for {name}..."),
+    ("secret.empty", "Please input a value for {name}."),
+    ("secret.session", "Use for this session only"),
+    ("secret.save", "Save to {path}"),
+    ("secret.cancel", "Cancel"),
+    ("secret.scope_prompt", "How should {name} be kept?"),
+    ("secret.set_session", "✓ {name} set for this session."),
+    ("secret.set_saved", "✓ {name} saved to {path}."),
+    (
+        "secret.declined",
+        "{name} not provided; the agent will continue without it.",
+    ),
+    ("secret.retrying", "Retrying: {command}"),
+    (
+        "init.untrusted",
+        "This workspace has an init script: {path}. It runs at startup and the variables it exports apply to every command.",
+    ),
+    ("init.run_once", "Run once"),
+    ("init.trust", "Trust this script and run it"),
+    ("init.skip", "Skip"),
+    ("init.running", "Running {path}..."),
+    (
+        "init.applied",
+        "✓ {path} finished; variables applied to commands: {names}",
+    ),
+    (
+        "init.no_env",
+        "✓ {path} finished; it exported no variables.",
+    ),
+    (
+        "init.failed",
+        "{path} exited with status {status}; its environment is not applied.",
+    ),
+    (
+        "init.stopped",
+        "{path} timed out or was killed; its environment is not applied.",
+    ),
+    ("init.error", "Couldn't run {path}: {error}"),
+    ("init.skipped", "Skipped {path}."),
+    (
+        "init.task_held",
+        "Waiting for the init script to finish before starting the task...",
+    ),
+    (
+        "init.skipped_untrusted",
+        "Skipped untrusted {path}; start GoldBot interactively once to confirm it.",
+    ),
+    ("plan.header", "Plan ({done}/{total})"),
+    ("raw.header", "Raw response ({lines} lines)"),
+    (
+        "raw.truncated",
+        "… {count} more lines (Ctrl+d after the task shows the full text)",
+    ),
+    ("transcript.position", "Transcript {from}-{to}/{total}"),
+    ("transcript.match", "match {current}/{count} \"{term}\""),
+    ("transcript.no_match", "no match for \"{term}\""),
+    (
+        "transcript.keys",
+        "j/k scroll · PgUp/PgDn page · / search · n/N next/prev · Esc close",
+    ),
+    ("transcript.empty", "(no events in this session yet)"),
+    ("memory.remembered", "✓ Remembered: {note}"),
+    ("memory.already_known", "Already in memory, nothing added."),
+    ("memory.remember_usage", "Usage: /remember <note>"),
+    ("memory.forgot", "Forgot {count} notes:"),
+    ("memory.forget_none", "No long-term note matches."),
+    ("memory.forget_usage", "Usage: /forget <text>"),
+    (
+        "mcp.rollback_prompt",
+        "MCP server `{name}` did not start. Roll back the config write?",
+    ),
+    ("mcp.option_rollback", "Roll back config"),
+    ("mcp.option_keep", "Keep config"),
+    (
+        "mcp.rolled_back",
+        "Rolled back: restored the MCP config from before `{name}` was written.",
+    ),
+    (
+        "mcp.kept",
+        "Kept MCP server `{name}` in the config despite the failed start.",
+    ),
+    (
+        "mcp.call_prompt",
+        "`{name}` is marked destructive by its server. Call it now?",
+    ),
+    ("mcp.option_call", "Call tool"),
+    ("mcp.option_cancel", "Cancel"),
+    ("mcp.call_cancelled", "MCP call cancelled."),
+    (
+        "delegate.prompt",
+        "`@{name}` runs the task without GoldBot's confirmations. Delegate it now?",
+    ),
+    ("delegate.option_run", "Delegate"),
+    ("delegate.option_cancel", "Cancel"),
+    ("delegate.cancelled", "Delegation cancelled."),
+    (
+        "prompt_warn.prompt",
+        "This prompt is ~{tokens} tokens (est. cost ${cost}). Send it?",
+    ),
+    ("prompt_warn.option_send", "Send"),
+    ("prompt_warn.option_trim", "Trim (compact context first)"),
+    ("prompt_warn.option_cancel", "Cancel"),
+    (
+        "prompt_warn.cancelled",
+        "Cancelled: the prompt exceeded GOLDBOT_PROMPT_WARN_TOKENS.",
+    ),
+    ("attach.empty", "The draft has no @ files or pasted text."),
+    (
+        "attach.header",
+        "Draft attachments ({count}; /attachments remove <n> to remove one):",
+    ),
+    ("attach.image", "image"),
+    ("attach.file", "file"),
+    ("attach.unreadable", "unreadable"),
+    ("attach.file_detail", "{kind}, {size}"),
+    ("attach.paste_detail", "pasted, {lines} lines / {size}"),
+    ("attach.removed", "Removed {placeholder}"),
+    (
+        "attach.remove_usage",
+        "Usage: /attachments remove <n> (numbers as in the /attachments list)",
+    ),
+    ("attach.usage", "Usage: /attachments [remove <n>]"),
+    ("skills.reloaded_none", "Reloaded: no skills found."),
+    ("mcp.rediscovering", "Rediscovering MCP tools…"),
+    ("mcp.reloaded_none", "Reloaded: no MCP servers configured."),
+    (
+        "raw.on",
+        "Raw responses: ON (each LLM response is written to the scroll area as received)",
+    ),
+    ("raw.off", "Raw responses: OFF"),
+];
+
+const ZH: &[(&str, &str)] = &[
+    ("confirm.needs_confirmation", "需要确认"),
+    (
+        "confirm.diff_more",
+        "还有 {count} 行 diff（Ctrl+D 展开完整 diff）",
+    ),
+    (
+        "confirm.hint",
+        "{prompt} 直接输入补充说明，或 ↑/↓ 选择后 Enter{countdown}",
+    ),
+    ("confirm.countdown", "（{left}s 后自动 {action}）"),
+    (
+        "confirm.note_prompt",
+        "✍ 输入补充说明后按 Enter；Esc 返回确认菜单",
+    ),
+    (
+        "question.hint",
+        "{prompt} ↑/↓ 选择，Enter 确认，或直接输入自定义内容",
+    ),
+    ("question.type_answer", "✍ 请输入你的答案后按 Enter"),
+    (
+        "compact.banner",
+        "── 上下文已压缩 · 折叠 {messages} 条消息 · 写入 {notes} 条长期记忆 ──",
+    ),
+    ("compact.preview_title", "/compact 预览："),
+    (
+        "compact.preview_title_auto",
+        "上下文即将超出预算，自动 compact 预览：",
+    ),
+    (
+        "compact.preview_range",
+        "将用摘要替换第 {start}–{end} 条消息（共 {total} 条，保留最近 {kept} 条）",
+    ),
+    (
+        "compact.preview_summary",
+        "摘要预览（本地提取；确认后由 LLM 重新生成）：",
+    ),
+    ("compact.preview_empty", "（无可提取的关键信息）"),
+    ("compact.option_compact", "压缩"),
+    ("compact.option_cancel", "取消"),
+    ("compact.cancelled", "/compact: 已取消，历史消息保持不变。"),
+    (
+        "events.reading_files",
+        "Reading {count} files... (Ctrl+d 查看详情)",
+    ),
+    (
+        "events.inspecting_git",
+        "查看 git 状态 {count} 次... (Ctrl+d 查看详情)",
+    ),
+    (
+        "events.repeated_run",
+        "执行 {command} ×{count}（结果相同，Ctrl+d 查看每次尝试）",
+    ),
+    ("picker.files", "文件 ({count})"),
+    ("session.empty", "（暂无历史会话）"),
+    ("session.current", "← 当前"),
+    ("session.picker_title", "历史会话（↑↓ 选择，Enter 恢复）:"),
+    ("help.keys_title", "键位绑定："),
+    ("help.quit", "退出"),
+    ("help.navigate", "导航菜单选项"),
+    ("help.enter", "确认选择 / 提交输入"),
+    ("help.esc", "取消输入焦点 / 关闭菜单"),
+    ("help.at", "搜索并附加文件"),
+    ("screen.line_truncated", "（行已截断，共 {count} 个字符）"),
+    ("help.slash", "打开命令选择器（空输入框或草稿中的空行）"),
+    ("help.ge", "进入 Golden Experience 督导模式"),
+    ("help.ge_key", "GE <目标>"),
+    ("help.builtins", "内置命令："),
+    ("key.collapse", "展开/折叠任务详情"),
+    ("key.toggle_thinking", "切换原生 Thinking ON/OFF"),
+    ("key.cycle_mode", "循环切换协助模式 (agent / Yolo / plan)"),
+    ("key.interrupt", "中断 LLM"),
+    ("key.help", "显示帮助"),
+    ("key.cycle_verbosity", "循环切换输出详细程度"),
+    ("key.compact_now", "立即压缩上下文"),
+    ("key.cycle_backend", "循环切换收藏的后端/模型"),
+    ("key.include_last_output", "插话时附上最近一次工具输出"),
+    ("interject.include_output", "插话将附上最近一次工具输出"),
+    ("interject.recap_only", "插话只发送你的消息"),
+    (
+        "key.toggle_thinking_panel",
+        "切换 Thinking 布局（交错 / 独立面板）",
+    ),
+    ("key.redraw", "清屏并重绘（修复错乱的显示）"),
+    ("key.clear_queue", "清空运行中排队的输入"),
+    ("key.transcript", "全屏查看本会话的转录（可滚动、搜索）"),
+    ("key.toggle_ge_view", "GE 模式：切换详细 / 紧凑视图"),
+    ("key.draft_command", "保留草稿打开命令选择器"),
+    ("key.pause", "任务运行中：暂停 / 继续 agent 循环"),
+    ("queue.cleared", "已清空 {count} 条排队输入"),
+    ("pause.on", "⏸ 已暂停 — {key} 继续"),
+    ("pause.after_step", "⏸ 当前步骤完成后暂停 — {key} 继续"),
+    ("pause.off", "▶ 已继续"),
+    ("thinking.layout_panel", "Thinking 布局：独立面板"),
+    ("thinking.layout_interleaved", "Thinking 布局：与输出交错"),
+    (
+        "rerun.banner",
+        "── /rerun · {backend} · {model} · 全新上下文 ──",
+    ),
+    (
+        "rerun.busy",
+        "/rerun: 当前任务仍在运行，请等待完成或先按 Esc。",
+    ),
+    ("rerun.no_task", "/rerun: 没有可重跑的上一个任务。"),
+    ("explain.busy", "/explain: 请等待当前命令或 LLM 调用结束。"),
+    ("explain.none", "/explain: 上一个任务中没有失败的命令。"),
+    ("explain.waiting", "正在诊断上一次失败..."),
+    ("explain.header", "诊断：{command}"),
+    ("explain.failed", "无法获取诊断：{error}"),
+    ("undo.busy", "/undo: 任务仍在运行，请等待完成或先按 Esc。"),
+    (
+        "undo.git_repo",
+        "/undo: 当前工作区是 git 仓库，请用 git 撤销（如 git restore）。",
+    ),
+    ("undo.none", "/undo: 没有可恢复的任务快照。"),
+    ("undo.done", "✓ 已撤销任务的文件改动：{task}"),
+    ("undo.restored", "已恢复"),
+    ("undo.removed", "已删除（任务新建）"),
+    ("undo.skipped", "已跳过（快照副本缺失或损坏）"),
+    (
+        "undo.changed",
+        "/undo: 以下文件在任务结束后又被改过，未做任何恢复；确认覆盖请用 /undo --force：",
+    ),
+    (
+        "undo.over_cap",
+        "快照已达大小上限，{path} 的改动无法通过 /undo 撤销。",
+    ),
+    (
+        "secret.requested",
+        "Agent 需要 {name} 才能继续（输入内容不会显示）。",
+    ),
+    ("secret.hint", "粘贴 {name} 后按 Enter；按 Esc 拒绝。"),
+    ("secret.waiting", "等待输入 {name}..."),
+    ("secret.empty", "请输入 {name} 的值。"),
+    ("secret.session", "仅本次会话使用"),
+    ("secret.save", "保存到 {path}"),
+    ("secret.cancel", "取消"),
+    ("secret.scope_prompt", "{name} 如何保存？"),
+    ("secret.set_session", "✓ 已为本次会话设置 {name}。"),
+    ("secret.set_saved", "✓ 已将 {name} 保存到 {path}。"),
+    (
+        "secret.declined",
+        "未提供 {name}，Agent 将在没有它的情况下继续。",
+    ),
+    ("secret.retrying", "重试：{command}"),
+    (
+        "init.untrusted",
+        "此工作区有初始化脚本：{path}。它在启动时运行，导出的变量会作用于之后的每条命令。",
+    ),
+    ("init.run_once", "仅本次运行"),
+    ("init.trust", "信任此脚本并运行"),
+    ("init.skip", "跳过"),
+    ("init.running", "正在运行 {path}..."),
+    (
+        "init.applied",
+        "✓ {path} 已完成，以下变量已应用到命令：{names}",
+    ),
+    ("init.no_env", "✓ {path} 已完成，没有导出变量。"),
+    ("init.failed", "{path} 退出码为 {status}，其环境未应用。"),
+    ("init.stopped", "{path} 超时或被终止，其环境未应用。"),
+    ("init.error", "无法运行 {path}：{error}"),
+    ("init.skipped", "已跳过 {path}。"),
+    ("init.task_held", "等待初始化脚本完成后开始任务..."),
+    (
+        "init.skipped_untrusted",
+        "已跳过未信任的 {path}；以交互模式启动 GoldBot 确认一次即可。",
+    ),
+    ("plan.header", "计划（{done}/{total}）"),
+    ("raw.header", "原始响应（{lines} 行）"),
+    (
+        "raw.truncated",
+        "… 还有 {count} 行（任务结束后 Ctrl+d 查看全文）",
+    ),
+    ("transcript.position", "转录 {from}-{to}/{total}"),
+    ("transcript.match", "匹配 {current}/{count} \"{term}\""),
+    ("transcript.no_match", "没有匹配 \"{term}\""),
+    (
+        "transcript.keys",
+        "j/k 滚动 · PgUp/PgDn 翻页 · / 搜索 · n/N 下一个/上一个 · Esc 关闭",
+    ),
+    ("transcript.empty", "（本会话还没有事件）"),
+    ("memory.remembered", "✓ 已记住：{note}"),
+    ("memory.already_known", "记忆中已有这条内容，未重复写入。"),
+    ("memory.remember_usage", "用法：/remember <内容>"),
+    ("memory.forgot", "已删除 {count} 条记忆："),
+    ("memory.forget_none", "没有匹配的长期记忆。"),
+    ("memory.forget_usage", "用法：/forget <关键词>"),
+    (
+        "mcp.rollback_prompt",
+        "MCP 服务端 `{name}` 启动失败，是否回滚这次配置写入？",
+    ),
+    ("mcp.option_rollback", "回滚配置"),
+    ("mcp.option_keep", "保留配置"),
+    (
+        "mcp.rolled_back",
+        "已回滚：MCP 配置已恢复到写入 `{name}` 之前。",
+    ),
+    (
+        "mcp.kept",
+        "已保留 MCP 服务端 `{name}` 的配置（启动校验未通过）。",
+    ),
+    (
+        "mcp.call_prompt",
+        "`{name}` 被服务端标记为破坏性操作，确定现在调用吗？",
+    ),
+    ("mcp.option_call", "调用工具"),
+    ("mcp.option_cancel", "取消"),
+    ("mcp.call_cancelled", "已取消 MCP 调用。"),
+    (
+        "delegate.prompt",
+        "`@{name}` 执行任务时不经过 GoldBot 的确认，确定现在委派吗？",
+    ),
+    ("delegate.option_run", "委派"),
+    ("delegate.option_cancel", "取消"),
+    ("delegate.cancelled", "已取消委派。"),
+    (
+        "prompt_warn.prompt",
+        "本次 prompt 约 {tokens} tokens（估算费用 ${cost}），是否发送？",
+    ),
+    ("prompt_warn.option_send", "发送"),
+    ("prompt_warn.option_trim", "裁剪（先压缩上下文）"),
+    ("prompt_warn.option_cancel", "取消"),
+    (
+        "prompt_warn.cancelled",
+        "已取消：prompt 超过 GOLDBOT_PROMPT_WARN_TOKENS 阈值。",
+    ),
+    ("attach.empty", "当前草稿没有 @ 文件或粘贴内容。"),
+    (
+        "attach.header",
+        "草稿附件（{count} 项，/attachments remove <序号> 移除）：",
+    ),
+    ("attach.image", "图片"),
+    ("attach.file", "文件"),
+    ("attach.unreadable", "无法读取"),
+    ("attach.file_detail", "{kind}，{size}"),
+    ("attach.paste_detail", "粘贴，{lines} 行 / {size}"),
+    ("attach.removed", "已移除 {placeholder}"),
+    (
+        "attach.remove_usage",
+        "用法：/attachments remove <序号>（序号见 /attachments 列表）",
+    ),
+    ("attach.usage", "用法：/attachments [remove <序号>]"),
+    ("skills.reloaded_none", "已重新加载：未发现任何 Skill。"),
+    ("mcp.rediscovering", "正在重新发现 MCP 工具…"),
+    ("mcp.reloaded_none", "已重新加载：未配置任何 MCP 服务器。"),
+    ("raw.on", "原始响应：ON（每轮 LLM 原始响应写入滚动区）"),
+    ("raw.off", "原始响应：OFF"),
+];
+
+impl Lang {
+    /// 解析 `en` / `zh` / `zh_CN.UTF-8` 一类的取值。
+    fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim().to_ascii_lowercase();
+        if raw.starts_with("zh") {
+            Some(Self::Zh)
+        } else if raw.starts_with("en") {
+            Some(Self::En)
+        } else {
+            None
+        }
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::En => EN,
+            Self::Zh => ZH,
+        }
+    }
+}
+
+/// 当前界面语言（进程内只判定一次）。
+pub(crate) fn lang() -> Lang {
+    static LANG: OnceLock<Lang> = OnceLock::new();
+    *LANG.get_or_init(|| {
+        [ENV_LANG, "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|v| !v.trim().is_empty())
+            .and_then(|v| Lang::parse(&v))
+            .unwrap_or(Lang::En)
+    })
+}
+
+/// 在 `table` 中查找；缺失时回落英文，英文也没有则原样返回键名。
+fn lookup_in(table: &[(&'static str, &'static str)], key: &'static str) -> &'static str {
+    let find = |table: &[(&'static str, &'static str)]| {
+        table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    };
+    find(table).or_else(|| find(EN)).unwrap_or(key)
+}
+
+/// 当前语言下的界面文案。
+pub(crate) fn t(key: &'static str) -> &'static str {
+    lookup_in(lang().table(), key)
+}
+
+/// 带 `{name}` 占位符的界面文案。
+pub(crate) fn t_with(key: &'static str, args: &[(&str, &str)]) -> String {
+    args.iter().fold(t(key).to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{name}}}"), value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EN, Lang, ZH, lookup_in};
+
+    #[test]
+    fn every_zh_key_exists_in_en() {
+        for (key, _) in ZH {
+            assert!(EN.iter().any(|(k, _)| k == key), "missing en string: {key}");
+        }
+    }
+
+    #[test]
+    fn lookup_falls_back_to_english_then_key() {
+        assert_eq!(lookup_in(Lang::Zh.table(), "help.quit"), "退出");
+        assert_eq!(lookup_in(Lang::En.table(), "help.quit"), "Quit");
+        // 译文表缺的键回落英文，英文也没有时返回键名
+        let partial: &[(&str, &str)] = &[("help.quit", "退出")];
+        assert_eq!(lookup_in(partial, "help.at"), "Search and attach files");
+        assert_eq!(lookup_in(partial, "no.such.key"), "no.such.key");
+        assert_eq!(Lang::parse("zh_CN.UTF-8"), Some(Lang::Zh));
+        assert_eq!(Lang::parse("C.UTF-8"), None);
+    }
+}
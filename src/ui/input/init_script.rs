@@ -0,0 +1,212 @@
+use std::path::Path;
+
+use crossterm::style::Stylize;
+
+use crate::App;
+use crate::config::display_config_path;
+use crate::memory::profile::{apply_init_env, init_script, init_script_trusted, trust_init_script};
+use crate::tools::init_env::{InitOutcome, capture_init_env};
+use crate::ui::format::save_warning_line;
+use crate::ui::i18n::{t, t_with};
+use crate::ui::screen::Screen;
+use crate::ui::symbols::Symbols;
+
+use super::submit::start_submitted_task;
+
+/// 脚本失败时显示的输出行数。
+const INIT_OUTPUT_TAIL_LINES: usize = 5;
+
+/// 启动时处理工作区的 `.goldbot/init.sh`：已信任的直接运行，否则先确认；
+/// headless 模式无人确认，未信任的脚本跳过。
+pub(crate) fn start_init_script(app: &mut App, screen: &mut Screen) {
+    let Some(script) = init_script(&app.workspace) else {
+        return;
+    };
+    if init_script_trusted(&app.workspace, &script) {
+        run_init_script(app, screen, &script);
+        return;
+    }
+    let path = display_config_path(&script);
+    if screen.headless {
+        screen.emit(&[format!(
+            "  {} {}",
+            Symbols::current().warning.yellow(),
+            t_with("init.skipped_untrusted", &[("path", &path)])
+        )]);
+        return;
+    }
+    screen.emit(&[format!(
+        "  {} {}",
+        Symbols::current().warning.yellow(),
+        t_with("init.untrusted", &[("path", &path)])
+    )]);
+    screen.question_labels = vec![
+        t("init.run_once").to_string(),
+        t("init.trust").to_string(),
+        t("init.skip").to_string(),
+    ];
+    screen.confirm_selected = Some(0);
+    screen.input_focused = false;
+    app.pending_init_script = Some(script);
+    screen.refresh();
+}
+
+/// 确认菜单：0 = 仅本次运行，1 = 信任并运行，其它 = 跳过。
+pub(super) fn resolve_init_script(app: &mut App, screen: &mut Screen, choice: usize) {
+    let Some(script) = app.pending_init_script.take() else {
+        return;
+    };
+    screen.confirm_selected = None;
+    screen.question_labels.clear();
+    screen.input_focused = true;
+    match choice {
+        0 | 1 => {
+            // 信任记录保存失败不影响本次运行
+            if choice == 1
+                && let Err(e) = trust_init_script(&app.workspace, &script)
+            {
+                screen.emit(&[save_warning_line(&e)]);
+            }
+            run_init_script(app, screen, &script);
+        }
+        _ => screen.emit(&[format!(
+            "  {}",
+            t_with("init.skipped", &[("path", &display_config_path(&script))])
+        )]),
+    }
+    screen.refresh();
+}
+
+/// 在后台线程运行脚本，结果由 `poll_init_script` 报告；脚本可能要装依赖、切换版本，
+/// 不能卡住界面。headless 模式马上要执行任务，同步运行保证工具命令拿到导出的变量。
+fn run_init_script(app: &mut App, screen: &mut Screen, script: &Path) {
+    let path = display_config_path(script);
+    if screen.headless {
+        let outcome = capture_init_env(script, &app.workspace);
+        report_init_outcome(screen, &path, outcome);
+        return;
+    }
+    screen.status = t_with("init.running", &[("path", &path)])
+        .dark_grey()
+        .to_string();
+    screen.refresh();
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let (script, workspace) = (script.to_path_buf(), app.workspace.clone());
+    std::thread::spawn(move || {
+        let _ = tx.send(capture_init_env(&script, &workspace));
+    });
+    app.init_script_rx = Some((rx, path));
+}
+
+/// 后台脚本结束后报告结果并导出变量。
+pub(crate) fn poll_init_script(app: &mut App, screen: &mut Screen) {
+    let Some((rx, _)) = app.init_script_rx.as_mut() else {
+        return;
+    };
+    let outcome = match rx.try_recv() {
+        Ok(outcome) => outcome,
+        Err(tokio::sync::oneshot::error::TryRecvError::Empty) => return,
+        Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+            Err(anyhow::anyhow!("init script worker exited"))
+        }
+    };
+    let Some((_, path)) = app.init_script_rx.take() else {
+        return;
+    };
+    screen.status.clear();
+    report_init_outcome(screen, &path, outcome);
+    if let Some(task) = app.task_after_init.take() {
+        start_submitted_task(app, screen, &task);
+    }
+    screen.refresh();
+}
+
+/// 报告退出码；成功时导出与 unset 的变量作用于之后的工具命令（只显示变量名，不显示值）。
+fn report_init_outcome(screen: &mut Screen, path: &str, outcome: anyhow::Result<InitOutcome>) {
+    let warning = Symbols::current().warning.yellow();
+    let mut lines = Vec::new();
+    match outcome {
+        Ok(outcome) if outcome.status == Some(0) => {
+            let names: Vec<String> = outcome
+                .env
+                .iter()
+                .map(|(name, value)| match value {
+                    Some(_) => name.clone(),
+                    None => format!("unset {name}"),
+                })
+                .collect();
+            lines.push(if names.is_empty() {
+                format!("  {}", t_with("init.no_env", &[("path", path)]))
+            } else {
+                format!(
+                    "  {}",
+                    t_with(
+                        "init.applied",
+                        &[("path", path), ("names", &names.join(", "))]
+                    )
+                )
+            });
+            apply_init_env(outcome.env);
+        }
+        Ok(outcome) => {
+            let line = match outcome.status {
+                Some(status) => t_with(
+                    "init.failed",
+                    &[("path", path), ("status", &status.to_string())],
+                ),
+                None => t_with("init.stopped", &[("path", path)]),
+            };
+            lines.push(format!("  {warning} {line}"));
+            // 失败时附上脚本输出的最后几行，方便定位
+            let output: Vec<&str> = outcome.output.lines().collect();
+            let tail = &output[output.len().saturating_sub(INIT_OUTPUT_TAIL_LINES)..];
+            lines.extend(tail.iter().map(|line| format!("    {}", line.dark_grey())));
+        }
+        Err(e) => lines.push(format!(
+            "  {warning} {}",
+            t_with(
+                "init.error",
+                &[("path", path), ("error", &format!("{e:#}"))]
+            )
+        )),
+    }
+    screen.emit(&lines);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::poll_init_script;
+    use crate::App;
+    use crate::tools::init_env::InitOutcome;
+    use crate::ui::input::submit::submit_user_input;
+    use crate::ui::screen::Screen;
+
+    #[test]
+    fn tasks_submitted_during_init_start_after_it_reports() {
+        let mut screen = Screen::new_headless().expect("headless screen");
+        let mut app = App::new();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        app.init_script_rx = Some((rx, ".goldbot/init.sh".to_string()));
+
+        submit_user_input(&mut app, &mut screen, "run the tests".to_string());
+        submit_user_input(&mut app, &mut screen, "then lint".to_string());
+        assert!(!app.running);
+        assert_eq!(
+            app.task_after_init.as_deref(),
+            Some("run the tests\n\nthen lint")
+        );
+
+        poll_init_script(&mut app, &mut screen);
+        assert!(!app.running);
+        // 脚本失败同样放行任务（失败的结果不改动全局的 init 环境）
+        let _ = tx.send(Ok(InitOutcome {
+            status: Some(1),
+            env: Default::default(),
+            output: String::new(),
+        }));
+        poll_init_script(&mut app, &mut screen);
+        assert!(app.init_script_rx.is_none() && app.task_after_init.is_none());
+        assert!(app.running);
+        assert_eq!(app.task, "run the tests\n\nthen lint");
+    }
+}
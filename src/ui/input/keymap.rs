@@ -0,0 +1,392 @@
+use std::{fmt, path::Path};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::ui::i18n::t;
+
+/// 用户键位配置文件名（位于 `~/.goldbot/`）。
+const KEYBINDINGS_FILE: &str = "keybindings.toml";
+
+/// 可重新绑定的快捷键动作。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyAction {
+    Collapse,
+    ToggleThinking,
+    CycleMode,
+    Interrupt,
+    Help,
+    CycleVerbosity,
+    CompactNow,
+    CycleBackend,
+    IncludeLastOutput,
+    ToggleThinkingPanel,
+    Redraw,
+    ClearQueue,
+    Transcript,
+    ToggleGeView,
+    DraftCommand,
+    Pause,
+}
+
+/// (动作, 配置名, 默认键位, 帮助说明的 i18n 键)
+const ACTIONS: &[(KeyAction, &str, &str, &str)] = &[
+    (KeyAction::Collapse, "collapse", "ctrl+d", "key.collapse"),
+    (
+        KeyAction::ToggleThinking,
+        "toggle_thinking",
+        "tab",
+        "key.toggle_thinking",
+    ),
+    (
+        KeyAction::CycleMode,
+        "cycle_mode",
+        "shift+tab",
+        "key.cycle_mode",
+    ),
+    (KeyAction::Interrupt, "interrupt", "esc", "key.interrupt"),
+    (KeyAction::Help, "help", "f1", "key.help"),
+    (
+        KeyAction::CycleVerbosity,
+        "cycle_verbosity",
+        "ctrl+o",
+        "key.cycle_verbosity",
+    ),
+    (
+        KeyAction::CompactNow,
+        "compact_now",
+        "ctrl+k",
+        "key.compact_now",
+    ),
+    (
+        KeyAction::CycleBackend,
+        "cycle_backend",
+        "ctrl+b",
+        "key.cycle_backend",
+    ),
+    (
+        KeyAction::IncludeLastOutput,
+        "include_last_output",
+        "ctrl+l",
+        "key.include_last_output",
+    ),
+    (
+        KeyAction::ToggleThinkingPanel,
+        "toggle_thinking_panel",
+        "ctrl+g",
+        "key.toggle_thinking_panel",
+    ),
+    (KeyAction::Redraw, "redraw", "f5", "key.redraw"),
+    (
+        KeyAction::ClearQueue,
+        "clear_queue",
+        "ctrl+x",
+        "key.clear_queue",
+    ),
+    (KeyAction::Transcript, "transcript", "f2", "key.transcript"),
+    (
+        KeyAction::ToggleGeView,
+        "toggle_ge_view",
+        "ctrl+e",
+        "key.toggle_ge_view",
+    ),
+    (
+        KeyAction::DraftCommand,
+        "draft_command",
+        "alt+/",
+        "key.draft_command",
+    ),
+    (KeyAction::Pause, "pause", "ctrl+p", "key.pause"),
+];
+
+/// 一个按键 + 修饰键组合，如 `ctrl+d`、`shift+tab`、`f1`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct KeySpec {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeySpec {
+    pub(crate) fn parse(raw: &str) -> Option<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<&str> = raw.split('+').map(str::trim).collect();
+        let key = parts.pop()?.to_ascii_lowercase();
+        for part in parts {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+        let code = match key.as_str() {
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            f if f.len() > 1 && f.starts_with('f') => KeyCode::F(f[1..].parse().ok()?),
+            c if c.chars().count() == 1 => KeyCode::Char(c.chars().next()?),
+            _ => return None,
+        };
+        Some(Self { code, modifiers })
+    }
+
+    /// 无修饰键的绑定要求按下时也没有修饰键；有修饰键的绑定只要求包含它们。
+    /// 字母按键忽略大小写（Ctrl+D 在部分终端上报为 `D`）。
+    fn matches(&self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let code_ok = match (self.code, code) {
+            (KeyCode::Char(a), KeyCode::Char(b)) => a.eq_ignore_ascii_case(&b),
+            (a, b) => a == b,
+        };
+        let mods_ok = if self.modifiers.is_empty() {
+            modifiers.is_empty()
+        } else {
+            modifiers.contains(self.modifiers)
+        };
+        code_ok && mods_ok
+    }
+}
+
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::BackTab | KeyCode::Tab => f.write_str("Tab"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+/// 动作到键位的映射；启动时从 `~/.goldbot/keybindings.toml` 加载，缺省为内置键位。
+#[derive(Debug, Clone)]
+pub(crate) struct KeyMap {
+    bindings: Vec<(KeyAction, KeySpec)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            bindings: ACTIONS
+                .iter()
+                .map(|(action, _, key, _)| (*action, default_spec(*action, key)))
+                .collect(),
+        }
+    }
+}
+
+fn default_spec(action: KeyAction, key: &str) -> KeySpec {
+    KeySpec::parse(key).unwrap_or_else(|| panic!("invalid default binding for {action:?}"))
+}
+
+impl KeyMap {
+    /// 读取 `<goldbot_home>/keybindings.toml`；文件不存在时使用默认键位。
+    pub(crate) fn load() -> (Self, Vec<String>) {
+        let path = crate::memory::store::default_memory_base_dir().join(KEYBINDINGS_FILE);
+        Self::load_from(&path)
+    }
+
+    fn load_from(path: &Path) -> (Self, Vec<String>) {
+        match std::fs::read_to_string(path) {
+            Ok(raw) => Self::parse(&raw),
+            Err(_) => (Self::default(), Vec::new()),
+        }
+    }
+
+    /// 解析键位配置，支持顶层键或 `[keybindings]` 表：`collapse = "ctrl+d"`。
+    /// 未知动作、无法解析或与其它动作冲突的键位会产生警告并回退到默认值。
+    pub(crate) fn parse(raw: &str) -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let mut warnings = Vec::new();
+        let doc: toml::Table = match toml::from_str(raw) {
+            Ok(doc) => doc,
+            Err(e) => {
+                warnings.push(format!("{KEYBINDINGS_FILE} is not valid TOML: {e}"));
+                return (keymap, warnings);
+            }
+        };
+        let table = match doc.get("keybindings").and_then(toml::Value::as_table) {
+            Some(table) => table,
+            None => &doc,
+        };
+
+        let mut configured = Vec::new();
+        for (name, value) in table {
+            let Some((action, ..)) = ACTIONS.iter().find(|(_, n, ..)| n == name) else {
+                warnings.push(format!("unknown action `{name}` ignored"));
+                continue;
+            };
+            let raw = value.as_str().unwrap_or_default();
+            let Some(spec) = KeySpec::parse(raw) else {
+                warnings.push(format!("invalid key `{raw}` for `{name}`; using default"));
+                continue;
+            };
+            keymap.set(*action, spec);
+            configured.push(*action);
+        }
+
+        // 冲突时把用户配置的那一方回退为默认键位，直到没有冲突
+        while let Some(action) = keymap.first_conflict(&configured) {
+            let (_, name, key, _) = ACTIONS.iter().find(|(a, ..)| *a == action).unwrap();
+            warnings.push(format!(
+                "`{name}` conflicts with another binding; using default {}",
+                default_spec(action, key)
+            ));
+            keymap.set(action, default_spec(action, key));
+            configured.retain(|a| *a != action);
+        }
+        (keymap, warnings)
+    }
+
+    fn set(&mut self, action: KeyAction, spec: KeySpec) {
+        if let Some(slot) = self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            slot.1 = spec;
+        }
+    }
+
+    fn first_conflict(&self, configured: &[KeyAction]) -> Option<KeyAction> {
+        configured.iter().copied().find(|action| {
+            let spec = self.spec(*action);
+            self.bindings
+                .iter()
+                .any(|(other, s)| other != action && Some(*s) == spec)
+        })
+    }
+
+    fn spec(&self, action: KeyAction) -> Option<KeySpec> {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, s)| *s)
+    }
+
+    /// 按键对应的动作。
+    pub(crate) fn action_for(&self, code: KeyCode, modifiers: KeyModifiers) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|(_, spec)| spec.matches(code, modifiers))
+            .map(|(action, _)| *action)
+    }
+
+    /// 动作当前绑定的键位，用于提示文字。
+    pub(crate) fn key_label(&self, action: KeyAction) -> String {
+        self.spec(action).map(|s| s.to_string()).unwrap_or_default()
+    }
+
+    /// `/help` 中的键位说明行。
+    pub(crate) fn help_lines(&self) -> Vec<String> {
+        ACTIONS
+            .iter()
+            .filter_map(|&(action, _, _, desc)| {
+                let key = self.spec(action)?.to_string();
+                Some(format!("    {key:<15}{}", t(desc)))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyAction, KeyMap};
+    use crate::App;
+    use crate::ui::input::handle_key;
+    use crate::ui::screen::Screen;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[test]
+    fn parses_config_and_falls_back_on_bad_entries() {
+        let (keymap, warnings) = KeyMap::parse(
+            r#"
+            [keybindings]
+            collapse = "alt+c"
+            toggle_thinking = "ctrl+t"
+            cycle_mode = "ctrl+k"      # 与 compact_now 冲突
+            help = "hyper+h"           # 无法解析
+            launch_rockets = "ctrl+r"  # 未知动作
+            "#,
+        );
+        assert_eq!(warnings.len(), 3, "{warnings:?}");
+
+        let action = |code, mods| keymap.action_for(code, mods);
+        assert_eq!(
+            action(KeyCode::Char('c'), KeyModifiers::ALT),
+            Some(KeyAction::Collapse)
+        );
+        // 原 Ctrl+D 不再触发折叠
+        assert_eq!(action(KeyCode::Char('d'), KeyModifiers::CONTROL), None);
+        assert_eq!(
+            action(KeyCode::Char('t'), KeyModifiers::CONTROL),
+            Some(KeyAction::ToggleThinking)
+        );
+        assert_eq!(action(KeyCode::Tab, KeyModifiers::NONE), None);
+        // 冲突与无法解析的条目回退为默认键位
+        assert_eq!(
+            action(KeyCode::BackTab, KeyModifiers::SHIFT),
+            Some(KeyAction::CycleMode)
+        );
+        assert_eq!(
+            action(KeyCode::Char('k'), KeyModifiers::CONTROL),
+            Some(KeyAction::CompactNow)
+        );
+        assert_eq!(
+            action(KeyCode::F(1), KeyModifiers::NONE),
+            Some(KeyAction::Help)
+        );
+    }
+
+    #[test]
+    fn default_bindings_match_builtin_keys() {
+        let keymap = KeyMap::default();
+        assert_eq!(
+            keymap.action_for(KeyCode::Char('D'), KeyModifiers::CONTROL),
+            Some(KeyAction::Collapse)
+        );
+        assert_eq!(
+            keymap.action_for(KeyCode::Tab, KeyModifiers::NONE),
+            Some(KeyAction::ToggleThinking)
+        );
+        assert_eq!(keymap.action_for(KeyCode::Esc, KeyModifiers::SHIFT), None);
+        assert!(keymap.help_lines()[0].starts_with("    Ctrl+D         "));
+    }
+
+    #[test]
+    fn configured_key_dispatches_action() {
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.keymap = KeyMap::parse("toggle_thinking = \"ctrl+t\"").0;
+        let before = app.show_thinking;
+
+        handle_key(
+            &mut app,
+            &mut screen,
+            KeyCode::Char('t'),
+            KeyModifiers::CONTROL,
+        );
+        assert_eq!(app.show_thinking, !before);
+
+        // 旧键位不再触发该动作
+        handle_key(&mut app, &mut screen, KeyCode::Tab, KeyModifiers::NONE);
+        assert_eq!(app.show_thinking, !before);
+    }
+}
@@ -2,20 +2,37 @@ use crossterm::{event::{self, Event as CEvent, KeyCode, KeyEventKind, KeyModifie
 use std::time::Duration;
 
 use crate::App;
+use crate::agent::executor::{CtrlCAction, begin_graceful_quit, ctrl_c_action};
 use crate::types::Mode;
-use crate::ui::format::toggle_collapse;
+use crate::ui::format::{edit_preview_lines, redraw_lines, toggle_collapse};
 use crate::ui::ge::is_ge_mode;
+use crate::ui::i18n::{t, t_with};
 use crate::ui::screen::Screen;
 
+mod init_script;
+mod keymap;
 mod modes;
 mod pickers;
+mod secret;
 mod submit;
 
-use self::modes::{handle_confirm_mode, handle_idle_mode, handle_note_mode, handle_running_mode};
-use self::pickers::{enter_at_file_mode, enter_command_mode};
+use self::modes::{
+    close_transcript, handle_confirm_mode, handle_idle_mode, handle_note_mode, handle_running_mode,
+    handle_transcript_mode, open_transcript, toggle_pause,
+};
+use self::pickers::{
+    cycle_backend_favorite, dispatch_builtin_command, enter_at_file_mode, enter_command_mode,
+    enter_command_mode_in_draft, queue_manual_compact,
+};
 use self::submit::{interrupt_llm_chat_loop, should_interrupt_llm_chat_loop};
 
-pub(crate) use self::pickers::apply_at_file_filter;
+pub(crate) use self::init_script::{poll_init_script, start_init_script};
+pub(crate) use self::keymap::{KeyAction, KeyMap};
+pub(crate) use self::pickers::{
+    apply_at_file_filter, apply_pending_backend_switch, open_compact_confirm,
+    request_api_key_reentry,
+};
+pub(crate) use self::secret::{PendingSecret, request_secret_input};
 pub(crate) use self::submit::handle_paste;
 
 pub(crate) fn handle_key(
@@ -25,7 +42,22 @@ pub(crate) fn handle_key(
     modifiers: KeyModifiers,
 ) -> bool {
     if key == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
-        return true;
+        // 先离开 F2 全屏视图，退出或停止提示才能落在主屏幕上
+        if app.transcript.is_some() {
+            close_transcript(app, screen);
+        }
+        let now = std::time::Instant::now();
+        return match ctrl_c_action(app.running, app.graceful_quit_at, now) {
+            CtrlCAction::Quit | CtrlCAction::ForceQuit => true,
+            CtrlCAction::GracefulStop => {
+                begin_graceful_quit(app, screen, now);
+                false
+            }
+        };
+    }
+    if app.transcript.is_some() {
+        handle_transcript_mode(app, screen, key, modifiers);
+        return false;
     }
     if key == KeyCode::Char('v') && modifiers.contains(KeyModifiers::CONTROL) {
         if let Ok(mut cb) = arboard::Clipboard::new() {
@@ -37,8 +69,9 @@ pub(crate) fn handle_key(
         }
         return false;
     }
-    if key == KeyCode::Esc && modifiers.is_empty() && should_interrupt_llm_chat_loop(app) {
-        interrupt_llm_chat_loop(app, screen);
+    if let Some(action) = app.keymap.action_for(key, modifiers)
+        && run_key_action(app, screen, action)
+    {
         return false;
     }
     if is_ge_mode(app.mode) && screen.input.trim().is_empty() {
@@ -86,45 +119,6 @@ pub(crate) fn handle_key(
         }
         return false;
     }
-    if key == KeyCode::Char('d')
-        && modifiers.contains(KeyModifiers::CONTROL)
-        && !app.running
-        && app.final_summary.is_some()
-        && screen.confirm_selected.is_none()
-        && !app.pending_confirm_note
-    {
-        toggle_collapse(app, screen);
-        return false;
-    }
-    if key == KeyCode::Tab
-        && modifiers.is_empty()
-        && screen.confirm_selected.is_none()
-        && !app.pending_confirm_note
-    {
-        app.show_thinking = !app.show_thinking;
-        let label = if app.show_thinking {
-            format!("{} {}", "Thinking:".grey(), "ON".green().bold())
-        } else {
-            format!("{} {}", "Thinking:".grey(), "OFF".yellow().bold())
-        };
-        if !app.llm_calling {
-            screen.status = label;
-            screen.refresh();
-        }
-        return false;
-    }
-    if key == KeyCode::BackTab
-        && modifiers.contains(KeyModifiers::SHIFT)
-        && screen.confirm_selected.is_none()
-        && !app.pending_confirm_note
-    {
-        app.assist_mode = app.assist_mode.cycle();
-        screen.assist_mode = app.assist_mode;
-        app.rebuild_system_message();
-        screen.refresh();
-        return false;
-    }
-
     if screen.confirm_selected.is_some() {
         handle_confirm_mode(app, screen, key, modifiers);
     } else if app.pending_confirm_note {
@@ -138,6 +132,167 @@ pub(crate) fn handle_key(
     false
 }
 
+/// 执行快捷键动作；前置条件不满足时返回 false，按键继续交给当前模式处理。
+fn run_key_action(app: &mut App, screen: &mut Screen, action: KeyAction) -> bool {
+    if action == KeyAction::Interrupt {
+        if !should_interrupt_llm_chat_loop(app) {
+            return false;
+        }
+        interrupt_llm_chat_loop(app, screen);
+        return true;
+    }
+    if action == KeyAction::Redraw {
+        // 任何模式（包括确认菜单）都可以重绘
+        let lines = redraw_lines(app, &screen.task_rendered);
+        screen.redraw(&lines);
+        return true;
+    }
+    if action == KeyAction::Transcript {
+        // 任何模式都可以打开；关闭后回到原来的界面状态
+        open_transcript(app, screen);
+        return true;
+    }
+    if action == KeyAction::Collapse
+        && screen.confirm_selected.is_some()
+        && let Some((preview, _)) = app.pending_edit.as_ref()
+        && app.pending_confirm.as_ref() == Some(preview)
+    {
+        // 编辑确认菜单中展开完整 diff
+        if let Some(lines) = edit_preview_lines(preview, usize::MAX, "    ") {
+            screen.emit(&lines);
+        }
+        return true;
+    }
+    if screen.confirm_selected.is_some() || app.pending_confirm_note {
+        return false;
+    }
+    match action {
+        // 这几个动作在上面已按任意模式处理，走到这里时不消费按键
+        KeyAction::Interrupt | KeyAction::Redraw | KeyAction::Transcript => return false,
+        KeyAction::ToggleGeView => {
+            if !is_ge_mode(app.mode) || !screen.input.trim().is_empty() {
+                return false;
+            }
+            app.ge_compact = !app.ge_compact;
+            let view = if app.ge_compact {
+                "compact (one line per todo transition)"
+            } else {
+                "verbose"
+            };
+            screen.emit(&[format!("  GE view: {view}")]);
+            screen.refresh();
+        }
+        KeyAction::Pause => {
+            if !app.running || is_ge_mode(app.mode) {
+                return false;
+            }
+            toggle_pause(app, screen);
+        }
+        KeyAction::Collapse => {
+            if app.running || app.final_summary.is_none() {
+                return false;
+            }
+            toggle_collapse(app, screen);
+        }
+        KeyAction::ToggleThinking => {
+            app.show_thinking = !app.show_thinking;
+            let label = if app.show_thinking {
+                format!("{} {}", "Thinking:".grey(), "ON".green().bold())
+            } else {
+                format!("{} {}", "Thinking:".grey(), "OFF".yellow().bold())
+            };
+            if !app.llm_calling {
+                screen.status = label;
+                screen.refresh();
+            }
+        }
+        KeyAction::CycleVerbosity => {
+            screen.verbosity = screen.verbosity.cycle();
+            if !app.llm_calling {
+                screen.status = format!(
+                    "{} {}",
+                    "Verbosity:".grey(),
+                    screen.verbosity.display_name().green().bold()
+                );
+                screen.refresh();
+            }
+        }
+        KeyAction::CompactNow => {
+            // 立即压缩，不看阈值；与 /compact 共用同一流程
+            queue_manual_compact(app, screen);
+            screen.refresh();
+        }
+        KeyAction::CycleBackend => {
+            if is_ge_mode(app.mode)
+                || app.pending_api_key_name.is_some()
+                || app.pending_ask_secret.is_some()
+            {
+                return false;
+            }
+            cycle_backend_favorite(app, screen);
+        }
+        KeyAction::CycleMode => {
+            app.assist_mode = app.assist_mode.cycle();
+            screen.assist_mode = app.assist_mode;
+            app.rebuild_system_message();
+            screen.refresh();
+        }
+        KeyAction::IncludeLastOutput => {
+            if !app.interjection_mode {
+                return false;
+            }
+            app.interjection_include_output = !app.interjection_include_output;
+            let label = if app.interjection_include_output {
+                t("interject.include_output")
+            } else {
+                t("interject.recap_only")
+            };
+            screen.status = label.dark_yellow().to_string();
+            screen.refresh();
+        }
+        KeyAction::ToggleThinkingPanel => {
+            screen.thinking_panel = !screen.thinking_panel;
+            let label = if screen.thinking_panel {
+                t("thinking.layout_panel")
+            } else {
+                t("thinking.layout_interleaved")
+            };
+            if !app.llm_calling {
+                screen.status = label.grey().to_string();
+            }
+            screen.refresh();
+        }
+        KeyAction::ClearQueue => {
+            if app.message_queue.is_empty() {
+                return false;
+            }
+            let count = app.message_queue.len().to_string();
+            app.clear_message_queue(screen);
+            screen.status = t_with("queue.cleared", &[("count", &count)])
+                .dark_yellow()
+                .to_string();
+            screen.refresh();
+        }
+        KeyAction::DraftCommand => {
+            if app.running
+                || app.cmd_picker.query.is_some()
+                || app.at_file.query.is_some()
+                || app.pending_api_key_name.is_some()
+                || app.pending_ask_secret.is_some()
+            {
+                return false;
+            }
+            enter_command_mode_in_draft(app, screen);
+            screen.refresh();
+        }
+        KeyAction::Help => {
+            dispatch_builtin_command(app, screen, crate::tools::command::BuiltinCommand::Help, "");
+            screen.refresh();
+        }
+    }
+    true
+}
+
 pub(super) fn insert_char_with_trigger(app: &mut App, screen: &mut Screen, c: char) {
     screen.insert_char_at_cursor(c);
     if c == '@' {
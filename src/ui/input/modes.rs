@@ -1,24 +1,35 @@
 use crossterm::{event::KeyCode, event::KeyModifiers, style::Stylize};
 
 use crate::App;
+use crate::agent::delegate::{ExternalExecutor, resolve_delegation};
 use crate::agent::executor::{
-    execute_command, finish, push_tool_result_to_llm, sync_context_budget,
+    abort_pending_confirm, apply_create_mcp, apply_mcp_call, apply_pending_edit, execute_command,
+    pause_status, refresh_llm_status, resolve_mcp_rollback, resolve_prompt_warn,
+    skip_pending_confirm, sync_context_budget,
 };
 use crate::agent::provider::Message;
 use crate::memory::Session;
+use crate::tools::command::expand_template_input;
 use crate::types::Event;
 use crate::ui::format::emit_live_event;
+use crate::ui::i18n::t;
 use crate::ui::screen::Screen;
+use crate::ui::transcript::TranscriptView;
 
+use super::init_script::resolve_init_script;
 use super::insert_char_with_trigger;
+use super::keymap::KeyAction;
 use super::pickers::{
-    attach_files_to_task, cancel_at_file_mode, cancel_command_mode, cancel_model_picker,
-    enter_model_picker_backend_stage, select_at_file, select_command, select_model_item,
+    at_query_is_complete_path, attach_explicit_at_paths, attach_files_to_task, cancel_at_file_mode,
+    cancel_command_mode, cancel_model_picker, dismiss_command_mode,
+    enter_model_picker_backend_stage, load_image_attachments, resolve_compact_confirm,
+    resolve_mcp_direct_call, select_at_file, select_command, select_model_item,
     submit_api_key_input, update_at_file_candidates, update_command_candidates,
 };
+use super::secret::{resolve_secret_scope, submit_secret_input};
 use super::submit::{
-    clear_input_buffer, expand_input_text, pop_input_at_cursor, submit_question_answer,
-    submit_user_input,
+    clear_input_buffer, expand_input_text, pop_input_at_cursor, resolve_secret_confirm,
+    submit_question_answer, submit_user_input,
 };
 
 pub(super) fn handle_confirm_mode(
@@ -29,16 +40,20 @@ pub(super) fn handle_confirm_mode(
 ) {
     let sel = screen.confirm_selected.unwrap();
 
+    if let Some(menu) = ConfirmMenu::pending(app) {
+        let cancel = menu.option_count() - 1;
+        match key {
+            KeyCode::Enter => menu.select(app, screen, sel),
+            KeyCode::Esc => menu.select(app, screen, cancel),
+            _ => move_menu_selection(screen, key, menu.option_count()),
+        }
+        return;
+    }
+
     if app.pending_session_list.is_some() {
-        let count = screen.question_labels.len();
         match key {
-            KeyCode::Up => {
-                screen.confirm_selected = Some(sel.saturating_sub(1));
-                screen.refresh();
-            }
-            KeyCode::Down => {
-                screen.confirm_selected = Some((sel + 1).min(count.saturating_sub(1)));
-                screen.refresh();
+            KeyCode::Up | KeyCode::Down => {
+                move_menu_selection(screen, key, screen.question_labels.len());
             }
             KeyCode::Enter => {
                 let sessions = app.pending_session_list.take().unwrap();
@@ -67,15 +82,9 @@ pub(super) fn handle_confirm_mode(
     }
 
     if app.pending_question.is_some() {
-        let opt_count = screen.question_labels.len();
         match key {
-            KeyCode::Up => {
-                screen.confirm_selected = Some(sel.saturating_sub(1));
-                screen.refresh();
-            }
-            KeyCode::Down => {
-                screen.confirm_selected = Some((sel + 1).min(opt_count.saturating_sub(1)));
-                screen.refresh();
+            KeyCode::Up | KeyCode::Down => {
+                move_menu_selection(screen, key, screen.question_labels.len());
             }
             KeyCode::Enter => {
                 let (_, options) = app.pending_question.take().unwrap();
@@ -86,7 +95,7 @@ pub(super) fn handle_confirm_mode(
                 screen.input_focused = true;
                 if raw_opt == "<user_input>" {
                     app.answering_question = true;
-                    screen.status = "✍ 请输入你的答案后按 Enter".dark_yellow().to_string();
+                    screen.status = t("question.type_answer").dark_yellow().to_string();
                     screen.refresh();
                 } else {
                     submit_question_answer(app, screen, raw_opt);
@@ -99,56 +108,45 @@ pub(super) fn handle_confirm_mode(
                 screen.input_focused = true;
                 app.answering_question = true;
                 screen.insert_char_at_cursor(c);
-                screen.status = "✍ 请输入你的答案后按 Enter".dark_yellow().to_string();
+                screen.status = t("question.type_answer").dark_yellow().to_string();
                 screen.refresh();
             }
             _ => {}
         }
     } else {
         match key {
-            KeyCode::Up => {
-                screen.confirm_selected = Some(sel.saturating_sub(1));
-                screen.refresh();
-            }
-            KeyCode::Down => {
-                screen.confirm_selected = Some((sel + 1).min(3));
-                screen.refresh();
-            }
+            KeyCode::Up | KeyCode::Down => move_menu_selection(screen, key, 4),
             KeyCode::Enter => match sel {
                 0 => {
                     screen.confirm_selected = None;
                     screen.input_focused = true;
                     app.pending_confirm_note = false;
+                    app.pending_confirm_started_at = None;
                     let Some(cmd) = app.pending_confirm.take() else {
                         screen.refresh();
                         return;
                     };
-                    execute_command(app, screen, &cmd);
-                }
-                1 => {
-                    screen.confirm_selected = None;
-                    screen.input_focused = true;
-                    app.pending_confirm_note = false;
-                    let Some(cmd) = app.pending_confirm.take() else {
-                        screen.refresh();
+                    if let Some((preview, action)) = app.pending_edit.take()
+                        && preview == cmd
+                    {
+                        apply_pending_edit(app, screen, action);
                         return;
-                    };
-                    let msg = format!("User chose to skip this command: {cmd}");
-                    push_tool_result_to_llm(app, "Tool result:", &msg);
-                    let ev = Event::ToolResult {
-                        exit_code: 0,
-                        output: msg,
-                    };
-                    emit_live_event(screen, &ev);
-                    app.task_events.push(ev);
-                    app.needs_agent_executor = true;
-                }
-                2 => {
-                    screen.confirm_selected = None;
-                    app.pending_confirm_note = false;
-                    app.pending_confirm = None;
-                    finish(app, screen, "Task aborted by user".to_string());
+                    }
+                    if let Some((preview, tool, arguments)) = app.pending_mcp_call.take()
+                        && preview == cmd
+                    {
+                        apply_mcp_call(app, screen, &tool, &arguments);
+                        return;
+                    }
+                    match app.pending_mcp_create.take() {
+                        Some((preview, config)) if preview == cmd => {
+                            apply_create_mcp(app, screen, &config)
+                        }
+                        _ => execute_command(app, screen, &cmd),
+                    }
                 }
+                1 => skip_pending_confirm(app, screen, false),
+                2 => abort_pending_confirm(app, screen, "Task aborted by user".to_string()),
                 _ => begin_confirm_note_mode(app, screen, None),
             },
             KeyCode::Char(c) if modifiers.is_empty() || modifiers == KeyModifiers::SHIFT => {
@@ -159,6 +157,70 @@ pub(super) fn handle_confirm_mode(
     }
 }
 
+/// 选项固定、最后一项为取消的确认菜单；各自的 `resolve_*` 处理选择结果。
+#[derive(Debug, Clone, Copy)]
+enum ConfirmMenu {
+    InitScript,
+    Compact,
+    McpDirectCall,
+    Delegation,
+    PromptWarn,
+    McpRollback,
+    AskSecret,
+    SecretInput,
+}
+
+impl ConfirmMenu {
+    /// 当前挂起的菜单；同时挂起多个时按这里的顺序取第一个。
+    fn pending(app: &App) -> Option<Self> {
+        let menus = [
+            (app.pending_init_script.is_some(), Self::InitScript),
+            (app.pending_compact_confirm.is_some(), Self::Compact),
+            (app.pending_mcp_direct_call.is_some(), Self::McpDirectCall),
+            (app.pending_delegation.is_some(), Self::Delegation),
+            (app.pending_prompt_warn.is_some(), Self::PromptWarn),
+            (app.pending_mcp_rollback.is_some(), Self::McpRollback),
+            (app.pending_ask_secret.is_some(), Self::AskSecret),
+            (app.pending_secret_input.is_some(), Self::SecretInput),
+        ];
+        menus
+            .into_iter()
+            .find_map(|(pending, menu)| pending.then_some(menu))
+    }
+
+    fn option_count(self) -> usize {
+        match self {
+            Self::InitScript | Self::PromptWarn | Self::AskSecret | Self::SecretInput => 3,
+            Self::Compact | Self::McpDirectCall | Self::Delegation | Self::McpRollback => 2,
+        }
+    }
+
+    /// `choice` 为选项序号；两项菜单的第一项是确认。
+    fn select(self, app: &mut App, screen: &mut Screen, choice: usize) {
+        match self {
+            Self::InitScript => resolve_init_script(app, screen, choice),
+            Self::Compact => resolve_compact_confirm(app, screen, choice == 0),
+            Self::McpDirectCall => resolve_mcp_direct_call(app, screen, choice == 0),
+            Self::Delegation => resolve_delegation(app, screen, choice == 0),
+            Self::PromptWarn => resolve_prompt_warn(app, screen, choice),
+            Self::McpRollback => resolve_mcp_rollback(app, screen, choice == 0),
+            Self::AskSecret => resolve_secret_scope(app, screen, choice),
+            Self::SecretInput => resolve_secret_confirm(app, screen, choice),
+        }
+    }
+}
+
+/// 菜单中 ↑/↓ 移动选中项（限制在 `count` 个选项内）；其它按键忽略。
+fn move_menu_selection(screen: &mut Screen, key: KeyCode, count: usize) {
+    let sel = screen.confirm_selected.unwrap_or(0);
+    screen.confirm_selected = Some(match key {
+        KeyCode::Up => sel.saturating_sub(1),
+        KeyCode::Down => (sel + 1).min(count.saturating_sub(1)),
+        _ => return,
+    });
+    screen.refresh();
+}
+
 pub(super) fn handle_note_mode(
     app: &mut App,
     screen: &mut Screen,
@@ -267,6 +329,44 @@ fn handle_api_key_input_mode(
     }
 }
 
+/// `<ask_secret>` 遮罩输入：Enter 提交，Esc 拒绝提供。
+fn handle_secret_input_mode(
+    app: &mut App,
+    screen: &mut Screen,
+    key: KeyCode,
+    modifiers: KeyModifiers,
+) {
+    match key {
+        KeyCode::Enter => submit_secret_input(app, screen),
+        KeyCode::Esc if modifiers.is_empty() => resolve_secret_scope(app, screen, 2),
+        KeyCode::Left => {
+            screen.cursor_left();
+            screen.refresh();
+        }
+        KeyCode::Right => {
+            screen.cursor_right();
+            screen.refresh();
+        }
+        KeyCode::Home => {
+            screen.cursor_home();
+            screen.refresh();
+        }
+        KeyCode::End => {
+            screen.cursor_end();
+            screen.refresh();
+        }
+        KeyCode::Backspace => {
+            pop_input_at_cursor(app, screen);
+            screen.refresh();
+        }
+        KeyCode::Char(c) if modifiers.is_empty() || modifiers == KeyModifiers::SHIFT => {
+            screen.insert_char_at_cursor(c);
+            screen.refresh();
+        }
+        _ => {}
+    }
+}
+
 pub(super) fn handle_idle_mode(
     app: &mut App,
     screen: &mut Screen,
@@ -277,6 +377,14 @@ pub(super) fn handle_idle_mode(
         handle_api_key_input_mode(app, screen, key, modifiers);
         return;
     }
+    if app
+        .pending_ask_secret
+        .as_ref()
+        .is_some_and(|secret| secret.awaiting_input())
+    {
+        handle_secret_input_mode(app, screen, key, modifiers);
+        return;
+    }
 
     if screen.input_focused {
         if app.at_file.query.is_some() {
@@ -294,6 +402,10 @@ pub(super) fn handle_idle_mode(
                     screen.refresh();
                     return;
                 }
+                // 已输入完整的存在路径：关闭选择器，回车按正常提交处理
+                KeyCode::Enter if at_query_is_complete_path(app) => {
+                    cancel_at_file_mode(app, screen);
+                }
                 KeyCode::Enter | KeyCode::Tab => {
                     if app.at_file.candidates.is_empty() {
                         cancel_at_file_mode(app, screen);
@@ -322,6 +434,20 @@ pub(super) fn handle_idle_mode(
                     screen.refresh();
                     return;
                 }
+                // `@codex ` / `@claude ` 是委派前缀而不是文件引用
+                KeyCode::Char(' ')
+                    if app
+                        .at_file
+                        .query
+                        .as_deref()
+                        .and_then(ExternalExecutor::from_name)
+                        .is_some() =>
+                {
+                    cancel_at_file_mode(app, screen);
+                    screen.insert_char_at_cursor(' ');
+                    screen.refresh();
+                    return;
+                }
                 KeyCode::Char(c) if modifiers.is_empty() || modifiers == KeyModifiers::SHIFT => {
                     let query = app.at_file.query.as_mut().unwrap();
                     query.push(c);
@@ -391,8 +517,7 @@ pub(super) fn handle_idle_mode(
                 }
                 KeyCode::Enter | KeyCode::Tab => {
                     if app.cmd_picker.candidates.is_empty() {
-                        cancel_command_mode(app, screen);
-                        clear_input_buffer(app, screen);
+                        dismiss_command_mode(app, screen);
                     } else {
                         select_command(app, screen);
                     }
@@ -400,8 +525,7 @@ pub(super) fn handle_idle_mode(
                     return;
                 }
                 KeyCode::Esc if modifiers.is_empty() => {
-                    cancel_command_mode(app, screen);
-                    clear_input_buffer(app, screen);
+                    dismiss_command_mode(app, screen);
                     screen.refresh();
                     return;
                 }
@@ -448,14 +572,16 @@ pub(super) fn handle_idle_mode(
                 let raw = expand_input_text(app, &screen.input);
                 let task = if let Some((ph, content)) = app.cmd_picker.pending_template.take() {
                     app.task_display_override = Some(raw.trim().to_string());
-                    raw.replace(&ph, &content).trim().to_string()
+                    expand_template_input(&raw, &ph, &content)
                 } else {
                     raw.trim().to_string()
                 };
                 if !task.is_empty() {
+                    attach_explicit_at_paths(app, screen, &task);
                     let at_file_chunks = std::mem::take(&mut app.at_file.chunks);
                     cancel_at_file_mode(app, screen);
                     let final_task = attach_files_to_task(&at_file_chunks, &task);
+                    load_image_attachments(app, screen, &at_file_chunks);
                     clear_input_buffer(app, screen);
                     if app.answering_question {
                         app.answering_question = false;
@@ -553,9 +679,11 @@ pub(super) fn handle_running_mode(
             let raw = expand_input_text(app, &screen.input);
             let task = raw.trim().to_string();
             if !task.is_empty() {
+                attach_explicit_at_paths(app, screen, &task);
                 let at_file_chunks = std::mem::take(&mut app.at_file.chunks);
                 cancel_at_file_mode(app, screen);
                 let final_task = attach_files_to_task(&at_file_chunks, &task);
+                load_image_attachments(app, screen, &at_file_chunks);
                 clear_input_buffer(app, screen);
                 let preview: String = final_task.chars().take(40).collect();
                 let queue_len = app.enqueue_message(screen, final_task);
@@ -602,6 +730,78 @@ pub(super) fn handle_running_mode(
     }
 }
 
+/// 暂停只挡住下一次 LLM 调用：正在进行的调用照常完成，继续后从原处接着跑。
+pub(super) fn toggle_pause(app: &mut App, screen: &mut Screen) {
+    app.paused = !app.paused;
+    if app.llm_calling {
+        refresh_llm_status(app, screen);
+        return;
+    }
+    screen.status = if app.paused {
+        pause_status(app)
+    } else {
+        t("pause.off").grey().to_string()
+    };
+    screen.refresh();
+}
+
+/// 全屏转录视图的尺寸：(列数, 正文行数)，最后一行留给状态栏。
+fn transcript_viewport() -> (usize, usize) {
+    let (cols, rows) = crossterm::terminal::size().unwrap_or((80, 24));
+    (cols.max(1) as usize, rows.saturating_sub(1).max(1) as usize)
+}
+
+/// 打开全屏转录视图：本会话之前的任务加上当前任务的全部事件。
+pub(super) fn open_transcript(app: &mut App, screen: &mut Screen) {
+    let (cols, height) = transcript_viewport();
+    let view = TranscriptView::new(app.transcript_ring.iter().chain(&app.task_events), cols);
+    screen.draw_overlay(&view.render(height, cols));
+    app.transcript = Some(view);
+}
+
+pub(super) fn handle_transcript_mode(
+    app: &mut App,
+    screen: &mut Screen,
+    key: KeyCode,
+    modifiers: KeyModifiers,
+) {
+    let (cols, height) = transcript_viewport();
+    let toggle = app.keymap.action_for(key, modifiers) == Some(KeyAction::Transcript);
+    let Some(view) = app.transcript.as_mut() else {
+        return;
+    };
+    if view.is_typing_query() {
+        match key {
+            KeyCode::Enter => view.submit_query(height),
+            KeyCode::Esc => view.cancel_query(),
+            KeyCode::Backspace => view.pop_query(),
+            KeyCode::Char(c) if !modifiers.contains(KeyModifiers::CONTROL) => view.push_query(c),
+            _ => {}
+        }
+    } else {
+        match key {
+            _ if toggle => return close_transcript(app, screen),
+            KeyCode::Esc | KeyCode::Char('q') => return close_transcript(app, screen),
+            KeyCode::Char('j') | KeyCode::Down => view.scroll_by(1, height),
+            KeyCode::Char('k') | KeyCode::Up => view.scroll_by(-1, height),
+            KeyCode::Char(' ') | KeyCode::PageDown => view.page(1, height),
+            KeyCode::Char('b') | KeyCode::PageUp => view.page(-1, height),
+            KeyCode::Char('g') | KeyCode::Home => view.home(),
+            KeyCode::Char('G') | KeyCode::End => view.end(height),
+            KeyCode::Char('/') => view.begin_query(),
+            KeyCode::Char('n') => view.next_match(height),
+            KeyCode::Char('N') => view.prev_match(height),
+            _ => {}
+        }
+    }
+    screen.draw_overlay(&view.render(height, cols));
+}
+
+pub(super) fn close_transcript(app: &mut App, screen: &mut Screen) {
+    app.transcript = None;
+    screen.close_overlay();
+}
+
 pub(super) fn begin_confirm_note_mode(
     app: &mut App,
     screen: &mut Screen,
@@ -615,9 +815,7 @@ pub(super) fn begin_confirm_note_mode(
     app.pending_confirm_note = true;
     screen.confirm_selected = None;
     screen.input_focused = true;
-    screen.status = "✍ 输入补充说明后按 Enter；Esc 返回确认菜单"
-        .dark_yellow()
-        .to_string();
+    screen.status = t("confirm.note_prompt").dark_yellow().to_string();
     clear_input_buffer(app, screen);
     if let Some(c) = first_char {
         screen.insert_char_at_cursor(c);
@@ -638,3 +836,65 @@ pub(super) fn exit_confirm_note_mode(app: &mut App, screen: &mut Screen, back_to
     }
     screen.refresh();
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use crate::agent::delegate::{ExternalExecutor, start_external_task};
+    use crate::ui::input::handle_key;
+    use crate::ui::screen::Screen;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[test]
+    fn typing_during_a_run_fills_the_input_and_ctrl_p_pauses() {
+        let mut screen = Screen::new_headless().expect("headless screen");
+        let mut app = App::new();
+        app.running = true;
+        for c in "please also add tests".chars() {
+            handle_key(&mut app, &mut screen, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        assert_eq!(screen.input, "please also add tests");
+        assert!(!app.paused);
+
+        handle_key(
+            &mut app,
+            &mut screen,
+            KeyCode::Char('p'),
+            KeyModifiers::CONTROL,
+        );
+        assert!(app.paused);
+        assert_eq!(screen.input, "please also add tests");
+        handle_key(
+            &mut app,
+            &mut screen,
+            KeyCode::Char('p'),
+            KeyModifiers::CONTROL,
+        );
+        assert!(!app.paused);
+    }
+
+    #[test]
+    fn confirm_menus_share_navigation_and_cancel_on_the_last_option() {
+        let mut screen = Screen::new_headless().expect("headless screen");
+        let mut app = App::new();
+        start_external_task(
+            &mut app,
+            &mut screen,
+            ExternalExecutor::Codex,
+            "fix the flaky test",
+            "@codex fix the flaky test".to_string(),
+        );
+        // ↓ 停在最后一项（取消），Enter 与 Esc 一样取消
+        for _ in 0..3 {
+            handle_key(&mut app, &mut screen, KeyCode::Down, KeyModifiers::NONE);
+        }
+        assert_eq!(screen.confirm_selected, Some(1));
+        handle_key(&mut app, &mut screen, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(screen.confirm_selected, Some(0));
+        handle_key(&mut app, &mut screen, KeyCode::Down, KeyModifiers::NONE);
+        handle_key(&mut app, &mut screen, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(app.pending_delegation.is_none());
+        assert!(screen.confirm_selected.is_none());
+        assert!(!app.running && !app.external_task_running);
+    }
+}
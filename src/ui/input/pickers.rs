@@ -1,9 +1,24 @@
+use std::path::{Component, Path, PathBuf};
+
 use crossterm::style::Stylize;
 
-use crate::agent::executor::sync_context_budget;
-use crate::agent::provider::BACKEND_PRESETS;
+use crate::agent::executor::{
+    CompactionPreview, context_report_lines, preview_compaction, prompt_report_lines,
+    refresh_mcp_server_tools, run_blocking_compat, sync_context_budget,
+};
+use crate::agent::provider::{BACKEND_PRESETS, ImagePart};
+use crate::agent::react::{DISABLEABLE_TOOLS, parse_disabled_tools};
 use crate::memory::Session;
-use crate::tools::command::{BuiltinCommand, CommandAction, all_commands, filter_commands};
+use crate::tools::command::{
+    BuiltinCommand, CommandAction, all_commands, filter_commands, split_command_args,
+};
+use crate::tools::fs::{
+    FileIndexFilter, format_size, index_threads, is_image_path, looks_binary, scan_workspace_files,
+};
+use crate::tools::mcp::{McpCallResult, McpServerHealth};
+use crate::types::{AssistMode, CompactConfirm};
+use crate::ui::format::save_warning_line;
+use crate::ui::i18n::{t, t_with};
 use crate::ui::screen::Screen;
 use crate::{App, AtFileChunk};
 
@@ -23,18 +38,26 @@ pub(super) fn submit_api_key_input(app: &mut App, screen: &mut Screen, raw: Stri
         return;
     };
 
-    persist_api_key_to_env(&key_name, &key_value);
+    let saved = persist_api_key_to_env(&key_name, &key_value);
     clear_input_buffer(app, screen);
 
     app.pending_api_key_name = None;
     app.running = true;
     app.needs_agent_executor = true;
-    screen.status = "API key saved. Retrying...".grey().to_string();
-    screen.emit(&[format!("  {} updated. Retrying current task...", key_name)]);
+    let mut lines = vec![format!("  {} updated. Retrying current task...", key_name)];
+    match saved {
+        Ok(()) => screen.status = "API key saved. Retrying...".grey().to_string(),
+        // 写入失败时 Key 仍对本次运行生效，只是下次启动需要重新输入
+        Err(e) => {
+            screen.status = "API key not saved. Retrying...".dark_yellow().to_string();
+            lines.push(save_warning_line(&e));
+        }
+    }
+    screen.emit(&lines);
     screen.refresh();
 }
 
-fn parse_api_key_input(raw: &str, key_name: &str) -> String {
+pub(super) fn parse_api_key_input(raw: &str, key_name: &str) -> String {
     let trimmed = raw.trim();
     if let Some((lhs, rhs)) = trimmed.split_once('=')
         && lhs.trim().eq_ignore_ascii_case(key_name)
@@ -119,9 +142,8 @@ pub(super) fn update_at_file_candidates(app: &mut App, screen: &mut Screen, _que
         let workspace = app.workspace.clone();
         let (tx, rx) = std::sync::mpsc::channel();
         std::thread::spawn(move || {
-            let mut results = Vec::new();
-            collect_all_files(&workspace, &workspace, &mut results, 0);
-            let _ = tx.send(results);
+            let filter = FileIndexFilter::for_workspace(&workspace);
+            scan_workspace_files(&workspace, &filter, index_threads(), &tx);
         });
         app.at_file_index_rx = Some(rx);
     }
@@ -169,15 +191,88 @@ pub(super) fn select_at_file(app: &mut App, screen: &mut Screen) {
     let at_pos = app.at_file.at_pos;
     let replace_start = at_pos.saturating_sub(1);
     screen.input.truncate(replace_start);
+
+    let abs_path = app.workspace.join(&rel_path);
+    let chunk = match at_file_chunk(app, placeholder.clone(), &rel_str, abs_path) {
+        Ok(chunk) => chunk,
+        Err(note) => {
+            screen.input_cursor = screen.input.len();
+            cancel_at_file_mode(app, screen);
+            screen.emit(&[note]);
+            return;
+        }
+    };
+
     screen.input.push_str(&placeholder);
     screen.input_cursor = screen.input.len();
+    app.at_file.chunks.push(chunk);
+    cancel_at_file_mode(app, screen);
+}
 
-    let abs_path = app.workspace.join(&rel_path);
-    app.at_file.chunks.push(AtFileChunk {
+/// 构造附件；非图片的二进制文件直接拒绝（返回提示），避免模型把乱码读进上下文。
+fn at_file_chunk(
+    app: &App,
+    placeholder: String,
+    rel_str: &str,
+    abs_path: PathBuf,
+) -> Result<AtFileChunk, String> {
+    let is_image = is_image_path(&abs_path);
+    if is_image && !app.backend.supports_images() {
+        return Err(format!(
+            "  ✗ 未附加 {rel_str}：当前模型 {} 不支持图片输入",
+            app.backend.model_name()
+        ));
+    }
+    if !is_image && looks_binary(&abs_path) {
+        return Err(format!(
+            "  ✗ 未附加 {rel_str}：二进制文件无法作为文本上下文"
+        ));
+    }
+    Ok(AtFileChunk {
         placeholder,
         path: abs_path,
-    });
-    cancel_at_file_mode(app, screen);
+        is_image,
+    })
+}
+
+/// 工作区内已存在的相对文件路径（不允许绝对路径与 `..`）。
+fn explicit_at_path(workspace: &Path, rel: &str) -> Option<PathBuf> {
+    let rel_path = Path::new(rel);
+    let inside = !rel.is_empty()
+        && rel_path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    let abs_path = workspace.join(rel_path);
+    (inside && abs_path.is_file()).then_some(abs_path)
+}
+
+/// 选择器的查询已是完整存在的路径时，回车直接提交而不是选择候选项。
+pub(super) fn at_query_is_complete_path(app: &App) -> bool {
+    app.at_file
+        .query
+        .as_deref()
+        .is_some_and(|q| explicit_at_path(&app.workspace, q).is_some())
+}
+
+/// 提交时识别输入中完整写出的 `@相对路径` 并加入附件列表，无需经过选择器；
+/// 路径不存在的 `@` 片段按普通文本保留。
+pub(super) fn attach_explicit_at_paths(app: &mut App, screen: &mut Screen, task: &str) {
+    for token in task.split_whitespace() {
+        let Some(rel) = token.strip_prefix('@') else {
+            continue;
+        };
+        if app.at_file.chunks.iter().any(|c| c.placeholder == token) {
+            continue;
+        }
+        let Some(abs_path) = explicit_at_path(&app.workspace, rel) else {
+            continue;
+        };
+        let rel_str = rel.replace('\\', "/");
+        match at_file_chunk(app, token.to_string(), &rel_str, abs_path) {
+            Ok(chunk) => app.at_file.chunks.push(chunk),
+            Err(note) => screen.emit(&[note]),
+        }
+    }
 }
 
 pub(super) fn attach_files_to_task(chunks: &[AtFileChunk], task: &str) -> String {
@@ -200,50 +295,118 @@ pub(super) fn attach_files_to_task(chunks: &[AtFileChunk], task: &str) -> String
     for (chunk, at_ref) in chunks.iter().zip(refs.iter()) {
         let abs_path = chunk.path.to_string_lossy().replace('\\', "/");
         result.push_str(&format!("\n- {at_ref} ({abs_path})"));
+        if chunk.is_image {
+            result.push_str(" [image, sent as image input; do not cat]");
+        }
     }
     result
 }
 
-fn collect_all_files(
-    base: &std::path::Path,
-    dir: &std::path::Path,
-    results: &mut Vec<std::path::PathBuf>,
-    depth: usize,
-) {
-    if depth > 6 || results.len() >= 20_000 {
-        return;
+/// 草稿中的一项附件，下标指向 `app.at_file.chunks` / `app.paste_chunks`。
+#[derive(Clone, Copy)]
+enum DraftAttachment {
+    File(usize),
+    Paste(usize),
+}
+
+/// 按占位符在输入框中出现的顺序排列草稿附件，序号与 /attachments 列表一致。
+fn draft_attachments(app: &App, input: &str) -> Vec<DraftAttachment> {
+    let pos = |placeholder: &str| input.find(placeholder).unwrap_or(usize::MAX);
+    let mut items: Vec<(usize, DraftAttachment)> = app
+        .at_file
+        .chunks
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (pos(&c.placeholder), DraftAttachment::File(i)))
+        .chain(
+            app.paste_chunks
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (pos(&c.placeholder), DraftAttachment::Paste(i))),
+        )
+        .collect();
+    items.sort_by_key(|(pos, _)| *pos);
+    items.into_iter().map(|(_, item)| item).collect()
+}
+
+fn attachment_lines(app: &App, input: &str) -> Vec<String> {
+    let items = draft_attachments(app, input);
+    if items.is_empty() {
+        return vec![format!("  {}", t("attach.empty"))];
     }
-    let Ok(entries) = std::fs::read_dir(dir) else {
-        return;
+    let mut lines = vec![format!(
+        "  {}",
+        t_with("attach.header", &[("count", &items.len().to_string())])
+    )];
+    for (n, item) in items.iter().enumerate() {
+        let (placeholder, detail) = match *item {
+            DraftAttachment::File(i) => {
+                let chunk = &app.at_file.chunks[i];
+                let kind = t(if chunk.is_image {
+                    "attach.image"
+                } else {
+                    "attach.file"
+                });
+                let size = std::fs::metadata(&chunk.path)
+                    .map(|m| format_size(m.len()))
+                    .unwrap_or_else(|_| t("attach.unreadable").to_string());
+                (
+                    &chunk.placeholder,
+                    t_with("attach.file_detail", &[("kind", kind), ("size", &size)]),
+                )
+            }
+            DraftAttachment::Paste(i) => {
+                let chunk = &app.paste_chunks[i];
+                (
+                    &chunk.placeholder,
+                    t_with(
+                        "attach.paste_detail",
+                        &[
+                            ("lines", &chunk.content.lines().count().to_string()),
+                            ("size", &format_size(chunk.content.len() as u64)),
+                        ],
+                    ),
+                )
+            }
+        };
+        lines.push(format!("    {}. {placeholder}  ({detail})", n + 1));
+    }
+    lines
+}
+
+/// 移除列表中第 `n` 项（从 1 开始）附件，并从输入框删掉对应占位符；返回该占位符。
+fn remove_attachment(app: &mut App, screen: &mut Screen, n: usize) -> Option<String> {
+    let item = *draft_attachments(app, &screen.input).get(n.checked_sub(1)?)?;
+    let placeholder = match item {
+        DraftAttachment::File(i) => app.at_file.chunks.remove(i).placeholder,
+        DraftAttachment::Paste(i) => app.paste_chunks.remove(i).placeholder,
     };
-    for entry in entries.flatten() {
-        let path = entry.path();
-        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-        if name.starts_with('.')
-            || matches!(
-                name,
-                "target"
-                    | "node_modules"
-                    | "dist"
-                    | "build"
-                    | "out"
-                    | "obj"
-                    | "vendor"
-                    | "__pycache__"
-                    | "Binaries"
-                    | "Saved"
-                    | "Intermediate"
-                    | "DerivedDataCache"
-            )
-        {
-            continue;
+    if let Some(found) = screen.input.find(&placeholder) {
+        // 与退格删除占位符一致，顺带去掉一个相邻空格
+        let bytes = screen.input.as_bytes();
+        let (mut start, mut end) = (found, found + placeholder.len());
+        if start > 0 && bytes[start - 1] == b' ' {
+            start -= 1;
+        } else if bytes.get(end) == Some(&b' ') {
+            end += 1;
         }
-        if path.is_dir() {
-            collect_all_files(base, &path, results, depth + 1);
-        } else if path.is_file() {
-            if let Ok(rel) = path.strip_prefix(base) {
-                results.push(rel.to_path_buf());
-            }
+        screen.input.replace_range(start..end, "");
+        let cursor = screen.input_cursor;
+        screen.input_cursor = if cursor >= end {
+            cursor - (end - start)
+        } else {
+            cursor.min(start)
+        };
+    }
+    Some(placeholder)
+}
+
+/// 把 @ 附加的图片编码后暂存，随下一条 user 消息发送。
+pub(super) fn load_image_attachments(app: &mut App, screen: &mut Screen, chunks: &[AtFileChunk]) {
+    for chunk in chunks.iter().filter(|c| c.is_image) {
+        match ImagePart::from_file(&chunk.path) {
+            Ok(image) => app.pending_images.push(image),
+            Err(e) => screen.emit(&[format!("  ✗ 图片附加失败：{e:#}")]),
         }
     }
 }
@@ -254,14 +417,57 @@ pub(super) fn enter_command_mode(app: &mut App, screen: &mut Screen) {
     update_command_candidates(app, screen, "");
 }
 
+/// 保留草稿打开命令选择器：在草稿末尾另起一行写入 `/`，并记录它的位置，
+/// 执行内置命令时只移除这一段。
+pub(super) fn enter_command_mode_in_draft(app: &mut App, screen: &mut Screen) {
+    screen.input_focused = true;
+    screen.input_cursor = screen.input.len();
+    if !screen.input.is_empty() {
+        screen.insert_char_at_cursor('\n');
+    }
+    screen.insert_char_at_cursor('/');
+    enter_command_mode(app, screen);
+    if screen.input != "/" {
+        app.cmd_picker.draft_at = Some(screen.input_cursor - 1);
+    }
+}
+
 pub(super) fn cancel_command_mode(app: &mut App, screen: &mut Screen) {
     app.cmd_picker.query = None;
     app.cmd_picker.candidates.clear();
     app.cmd_picker.sel = 0;
+    app.cmd_picker.draft_at = None;
     screen.command_labels.clear();
     screen.command_sel = 0;
 }
 
+/// Esc / 无匹配时关闭选择器：在草稿中打开的保留已输入内容，否则清空输入框。
+pub(super) fn dismiss_command_mode(app: &mut App, screen: &mut Screen) {
+    let in_draft = app.cmd_picker.draft_at.is_some();
+    cancel_command_mode(app, screen);
+    if !in_draft {
+        clear_input_buffer(app, screen);
+    }
+}
+
+/// 从草稿中删掉 `/query` 及其所在的空行，光标回到原位置。
+fn remove_command_text(screen: &mut Screen, at: usize, query: &str) {
+    let typed = format!("/{query}");
+    if screen.input.get(at..at + typed.len()) != Some(typed.as_str()) {
+        return;
+    }
+    let mut start = at;
+    let end = at + typed.len();
+    let line_empty = start > 0
+        && screen.input.as_bytes()[start - 1] == b'\n'
+        && matches!(screen.input.as_bytes().get(end), None | Some(b'\n'));
+    if line_empty {
+        start -= 1;
+    }
+    screen.input.replace_range(start..end, "");
+    screen.input_cursor = start;
+}
+
 pub(super) fn update_command_candidates(app: &mut App, screen: &mut Screen, query: &str) {
     let all = all_commands(&app.user_commands);
     let filtered = filter_commands(&all, query);
@@ -275,6 +481,8 @@ pub(super) fn update_command_candidates(app: &mut App, screen: &mut Screen, quer
 }
 
 pub(super) fn select_command(app: &mut App, screen: &mut Screen) {
+    let query = app.cmd_picker.query.clone().unwrap_or_default();
+    let args = split_command_args(&query).1.to_string();
     let sel = app.cmd_picker.sel;
     let Some(name) = app.cmd_picker.candidates.get(sel).cloned() else {
         cancel_command_mode(app, screen);
@@ -287,46 +495,90 @@ pub(super) fn select_command(app: &mut App, screen: &mut Screen) {
         return;
     };
 
+    let draft_at = app.cmd_picker.draft_at;
     cancel_command_mode(app, screen);
-    clear_input_buffer(app, screen);
+    match (draft_at, &cmd.action) {
+        // 内置命令不占用输入框，草稿与附件原样保留
+        (Some(at), CommandAction::Builtin(_)) => remove_command_text(screen, at, &query),
+        _ => clear_input_buffer(app, screen),
+    }
 
     match cmd.action {
         CommandAction::Builtin(builtin) => {
-            dispatch_builtin_command(app, screen, builtin);
+            dispatch_builtin_command(app, screen, builtin, &args);
         }
         CommandAction::Template(content) => {
             let placeholder = format!("/{}", cmd.name);
-            screen.input = placeholder.clone();
+            // 已输入的参数保留在输入框，提交时代入 `$ARGUMENTS` / `$1`…
+            screen.input = if args.is_empty() {
+                placeholder.clone()
+            } else {
+                format!("{placeholder} {args}")
+            };
+            screen.input_cursor = screen.input.len();
             app.cmd_picker.pending_template = Some((placeholder, content));
             screen.refresh();
         }
     }
 }
 
-pub(super) fn dispatch_builtin_command(app: &mut App, screen: &mut Screen, cmd: BuiltinCommand) {
+pub(super) fn dispatch_builtin_command(
+    app: &mut App,
+    screen: &mut Screen,
+    cmd: BuiltinCommand,
+    args: &str,
+) {
     match cmd {
         BuiltinCommand::Help => {
-            screen.emit(&[
-                "  键位绑定：".to_string(),
-                "    Ctrl+C         退出".to_string(),
-                "    Ctrl+D         展开/折叠任务详情".to_string(),
-                "    Tab            切换原生 Thinking ON/OFF".to_string(),
-                "    Shift+Tab      循环切换协助模式 (agent / Yolo / plan)".to_string(),
-                "    ↑ / ↓          导航菜单选项".to_string(),
-                "    Enter          确认选择 / 提交输入".to_string(),
-                "    Esc            中断 LLM / 取消输入焦点".to_string(),
-                "    @              搜索并附加文件".to_string(),
-                "    /              打开命令选择器".to_string(),
-                "    GE <目标>       进入 Golden Experience 督导模式".to_string(),
+            // 可重新绑定的键位来自 ~/.goldbot/keybindings.toml
+            let key_line = |key: &str, desc: &'static str| format!("    {key:<15}{}", t(desc));
+            let mut lines = vec![
+                format!("  {}", t("help.keys_title")),
+                key_line("Ctrl+C", "help.quit"),
+            ];
+            lines.extend(app.keymap.help_lines());
+            lines.extend([
+                key_line("↑ / ↓", "help.navigate"),
+                key_line("Enter", "help.enter"),
+                key_line("Esc", "help.esc"),
+                key_line("@", "help.at"),
+                key_line("/", "help.slash"),
+                key_line(t("help.ge_key"), "help.ge"),
                 String::new(),
-                "  内置命令：/help  /clear  /compact  /memory  /nomemory  /thinking  /skills  /mcp  /status"
-                    .to_string(),
+                format!(
+                    "  {}/help  /attachments  /clear  /compact  /compact-preview  /context  /diff  /undo  /export  /history  /memory  /remember  /forget  /rerun  /explain  /profile  /prompt  /review  /nomemory  /observe  /thinking  /raw  /timestamps  /skills  /mcp  /tools  /status",
+                    t("help.builtins")
+                ),
             ]);
+            screen.emit(&lines);
+        }
+        BuiltinCommand::Attachments => {
+            let (sub, rest) = split_command_args(args);
+            let lines = match sub {
+                "" | "list" => attachment_lines(app, &screen.input),
+                "remove" | "rm" => {
+                    match rest
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| remove_attachment(app, screen, n))
+                    {
+                        Some(placeholder) => vec![format!(
+                            "  {}",
+                            t_with("attach.removed", &[("placeholder", &placeholder)])
+                        )],
+                        None => vec![format!("  {}", t("attach.remove_usage"))],
+                    }
+                }
+                _ => vec![format!("  {}", t("attach.usage"))],
+            };
+            screen.emit(&lines);
+            screen.refresh();
         }
         BuiltinCommand::Clear => {
             let clear_session_error = Session::current().clear_current_session().err();
             app.messages.truncate(1);
             app.task_events.clear();
+            app.transcript_ring.clear();
             app.task.clear();
             app.final_summary = None;
             app.running = false;
@@ -350,21 +602,25 @@ pub(super) fn dispatch_builtin_command(app: &mut App, screen: &mut Screen, cmd:
                 screen.emit(&[format!("  /clear: 清理当前 session 失败：{err}")]);
             }
         }
-        BuiltinCommand::Compact => {
-            if app.pending_manual_compact {
-                screen.emit(&["  /compact: 已在排队，等待当前步骤结束后执行。".to_string()]);
-            } else {
-                app.pending_manual_compact = true;
-                let busy = app.llm_calling || app.shell_task_running || app.dag_task_running;
-                if busy {
-                    screen.emit(&[
-                        "  /compact: 已加入队列，将在当前步骤结束后走 LLM 压缩摘要。".to_string(),
-                    ]);
-                } else {
-                    screen.emit(&["  /compact: 开始走 LLM 压缩摘要流程。".to_string()]);
-                }
+        BuiltinCommand::Compact => queue_manual_compact(app, screen),
+        BuiltinCommand::Export => {
+            let path = (!args.is_empty()).then_some(args);
+            match crate::ui::export::export_transcript(app, path) {
+                Ok(path) => screen.emit(&[format!("  已导出至 {}", path.display())]),
+                Err(e) => screen.emit(&[format!("  ✗ 导出失败：{e:#}")]),
             }
         }
+        BuiltinCommand::History => screen.emit(&history_lines(args)),
+        BuiltinCommand::CompactPreview => match preview_compaction(app) {
+            Some(preview) => open_compact_confirm(app, screen, &preview, CompactConfirm::Manual),
+            None => {
+                let total = app.messages.len();
+                screen.emit(&[format!("  /compact: 只有 {} 条消息，无需压缩。", total)]);
+            }
+        },
+        BuiltinCommand::Memory if args == "promote" => {
+            crate::agent::executor::promote_short_term_memory(app, screen, true);
+        }
         BuiltinCommand::Memory => {
             let store = crate::memory::project::ProjectStore::current();
             match store.build_memory_message(None) {
@@ -377,27 +633,41 @@ pub(super) fn dispatch_builtin_command(app: &mut App, screen: &mut Screen, cmd:
                 }
             }
         }
+        BuiltinCommand::Rerun => rerun_last_task(app, screen),
+        BuiltinCommand::Explain => crate::agent::explain::start_explain(app, screen),
+        BuiltinCommand::Undo => undo_last_task(app, screen, args == "--force"),
+        BuiltinCommand::Remember => remember_note(app, screen, args),
+        BuiltinCommand::Forget => forget_notes(app, screen, args),
+        BuiltinCommand::Profile => {
+            screen.emit(&crate::memory::profile::describe_profile(&app.workspace));
+        }
+        BuiltinCommand::Review => {
+            let report = crate::consensus::evaluate::self_review(
+                &app.workspace,
+                &std::sync::atomic::AtomicBool::new(false),
+            );
+            screen.emit(&crate::consensus::evaluate::format_review_report(&report));
+        }
         BuiltinCommand::Session => {
             let store = Session::current();
             let sessions = store.list_sessions();
             if sessions.is_empty() {
-                screen.emit(&["  （暂无历史会话）".to_string()]);
+                screen.emit(&[format!("  {}", t("session.empty"))]);
             } else {
                 let labels: Vec<String> = sessions
                     .iter()
                     .map(|id| {
                         let ts = Session::format_session_timestamp(id);
                         let active_session_id = Session::active_id();
-                        let marker = if id == &active_session_id {
-                            "  ← 当前"
+                        if id == &active_session_id {
+                            format!("{ts}  {}", t("session.current"))
                         } else {
-                            ""
-                        };
-                        format!("{ts}{marker}")
+                            ts
+                        }
                     })
                     .collect();
                 screen.emit(&{
-                    let mut v = vec!["  历史会话（↑↓ 选择，Enter 恢复）:".to_string()];
+                    let mut v = vec![format!("  {}", t("session.picker_title"))];
                     for (i, l) in labels.iter().enumerate() {
                         v.push(format!("  {}. {l}", i + 1));
                     }
@@ -410,6 +680,14 @@ pub(super) fn dispatch_builtin_command(app: &mut App, screen: &mut Screen, cmd:
                 screen.refresh();
             }
         }
+        BuiltinCommand::Thinking if args.trim() == "concise" => {
+            let state = if crate::ui::thinking_filter::toggle_concise() {
+                "ON"
+            } else {
+                "OFF"
+            };
+            screen.emit(&[format!("  Thinking concise: {}", state)]);
+        }
         BuiltinCommand::Thinking => {
             app.show_thinking = !app.show_thinking;
             let state = if app.show_thinking { "ON" } else { "OFF" };
@@ -421,6 +699,55 @@ pub(super) fn dispatch_builtin_command(app: &mut App, screen: &mut Screen, cmd:
             let state = if app.no_memory { "ON（记忆注入已禁用）" } else { "OFF（记忆注入已启用）" };
             screen.emit(&[format!("  NoMemory: {}", state)]);
         }
+        BuiltinCommand::Context => {
+            let verbose = matches!(args.trim(), "--verbose" | "-v");
+            screen.emit(&context_report_lines(app, verbose));
+        }
+        BuiltinCommand::Diff => {
+            match crate::ui::pager::open_diff_in_pager(&app.workspace, screen) {
+                Ok(true) => {}
+                Ok(false) => {
+                    screen.emit(&["  没有未提交的改动（或当前目录不是 git 仓库）。".to_string()])
+                }
+                Err(e) => screen.emit(&[format!("  ✗ 打开 pager 失败：{e:#}")]),
+            }
+        }
+        BuiltinCommand::Prompt => {
+            let full = args.trim() == "full";
+            screen.emit(&prompt_report_lines(app, full));
+        }
+        BuiltinCommand::Observe => {
+            app.observe = !app.observe;
+            screen.observe = app.observe;
+            let state = if app.observe {
+                "ON（命令与写入只模拟、不执行）"
+            } else {
+                "OFF（工具正常执行）"
+            };
+            screen.emit(&[format!("  Observe: {}", state)]);
+            screen.refresh();
+        }
+        BuiltinCommand::Timestamps => {
+            app.timestamps = !app.timestamps;
+            screen.timestamps = app.timestamps;
+            let state = if app.timestamps { "ON" } else { "OFF" };
+            screen.emit(&[format!("  Timestamps: {}", state)]);
+            screen.refresh();
+        }
+        BuiltinCommand::Raw => {
+            app.show_raw = !app.show_raw;
+            let line = t(if app.show_raw { "raw.on" } else { "raw.off" });
+            screen.emit(&[format!("  {line}")]);
+            screen.refresh();
+        }
+        BuiltinCommand::Skills if args.trim() == "reload" => {
+            app.reload_skills();
+            let names: Vec<String> = app.skills.iter().map(|s| s.name.clone()).collect();
+            match crate::ui::screen::format_skills_status_line(&names) {
+                Some(line) => screen.emit(&[line]),
+                None => screen.emit(&[format!("  {}", t("skills.reloaded_none"))]),
+            }
+        }
         BuiltinCommand::Skills => {
             if app.skills.is_empty() {
                 screen.emit(&["  未发现任何 Skill。".to_string()]);
@@ -429,117 +756,327 @@ pub(super) fn dispatch_builtin_command(app: &mut App, screen: &mut Screen, cmd:
                 screen.emit(&[format!("  Skills ({}): {}", names.len(), names.join(", "))]);
             }
         }
+        BuiltinCommand::Mcp if args.trim() == "reload" => {
+            let mut lines: Vec<String> = app
+                .reload_mcp()
+                .iter()
+                .map(|w| format!("  {}", w.as_str().dark_yellow()))
+                .collect();
+            let status = if app.mcp_discovery_rx.is_some() {
+                t("mcp.rediscovering")
+            } else {
+                t("mcp.reloaded_none")
+            };
+            lines.push(format!("  {status}"));
+            screen.emit(&lines);
+        }
+        BuiltinCommand::Mcp if args.split_whitespace().next() == Some("call") => {
+            let rest = args.trim_start()["call".len()..].trim();
+            mcp_call_command(app, screen, rest);
+        }
         BuiltinCommand::Mcp => {
-            let status = app.mcp_registry.startup_status();
-            if status.ok.is_empty() && status.failed.is_empty() {
+            let health = app.mcp_registry.server_health();
+            if health.is_empty() {
                 screen.emit(&["  未配置任何 MCP 服务器。".to_string()]);
             } else {
                 let mut lines = vec!["  MCP 服务器：".to_string()];
-                for (server, tool_count) in &status.ok {
-                    lines.push(format!("    ✓ {}  ({} 个工具)", server, tool_count));
-                }
-                for server in &status.failed {
-                    lines.push(format!("    ✗ {}  (连接失败)", server));
-                }
+                lines.extend(mcp_health_lines(&health));
                 screen.emit(&lines);
             }
         }
+        BuiltinCommand::Tools => {
+            screen.emit(&tools_command(app, args));
+        }
         BuiltinCommand::Status => {
             let ws = app.workspace.to_string_lossy().replace('\\', "/");
             let mode_str = app.assist_mode.display_name();
             let thinking = if app.show_thinking { "ON" } else { "OFF" };
             let no_memory = if app.no_memory { "ON" } else { "OFF" };
-            screen.emit(&[
+            let observe = if app.observe { "ON" } else { "OFF" };
+            let safe = if app.safe_mode { "ON" } else { "OFF" };
+            let disabled = disabled_tools_label(app);
+            let exec_env = crate::memory::profile::describe_exec_env(
+                &crate::memory::profile::exec_env(&app.workspace),
+            );
+            let exec_env = if exec_env.is_empty() {
+                "无".to_string()
+            } else {
+                exec_env.join(", ")
+            };
+            let config_dir = crate::tools::mcp::goldbot_home_dir();
+            let config_state = if crate::config::config_dir_writable(&config_dir) {
+                "可写"
+            } else {
+                "不可写，设置不会被保存"
+            };
+            let mcp_health = app.mcp_registry.server_health();
+            let mut lines = vec![
                 format!("  Workspace:  {}", ws),
-                format!("  Backend:    {}", app.backend.backend_label()),
+                format!(
+                    "  Backend:    {} ({})",
+                    app.backend.backend_label(),
+                    if app.backend_from_workspace {
+                        "workspace .goldbot/backend"
+                    } else {
+                        "global ~/.goldbot/.env"
+                    }
+                ),
                 format!("  Model:      {}", app.backend.model_name()),
                 format!("  Mode:       {}", mode_str),
                 format!("  Thinking:   {}", thinking),
                 format!("  NoMemory:   {}", no_memory),
+                format!("  Observe:    {}", observe),
+                format!("  Safe:       {}", safe),
+                format!(
+                    "  Palette:    {}",
+                    crate::ui::palette::Palette::current().as_str()
+                ),
+                format!("  Disabled:   {}", disabled),
+                format!("  ExecEnv:    {}", exec_env),
+                format!(
+                    "  Config:     {} ({})",
+                    crate::config::display_config_path(&config_dir),
+                    config_state
+                ),
                 format!("  Skills:     {}", app.skills.len()),
                 format!("  Commands:   {} 用户 + 10 内置", app.user_commands.len()),
                 format!("  Messages:   {}", app.messages.len()),
-            ]);
+            ];
+            if !mcp_health.is_empty() {
+                lines.push("  MCP:".to_string());
+                lines.extend(mcp_health_lines(&mcp_health));
+            }
+            screen.emit(&lines);
         }
         BuiltinCommand::Model => {
+            app.model_picker.save_global = args.trim() == "--global";
+            if crate::memory::profile::workspace_backend_file(&app.workspace).is_some() {
+                let target = if app.model_picker.save_global {
+                    "本次选择写入全局 ~/.goldbot/.env"
+                } else {
+                    "选择将写入 .goldbot/backend（/model --global 改写全局 ~/.goldbot/.env）"
+                };
+                screen.emit(&[format!("  本工作区有自己的后端配置：{target}")]);
+            }
             enter_model_picker_backend_stage(app, screen);
         }
     }
 }
 
-fn persist_backend_to_env(backend_label: &str, model: &str) {
-    let env_path = crate::tools::mcp::goldbot_home_dir().join(".env");
-    let raw = std::fs::read_to_string(&env_path).unwrap_or_default();
+/// `/mcp`、`/status` 的服务器健康行：可达性、工具数、上次调用耗时或失败原因。
+fn mcp_health_lines(health: &[McpServerHealth]) -> Vec<String> {
+    health
+        .iter()
+        .map(|server| match &server.failure {
+            Some(reason) => format!("    ✗ {}  (连接失败：{})", server.name, reason),
+            None => {
+                let latency = server
+                    .last_latency
+                    .map(|d| {
+                        if d.as_millis() < 1000 {
+                            format!("，上次调用 {}ms", d.as_millis())
+                        } else {
+                            format!("，上次调用 {:.1}s", d.as_secs_f64())
+                        }
+                    })
+                    .unwrap_or_default();
+                format!(
+                    "    ✓ {}  ({} 个工具{})",
+                    server.name, server.tool_count, latency
+                )
+            }
+        })
+        .collect()
+}
 
-    let provider_value = match backend_label {
-        "Kimi" => "kimi",
-        "Mimo" => "mimo",
-        "MiniMax" => "minimax",
-        _ => "glm",
+/// `/mcp call <工具名> [JSON 参数]`：绕过 LLM 直接调用一个 MCP 工具，结果只显示在滚动区、不进入对话。
+fn mcp_call_command(app: &mut App, screen: &mut Screen, args: &str) {
+    let (action, json) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+    if action.is_empty() {
+        screen.emit(&["  用法：/mcp call <工具名> [JSON 参数]".to_string()]);
+        return;
+    }
+    let arguments = match crate::tools::mcp::parse_call_arguments(json) {
+        Ok(arguments) => arguments,
+        Err(e) => {
+            screen.emit(&[format!("  {}", format!("{e:#}").red())]);
+            return;
+        }
     };
-    let model_key = match backend_label {
-        "Kimi" => "KIMI_MODEL",
-        "Mimo" => "MIMO_MODEL",
-        "MiniMax" => "MINIMAX_MODEL",
-        _ => "BIGMODEL_MODEL",
+    // 与 agent 调用走同一个过滤：`/tools disable mcp` 后手动调用同样拒绝
+    if app.disabled_tools.contains("mcp") {
+        screen.emit(&[format!(
+            "  {}",
+            "MCP 工具已在本会话中禁用（/tools enable mcp 重新启用）".red()
+        )]);
+        return;
+    }
+    if app.observe {
+        screen.emit(&[format!("  观察模式：未调用 {action}")]);
+        return;
+    }
+    // 破坏性工具与 agent 调用一样先确认（Yolo 直接放行）
+    if app.mcp_registry.is_destructive(action) && app.assist_mode != AssistMode::Yolo {
+        let name = app.mcp_registry.display_name(action);
+        screen.emit(&[format!(
+            "  {}",
+            t_with("mcp.call_prompt", &[("name", &name)])
+        )]);
+        screen.question_labels = vec![
+            t("mcp.option_call").to_string(),
+            t("mcp.option_cancel").to_string(),
+        ];
+        screen.confirm_selected = Some(0);
+        screen.input_focused = false;
+        app.pending_mcp_direct_call = Some((action.to_string(), arguments));
+        screen.refresh();
+        return;
+    }
+    run_mcp_direct_call(app, screen, action, &arguments);
+}
+
+/// 确认菜单的选择：调用或取消 `/mcp call` 挂起的破坏性工具。
+pub(super) fn resolve_mcp_direct_call(app: &mut App, screen: &mut Screen, run: bool) {
+    let Some((action, arguments)) = app.pending_mcp_direct_call.take() else {
+        return;
     };
+    screen.confirm_selected = None;
+    screen.question_labels.clear();
+    screen.input_focused = true;
+    if run {
+        run_mcp_direct_call(app, screen, &action, &arguments);
+    } else {
+        screen.emit(&[format!("  {}", t("mcp.call_cancelled"))]);
+    }
+    screen.refresh();
+}
 
-    let mut lines: Vec<String> = raw.lines().map(|l| l.to_string()).collect();
-    let mut found_provider = false;
-    let mut found_model = false;
+fn run_mcp_direct_call(
+    app: &mut App,
+    screen: &mut Screen,
+    action: &str,
+    arguments: &serde_json::Value,
+) {
+    let label = format!("MCP({})", app.mcp_registry.display_name(action));
+    let registry = &app.mcp_registry;
+    let mut on_progress = |p: crate::tools::mcp::McpProgress| {
+        screen.status = format!("MCP: {}…", p.label());
+        screen.refresh();
+    };
+    let result = run_blocking_compat(|| registry.execute_tool(action, arguments, &mut on_progress));
+    screen.status.clear();
+    let tools_changed = result.as_ref().is_ok_and(|out| out.tools_changed);
+    screen.emit(&mcp_call_lines(&label, result));
+    if tools_changed {
+        refresh_mcp_server_tools(app, screen, action);
+    }
+}
 
-    for line in &mut lines {
-        let trimmed = line.trim_start();
-        if trimmed.starts_with("LLM_PROVIDER=") || trimmed.starts_with("LLM_PROVIDER =") {
-            *line = format!("LLM_PROVIDER={}", provider_value);
-            found_provider = true;
-        } else if trimmed.starts_with(&format!("{}=", model_key))
-            || trimmed.starts_with(&format!("{} =", model_key))
-        {
-            *line = format!("{}={}", model_key, model);
-            found_model = true;
+fn mcp_call_lines(label: &str, result: anyhow::Result<McpCallResult>) -> Vec<String> {
+    match result {
+        Ok(out) => {
+            let mut lines = vec![format!("  {label} exit={}", out.exit_code)];
+            lines.extend(out.output.lines().map(|line| format!("    {line}")));
+            lines
         }
+        Err(e) => vec![format!("  {label} {}", format!("{e:#}").red())],
+    }
+}
+
+fn disabled_tools_label(app: &App) -> String {
+    if app.disabled_tools.is_empty() {
+        "无".to_string()
+    } else {
+        app.disabled_tools
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
     }
-    if !found_provider {
-        lines.push(format!("LLM_PROVIDER={}", provider_value));
+}
+
+/// `/tools`：列出内置工具的启用状态；`disable` / `enable` 后重建系统提示词。
+fn tools_command(app: &mut App, args: &str) -> Vec<String> {
+    let mut parts = args.split_whitespace();
+    let sub = parts.next().unwrap_or("");
+    let rest = parts.collect::<Vec<_>>().join(",");
+    match sub {
+        "" => {
+            let mut lines = vec!["  内置工具：".to_string()];
+            for name in DISABLEABLE_TOOLS {
+                let mark = if app.disabled_tools.contains(*name) {
+                    "✗"
+                } else {
+                    "✓"
+                };
+                lines.push(format!("    {mark} {name}"));
+            }
+            lines
+        }
+        "disable" | "enable" => {
+            let (names, unknown) = parse_disabled_tools(&rest);
+            if !unknown.is_empty() || names.is_empty() {
+                return vec![format!(
+                    "  用法：/tools {sub} <名称>，可选：{}",
+                    DISABLEABLE_TOOLS.join(", ")
+                )];
+            }
+            for name in names {
+                if sub == "disable" {
+                    app.disabled_tools.insert(name);
+                } else {
+                    app.disabled_tools.remove(&name);
+                }
+            }
+            app.rebuild_system_message();
+            vec![format!("  已禁用工具：{}", disabled_tools_label(app))]
+        }
+        _ => vec!["  用法：/tools [disable|enable <名称>]".to_string()],
     }
-    if !found_model {
-        lines.push(format!("{}={}", model_key, model));
+}
+
+/// 后端 label 对应的 `LLM_PROVIDER` 取值与模型变量名。
+fn backend_env_keys(backend_label: &str) -> (&'static str, &'static str) {
+    match backend_label {
+        "Kimi" => ("kimi", "KIMI_MODEL"),
+        "Mimo" => ("mimo", "MIMO_MODEL"),
+        "MiniMax" => ("minimax", "MINIMAX_MODEL"),
+        _ => ("glm", "BIGMODEL_MODEL"),
     }
+}
 
-    let content = lines.join("\n") + "\n";
-    let _ = std::fs::write(&env_path, content);
-    // 同步当前进程环境，确保切换后无需重启即可让 from_env() 读到新值。
+/// 持久化后端选择：工作区有 `.goldbot/backend` 且未指定 `--global` 时写入该文件，
+/// 否则写入全局 `~/.goldbot/.env`。返回是否写入了工作区文件。
+fn persist_backend(app: &App, backend_label: &str, model: &str) -> anyhow::Result<bool> {
+    let (provider_value, model_key) = backend_env_keys(backend_label);
+    let (env_path, local) = backend_persist_target(&app.workspace, app.model_picker.save_global);
+    // 同步当前进程环境，确保切换后无需重启即可读到新值（即使写入失败）。
     unsafe {
         std::env::set_var("LLM_PROVIDER", provider_value);
         std::env::set_var(model_key, model);
     }
+    let entries = [("LLM_PROVIDER", provider_value), (model_key, model)];
+    crate::config::upsert_env_file(&env_path, &entries)?;
+    Ok(local)
 }
 
-fn persist_api_key_to_env(key_name: &str, key_value: &str) {
-    let env_path = crate::tools::mcp::goldbot_home_dir().join(".env");
-    let raw = std::fs::read_to_string(&env_path).unwrap_or_default();
-
-    let mut lines: Vec<String> = raw.lines().map(|l| l.to_string()).collect();
-    let mut found = false;
-    for line in &mut lines {
-        let trimmed = line.trim_start();
-        if trimmed.starts_with(&format!("{key_name}="))
-            || trimmed.starts_with(&format!("{key_name} ="))
-        {
-            *line = format!("{key_name}={key_value}");
-            found = true;
-        }
-    }
-    if !found {
-        lines.push(format!("{key_name}={key_value}"));
+/// 后端选择写入的文件，以及它是否为工作区文件。
+fn backend_persist_target(
+    workspace: &std::path::Path,
+    save_global: bool,
+) -> (std::path::PathBuf, bool) {
+    match crate::memory::profile::workspace_backend_file(workspace).filter(|_| !save_global) {
+        Some(local) => (local, true),
+        None => (crate::tools::mcp::goldbot_home_dir().join(".env"), false),
     }
+}
 
-    let content = lines.join("\n") + "\n";
-    let _ = std::fs::write(&env_path, content);
+fn persist_api_key_to_env(key_name: &str, key_value: &str) -> anyhow::Result<()> {
     unsafe {
         std::env::set_var(key_name, key_value);
     }
+    let env_path = crate::tools::mcp::goldbot_home_dir().join(".env");
+    crate::config::upsert_env_file(&env_path, &[(key_name, key_value)])
 }
 
 pub(super) fn enter_model_picker_backend_stage(app: &mut App, screen: &mut Screen) {
@@ -600,6 +1137,7 @@ pub(super) fn cancel_model_picker(app: &mut App, screen: &mut Screen) {
     app.model_picker.values.clear();
     app.model_picker.sel = 0;
     app.model_picker.pending_backend = None;
+    app.model_picker.save_global = false;
     screen.model_picker_labels.clear();
     screen.model_picker_sel = 0;
 }
@@ -616,85 +1154,639 @@ pub(super) fn select_model_item(app: &mut App, screen: &mut Screen) {
         }
         crate::ModelPickerStage::Model => {
             let backend = app.model_picker.pending_backend.clone().unwrap_or_default();
-            let model = value;
-            app.backend = match backend.as_str() {
-                "Kimi" => crate::agent::provider::LlmBackend::Kimi(model.clone()),
-                "Mimo" => crate::agent::provider::LlmBackend::Mimo(model.clone()),
-                "MiniMax" => crate::agent::provider::LlmBackend::MiniMax(model.clone()),
-                _ => crate::agent::provider::LlmBackend::Glm(model.clone()),
-            };
-            app.prompt_token_scale = 1.0;
-            app.recent_completion_tokens_ema = 0;
-            persist_backend_to_env(app.backend.backend_label(), app.backend.model_name());
-            // 切换 provider 后，刷新内置 MCP 和 system prompt，
-            // 避免“启动时 provider”与“当前 provider”能力集合不一致。
-            app.mcp_registry
-                .inject_builtin_for_backend(app.backend.backend_label());
-            app.rebuild_system_message();
-            if app.mcp_discovery_rx.is_none() && app.mcp_registry.has_servers() {
-                let registry = app.mcp_registry.clone();
-                let (tx, rx) = std::sync::mpsc::channel();
-                std::thread::spawn(move || {
-                    let _ = tx.send(registry.run_discovery());
-                });
-                app.mcp_discovery_rx = Some(rx);
-            }
-            sync_context_budget(app, screen);
+            let save_global = app.model_picker.save_global;
             cancel_model_picker(app, screen);
+            app.model_picker.save_global = save_global;
             clear_input_buffer(app, screen);
+            switch_backend(app, screen, &backend, &value);
+        }
+    }
+}
+
+// ── compact 预览与确认 ───────────────────────────────────────────────────────
+
+/// 将手动 compact 加入队列，在当前步骤结束后执行。
+pub(super) fn queue_manual_compact(app: &mut App, screen: &mut Screen) {
+    if app.pending_manual_compact {
+        screen.emit(&["  /compact: 已在排队，等待当前步骤结束后执行。".to_string()]);
+        return;
+    }
+    app.pending_manual_compact = true;
+    let busy = app.llm_calling || app.shell_task_running || app.dag_task_running;
+    if busy {
+        screen.emit(&["  /compact: 已加入队列，将在当前步骤结束后走 LLM 压缩摘要。".to_string()]);
+    } else {
+        screen.emit(&["  /compact: 开始走 LLM 压缩摘要流程。".to_string()]);
+    }
+}
+
+/// 展示 compact 预览（将丢弃的消息区间 + 本地摘要），并打开 压缩/取消 确认菜单。
+pub(crate) fn open_compact_confirm(
+    app: &mut App,
+    screen: &mut Screen,
+    preview: &CompactionPreview,
+    source: CompactConfirm,
+) {
+    let title = match source {
+        CompactConfirm::Manual => t("compact.preview_title"),
+        CompactConfirm::Auto => t("compact.preview_title_auto"),
+    };
+    let mut lines = vec![
+        format!("  {title}"),
+        format!(
+            "    {}",
+            t_with(
+                "compact.preview_range",
+                &[
+                    ("start", &preview.dropped.start.to_string()),
+                    ("end", &preview.dropped.end.saturating_sub(1).to_string()),
+                    ("total", &preview.total.to_string()),
+                    ("kept", &(preview.total - preview.dropped.end).to_string()),
+                ],
+            )
+        ),
+        format!("    {}", t("compact.preview_summary")),
+    ];
+    if preview.summary.trim().is_empty() {
+        lines.push(format!("      {}", t("compact.preview_empty")));
+    } else {
+        lines.extend(preview.summary.lines().map(|l| format!("      {l}")));
+    }
+    screen.emit(&lines);
+    screen.question_labels = vec![
+        t("compact.option_compact").to_string(),
+        t("compact.option_cancel").to_string(),
+    ];
+    screen.confirm_selected = Some(0);
+    screen.input_focused = false;
+    app.pending_compact_confirm = Some(source);
+    screen.refresh();
+}
+
+/// 处理 compact 确认菜单的选择结果。
+pub(super) fn resolve_compact_confirm(app: &mut App, screen: &mut Screen, accept: bool) {
+    let Some(source) = app.pending_compact_confirm.take() else {
+        return;
+    };
+    screen.confirm_selected = None;
+    screen.question_labels.clear();
+    screen.input_focused = true;
+    if accept {
+        queue_manual_compact(app, screen);
+    } else {
+        if source == CompactConfirm::Auto {
+            app.skip_next_auto_compact = true;
+        }
+        screen.emit(&[format!("  {}", t("compact.cancelled"))]);
+    }
+    screen.refresh();
+}
+
+// ── /rerun ───────────────────────────────────────────────────────────────────
+
+/// `/rerun`：以全新上下文把上一个任务的原始输入交给当前模型重跑；上一次的输出留在屏幕上方便对比。
+fn rerun_last_task(app: &mut App, screen: &mut Screen) {
+    if app.running {
+        screen.emit(&[format!("  {}", t("rerun.busy"))]);
+        return;
+    }
+    let task = app.task.clone();
+    if task.trim().is_empty() {
+        screen.emit(&[format!("  {}", t("rerun.no_task"))]);
+        return;
+    }
+    app.messages.truncate(1);
+    app.final_summary = None;
+    screen.emit(&[
+        String::new(),
+        format!(
+            "  {}",
+            t_with(
+                "rerun.banner",
+                &[
+                    ("backend", app.backend.backend_label()),
+                    ("model", app.backend.model_name()),
+                ],
+            )
+        )
+        .cyan()
+        .to_string(),
+    ]);
+    crate::agent::executor::start_task(app, screen, task);
+}
+
+// ── /undo ────────────────────────────────────────────────────────────────────
+
+/// `/undo`：非 git 工作区中恢复最近一个任务的编辑前快照；git 仓库交给 git 处理。
+/// 任务结束后又被改过的文件需要 `/undo --force` 才会覆盖。
+fn undo_last_task(app: &mut App, screen: &mut Screen, force: bool) {
+    use crate::tools::git::is_git_workspace;
+    use crate::tools::snapshot::{snapshots_dir, undo_latest};
+
+    if app.running {
+        screen.emit(&[format!("  {}", t("undo.busy"))]);
+        return;
+    }
+    if is_git_workspace(&app.workspace) {
+        screen.emit(&[format!("  {}", t("undo.git_repo"))]);
+        return;
+    }
+    // 被取消的任务没走到 finish，这里补记结束时的状态
+    crate::agent::executor::seal_task_snapshot(app, screen);
+    let report = match undo_latest(&snapshots_dir(), &app.workspace, force) {
+        Ok(Some(report)) => report,
+        Ok(None) => {
+            screen.emit(&[format!("  {}", t("undo.none"))]);
+            return;
+        }
+        Err(e) => {
+            screen.emit(&[format!("  {}", format!("{e:#}").red())]);
+            return;
+        }
+    };
+    if !report.changed.is_empty() {
+        let mut lines = vec![format!("  {}", t("undo.changed")).dark_yellow().to_string()];
+        lines.extend(
+            report
+                .changed
+                .iter()
+                .map(|path| format!("    {}", path.display())),
+        );
+        screen.emit(&lines);
+        return;
+    }
+    app.task_snapshot = None;
+    let mut lines = vec![
+        format!("  {}", t_with("undo.done", &[("task", &report.task)]))
+            .green()
+            .to_string(),
+    ];
+    for (paths, key) in [
+        (&report.restored, "undo.restored"),
+        (&report.removed, "undo.removed"),
+        (&report.skipped, "undo.skipped"),
+    ] {
+        lines.extend(
+            paths
+                .iter()
+                .map(|path| format!("    {} {}", path.display(), t(key).grey())),
+        );
+    }
+    screen.emit(&lines);
+}
+
+// ── /remember · /forget ───────────────────────────────────────────────────────
+
+/// `/remember <内容>`：写入长期记忆并重建系统提示词，本会话后续调用立即生效。
+fn remember_note(app: &mut App, screen: &mut Screen, note: &str) {
+    if note.trim().is_empty() {
+        screen.emit(&[format!("  {}", t("memory.remember_usage"))]);
+        return;
+    }
+    let store = crate::memory::project::ProjectStore::current();
+    match store.pin_memory(note) {
+        Ok(true) => {
+            app.rebuild_system_message();
+            sync_context_budget(app, screen);
+            screen.emit(&[format!(
+                "  {}",
+                t_with("memory.remembered", &[("note", note.trim())])
+            )]);
+        }
+        Ok(false) => screen.emit(&[format!("  {}", t("memory.already_known"))]),
+        Err(e) => screen.emit(&[format!("  ✗ /remember: {e:#}")]),
+    }
+}
+
+/// `/forget <关键词>`：删除包含该文字的长期记忆（不区分大小写）。
+fn forget_notes(app: &mut App, screen: &mut Screen, pattern: &str) {
+    if pattern.trim().is_empty() {
+        screen.emit(&[format!("  {}", t("memory.forget_usage"))]);
+        return;
+    }
+    let store = crate::memory::project::ProjectStore::current();
+    match store.forget_memory(pattern) {
+        Ok(removed) if removed.is_empty() => {
+            screen.emit(&[format!("  {}", t("memory.forget_none"))]);
+        }
+        Ok(removed) => {
+            app.rebuild_system_message();
+            sync_context_budget(app, screen);
             let mut lines = vec![format!(
-                "  已切换至 {} / {}",
-                app.backend.backend_label(),
-                app.backend.model_name()
+                "  {}",
+                t_with("memory.forgot", &[("count", &removed.len().to_string())])
             )];
-            lines.push("  已刷新当前 provider 的 MCP / system prompt。".to_string());
-            app.pending_api_key_name = None;
-            screen.status.clear();
-            let key_name = app.backend.required_key_name().to_string();
-            if let Some(key_value) = resolve_valid_api_key(&key_name) {
-                unsafe {
-                    std::env::set_var(&key_name, &key_value);
-                }
-            } else {
-                let env_path = crate::tools::mcp::goldbot_home_dir().join(".env");
-                app.pending_api_key_name = Some(key_name.clone());
-                app.running = false;
-                app.needs_agent_executor = false;
-                screen.input_focused = true;
-                lines.push(format!(
-                    "  {} {} 未配置，请编辑: {}",
-                    crossterm::style::Stylize::yellow(
-                        crate::ui::symbols::Symbols::current().warning
-                    ),
-                    key_name,
-                    env_path.display()
-                ));
-                lines.push(format!(
-                    "  Paste {key_name} now and press Enter to continue this session."
-                ));
-                screen.status = format!("Waiting for {} input...", key_name)
-                    .dark_yellow()
-                    .to_string();
-            }
+            lines.extend(removed.iter().map(|n| format!("    - {n}")));
             screen.emit(&lines);
         }
+        Err(e) => screen.emit(&[format!("  ✗ /forget: {e:#}")]),
+    }
+}
+
+// ── /history ─────────────────────────────────────────────────────────────────
+
+/// `/history` 默认显示的记录条数。
+const HISTORY_DEFAULT_LIMIT: usize = 10;
+
+/// `/history [数量]` 列出本会话最近的任务；`/history search <关键词>` 按子串过滤（不区分大小写）。
+fn history_lines(args: &str) -> Vec<String> {
+    let store = Session::current();
+    let (term, limit) = match args.strip_prefix("search") {
+        Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+            (Some(rest.trim().to_lowercase()), HISTORY_DEFAULT_LIMIT)
+        }
+        _ => (None, args.parse().unwrap_or(HISTORY_DEFAULT_LIMIT)),
+    };
+    let entries: Vec<_> = match term.as_deref() {
+        Some(term) => store
+            .recent_short_term(usize::MAX)
+            .into_iter()
+            .filter(|e| {
+                e.task.to_lowercase().contains(term) || e.summary.to_lowercase().contains(term)
+            })
+            .take(limit)
+            .collect(),
+        None => store.recent_short_term(limit),
+    };
+    if entries.is_empty() {
+        return vec!["  （本会话暂无匹配的任务记录）".to_string()];
+    }
+    let mut lines = Vec::with_capacity(entries.len() * 2);
+    for entry in entries {
+        let task = entry.task.lines().next().unwrap_or("");
+        lines.push(format!("  {}  {}", entry.time.as_str().dark_grey(), task));
+        if !entry.summary.is_empty() {
+            lines.push(format!("    {}", entry.summary).grey().to_string());
+        }
+    }
+    lines
+}
+
+// ── 收藏后端快速切换（Ctrl+B） ───────────────────────────────────────────────
+
+/// 收藏列表文件：`~/.goldbot/favorites.txt`，每行 `<backend> <model>`，`#` 开头为注释。
+fn favorites_file_path() -> std::path::PathBuf {
+    crate::tools::mcp::goldbot_home_dir().join("favorites.txt")
+}
+
+/// 解析收藏列表；后端名大小写不敏感，未知后端的行会被忽略。
+fn parse_backend_favorites(raw: &str) -> Vec<(String, String)> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (backend, model) = line.split_once(char::is_whitespace)?;
+            let label = BACKEND_PRESETS
+                .iter()
+                .find(|(label, _)| label.eq_ignore_ascii_case(backend))?
+                .0;
+            let model = model.trim();
+            (!model.is_empty()).then(|| (label.to_string(), model.to_string()))
+        })
+        .collect()
+}
+
+/// 读取收藏列表；文件不存在或为空时退回每个后端的默认预设模型。
+fn load_backend_favorites() -> Vec<(String, String)> {
+    let raw = std::fs::read_to_string(favorites_file_path()).unwrap_or_default();
+    let favorites = parse_backend_favorites(&raw);
+    if !favorites.is_empty() {
+        return favorites;
+    }
+    BACKEND_PRESETS
+        .iter()
+        .filter_map(|(label, models)| Some((label.to_string(), models.first()?.to_string())))
+        .collect()
+}
+
+/// 返回当前选择之后的下一个收藏项；当前选择不在列表中时返回第一项。
+fn next_backend_favorite<'a>(
+    favorites: &'a [(String, String)],
+    backend_label: &str,
+    model: &str,
+) -> Option<&'a (String, String)> {
+    let next = favorites
+        .iter()
+        .position(|(b, m)| b == backend_label && m.eq_ignore_ascii_case(model))
+        .map_or(0, |idx| (idx + 1) % favorites.len());
+    favorites.get(next)
+}
+
+/// Ctrl+B：切换到下一个收藏的后端/模型。
+/// 任务运行中不打断当前调用，而是记录下来，在下一次 LLM 调用前生效。
+pub(super) fn cycle_backend_favorite(app: &mut App, screen: &mut Screen) {
+    let favorites = load_backend_favorites();
+    let (current_label, current_model) = app.pending_backend_switch.clone().unwrap_or_else(|| {
+        (
+            app.backend.backend_label().to_string(),
+            app.backend.model_name().to_string(),
+        )
+    });
+    let Some((label, model)) =
+        next_backend_favorite(&favorites, &current_label, &current_model).cloned()
+    else {
+        return;
+    };
+    if app.running {
+        screen.status = format!("下一次调用将切换至 {label} / {model}")
+            .grey()
+            .to_string();
+        app.pending_backend_switch = Some((label, model));
+        screen.refresh();
+        return;
     }
+    switch_backend(app, screen, &label, &model);
+}
+
+/// 应用 Ctrl+B 在运行期间记录的待切换后端（在发起下一次 LLM 调用前调用）。
+pub(crate) fn apply_pending_backend_switch(app: &mut App, screen: &mut Screen) {
+    if let Some((label, model)) = app.pending_backend_switch.take() {
+        switch_backend(app, screen, &label, &model);
+    }
+}
+
+/// 切换当前后端与模型：持久化到 `.env`，刷新 MCP / system prompt，并处理缺失 API key 的流程。
+fn switch_backend(app: &mut App, screen: &mut Screen, backend: &str, model: &str) {
+    let model = model.to_string();
+    app.backend = match backend {
+        "Kimi" => crate::agent::provider::LlmBackend::Kimi(model),
+        "Mimo" => crate::agent::provider::LlmBackend::Mimo(model),
+        "MiniMax" => crate::agent::provider::LlmBackend::MiniMax(model),
+        _ => crate::agent::provider::LlmBackend::Glm(model),
+    };
+    app.pending_backend_switch = None;
+    app.prompt_token_scale = 1.0;
+    app.recent_completion_tokens_ema = 0;
+    let label = app.backend.backend_label().to_string();
+    let model_name = app.backend.model_name().to_string();
+    let saved = persist_backend(app, &label, &model_name);
+    if let Ok(local) = saved {
+        app.backend_from_workspace = local;
+    }
+    app.model_picker.save_global = false;
+    // 切换 provider 后，刷新内置 MCP 和 system prompt，
+    // 避免“启动时 provider”与“当前 provider”能力集合不一致。
+    app.mcp_registry
+        .inject_builtin_for_backend(app.backend.backend_label());
+    app.rebuild_system_message();
+    if app.mcp_discovery_rx.is_none() && app.mcp_registry.has_servers() {
+        let registry = app.mcp_registry.clone();
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(registry.run_discovery());
+        });
+        app.mcp_discovery_rx = Some(rx);
+    }
+    sync_context_budget(app, screen);
+    let mut lines = vec![format!(
+        "  已切换至 {} / {}",
+        app.backend.backend_label(),
+        app.backend.model_name()
+    )];
+    lines.push("  已刷新当前 provider 的 MCP / system prompt。".to_string());
+    if let Err(e) = &saved {
+        lines.push(save_warning_line(e));
+    }
+    app.pending_api_key_name = None;
+    screen.status = format!(
+        "Model: {} / {}",
+        app.backend.backend_label(),
+        app.backend.model_name()
+    )
+    .grey()
+    .to_string();
+    let key_name = app.backend.required_key_name().to_string();
+    if let Some(key_value) = resolve_valid_api_key(&key_name) {
+        unsafe {
+            std::env::set_var(&key_name, &key_value);
+        }
+    } else {
+        lines.extend(enter_api_key_input(app, screen, &key_name, "未配置"));
+    }
+    screen.emit(&lines);
+}
+
+/// 进入 API Key 输入流程：暂停当前任务，提交 Key 后自动重试。
+fn enter_api_key_input(
+    app: &mut App,
+    screen: &mut Screen,
+    key_name: &str,
+    reason: &str,
+) -> Vec<String> {
+    let env_path = crate::tools::mcp::goldbot_home_dir().join(".env");
+    app.pending_api_key_name = Some(key_name.to_string());
+    app.running = false;
+    app.needs_agent_executor = false;
+    screen.input_focused = true;
+    screen.status = format!("Waiting for {} input...", key_name)
+        .dark_yellow()
+        .to_string();
+    vec![
+        format!(
+            "  {} {} {}，请编辑: {}",
+            crossterm::style::Stylize::yellow(crate::ui::symbols::Symbols::current().warning),
+            key_name,
+            reason,
+            env_path.display()
+        ),
+        format!("  Paste {key_name} now and press Enter to continue this session."),
+    ]
+}
+
+/// Provider 返回 Unauthorized 时调用：提示重新输入当前后端的 API Key。
+pub(crate) fn request_api_key_reentry(app: &mut App, screen: &mut Screen, detail: &str) {
+    let key_name = app.backend.required_key_name();
+    let mut lines = vec![format!("  ✗ 鉴权失败：{detail}")];
+    lines.extend(enter_api_key_input(app, screen, key_name, "无效或已过期"));
+    screen.emit(&lines);
+    screen.refresh();
 }
 
 #[cfg(test)]
 mod tests {
-    use super::dispatch_builtin_command;
-    use crate::App;
+    use super::{
+        FileIndexFilter, attachment_lines, backend_persist_target, dispatch_builtin_command,
+        mcp_health_lines, next_backend_favorite, parse_backend_favorites,
+    };
     use crate::agent::provider::Message;
     use crate::tools::command::BuiltinCommand;
+    use crate::tools::fs::collect_all_files;
+    use crate::ui::i18n::{t, t_with};
+    use crate::ui::input::handle_key;
     use crate::ui::screen::Screen;
+    use crate::{App, AtFileChunk, PasteChunk};
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[test]
+    fn mcp_health_lines_show_latency_and_failure_reason() {
+        use crate::tools::mcp::McpServerHealth;
+        use std::time::Duration;
+
+        let lines = mcp_health_lines(&[
+            McpServerHealth {
+                name: "context7".to_string(),
+                tool_count: 2,
+                failure: None,
+                last_latency: Some(Duration::from_millis(120)),
+            },
+            McpServerHealth {
+                name: "slow".to_string(),
+                tool_count: 1,
+                failure: None,
+                last_latency: Some(Duration::from_millis(2500)),
+            },
+            McpServerHealth {
+                name: "broken".to_string(),
+                tool_count: 0,
+                failure: Some("failed to spawn `nope`: No such file or directory".to_string()),
+                last_latency: None,
+            },
+        ]);
+        assert_eq!(lines[0], "    ✓ context7  (2 个工具，上次调用 120ms)");
+        assert_eq!(lines[1], "    ✓ slow  (1 个工具，上次调用 2.5s)");
+        assert!(lines[2].contains("✗ broken") && lines[2].contains("No such file"));
+    }
+
+    fn draft_with_attachments(app: &mut App, screen: &mut Screen) -> std::path::PathBuf {
+        let file = std::env::temp_dir().join(format!("goldbot-attach-{}.rs", std::process::id()));
+        std::fs::write(&file, "fn main() {}\n").unwrap();
+        app.at_file.chunks.push(AtFileChunk {
+            placeholder: "@src/main.rs".to_string(),
+            path: file.clone(),
+            is_image: false,
+        });
+        app.paste_chunks.push(PasteChunk {
+            placeholder: "[Pasted text #1 +3 lines]".to_string(),
+            content: "a\nb\nc".to_string(),
+        });
+        screen.input = "explain [Pasted text #1 +3 lines] using @src/main.rs please".to_string();
+        screen.input_cursor = screen.input.len();
+        file
+    }
+
+    #[test]
+    fn attachments_lists_draft_chunks_in_input_order() {
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        assert_eq!(
+            attachment_lines(&app, ""),
+            [format!("  {}", t("attach.empty"))]
+        );
+
+        let file = draft_with_attachments(&mut app, &mut screen);
+        let lines = attachment_lines(&app, &screen.input);
+        let _ = std::fs::remove_file(file);
+
+        assert_eq!(lines.len(), 3, "{lines:?}");
+        let pasted = t_with("attach.paste_detail", &[("lines", "3"), ("size", "5 B")]);
+        assert_eq!(
+            lines[1],
+            format!("    1. [Pasted text #1 +3 lines]  ({pasted})")
+        );
+        let file = t_with(
+            "attach.file_detail",
+            &[("kind", t("attach.file")), ("size", "13 B")],
+        );
+        assert_eq!(lines[2], format!("    2. @src/main.rs  ({file})"));
+    }
+
+    #[test]
+    fn ctrl_c_leaves_the_transcript_overlay_before_quitting() {
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        crate::ui::input::modes::open_transcript(&mut app, &mut screen);
+        assert!(app.transcript.is_some());
+
+        assert!(handle_key(
+            &mut app,
+            &mut screen,
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL,
+        ));
+        assert!(app.transcript.is_none());
+    }
+
+    #[test]
+    fn attachments_remove_strips_placeholder_and_keeps_draft() {
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        let file = draft_with_attachments(&mut app, &mut screen);
+        let draft = screen.input.clone();
+
+        // 草稿中换行后输入的 `/` 只是普通字符（如 `/usr/...` 路径）
+        handle_key(
+            &mut app,
+            &mut screen,
+            KeyCode::Char('j'),
+            KeyModifiers::CONTROL,
+        );
+        handle_key(
+            &mut app,
+            &mut screen,
+            KeyCode::Char('/'),
+            KeyModifiers::NONE,
+        );
+        assert!(app.cmd_picker.query.is_none());
+        handle_key(
+            &mut app,
+            &mut screen,
+            KeyCode::Backspace,
+            KeyModifiers::NONE,
+        );
+        handle_key(
+            &mut app,
+            &mut screen,
+            KeyCode::Backspace,
+            KeyModifiers::NONE,
+        );
+        assert_eq!(screen.input, draft);
+
+        // Alt+/ 保留草稿打开选择器，执行 `/attachments` 后草稿与附件不丢失
+        handle_key(&mut app, &mut screen, KeyCode::Char('/'), KeyModifiers::ALT);
+        assert!(app.cmd_picker.query.is_some());
+        for c in "attachments".chars() {
+            handle_key(&mut app, &mut screen, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        handle_key(&mut app, &mut screen, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(app.cmd_picker.query.is_none());
+        assert_eq!(screen.input, draft);
+        assert_eq!(screen.input_cursor, draft.len());
+        assert_eq!((app.at_file.chunks.len(), app.paste_chunks.len()), (1, 1));
+
+        dispatch_builtin_command(
+            &mut app,
+            &mut screen,
+            BuiltinCommand::Attachments,
+            "remove 2",
+        );
+        assert!(app.at_file.chunks.is_empty());
+        assert_eq!(
+            screen.input,
+            "explain [Pasted text #1 +3 lines] using please"
+        );
+        assert_eq!(screen.input_cursor, screen.input.len());
+
+        dispatch_builtin_command(&mut app, &mut screen, BuiltinCommand::Attachments, "rm 1");
+        assert!(app.paste_chunks.is_empty());
+        assert_eq!(screen.input, "explain using please");
+
+        // 越界序号不改动草稿
+        dispatch_builtin_command(
+            &mut app,
+            &mut screen,
+            BuiltinCommand::Attachments,
+            "remove 1",
+        );
+        assert_eq!(screen.input, "explain using please");
+        let _ = std::fs::remove_file(file);
+    }
 
     #[test]
     fn compact_command_queues_manual_compaction() {
         let mut app = App::new();
         let mut screen = Screen::new_headless().expect("headless screen");
 
-        dispatch_builtin_command(&mut app, &mut screen, BuiltinCommand::Compact);
+        dispatch_builtin_command(&mut app, &mut screen, BuiltinCommand::Compact, "");
 
         assert!(app.pending_manual_compact);
     }
@@ -713,7 +1805,7 @@ mod tests {
             .map(|msg| (msg.role.clone(), msg.content.clone()))
             .collect();
 
-        dispatch_builtin_command(&mut app, &mut screen, BuiltinCommand::Compact);
+        dispatch_builtin_command(&mut app, &mut screen, BuiltinCommand::Compact, "");
 
         let after: Vec<_> = app
             .messages
@@ -723,4 +1815,188 @@ mod tests {
         assert_eq!(after, before);
         assert!(app.pending_manual_compact);
     }
+
+    #[test]
+    fn backend_favorites_cycle_wraps_and_skips_unknown_lines() {
+        let favorites = parse_backend_favorites(
+            "# quick A/B\nglm glm-5\nminimax MiniMax-M2.7\nopenai gpt-x\n\nKimi kimi-for-coding\n",
+        );
+        assert_eq!(
+            favorites,
+            vec![
+                ("GLM".to_string(), "glm-5".to_string()),
+                ("MiniMax".to_string(), "MiniMax-M2.7".to_string()),
+                ("Kimi".to_string(), "kimi-for-coding".to_string()),
+            ]
+        );
+
+        let next = next_backend_favorite(&favorites, "GLM", "glm-5").unwrap();
+        assert_eq!(next.0, "MiniMax");
+        let next = next_backend_favorite(&favorites, "Kimi", "kimi-for-coding").unwrap();
+        assert_eq!(next.0, "GLM");
+        // 当前模型不在收藏里时从第一项开始
+        let next = next_backend_favorite(&favorites, "Mimo", "mimo-v2-pro").unwrap();
+        assert_eq!(next.0, "GLM");
+        assert!(next_backend_favorite(&[], "GLM", "glm-5").is_none());
+    }
+
+    #[test]
+    fn compact_preview_shows_summary_without_touching_messages() {
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        for i in 0..30 {
+            app.messages.push(Message::user(format!("task {i}")));
+            app.messages
+                .push(Message::assistant(format!("<final>done {i}</final>")));
+        }
+        let before: Vec<_> = app.messages.iter().map(|m| m.content.clone()).collect();
+
+        let preview = crate::agent::executor::preview_compaction(&app).expect("preview");
+        assert!(preview.summary.contains("final: done"));
+        assert_eq!(preview.dropped.start, 1);
+        assert!(preview.dropped.end < app.messages.len());
+
+        dispatch_builtin_command(&mut app, &mut screen, BuiltinCommand::CompactPreview, "");
+
+        let after: Vec<_> = app.messages.iter().map(|m| m.content.clone()).collect();
+        assert_eq!(after, before);
+        assert!(!app.pending_manual_compact);
+        assert_eq!(
+            app.pending_compact_confirm,
+            Some(crate::types::CompactConfirm::Manual)
+        );
+    }
+
+    #[test]
+    fn file_index_skips_custom_ignored_dirs() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-index-test-{nanos}"));
+        for file in [
+            "src/main.rs",
+            "gen/api.rs",
+            "packages/web/generated/types.ts",
+            "packages/web/lib.ts",
+            "target/debug/out.rs",
+        ] {
+            let path = ws.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        std::fs::create_dir_all(ws.join(".goldbot")).unwrap();
+        std::fs::write(
+            ws.join(".goldbot/ignore"),
+            "# 生成代码\ngen/\npackages/*/generated\n",
+        )
+        .unwrap();
+
+        let filter = FileIndexFilter::for_workspace(&ws);
+        let mut files = Vec::new();
+        collect_all_files(&ws, &ws, &mut files, 0, &filter);
+        let mut names: Vec<String> = files
+            .iter()
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .collect();
+        names.sort();
+        assert_eq!(names, ["packages/web/lib.ts", "src/main.rs"]);
+
+        // 上限生效
+        let capped = FileIndexFilter::from_ignore_list("", 1);
+        let mut files = Vec::new();
+        collect_all_files(&ws, &ws, &mut files, 0, &capped);
+        assert_eq!(files.len(), 1);
+        let _ = std::fs::remove_dir_all(ws);
+    }
+
+    fn type_text(app: &mut App, screen: &mut Screen, text: &str) {
+        for c in text.chars() {
+            handle_key(app, screen, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+    }
+
+    #[test]
+    fn complete_at_path_attaches_without_picker() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-at-path-test-{nanos}"));
+        std::fs::create_dir_all(ws.join("src")).unwrap();
+        std::fs::write(ws.join("src/lib.rs"), "pub fn answer() -> u32 { 42 }\n").unwrap();
+
+        // 完整路径：回车直接提交并作为附件，不经过候选项选择
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.workspace = ws.clone();
+        app.no_memory = true;
+        type_text(&mut app, &mut screen, "解释 @src/lib.rs");
+        assert_eq!(app.at_file.query.as_deref(), Some("src/lib.rs"));
+        handle_key(&mut app, &mut screen, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(app.running);
+        assert!(app.at_file.query.is_none());
+        assert!(
+            app.task.contains("Attached file paths:\n- @src/lib.rs ("),
+            "{}",
+            app.task
+        );
+
+        // 不完整的路径：回车交给选择器处理，不会提交
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.workspace = ws.clone();
+        app.no_memory = true;
+        type_text(&mut app, &mut screen, "@src/li");
+        assert_eq!(app.at_file.query.as_deref(), Some("src/li"));
+        handle_key(&mut app, &mut screen, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(!app.running && app.task.is_empty());
+
+        let _ = std::fs::remove_dir_all(ws);
+    }
+
+    #[test]
+    fn rerun_reissues_last_task_with_fresh_context() {
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.no_memory = true;
+
+        // 没有上一个任务时不做任何事
+        dispatch_builtin_command(&mut app, &mut screen, BuiltinCommand::Rerun, "");
+        assert!(!app.running);
+
+        app.task = "summarize src/main.rs".to_string();
+        app.messages
+            .push(Message::user("summarize src/main.rs".to_string()));
+        app.messages
+            .push(Message::assistant("old answer".to_string()));
+        dispatch_builtin_command(&mut app, &mut screen, BuiltinCommand::Rerun, "");
+        assert!(app.running && app.needs_agent_executor);
+        assert_eq!(app.messages.len(), 2);
+        assert_eq!(app.messages[1].content, "summarize src/main.rs");
+
+        // 任务进行中不允许重跑
+        app.task = "another".to_string();
+        dispatch_builtin_command(&mut app, &mut screen, BuiltinCommand::Rerun, "");
+        assert_eq!(app.messages.len(), 2);
+        assert_eq!(app.messages[1].content, "summarize src/main.rs");
+    }
+
+    #[test]
+    fn model_switch_targets_workspace_backend_file_unless_global() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-model-target-{nanos}"));
+        std::fs::create_dir_all(ws.join(".goldbot")).unwrap();
+        let global = crate::tools::mcp::goldbot_home_dir().join(".env");
+        assert_eq!(backend_persist_target(&ws, false), (global.clone(), false));
+
+        let local = ws.join(".goldbot").join("backend");
+        std::fs::write(&local, "LLM_PROVIDER=kimi\n").unwrap();
+        assert_eq!(backend_persist_target(&ws, false), (local, true));
+        assert_eq!(backend_persist_target(&ws, true), (global, false));
+        let _ = std::fs::remove_dir_all(&ws);
+    }
 }
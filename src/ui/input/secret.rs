@@ -0,0 +1,260 @@
+use crossterm::style::Stylize;
+
+use crate::App;
+use crate::agent::executor::execute_command;
+use crate::agent::provider::Message;
+use crate::config::display_config_path;
+use crate::memory::profile::{SecretScope, exec_env_file, provide_secret};
+use crate::ui::format::save_warning_line;
+use crate::ui::i18n::{t, t_with};
+use crate::ui::screen::Screen;
+use crate::ui::symbols::Symbols;
+
+use super::pickers::parse_api_key_input;
+use super::submit::expand_input_text;
+
+/// `<ask_secret>` 流程状态：`value` 为 None 时等待遮罩输入，Some 时等待选择保存方式。
+pub(crate) struct PendingSecret {
+    name: String,
+    value: Option<String>,
+    /// 凭据设置后重试的失败命令；找不到时交回 Agent 继续。
+    retry: Option<String>,
+    /// 进入流程前输入框中的草稿，结束后放回。
+    draft: String,
+}
+
+impl PendingSecret {
+    pub(super) fn awaiting_input(&self) -> bool {
+        self.value.is_none()
+    }
+}
+
+/// Agent 请求凭据：暂停任务，输入框切换为遮罩输入。
+pub(crate) fn request_secret_input(
+    app: &mut App,
+    screen: &mut Screen,
+    name: &str,
+    retry: Option<String>,
+) {
+    let draft = std::mem::take(&mut screen.input);
+    screen.input_cursor = 0;
+    app.pending_ask_secret = Some(PendingSecret {
+        name: name.to_string(),
+        value: None,
+        retry,
+        draft,
+    });
+    app.running = false;
+    app.needs_agent_executor = false;
+    screen.mask_input = true;
+    screen.input_focused = true;
+    screen.status = t_with("secret.waiting", &[("name", name)])
+        .dark_yellow()
+        .to_string();
+    screen.emit(&[
+        format!(
+            "  {} {}",
+            Symbols::current().warning.yellow(),
+            t_with("secret.requested", &[("name", name)])
+        ),
+        format!("  {}", t_with("secret.hint", &[("name", name)])),
+    ]);
+    screen.refresh();
+}
+
+/// 遮罩输入按 Enter：记下值并打开“仅本次会话 / 保存 / 取消”菜单。
+pub(super) fn submit_secret_input(app: &mut App, screen: &mut Screen) {
+    let raw = expand_input_text(app, &screen.input);
+    let Some(pending) = app.pending_ask_secret.as_mut() else {
+        return;
+    };
+    let value = parse_api_key_input(&raw, &pending.name);
+    if value.is_empty() {
+        screen.status = t_with("secret.empty", &[("name", &pending.name)])
+            .dark_yellow()
+            .to_string();
+        screen.refresh();
+        return;
+    }
+    // 粘贴的长文本以占位符形式存放，用完即丢弃，不留在草稿附件里
+    app.paste_chunks
+        .retain(|chunk| !screen.input.contains(&chunk.placeholder));
+    screen.input.clear();
+    screen.input_cursor = 0;
+    screen.mask_input = false;
+    pending.value = Some(value);
+
+    let path = display_config_path(&exec_env_file(&app.workspace));
+    screen.emit(&[format!(
+        "  {}",
+        t_with("secret.scope_prompt", &[("name", &pending.name)])
+    )]);
+    screen.question_labels = vec![
+        t("secret.session").to_string(),
+        t_with("secret.save", &[("path", &path)]),
+        t("secret.cancel").to_string(),
+    ];
+    screen.confirm_selected = Some(0);
+    screen.input_focused = false;
+    screen.status.clear();
+    screen.refresh();
+}
+
+/// 选择保存方式：0 = 仅本次会话，1 = 写入 exec_env，其它 = 放弃。之后恢复任务，
+/// 有失败命令时直接重试。凭据值只进入命令环境，不写进对话。
+pub(super) fn resolve_secret_scope(app: &mut App, screen: &mut Screen, choice: usize) {
+    let Some(pending) = app.pending_ask_secret.take() else {
+        return;
+    };
+    screen.confirm_selected = None;
+    screen.question_labels.clear();
+    screen.input_focused = true;
+    screen.mask_input = false;
+    screen.status.clear();
+    screen.input = pending.draft;
+    screen.input_cursor = screen.input.len();
+    app.running = true;
+
+    let name = pending.name;
+    let scope = match choice {
+        0 => Some(SecretScope::Session),
+        1 => Some(SecretScope::Saved),
+        _ => None,
+    };
+    let (Some(scope), Some(value)) = (scope, pending.value) else {
+        screen.emit(&[format!(
+            "  {}",
+            t_with("secret.declined", &[("name", &name)])
+        )]);
+        app.messages.push(Message::user(format!(
+            "[ask_secret] The user declined to provide {name}. Continue without it or explain what is needed."
+        )));
+        app.needs_agent_executor = true;
+        screen.refresh();
+        return;
+    };
+
+    let mut lines = Vec::new();
+    let mut kept = "for this session";
+    match provide_secret(&app.workspace, &name, &value, scope) {
+        Ok(()) if scope == SecretScope::Saved => {
+            kept = "and saved it to exec_env";
+            let path = display_config_path(&exec_env_file(&app.workspace));
+            lines.push(format!(
+                "  {}",
+                t_with("secret.set_saved", &[("name", &name), ("path", &path)])
+            ));
+        }
+        Ok(()) => lines.push(format!(
+            "  {}",
+            t_with("secret.set_session", &[("name", &name)])
+        )),
+        // 保存失败时值仍对本次会话生效
+        Err(e) => {
+            lines.push(format!(
+                "  {}",
+                t_with("secret.set_session", &[("name", &name)])
+            ));
+            lines.push(save_warning_line(&e));
+        }
+    }
+    match pending.retry {
+        Some(command) => {
+            lines.push(format!(
+                "  {}",
+                t_with("secret.retrying", &[("command", &command)])
+            ));
+            screen.emit(&lines);
+            app.messages.push(Message::user(format!(
+                "[ask_secret] The user provided {name} {kept}. Re-running the failed command: {command}"
+            )));
+            execute_command(app, screen, &command);
+        }
+        None => {
+            screen.emit(&lines);
+            app.messages.push(Message::user(format!(
+                "[ask_secret] The user provided {name} {kept}. It is now set in the environment of your commands; continue the task."
+            )));
+            app.needs_agent_executor = true;
+        }
+    }
+    screen.refresh();
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    use super::request_secret_input;
+    use crate::App;
+    use crate::ui::input::handle_key;
+    use crate::ui::screen::{Screen, masked_input};
+
+    fn press(app: &mut App, screen: &mut Screen, key: KeyCode) {
+        handle_key(app, screen, key, KeyModifiers::NONE);
+    }
+
+    #[test]
+    fn secret_input_is_masked_and_saved_only_when_chosen() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ws = std::env::temp_dir().join(format!("goldbot-ask-secret-{nanos}"));
+        std::fs::create_dir_all(ws.join(".goldbot")).unwrap();
+        let exec_env = ws.join(".goldbot").join("exec_env");
+
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        app.workspace = ws.clone();
+        screen.input = "half-typed draft".to_string();
+
+        for (name, choice) in [
+            ("GOLDBOT_TEST_ASK_SESSION", 0),
+            ("GOLDBOT_TEST_ASK_SAVED", 1),
+        ] {
+            request_secret_input(&mut app, &mut screen, name, None);
+            assert!(screen.mask_input && screen.input.is_empty());
+            for c in "ghp_密钥".chars() {
+                press(&mut app, &mut screen, KeyCode::Char(c));
+            }
+            assert_eq!(screen.input, "ghp_密钥");
+            // 每个字符一个圆点，光标列数与圆点数一致（宽字符也只占一格）
+            assert_eq!(masked_input(&screen.input, "•"), "••••••");
+            assert_eq!(screen.cursor_row_col(), (0, 6));
+            press(&mut app, &mut screen, KeyCode::Backspace);
+            assert_eq!(screen.cursor_row_col(), (0, 5));
+
+            press(&mut app, &mut screen, KeyCode::Enter);
+            assert_eq!(screen.confirm_selected, Some(0));
+            assert!(!screen.mask_input && screen.input.is_empty());
+            for _ in 0..choice {
+                press(&mut app, &mut screen, KeyCode::Down);
+            }
+            press(&mut app, &mut screen, KeyCode::Enter);
+
+            assert!(app.pending_ask_secret.is_none());
+            assert!(app.running && app.needs_agent_executor);
+            assert_eq!(screen.input, "half-typed draft");
+            let env = crate::memory::profile::exec_env(&ws);
+            assert_eq!(env[name], "ghp_密");
+            // 值只进入命令环境，不进入对话
+            let last = app.messages.last().unwrap();
+            assert!(last.content.contains(name) && !last.content.contains("ghp_"));
+            screen.input = "half-typed draft".to_string();
+        }
+
+        let saved = std::fs::read_to_string(&exec_env).unwrap();
+        assert_eq!(saved, "GOLDBOT_TEST_ASK_SAVED=ghp_密\n");
+
+        // Esc 放弃：不设置、不重试，交回 Agent
+        request_secret_input(&mut app, &mut screen, "GOLDBOT_TEST_ASK_DECLINED", None);
+        press(&mut app, &mut screen, KeyCode::Char('x'));
+        press(&mut app, &mut screen, KeyCode::Esc);
+        assert!(app.pending_ask_secret.is_none() && !screen.mask_input);
+        assert!(!crate::memory::profile::exec_env(&ws).contains_key("GOLDBOT_TEST_ASK_DECLINED"));
+        assert!(app.messages.last().unwrap().content.contains("declined"));
+
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+}
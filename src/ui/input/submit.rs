@@ -1,14 +1,18 @@
 use crossterm::style::Stylize;
 
+use crate::agent::delegate::{parse_executor_prefix, start_external_task};
 use crate::agent::executor::{finish, sync_context_budget};
 use crate::agent::provider::Message;
 use crate::agent::react::build_interjection_user_message;
+use crate::tools::secrets::{find_secrets, redact_secrets, secret_kinds};
 use crate::types::{Event, Mode};
 use crate::ui::format::emit_live_event;
 use crate::ui::ge::{drain_ge_events, parse_ge_command};
+use crate::ui::i18n::t;
 use crate::ui::screen::Screen;
 use crate::{App, PasteChunk};
 
+use super::keymap::KeyAction;
 use super::modes::begin_confirm_note_mode;
 
 pub(crate) fn submit_question_answer(app: &mut App, screen: &mut Screen, answer: String) {
@@ -40,7 +44,10 @@ pub(super) fn submit_user_input(app: &mut App, screen: &mut Screen, task: String
 pub(super) fn should_interrupt_llm_chat_loop(app: &App) -> bool {
     app.mode == Mode::Normal
         && app.running
-        && (app.llm_calling || app.needs_agent_executor || app.shell_task_running)
+        && (app.llm_calling
+            || app.needs_agent_executor
+            || app.shell_task_running
+            || app.external_task_running)
         && app.pending_confirm.is_none()
         && app.pending_question.is_none()
         && !app.pending_confirm_note
@@ -86,7 +93,12 @@ fn submit_interjection_input(app: &mut App, screen: &mut Screen, task: &str) {
     app.llm_stream_preview.clear();
     app.llm_preview_shown.clear();
     app.final_summary = None;
-    let wrapped = build_interjection_user_message(task);
+    let last_output = if std::mem::take(&mut app.interjection_include_output) {
+        last_tool_output(&app.task_events)
+    } else {
+        None
+    };
+    let wrapped = build_interjection_user_message(task, last_output);
     app.messages.push(Message::user(wrapped));
     sync_context_budget(app, screen);
     let ev = Event::UserTask {
@@ -98,7 +110,112 @@ fn submit_interjection_input(app: &mut App, screen: &mut Screen, task: &str) {
     screen.refresh();
 }
 
+/// 当前任务中最近一次工具结果的输出。
+fn last_tool_output(events: &[Event]) -> Option<&str> {
+    events.iter().rev().find_map(|ev| match ev {
+        Event::ToolResult { output, .. } => Some(output.as_str()),
+        _ => None,
+    })
+}
+
+/// 发送前是否扫描输入中的密钥（`GOLDBOT_INPUT_SECRET_GUARD=0` 关闭）。
+fn input_secret_guard_enabled() -> bool {
+    !matches!(
+        std::env::var("GOLDBOT_INPUT_SECRET_GUARD")
+            .unwrap_or_default()
+            .trim(),
+        "0" | "false" | "no" | "off"
+    )
+}
+
 fn try_submit_user_input(app: &mut App, screen: &mut Screen, task: &str) -> anyhow::Result<()> {
+    if input_secret_guard_enabled() {
+        let found = find_secrets(task);
+        if !found.is_empty() {
+            open_secret_confirm(app, screen, task, &secret_kinds(&found));
+            return Ok(());
+        }
+    }
+    dispatch_user_input(app, screen, task)
+}
+
+/// 输入疑似含密钥时打开 仍然发送 / 脱敏 / 取消 确认菜单。
+fn open_secret_confirm(app: &mut App, screen: &mut Screen, task: &str, kinds: &[&str]) {
+    screen.emit(&[format!(
+        "  ⚠ Your message appears to contain a secret ({}). Send anyway / Redact / Cancel",
+        kinds.join(", ")
+    )
+    .dark_yellow()
+    .to_string()]);
+    screen.question_labels = vec![
+        "Send anyway".to_string(),
+        "Redact".to_string(),
+        "Cancel".to_string(),
+    ];
+    screen.confirm_selected = Some(1);
+    screen.input_focused = false;
+    app.pending_secret_input = Some(task.to_string());
+    screen.refresh();
+}
+
+/// 处理密钥确认菜单：0 = 原样发送，1 = 脱敏后发送，其它 = 取消并放回输入框。
+pub(super) fn resolve_secret_confirm(app: &mut App, screen: &mut Screen, choice: usize) {
+    let Some(task) = app.pending_secret_input.take() else {
+        return;
+    };
+    screen.confirm_selected = None;
+    screen.question_labels.clear();
+    screen.input_focused = true;
+    let result = match choice {
+        0 => dispatch_user_input(app, screen, &task),
+        1 => {
+            screen.emit(&["  已将疑似密钥替换为 [REDACTED]。".to_string()]);
+            dispatch_user_input(app, screen, &redact_secrets(&task))
+        }
+        _ => {
+            app.task_display_override = None;
+            screen.insert_at_cursor(&task);
+            screen.emit(&["  已取消发送，内容已放回输入框。".to_string()]);
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        screen.emit(&[format!("  {}", format!("GE error: {e}").red())]);
+    }
+    screen.refresh();
+}
+
+/// `GE timeline [todo-id]` / `GE 时间线 [todo-id]`：返回可选的 todo 过滤条件。
+fn parse_timeline_args(rest: &str) -> Option<Option<&str>> {
+    let args = ["timeline", "时间线"]
+        .iter()
+        .find_map(|cmd| rest.strip_prefix(cmd))?;
+    if !args.is_empty() && !args.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut parts = args.split_whitespace();
+    let todo = parts.next();
+    // 多个词更像是一个 GE 目标，而不是时间线命令
+    parts.next().is_none().then_some(todo)
+}
+
+/// `GE run T005` / `GE 运行 T005`：返回要恢复执行的 todo id（形如 `T` + 数字）。
+fn parse_run_todo_args(rest: &str) -> Option<&str> {
+    let args = ["run", "运行"]
+        .iter()
+        .find_map(|cmd| rest.strip_prefix(cmd))?;
+    if !args.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut parts = args.split_whitespace();
+    let id = parts.next()?;
+    let looks_like_todo =
+        id.len() > 1 && id.starts_with(['T', 't']) && id[1..].chars().all(|c| c.is_ascii_digit());
+    // 其它写法更像是一个 GE 目标
+    (looks_like_todo && parts.next().is_none()).then_some(id)
+}
+
+fn dispatch_user_input(app: &mut App, screen: &mut Screen, task: &str) -> anyhow::Result<()> {
     if let Some(rest) = parse_ge_command(task) {
         let rest = rest.trim();
         if rest == "退出" || rest.eq_ignore_ascii_case("exit") {
@@ -163,6 +280,39 @@ fn try_submit_user_input(app: &mut App, screen: &mut Screen, task: &str) -> anyh
             return Ok(());
         }
 
+        if rest == "差异" || rest.eq_ignore_ascii_case("diff") {
+            if let Some(agent) = app.ge_agent.as_ref() {
+                if !agent.send(crate::consensus::subagent::GeAgentCommand::DiffConsensus) {
+                    app.ge_agent = None;
+                    app.mode = Mode::Normal;
+                    screen.emit(&["  GE channel disconnected.".to_string()]);
+                }
+            } else {
+                screen.emit(&["  GE is not active. Start with `GE <goal>` first.".to_string()]);
+            }
+            return Ok(());
+        }
+
+        if let Some(id) = parse_run_todo_args(rest) {
+            if let Some(agent) = app.ge_agent.as_ref() {
+                let cmd = crate::consensus::subagent::GeAgentCommand::RunTodo(id.to_string());
+                if !agent.send(cmd) {
+                    app.ge_agent = None;
+                    app.mode = Mode::Normal;
+                    screen.emit(&["  GE channel disconnected.".to_string()]);
+                }
+            } else {
+                screen.emit(&["  GE is not active. Start with `GE <goal>` first.".to_string()]);
+            }
+            return Ok(());
+        }
+
+        if let Some(todo_filter) = parse_timeline_args(rest) {
+            let cwd = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+            screen.emit(&crate::consensus::audit::timeline_lines(&cwd, todo_filter));
+            return Ok(());
+        }
+
         if app.running {
             finish(
                 app,
@@ -175,8 +325,9 @@ fn try_submit_user_input(app: &mut App, screen: &mut Screen, task: &str) -> anyh
             let agent = crate::consensus::subagent::GeSubagent::start(cwd, rest)?;
             app.ge_agent = Some(agent);
             drain_ge_events(app, screen);
-            screen.emit(&[String::from(
-                "  GE controls: `q` hard exit | Ctrl+P expand prompt | Ctrl+R expand result.",
+            let compact_key = app.keymap.key_label(KeyAction::ToggleGeView);
+            screen.emit(&[format!(
+                "  GE controls: `q` hard exit | Ctrl+P expand prompt | Ctrl+R expand result | {compact_key} compact view.",
             )]);
         } else {
             screen.emit(&["  GE already active. Use `GE 退出` to leave this mode.".to_string()]);
@@ -215,10 +366,31 @@ fn try_submit_user_input(app: &mut App, screen: &mut Screen, task: &str) -> anyh
         return Ok(());
     }
 
-    crate::agent::executor::start_task(app, screen, task.to_string());
+    // 工具命令要用到 init.sh 导出的环境，脚本报告结果前先不开始
+    if app.init_script_rx.is_some() {
+        let held = match app.task_after_init.take() {
+            Some(held) => format!("{held}\n\n{task}"),
+            None => task.to_string(),
+        };
+        app.task_after_init = Some(held);
+        screen.status = t("init.task_held").dark_yellow().to_string();
+        screen.refresh();
+        return Ok(());
+    }
+
+    start_submitted_task(app, screen, task);
     Ok(())
 }
 
+/// 开始一条新任务：`@claude` / `@codex` 前缀交给外部 executor，其余进入 LLM 循环。
+pub(super) fn start_submitted_task(app: &mut App, screen: &mut Screen, task: &str) {
+    if let Some((executor, delegated)) = parse_executor_prefix(task) {
+        start_external_task(app, screen, executor, delegated, task.to_string());
+        return;
+    }
+    crate::agent::executor::start_task(app, screen, task.to_string());
+}
+
 pub(crate) fn handle_paste(app: &mut App, screen: &mut Screen, pasted: &str) {
     if pasted.is_empty() {
         return;
@@ -1,5 +1,12 @@
+pub mod export;
 pub mod format;
 pub mod ge;
+pub mod i18n;
 pub mod input;
+pub mod pager;
+pub mod palette;
 pub mod screen;
 pub mod symbols;
+pub mod terminal;
+pub mod thinking_filter;
+pub mod transcript;
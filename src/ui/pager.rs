@@ -0,0 +1,117 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+use crate::ui::screen::Screen;
+use crate::ui::terminal::suspend_tui;
+
+/// 设为 1 时，任务结束且工作区有改动就自动在 pager 中打开完整 diff。
+const ENV_FINAL_PAGER: &str = "GOLDBOT_FINAL_PAGER";
+
+#[cfg(windows)]
+const DEFAULT_PAGER: &str = "more";
+#[cfg(not(windows))]
+const DEFAULT_PAGER: &str = "less -R";
+
+pub(crate) fn final_pager_enabled() -> bool {
+    matches!(
+        std::env::var(ENV_FINAL_PAGER).unwrap_or_default().trim(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// 工作区相对 HEAD 的改动；不在 git 仓库或没有改动时返回 None。
+pub(crate) fn workspace_diff(workspace: &Path) -> Option<String> {
+    let inside = git(workspace, &["rev-parse", "--is-inside-work-tree"])?;
+    if inside.trim() != "true" {
+        return None;
+    }
+    // 还没有提交时 HEAD 不存在，退回到工作区 vs 暂存区
+    let diff = git(workspace, &["--no-pager", "diff", "HEAD"])
+        .or_else(|| git(workspace, &["--no-pager", "diff"]))?;
+    (!diff.trim().is_empty()).then_some(diff)
+}
+
+fn git(workspace: &Path, args: &[&str]) -> Option<String> {
+    let out = Command::new("git")
+        .current_dir(workspace)
+        .args(args)
+        .output()
+        .ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// 暂停 TUI，把 diff 写入临时文件后用 `$PAGER`（默认 `less -R`）打开，pager 退出后恢复。
+/// 没有可看的改动时返回 false。
+pub(crate) fn open_diff_in_pager(workspace: &Path, screen: &mut Screen) -> Result<bool> {
+    let Some(diff) = workspace_diff(workspace) else {
+        return Ok(false);
+    };
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let mut parts = pager.split_whitespace();
+    let Some(program) = parts.next() else {
+        bail!("PAGER is empty");
+    };
+    let args: Vec<&str> = parts.collect();
+
+    let path = std::env::temp_dir().join(format!("goldbot-diff-{}.diff", std::process::id()));
+    std::fs::write(&path, diff).with_context(|| format!("failed to write {}", path.display()))?;
+    let status = suspend_tui(screen, || {
+        Command::new(program).args(&args).arg(&path).status()
+    });
+    let _ = std::fs::remove_file(&path);
+    let status = status
+        .context("failed to suspend terminal")?
+        .with_context(|| format!("failed to run pager `{pager}`"))?;
+    if !status.success() {
+        bail!("pager `{pager}` exited with {status}");
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::workspace_diff;
+    use std::process::Command;
+
+    #[test]
+    fn workspace_diff_requires_git_repo_with_changes() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("goldbot-pager-test-{nanos}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(workspace_diff(&dir), None);
+
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .current_dir(&dir)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .map(|o| o.status.success())
+                .unwrap_or(false)
+        };
+        if !git(&["init", "-q"]) {
+            // 没有 git 可用的环境直接跳过
+            let _ = std::fs::remove_dir_all(&dir);
+            return;
+        }
+        std::fs::write(dir.join("a.txt"), "one\n").unwrap();
+        assert!(git(&["add", "a.txt"]));
+        assert!(git(&["commit", "-qm", "init"]));
+        assert_eq!(workspace_diff(&dir), None);
+
+        std::fs::write(dir.join("a.txt"), "two\n").unwrap();
+        let diff = workspace_diff(&dir).expect("diff after edit");
+        assert!(diff.contains("-one") && diff.contains("+two"), "{diff}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
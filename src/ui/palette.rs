@@ -0,0 +1,115 @@
+use std::sync::OnceLock;
+
+use crossterm::style::Color;
+
+/// 配色方案：`deuteranopia`（别名 `protanopia` / `colorblind`）把红/绿换成橙/蓝，其余值为默认配色。
+const ENV_PALETTE: &str = "GOLDBOT_PALETTE";
+
+/// Okabe-Ito 色盲友好色板中的蓝与橙，以及对应的 diff 背景色。
+const CB_BLUE: Color = Color::Rgb {
+    r: 86,
+    g: 180,
+    b: 233,
+};
+const CB_ORANGE: Color = Color::Rgb {
+    r: 230,
+    g: 159,
+    b: 0,
+};
+const CB_BLUE_BG: Color = Color::Rgb {
+    r: 0,
+    g: 70,
+    b: 120,
+};
+const CB_ORANGE_BG: Color = Color::Rgb {
+    r: 130,
+    g: 70,
+    b: 0,
+};
+
+/// 成功/失败与 diff 增删使用的颜色；启动时从 `GOLDBOT_PALETTE` 读取一次。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Palette {
+    Default,
+    Deuteranopia,
+}
+
+impl Palette {
+    pub fn current() -> Self {
+        static CURRENT: OnceLock<Palette> = OnceLock::new();
+        *CURRENT.get_or_init(|| Self::parse(&std::env::var(ENV_PALETTE).unwrap_or_default()))
+    }
+
+    fn parse(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "deuteranopia" | "protanopia" | "colorblind" => Self::Deuteranopia,
+            _ => Self::Default,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Deuteranopia => "deuteranopia",
+        }
+    }
+
+    /// 成功结果与 diff 新增行的前景色。
+    pub fn ok(self) -> Color {
+        match self {
+            Self::Default => Color::Green,
+            Self::Deuteranopia => CB_BLUE,
+        }
+    }
+
+    /// 失败结果与 diff 删除行的前景色。
+    pub fn error(self) -> Color {
+        match self {
+            Self::Default => Color::Red,
+            Self::Deuteranopia => CB_ORANGE,
+        }
+    }
+
+    /// 带行号 diff 中新增行的背景色。
+    pub fn added_bg(self) -> Color {
+        match self {
+            Self::Default => Color::DarkGreen,
+            Self::Deuteranopia => CB_BLUE_BG,
+        }
+    }
+
+    /// 带行号 diff 中删除行的背景色。
+    pub fn removed_bg(self) -> Color {
+        match self {
+            Self::Default => Color::DarkRed,
+            Self::Deuteranopia => CB_ORANGE_BG,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Palette;
+    use crossterm::style::Color;
+
+    #[test]
+    fn colorblind_palette_replaces_red_and_green() {
+        assert_eq!(Palette::parse(""), Palette::Default);
+        assert_eq!(Palette::parse("solarized"), Palette::Default);
+        assert_eq!(Palette::parse(" Deuteranopia "), Palette::Deuteranopia);
+        assert_eq!(Palette::parse("colorblind"), Palette::Deuteranopia);
+
+        assert_eq!(Palette::Default.ok(), Color::Green);
+        assert_eq!(Palette::Default.error(), Color::Red);
+        let cb = Palette::Deuteranopia;
+        for color in [cb.ok(), cb.error(), cb.added_bg(), cb.removed_bg()] {
+            assert!(
+                !matches!(
+                    color,
+                    Color::Red | Color::DarkRed | Color::Green | Color::DarkGreen
+                ),
+                "{color:?}"
+            );
+        }
+    }
+}
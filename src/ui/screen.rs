@@ -3,11 +3,12 @@ use std::io::{self, Write};
 use crossterm::{
     cursor, execute,
     style::{Color, Print, Stylize},
-    terminal::{Clear, ClearType},
+    terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use unicode_width::UnicodeWidthChar;
 
-use crate::types::{AssistMode, TodoItem, TodoStatus};
+use crate::types::{AssistMode, TodoItem, TodoStatus, Verbosity};
+use crate::ui::i18n::t_with;
 use crate::ui::symbols::Symbols;
 
 const TITLE_BORDER_COLOR: Color = Color::Rgb {
@@ -46,6 +47,12 @@ const TITLE_LOGO_GREEN: Color = Color::Rgb {
     b: 171,
 };
 const TITLE_CARD_MAX_INNER_WIDTH: usize = 72;
+/// 独立 Thinking 面板最多显示的正文行数（不含标题行）。
+const THINKING_PANEL_ROWS: usize = 3;
+/// 流式最终总结预览最多显示的正文行数（不含标题行）。
+const FINAL_PREVIEW_ROWS: usize = 12;
+/// 单个逻辑行最多占用的终端行数；超长单行（压缩文件、base64）截断，避免上万行的光标移动卡住界面。
+const MAX_ROWS_PER_LINE: usize = 10;
 
 fn render_title_logo_line(row: usize) -> String {
     match row {
@@ -148,12 +155,22 @@ pub(crate) struct Screen {
     pub input_focused: bool,
     /// When non-empty, the confirm menu renders these labels instead of the hardcoded Execute/Skip/Abort/Add Note.
     pub question_labels: Vec<String>,
+    /// 确认超时倒计时：(剩余秒数, 超时后的默认动作)
+    pub confirm_countdown: Option<(u64, &'static str)>,
     /// Active todo progress panel items.
     pub todo_items: Vec<TodoItem>,
     /// Current Shift+Tab assist mode.
     pub assist_mode: AssistMode,
+    /// Observe mode: tools are simulated, shown next to the mode hint.
+    pub observe: bool,
+    /// Prefix live event lines with an `HH:MM:SS` timestamp.
+    pub timestamps: bool,
+    /// Live event verbosity (Ctrl+O).
+    pub verbosity: Verbosity,
     /// Current workspace path (shown in UI hint bar).
     pub workspace: String,
+    /// 工作区当前的 git 分支（不是 git 仓库或 detached HEAD 时为 None），显示在模式提示行。
+    pub git_branch: Option<String>,
     /// Whether the agent is currently running (shows animated spinner).
     pub is_running: bool,
     /// Spinner animation frame counter, incremented by the main loop.
@@ -181,6 +198,25 @@ pub(crate) struct Screen {
     pub dag_tree: Option<String>,
     /// 排队中的用户消息队列显示（预览文本）
     pub message_queue_labels: Vec<String>,
+    /// Thinking 独立面板布局：开启时 Thinking 只显示在管理区顶部的固定面板，
+    /// 滚动区只保留工具调用/结果与最终总结；关闭时与工具输出交错显示。
+    pub thinking_panel: bool,
+    /// 面板中显示的最新 Thinking（含流式预览）。
+    pub thinking_text: String,
+    /// 流式输出中的 `<final>` 正文；完成后清空，由格式化后的总结取代。
+    pub final_preview: String,
+    /// 输入凭据时开启：输入框中的字符显示为圆点，不回显明文。
+    pub mask_input: bool,
+    /// 全屏转录视图（备用屏幕）打开期间为 true：不绘制管理区，滚动区输出暂存到
+    /// `overlay_deferred`，关闭后按原顺序补上。
+    overlay_open: bool,
+    overlay_deferred: Vec<DeferredOutput>,
+}
+
+/// 转录视图打开期间推迟执行的滚动区输出。
+enum DeferredOutput {
+    Emit(Vec<String>),
+    CollapseTo(Vec<String>),
 }
 
 impl Screen {
@@ -197,9 +233,14 @@ impl Screen {
             confirm_selected: None,
             input_focused: true,
             question_labels: Vec::new(),
+            confirm_countdown: None,
             todo_items: Vec::new(),
             assist_mode: AssistMode::Off,
+            observe: false,
+            timestamps: false,
+            verbosity: Verbosity::from_env(),
             workspace: String::new(),
+            git_branch: None,
             is_running: false,
             spinner_tick: 0,
             input_cursor: 0,
@@ -213,10 +254,17 @@ impl Screen {
             model_picker_sel: 0,
             dag_tree: None,
             message_queue_labels: Vec::new(),
+            thinking_panel: false,
+            thinking_text: String::new(),
+            final_preview: String::new(),
+            mask_input: false,
+            overlay_open: false,
+            overlay_deferred: Vec::new(),
         })
     }
 
-    pub(crate) fn new() -> io::Result<Self> {
+    /// `backend` 为启动横幅显示的后端（已叠加工作区覆盖）。
+    pub(crate) fn new(backend: &crate::agent::provider::LlmBackend) -> io::Result<Self> {
         let mut s = Self {
             stdout: io::stdout(),
             headless: false,
@@ -229,9 +277,14 @@ impl Screen {
             confirm_selected: None,
             input_focused: true,
             question_labels: Vec::new(),
+            confirm_countdown: None,
             todo_items: Vec::new(),
             assist_mode: AssistMode::Off,
+            observe: false,
+            timestamps: false,
+            verbosity: Verbosity::from_env(),
             workspace: String::new(),
+            git_branch: None,
             is_running: false,
             spinner_tick: 0,
             input_cursor: 0,
@@ -245,11 +298,17 @@ impl Screen {
             model_picker_sel: 0,
             dag_tree: None,
             message_queue_labels: Vec::new(),
+            thinking_panel: false,
+            thinking_text: String::new(),
+            final_preview: String::new(),
+            mask_input: false,
+            overlay_open: false,
+            overlay_deferred: Vec::new(),
         };
         let cols = crossterm::terminal::size()
             .map(|(c, _)| c.max(1) as usize)
             .unwrap_or(80);
-        let subtitle_lines = startup_subtitle_lines();
+        let subtitle_lines = startup_subtitle_lines(backend);
         execute!(s.stdout, cursor::MoveToColumn(0), Print("\r\n"))?;
         for line in render_title_banner_lines(cols, &subtitle_lines) {
             execute!(
@@ -273,7 +332,7 @@ impl Screen {
     }
 
     pub(crate) fn clear_managed(&mut self) {
-        if self.headless {
+        if self.headless || self.overlay_open {
             return;
         }
         // 如果光标在 hint 行上方，先移回 hint 行（managed 区底部）
@@ -292,14 +351,20 @@ impl Screen {
     }
 
     pub(crate) fn draw_managed(&mut self) {
-        if self.headless {
+        if self.headless || self.overlay_open {
             return;
         }
         let cols = crossterm::terminal::size()
             .map(|(c, _)| c.max(1) as usize)
             .unwrap_or(80);
 
-        // ── Todo panel (topmost in managed area) ──
+        // ── Thinking panel (topmost in managed area) ──
+        let thinking_rows = self.draw_thinking_panel(cols);
+
+        // ── Streaming final summary ──
+        let final_rows = self.draw_final_preview(cols);
+
+        // ── Todo panel ──
         let todo_rows = self.draw_todo_panel(cols);
 
         // ── Queued input panel ──
@@ -320,15 +385,24 @@ impl Screen {
         if let Some(selected) = self.confirm_selected {
             let sym = Symbols::current();
             let (labels, hint): (&[&str], String) = if self.question_labels.is_empty() {
+                let countdown = self
+                    .confirm_countdown
+                    .map(|(left, action)| {
+                        t_with(
+                            "confirm.countdown",
+                            &[("left", &left.to_string()), ("action", action)],
+                        )
+                    })
+                    .unwrap_or_default();
                 (
                     &["Execute", "Skip", "Abort", "Add Note"],
-                    format!("{} 直接输入补充说明，或 ↑/↓ 选择后 Enter", sym.prompt),
+                    t_with(
+                        "confirm.hint",
+                        &[("prompt", sym.prompt), ("countdown", &countdown)],
+                    ),
                 )
             } else {
-                (
-                    &[],
-                    format!("{} ↑/↓ 选择，Enter 确认，或直接输入自定义内容", sym.prompt),
-                )
+                (&[], t_with("question.hint", &[("prompt", sym.prompt)]))
             };
 
             let display_labels: Vec<String> = if self.question_labels.is_empty() {
@@ -353,7 +427,9 @@ impl Screen {
             }
             let hint = fit_single_line_tail(&hint, cols);
             let _ = execute!(self.stdout, Print(hint.dark_yellow().to_string()));
-            self.managed_lines = todo_rows
+            self.managed_lines = thinking_rows
+                + final_rows
+                + todo_rows
                 + queue_rows
                 + at_file_rows
                 + command_rows
@@ -418,7 +494,12 @@ impl Screen {
             let input_budget = cols.saturating_sub(prompt_width);
             let indent = " ".repeat(prompt_width);
 
-            let input_lines: Vec<&str> = self.input.split('\n').collect();
+            let input = if self.mask_input {
+                masked_input(&self.input, sym.bullet)
+            } else {
+                self.input.clone()
+            };
+            let input_lines: Vec<&str> = input.split('\n').collect();
             let input_row_count = input_lines.len();
 
             for (i, line) in input_lines.iter().enumerate() {
@@ -437,34 +518,11 @@ impl Screen {
                 let _ = execute!(self.stdout, Print(format!("{}\r\n", rendered)));
             }
 
-            let mode_hint = match self.assist_mode {
-                AssistMode::Off => format!(
-                    "  {} {}{}",
-                    sym.arrow_right.dark_grey(),
-                    "mode: agent".grey(),
-                    " (shift+tab to cycle)".grey(),
-                ),
-                AssistMode::Yolo => format!(
-                    "  {} {} {}{}",
-                    format!("{}{}", sym.arrow_right, sym.arrow_right)
-                        .green()
-                        .bold(),
-                    "mode:".grey(),
-                    self.assist_mode.display_name().green().bold(),
-                    " (shift+tab to cycle)".grey(),
-                ),
-                AssistMode::Plan => format!(
-                    "  {} {} {}{}",
-                    format!("{}{}", sym.arrow_right, sym.arrow_right)
-                        .cyan()
-                        .bold(),
-                    "mode:".grey(),
-                    "plan".cyan().bold(),
-                    " (shift+tab to cycle)".grey(),
-                ),
-            };
+            let mode_hint = self.mode_hint_line();
             let _ = execute!(self.stdout, Print(mode_hint));
-            self.managed_lines = todo_rows
+            self.managed_lines = thinking_rows
+                + final_rows
+                + todo_rows
                 + queue_rows
                 + at_file_rows
                 + command_rows
@@ -499,6 +557,50 @@ impl Screen {
         let _ = self.stdout.flush();
     }
 
+    /// Render the thinking panel at the top of the managed area.
+    /// Returns the number of terminal rows consumed.
+    fn draw_thinking_panel(&mut self, cols: usize) -> usize {
+        if !self.thinking_panel || !self.is_running {
+            return 0;
+        }
+        let lines = thinking_panel_lines(&self.thinking_text, cols);
+        for line in &lines {
+            let _ = execute!(self.stdout, Print(format!("{line}\r\n")));
+        }
+        lines.len()
+    }
+
+    /// 面板布局下更新最新 Thinking 并重绘管理区。
+    pub(crate) fn set_thinking(&mut self, text: &str) {
+        if self.thinking_text == text {
+            return;
+        }
+        self.thinking_text = text.to_string();
+        self.refresh();
+    }
+
+    /// 渲染流式中的最终总结（管理区内，随每次重绘原地更新）。
+    /// Returns the number of terminal rows consumed.
+    fn draw_final_preview(&mut self, cols: usize) -> usize {
+        if self.final_preview.is_empty() {
+            return 0;
+        }
+        let lines = final_preview_lines(&self.final_preview, cols);
+        for line in &lines {
+            let _ = execute!(self.stdout, Print(format!("{line}\r\n")));
+        }
+        lines.len()
+    }
+
+    /// 更新流式最终总结预览并重绘管理区。
+    pub(crate) fn set_final_preview(&mut self, text: &str) {
+        if self.final_preview == text {
+            return;
+        }
+        self.final_preview = text.to_string();
+        self.refresh();
+    }
+
     /// Render the todo progress panel above the main status area.
     /// Returns the number of terminal rows consumed.
     fn draw_todo_panel(&mut self, cols: usize) -> usize {
@@ -602,7 +704,11 @@ impl Screen {
 
         let sym = Symbols::current();
         let count = self.at_file_labels.len();
-        let header = format!("  {} 文件 ({count})", sym.arrow_down);
+        let header = format!(
+            "  {} {}",
+            sym.arrow_down,
+            t_with("picker.files", &[("count", &count.to_string())])
+        );
         let _ = execute!(self.stdout, Print(format!("{}\r\n", header.grey())));
 
         let max_visible = 8.min(count);
@@ -711,6 +817,12 @@ impl Screen {
         if self.headless {
             return;
         }
+        if self.overlay_open {
+            self.overlay_deferred
+                .push(DeferredOutput::Emit(lines.to_vec()));
+            return;
+        }
+        let lines = &self.capped(lines);
         self.task_lines += lines.iter().map(|l| self.rendered_rows(l)).sum::<usize>();
         self.task_rendered.extend(lines.iter().cloned());
         self.clear_managed();
@@ -720,6 +832,53 @@ impl Screen {
         self.draw_managed();
     }
 
+    /// 管理区最底部的提示行：协助模式、观察模式标记与当前 git 分支。
+    pub(crate) fn mode_hint_line(&self) -> String {
+        let sym = Symbols::current();
+        let mode_hint = match self.assist_mode {
+            AssistMode::Off => format!(
+                "  {} {}{}",
+                sym.arrow_right.dark_grey(),
+                "mode: agent".grey(),
+                " (shift+tab to cycle)".grey(),
+            ),
+            AssistMode::Yolo => format!(
+                "  {} {} {}{}",
+                format!("{}{}", sym.arrow_right, sym.arrow_right)
+                    .green()
+                    .bold(),
+                "mode:".grey(),
+                self.assist_mode.display_name().green().bold(),
+                " (shift+tab to cycle)".grey(),
+            ),
+            AssistMode::Plan => format!(
+                "  {} {} {}{}",
+                format!("{}{}", sym.arrow_right, sym.arrow_right)
+                    .cyan()
+                    .bold(),
+                "mode:".grey(),
+                "plan".cyan().bold(),
+                " (shift+tab to cycle)".grey(),
+            ),
+        };
+        let mode_hint = if self.observe {
+            format!(
+                "{mode_hint}  {}",
+                "[observe: nothing executes]".yellow().bold()
+            )
+        } else {
+            mode_hint
+        };
+        match &self.git_branch {
+            Some(branch) => format!(
+                "{mode_hint}  {} {}",
+                "branch:".grey(),
+                branch.as_str().cyan()
+            ),
+            None => mode_hint,
+        }
+    }
+
     pub(crate) fn refresh(&mut self) {
         if self.headless {
             return;
@@ -747,14 +906,36 @@ impl Screen {
         self.draw_managed();
     }
 
+    /// 整屏清空后从头重绘 `lines` 和底部管理区，用于光标错位后的手动恢复。
+    /// 与 `clear_screen` 不同，任务记录会以 `lines` 重新建立而不是丢弃。
+    pub(crate) fn redraw(&mut self, lines: &[String]) {
+        let lines = &self.capped(lines);
+        self.task_lines = 0;
+        self.task_rendered.clear();
+        self.task_rendered.extend(lines.iter().cloned());
+        if self.headless {
+            return;
+        }
+        self.managed_lines = 0;
+        self.cursor_rows_above_hint = 0;
+        let _ = execute!(self.stdout, Clear(ClearType::All), cursor::MoveTo(0, 0));
+        for line in lines {
+            let _ = execute!(self.stdout, Print(format!("{}\r\n", line)));
+            self.task_lines += self.rendered_rows(line);
+        }
+        self.draw_managed();
+    }
+
     /// 只原地刷新状态行，不动输入栏和 hint 行，避免光标在两行之间跳动。
     /// 仅适用于 spinner 跳帧和思考预览更新场景。
     /// 若行数发生变化或处于确认/todo 界面，则回退到完整 refresh()。
     pub(crate) fn refresh_status_only(&mut self) {
-        if self.headless {
+        if self.headless || self.overlay_open {
             return;
         }
         if self.confirm_selected.is_some()
+            || (self.thinking_panel && !self.thinking_text.is_empty())
+            || !self.final_preview.is_empty()
             || !self.todo_items.is_empty()
             || !self.message_queue_labels.is_empty()
             || !self.at_file_labels.is_empty()
@@ -846,19 +1027,34 @@ impl Screen {
         self.task_rendered.clear();
     }
 
+    /// 按当前终端宽度截断超长行，见 [`cap_line_width`]。
+    fn capped(&self, lines: &[String]) -> Vec<String> {
+        let cols = crossterm::terminal::size()
+            .map(|(c, _)| c.max(1) as usize)
+            .unwrap_or(80);
+        lines
+            .iter()
+            .map(|line| cap_line_width(line, cols))
+            .collect()
+    }
+
     pub(crate) fn rendered_rows(&self, line: &str) -> usize {
         let cols = crossterm::terminal::size()
             .map(|(c, _)| c.max(1) as usize)
             .unwrap_or(80);
-        let plain = strip_ansi(line);
-        let width = rendered_text_width(plain.as_str());
-        width.saturating_sub(1) / cols + 1
+        rows_for_width(line, cols)
     }
 
     pub(crate) fn collapse_to(&mut self, kept: &[String]) {
         if self.headless {
             return;
         }
+        if self.overlay_open {
+            self.overlay_deferred
+                .push(DeferredOutput::CollapseTo(kept.to_vec()));
+            return;
+        }
+        let kept = &self.capped(kept);
         let rendered_rows = self
             .task_rendered
             .iter()
@@ -883,6 +1079,41 @@ impl Screen {
         self.draw_managed();
     }
 
+    /// 在备用屏幕上整屏绘制全屏视图（首次调用时切换过去），原滚动区与管理区保持不动。
+    pub(crate) fn draw_overlay(&mut self, lines: &[String]) {
+        if self.headless {
+            return;
+        }
+        if !self.overlay_open {
+            self.clear_managed();
+            let _ = execute!(self.stdout, EnterAlternateScreen);
+            self.overlay_open = true;
+        }
+        let _ = execute!(self.stdout, Clear(ClearType::All), cursor::MoveTo(0, 0));
+        let last = lines.len().saturating_sub(1);
+        for (i, line) in lines.iter().enumerate() {
+            let end = if i == last { "" } else { "\r\n" };
+            let _ = execute!(self.stdout, Print(format!("{line}{end}")));
+        }
+        let _ = self.stdout.flush();
+    }
+
+    /// 离开备用屏幕，补上期间推迟的输出并重绘管理区。
+    pub(crate) fn close_overlay(&mut self) {
+        if !self.overlay_open {
+            return;
+        }
+        let _ = execute!(self.stdout, LeaveAlternateScreen);
+        self.overlay_open = false;
+        self.draw_managed();
+        for output in std::mem::take(&mut self.overlay_deferred) {
+            match output {
+                DeferredOutput::Emit(lines) => self.emit(&lines),
+                DeferredOutput::CollapseTo(kept) => self.collapse_to(&kept),
+            }
+        }
+    }
+
     // ── Cursor helper methods ────────────────────────────────────────────────
 
     /// Move cursor left by one char. Returns true if moved.
@@ -1021,7 +1252,12 @@ impl Screen {
         let before = &self.input[..self.input_cursor];
         let row = before.matches('\n').count();
         let last_nl = before.rfind('\n').map(|p| p + 1).unwrap_or(0);
-        let col = rendered_text_width(&self.input[last_nl..self.input_cursor]);
+        let line = &self.input[last_nl..self.input_cursor];
+        let col = if self.mask_input {
+            line.chars().count() * rendered_text_width(Symbols::current().bullet)
+        } else {
+            rendered_text_width(line)
+        };
         (row, col)
     }
 
@@ -1074,19 +1310,20 @@ impl Screen {
     }
 }
 
-fn startup_subtitle_lines() -> Vec<String> {
+fn startup_subtitle_lines(backend: &crate::agent::provider::LlmBackend) -> Vec<String> {
     let version = env!("CARGO_PKG_VERSION");
-    let (model, base_url) = crate::agent::provider::LlmBackend::from_env().display_info();
+    let (model, base_url) = backend.display_info();
     let provider = extract_host_from_url(&base_url).unwrap_or(base_url);
     let cwd = std::env::current_dir()
         .map(|p| p.to_string_lossy().replace('\\', "/"))
         .unwrap_or_else(|_| ".".to_string());
 
-    vec![
-        format!("GoldBot v{version}"),
-        format!("{model} · {provider}"),
-        cwd,
-    ]
+    let title = if crate::agent::executor::safe_mode_from_env() {
+        format!("GoldBot v{version} · SAFE MODE")
+    } else {
+        format!("GoldBot v{version}")
+    };
+    vec![title, format!("{model} · {provider}"), cwd]
 }
 
 fn extract_host_from_url(url: &str) -> Option<String> {
@@ -1118,6 +1355,12 @@ pub(crate) fn strip_ansi(s: &str) -> String {
     out
 }
 
+/// 一行（含 ANSI 样式与时间戳前缀）在 `cols` 列宽终端上折行后占用的行数。
+pub(crate) fn rows_for_width(line: &str, cols: usize) -> usize {
+    let width = rendered_text_width(strip_ansi(line).as_str());
+    width.saturating_sub(1) / cols.max(1) + 1
+}
+
 pub(crate) fn rendered_text_width(s: &str) -> usize {
     const TAB_STOP: usize = 8;
     let mut col = 0usize;
@@ -1135,6 +1378,57 @@ pub(crate) fn rendered_text_width(s: &str) -> usize {
     col
 }
 
+/// 显示宽度超过 `MAX_ROWS_PER_LINE` 个终端行的单行截断到该范围内，末尾标注原长度。
+/// 保留 ANSI 样式序列；只影响屏幕显示，发给 LLM 的内容不经过这里。
+pub(crate) fn cap_line_width(line: &str, cols: usize) -> String {
+    let max_width = MAX_ROWS_PER_LINE * cols.max(1);
+    let plain = strip_ansi(line);
+    if rendered_text_width(&plain) <= max_width {
+        return line.to_string();
+    }
+    let marker = format!(
+        " {} {}",
+        Symbols::current().ellipsis,
+        t_with(
+            "screen.line_truncated",
+            &[("count", &plain.chars().count().to_string())]
+        )
+    );
+    let budget = max_width.saturating_sub(rendered_text_width(&marker));
+    let mut out = String::new();
+    let mut used = 0usize;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '\u{1b}' && matches!(chars.peek(), Some('[')) {
+            out.push(ch);
+            out.extend(chars.next());
+            for c in chars.by_ref() {
+                out.push(c);
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+            continue;
+        }
+        let w = rendered_text_width(ch.encode_utf8(&mut [0; 4]));
+        if used + w > budget {
+            break;
+        }
+        out.push(ch);
+        used += w;
+    }
+    out.push_str("\u{1b}[0m");
+    out.push_str(&marker.dark_grey().to_string());
+    out
+}
+
+/// 凭据输入的遮罩：每个字符显示为一个 `mask`，保留换行以便光标行号一致。
+pub(crate) fn masked_input(text: &str, mask: &str) -> String {
+    text.chars()
+        .map(|c| if c == '\n' { "\n" } else { mask })
+        .collect()
+}
+
 pub(crate) fn fit_single_line_tail(s: &str, max_width: usize) -> String {
     if max_width == 0 {
         return String::new();
@@ -1244,6 +1538,47 @@ pub(crate) fn split_tail_lines_by_width(
     tail
 }
 
+/// Thinking 面板的各行：标题 + 最近几行折行后的正文，每行都不超过终端宽度，
+/// 保证 `managed_lines` 与实际占用的终端行数一致（collapse 回卷依赖这一点）。
+pub(crate) fn thinking_panel_lines(text: &str, cols: usize) -> Vec<String> {
+    let body = split_tail_lines_by_width(
+        text.trim(),
+        cols.saturating_sub(rendered_text_width("    ")),
+        THINKING_PANEL_ROWS,
+    );
+    if body.is_empty() {
+        return Vec::new();
+    }
+    let sym = Symbols::current();
+    let header = fit_single_line_tail(&format!("  {} Thinking", sym.arrow_down), cols);
+    let mut lines = vec![header.grey().to_string()];
+    lines.extend(
+        body.iter()
+            .map(|line| format!("    {}", strip_ansi(line)).dark_grey().to_string()),
+    );
+    lines
+}
+
+/// 流式最终总结预览的各行：标题 + 最近几行折行后的正文，每行不超过终端宽度。
+pub(crate) fn final_preview_lines(text: &str, cols: usize) -> Vec<String> {
+    let body = split_tail_lines_by_width(
+        text.trim(),
+        cols.saturating_sub(rendered_text_width("    ")),
+        FINAL_PREVIEW_ROWS,
+    );
+    if body.is_empty() {
+        return Vec::new();
+    }
+    let sym = Symbols::current();
+    let header = fit_single_line_tail(&format!("  {} Final {}", sym.record, sym.ellipsis), cols);
+    let mut lines = vec![header.bold().to_string()];
+    lines.extend(
+        body.iter()
+            .map(|line| format!("    {}", strip_ansi(line)).white().to_string()),
+    );
+    lines
+}
+
 fn compose_status_lines(
     status_left: &str,
     status_right: &str,
@@ -1333,14 +1668,47 @@ pub(crate) fn format_mcp_status_line(ok: &[(String, usize)], failed: &[String])
         .map(|(name, _)| name.as_str().dark_cyan().to_string())
         .collect();
     for name in failed {
-        parts.push(name.as_str().red().to_string());
+        parts.push(
+            format!("{} {name}", Symbols::current().fail)
+                .with(crate::ui::palette::Palette::current().error())
+                .to_string(),
+        );
     }
     Some(format!("  {}{}", "MCP  ".grey(), parts.join(&sep)))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{render_title_banner_lines, strip_ansi};
+    use crossterm::style::Stylize;
+
+    use super::{
+        MAX_ROWS_PER_LINE, THINKING_PANEL_ROWS, cap_line_width, render_title_banner_lines,
+        rendered_text_width, rows_for_width, strip_ansi, thinking_panel_lines,
+    };
+
+    #[test]
+    fn thinking_panel_lines_each_fit_one_terminal_row() {
+        assert!(thinking_panel_lines("   \n ", 80).is_empty());
+
+        let text = format!(
+            "first idea\n{}\n检查一下配置文件里的超时设置是否生效，然后再决定下一步要运行的命令",
+            "x".repeat(200)
+        );
+        for cols in [20, 37, 80] {
+            let lines = thinking_panel_lines(&text, cols);
+            // 标题 + 最多 THINKING_PANEL_ROWS 行正文
+            assert_eq!(lines.len(), 1 + THINKING_PANEL_ROWS, "cols={cols}");
+            for line in &lines {
+                let width = rendered_text_width(&strip_ansi(line));
+                assert!(width <= cols, "cols={cols} width={width}: {line}");
+            }
+        }
+        // 面板只保留最新的内容
+        let lines = thinking_panel_lines(&text, 80);
+        assert!(strip_ansi(lines.last().unwrap()).contains("要运行的命令"));
+        assert!(!lines.iter().any(|l| strip_ansi(l).contains("first idea")));
+        assert_eq!(thinking_panel_lines("short", 80).len(), 2);
+    }
 
     #[test]
     fn title_banner_renders_frame() {
@@ -1377,4 +1745,20 @@ mod tests {
         assert!(body[2].contains("glm-5.1 · open.bigmodel.cn"));
         assert!(body[3].contains("E:/GoldBot"));
     }
+
+    #[test]
+    fn huge_single_line_renders_within_bounded_rows() {
+        let blob = "QUJD".repeat(25_000);
+        let line = format!("    {}", blob).grey().to_string();
+        let capped = cap_line_width(&line, 80);
+        let rows = rows_for_width(&capped, 80);
+        assert!(rows <= MAX_ROWS_PER_LINE, "{rows} rows");
+        let plain = strip_ansi(&capped);
+        assert!(plain.starts_with("    QUJDQUJD"));
+        assert!(plain.contains("100004"), "{plain}");
+
+        // 正常长度的行原样保留
+        let short = "  ✓ ok".green().to_string();
+        assert_eq!(cap_line_width(&short, 80), short);
+    }
 }
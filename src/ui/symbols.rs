@@ -12,6 +12,9 @@ pub(crate) struct Symbols {
     pub corner: &'static str,
     pub bullet: &'static str,
     pub warning: &'static str,
+    /// 工具结果成功/失败标记，让结果不只靠颜色区分。
+    pub ok: &'static str,
+    pub fail: &'static str,
 }
 
 impl Symbols {
@@ -43,6 +46,8 @@ const UNICODE_SYMBOLS: Symbols = Symbols {
     corner: "⎿",
     bullet: "•",
     warning: "⚠",
+    ok: "✓",
+    fail: "✗",
 };
 
 const ASCII_SYMBOLS: Symbols = Symbols {
@@ -59,4 +64,6 @@ const ASCII_SYMBOLS: Symbols = Symbols {
     corner: "\\",
     bullet: "*",
     warning: "!",
+    ok: "v",
+    fail: "x",
 };
@@ -0,0 +1,109 @@
+use std::io;
+
+use crossterm::{
+    cursor,
+    event::{DisableBracketedPaste, EnableBracketedPaste},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
+
+use crate::ui::screen::Screen;
+
+/// TUI 占用终端时设置的模式（raw mode、bracketed paste、隐藏光标）。
+pub(crate) trait TerminalModes {
+    /// 交还终端：恢复成普通的行缓冲模式，供外部程序（pager、编辑器）使用。
+    fn leave(&mut self) -> io::Result<()>;
+    /// 重新接管终端。
+    fn enter(&mut self) -> io::Result<()>;
+}
+
+/// 真实终端。
+pub(crate) struct Crossterm;
+
+impl TerminalModes for Crossterm {
+    fn leave(&mut self) -> io::Result<()> {
+        execute!(io::stdout(), DisableBracketedPaste, cursor::Show)?;
+        disable_raw_mode()
+    }
+
+    fn enter(&mut self) -> io::Result<()> {
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnableBracketedPaste, cursor::Hide)
+    }
+}
+
+/// 先交还终端再执行 `run`，结束后无论结果如何都重新接管。
+/// 交还失败时尽量恢复原状并返回错误，不执行 `run`。
+pub(crate) fn with_suspended<M: TerminalModes, T>(
+    modes: &mut M,
+    run: impl FnOnce() -> T,
+) -> io::Result<T> {
+    if let Err(e) = modes.leave() {
+        let _ = modes.enter();
+        return Err(e);
+    }
+    let out = run();
+    modes.enter()?;
+    Ok(out)
+}
+
+/// 暂停 TUI 运行外部全屏程序，返回后重绘底部管理区。headless 模式不调用外部程序。
+pub(crate) fn suspend_tui<T>(screen: &mut Screen, run: impl FnOnce() -> T) -> io::Result<T> {
+    if screen.headless {
+        return Err(io::Error::other("terminal is not interactive"));
+    }
+    screen.clear_managed();
+    let out = with_suspended(&mut Crossterm, run);
+    screen.draw_managed();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TerminalModes, with_suspended};
+    use std::io;
+
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<&'static str>,
+        fail_leave: bool,
+    }
+
+    impl TerminalModes for Recorder {
+        fn leave(&mut self) -> io::Result<()> {
+            self.calls.push("leave");
+            if self.fail_leave {
+                return Err(io::Error::other("no tty"));
+            }
+            Ok(())
+        }
+
+        fn enter(&mut self) -> io::Result<()> {
+            self.calls.push("enter");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn suspend_brackets_the_external_program() {
+        let mut modes = Recorder::default();
+        let mut ran = false;
+        let out = with_suspended(&mut modes, || {
+            ran = true;
+            7
+        })
+        .unwrap();
+        assert!(ran);
+        assert_eq!(out, 7);
+        assert_eq!(modes.calls, ["leave", "enter"]);
+
+        let mut modes = Recorder {
+            fail_leave: true,
+            ..Default::default()
+        };
+        let mut ran = false;
+        assert!(with_suspended(&mut modes, || ran = true).is_err());
+        assert!(!ran);
+        assert_eq!(modes.calls, ["leave", "enter"]);
+    }
+}
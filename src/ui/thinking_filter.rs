@@ -0,0 +1,148 @@
+use std::borrow::Cow;
+use std::sync::{OnceLock, RwLock};
+
+use regex::Regex;
+
+/// Thinking 显示过滤：`concise` 启用内置的客套话启发式，其余为以 `;` 分隔的正则，
+/// 可混用（如 `concise;^Hmm`）。匹配的行只在显示时隐藏，上下文中的完整 Thinking 不变。
+const ENV_THINKING_FILTER: &str = "GOLDBOT_THINKING_FILTER";
+
+/// 只有语气词、没有内容的行（如 "Okay."、"Hmm..."、"好的。"）。
+const FILLER_ONLY: &str = r"(?i)^(ok(ay)?|alright|all right|hmm+|um+|well|so|right|sure|got it|好的?|好吧|嗯+|那么)[\s.,!?…。，！？~-]*$";
+/// 只是在说"我要开始想了"的开场白。
+const FILLER_OPENER: &str = r"(?i)^(let me (think|see|consider|figure)|let's (think|see)|i need to think|thinking about|wait[,.!]|让我(想想|思考|看看)|我(想想|来想想|思考一下)|思考一下)";
+/// 以省略号收尾、不超过这么多词的短句视为话说一半的过渡句（如 "First I'll..."）。
+const DANGLING_MAX_WORDS: usize = 6;
+/// 中文过渡句没有空格分词，按字符数判断。
+const DANGLING_MAX_CHARS: usize = 12;
+
+#[derive(Debug, Clone, Default)]
+struct ThinkingFilter {
+    concise: bool,
+    patterns: Vec<Regex>,
+}
+
+impl ThinkingFilter {
+    /// 无法编译的正则直接忽略，不影响其余规则。
+    fn parse(raw: &str) -> Self {
+        let mut filter = Self::default();
+        for item in raw.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+            if item.eq_ignore_ascii_case("concise") {
+                filter.concise = true;
+            } else if let Ok(re) = Regex::new(item) {
+                filter.patterns.push(re);
+            }
+        }
+        filter
+    }
+
+    fn is_active(&self) -> bool {
+        self.concise || !self.patterns.is_empty()
+    }
+
+    fn hides(&self, line: &str) -> bool {
+        let line = line.trim();
+        if line.is_empty() {
+            return false;
+        }
+        (self.concise && is_filler(line)) || self.patterns.iter().any(|re| re.is_match(line))
+    }
+
+    fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if !self.is_active() {
+            return Cow::Borrowed(text);
+        }
+        let kept: Vec<&str> = text.lines().filter(|line| !self.hides(line)).collect();
+        // 只剩空行时整段视为客套话
+        if kept.iter().all(|line| line.trim().is_empty()) {
+            return Cow::Borrowed("");
+        }
+        Cow::Owned(kept.join("\n"))
+    }
+}
+
+fn is_filler(line: &str) -> bool {
+    static RULES: OnceLock<(Regex, Regex)> = OnceLock::new();
+    let (only, opener) = RULES.get_or_init(|| {
+        (
+            Regex::new(FILLER_ONLY).expect("valid filler regex"),
+            Regex::new(FILLER_OPENER).expect("valid opener regex"),
+        )
+    });
+    if only.is_match(line) || opener.is_match(line) {
+        return true;
+    }
+    let body = line.trim_end_matches(['.', '…', '。']);
+    let dangling = body.len() < line.len() && (line.ends_with("...") || line.ends_with('…'));
+    dangling
+        && if body.is_ascii() {
+            body.split_whitespace().count() <= DANGLING_MAX_WORDS
+        } else {
+            body.chars().count() <= DANGLING_MAX_CHARS
+        }
+}
+
+fn state() -> &'static RwLock<ThinkingFilter> {
+    static STATE: OnceLock<RwLock<ThinkingFilter>> = OnceLock::new();
+    STATE.get_or_init(|| {
+        RwLock::new(ThinkingFilter::parse(
+            &std::env::var(ENV_THINKING_FILTER).unwrap_or_default(),
+        ))
+    })
+}
+
+/// 按当前过滤规则去掉 Thinking 中的客套行；全部被过滤时返回空串。
+pub(crate) fn filter_thinking(text: &str) -> Cow<'_, str> {
+    match state().read() {
+        Ok(filter) => filter.apply(text),
+        Err(_) => Cow::Borrowed(text),
+    }
+}
+
+/// `/thinking concise`：切换内置启发式，自定义正则保持不变；返回切换后的状态。
+pub(crate) fn toggle_concise() -> bool {
+    let mut filter = state().write().unwrap_or_else(|e| e.into_inner());
+    filter.concise = !filter.concise;
+    filter.concise
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ThinkingFilter;
+
+    #[test]
+    fn concise_filter_drops_filler_and_keeps_substantive_lines() {
+        let filter = ThinkingFilter::parse("concise");
+        let text = "Okay.\n\
+                    Let me think about this...\n\
+                    First I'll...\n\
+                    The timeout comes from `REQUEST_TIMEOUT` in config.rs, so raising it fixes the flake.\n\
+                    Hmm\n\
+                    让我想想。\n\
+                    测试失败是因为缓存目录没有清理。\n\
+                    Let me check src/main.rs for where the parser is built.";
+        let shown = filter.apply(text);
+        assert_eq!(
+            shown,
+            "The timeout comes from `REQUEST_TIMEOUT` in config.rs, so raising it fixes the flake.\n\
+             测试失败是因为缓存目录没有清理。\n\
+             Let me check src/main.rs for where the parser is built."
+        );
+
+        // 全是客套话时什么都不显示
+        assert_eq!(filter.apply("Alright...\nLet's see."), "");
+        // 未启用时原样返回
+        assert_eq!(ThinkingFilter::parse("").apply(text), text);
+    }
+
+    #[test]
+    fn custom_patterns_combine_with_concise_and_skip_invalid_regexes() {
+        let filter = ThinkingFilter::parse(" concise ; ^Double-check ; ([ ");
+        assert!(filter.concise);
+        assert_eq!(filter.patterns.len(), 1);
+        assert_eq!(
+            filter.apply("Double-check the flags.\nUse --locked in CI.\nOkay!"),
+            "Use --locked in CI."
+        );
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::VecDeque;
+
+use crossterm::style::Stylize;
+
+use crate::types::Event;
+use crate::ui::format::format_event;
+use crate::ui::i18n::{t, t_with};
+use crate::ui::screen::{
+    fit_single_line_tail, rendered_text_width, split_tail_lines_by_width, strip_ansi,
+};
+
+/// 本会话转录环最多保留的事件数（跨任务累计），超出后丢弃最早的。
+const TRANSCRIPT_RING_MAX_EVENTS: usize = 5000;
+
+/// 新任务开始时把上一任务的事件移入本会话的转录环。
+pub(crate) fn archive_task_events(ring: &mut VecDeque<Event>, events: &mut Vec<Event>) {
+    ring.extend(events.drain(..));
+    let excess = ring.len().saturating_sub(TRANSCRIPT_RING_MAX_EVENTS);
+    ring.drain(..excess);
+}
+
+/// 全屏转录视图：事件按 `format_event` 渲染后按终端宽度折行，视口可滚动、可搜索。
+/// 打开时生成快照，不改动 `task_events` 与滚动区。
+pub(crate) struct TranscriptView {
+    rows: Vec<String>,
+    /// 每个事件行（折行前）的首个显示行下标与小写纯文本，搜索按整行匹配。
+    lines: Vec<(usize, String)>,
+    /// 视口首行；可能超出末页，使用前经 `top()` 收紧。
+    top: usize,
+    /// `/` 之后正在输入的搜索词；None 表示不在输入状态。
+    query: Option<String>,
+    search: String,
+    /// 命中行的显示行下标（升序）。
+    matches: Vec<usize>,
+    current: usize,
+}
+
+impl TranscriptView {
+    /// 打开时停在末页，与滚动区最后看到的内容衔接。
+    pub(crate) fn new<'a>(events: impl IntoIterator<Item = &'a Event>, cols: usize) -> Self {
+        let mut rows = Vec::new();
+        let mut lines = Vec::new();
+        for line in events.into_iter().flat_map(format_event) {
+            lines.push((rows.len(), strip_ansi(&line).to_lowercase()));
+            let wrapped = split_tail_lines_by_width(&line, cols.max(1), usize::MAX);
+            if wrapped.is_empty() {
+                rows.push(String::new());
+            } else {
+                rows.extend(wrapped);
+            }
+        }
+        Self {
+            rows,
+            lines,
+            top: usize::MAX,
+            query: None,
+            search: String::new(),
+            matches: Vec::new(),
+            current: 0,
+        }
+    }
+
+    fn max_top(&self, height: usize) -> usize {
+        self.rows.len().saturating_sub(height.max(1))
+    }
+
+    pub(crate) fn top(&self, height: usize) -> usize {
+        self.top.min(self.max_top(height))
+    }
+
+    pub(crate) fn scroll_by(&mut self, delta: isize, height: usize) {
+        let top = self.top(height).saturating_add_signed(delta);
+        self.top = top.min(self.max_top(height));
+    }
+
+    pub(crate) fn page(&mut self, pages: isize, height: usize) {
+        self.scroll_by(pages.saturating_mul(height.max(1) as isize), height);
+    }
+
+    pub(crate) fn home(&mut self) {
+        self.top = 0;
+    }
+
+    pub(crate) fn end(&mut self, height: usize) {
+        self.top = self.max_top(height);
+    }
+
+    pub(crate) fn is_typing_query(&self) -> bool {
+        self.query.is_some()
+    }
+
+    pub(crate) fn begin_query(&mut self) {
+        self.query = Some(String::new());
+    }
+
+    pub(crate) fn push_query(&mut self, c: char) {
+        if let Some(query) = self.query.as_mut() {
+            query.push(c);
+        }
+    }
+
+    pub(crate) fn pop_query(&mut self) {
+        if let Some(query) = self.query.as_mut() {
+            query.pop();
+        }
+    }
+
+    pub(crate) fn cancel_query(&mut self) {
+        self.query = None;
+    }
+
+    /// 提交输入中的搜索词；空词沿用上一次的搜索。
+    pub(crate) fn submit_query(&mut self, height: usize) {
+        let Some(query) = self.query.take() else {
+            return;
+        };
+        let term = if query.is_empty() {
+            self.search.clone()
+        } else {
+            query
+        };
+        self.search_for(&term, height);
+    }
+
+    /// 不区分大小写地搜索，跳到视口首行及之后的第一个命中（没有则回绕到第一个）。
+    pub(crate) fn search_for(&mut self, term: &str, height: usize) {
+        self.search = term.to_string();
+        let needle = term.to_lowercase();
+        self.matches = if needle.is_empty() {
+            Vec::new()
+        } else {
+            self.lines
+                .iter()
+                .filter(|(_, text)| text.contains(&needle))
+                .map(|(row, _)| *row)
+                .collect()
+        };
+        let top = self.top(height);
+        self.current = self.matches.iter().position(|&r| r >= top).unwrap_or(0);
+        self.reveal_current(height);
+    }
+
+    pub(crate) fn next_match(&mut self, height: usize) {
+        if self.matches.is_empty() {
+            return;
+        }
+        self.current = (self.current + 1) % self.matches.len();
+        self.reveal_current(height);
+    }
+
+    pub(crate) fn prev_match(&mut self, height: usize) {
+        if self.matches.is_empty() {
+            return;
+        }
+        self.current = (self.current + self.matches.len() - 1) % self.matches.len();
+        self.reveal_current(height);
+    }
+
+    /// 当前命中不在视口内时滚动到以它为首行。
+    fn reveal_current(&mut self, height: usize) {
+        let Some(&row) = self.matches.get(self.current) else {
+            return;
+        };
+        let top = self.top(height);
+        if row < top || row >= top + height.max(1) {
+            self.top = row.min(self.max_top(height));
+        }
+    }
+
+    /// 整屏内容：`height` 行正文（当前命中行反色），最后一行为状态栏。
+    pub(crate) fn render(&self, height: usize, cols: usize) -> Vec<String> {
+        let height = height.max(1);
+        let top = self.top(height);
+        let end = (top + height).min(self.rows.len());
+        let current = self.matches.get(self.current).copied();
+        let mut out: Vec<String> = (top..end)
+            .map(|row| {
+                if Some(row) == current {
+                    strip_ansi(&self.rows[row]).reverse().to_string()
+                } else {
+                    self.rows[row].clone()
+                }
+            })
+            .collect();
+        if self.rows.is_empty() {
+            out.push(format!("  {}", t("transcript.empty")).grey().to_string());
+        }
+        out.resize(height, String::new());
+        out.push(
+            fit_single_line_tail(&self.status_line(top, end, cols), cols)
+                .reverse()
+                .to_string(),
+        );
+        out
+    }
+
+    /// 位置与搜索状态优先；放不下时省略按键提示。
+    fn status_line(&self, top: usize, end: usize, cols: usize) -> String {
+        if let Some(query) = &self.query {
+            return format!("/{query}");
+        }
+        let position = t_with(
+            "transcript.position",
+            &[
+                ("from", &(top + 1).min(end).to_string()),
+                ("to", &end.to_string()),
+                ("total", &self.rows.len().to_string()),
+            ],
+        );
+        let search = if self.search.is_empty() {
+            String::new()
+        } else if self.matches.is_empty() {
+            format!(
+                "  {}",
+                t_with("transcript.no_match", &[("term", &self.search)])
+            )
+        } else {
+            format!(
+                "  {}",
+                t_with(
+                    "transcript.match",
+                    &[
+                        ("current", &(self.current + 1).to_string()),
+                        ("count", &self.matches.len().to_string()),
+                        ("term", &self.search),
+                    ],
+                )
+            )
+        };
+        let status = format!(" {position}{search}");
+        let with_keys = format!("{status}  {}", t("transcript.keys"));
+        if rendered_text_width(&with_keys) <= cols {
+            with_keys
+        } else {
+            status
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::{TRANSCRIPT_RING_MAX_EVENTS, TranscriptView, archive_task_events};
+    use crate::types::Event;
+
+    fn numbered(count: usize) -> Vec<Event> {
+        (0..count)
+            .map(|i| Event::PhaseSummary {
+                text: format!("step {i}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn viewport_scroll_is_clamped_to_the_content() {
+        let events = numbered(30);
+        let mut view = TranscriptView::new(&events, 80);
+        // 打开时停在末页
+        assert_eq!(view.top(10), 20);
+        view.scroll_by(5, 10);
+        assert_eq!(view.top(10), 20);
+        view.scroll_by(-3, 10);
+        assert_eq!(view.top(10), 17);
+        view.page(-1, 10);
+        assert_eq!(view.top(10), 7);
+        view.page(-1, 10);
+        assert_eq!(view.top(10), 0);
+        view.page(1, 10);
+        assert_eq!(view.top(10), 10);
+        view.end(10);
+        assert_eq!(view.top(10), 20);
+        view.home();
+        assert_eq!(view.top(10), 0);
+
+        // 视口比内容高时只有一页
+        assert_eq!(TranscriptView::new(&events[..3], 80).top(10), 0);
+        let page = view.render(10, 80);
+        assert_eq!(page.len(), 11);
+        assert!(crate::ui::screen::strip_ansi(&page[10]).contains("1-10/30"));
+
+        // 长行按宽度折行，每个显示行单独滚动
+        let long = [Event::PhaseSummary {
+            text: "x".repeat(50),
+        }];
+        let view = TranscriptView::new(&long, 20);
+        assert_eq!(view.rows.len(), 3);
+        assert_eq!(view.top(1), 2);
+    }
+
+    #[test]
+    fn search_jumps_between_matches_and_wraps_around() {
+        let mut events = numbered(40);
+        for i in [5, 18, 33] {
+            events[i] = Event::PhaseSummary {
+                text: format!("Needle {i}"),
+            };
+        }
+        let mut view = TranscriptView::new(&events, 80);
+        view.home();
+        view.scroll_by(10, 10);
+
+        view.begin_query();
+        for c in "needle".chars() {
+            view.push_query(c);
+        }
+        assert!(view.is_typing_query());
+        view.submit_query(10);
+        assert!(!view.is_typing_query());
+        // 从视口首行开始找：第 18 行已在视口内，不滚动
+        assert_eq!(view.matches, [5, 18, 33]);
+        assert_eq!(view.current, 1);
+        assert_eq!(view.top(10), 10);
+
+        view.next_match(10);
+        assert_eq!(view.top(10), 30);
+        view.next_match(10);
+        assert_eq!((view.current, view.top(10)), (0, 5));
+        view.prev_match(10);
+        assert_eq!((view.current, view.top(10)), (2, 30));
+
+        // 空搜索词沿用上一次；无命中时 n 不动
+        view.begin_query();
+        view.submit_query(10);
+        assert_eq!(view.matches.len(), 3);
+        view.search_for("absent", 10);
+        view.next_match(10);
+        assert!(view.matches.is_empty());
+        assert_eq!(view.top(10), 30);
+        let page = view.render(10, 120);
+        assert!(crate::ui::screen::strip_ansi(&page[10]).contains("absent"));
+    }
+
+    #[test]
+    fn ring_keeps_the_most_recent_events() {
+        let mut ring = VecDeque::new();
+        let mut task = numbered(3);
+        archive_task_events(&mut ring, &mut task);
+        assert!(task.is_empty());
+        assert_eq!(ring.len(), 3);
+
+        let mut big = numbered(TRANSCRIPT_RING_MAX_EVENTS);
+        archive_task_events(&mut ring, &mut big);
+        assert_eq!(ring.len(), TRANSCRIPT_RING_MAX_EVENTS);
+        assert!(matches!(
+            ring.front(),
+            Some(Event::PhaseSummary { text }) if text == "step 0"
+        ));
+    }
+
+    #[test]
+    fn transcript_key_opens_overlay_without_touching_task_state() {
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        use crate::App;
+        use crate::ui::input::handle_key;
+        use crate::ui::screen::Screen;
+
+        let mut app = App::new();
+        let mut screen = Screen::new_headless().expect("headless screen");
+        let mut earlier = numbered(2);
+        archive_task_events(&mut app.transcript_ring, &mut earlier);
+        app.task_events = numbered(3);
+        screen.input = "draft".to_string();
+        let press = |app: &mut App, screen: &mut Screen, key| {
+            handle_key(app, screen, key, KeyModifiers::NONE);
+        };
+
+        press(&mut app, &mut screen, KeyCode::F(2));
+        let view = app.transcript.as_ref().expect("transcript open");
+        assert_eq!(view.rows.len(), 5);
+        // 视图内的按键不进入输入框
+        for key in [KeyCode::Char('/'), KeyCode::Char('2'), KeyCode::Enter] {
+            press(&mut app, &mut screen, key);
+        }
+        let view = app.transcript.as_ref().unwrap();
+        assert_eq!(view.search, "2");
+        assert_eq!(view.matches, [4]);
+        assert_eq!(screen.input, "draft");
+
+        press(&mut app, &mut screen, KeyCode::Esc);
+        assert!(app.transcript.is_none());
+        assert_eq!(app.task_events.len(), 3);
+        assert_eq!(app.transcript_ring.len(), 2);
+        assert_eq!(screen.input, "draft");
+    }
+}